@@ -46,6 +46,7 @@ static const char *IgnoredCommonSpecFields[] = {
 	"$db",
 	"$readPreference",
 	"$sort",
+	"$trace", /* trace context read by the gateway */
 	"allowDiskUse",
 	"allowPartialResults",
 	"apiDeprecationErrors",
@@ -14,7 +14,7 @@
     clippy::unwrap_used,
     reason = "Main binary uses unwrap for failures that should crash the process"
 )]
-use std::{env, path::PathBuf, sync::Arc};
+use std::{env, path::PathBuf, process, sync::Arc};
 
 use documentdb_gateway_core::{
     configuration::{DocumentDBSetupConfiguration, PgConfiguration, SetupConfiguration},
@@ -24,11 +24,11 @@ use documentdb_gateway_core::{
     run_gateway,
     service::TlsProvider,
     shutdown_controller::SHUTDOWN_CONTROLLER,
-    startup::{create_postgres_object, get_service_context},
-    telemetry::{TelemetryConfig, TelemetryManager},
+    startup::{create_postgres_object, get_service_context, preflight, runtime_builder},
+    telemetry::{log_level, TelemetryConfig, TelemetryManager},
 };
 use tokio::signal;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 fn main() {
     // Takes the configuration file as an argument
@@ -39,27 +39,44 @@ fn main() {
         PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../SetupConfiguration.json")
     };
 
-    // Load configuration
-    let setup_configuration =
-        DocumentDBSetupConfiguration::new(&cfg_file).expect("Failed to load configuration.");
-
     tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(log_level::reloadable_log_filter())
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    // Load configuration
+    let setup_configuration = match DocumentDBSetupConfiguration::new(&cfg_file) {
+        Ok(setup_configuration) => setup_configuration,
+        Err(e) => {
+            tracing::error!(
+                "Failed to load configuration from {}: {e}",
+                cfg_file.display()
+            );
+            process::exit(1);
+        }
+    };
+
+    // Report every configuration problem before any runtime resources are created
+    let problems = preflight(&setup_configuration);
+    if !problems.is_empty() {
+        tracing::error!(
+            "Startup preflight found {} configuration problem(s) in {}:",
+            problems.len(),
+            cfg_file.display()
+        );
+        for problem in &problems {
+            tracing::error!("  {problem}");
+        }
+        process::exit(1);
+    }
+
     tracing::info!("Starting server with configuration: {setup_configuration:?}");
 
-    // Create Tokio runtime with configured worker threads
-    let async_runtime_worker_threads = setup_configuration.async_runtime_worker_threads();
-    let runtime = tokio::runtime::Builder::new_multi_thread()
-        .worker_threads(async_runtime_worker_threads)
-        .enable_all()
+    // Create Tokio runtime of the configured flavor
+    let runtime = runtime_builder(&setup_configuration)
         .build()
         .expect("Failed to create Tokio runtime");
 
-    tracing::info!("Created Tokio runtime with {async_runtime_worker_threads} worker threads");
-
     // Run the async main logic
     runtime.block_on(start_gateway(setup_configuration));
 }
@@ -86,6 +103,10 @@ async fn start_gateway(setup_configuration: DocumentDBSetupConfiguration) {
         signal::ctrl_c().await.expect("Failed to listen for Ctrl+C");
         tracing::info!("Ctrl+C received. Shutting down Rust gateway.");
         SHUTDOWN_CONTROLLER.shutdown();
+
+        signal::ctrl_c().await.expect("Failed to listen for Ctrl+C");
+        tracing::warn!("Second Ctrl+C received. Skipping the shutdown grace period.");
+        SHUTDOWN_CONTROLLER.shutdown();
     });
 
     let tls_provider = TlsProvider::new(
@@ -122,6 +143,7 @@ async fn start_gateway(setup_configuration: DocumentDBSetupConfiguration) {
         connection_pool_manager,
         tls_provider,
         None, // custom_pg_error_mapper
+        None, // database_authorizer
     );
 
     run_gateway::<DocumentDBDataClient>(service_context, None, shutdown_token)
@@ -7,6 +7,9 @@
  */
 
 use std::{
+    collections::HashMap,
+    fmt::Debug,
+    hash::BuildHasher,
     str::from_utf8,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -29,10 +32,30 @@ use crate::{
     protocol::OK_SUCCEEDED,
     requests::{Request, RequestType},
     responses::{self, constant::generic_internal_error_message, RawResponse, Response},
+    telemetry::metrics::record_auth_attempt,
 };
 
 const NONCE_LENGTH: usize = 2;
 
+/// Trait allowing consumers of the documentdb gateway to restrict which databases a user can see.
+///
+/// When no authorizer is configured, commands such as `listDatabases` return every database.
+/// The gateway ships none; embedders pass theirs to [`crate::startup::get_service_context`].
+pub trait DatabaseAuthorizer: Send + Sync + Debug {
+    /// Whether `username` may see every database regardless of per-database privileges.
+    fn is_admin(&self, username: &str) -> bool;
+
+    /// Whether `username` holds any privilege on `database`.
+    fn is_authorized(&self, username: &str, database: &str) -> bool;
+
+    /// Whether `username` holds any privilege on `collection` in `database`. Defaults to the
+    /// database-level check, for authorizers without collection-level privileges.
+    fn is_collection_authorized(&self, username: &str, database: &str, collection: &str) -> bool {
+        let _ = collection;
+        self.is_authorized(username, database)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AuthKind {
     Native,
@@ -59,6 +82,7 @@ pub struct AuthState {
     first_state: Option<ScramFirstState>,
     username: Option<String>,
     user_oid: Option<u32>,
+    roles: Vec<String>,
     auth_kind: Option<AuthKind>,
     timer_initialized: Arc<AtomicBool>,
     auth_mechanism: AuthMechanism,
@@ -78,6 +102,7 @@ impl AuthState {
             first_state: None,
             username: None,
             user_oid: None,
+            roles: Vec::new(),
             auth_kind: None,
             timer_initialized: Arc::new(AtomicBool::new(false)),
             auth_mechanism: AuthMechanism::Unknown,
@@ -108,6 +133,12 @@ impl AuthState {
         ))
     }
 
+    /// The roles granted directly to the authenticated user.
+    #[must_use]
+    pub fn roles(&self) -> &[String] {
+        &self.roles
+    }
+
     #[must_use]
     pub fn is_authorized(&self) -> bool {
         self.authorized.load(Ordering::Acquire)
@@ -135,6 +166,10 @@ impl AuthState {
         self.user_oid = Some(user_oid);
     }
 
+    pub fn set_roles(&mut self, roles: Vec<String>) {
+        self.roles = roles;
+    }
+
     /// Sets the auth kind
     ///
     /// # Errors
@@ -208,16 +243,83 @@ where
         return Ok(response);
     }
 
-    if request.request_type().allowed_unauthorized() {
-        let service_context = Arc::clone(&connection_context.service_context);
-        let data_client = T::new_unauthorized(&service_context)?;
+    let service_context = Arc::clone(&connection_context.service_context);
+    check_allowed_before_auth(
+        request.request_type(),
+        service_context
+            .setup_configuration()
+            .pre_auth_allowed_commands(),
+    )?;
+
+    if request.request_type().gateway_only() {
+        return processor::process_gateway_only(request_context, connection_context);
+    }
+
+    let data_client = T::new_unauthorized(&service_context)?;
+    processor::process_request(request_context, connection_context, &data_client).await
+}
+
+/// Rejects commands that may not run before the connection has authenticated.
+///
+/// `allowed_commands` overrides the default pre-auth set when configured. SASL and
+/// logout commands are handled before this check and are always permitted.
+fn check_allowed_before_auth(
+    request_type: RequestType,
+    allowed_commands: Option<&[String]>,
+) -> Result<()> {
+    let allowed = allowed_commands.map_or_else(
+        || request_type.allowed_unauthorized(),
+        |commands| {
+            commands
+                .iter()
+                .any(|command| command.eq_ignore_ascii_case(request_type.to_command_str()))
+        },
+    );
 
-        return processor::process_request(request_context, connection_context, &data_client).await;
+    if allowed {
+        return Ok(());
     }
 
     Err(DocumentDBError::unauthorized(format!(
         "Command {} is not allowed as the connection is not authenticated yet.",
-        request.request_type().to_string().to_lowercase()
+        request_type.to_string().to_lowercase()
+    )))
+}
+
+/// Rejects a command that none of the user's roles may run under `allowlist`.
+///
+/// Each listed role may run only its listed commands, while roles that aren't listed are
+/// unrestricted. As with privileges, a user may run a command if any of its roles may.
+///
+/// # Errors
+///
+/// Returns `Unauthorized`, naming the command and the user's restricted roles, if the
+/// command is not allowed.
+pub fn check_role_command_allowed<S: BuildHasher>(
+    request_type: RequestType,
+    roles: &[String],
+    allowlist: &HashMap<String, Vec<String>, S>,
+) -> Result<()> {
+    if allowlist.is_empty() || roles.is_empty() {
+        return Ok(());
+    }
+
+    let command = request_type.to_command_str();
+    let allowed = roles.iter().any(|role| {
+        allowlist.get(role).is_none_or(|commands| {
+            commands
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(command))
+        })
+    });
+    if allowed {
+        return Ok(());
+    }
+
+    Err(DocumentDBError::unauthorized(format!(
+        "Command {command} is not allowed for {} {}.",
+        if roles.len() == 1 { "role" } else { "roles" },
+        roles.join(", ")
     )))
 }
 
@@ -226,10 +328,16 @@ async fn handle_auth_request(
     request: &Request<'_>,
 ) -> Result<Option<Response>> {
     match request.request_type() {
-        RequestType::SaslStart => Ok(Some(handle_sasl_start(connection_context, request).await?)),
-        RequestType::SaslContinue => Ok(Some(
-            handle_sasl_continue(connection_context, request).await?,
-        )),
+        RequestType::SaslStart => {
+            let result = handle_sasl_start(connection_context, request).await;
+            record_sasl_outcome(connection_context, request, &result);
+            Ok(Some(result?))
+        }
+        RequestType::SaslContinue => {
+            let result = handle_sasl_continue(connection_context, request).await;
+            record_sasl_outcome(connection_context, request, &result);
+            Ok(Some(result?))
+        }
         RequestType::Logout => {
             connection_context.auth_state = AuthState::new();
             Ok(Some(Response::Raw(RawResponse(rawdoc! {
@@ -240,6 +348,58 @@ async fn handle_auth_request(
     }
 }
 
+/// The mechanism of a SASL command, limited to the supported ones so that clients can't
+/// create arbitrary metric series. `saslContinue` is only supported for SCRAM.
+fn sasl_mechanism(request: &Request<'_>) -> &'static str {
+    match request.document().get_str("mechanism") {
+        Ok("SCRAM-SHA-256") => "SCRAM-SHA-256",
+        Ok("MONGODB-OIDC") => "MONGODB-OIDC",
+        Err(_) if request.request_type() == RequestType::SaslContinue => "SCRAM-SHA-256",
+        _ => "other",
+    }
+}
+
+/// Counts failed SASL steps and completed authentications, logging failures with the
+/// identity the client claimed. Proofs, tokens and other payload contents are never logged.
+fn record_sasl_outcome(
+    connection_context: &ConnectionContext,
+    request: &Request<'_>,
+    result: &Result<Response>,
+) {
+    let mechanism = sasl_mechanism(request);
+    match result {
+        Ok(response) => {
+            let done = response
+                .as_raw_document()
+                .is_ok_and(|document| document.get_bool("done").unwrap_or(false));
+            if done {
+                record_auth_attempt(true, mechanism, &connection_context.ip_address);
+            }
+        }
+        Err(error) => {
+            let username = connection_context
+                .auth_state
+                .username
+                .as_deref()
+                .or_else(|| {
+                    (mechanism == "SCRAM-SHA-256")
+                        .then(|| parse_sasl_payload(request, true).ok()?.username)
+                        .flatten()
+                });
+            tracing::warn!(
+                activity_id = connection_context.connection_id.to_string().as_str(),
+                mechanism,
+                username = username.unwrap_or(""),
+                client_ip = connection_context.ip_address.as_str(),
+                error_code = error.error_code_enum().map_or(0, |code| code as i32),
+                "Authentication failed for {}.",
+                request.request_type()
+            );
+            record_auth_attempt(false, mechanism, &connection_context.ip_address);
+        }
+    }
+}
+
 fn generate_server_nonce(client_nonce: &str) -> String {
     const CHARSET: &[u8] = b"!\"#$%&'()*+-./0123456789:;<>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
     let mut rng = rand::thread_rng();
@@ -447,7 +607,10 @@ async fn handle_oidc_token_authentication(
     };
 
     connection_context.auth_state.set_username(&oid);
-    connection_context.auth_state.user_oid = Some(get_user_oid(connection_context, &oid).await?);
+    let user_oid = get_user_oid(connection_context, &oid).await?;
+    connection_context.auth_state.user_oid = Some(user_oid);
+    let roles = get_user_roles(connection_context, user_oid).await?;
+    connection_context.auth_state.set_roles(roles);
 
     connection_context.auth_state.set_authorized(true);
     connection_context
@@ -644,8 +807,10 @@ async fn handle_sasl_continue(
             bytes: format!("v={server_signature}").as_bytes().to_vec(),
         };
 
-        connection_context.auth_state.user_oid =
-            Some(get_user_oid(connection_context, username).await?);
+        let user_oid = get_user_oid(connection_context, username).await?;
+        connection_context.auth_state.user_oid = Some(user_oid);
+        let roles = get_user_roles(connection_context, user_oid).await?;
+        connection_context.auth_state.set_roles(roles);
 
         connection_context.auth_state.set_authorized(true);
         connection_context.allocate_data_pool("")?;
@@ -814,3 +979,122 @@ pub async fn get_user_oid(connection_context: &ConnectionContext, username: &str
 
     Ok(user_oid)
 }
+
+/// Gets the names of the roles granted directly to the user with `user_oid`
+///
+/// # Errors
+///
+/// Returns an error if the operation fails.
+pub async fn get_user_roles(
+    connection_context: &ConnectionContext,
+    user_oid: u32,
+) -> Result<Vec<String>> {
+    let role_rows = connection_context
+        .service_context
+        .connection_pool_manager()
+        .authentication_connection()
+        .await?
+        .query(
+            "SELECT r.rolname FROM pg_auth_members m JOIN pg_roles r ON r.oid = m.roleid WHERE m.member = $1",
+            &[Type::OID],
+            &[&user_oid],
+        )
+        .await?;
+
+    role_rows
+        .iter()
+        .map(|row| Ok(row.try_get::<_, String>(0)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_pre_auth_commands_are_allowed() {
+        for request_type in [
+            RequestType::Hello,
+            RequestType::IsMaster,
+            RequestType::Ping,
+            RequestType::BuildInfo,
+        ] {
+            check_allowed_before_auth(request_type, None).unwrap();
+        }
+    }
+
+    #[test]
+    fn data_command_rejected_before_auth() {
+        let err = check_allowed_before_auth(RequestType::Find, None).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+    }
+
+    #[test]
+    fn configured_pre_auth_commands_replace_default() {
+        let allowed = vec!["hello".to_owned(), "ISMASTER".to_owned()];
+
+        check_allowed_before_auth(RequestType::Hello, Some(&allowed)).unwrap();
+        check_allowed_before_auth(RequestType::IsMaster, Some(&allowed)).unwrap();
+
+        let err = check_allowed_before_auth(RequestType::BuildInfo, Some(&allowed)).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+    }
+
+    fn allowlist(entries: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(role, commands)| {
+                (
+                    (*role).to_owned(),
+                    commands.iter().map(|&command| command.to_owned()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn restricted_role_is_blocked_from_unlisted_commands() {
+        let allowlist = allowlist(&[("app_role", &["find", "INSERT"])]);
+        let roles = vec!["app_role".to_owned()];
+
+        check_role_command_allowed(RequestType::Find, &roles, &allowlist).unwrap();
+        check_role_command_allowed(RequestType::Insert, &roles, &allowlist).unwrap();
+
+        let err = check_role_command_allowed(RequestType::Compact, &roles, &allowlist).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+        let message = err.to_string();
+        assert!(message.contains("compact"), "{message}");
+        assert!(message.contains("app_role"), "{message}");
+    }
+
+    #[test]
+    fn unlisted_roles_are_unrestricted() {
+        let allowlist = allowlist(&[("app_role", &["find"])]);
+
+        check_role_command_allowed(RequestType::Compact, &[], &allowlist).unwrap();
+        check_role_command_allowed(
+            RequestType::Compact,
+            &["admin_role".to_owned()],
+            &HashMap::new(),
+        )
+        .unwrap();
+        // Any role that may run the command is enough.
+        check_role_command_allowed(
+            RequestType::Compact,
+            &["app_role".to_owned(), "admin_role".to_owned()],
+            &allowlist,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn drop_commands_follow_the_allowlist() {
+        let allowlist = allowlist(&[("app_role", &["drop"])]);
+        let roles = vec!["app_role".to_owned()];
+
+        check_role_command_allowed(RequestType::Drop, &roles, &allowlist).unwrap();
+        let err =
+            check_role_command_allowed(RequestType::DropDatabase, &roles, &allowlist).unwrap_err();
+        assert!(err.to_string().contains("dropDatabase"), "{err}");
+    }
+}
@@ -0,0 +1,229 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/configuration/backend_routing.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::error::{DocumentDBError, ErrorCode, Result};
+
+/// Sends the databases matched by exactly one of `Database`, `DatabasePrefix` or
+/// `DatabasePattern` to a separate `PostgreSQL` cluster.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BackendRoute {
+    pub database: Option<String>,
+    pub database_prefix: Option<String>,
+    /// A regular expression that must match the whole database name.
+    pub database_pattern: Option<String>,
+    pub host_name: String,
+    /// Defaults to `PostgresPort`.
+    pub port: Option<u16>,
+}
+
+/// A `PostgreSQL` cluster serving some databases instead of the default backend.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BackendTarget {
+    pub host_name: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone)]
+enum DatabaseMatcher {
+    Exact(String),
+    Prefix(String),
+    Pattern(Regex),
+}
+
+impl DatabaseMatcher {
+    fn matches(&self, db: &str) -> bool {
+        match self {
+            Self::Exact(name) => name == db,
+            Self::Prefix(prefix) => db.starts_with(prefix.as_str()),
+            Self::Pattern(pattern) => pattern.is_match(db),
+        }
+    }
+}
+
+/// Resolves the backend serving a database. Routes are tried in configuration order and
+/// databases no route matches are served by the default backend.
+#[derive(Debug, Clone, Default)]
+pub struct BackendRouter {
+    routes: Vec<(DatabaseMatcher, BackendTarget)>,
+}
+
+impl BackendRouter {
+    /// # Errors
+    ///
+    /// Returns an error if a route does not set exactly one matcher or its pattern is invalid.
+    pub fn new(routes: &[BackendRoute], default_port: u16) -> Result<Self> {
+        let routes = routes
+            .iter()
+            .map(|route| {
+                let matcher = match (
+                    &route.database,
+                    &route.database_prefix,
+                    &route.database_pattern,
+                ) {
+                    (Some(name), None, None) => DatabaseMatcher::Exact(name.clone()),
+                    (None, Some(prefix), None) => DatabaseMatcher::Prefix(prefix.clone()),
+                    (None, None, Some(pattern)) => DatabaseMatcher::Pattern(
+                        Regex::new(&format!("^(?:{pattern})$")).map_err(|e| {
+                            DocumentDBError::internal_error(format!(
+                                "Invalid DatabasePattern '{pattern}' in PostgresBackendRoutes: {e}"
+                            ))
+                        })?,
+                    ),
+                    _ => {
+                        return Err(DocumentDBError::internal_error(format!(
+                            "PostgresBackendRoutes entry for '{}' must set exactly one of Database, DatabasePrefix or DatabasePattern.",
+                            route.host_name
+                        )))
+                    }
+                };
+                Ok((
+                    matcher,
+                    BackendTarget {
+                        host_name: route.host_name.clone(),
+                        port: route.port.unwrap_or(default_port),
+                    },
+                ))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { routes })
+    }
+
+    /// Returns the backend serving `db`, or `None` when it is served by the default backend.
+    #[must_use]
+    pub fn resolve(&self, db: &str) -> Option<&BackendTarget> {
+        self.routes
+            .iter()
+            .find(|(matcher, _)| matcher.matches(db))
+            .map(|(_, target)| target)
+    }
+
+    /// Returns each distinct routed backend once.
+    #[must_use]
+    pub fn targets(&self) -> Vec<&BackendTarget> {
+        let mut targets: Vec<&BackendTarget> = Vec::new();
+        for (_, target) in &self.routes {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        targets
+    }
+
+    /// Rejects an operation on `db` that also touches `other_db` when the two are served by
+    /// different backends, since nothing coordinates work across clusters.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CommandNotSupported` if the databases resolve to different backends.
+    pub fn check_same_backend(&self, db: &str, other_db: &str, operation: &str) -> Result<()> {
+        if self.resolve(db) == self.resolve(other_db) {
+            Ok(())
+        } else {
+            Err(DocumentDBError::documentdb_error(
+                ErrorCode::CommandNotSupported,
+                format!(
+                    "{operation} across databases '{db}' and '{other_db}' served by different backends is not supported"
+                ),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(
+        database: Option<&str>,
+        prefix: Option<&str>,
+        pattern: Option<&str>,
+        host_name: &str,
+    ) -> BackendRoute {
+        BackendRoute {
+            database: database.map(str::to_owned),
+            database_prefix: prefix.map(str::to_owned),
+            database_pattern: pattern.map(str::to_owned),
+            host_name: host_name.to_owned(),
+            port: None,
+        }
+    }
+
+    fn host(router: &BackendRouter, db: &str) -> Option<String> {
+        router.resolve(db).map(|target| target.host_name.clone())
+    }
+
+    #[test]
+    fn resolves_first_matching_route_and_falls_back_to_default() {
+        let router = BackendRouter::new(
+            &[
+                route(Some("billing"), None, None, "billing-host"),
+                route(None, Some("tenant_"), None, "tenant-host"),
+                route(None, None, Some("archive_[0-9]+"), "archive-host"),
+                route(None, Some("bill"), None, "unused-host"),
+            ],
+            9712,
+        )
+        .unwrap();
+
+        assert_eq!(host(&router, "billing").as_deref(), Some("billing-host"));
+        assert_eq!(host(&router, "billings").as_deref(), Some("unused-host"));
+        assert_eq!(host(&router, "tenant_42").as_deref(), Some("tenant-host"));
+        assert_eq!(
+            host(&router, "archive_2024").as_deref(),
+            Some("archive-host")
+        );
+        // Patterns match the whole name.
+        assert_eq!(host(&router, "archive_2024_old"), None);
+        assert_eq!(host(&router, "test"), None);
+        assert_eq!(router.resolve("billing").unwrap().port, 9712);
+        assert_eq!(router.targets().len(), 4);
+
+        assert_eq!(BackendRouter::default().resolve("billing"), None);
+    }
+
+    #[test]
+    fn rejects_routes_without_exactly_one_matcher() {
+        BackendRouter::new(&[route(None, None, None, "host")], 9712).unwrap_err();
+        BackendRouter::new(&[route(Some("a"), Some("b"), None, "host")], 9712).unwrap_err();
+        BackendRouter::new(&[route(None, None, Some("("), "host")], 9712).unwrap_err();
+    }
+
+    #[test]
+    fn cross_backend_operations_are_not_supported() {
+        let router = BackendRouter::new(
+            &[
+                route(None, Some("tenant_"), None, "tenant-host"),
+                BackendRoute {
+                    port: Some(5433),
+                    ..route(Some("other"), None, None, "tenant-host")
+                },
+            ],
+            9712,
+        )
+        .unwrap();
+
+        router
+            .check_same_backend("tenant_a", "tenant_b", "$lookup")
+            .unwrap();
+        router
+            .check_same_backend("test", "admin", "$lookup")
+            .unwrap();
+        router
+            .check_same_backend("tenant_a", "test", "$lookup")
+            .unwrap_err();
+        // Same host on a different port is a different cluster.
+        router
+            .check_same_backend("tenant_a", "other", "$out")
+            .unwrap_err();
+    }
+}
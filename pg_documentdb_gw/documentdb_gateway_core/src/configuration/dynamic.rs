@@ -6,14 +6,71 @@
  *-------------------------------------------------------------------------
  */
 
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
 
 use bson::RawBson;
+use serde::Deserialize;
 
-use crate::{configuration::Version, postgres::conn_mgmt};
+use crate::{
+    configuration::Version,
+    error::{DocumentDBError, Result},
+    postgres::conn_mgmt,
+    requests::{
+        query_limits::QueryLimits, request_type::CommandCategory,
+        special_values::SpecialValuePolicy, unknown_options::UnknownOptionPolicy,
+    },
+    telemetry::tenant::TenantMapping,
+};
 
 pub const POSTGRES_RECOVERY_KEY: &str = "IsPostgresInRecovery";
 
+pub const OPERATION_TIMEOUTS_BY_CATEGORY_KEY: &str = "defaultOperationTimeoutByCategoryMs";
+
+/// Default time limits per command category, in milliseconds, from a JSON object such as
+/// `{"reads": 60000, "writes": 10000, "admin": 2000, "ddl": 300000}`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryTimeouts {
+    pub reads: Option<u64>,
+    pub writes: Option<u64>,
+    pub admin: Option<u64>,
+    pub ddl: Option<u64>,
+}
+
+impl CategoryTimeouts {
+    /// # Errors
+    ///
+    /// Returns an error if `value` is not such an object or a timeout is not positive.
+    pub fn parse(value: &str) -> Result<Self> {
+        let timeouts: Self = serde_json::from_str(value).map_err(|e| {
+            DocumentDBError::bad_value(format!("Invalid {OPERATION_TIMEOUTS_BY_CATEGORY_KEY}: {e}"))
+        })?;
+        for (name, timeout) in [
+            ("reads", timeouts.reads),
+            ("writes", timeouts.writes),
+            ("admin", timeouts.admin),
+            ("ddl", timeouts.ddl),
+        ] {
+            if timeout == Some(0) {
+                return Err(DocumentDBError::bad_value(format!(
+                    "{OPERATION_TIMEOUTS_BY_CATEGORY_KEY}.{name} must be positive"
+                )));
+            }
+        }
+        Ok(timeouts)
+    }
+
+    #[must_use]
+    pub const fn get(&self, category: CommandCategory) -> Option<u64> {
+        match category {
+            CommandCategory::Read => self.reads,
+            CommandCategory::Write => self.writes,
+            CommandCategory::Admin => self.admin,
+            CommandCategory::Ddl => self.ddl,
+        }
+    }
+}
+
 /// Used for configurations which can change during runtime.
 pub trait DynamicConfiguration: Send + Sync + Debug {
     fn get_str(&self, key: &str) -> Option<String>;
@@ -29,6 +86,12 @@ pub trait DynamicConfiguration: Send + Sync + Debug {
     // Needed to downcast to concrete type
     fn as_any(&self) -> &dyn std::any::Any;
 
+    /// Sets a value for the life of the process, taking precedence over reloaded values.
+    /// Returns false if this configuration can't be changed at runtime.
+    fn set_override(&self, _key: &str, _value: String) -> bool {
+        false
+    }
+
     fn enable_change_streams(&self) -> bool {
         self.get_bool("enableChangeStreams", false)
     }
@@ -41,10 +104,32 @@ pub trait DynamicConfiguration: Send + Sync + Debug {
         self.get_bool("enableWriteProceduresWithBatchCommit", false)
     }
 
+    /// Entries an insert or update batch outside a transaction commits together; larger
+    /// batches are split into chunks of this size. 0 disables splitting.
+    fn write_batch_commit_size(&self) -> u64 {
+        self.get_u64("writeBatchCommitSize", 0)
+    }
+
+    /// Keeps insert and update batches whole regardless of `writeBatchCommitSize`, so that an
+    /// ordered failure never leaves an earlier chunk of the batch committed on its own.
+    fn write_batch_atomic(&self) -> bool {
+        self.get_bool("writeBatchAtomic", false)
+    }
+
+    /// Names the backend session serving each request after its client's `appName` and
+    /// activity id, as seen in `pg_stat_activity`. Costs up to two statements per request.
+    fn enable_client_application_name(&self) -> bool {
+        self.get_bool("enableClientApplicationName", false)
+    }
+
     fn enable_connection_status(&self) -> bool {
         self.get_bool("enableConnectionStatus", true)
     }
 
+    fn enable_connection_close_summary_log(&self) -> bool {
+        self.get_bool("enableConnectionCloseSummaryLog", false)
+    }
+
     fn enable_verbose_logging_in_gateway(&self) -> bool {
         self.get_bool("enableVerboseLoggingInGateway", false)
     }
@@ -102,6 +187,123 @@ pub trait DynamicConfiguration: Send + Sync + Debug {
         self.get_u64("mongoCursorIdleResolutionIntervalSeconds", 5)
     }
 
+    /// Maximum number of cursors saved across the gateway; 0 disables the limit.
+    fn max_open_cursors(&self) -> u64 {
+        self.get_u64("maxOpenCursors", 0)
+    }
+
+    /// Time limit for commands sent without `maxTimeMS`; 0 disables it.
+    fn default_operation_timeout_ms(&self) -> u64 {
+        self.get_u64("defaultOperationTimeoutMs", 0)
+    }
+
+    /// Per-category time limits for commands sent without `maxTimeMS`. An invalid value is
+    /// ignored.
+    fn operation_timeouts_by_category(&self) -> CategoryTimeouts {
+        self.get_str(OPERATION_TIMEOUTS_BY_CATEGORY_KEY)
+            .and_then(|value| {
+                CategoryTimeouts::parse(&value)
+                    .inspect_err(|e| tracing::warn!("Ignoring {e}"))
+                    .ok()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Time limit for commands of `category` sent without `maxTimeMS`: the category's entry
+    /// in `defaultOperationTimeoutByCategoryMs`, or else `defaultOperationTimeoutMs`.
+    fn default_operation_timeout_ms_for(&self, category: CommandCategory) -> u64 {
+        self.operation_timeouts_by_category()
+            .get(category)
+            .unwrap_or_else(|| self.default_operation_timeout_ms())
+    }
+
+    /// Commands each role may run, keyed by role name, from a JSON object such as
+    /// `{"app_role": ["find", "insert"]}`. Roles that aren't listed are unrestricted.
+    fn role_command_allowlist(&self) -> HashMap<String, Vec<String>> {
+        self.get_str("roleCommandAllowlist")
+            .and_then(|value| {
+                serde_json::from_str(&value)
+                    .inspect_err(|e| tracing::warn!("Ignoring invalid roleCommandAllowlist: {e}"))
+                    .ok()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Tenants recorded on the spans and metrics of each connection's requests, keyed by the
+    /// connection's user or role, from a JSON object such as
+    /// `{"users": {"alice": "team_a"}, "roles": {"reporting_role": "team_b"}}`.
+    fn tenant_mapping(&self) -> TenantMapping {
+        self.get_str("tenantMapping")
+            .and_then(|value| {
+                serde_json::from_str(&value)
+                    .inspect_err(|e| tracing::warn!("Ignoring invalid tenantMapping: {e}"))
+                    .ok()
+            })
+            .unwrap_or_default()
+    }
+
+    /// How writes treat NaN and infinite numbers: `allow`, `reject` or `coerce` to null. An
+    /// unknown value is ignored.
+    fn special_value_policy(&self) -> SpecialValuePolicy {
+        self.get_str("specialValuePolicy")
+            .and_then(|value| {
+                let policy = SpecialValuePolicy::parse(&value);
+                if policy.is_none() {
+                    tracing::warn!("Ignoring invalid specialValuePolicy: {value}");
+                }
+                policy
+            })
+            .unwrap_or_default()
+    }
+
+    /// How commands carrying options the gateway doesn't recognize are handled: `ignore`,
+    /// `warn` or `reject`. An unknown value is ignored.
+    fn unknown_option_policy(&self) -> UnknownOptionPolicy {
+        self.get_str("unknownOptionPolicy")
+            .and_then(|value| {
+                let policy = UnknownOptionPolicy::parse(&value);
+                if policy.is_none() {
+                    tracing::warn!("Ignoring invalid unknownOptionPolicy: {value}");
+                }
+                policy
+            })
+            .unwrap_or_default()
+    }
+
+    /// Limits on the filters and pipelines of requests, checked before they reach the
+    /// backend.
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "limits are far below usize::MAX"
+    )]
+    fn query_limits(&self) -> QueryLimits {
+        let defaults = QueryLimits::default();
+        QueryLimits {
+            pipeline_stages: self.get_u64("maxPipelineStages", defaults.pipeline_stages as u64)
+                as usize,
+            nesting_depth: self.get_u64("maxQueryNestingDepth", defaults.nesting_depth as u64)
+                as usize,
+            list_length: self.get_u64("maxQueryListLength", defaults.list_length as u64) as usize,
+        }
+    }
+
+    /// Documents in the first batch of a find or aggregate that sets no `batchSize`; 0 keeps
+    /// the backend's default of 101.
+    fn default_first_batch_size(&self) -> u64 {
+        self.get_u64("defaultFirstBatchSize", 0)
+    }
+
+    /// Documents in each getMore batch that sets no `batchSize`; 0 keeps the backend's
+    /// default of filling the batch up to the maximum message size.
+    fn default_get_more_batch_size(&self) -> u64 {
+        self.get_u64("defaultGetMoreBatchSize", 0)
+    }
+
+    /// Bytes a cursor may pull from the backend in one getMore; 0 disables the limit.
+    fn cursor_batch_high_water_mark_bytes(&self) -> u64 {
+        self.get_u64("cursorBatchHighWaterMarkBytes", 0)
+    }
+
     #[expect(clippy::cast_possible_truncation, reason = "value fits in i32")]
     #[expect(clippy::cast_possible_wrap, reason = "value is small positive")]
     #[expect(clippy::cast_sign_loss, reason = "value is always positive")]
@@ -136,6 +338,8 @@ pub trait DynamicConfiguration: Send + Sync + Debug {
         )
     }
 
+    /// Requests whose `HandleMessage` interval reaches this many milliseconds have their
+    /// latency logged as a warning; a value that isn't positive disables it.
     fn slow_query_log_interval_ms(&self) -> i32 {
         self.get_i32("slowQueryLogIntervalInMilliseconds", -1)
     }
@@ -147,3 +351,119 @@ pub trait DynamicConfiguration: Send + Sync + Debug {
         write!(f, "")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bson::rawbson;
+
+    use super::*;
+    use crate::requests::RequestType;
+
+    #[derive(Debug, Default)]
+    struct MapConfig(HashMap<String, String>);
+
+    impl DynamicConfiguration for MapConfig {
+        fn get_str(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn get_bool(&self, _: &str, default: bool) -> bool {
+            default
+        }
+
+        fn get_i32(&self, _: &str, default: i32) -> i32 {
+            default
+        }
+
+        fn get_u64(&self, key: &str, default: u64) -> u64 {
+            self.0.get(key).map_or(default, |v| v.parse().unwrap())
+        }
+
+        fn equals_value(&self, _: &str, _: &str) -> bool {
+            false
+        }
+
+        fn topology(&self) -> RawBson {
+            rawbson!({})
+        }
+
+        fn enable_developer_explain(&self) -> bool {
+            false
+        }
+
+        fn max_connections(&self) -> usize {
+            100
+        }
+
+        fn allow_transaction_snapshot(&self) -> bool {
+            false
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    fn config(values: &[(&str, &str)]) -> MapConfig {
+        MapConfig(
+            values
+                .iter()
+                .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn commands_pick_up_their_category_default_timeout() {
+        let config = config(&[
+            ("defaultOperationTimeoutMs", "5000"),
+            (
+                OPERATION_TIMEOUTS_BY_CATEGORY_KEY,
+                r#"{"reads": 60000, "ddl": 300000}"#,
+            ),
+        ]);
+
+        assert_eq!(
+            config.default_operation_timeout_ms_for(RequestType::Aggregate.category()),
+            60_000
+        );
+        assert_eq!(
+            config.default_operation_timeout_ms_for(RequestType::DropDatabase.category()),
+            300_000
+        );
+        // Categories without an entry keep the global default.
+        assert_eq!(
+            config.default_operation_timeout_ms_for(RequestType::Insert.category()),
+            5000
+        );
+        assert_eq!(
+            MapConfig::default().default_operation_timeout_ms_for(CommandCategory::Read),
+            0
+        );
+    }
+
+    #[test]
+    fn category_timeouts_must_be_positive() {
+        for invalid in [
+            r#"{"admin": 0}"#,
+            r#"{"writes": -1}"#,
+            r#"{"queries": 1000}"#,
+            "[]",
+        ] {
+            CategoryTimeouts::parse(invalid).unwrap_err();
+        }
+
+        // An invalid map is ignored as a whole.
+        let config = config(&[
+            ("defaultOperationTimeoutMs", "5000"),
+            (
+                OPERATION_TIMEOUTS_BY_CATEGORY_KEY,
+                r#"{"reads": 1, "ddl": 0}"#,
+            ),
+        ]);
+        assert_eq!(
+            config.default_operation_timeout_ms_for(CommandCategory::Read),
+            5000
+        );
+    }
+}
@@ -6,22 +6,26 @@
  *-------------------------------------------------------------------------
  */
 
+mod backend_routing;
 mod certs;
 mod dynamic;
 mod pg_configuration;
+mod runtime;
 mod setup;
 mod version;
 
+pub use backend_routing::{BackendRoute, BackendRouter, BackendTarget};
 pub use certs::{CertInputType, CertificateOptions};
 pub use dynamic::DynamicConfiguration;
 pub use pg_configuration::PgConfiguration;
+pub use runtime::AsyncRuntimeFlavor;
 pub use setup::DocumentDBSetupConfiguration;
-pub use version::Version;
+pub use version::{ServerDescription, ServerDescriptionOptions, Version};
 
 use dyn_clone::{clone_trait_object, DynClone};
 use std::fmt::Debug;
 
-use crate::telemetry::config::TelemetryOptions;
+use crate::telemetry::{audit::AuditOptions, config::TelemetryOptions};
 
 /// These are the required configuration fields.
 ///
@@ -47,6 +51,10 @@ pub trait SetupConfiguration: DynClone + Send + Sync + Debug {
     /// Returns the port number of the backend `PostgreSQL` server.
     fn postgres_port(&self) -> u16;
 
+    /// Returns the routes sending some databases to other `PostgreSQL` clusters; every other
+    /// database is served by `postgres_host_name` and `postgres_port`.
+    fn backend_router(&self) -> &BackendRouter;
+
     /// Returns the system user for connecting to the backend `PostgreSQL` server.
     fn postgres_system_user(&self) -> &str;
 
@@ -84,6 +92,9 @@ pub trait SetupConfiguration: DynClone + Send + Sync + Debug {
     /// Returns the time to wait for `PostgreSQL` to start up before giving up.
     fn postgres_startup_wait_time_seconds(&self) -> u64;
 
+    /// Returns the kind of async runtime to run on.
+    fn async_runtime_flavor(&self) -> AsyncRuntimeFlavor;
+
     /// Returns the number of worker threads for the async runtime.
     fn async_runtime_worker_threads(&self) -> usize;
 
@@ -100,6 +111,30 @@ pub trait SetupConfiguration: DynClone + Send + Sync + Debug {
     /// Returns the timeout duration (in minutes) for `PostgreSQL` connections
     fn postgres_idle_connection_timeout_minutes(&self) -> u64;
 
+    /// Returns how many prepared statements each `PostgreSQL` connection keeps; 0 disables caching.
+    fn postgres_statement_cache_size(&self) -> usize;
+
+    /// Returns whether backend connections enable `documentdb_core.enableCollation`, so that
+    /// find, update and delete compare strings by the `collation` they are given.
+    fn enable_collation(&self) -> bool;
+
+    /// Returns how long a client connection may wait between requests before it is closed;
+    /// 0 disables the timeout.
+    fn client_idle_timeout_ms(&self) -> u64;
+
+    /// Returns whether client connections are probed with TCP keepalives.
+    fn tcp_keepalive_enabled(&self) -> bool;
+
+    /// Returns how long, in seconds, a client connection may be silent before the first
+    /// keepalive probe is sent.
+    fn tcp_keepalive_idle_secs(&self) -> u64;
+
+    /// Returns the interval, in seconds, between unanswered keepalive probes.
+    fn tcp_keepalive_interval_secs(&self) -> u64;
+
+    /// Returns how many unanswered keepalive probes close a client connection.
+    fn tcp_keepalive_probes(&self) -> u32;
+
     /// Returns whether TLS should be enforced for all connections.
     fn enforce_tls(&self) -> bool;
 
@@ -111,6 +146,59 @@ pub trait SetupConfiguration: DynClone + Send + Sync + Debug {
     /// Included in the hello command's `internal` response document.
     fn instance_kind(&self) -> &str;
 
+    /// Returns additional command fields whose values are redacted before a command
+    /// document is logged. `payload`, `pwd` and `saslPayload` are always redacted.
+    fn redacted_command_fields(&self) -> &[String];
+
+    /// Returns whether logged commands show the values of their filters, documents and
+    /// pipelines, which otherwise are redacted as they may hold personal data.
+    fn log_command_user_data(&self) -> bool;
+
+    /// Returns the commands permitted before a connection authenticates, replacing the
+    /// default set (`hello`, `isMaster`, `ping`, `buildInfo`) when configured.
+    fn pre_auth_allowed_commands(&self) -> Option<&[String]>;
+
+    /// Returns the aggregation stages a pipeline may use, when only those are permitted.
+    /// Operators within stages are not restricted by it. Stages in the denylist are rejected
+    /// even if listed here.
+    fn aggregation_stage_allowlist(&self) -> Option<&[String]>;
+
+    /// Returns the aggregation stages rejected anywhere in a pipeline, including nested
+    /// `$lookup`, `$facet` and `$unionWith` pipelines, and the operators, such as `$where`
+    /// or `$function`, rejected anywhere within a stage.
+    fn aggregation_stage_denylist(&self) -> &[String];
+
+    /// Returns the largest BSON document size advertised to clients as `maxBsonObjectSize`.
+    fn max_bson_object_size(&self) -> i32;
+
+    /// Returns the largest wire message the gateway accepts or sends, advertised to clients
+    /// as `maxMessageSizeBytes`.
+    fn max_message_size_bytes(&self) -> i32;
+
+    /// Returns the port serving HTTP `/healthz` and `/readyz`, or `None` to disable them.
+    fn health_check_port(&self) -> Option<u16>;
+
+    /// Returns how long shutdown waits for in-flight requests before closing connections.
+    fn shutdown_grace_ms(&self) -> u64;
+
+    /// Returns the most client connections served at once, or `None` for no limit.
+    fn max_concurrent_connections(&self) -> Option<usize>;
+
+    /// Returns how many connections past `max_concurrent_connections` wait for a slot;
+    /// further connections are rejected. 0 rejects them all immediately.
+    fn connection_backlog(&self) -> usize;
+
+    /// Returns the most BSON a single request may hold buffered in the gateway, or `None`
+    /// for no limit. Requests past it fail with `ExceededMemoryLimit`.
+    fn max_request_memory_bytes(&self) -> Option<usize>;
+
+    /// Returns the server version and wire range advertised to clients, or `None` to
+    /// advertise the dynamic `serverVersion`.
+    fn server_description(&self) -> Option<&ServerDescription>;
+
+    /// Returns where privileged commands are audited, or `None` to disable auditing.
+    fn audit_options(&self) -> Option<&AuditOptions>;
+
     /// Returns telemetry options from static setup configuration, if present.
     fn telemetry_options(&self) -> Option<&TelemetryOptions>;
 
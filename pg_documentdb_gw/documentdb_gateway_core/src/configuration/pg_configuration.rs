@@ -10,6 +10,7 @@ use std::{collections::HashMap, path::Path, sync::Arc, time::SystemTime};
 
 use arc_swap::ArcSwap;
 use bson::{rawbson, RawBson};
+use dashmap::DashMap;
 use serde::Deserialize;
 use tokio::{
     task::JoinHandle,
@@ -17,7 +18,10 @@ use tokio::{
 };
 
 use crate::{
-    configuration::{dynamic::POSTGRES_RECOVERY_KEY, DynamicConfiguration, SetupConfiguration},
+    configuration::{
+        dynamic::{CategoryTimeouts, OPERATION_TIMEOUTS_BY_CATEGORY_KEY, POSTGRES_RECOVERY_KEY},
+        DynamicConfiguration, SetupConfiguration,
+    },
     error::{DocumentDBError, Result},
     postgres::{conn_mgmt::PoolManager, PgDocument},
 };
@@ -99,6 +103,13 @@ impl PgConfigurationInner {
         let in_recovery: bool = pg_is_in_recovery_row.first().is_some_and(|row| row.get(0));
         configs.insert(POSTGRES_RECOVERY_KEY.to_owned(), in_recovery.to_string());
 
+        if let Some(value) = configs.get(OPERATION_TIMEOUTS_BY_CATEGORY_KEY) {
+            if let Err(e) = CategoryTimeouts::parse(value) {
+                tracing::error!("Ignoring {e}");
+                configs.remove(OPERATION_TIMEOUTS_BY_CATEGORY_KEY);
+            }
+        }
+
         tracing::info!("Dynamic configurations loaded: {configs:?}");
         Ok(configs)
     }
@@ -116,6 +127,8 @@ impl PgConfigurationInner {
 pub struct PgConfiguration {
     inner: PgConfigurationInner,
     values: ArcSwap<HashMap<String, String>>,
+    /// Values set by `setParameter`, which survive reloads.
+    overrides: DashMap<String, String>,
     last_update_at: ArcSwap<Instant>,
     topology_bson: ArcSwap<RawBson>,
     refresh_task: Option<JoinHandle<()>>,
@@ -192,6 +205,7 @@ impl PgConfiguration {
         let mut configuration = Arc::new(Self {
             inner,
             values,
+            overrides: DashMap::new(),
             last_update_at,
             topology_bson,
             refresh_task: None,
@@ -215,6 +229,13 @@ impl PgConfiguration {
         Ok(configuration)
     }
 
+    fn value(&self, key: &str) -> Option<String> {
+        self.overrides
+            .get(key)
+            .map(|value| value.clone())
+            .or_else(|| self.values.load().get(key).cloned())
+    }
+
     pub fn last_update_at(&self) -> Instant {
         *self.last_update_at.load_full()
     }
@@ -319,28 +340,22 @@ impl PgConfiguration {
 
 impl DynamicConfiguration for PgConfiguration {
     fn get_str(&self, key: &str) -> Option<String> {
-        self.values.load_full().get(key).cloned()
+        self.value(key)
     }
     fn get_bool(&self, key: &str, default: bool) -> bool {
-        self.values
-            .load_full()
-            .get(key)
+        self.value(key)
             .map_or(default, |v| v.parse::<bool>().unwrap_or(default))
     }
     fn get_i32(&self, key: &str, default: i32) -> i32 {
-        self.values
-            .load_full()
-            .get(key)
+        self.value(key)
             .map_or(default, |v| v.parse::<i32>().unwrap_or(default))
     }
     fn get_u64(&self, key: &str, default: u64) -> u64 {
-        self.values
-            .load_full()
-            .get(key)
+        self.value(key)
             .map_or(default, |v| v.parse::<u64>().unwrap_or(default))
     }
     fn equals_value(&self, key: &str, value: &str) -> bool {
-        self.values.load_full().get(key).is_some_and(|v| v == value)
+        self.value(key).is_some_and(|v| v == value)
     }
 
     fn topology(&self) -> RawBson {
@@ -370,6 +385,11 @@ impl DynamicConfiguration for PgConfiguration {
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
+
+    fn set_override(&self, key: &str, value: String) -> bool {
+        self.overrides.insert(key.to_owned(), value);
+        true
+    }
 }
 
 impl Drop for PgConfiguration {
@@ -0,0 +1,20 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/configuration/runtime.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use serde::Deserialize;
+
+/// The kind of Tokio runtime the gateway runs on.
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AsyncRuntimeFlavor {
+    /// A work-stealing runtime with `AsyncRuntimeWorkerThreads` worker threads
+    #[default]
+    MultiThread,
+    /// A single-threaded runtime, suited to deployments with one vCPU
+    CurrentThread,
+}
@@ -11,9 +11,13 @@ use std::{fs::File, path::Path};
 use serde::Deserialize;
 
 use crate::{
-    configuration::{CertificateOptions, SetupConfiguration},
+    configuration::{
+        AsyncRuntimeFlavor, BackendRoute, BackendRouter, CertificateOptions, ServerDescription,
+        ServerDescriptionOptions, SetupConfiguration,
+    },
     error::{DocumentDBError, Result},
-    telemetry::config::TelemetryOptions,
+    protocol,
+    telemetry::{audit::AuditOptions, config::TelemetryOptions},
 };
 
 // Configurations which are populated statically on process start
@@ -27,7 +31,17 @@ pub struct DocumentDBSetupConfiguration {
     // Gateway listener configuration
     pub use_local_host: Option<bool>,
     pub gateway_listen_port: Option<u16>,
+    // Port for the HTTP /healthz and /readyz endpoints; disabled when not set.
+    pub health_check_port: Option<u16>,
     pub enforce_tls: Option<bool>,
+    // Closes client connections idle for longer than this; disabled when not set or 0.
+    pub client_idle_timeout_ms: Option<u64>,
+    // Keepalive probing of client connections, on by default, so that clients that vanish
+    // without closing their connection are detected and released.
+    pub tcp_keepalive_enabled: Option<bool>,
+    pub tcp_keepalive_idle_secs: Option<u64>,
+    pub tcp_keepalive_interval_secs: Option<u64>,
+    pub tcp_keepalive_probes: Option<u32>,
 
     // Postgres configuration
     #[serde(default = "default_user")]
@@ -38,6 +52,10 @@ pub struct DocumentDBSetupConfiguration {
     pub postgres_host_name: Option<String>,
     pub postgres_port: Option<u16>,
     pub postgres_database: Option<String>,
+    // Databases served by other Postgres clusters, matched by name, prefix or pattern.
+    pub postgres_backend_routes: Option<Vec<BackendRoute>>,
+    #[serde(skip)]
+    pub backend_router: BackendRouter,
 
     #[serde(default)]
     pub allow_transaction_snapshot: Option<bool>,
@@ -51,11 +69,25 @@ pub struct DocumentDBSetupConfiguration {
     pub postgres_command_timeout_secs: Option<u64>,
     pub postgres_idle_connection_timeout_minutes: Option<u64>,
     pub postgres_startup_wait_time_seconds: Option<u64>,
+    pub postgres_statement_cache_size: Option<usize>,
+    // Lets the backend honour command collations; commands ignore or reject them when not set.
+    pub enable_collation: Option<bool>,
 
     // Runtime configuration
+    // Either "multi_thread" (the default) or "current_thread", which ignores the worker thread count.
+    pub async_runtime_flavor: Option<AsyncRuntimeFlavor>,
     pub async_runtime_worker_threads: Option<usize>,
     pub stream_read_buffer_size: Option<usize>,
     pub stream_write_buffer_size: Option<usize>,
+    pub max_bson_object_size: Option<i32>,
+    pub max_message_size_bytes: Option<i32>,
+    pub shutdown_grace_ms: Option<u64>,
+    // Client connections served at once; unlimited when not set.
+    pub max_concurrent_connections: Option<usize>,
+    // Connections past MaxConcurrentConnections that wait for a slot instead of being rejected.
+    pub connection_backlog: Option<usize>,
+    // BSON a single request may hold buffered at once; unlimited when not set.
+    pub max_request_memory_bytes: Option<usize>,
 
     // Unix domain socket configuration
     // If specified with a non-empty path, Unix socket is enabled at that path.
@@ -69,6 +101,29 @@ pub struct DocumentDBSetupConfiguration {
     // Kind identifier for this gateway instance, included in hello command response.
     pub instance_kind: Option<String>,
 
+    // Version and wire range reported by hello and buildInfo; the dynamic serverVersion when not set.
+    pub server_description: Option<ServerDescriptionOptions>,
+    #[serde(skip)]
+    pub advertised_server_description: Option<ServerDescription>,
+
+    // Command fields redacted before logging, in addition to the built-in sensitive fields.
+    pub redacted_command_fields: Option<Vec<String>>,
+
+    // Whether logged commands show the values of their filters and documents; redacted when not set.
+    pub log_command_user_data: Option<bool>,
+
+    // Commands permitted before authentication; SASL and logout are always permitted.
+    pub pre_auth_allowed_commands: Option<Vec<String>>,
+
+    // Aggregation stages permitted in pipelines; every stage is permitted when not set.
+    pub aggregation_stage_allowlist: Option<Vec<String>>,
+    // Aggregation stages, and operators such as $where or $function, rejected in pipelines,
+    // whether or not they are allowlisted.
+    pub aggregation_stage_denylist: Option<Vec<String>>,
+
+    // Audit log of privileged commands; disabled when not set.
+    pub audit_options: Option<AuditOptions>,
+
     // Telemetry configuration
     pub telemetry_options: Option<TelemetryOptions>,
 }
@@ -79,7 +134,7 @@ impl DocumentDBSetupConfiguration {
     /// Returns an error if the operation fails.
     pub fn new(config_path: &Path) -> Result<Self> {
         let config_file = File::open(config_path)?;
-        let config: Self = serde_json::from_reader(config_file).map_err(|e| {
+        let mut config: Self = serde_json::from_reader(config_file).map_err(|e| {
             DocumentDBError::internal_error(format!("Failed to parse configuration file: {e}"))
         })?;
 
@@ -101,6 +156,20 @@ impl DocumentDBSetupConfiguration {
             }
         }
 
+        config.backend_router = BackendRouter::new(
+            config
+                .postgres_backend_routes
+                .as_deref()
+                .unwrap_or_default(),
+            config.postgres_port(),
+        )?;
+
+        config.advertised_server_description = config
+            .server_description
+            .as_ref()
+            .map(ServerDescription::new)
+            .transpose()?;
+
         Ok(config)
     }
 }
@@ -123,6 +192,10 @@ impl SetupConfiguration for DocumentDBSetupConfiguration {
         self.postgres_port.unwrap_or(9712)
     }
 
+    fn backend_router(&self) -> &BackendRouter {
+        &self.backend_router
+    }
+
     fn postgres_database(&self) -> &str {
         self.postgres_database.as_deref().unwrap_or("postgres")
     }
@@ -190,6 +263,10 @@ impl SetupConfiguration for DocumentDBSetupConfiguration {
         self.postgres_startup_wait_time_seconds.unwrap_or(60)
     }
 
+    fn async_runtime_flavor(&self) -> AsyncRuntimeFlavor {
+        self.async_runtime_flavor.unwrap_or_default()
+    }
+
     fn async_runtime_worker_threads(&self) -> usize {
         self.async_runtime_worker_threads.unwrap_or_else(|| {
             std::thread::available_parallelism()
@@ -214,6 +291,34 @@ impl SetupConfiguration for DocumentDBSetupConfiguration {
         self.postgres_idle_connection_timeout_minutes.unwrap_or(5)
     }
 
+    fn postgres_statement_cache_size(&self) -> usize {
+        self.postgres_statement_cache_size.unwrap_or(256)
+    }
+
+    fn enable_collation(&self) -> bool {
+        self.enable_collation.unwrap_or(false)
+    }
+
+    fn client_idle_timeout_ms(&self) -> u64 {
+        self.client_idle_timeout_ms.unwrap_or(0)
+    }
+
+    fn tcp_keepalive_enabled(&self) -> bool {
+        self.tcp_keepalive_enabled.unwrap_or(true)
+    }
+
+    fn tcp_keepalive_idle_secs(&self) -> u64 {
+        self.tcp_keepalive_idle_secs.unwrap_or(180)
+    }
+
+    fn tcp_keepalive_interval_secs(&self) -> u64 {
+        self.tcp_keepalive_interval_secs.unwrap_or(60)
+    }
+
+    fn tcp_keepalive_probes(&self) -> u32 {
+        self.tcp_keepalive_probes.unwrap_or(9)
+    }
+
     fn enforce_tls(&self) -> bool {
         self.enforce_tls.unwrap_or(true)
     }
@@ -230,6 +335,66 @@ impl SetupConfiguration for DocumentDBSetupConfiguration {
         self.instance_kind.as_deref().unwrap_or("")
     }
 
+    fn redacted_command_fields(&self) -> &[String] {
+        self.redacted_command_fields.as_deref().unwrap_or_default()
+    }
+
+    fn log_command_user_data(&self) -> bool {
+        self.log_command_user_data.unwrap_or(false)
+    }
+
+    fn pre_auth_allowed_commands(&self) -> Option<&[String]> {
+        self.pre_auth_allowed_commands.as_deref()
+    }
+
+    fn aggregation_stage_allowlist(&self) -> Option<&[String]> {
+        self.aggregation_stage_allowlist.as_deref()
+    }
+
+    fn aggregation_stage_denylist(&self) -> &[String] {
+        self.aggregation_stage_denylist
+            .as_deref()
+            .unwrap_or_default()
+    }
+
+    fn max_bson_object_size(&self) -> i32 {
+        self.max_bson_object_size
+            .unwrap_or(protocol::DEFAULT_MAX_BSON_OBJECT_SIZE)
+    }
+
+    fn max_message_size_bytes(&self) -> i32 {
+        self.max_message_size_bytes
+            .unwrap_or(protocol::DEFAULT_MAX_MESSAGE_SIZE_BYTES)
+    }
+
+    fn health_check_port(&self) -> Option<u16> {
+        self.health_check_port
+    }
+
+    fn shutdown_grace_ms(&self) -> u64 {
+        self.shutdown_grace_ms.unwrap_or(15_000)
+    }
+
+    fn max_concurrent_connections(&self) -> Option<usize> {
+        self.max_concurrent_connections
+    }
+
+    fn connection_backlog(&self) -> usize {
+        self.connection_backlog.unwrap_or(0)
+    }
+
+    fn max_request_memory_bytes(&self) -> Option<usize> {
+        self.max_request_memory_bytes
+    }
+
+    fn server_description(&self) -> Option<&ServerDescription> {
+        self.advertised_server_description.as_ref()
+    }
+
+    fn audit_options(&self) -> Option<&AuditOptions> {
+        self.audit_options.as_ref()
+    }
+
     fn telemetry_options(&self) -> Option<&TelemetryOptions> {
         self.telemetry_options.as_ref()
     }
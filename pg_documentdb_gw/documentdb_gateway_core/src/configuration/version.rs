@@ -6,7 +6,15 @@
  *-------------------------------------------------------------------------
  */
 
+use std::borrow::Cow;
+
 use bson::RawArrayBuf;
+use serde::Deserialize;
+
+use crate::{
+    configuration::{DynamicConfiguration, SetupConfiguration},
+    error::{DocumentDBError, Result},
+};
 
 #[derive(Debug)]
 pub enum Version {
@@ -73,3 +81,172 @@ impl Version {
         }
     }
 }
+
+/// JSON configuration for the server version advertised by `hello` and `buildInfo`
+/// (matches SetupConfiguration.json `ServerDescription`).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ServerDescriptionOptions {
+    /// A version such as `6.0.14`.
+    pub version: String,
+    /// Defaults to 0.
+    pub min_wire_version: Option<i32>,
+    /// Defaults to the wire version of the `Version` release; required for releases the
+    /// gateway does not know.
+    pub max_wire_version: Option<i32>,
+}
+
+/// The server version and wire protocol range advertised to clients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerDescription {
+    version: String,
+    version_array: [i32; 4],
+    min_wire_version: i32,
+    max_wire_version: i32,
+}
+
+impl ServerDescription {
+    /// # Errors
+    ///
+    /// Returns an error if the version is not two to four dot-separated numbers, the
+    /// maximum wire version is missing for an unknown release, or the wire range is empty.
+    pub fn new(options: &ServerDescriptionOptions) -> Result<Self> {
+        let invalid_version = || {
+            DocumentDBError::internal_error(format!(
+                "Invalid ServerDescription Version '{}'. Expected a version like '6.0.14'.",
+                options.version
+            ))
+        };
+
+        let parts = options
+            .version
+            .split('.')
+            .map(|part| part.parse::<i32>().ok().filter(|number| *number >= 0))
+            .collect::<Option<Vec<i32>>>()
+            .ok_or_else(invalid_version)?;
+        if !(2..=4).contains(&parts.len()) {
+            return Err(invalid_version());
+        }
+        let mut version_array = [0; 4];
+        version_array[..parts.len()].copy_from_slice(&parts);
+
+        let max_wire_version = match options.max_wire_version {
+            Some(max_wire_version) => max_wire_version,
+            None => Version::parse(&format!("{}.{}", parts[0], parts[1]))
+                .map(|version| version.max_wire_protocol())
+                .ok_or_else(|| {
+                    DocumentDBError::internal_error(format!(
+                        "ServerDescription MaxWireVersion is required for version '{}'.",
+                        options.version
+                    ))
+                })?,
+        };
+        let min_wire_version = options.min_wire_version.unwrap_or(0);
+        if min_wire_version < 0 || min_wire_version > max_wire_version {
+            return Err(DocumentDBError::internal_error(format!(
+                "ServerDescription MinWireVersion {min_wire_version} must be between 0 and MaxWireVersion {max_wire_version}."
+            )));
+        }
+
+        Ok(Self {
+            version: options.version.clone(),
+            version_array,
+            min_wire_version,
+            max_wire_version,
+        })
+    }
+
+    /// Returns the configured description, or the one of the dynamic `serverVersion`.
+    #[must_use]
+    pub fn advertised<'a>(
+        setup_configuration: &'a dyn SetupConfiguration,
+        dynamic_configuration: &dyn DynamicConfiguration,
+    ) -> Cow<'a, Self> {
+        setup_configuration.server_description().map_or_else(
+            || Cow::Owned(Self::from(&dynamic_configuration.server_version())),
+            Cow::Borrowed,
+        )
+    }
+
+    #[must_use]
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    #[must_use]
+    pub fn version_bson_array(&self) -> RawArrayBuf {
+        let mut array = RawArrayBuf::new();
+        for v in self.version_array {
+            array.push(v);
+        }
+        array
+    }
+
+    #[must_use]
+    pub const fn min_wire_version(&self) -> i32 {
+        self.min_wire_version
+    }
+
+    #[must_use]
+    pub const fn max_wire_version(&self) -> i32 {
+        self.max_wire_version
+    }
+}
+
+impl From<&Version> for ServerDescription {
+    fn from(version: &Version) -> Self {
+        Self {
+            version: version.as_str().to_owned(),
+            version_array: version.as_array(),
+            min_wire_version: 0,
+            max_wire_version: version.max_wire_protocol(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(
+        version: &str,
+        min_wire_version: Option<i32>,
+        max_wire_version: Option<i32>,
+    ) -> ServerDescriptionOptions {
+        ServerDescriptionOptions {
+            version: version.to_owned(),
+            min_wire_version,
+            max_wire_version,
+        }
+    }
+
+    #[test]
+    fn known_release_defaults_the_wire_range() {
+        let description = ServerDescription::new(&options("6.0.14", None, None)).unwrap();
+
+        assert_eq!(description.version(), "6.0.14");
+        assert_eq!(description.version_array, [6, 0, 14, 0]);
+        assert_eq!(description.min_wire_version(), 0);
+        assert_eq!(description.max_wire_version(), 17);
+    }
+
+    #[test]
+    fn unknown_release_requires_max_wire_version() {
+        ServerDescription::new(&options("9.1.0", None, None)).unwrap_err();
+
+        let description = ServerDescription::new(&options("9.1", Some(6), Some(30))).unwrap();
+        assert_eq!(description.version_array, [9, 1, 0, 0]);
+        assert_eq!(description.min_wire_version(), 6);
+        assert_eq!(description.max_wire_version(), 30);
+    }
+
+    #[test]
+    fn rejects_invalid_versions_and_wire_ranges() {
+        for version in ["", "7", "7.0.x", "7.0.0.0.0", "-7.0"] {
+            ServerDescription::new(&options(version, None, Some(21))).unwrap_err();
+        }
+        ServerDescription::new(&options("7.0.0", Some(22), None)).unwrap_err();
+        ServerDescription::new(&options("7.0.0", Some(-1), None)).unwrap_err();
+        ServerDescription::new(&options("7.0.0", Some(21), Some(21))).unwrap();
+    }
+}
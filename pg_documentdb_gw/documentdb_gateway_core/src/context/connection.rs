@@ -19,10 +19,13 @@ use uuid::{Builder, Uuid};
 use crate::{
     auth::AuthState,
     configuration::DynamicConfiguration,
-    context::{Cursor, CursorKey, CursorStoreEntry, ServiceContext, SessionId, TransactionNumber},
+    context::{
+        ConnectionCloseReason, ConnectionStats, Cursor, CursorKey, CursorStoreEntry,
+        ServiceContext, SessionId, TransactionNumber,
+    },
     error::Result,
     postgres::conn_mgmt::Connection,
-    telemetry::TelemetryProvider,
+    telemetry::{client_info::UNKNOWN_APP_NAME, event_id::EventId, TelemetryProvider},
 };
 
 #[derive(Debug)]
@@ -33,11 +36,14 @@ pub struct ConnectionContext {
     pub auth_state: AuthState,
     pub requires_response: bool,
     pub client_information: Option<RawDocumentBuf>,
+    /// The `appName` sent in the handshake, if any.
+    pub app_name: Option<String>,
     pub transaction: Option<(SessionId, TransactionNumber)>,
     pub telemetry_provider: Option<Box<dyn TelemetryProvider>>,
     pub ip_address: String,
     pub cipher_type: i32,
     pub ssl_protocol: String,
+    pub stats: ConnectionStats,
     transport_protocol: String,
     connection_id_hash: i32,
 }
@@ -71,16 +77,34 @@ impl ConnectionContext {
             auth_state: AuthState::new(),
             requires_response: true,
             client_information: None,
+            app_name: None,
             transaction: None,
             telemetry_provider,
             ip_address,
             cipher_type,
             ssl_protocol,
+            stats: ConnectionStats::default(),
             transport_protocol,
             connection_id_hash: Self::get_uuid_hash(connection_id),
         }
     }
 
+    /// The client's `appName`, or `"unknown"` if the handshake had none.
+    #[must_use]
+    pub fn app_name(&self) -> &str {
+        self.app_name.as_deref().unwrap_or(UNKNOWN_APP_NAME)
+    }
+
+    /// The tenant the authenticated identity maps to under the dynamic `tenantMapping`, if
+    /// any, recorded on the spans and metrics of the connection's requests.
+    #[must_use]
+    pub fn tenant(&self) -> Option<String> {
+        self.dynamic_configuration()
+            .tenant_mapping()
+            .tenant(self.auth_state.username().ok(), self.auth_state.roles())
+            .map(str::to_owned)
+    }
+
     #[must_use]
     pub fn get_cursor(&self, id: i64, username: &str) -> Option<CursorStoreEntry> {
         let key = CursorKey {
@@ -113,6 +137,13 @@ impl ConnectionContext {
         cursor_timeout: Duration,
         session_id: Option<SessionId>,
     ) {
+        if let Some(bytes_per_document) = cursor.bytes_per_document {
+            self.service_context.cursor_store().record_document_size(
+                db,
+                collection,
+                bytes_per_document,
+            );
+        }
         let key = CursorKey {
             cursor_id: cursor.cursor_id,
             username: username.to_owned(),
@@ -125,6 +156,7 @@ impl ConnectionContext {
             timestamp: Instant::now(),
             cursor_timeout,
             session_id,
+            connection_id: self.connection_id,
         };
 
         // If there is a transaction, add the cursor to its store
@@ -195,6 +227,32 @@ impl ConnectionContext {
         self.service_context.request_metrics_enabled()
     }
 
+    /// Emits a single structured summary of the connection's lifetime counters,
+    /// if enabled by `enableConnectionCloseSummaryLog`.
+    pub fn log_close_summary(&self, close_reason: ConnectionCloseReason) {
+        if !self
+            .dynamic_configuration()
+            .enable_connection_close_summary_log()
+        {
+            return;
+        }
+
+        let summary = self.stats.summary(self.start_time, close_reason);
+        tracing::info!(
+            activity_id = self.connection_id.to_string().as_str(),
+            event_id = EventId::ConnectionClose.code(),
+            total_requests = summary.requests,
+            bytes_in = summary.bytes_in,
+            bytes_out = summary.bytes_out,
+            peak_concurrent_requests = summary.peak_concurrent_requests,
+            duration_ms = u64::try_from(summary.duration.as_millis()).unwrap_or(u64::MAX),
+            close_reason = summary.close_reason.as_str(),
+            address = %self.ip_address,
+            transport_protocol = %self.transport_protocol,
+            "Connection close summary."
+        );
+    }
+
     #[must_use]
     pub const fn get_connection_id_hash(&self) -> i32 {
         self.connection_id_hash
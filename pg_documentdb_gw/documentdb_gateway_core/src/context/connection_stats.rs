@@ -0,0 +1,195 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/context/connection_stats.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tokio::time::{Duration, Instant};
+
+/// Why a client connection was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionCloseReason {
+    /// The client closed the stream.
+    ClientDisconnected,
+
+    /// A response could not be written back to the client.
+    WriteFailed,
+
+    /// The gateway closed the connection while shutting down.
+    ServerShutdown,
+
+    /// The client sent a message that left the stream unreadable.
+    InvalidMessage,
+
+    /// No request arrived within the client idle timeout.
+    IdleTimeout,
+
+    /// The client stopped answering TCP keepalive probes.
+    KeepaliveTimeout,
+
+    /// The handler of a request panicked.
+    HandlerPanic,
+}
+
+impl ConnectionCloseReason {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ClientDisconnected => "ClientDisconnected",
+            Self::WriteFailed => "WriteFailed",
+            Self::ServerShutdown => "ServerShutdown",
+            Self::InvalidMessage => "InvalidMessage",
+            Self::IdleTimeout => "IdleTimeout",
+            Self::KeepaliveTimeout => "KeepaliveTimeout",
+            Self::HandlerPanic => "HandlerPanic",
+        }
+    }
+}
+
+/// Counters accumulated over the life of a single client connection.
+#[derive(Debug, Default)]
+pub struct ConnectionStats {
+    requests: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    peak_concurrent_requests: AtomicU64,
+}
+
+/// Snapshot of a connection's counters, emitted when the connection closes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionSummary {
+    pub requests: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub peak_concurrent_requests: u64,
+    pub duration: Duration,
+    pub close_reason: ConnectionCloseReason,
+}
+
+impl ConnectionStats {
+    /// Records a request of `message_length` bytes entering the connection, with the
+    /// `queued_requests` the client has already sent behind it.
+    ///
+    /// Requests are handled one at a time, so the client's concurrency is the request being
+    /// handled and those it pipelined without waiting for a response.
+    pub fn begin_request(&self, message_length: usize, queued_requests: usize) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.bytes_in
+            .fetch_add(message_length as u64, Ordering::Relaxed);
+        self.peak_concurrent_requests
+            .fetch_max(queued_requests as u64 + 1, Ordering::Relaxed);
+    }
+
+    /// Records a response message of `response_length` bytes, header included, written back
+    /// to the client.
+    pub fn record_response(&self, response_length: usize) {
+        self.bytes_out
+            .fetch_add(response_length as u64, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn summary(
+        &self,
+        start_time: Instant,
+        close_reason: ConnectionCloseReason,
+    ) -> ConnectionSummary {
+        ConnectionSummary {
+            requests: self.requests.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            peak_concurrent_requests: self.peak_concurrent_requests.load(Ordering::Relaxed),
+            duration: start_time.elapsed(),
+            close_reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+    use tokio::io::{AsyncWriteExt, BufStream};
+    use tokio_util::sync::CancellationToken;
+
+    use super::*;
+    use crate::{
+        handle_stream,
+        testing::{self, MapConfiguration, StubDataClient},
+    };
+
+    /// Sends each of `requests` over a connection once the previous one is answered, then
+    /// closes it, returning the connection's summary and the length of each reply message.
+    async fn serve(requests: Vec<Vec<u8>>) -> (ConnectionSummary, Vec<usize>) {
+        let service_context = testing::service_context(MapConfiguration::default()).await;
+        let connection_context = testing::connection_context(service_context, "user");
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+
+        let client_side = async move {
+            let mut reply_lengths = Vec::new();
+            for request in requests {
+                client.write_all(&request).await.unwrap();
+                let reply = testing::read_message(&mut client).await;
+                reply_lengths.push(reply.len());
+            }
+            drop(client);
+            reply_lengths
+        };
+
+        let draining = CancellationToken::new();
+        tokio::join!(
+            handle_stream::<StubDataClient, _>(
+                BufStream::new(server),
+                connection_context,
+                Duration::ZERO,
+                &draining,
+            ),
+            client_side,
+        )
+    }
+
+    #[tokio::test]
+    async fn summary_reflects_accumulated_requests() {
+        let requests = vec![
+            testing::op_msg(1, &rawdoc! { "ping": 1, "$db": "admin" }),
+            // Failed requests are answered, and counted, too.
+            testing::op_msg(2, &rawdoc! { "notACommand": 1, "$db": "admin" }),
+            testing::op_msg(
+                3,
+                &rawdoc! { "ping": 1, "comment": "again", "$db": "admin" },
+            ),
+        ];
+        let bytes_in: usize = requests.iter().map(Vec::len).sum();
+
+        let (summary, reply_lengths) = serve(requests).await;
+
+        assert_eq!(summary.requests, 3);
+        assert_eq!(summary.bytes_in, bytes_in as u64);
+        assert_eq!(
+            summary.bytes_out,
+            reply_lengths.iter().sum::<usize>() as u64
+        );
+        assert_eq!(summary.peak_concurrent_requests, 1);
+        assert_eq!(
+            summary.close_reason,
+            ConnectionCloseReason::ClientDisconnected
+        );
+    }
+
+    #[tokio::test]
+    async fn connection_closed_without_requests_is_empty() {
+        let (summary, reply_lengths) = serve(Vec::new()).await;
+
+        assert!(reply_lengths.is_empty());
+        assert_eq!(summary.requests, 0);
+        assert_eq!(summary.bytes_in, 0);
+        assert_eq!(summary.bytes_out, 0);
+        assert_eq!(summary.peak_concurrent_requests, 0);
+        assert_eq!(
+            summary.close_reason,
+            ConnectionCloseReason::ClientDisconnected
+        );
+    }
+}
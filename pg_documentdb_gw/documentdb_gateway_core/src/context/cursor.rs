@@ -6,19 +6,33 @@
  *-------------------------------------------------------------------------
  */
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, Weak,
+    },
+};
 
-use bson::RawDocumentBuf;
+use bson::{RawBson, RawDocumentBuf};
 use dashmap::DashMap;
+use opentelemetry::metrics::ObservableUpDownCounter;
 use tokio::{
     task::JoinHandle,
     time::{Duration, Instant},
 };
+use uuid::Uuid;
 
 use crate::{
-    configuration::DynamicConfiguration, context::SessionId, postgres::conn_mgmt::Connection,
+    configuration::DynamicConfiguration,
+    context::SessionId,
+    postgres::conn_mgmt::Connection,
+    telemetry::metrics::{record_cursor_evicted, register_open_cursors_gauge},
 };
 
+/// How many namespaces the store keeps a document size estimate for.
+const MAX_DOCUMENT_SIZE_NAMESPACES: usize = 1024;
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct CursorId(i64);
 
@@ -66,9 +80,32 @@ pub struct CursorKey {
 }
 
 #[derive(Debug)]
+#[expect(
+    clippy::struct_field_names,
+    reason = "cursor_id matches the name used throughout the cursor store"
+)]
 pub struct Cursor {
     pub continuation: RawDocumentBuf,
     pub cursor_id: CursorId,
+    /// Set for tailable cursors, which the gateway serves itself instead of from a backend continuation.
+    pub tailable: Option<TailableCursor>,
+    /// Average size of the documents in the last batch, used to bound the size of the next one.
+    pub bytes_per_document: Option<usize>,
+}
+
+/// The state of a tailable `find`, resumed by querying for the documents it has not returned.
+///
+/// `_id`s need not grow with insertion, so the cursor excludes every `_id` it returned rather
+/// than resuming past the last one.
+#[derive(Debug)]
+pub struct TailableCursor {
+    pub filter: RawDocumentBuf,
+    /// Find options passed through unchanged on every query, such as `projection` and `collation`.
+    pub options: RawDocumentBuf,
+    pub returned_ids: Vec<RawBson>,
+    /// Documents left before the `limit` of the find is reached, if it had one.
+    pub remaining: Option<i64>,
+    pub await_data: bool,
 }
 
 #[derive(Debug)]
@@ -80,20 +117,37 @@ pub struct CursorStoreEntry {
     pub timestamp: Instant,
     pub cursor_timeout: Duration,
     pub session_id: Option<SessionId>,
+    /// The client connection that opened the cursor.
+    pub connection_id: Uuid,
 }
 
 // Maps CursorKey -> Connection, Cursor
 #[derive(Debug)]
 pub struct CursorStore {
     cursors: Arc<DashMap<CursorKey, CursorStoreEntry>>,
+    /// The last id handed out for a cursor the gateway serves itself. These count down from -1,
+    /// so they never collide with each other or with the positive ids of backend cursors.
+    last_gateway_cursor_id: AtomicI64,
+    /// The last average document size seen in a batch of each namespace, by database and
+    /// collection, to bound the first batch of the next cursor opened on it.
+    document_sizes: DashMap<(String, String), usize>,
+    config: Arc<dyn DynamicConfiguration>,
     _reaper: Option<JoinHandle<()>>,
+    _open_cursors_gauge: Option<ObservableUpDownCounter<i64>>,
 }
 
 impl CursorStore {
+    /// The reaped store is the gateway-wide one, so it also reports the open cursor gauge.
     pub fn new(config: Arc<dyn DynamicConfiguration>, use_reaper: bool) -> Self {
         let cursors: Arc<DashMap<CursorKey, CursorStoreEntry>> = Arc::new(DashMap::new());
         let cursors_clone = Arc::clone(&cursors);
+        let reaper_config = Arc::clone(&config);
+        let open_cursors_gauge = use_reaper.then(|| {
+            let cursors = Arc::downgrade(&cursors);
+            register_open_cursors_gauge(move || open_cursors_by_db(&cursors))
+        });
         let reaper = use_reaper.then(|| {
+            let config = reaper_config;
             tokio::spawn(async move {
                 let mut cursor_timeout_resolution =
                     Duration::from_secs(config.cursor_resolution_interval());
@@ -114,14 +168,73 @@ impl CursorStore {
 
         Self {
             cursors,
+            last_gateway_cursor_id: AtomicI64::new(0),
+            document_sizes: DashMap::new(),
+            config,
             _reaper: reaper,
+            _open_cursors_gauge: open_cursors_gauge,
         }
     }
 
+    /// Saves a cursor, first evicting the least recently used ones if the store is at
+    /// the configured limit. Dropping an evicted entry returns its connection to the pool.
     pub fn add_cursor(&self, k: CursorKey, v: CursorStoreEntry) {
+        let max_open_cursors =
+            usize::try_from(self.config.max_open_cursors()).unwrap_or(usize::MAX);
+        if max_open_cursors > 0 && !self.cursors.contains_key(&k) {
+            while self.cursors.len() >= max_open_cursors {
+                if !self.evict_least_recently_used() {
+                    break;
+                }
+            }
+        }
         self.cursors.insert(k, v);
     }
 
+    /// Records the average document size of a batch on `db.collection`. Once
+    /// `MAX_DOCUMENT_SIZE_NAMESPACES` namespaces are known, only those are kept up to date.
+    pub fn record_document_size(&self, db: &str, collection: &str, bytes_per_document: usize) {
+        let key = (db.to_owned(), collection.to_owned());
+        if let Some(mut size) = self.document_sizes.get_mut(&key) {
+            *size = bytes_per_document;
+        } else if self.document_sizes.len() < MAX_DOCUMENT_SIZE_NAMESPACES {
+            self.document_sizes.insert(key, bytes_per_document);
+        }
+    }
+
+    /// The last average document size recorded for `db.collection`, if any.
+    #[must_use]
+    pub fn document_size_estimate(&self, db: &str, collection: &str) -> Option<usize> {
+        self.document_sizes
+            .get(&(db.to_owned(), collection.to_owned()))
+            .map(|size| *size)
+    }
+
+    /// Allocates the id of a cursor served by the gateway rather than the backend.
+    pub fn allocate_cursor_id(&self) -> CursorId {
+        CursorId::new(self.last_gateway_cursor_id.fetch_sub(1, Ordering::Relaxed) - 1)
+    }
+
+    /// Cursors are re-saved after every `getMore`, so the oldest timestamp is the least recently used.
+    fn evict_least_recently_used(&self) -> bool {
+        let oldest = self
+            .cursors
+            .iter()
+            .min_by_key(|entry| entry.timestamp)
+            .map(|entry| entry.key().clone());
+        let Some((_, evicted)) = oldest.and_then(|key| self.cursors.remove(&key)) else {
+            return false;
+        };
+        tracing::info!(
+            "Evicted cursor {} on {}.{} to stay under the open cursor limit.",
+            evicted.cursor.cursor_id,
+            evicted.db,
+            evicted.collection
+        );
+        record_cursor_evicted(&evicted.db);
+        true
+    }
+
     #[must_use]
     pub fn get_cursor(&self, k: &CursorKey) -> Option<CursorStoreEntry> {
         self.cursors.remove(k).map(|(_, v)| v)
@@ -149,6 +262,20 @@ impl CursorStore {
         invalidated_cursor_ids
     }
 
+    /// Removes the cursors opened by a client connection, returning their ids.
+    #[must_use]
+    pub fn invalidate_cursors_by_connection(&self, connection_id: Uuid) -> Vec<i64> {
+        let mut invalidated_cursor_ids = Vec::new();
+        self.cursors.retain(|key, v| {
+            let should_remove = v.connection_id == connection_id;
+            if should_remove {
+                invalidated_cursor_ids.push(i64::from(key.cursor_id));
+            }
+            !should_remove
+        });
+        invalidated_cursor_ids
+    }
+
     #[must_use]
     pub fn kill_cursors(&self, user: &str, cursors: &[i64]) -> (Vec<i64>, Vec<i64>) {
         let mut removed_cursors = Vec::new();
@@ -169,10 +296,23 @@ impl CursorStore {
     }
 }
 
+fn open_cursors_by_db(
+    cursors: &Weak<DashMap<CursorKey, CursorStoreEntry>>,
+) -> HashMap<String, i64> {
+    let mut counts = HashMap::new();
+    if let Some(cursors) = cursors.upgrade() {
+        for entry in cursors.iter() {
+            *counts.entry(entry.db.clone()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
 #[cfg(test)]
 mod tests {
+    use bson::rawbson;
+
     use super::*;
-    use std::collections::HashMap;
 
     // ── CursorId tests ──
 
@@ -290,25 +430,86 @@ mod tests {
 
     // ── CursorStore tests (no reaper, no tokio runtime needed for basic ops) ──
 
-    fn make_store() -> CursorStore {
+    /// Configuration with only `maxOpenCursors` set.
+    #[derive(Debug)]
+    struct MaxOpenCursorsConfig(u64);
+
+    impl DynamicConfiguration for MaxOpenCursorsConfig {
+        fn get_str(&self, _: &str) -> Option<String> {
+            None
+        }
+
+        fn get_bool(&self, _: &str, default: bool) -> bool {
+            default
+        }
+
+        fn get_i32(&self, _: &str, default: i32) -> i32 {
+            default
+        }
+
+        fn get_u64(&self, key: &str, default: u64) -> u64 {
+            if key == "maxOpenCursors" {
+                self.0
+            } else {
+                default
+            }
+        }
+
+        fn equals_value(&self, _: &str, _: &str) -> bool {
+            false
+        }
+
+        fn topology(&self) -> RawBson {
+            rawbson!({})
+        }
+
+        fn enable_developer_explain(&self) -> bool {
+            false
+        }
+
+        fn max_connections(&self) -> usize {
+            0
+        }
+
+        fn allow_transaction_snapshot(&self) -> bool {
+            false
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    fn make_capped_store(max_open_cursors: u64) -> CursorStore {
         CursorStore {
             cursors: Arc::new(DashMap::new()),
+            last_gateway_cursor_id: AtomicI64::new(0),
+            document_sizes: DashMap::new(),
+            config: Arc::new(MaxOpenCursorsConfig(max_open_cursors)),
             _reaper: None,
+            _open_cursors_gauge: None,
         }
     }
 
+    fn make_store() -> CursorStore {
+        make_capped_store(0)
+    }
+
     fn make_entry(session_id: Option<SessionId>) -> CursorStoreEntry {
         CursorStoreEntry {
             conn: None,
             cursor: Cursor {
                 continuation: RawDocumentBuf::new(),
                 cursor_id: CursorId::new(0),
+                tailable: None,
+                bytes_per_document: None,
             },
             db: "testdb".to_owned(),
             collection: "testcol".to_owned(),
             timestamp: Instant::now(),
             cursor_timeout: Duration::from_secs(600),
             session_id,
+            connection_id: Uuid::nil(),
         }
     }
 
@@ -327,6 +528,14 @@ mod tests {
         assert!(entry.is_some());
     }
 
+    #[test]
+    fn store_allocates_distinct_negative_cursor_ids() {
+        let store = make_store();
+        let first = i64::from(store.allocate_cursor_id());
+        let second = i64::from(store.allocate_cursor_id());
+        assert_eq!((first, second), (-1, -2));
+    }
+
     #[test]
     fn store_get_removes_entry() {
         let store = make_store();
@@ -363,6 +572,24 @@ mod tests {
         assert!(store.get_cursor(&key(2, "alice")).is_some());
     }
 
+    #[test]
+    fn store_invalidate_by_connection() {
+        let store = make_store();
+        store.add_cursor(key(1, "alice"), make_entry(None));
+
+        let mut other = make_entry(None);
+        other.connection_id = Uuid::new_v4();
+        let other_connection = other.connection_id;
+        store.add_cursor(key(2, "alice"), other);
+
+        assert_eq!(
+            store.invalidate_cursors_by_connection(other_connection),
+            vec![2]
+        );
+        assert!(store.get_cursor(&key(1, "alice")).is_some());
+        assert!(store.get_cursor(&key(2, "alice")).is_none());
+    }
+
     #[test]
     fn store_invalidate_by_database() {
         let store = make_store();
@@ -417,4 +644,38 @@ mod tests {
         assert_eq!(removed, vec![1]);
         assert_eq!(missing, vec![99]);
     }
+
+    #[test]
+    fn store_evicts_least_recently_used_past_cap() {
+        let store = make_capped_store(2);
+        let now = Instant::now();
+        let entry_at = |age_secs| {
+            let mut entry = make_entry(None);
+            entry.timestamp = now.checked_sub(Duration::from_secs(age_secs)).unwrap();
+            entry
+        };
+        store.add_cursor(key(1, "alice"), entry_at(30));
+        store.add_cursor(key(2, "bob"), entry_at(20));
+        store.add_cursor(key(3, "alice"), entry_at(10));
+
+        assert!(store.get_cursor(&key(1, "alice")).is_none());
+        assert!(store.get_cursor(&key(2, "bob")).is_some());
+        assert!(store.get_cursor(&key(3, "alice")).is_some());
+    }
+
+    #[test]
+    fn open_cursors_counted_by_database() {
+        let store = make_store();
+        store.add_cursor(key(1, "alice"), make_entry(None));
+        store.add_cursor(key(2, "alice"), make_entry(None));
+        let mut other = make_entry(None);
+        other.db = "otherdb".to_owned();
+        store.add_cursor(key(3, "alice"), other);
+
+        let counts = open_cursors_by_db(&Arc::downgrade(&store.cursors));
+        assert_eq!(
+            counts,
+            HashMap::from([("testdb".to_owned(), 2), ("otherdb".to_owned(), 1)])
+        );
+    }
 }
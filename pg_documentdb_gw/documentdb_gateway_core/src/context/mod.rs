@@ -7,17 +7,23 @@
  */
 
 mod connection;
+mod connection_stats;
 mod cursor;
+mod operation;
 mod request;
 mod service;
 mod session;
 mod transaction;
 
 pub use connection::ConnectionContext;
-pub use cursor::{Cursor, CursorId, CursorKey, CursorStore, CursorStoreEntry};
-pub use request::RequestContext;
+pub use connection_stats::{ConnectionCloseReason, ConnectionStats, ConnectionSummary};
+pub use cursor::{Cursor, CursorId, CursorKey, CursorStore, CursorStoreEntry, TailableCursor};
+pub use operation::{
+    OperationInfo, OperationRegistry, OperationSnapshot, RegisteredConnection, RegisteredOperation,
+};
+pub use request::{MemoryCharge, RequestContext, RequestMemory};
 pub use service::ServiceContext;
-pub use session::SessionId;
+pub use session::{SessionEntry, SessionId, SessionStore};
 pub use transaction::{
     GatewayTransaction, RequestTransactionInfo, TransactionNumber, TransactionStore,
 };
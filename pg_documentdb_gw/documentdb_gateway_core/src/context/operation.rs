@@ -0,0 +1,318 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/context/operation.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use dashmap::DashMap;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::requests::RequestType;
+
+/// Describes an in-flight request for `currentOp`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationInfo {
+    pub connection_id: Uuid,
+    pub client: String,
+    /// The currentOp operation type, e.g. "query" or "insert".
+    pub op: &'static str,
+    pub ns: String,
+    /// Describes the operation in `currentOp`; defaults to the connection.
+    pub desc: Option<String>,
+    /// The client's `comment`, reported in `currentOp` as `command.comment`.
+    pub comment: Option<String>,
+}
+
+impl OperationInfo {
+    #[must_use]
+    pub const fn new(
+        request_type: RequestType,
+        ns: String,
+        connection_id: Uuid,
+        client: String,
+    ) -> Self {
+        let op = match request_type {
+            RequestType::Find => "query",
+            RequestType::Insert => "insert",
+            RequestType::Update => "update",
+            RequestType::Delete => "remove",
+            RequestType::GetMore => "getmore",
+            _ => "command",
+        };
+        Self {
+            connection_id,
+            client,
+            op,
+            ns,
+            desc: None,
+            comment: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_desc(mut self, desc: String) -> Self {
+        self.desc = Some(desc);
+        self
+    }
+}
+
+#[derive(Debug)]
+struct OperationEntry {
+    token: CancellationToken,
+    info: OperationInfo,
+    started: Instant,
+}
+
+/// A point-in-time view of an in-flight request.
+#[derive(Debug, Clone)]
+pub struct OperationSnapshot {
+    pub op_id: i64,
+    pub info: OperationInfo,
+    pub running: Duration,
+}
+
+/// Requests currently being handled by this gateway, keyed by the opid assigned at intake.
+///
+/// Lets `killOp` cancel requests that are still queued or waiting on a pool connection,
+/// which the backend has no way to see, and lets `currentOp` report them.
+#[derive(Debug, Default)]
+pub struct OperationRegistry {
+    next_op_id: AtomicI64,
+    operations: Arc<DashMap<i64, OperationEntry>>,
+    /// Open client connections and their client addresses.
+    connections: Arc<DashMap<Uuid, String>>,
+}
+
+impl OperationRegistry {
+    /// Assigns an opid to a new request. The request stays registered until the returned guard is dropped.
+    pub fn register(&self, info: OperationInfo) -> RegisteredOperation {
+        let op_id = self.next_op_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let token = CancellationToken::new();
+        self.operations.insert(
+            op_id,
+            OperationEntry {
+                token: token.clone(),
+                info,
+                started: Instant::now(),
+            },
+        );
+        RegisteredOperation {
+            op_id,
+            token,
+            operations: Arc::clone(&self.operations),
+        }
+    }
+
+    /// Tracks an open client connection until the returned guard is dropped.
+    pub fn register_connection(&self, connection_id: Uuid, client: String) -> RegisteredConnection {
+        self.connections.insert(connection_id, client);
+        RegisteredConnection {
+            connection_id,
+            connections: Arc::clone(&self.connections),
+        }
+    }
+
+    /// Cancels the request with the given opid, returning false if it is not in flight.
+    pub fn kill(&self, op_id: i64) -> bool {
+        self.operations.get(&op_id).is_some_and(|entry| {
+            entry.token.cancel();
+            true
+        })
+    }
+
+    #[must_use]
+    pub fn operations(&self) -> Vec<OperationSnapshot> {
+        self.operations
+            .iter()
+            .map(|entry| OperationSnapshot {
+                op_id: *entry.key(),
+                info: entry.info.clone(),
+                running: entry.started.elapsed(),
+            })
+            .collect()
+    }
+
+    /// Open connections with no request in flight, as (connection id, client) pairs.
+    #[must_use]
+    pub fn idle_connections(&self) -> Vec<(Uuid, String)> {
+        self.connections
+            .iter()
+            .filter(|connection| {
+                !self
+                    .operations
+                    .iter()
+                    .any(|entry| entry.info.connection_id == *connection.key())
+            })
+            .map(|connection| (*connection.key(), connection.value().clone()))
+            .collect()
+    }
+
+    /// Open client connections, busy or idle.
+    #[must_use]
+    pub fn connection_count(&self) -> usize {
+        self.connections.len()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+}
+
+/// An in-flight request, deregistered when dropped.
+#[derive(Debug)]
+pub struct RegisteredOperation {
+    op_id: i64,
+    token: CancellationToken,
+    operations: Arc<DashMap<i64, OperationEntry>>,
+}
+
+impl RegisteredOperation {
+    #[must_use]
+    pub const fn op_id(&self) -> i64 {
+        self.op_id
+    }
+
+    /// Resolves once `killOp` targets this request.
+    pub async fn killed(&self) {
+        self.token.cancelled().await;
+    }
+}
+
+impl Drop for RegisteredOperation {
+    fn drop(&mut self) {
+        self.operations.remove(&self.op_id);
+    }
+}
+
+/// An open client connection, deregistered when dropped.
+#[derive(Debug)]
+pub struct RegisteredConnection {
+    connection_id: Uuid,
+    connections: Arc<DashMap<Uuid, String>>,
+}
+
+impl Drop for RegisteredConnection {
+    fn drop(&mut self) {
+        self.connections.remove(&self.connection_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{io::BufReader, sync::oneshot};
+
+    use super::*;
+    use crate::protocol::reader::wait_for_disconnect;
+
+    fn info(connection_id: Uuid) -> OperationInfo {
+        OperationInfo::new(
+            RequestType::Find,
+            "db.coll".to_owned(),
+            connection_id,
+            "127.0.0.1".to_owned(),
+        )
+    }
+
+    #[test]
+    fn operations_are_deregistered_on_drop() {
+        let registry = OperationRegistry::default();
+        let first = registry.register(info(Uuid::new_v4()));
+        let second = registry.register(info(Uuid::new_v4()));
+        assert_ne!(first.op_id(), second.op_id());
+        assert_eq!(registry.len(), 2);
+
+        let op_id = first.op_id();
+        drop(first);
+        assert!(!registry.kill(op_id));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn kill_cancels_queued_operation() {
+        let registry = OperationRegistry::default();
+        let operation = registry.register(info(Uuid::new_v4()));
+        let op_id = operation.op_id();
+
+        // Stands in for a request stuck waiting on pool checkout.
+        let task = tokio::spawn(async move {
+            tokio::select! {
+                () = tokio::time::sleep(Duration::from_secs(60)) => false,
+                () = operation.killed() => true,
+            }
+        });
+
+        assert!(registry.kill(op_id));
+        assert!(task.await.unwrap());
+        assert!(registry.is_empty());
+    }
+
+    #[tokio::test]
+    async fn client_disconnect_abandons_backend_query() {
+        // Stands in for a query whose cancel-on-drop guard sends the backend cancel.
+        struct BackendQuery(Option<oneshot::Sender<()>>);
+        impl Drop for BackendQuery {
+            fn drop(&mut self) {
+                if let Some(cancelled) = self.0.take() {
+                    cancelled.send(()).unwrap();
+                }
+            }
+        }
+
+        let registry = OperationRegistry::default();
+        let operation = registry.register(info(Uuid::new_v4()));
+        let (client, server) = tokio::io::duplex(64);
+        let mut stream = BufReader::new(server);
+        let (cancelled_tx, cancelled_rx) = oneshot::channel();
+
+        let query = BackendQuery(Some(cancelled_tx));
+
+        let task = tokio::spawn(async move {
+            let disconnected = tokio::select! {
+                () = async move {
+                    let _query = query;
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                } => false,
+                () = operation.killed() => false,
+                () = wait_for_disconnect(&mut stream) => true,
+            };
+            drop(operation);
+            disconnected
+        });
+
+        drop(client);
+        assert!(task.await.unwrap());
+        cancelled_rx.await.unwrap();
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn idle_connections_exclude_busy_ones() {
+        let registry = OperationRegistry::default();
+        let busy = Uuid::new_v4();
+        let idle = Uuid::new_v4();
+        let _busy_connection = registry.register_connection(busy, "10.0.0.1".to_owned());
+        let _idle_connection = registry.register_connection(idle, "10.0.0.2".to_owned());
+        let _operation = registry.register(info(busy));
+
+        assert_eq!(registry.idle_connections(), [(idle, "10.0.0.2".to_owned())]);
+        assert_eq!(registry.operations()[0].info.op, "query");
+    }
+}
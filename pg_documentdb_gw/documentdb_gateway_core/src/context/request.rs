@@ -6,7 +6,14 @@
  *-------------------------------------------------------------------------
  */
 
-use crate::requests::{request_tracker::RequestTracker, Request, RequestInfo};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bson::RawDocument;
+
+use crate::{
+    error::{DocumentDBError, ErrorCode, Result},
+    requests::{request_tracker::RequestTracker, Request, RequestInfo},
+};
 
 #[derive(Debug)]
 pub struct RequestContext<'a> {
@@ -14,6 +21,7 @@ pub struct RequestContext<'a> {
     pub payload: &'a Request<'a>,
     pub info: &'a RequestInfo<'a>,
     pub tracker: &'a RequestTracker,
+    pub memory: &'a RequestMemory,
 }
 
 impl<'a> RequestContext<'a> {
@@ -31,4 +39,137 @@ impl<'a> RequestContext<'a> {
     pub const fn info(&self) -> &'a RequestInfo<'a> {
         self.info
     }
+
+    /// The request with `document`, a rewrite of its command, in place of the command, of the
+    /// same type and with the same document sequence.
+    #[must_use]
+    pub const fn rewritten_payload<'b>(&self, document: &'b RawDocument) -> Request<'b>
+    where
+        'a: 'b,
+    {
+        Request::Raw(self.payload.request_type(), document, self.payload.extra())
+    }
+
+    /// This context serving `payload` instead of its own request, if given, with the request's
+    /// info, tracker and memory shared.
+    #[must_use]
+    pub fn with_payload<'b>(&self, payload: Option<&'b Request<'b>>) -> RequestContext<'b>
+    where
+        'a: 'b,
+    {
+        RequestContext {
+            activity_id: self.activity_id,
+            payload: payload.unwrap_or(self.payload),
+            info: self.info,
+            tracker: self.tracker,
+            memory: self.memory,
+        }
+    }
+}
+
+/// Accounts for the BSON a request holds buffered in the gateway: its body, intermediate
+/// results and the response batch.
+///
+/// Charging is a pair of atomic adds, cheap enough to wrap every buffer on the hot path.
+#[derive(Debug, Default)]
+pub struct RequestMemory {
+    /// Cap on the bytes held at once, or `None` when requests are not limited.
+    limit: Option<usize>,
+    used: AtomicUsize,
+    high_water_mark: AtomicUsize,
+}
+
+impl RequestMemory {
+    #[must_use]
+    pub const fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            used: AtomicUsize::new(0),
+            high_water_mark: AtomicUsize::new(0),
+        }
+    }
+
+    /// Charges `bytes` buffered by the request until the returned charge is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExceededMemoryLimit`, charging nothing, if the request would hold more than
+    /// the limit.
+    pub fn charge(&self, bytes: usize) -> Result<MemoryCharge<'_>> {
+        let used = self.used.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        if let Some(limit) = self.limit.filter(|limit| used > *limit) {
+            self.used.fetch_sub(bytes, Ordering::Relaxed);
+            return Err(DocumentDBError::documentdb_error(
+                ErrorCode::ExceededMemoryLimit,
+                format!("Request exceeded the memory limit of {limit} bytes"),
+            ));
+        }
+        self.high_water_mark.fetch_max(used, Ordering::Relaxed);
+        Ok(MemoryCharge {
+            memory: self,
+            bytes,
+        })
+    }
+
+    /// Returns the bytes currently charged.
+    #[must_use]
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Returns the most bytes charged at once over the request.
+    #[must_use]
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark.load(Ordering::Relaxed)
+    }
+}
+
+/// Memory charged to a request, released when dropped along with the buffer it covers.
+#[derive(Debug)]
+#[must_use]
+pub struct MemoryCharge<'a> {
+    memory: &'a RequestMemory,
+    bytes: usize,
+}
+
+impl Drop for MemoryCharge<'_> {
+    fn drop(&mut self) {
+        self.memory.used.fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charge_past_the_limit_is_rejected_and_released() {
+        let memory = RequestMemory::new(Some(1_000));
+
+        let body = memory.charge(600).unwrap();
+        let error = memory.charge(500).unwrap_err();
+        assert_eq!(
+            error.error_code_enum(),
+            Some(ErrorCode::ExceededMemoryLimit)
+        );
+        assert_eq!(memory.used(), 600);
+
+        drop(body);
+        assert_eq!(memory.used(), 0);
+        let response = memory.charge(1_000).unwrap();
+        assert_eq!(memory.high_water_mark(), 1_000);
+        drop(response);
+        assert_eq!(memory.used(), 0);
+    }
+
+    #[test]
+    fn unlimited_memory_tracks_the_high_water_mark() {
+        let memory = RequestMemory::default();
+
+        let first = memory.charge(usize::MAX / 4).unwrap();
+        let second = memory.charge(10).unwrap();
+        drop((first, second));
+        assert_eq!(memory.used(), 0);
+        assert_eq!(memory.high_water_mark(), usize::MAX / 4 + 10);
+    }
 }
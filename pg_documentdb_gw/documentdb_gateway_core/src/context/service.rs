@@ -9,12 +9,15 @@
 use std::{sync::Arc, time::Duration};
 
 use crate::{
+    auth::DatabaseAuthorizer,
     configuration::{DynamicConfiguration, SetupConfiguration},
-    context::{CursorStore, TransactionStore},
+    context::{CursorStore, OperationRegistry, SessionId, SessionStore, TransactionStore},
+    error::Result,
     postgres::{conn_mgmt::PoolManager, QueryCatalog},
+    protocol::LOGICAL_SESSION_TIMEOUT_MINUTES,
     responses::CustomPostgresErrorMapper,
     service::TlsProvider,
-    telemetry::TelemetryConfig,
+    telemetry::{audit::AuditLog, TelemetryConfig},
 };
 
 #[derive(Debug)]
@@ -24,9 +27,13 @@ pub struct ServiceContextInner {
     pub connection_pool_manager: Arc<PoolManager>,
     pub cursor_store: CursorStore,
     pub transaction_store: TransactionStore,
+    pub session_store: SessionStore,
+    pub operation_registry: OperationRegistry,
     pub tls_provider: TlsProvider,
     pub custom_pg_error_mapper: Option<Box<dyn CustomPostgresErrorMapper>>,
+    pub database_authorizer: Option<Box<dyn DatabaseAuthorizer>>,
     pub request_metrics_enabled: bool,
+    pub audit_log: Option<AuditLog>,
 }
 
 #[derive(Debug, Clone)]
@@ -40,12 +47,24 @@ impl ServiceContext {
         connection_pool_manager: Arc<PoolManager>,
         tls_provider: TlsProvider,
         custom_pg_error_mapper: Option<Box<dyn CustomPostgresErrorMapper>>,
+        database_authorizer: Option<Box<dyn DatabaseAuthorizer>>,
     ) -> Self {
         let request_metrics_enabled = TelemetryConfig::new(setup_configuration.telemetry_options())
             .metrics()
             .metrics_enabled();
         let timeout_secs = setup_configuration.transaction_timeout_secs();
         let cursor_store = CursorStore::new(Arc::clone(&dynamic_configuration), true);
+        // Startup preflight reports an audit file that cannot be opened.
+        let audit_log =
+            setup_configuration
+                .audit_options()
+                .and_then(|options| match AuditLog::open(options) {
+                    Ok(audit_log) => Some(audit_log),
+                    Err(e) => {
+                        tracing::error!("Audit logging disabled: {e}");
+                        None
+                    }
+                });
 
         let inner = ServiceContextInner {
             setup_configuration,
@@ -53,11 +72,66 @@ impl ServiceContext {
             connection_pool_manager,
             cursor_store,
             transaction_store: TransactionStore::new(Duration::from_secs(timeout_secs)),
+            session_store: SessionStore::new(Duration::from_secs(
+                u64::from(LOGICAL_SESSION_TIMEOUT_MINUTES) * 60,
+            )),
+            operation_registry: OperationRegistry::default(),
             tls_provider,
             custom_pg_error_mapper,
+            database_authorizer,
             request_metrics_enabled,
+            audit_log,
         };
-        Self(Arc::new(inner))
+        let service_context = Self(Arc::new(inner));
+        service_context.spawn_session_reaper();
+        service_context
+    }
+
+    /// Reaps idle sessions every half timeout, for as long as the service context lives.
+    fn spawn_session_reaper(&self) {
+        let inner = Arc::downgrade(&self.0);
+        let timeout = self.session_store().timeout();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(timeout / 2);
+            loop {
+                interval.tick().await;
+                let Some(inner) = inner.upgrade() else {
+                    return;
+                };
+                Self(inner).reap_sessions(timeout).await;
+            }
+        });
+    }
+
+    /// Ends the sessions idle for at least `idle_for` as `endSessions` does, aborting their
+    /// transactions and dropping their cursors, which returns the cursors' connections.
+    pub async fn reap_sessions(&self, idle_for: Duration) {
+        for session_id in self.session_store().reap(idle_for) {
+            match self.end_session(&session_id).await {
+                Ok(cursor_ids) => tracing::debug!(
+                    "Reaped idle session {session_id} and its {} cursors.",
+                    cursor_ids.len()
+                ),
+                Err(e) => tracing::warn!("Failed to end idle session {session_id}: {e}"),
+            }
+        }
+    }
+
+    /// Forgets `session_id`, aborting its transaction and removing its cursors, whose ids are
+    /// returned so that the backend can be told to kill them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session's transaction fails to abort.
+    pub async fn end_session(&self, session_id: &SessionId) -> Result<Vec<i64>> {
+        self.session_store().end(session_id);
+        let cursor_ids = self
+            .cursor_store()
+            .invalidate_cursors_by_session(session_id);
+        self.transaction_store()
+            .remove_transaction_by_session(session_id)
+            .await?;
+        Ok(cursor_ids)
     }
 
     #[must_use]
@@ -80,6 +154,16 @@ impl ServiceContext {
         &self.0.transaction_store
     }
 
+    #[must_use]
+    pub fn session_store(&self) -> &SessionStore {
+        &self.0.session_store
+    }
+
+    #[must_use]
+    pub fn operation_registry(&self) -> &OperationRegistry {
+        &self.0.operation_registry
+    }
+
     #[must_use]
     pub fn query_catalog(&self) -> &QueryCatalog {
         self.0.connection_pool_manager.query_catalog()
@@ -100,8 +184,18 @@ impl ServiceContext {
         self.0.custom_pg_error_mapper.as_deref()
     }
 
+    #[must_use]
+    pub fn database_authorizer(&self) -> Option<&dyn DatabaseAuthorizer> {
+        self.0.database_authorizer.as_deref()
+    }
+
     #[must_use]
     pub fn request_metrics_enabled(&self) -> bool {
         self.0.request_metrics_enabled
     }
+
+    #[must_use]
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.0.audit_log.as_ref()
+    }
 }
@@ -8,6 +8,11 @@
 
 use std::fmt;
 
+use dashmap::DashMap;
+use tokio::time::{Duration, Instant};
+
+use crate::context::TransactionNumber;
+
 #[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct SessionId(Vec<u8>);
 
@@ -53,9 +58,102 @@ impl fmt::Debug for SessionId {
     }
 }
 
+/// What the gateway knows about a logical session.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionEntry {
+    pub last_use: Instant,
+    /// Highest `txnNumber` seen on the session, for transactions and retryable writes.
+    pub transaction_number: Option<TransactionNumber>,
+}
+
+/// Logical sessions keyed by `lsid`.
+///
+/// The service context reaps those idle for longer than the timeout advertised as
+/// `logicalSessionTimeoutMinutes`, see
+/// [`ServiceContext::reap_sessions`](crate::context::ServiceContext::reap_sessions).
+#[derive(Debug)]
+pub struct SessionStore {
+    sessions: DashMap<SessionId, SessionEntry>,
+    timeout: Duration,
+}
+
+impl SessionStore {
+    #[must_use]
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            sessions: DashMap::new(),
+            timeout,
+        }
+    }
+
+    /// How long a session may stay idle before it is reaped.
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Records a command on `session_id`, starting the session if it is new.
+    pub fn touch(&self, session_id: &SessionId, transaction_number: Option<TransactionNumber>) {
+        let now = Instant::now();
+        self.sessions
+            .entry(session_id.clone())
+            .and_modify(|entry| {
+                entry.last_use = now;
+                entry.transaction_number = entry.transaction_number.max(transaction_number);
+            })
+            .or_insert(SessionEntry {
+                last_use: now,
+                transaction_number,
+            });
+    }
+
+    /// Keeps `session_id` alive as `refreshSessions` does, without touching its `txnNumber`.
+    pub fn refresh(&self, session_id: &SessionId) {
+        self.touch(session_id, None);
+    }
+
+    /// Forgets `session_id` as `endSessions` does.
+    pub fn end(&self, session_id: &SessionId) {
+        self.sessions.remove(session_id);
+    }
+
+    #[must_use]
+    pub fn get(&self, session_id: &SessionId) -> Option<SessionEntry> {
+        self.sessions.get(session_id).map(|entry| *entry)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Removes the sessions idle for at least `idle_for`, returning their ids so that their
+    /// transactions and cursors can be ended too.
+    #[must_use]
+    pub fn reap(&self, idle_for: Duration) -> Vec<SessionId> {
+        let mut reaped = Vec::new();
+        self.sessions.retain(|session_id, entry| {
+            let idle = entry.last_use.elapsed() >= idle_for;
+            if idle {
+                reaped.push(session_id.clone());
+            }
+            !idle
+        });
+        reaped
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use bson::rawdoc;
+
     use super::*;
+    use crate::{context::Cursor, testing};
 
     #[test]
     fn new_stores_bytes() {
@@ -146,4 +244,76 @@ mod tests {
         assert_eq!(map.get(&id1), Some(&"first"));
         assert_eq!(map.get(&id2), Some(&"second"));
     }
+
+    // ── SessionStore tests ──
+
+    #[test]
+    fn touch_keeps_the_highest_transaction_number() {
+        let store = SessionStore::new(Duration::from_secs(60));
+        let id = SessionId::new(vec![1]);
+
+        store.touch(&id, Some(TransactionNumber::new(3)));
+        store.touch(&id, Some(TransactionNumber::new(2)));
+        store.touch(&id, None);
+
+        assert_eq!(
+            store.get(&id).unwrap().transaction_number,
+            Some(TransactionNumber::new(3))
+        );
+    }
+
+    #[tokio::test]
+    async fn ended_session_is_removed_and_refreshed_session_survives_reaper() {
+        let timeout = Duration::from_millis(200);
+        let store = SessionStore::new(timeout);
+        let ended = SessionId::new(vec![1]);
+        let refreshed = SessionId::new(vec![2]);
+        let idle = SessionId::new(vec![3]);
+        for id in [&ended, &refreshed, &idle] {
+            store.touch(id, None);
+        }
+
+        store.end(&ended);
+        assert!(store.get(&ended).is_none());
+
+        tokio::time::sleep(timeout * 3 / 4).await;
+        store.refresh(&refreshed);
+        tokio::time::sleep(timeout / 2).await;
+        assert_eq!(store.reap(store.timeout()), std::slice::from_ref(&idle));
+
+        assert!(store.get(&refreshed).is_some());
+        assert!(store.get(&idle).is_none());
+        assert_eq!(store.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reaped_session_ends_its_cursors() {
+        let service_context = testing::service_context(testing::MapConfiguration::default()).await;
+        let connection_context = testing::connection_context(service_context.clone(), "user");
+        let reaped = SessionId::new(vec![1]);
+        let other = SessionId::new(vec![2]);
+        for (cursor_id, session_id) in [(1_i64, &reaped), (2, &other)] {
+            connection_context.add_cursor(
+                None,
+                Cursor {
+                    continuation: rawdoc! {},
+                    cursor_id: cursor_id.into(),
+                    tailable: None,
+                    bytes_per_document: None,
+                },
+                "user",
+                "db",
+                "coll",
+                Duration::from_secs(60),
+                Some(session_id.clone()),
+            );
+        }
+        service_context.session_store().touch(&reaped, None);
+
+        service_context.reap_sessions(Duration::ZERO).await;
+
+        assert!(service_context.session_store().is_empty());
+        assert!(connection_context.get_cursor(1, "user").is_none());
+        assert!(connection_context.get_cursor(2, "user").is_some());
+    }
 }
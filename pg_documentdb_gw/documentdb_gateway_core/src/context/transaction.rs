@@ -17,7 +17,7 @@ use tokio::{
 use tokio_postgres::IsolationLevel;
 
 use crate::{
-    configuration::DynamicConfiguration,
+    configuration::{BackendTarget, DynamicConfiguration},
     context::{ConnectionContext, CursorStore, SessionId},
     error::{DocumentDBError, ErrorCode, Result},
     postgres::{self, conn_mgmt::Connection, PgDataClient},
@@ -77,6 +77,8 @@ pub struct GatewayTransaction {
     pub session_id: SessionId,
     pub transaction_number: TransactionNumber,
     pub cursors: CursorStore,
+    /// The routed backend the transaction runs on, or `None` for the default backend.
+    backend: Option<BackendTarget>,
     pg_transaction: Option<postgres::Transaction>,
 }
 
@@ -90,10 +92,12 @@ impl GatewayTransaction {
         conn: Arc<Connection>,
         isolation_level: IsolationLevel,
         session_id: SessionId,
+        backend: Option<BackendTarget>,
     ) -> Result<Self> {
         Ok(Self {
             session_id,
             transaction_number: request.transaction_number,
+            backend,
             pg_transaction: Some(postgres::Transaction::start(conn, isolation_level).await?),
             cursors: CursorStore::new(config, false),
         })
@@ -225,7 +229,6 @@ impl TransactionStore {
     /// # Errors
     ///
     /// Returns an error if the operation fails.
-    #[expect(clippy::too_many_lines, reason = "complex transaction logic")]
     pub async fn create(
         &self,
         connection_context: &ConnectionContext,
@@ -233,15 +236,6 @@ impl TransactionStore {
         session_id: SessionId,
         pg_data_client: &impl PgDataClient,
     ) -> Result<()> {
-        if let Some((_, transaction_number)) = connection_context.transaction.as_ref() {
-            if transaction_number > &transaction_info.transaction_number {
-                return Err(DocumentDBError::documentdb_error(
-                    ErrorCode::TransactionTooOld,
-                    "Transaction number is lower than last seen transaction".to_owned(),
-                ));
-            }
-        }
-
         if transaction_info.start_transaction && !transaction_info.auto_commit {
             if let Some(last_transaction) = self.last_seen_transactions.get(&session_id) {
                 if last_transaction.transaction_number == transaction_info.transaction_number {
@@ -289,6 +283,7 @@ impl TransactionStore {
                     .isolation_level
                     .unwrap_or(IsolationLevel::ReadCommitted),
                 session_id.clone(),
+                pg_data_client.backend().cloned(),
             )
             .await?;
 
@@ -343,6 +338,28 @@ impl TransactionStore {
         }
     }
 
+    /// Rejects continuing the session's transaction on a different backend than it started on.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CommandNotSupported` if the transaction runs on another backend.
+    pub fn check_backend(
+        &self,
+        session_id: &SessionId,
+        backend: Option<&BackendTarget>,
+    ) -> Result<()> {
+        match self.transactions.get(session_id) {
+            Some(entry) if entry.value().1.backend.as_ref() != backend => {
+                Err(DocumentDBError::documentdb_error(
+                    ErrorCode::CommandNotSupported,
+                    "Transactions spanning databases served by different backends are not supported"
+                        .to_owned(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Removes the active transaction for `session_id`, aborts it, and marks the
     /// last-seen transaction as aborted.
     ///
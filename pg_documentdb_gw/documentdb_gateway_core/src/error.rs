@@ -40,12 +40,15 @@ pub enum ErrorKind {
     ValueAccessError(ValueAccessError, Backtrace),
 }
 
-pub struct DocumentDBError(Box<ErrorKind>);
+/// Label telling drivers that a failed write may be retried.
+pub const RETRYABLE_WRITE_ERROR_LABEL: &str = "RetryableWriteError";
+
+pub struct DocumentDBError(Box<ErrorKind>, Vec<&'static str>);
 
 impl DocumentDBError {
     #[must_use]
     pub fn new(document_db_error: ErrorKind) -> Self {
-        Self(Box::new(document_db_error))
+        Self(Box::new(document_db_error), Vec::new())
     }
 
     #[must_use]
@@ -53,6 +56,20 @@ impl DocumentDBError {
         &self.0
     }
 
+    /// Adds an `errorLabels` entry to the response sent for this error.
+    #[must_use]
+    pub fn with_error_label(mut self, label: &'static str) -> Self {
+        if !self.1.contains(&label) {
+            self.1.push(label);
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn error_labels(&self) -> &[&'static str] {
+        &self.1
+    }
+
     pub fn parse_failure<'a, E: std::fmt::Display>() -> impl Fn(E) -> Self + 'a {
         move |e| Self::bad_value(format!("Failed to parse: {e}"))
     }
@@ -0,0 +1,324 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/explain/execution_run.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::{rawdoc, RawBson, RawBsonRef, RawDocument, RawDocumentBuf};
+use tokio::time::Instant;
+
+use super::{smallest_from_f64, truncate_latency};
+use crate::{
+    context::{ConnectionContext, RequestContext},
+    error::{DocumentDBError, ErrorCode, Result},
+    postgres::PgDataClient,
+    processor,
+    requests::{request_tracker::RequestTracker, Request, RequestType},
+};
+
+/// What running an explained command measured, to be folded into its `executionStats`.
+#[derive(Debug)]
+pub struct ExecutionRun {
+    n_returned: i64,
+    execution_time_millis: f64,
+    /// The backend phases of the run, as reported for `$gatewayTiming`.
+    gateway_timing: RawDocumentBuf,
+}
+
+/// The explained command as it is run: without its explain fields, and with a cursor for an
+/// aggregate.
+fn run_command(document: &RawDocument, request_type: RequestType) -> Result<RawDocumentBuf> {
+    let mut command = RawDocumentBuf::new();
+    let mut has_cursor = false;
+    for entry in document {
+        let (key, value) = entry?;
+        if !matches!(key, "explain" | "verbosity") {
+            has_cursor |= key == "cursor";
+            command.append(key, value.to_raw_bson());
+        }
+    }
+    if request_type == RequestType::Aggregate && !has_cursor {
+        command.append("cursor", RawDocumentBuf::new());
+    }
+    Ok(command)
+}
+
+/// The documents a response returned: those of its batch for cursor commands, or its single
+/// result document for `count` and `distinct`.
+fn returned_documents(response: &RawDocument) -> i64 {
+    response
+        .get_document("cursor")
+        .and_then(|cursor| {
+            cursor
+                .get_array("firstBatch")
+                .or_else(|_| cursor.get_array("nextBatch"))
+        })
+        .map_or(1, |batch| {
+            i64::try_from(batch.into_iter().count()).unwrap_or(i64::MAX)
+        })
+}
+
+/// The getMore continuing the cursor of `response`, or `None` once it is exhausted.
+fn next_get_more(response: &RawDocument) -> Result<Option<RawDocumentBuf>> {
+    let Ok(cursor) = response.get_document("cursor") else {
+        return Ok(None);
+    };
+    let id = cursor.get_i64("id").unwrap_or_default();
+    if id == 0 {
+        return Ok(None);
+    }
+    let (db, collection) = cursor
+        .get_str("ns")
+        .ok()
+        .and_then(|ns| ns.split_once('.'))
+        .ok_or_else(|| {
+            DocumentDBError::internal_error("Explained cursor has no namespace.".to_owned())
+        })?;
+    Ok(Some(
+        rawdoc! { "getMore": id, "collection": collection, "$db": db },
+    ))
+}
+
+/// Runs the explained command with its own `RequestTracker`, discarding its results, and
+/// returns what the run measured. A cursor is drained, so that the statistics cover every
+/// batch and no cursor is left open.
+///
+/// # Errors
+/// Returns an error if the command or one of its getMores fails.
+pub async fn execute(
+    request_context: &RequestContext<'_>,
+    request_type: RequestType,
+    connection_context: &ConnectionContext,
+    pg_data_client: &impl PgDataClient,
+) -> Result<ExecutionRun> {
+    let command = run_command(request_context.payload.document(), request_type)?;
+    let request = Request::Raw(request_type, &command, request_context.payload.extra());
+    let tracker = RequestTracker::new();
+    let run_context = RequestContext {
+        payload: &request,
+        tracker: &tracker,
+        ..*request_context
+    };
+
+    let start = Instant::now();
+    let response = match request_type {
+        RequestType::Find => {
+            pg_data_client
+                .execute_find(&run_context, connection_context)
+                .await?
+        }
+        RequestType::Aggregate => {
+            pg_data_client
+                .execute_aggregate(&run_context, connection_context)
+                .await?
+        }
+        RequestType::Count => {
+            pg_data_client
+                .execute_count_query(&run_context, connection_context)
+                .await?
+        }
+        RequestType::Distinct => {
+            pg_data_client
+                .execute_distinct_query(&run_context, connection_context)
+                .await?
+        }
+        _ => {
+            return Err(DocumentDBError::documentdb_error(
+                ErrorCode::IllegalOperation,
+                format!("Explain cannot run {request_type} to gather execution statistics."),
+            ))
+        }
+    };
+
+    let mut n_returned = returned_documents(response.as_raw_document()?);
+    let mut get_more = next_get_more(response.as_raw_document()?)?;
+    while let Some(command) = get_more {
+        let request = Request::Raw(RequestType::GetMore, &command, None);
+        let info = request.extract_common()?;
+        let get_more_context = RequestContext {
+            payload: &request,
+            info: &info,
+            ..run_context
+        };
+        let response =
+            processor::process_get_more(&get_more_context, connection_context, pg_data_client)
+                .await?;
+        n_returned = n_returned.saturating_add(returned_documents(response.as_raw_document()?));
+        get_more = next_get_more(response.as_raw_document()?)?;
+    }
+    let execution_time_millis = start.elapsed().as_secs_f64() * 1000.0;
+
+    Ok(ExecutionRun {
+        n_returned,
+        execution_time_millis,
+        gateway_timing: tracker.gateway_timing(),
+    })
+}
+
+/// Replaces the planner's `nReturned` and `executionTimeMillis` in `execution_stats` with
+/// those of the run, and adds the run's `gatewayTiming`.
+fn fold_execution_stats(
+    execution_stats: Option<&RawDocument>,
+    run: &ExecutionRun,
+) -> Result<RawDocumentBuf> {
+    let mut folded = RawDocumentBuf::new();
+    folded.append("nReturned", run.n_returned);
+    folded.append(
+        "executionTimeMillis",
+        smallest_from_f64(truncate_latency(run.execution_time_millis)),
+    );
+    for entry in execution_stats.into_iter().flatten() {
+        let (key, value) = entry?;
+        if !matches!(key, "nReturned" | "executionTimeMillis" | "gatewayTiming") {
+            folded.append(key, value.to_raw_bson());
+        }
+    }
+    folded.append("gatewayTiming", run.gateway_timing.clone());
+    Ok(folded)
+}
+
+/// Folds `run` into the top-level `executionStats` of an explain body, adding one if the
+/// body has none, as for an aggregate whose statistics are per stage.
+///
+/// # Errors
+/// Returns an error if `body` is malformed.
+pub fn fold_into(body: &RawDocument, run: &ExecutionRun) -> Result<RawDocumentBuf> {
+    let execution_stats = body
+        .get("executionStats")?
+        .and_then(RawBsonRef::as_document);
+    let folded = fold_execution_stats(execution_stats, run)?;
+
+    let mut result = RawDocumentBuf::new();
+    for entry in body {
+        let (key, value) = entry?;
+        if key != "executionStats" {
+            result.append(key, value.to_raw_bson());
+        }
+    }
+    result.append("executionStats", RawBson::Document(folded));
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        context::{Cursor, RequestMemory},
+        testing::{self, MapConfiguration, StubDataClient},
+    };
+
+    fn run() -> ExecutionRun {
+        ExecutionRun {
+            n_returned: 3,
+            execution_time_millis: 1.25,
+            gateway_timing: rawdoc! { "executionMicros": 1200_i64 },
+        }
+    }
+
+    #[test]
+    fn run_replaces_planner_statistics() {
+        let body = rawdoc! {
+            "queryPlanner": {},
+            "executionStats": { "nReturned": 0, "executionTimeMillis": 0, "totalDocsExamined": 3 },
+        };
+
+        let folded = fold_into(&body, &run()).unwrap();
+
+        let stats = folded.get_document("executionStats").unwrap();
+        assert_eq!(stats.get_i64("nReturned").unwrap(), 3);
+        assert_eq!(
+            stats.get("executionTimeMillis").unwrap(),
+            Some(RawBsonRef::Double(1.25))
+        );
+        assert_eq!(stats.get_i32("totalDocsExamined").unwrap(), 3);
+        assert_eq!(
+            stats
+                .get_document("gatewayTiming")
+                .unwrap()
+                .get_i64("executionMicros")
+                .unwrap(),
+            1200
+        );
+        folded.get_document("queryPlanner").unwrap();
+    }
+
+    #[test]
+    fn run_command_strips_explain() {
+        let find = rawdoc! { "find": "c", "explain": true, "batchSize": 2, "$db": "db" };
+        assert_eq!(
+            run_command(&find, RequestType::Find).unwrap(),
+            rawdoc! { "find": "c", "batchSize": 2, "$db": "db" }
+        );
+
+        let aggregate = rawdoc! { "aggregate": "c", "pipeline": [], "verbosity": "executionStats" };
+        assert_eq!(
+            run_command(&aggregate, RequestType::Aggregate).unwrap(),
+            rawdoc! { "aggregate": "c", "pipeline": [], "cursor": {} }
+        );
+    }
+
+    #[test]
+    fn returned_documents_counts_the_batch() {
+        let cursor = rawdoc! { "cursor": { "firstBatch": [{}, {}], "id": 0_i64 }, "ok": 1.0 };
+        assert_eq!(returned_documents(&cursor), 2);
+        let cursor = rawdoc! { "cursor": { "nextBatch": [{}], "id": 0_i64 }, "ok": 1.0 };
+        assert_eq!(returned_documents(&cursor), 1);
+        assert_eq!(returned_documents(&rawdoc! { "n": 5, "ok": 1.0 }), 1);
+    }
+
+    #[tokio::test]
+    async fn run_drains_the_cursor_past_the_first_batch() {
+        let service_context = testing::service_context(MapConfiguration::default()).await;
+        let client = StubDataClient::new(service_context.clone());
+        let connection_context = testing::connection_context(service_context, "user");
+        client.reply_with(
+            "execute_find",
+            rawdoc! { "cursor": { "firstBatch": [{}, {}], "id": 7_i64, "ns": "db.c" }, "ok": 1.0 },
+        );
+        connection_context.add_cursor(
+            None,
+            Cursor {
+                continuation: rawdoc! {},
+                cursor_id: 7.into(),
+                tailable: None,
+                bytes_per_document: None,
+            },
+            "user",
+            "db",
+            "c",
+            Duration::from_secs(60),
+            None,
+        );
+
+        let command = rawdoc! { "find": "c", "batchSize": 2, "$db": "db" };
+        let request = Request::Raw(RequestType::Find, &command, None);
+        let info = request.extract_common().unwrap();
+        let request_context = RequestContext {
+            activity_id: "test",
+            payload: &request,
+            info: &info,
+            tracker: &RequestTracker::new(),
+            memory: &RequestMemory::default(),
+        };
+
+        // The stub cannot answer the getMore, which shows the run went on past the first batch.
+        execute(
+            &request_context,
+            RequestType::Find,
+            &connection_context,
+            &client,
+        )
+        .await
+        .unwrap_err();
+        let calls = client.calls();
+        assert_eq!(
+            client.methods(),
+            ["execute_find", "execute_cursor_get_more"]
+        );
+        assert_eq!(calls[1].command.get_i64("getMore").unwrap(), 7);
+    }
+}
@@ -10,7 +10,7 @@ use core::f64;
 use std::{cmp::Ordering, collections::HashMap, str::FromStr};
 
 use async_recursion::async_recursion;
-use bson::{rawdoc, Document, RawArrayBuf, RawBson, RawDocument, RawDocumentBuf};
+use bson::{rawdoc, Document, RawArrayBuf, RawBson, RawBsonRef, RawDocument, RawDocumentBuf};
 use model::{
     DistributedJob, DistributedQueryPlan, DistributedSubPlan, ExplainPlan, IndexCost, IndexDetails,
     PostgresExplain, VectorSearchParams,
@@ -20,13 +20,15 @@ use std::sync::LazyLock;
 
 use crate::{
     context::{ConnectionContext, RequestContext},
-    error::{DocumentDBError, Result},
+    error::{DocumentDBError, ErrorCode, Result},
     postgres::{PgDataClient, QueryCatalog},
+    processor,
     protocol::OK_SUCCEEDED,
     requests::{Request, RequestInfo, RequestType},
     responses::{RawResponse, Response},
 };
 
+mod execution_run;
 mod model;
 mod query_diagnostics;
 
@@ -126,13 +128,14 @@ pub async fn process_explain(
         let result = result?;
 
         // Default to QueryPlanner here, as Default tends to be too brief
-        let verbosity = verbosity.unwrap_or_else(|| {
-            let request = request_context.payload;
-            request
+        let verbosity = match verbosity {
+            Some(verbosity) => verbosity,
+            None => request_context
+                .payload
                 .document()
-                .get_str("verbosity")
-                .map_or(Verbosity::QueryPlanner, Verbosity::from_str)
-        });
+                .get("verbosity")?
+                .map_or(Ok(Verbosity::QueryPlanner), Verbosity::from_bson)?,
+        };
 
         match result.0 {
             "explain" => {
@@ -143,12 +146,7 @@ pub async fn process_explain(
                         request_context.payload.extra(),
                     );
 
-                    let new_request_context = RequestContext {
-                        activity_id: request_context.activity_id,
-                        payload: &new_request,
-                        info: request_context.info,
-                        tracker: request_context.tracker,
-                    };
+                    let new_request_context = request_context.with_payload(Some(&new_request));
 
                     // Recursive call with the unwrapped command
                     Box::pin(process_explain(
@@ -164,46 +162,45 @@ pub async fn process_explain(
                     ))
                 }
             }
-            "aggregate" => {
-                run_explain(
-                    request_context,
-                    "pipeline",
-                    verbosity,
-                    connection_context,
-                    pg_data_client,
-                )
-                .await
-            }
-            "find" => {
-                run_explain(
-                    request_context,
-                    "find",
-                    verbosity,
-                    connection_context,
-                    pg_data_client,
-                )
-                .await
-            }
-            "count" => {
+            "aggregate" | "find" | "count" | "distinct" => {
+                // Gathering execution statistics runs the command, and so would run the write.
+                if result.0 == "aggregate"
+                    && verbosity.includes_execution()
+                    && processor::has_output_stage(request_context.payload.document())?
+                {
+                    return Err(DocumentDBError::documentdb_error(
+                        ErrorCode::IllegalOperation,
+                        "Explain of an aggregate with $out or $merge cannot gather execution statistics without running the write.".to_owned(),
+                    ));
+                }
+                let query_base = if result.0 == "aggregate" {
+                    "pipeline"
+                } else {
+                    result.0
+                };
                 run_explain(
                     request_context,
-                    "count",
+                    query_base,
                     verbosity,
                     connection_context,
                     pg_data_client,
                 )
                 .await
             }
-            "distinct" => {
-                run_explain(
+            "update" | "delete" | "findAndModify" | "findandmodify" => {
+                run_write_explain(
                     request_context,
-                    "distinct",
+                    result.0,
                     verbosity,
                     connection_context,
                     pg_data_client,
                 )
                 .await
             }
+            "insert" => Err(DocumentDBError::documentdb_error(
+                ErrorCode::IllegalOperation,
+                "Explain of insert is not supported.".to_owned(),
+            )),
             _ => Err(DocumentDBError::bad_value(
                 "Unrecognized explain command.".to_owned(),
             )),
@@ -215,6 +212,151 @@ pub async fn process_explain(
     }
 }
 
+/// Writes are never executed under explain: the statement is planned as the equivalent find,
+/// which only reads the documents the write would target. Verbosities that need the command
+/// run are refused.
+async fn run_write_explain(
+    request_context: &RequestContext<'_>,
+    command: &str,
+    verbosity: Verbosity,
+    connection_context: &ConnectionContext,
+    pg_data_client: &impl PgDataClient,
+) -> Result<Response> {
+    if verbosity.includes_execution() {
+        return Err(DocumentDBError::documentdb_error(
+            ErrorCode::IllegalOperation,
+            format!("Explain of {command} cannot gather execution statistics without running the write."),
+        ));
+    }
+
+    let find_spec = write_explain_find_spec(command, request_context.payload.document())?;
+    let find_request = Request::Raw(
+        RequestType::Find,
+        &find_spec,
+        request_context.payload.extra(),
+    );
+
+    let find_request_context = request_context.with_payload(Some(&find_request));
+
+    run_explain(
+        &find_request_context,
+        "find",
+        verbosity,
+        connection_context,
+        pg_data_client,
+    )
+    .await
+}
+
+/// Builds the find command that selects the documents targeted by a single-statement
+/// `update`, `delete` or `findAndModify`, so its plan can be explained without running the write.
+fn write_explain_find_spec(command: &str, document: &RawDocument) -> Result<RawDocumentBuf> {
+    let collection = document
+        .get(command)?
+        .and_then(RawBsonRef::as_str)
+        .ok_or_else(|| {
+            DocumentDBError::type_mismatch(format!("{command} collection name must be a string"))
+        })?;
+
+    let (statement, filter_key, sort, single) = match command {
+        "update" => {
+            let statement = single_write_statement(document, "updates")?;
+            let multi = statement
+                .get("multi")?
+                .is_some_and(|v| v.as_bool() == Some(true));
+            (statement, "q", None, !multi)
+        }
+        "delete" => {
+            let statement = single_write_statement(document, "deletes")?;
+            let limit = statement.get("limit")?.and_then(|v| match v {
+                RawBsonRef::Int32(l) => Some(i64::from(l)),
+                RawBsonRef::Int64(l) => Some(l),
+                _ => None,
+            });
+            (statement, "q", None, limit == Some(1))
+        }
+        _ => (
+            document,
+            "query",
+            optional_document(document, "sort")?,
+            true,
+        ),
+    };
+    reject_unplanned_options(command, statement)?;
+
+    let mut find_spec = rawdoc! { "find": collection };
+    if let Some(filter) = optional_document(statement, filter_key)? {
+        find_spec.append("filter", filter.to_raw_document_buf());
+    }
+    if let Some(sort) = sort {
+        find_spec.append("sort", sort.to_raw_document_buf());
+    }
+    if single {
+        find_spec.append("limit", 1);
+    }
+    if let Some(hint) = statement.get("hint")? {
+        find_spec.append("hint", hint.to_raw_bson());
+    }
+    if let Some(collation) = statement.get("collation")?.or(document.get("collation")?) {
+        find_spec.append("collation", collation.to_raw_bson());
+    }
+    if let Some(db) = document.get("$db")? {
+        find_spec.append("$db", db.to_raw_bson());
+    }
+    Ok(find_spec)
+}
+
+/// The find plan only covers which documents the write selects, so options that change what
+/// the write does with them are refused rather than silently dropped from the explain.
+fn reject_unplanned_options(command: &str, statement: &RawDocument) -> Result<()> {
+    for option in ["upsert", "new"] {
+        if statement
+            .get(option)?
+            .is_some_and(|v| v.as_bool() == Some(true))
+        {
+            return Err(unplanned_option(command, option));
+        }
+    }
+    if statement.get("arrayFilters")?.is_some() {
+        return Err(unplanned_option(command, "arrayFilters"));
+    }
+    Ok(())
+}
+
+fn unplanned_option(command: &str, option: &str) -> DocumentDBError {
+    DocumentDBError::documentdb_error(
+        ErrorCode::IllegalOperation,
+        format!("Explain of {command} with {option} is not supported, as only the selection of documents is planned."),
+    )
+}
+
+fn single_write_statement<'a>(document: &'a RawDocument, key: &str) -> Result<&'a RawDocument> {
+    let statements = document
+        .get(key)?
+        .and_then(RawBsonRef::as_array)
+        .ok_or_else(|| DocumentDBError::type_mismatch(format!("{key} must be an array")))?;
+
+    let mut statements = statements.into_iter();
+    match (statements.next(), statements.next()) {
+        (Some(statement), None) => statement?.as_document().ok_or_else(|| {
+            DocumentDBError::type_mismatch(format!("{key} entries must be documents"))
+        }),
+        _ => Err(DocumentDBError::bad_value(format!(
+            "Explained write batches must be of size 1, but {key} does not contain exactly one statement."
+        ))),
+    }
+}
+
+fn optional_document<'a>(document: &'a RawDocument, key: &str) -> Result<Option<&'a RawDocument>> {
+    document
+        .get(key)?
+        .map(|v| {
+            v.as_document()
+                .ok_or_else(|| DocumentDBError::type_mismatch(format!("{key} must be a document")))
+        })
+        .transpose()
+}
+
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Verbosity {
     Default,
@@ -226,19 +368,54 @@ pub enum Verbosity {
 }
 
 impl Verbosity {
-    fn from_str(value: &str) -> Self {
+    /// The verbosity strings an explain accepts.
+    const NAMED: [(&'static str, Self); 5] = [
+        ("queryPlanner", Self::QueryPlanner),
+        ("executionStats", Self::ExecutionStats),
+        ("allPlansExecution", Self::AllPlansExecution),
+        ("allShardsQueryPlan", Self::AllShardsQueryPlan),
+        ("allShardsExecution", Self::AllShardsExecution),
+    ];
+
+    fn parse(value: &str) -> Result<Self> {
+        Self::NAMED
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|&(_, verbosity)| verbosity)
+            .ok_or_else(|| {
+                let names = Self::NAMED
+                    .iter()
+                    .map(|(name, _)| format!("'{name}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                DocumentDBError::bad_value(format!(
+                    "verbosity string must be one of {{{names}}}, but got: {value}"
+                ))
+            })
+    }
+
+    /// Reads the `verbosity` field of an explain command.
+    /// The legacy boolean form maps `true` to `allPlansExecution` and `false` to `queryPlanner`.
+    fn from_bson(value: RawBsonRef<'_>) -> Result<Self> {
         match value {
-            "queryPlanner" => Self::QueryPlanner,
-            "executionStats" => Self::ExecutionStats,
-            "allPlansExecution" => Self::AllPlansExecution,
-            "allShardsQueryPlan" => Self::AllShardsQueryPlan,
-            "allShardsExecution" => Self::AllShardsExecution,
-            _ => Self::Default,
+            RawBsonRef::String(value) => Self::parse(value),
+            RawBsonRef::Boolean(true) => Ok(Self::AllPlansExecution),
+            RawBsonRef::Boolean(false) => Ok(Self::QueryPlanner),
+            _ => Err(DocumentDBError::type_mismatch(
+                "verbosity must be a string".to_owned(),
+            )),
         }
     }
+
+    /// Whether the explain reports `executionStats`, which are gathered by running the command.
+    const fn includes_execution(self) -> bool {
+        matches!(
+            self,
+            Self::ExecutionStats | Self::AllPlansExecution | Self::AllShardsExecution
+        )
+    }
 }
 
-#[expect(clippy::expect_used, reason = "values are checked before access")]
 async fn run_explain(
     request_context: &RequestContext<'_>,
     query_base: &str,
@@ -262,6 +439,19 @@ async fn run_explain(
                 .then(|| convert_to_bson(content.clone()));
 
             let (collection_name, subtype) = get_subtype_and_collection_name(request)?;
+            let run = if verbosity.includes_execution() {
+                Some(
+                    execution_run::execute(
+                        request_context,
+                        subtype,
+                        connection_context,
+                        pg_data_client,
+                    )
+                    .await?,
+                )
+            } else {
+                None
+            };
             let (body, planning_time, execution_time, data_size) = transform_explain(
                 content,
                 request_info.db()?,
@@ -308,20 +498,20 @@ async fn run_explain(
                 explain.append("dataSize", data_size);
             }
 
+            let body = match &run {
+                Some(run) => execution_run::fold_into(&body, run)?,
+                None => body,
+            };
+
             // Merge body fields into explain
             for (key, val) in body.into_iter().flatten() {
                 explain.append(key, val.to_raw_bson());
             }
 
-            if dynamic_config.enable_developer_explain() {
+            if let Some(explain_content) = explain_content {
                 explain.append(
                     "internal",
-                    developer_explain(
-                        &query,
-                        explain_content.expect("Set during developer explain"),
-                        request.document(),
-                        request_info,
-                    ),
+                    developer_explain(&query, explain_content, request.document(), request_info),
                 );
             }
 
@@ -2008,10 +2198,7 @@ fn cursor_explain(
     let mut doc = rawdoc! {
         "queryPlanner": query_planner(plan.clone(), collection_path, is_aggregation_stage, query_catalog),
     };
-    if matches!(
-        verbosity,
-        Verbosity::ExecutionStats | Verbosity::AllPlansExecution | Verbosity::AllShardsExecution
-    ) {
+    if verbosity.includes_execution() {
         doc.append("executionStats", execution_stats(plan, query_catalog));
     }
     doc
@@ -2074,10 +2261,21 @@ fn smallest_from_i64(value: i64) -> RawBson {
 
 #[cfg(test)]
 mod tests {
-    use crate::postgres::QueryCatalog;
+    use bson::{rawdoc, RawBsonRef, RawDocument};
+
+    use crate::{
+        context::{ConnectionContext, RequestContext, RequestMemory},
+        error::{ErrorCode, Result},
+        postgres::QueryCatalog,
+        requests::{request_tracker::RequestTracker, Request, RequestType},
+        responses::Response,
+        testing::{self, MapConfiguration, StubDataClient},
+    };
 
-    use super::get_stage_from_plan;
     use super::model::ExplainPlan;
+    use super::{
+        aggregate_explain, get_stage_from_plan, process_explain, write_explain_find_spec, Verbosity,
+    };
 
     /// Helper that builds a minimal [`ExplainPlan`] with the given `node_type`.
     fn plan_with_node_type(node_type: &str) -> ExplainPlan {
@@ -2118,4 +2316,257 @@ mod tests {
 
         assert_eq!(stage, "COLLSCAN");
     }
+
+    fn has_execution_stats(verbosity: Verbosity) -> bool {
+        let explain = aggregate_explain(
+            plan_with_node_type("Seq Scan"),
+            "db.coll",
+            verbosity,
+            &QueryCatalog::default(),
+        );
+        let stages = explain.get_array("stages").unwrap();
+        let cursor = stages
+            .into_iter()
+            .next()
+            .unwrap()
+            .unwrap()
+            .as_document()
+            .unwrap()
+            .get_document("$cursor")
+            .unwrap();
+
+        assert!(cursor.get("queryPlanner").unwrap().is_some());
+        cursor.get("executionStats").unwrap().is_some()
+    }
+
+    #[test]
+    fn verbosity_parses_known_levels() {
+        for (name, expected) in [
+            ("queryPlanner", Verbosity::QueryPlanner),
+            ("executionStats", Verbosity::ExecutionStats),
+            ("allPlansExecution", Verbosity::AllPlansExecution),
+        ] {
+            assert_eq!(
+                Verbosity::from_bson(RawBsonRef::String(name)).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn verbosity_legacy_boolean() {
+        assert_eq!(
+            Verbosity::from_bson(RawBsonRef::Boolean(true)).unwrap(),
+            Verbosity::AllPlansExecution
+        );
+        assert_eq!(
+            Verbosity::from_bson(RawBsonRef::Boolean(false)).unwrap(),
+            Verbosity::QueryPlanner
+        );
+    }
+
+    #[test]
+    fn verbosity_rejects_unknown_level() {
+        let err = Verbosity::from_bson(RawBsonRef::String("fullPlans")).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+        for (name, _) in Verbosity::NAMED {
+            assert!(err.to_string().contains(&format!("'{name}'")), "{err}");
+        }
+
+        let doc = rawdoc! { "verbosity": 1 };
+        let err = Verbosity::from_bson(doc.get("verbosity").unwrap().unwrap()).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::TypeMismatch));
+    }
+
+    #[test]
+    fn aggregate_query_planner_omits_execution_stats() {
+        assert!(!has_execution_stats(Verbosity::QueryPlanner));
+    }
+
+    #[test]
+    fn aggregate_execution_stats_includes_execution_stats() {
+        assert!(has_execution_stats(Verbosity::ExecutionStats));
+    }
+
+    #[test]
+    fn aggregate_all_plans_execution_includes_execution_stats() {
+        assert!(has_execution_stats(Verbosity::AllPlansExecution));
+    }
+
+    #[test]
+    fn update_explain_plans_matching_find() {
+        let update = rawdoc! {
+            "update": "coll",
+            "updates": [{ "q": { "a": 1 }, "u": { "$set": { "b": 2 } } }],
+            "$db": "db",
+        };
+
+        let spec = write_explain_find_spec("update", &update).unwrap();
+
+        assert_eq!(
+            spec,
+            rawdoc! { "find": "coll", "filter": { "a": 1 }, "limit": 1, "$db": "db" }
+        );
+    }
+
+    #[test]
+    fn multi_update_and_delete_all_are_unlimited() {
+        let update = rawdoc! {
+            "update": "coll",
+            "updates": [{ "q": { "a": 1 }, "u": { "$set": { "b": 2 } }, "multi": true }],
+        };
+        let delete = rawdoc! { "delete": "coll", "deletes": [{ "q": { "a": 1 }, "limit": 0 }] };
+
+        for (command, doc) in [("update", &update), ("delete", &delete)] {
+            let spec = write_explain_find_spec(command, doc).unwrap();
+            assert_eq!(spec, rawdoc! { "find": "coll", "filter": { "a": 1 } });
+        }
+    }
+
+    #[test]
+    fn find_and_modify_explain_keeps_sort() {
+        let find_and_modify = rawdoc! {
+            "findAndModify": "coll",
+            "query": { "a": 1 },
+            "sort": { "b": -1 },
+            "remove": true,
+        };
+
+        let spec = write_explain_find_spec("findAndModify", &find_and_modify).unwrap();
+
+        assert_eq!(
+            spec,
+            rawdoc! { "find": "coll", "filter": { "a": 1 }, "sort": { "b": -1 }, "limit": 1 }
+        );
+    }
+
+    #[test]
+    fn explained_write_batch_must_have_one_statement() {
+        let delete = rawdoc! {
+            "delete": "coll",
+            "deletes": [{ "q": {}, "limit": 1 }, { "q": {}, "limit": 1 }],
+        };
+
+        let err = write_explain_find_spec("delete", &delete).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+    }
+
+    #[test]
+    fn explained_write_keeps_hint_and_collation() {
+        let update = rawdoc! {
+            "update": "coll",
+            "updates": [{ "q": { "a": 1 }, "u": { "$set": { "b": 2 } }, "hint": { "a": 1 } }],
+            "collation": { "locale": "fr" },
+        };
+
+        let spec = write_explain_find_spec("update", &update).unwrap();
+
+        assert_eq!(
+            spec,
+            rawdoc! {
+                "find": "coll",
+                "filter": { "a": 1 },
+                "limit": 1,
+                "hint": { "a": 1 },
+                "collation": { "locale": "fr" },
+            }
+        );
+    }
+
+    #[test]
+    fn explained_write_refuses_options_the_find_cannot_plan() {
+        let upsert = rawdoc! {
+            "update": "coll",
+            "updates": [{ "q": {}, "u": { "$set": { "b": 2 } }, "upsert": true }],
+        };
+        let array_filters = rawdoc! {
+            "update": "coll",
+            "updates": [{ "q": {}, "u": { "$set": { "b.$[x]": 2 } }, "arrayFilters": [{ "x": 1 }] }],
+        };
+        let find_and_modify_new = rawdoc! {
+            "findAndModify": "coll",
+            "query": {},
+            "update": { "$set": { "b": 2 } },
+            "new": true,
+        };
+        let find_and_modify_upsert = rawdoc! {
+            "findAndModify": "coll",
+            "query": {},
+            "update": { "$set": { "b": 2 } },
+            "upsert": true,
+        };
+
+        for (command, doc) in [
+            ("update", &upsert),
+            ("update", &array_filters),
+            ("findAndModify", &find_and_modify_new),
+            ("findAndModify", &find_and_modify_upsert),
+        ] {
+            let err = write_explain_find_spec(command, doc).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::IllegalOperation));
+        }
+    }
+
+    async fn explain(
+        command: &RawDocument,
+        connection_context: &ConnectionContext,
+        client: &StubDataClient,
+    ) -> Result<Response> {
+        let request = Request::Raw(RequestType::Explain, command, None);
+        let info = request.extract_common().unwrap();
+        let request_context = RequestContext {
+            activity_id: "test",
+            payload: &request,
+            info: &info,
+            tracker: &RequestTracker::new(),
+            memory: &RequestMemory::default(),
+        };
+        process_explain(&request_context, None, connection_context, client).await
+    }
+
+    #[tokio::test]
+    async fn execution_stats_of_a_writing_aggregate_is_refused_before_running() {
+        let service_context = testing::service_context(MapConfiguration::default()).await;
+        let client = StubDataClient::new(service_context.clone());
+        let connection_context = testing::connection_context(service_context, "user");
+        for stage in [
+            rawdoc! { "$out": "dst" },
+            rawdoc! { "$merge": { "into": "dst" } },
+        ] {
+            let command = rawdoc! {
+                "explain": { "aggregate": "src", "pipeline": [{ "$match": {} }, stage], "cursor": {} },
+                "verbosity": "executionStats",
+                "$db": "db",
+            };
+
+            let err = explain(&command, &connection_context, &client)
+                .await
+                .unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::IllegalOperation));
+        }
+        // Nothing reached the backend, so the target is untouched.
+        assert!(client.methods().is_empty());
+    }
+
+    #[tokio::test]
+    async fn explained_update_only_issues_the_explain_query() {
+        let service_context = testing::service_context(MapConfiguration::default()).await;
+        let client = StubDataClient::new(service_context.clone());
+        let connection_context = testing::connection_context(service_context, "user");
+        let command = rawdoc! {
+            "explain": {
+                "update": "coll",
+                "updates": [{ "q": { "a": 1 }, "u": { "$set": { "b": 2 } } }],
+            },
+            "$db": "db",
+        };
+
+        // The stub has no plan to return, so the explain itself fails once it is issued.
+        explain(&command, &connection_context, &client)
+            .await
+            .unwrap_err();
+        let calls = client.calls();
+        assert_eq!(client.methods(), ["execute_explain"]);
+        assert_eq!(calls[0].command.get_str("find").unwrap(), "coll");
+    }
 }
@@ -25,13 +25,12 @@ pub mod telemetry;
 #[cfg(test)]
 pub(crate) mod testing;
 
-use std::{net::IpAddr, pin::Pin, sync::Arc};
+use std::{collections::VecDeque, net::IpAddr, pin::Pin, sync::Arc};
 
-use either::Either::{Left, Right};
+use either::Either::{self, Left, Right};
 use openssl::ssl::Ssl;
-use socket2::TcpKeepalive;
 use tokio::{
-    io::{AsyncRead, AsyncWrite, BufStream},
+    io::{AsyncBufRead, AsyncRead, AsyncWrite, BufStream},
     net::{unix::SocketAddr as UnixSocketAddr, TcpStream, UnixListener, UnixStream},
     time::{Duration, Instant},
 };
@@ -40,22 +39,45 @@ use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use crate::{
-    context::{ConnectionContext, RequestContext, ServiceContext},
-    error::{DocumentDBError, ErrorCode, Result},
+    context::{
+        ConnectionCloseReason, ConnectionContext, ConnectionSummary, OperationInfo, RequestContext,
+        RequestMemory, ServiceContext,
+    },
+    error::{DocumentDBError, ErrorCode, ErrorKind, Result},
     postgres::PgDataClient,
+    processor::AwaitableHello,
     protocol::header::Header,
-    requests::{request_tracker::RequestTracker, validation, Request, RequestIntervalKind},
+    requests::{
+        query_limits, request_tracker::RequestTracker, unknown_options, validation, Request,
+        RequestIntervalKind, RequestType, GATEWAY_TIMING_FIELD,
+    },
     responses::{CommandError, Response},
-    service::create_tcp_listeners,
-    telemetry::{client_info::parse_client_info, record_gateway_metrics, TelemetryProvider},
+    service::{
+        catch_handler_panic, configure_client_socket, create_health_listener, create_tcp_listeners,
+        Admission, ConnectionGate,
+    },
+    shutdown_controller::{DrainOutcome, SHUTDOWN_CONTROLLER},
+    telemetry::{
+        client_info::parse_client_info,
+        metrics::{record_request_client_aborted, record_request_memory_high_water_mark},
+        record_connection_metrics, record_gateway_metrics, record_request_span, TelemetryProvider,
+    },
 };
-// TCP keepalive configuration constants
-const TCP_KEEPALIVE_TIME_SECS: u64 = 180;
-const TCP_KEEPALIVE_INTERVAL_SECS: u64 = 60;
-
 // TLS detection timeout
 const TLS_PEEK_TIMEOUT_SECS: u64 = 5;
 
+// OP_MSG header, flags and section kind written around a response document
+const RESPONSE_MESSAGE_OVERHEAD: usize = Header::LENGTH + 4 + 1;
+
+/// Shutdown signals handed to each connection task.
+#[derive(Debug, Clone)]
+struct ConnectionShutdown {
+    /// Cancelled when draining starts; connections close after their in-flight request.
+    draining: CancellationToken,
+    /// Cancelled when draining ends; remaining connections are dropped.
+    close: CancellationToken,
+}
+
 /// Applies configurable permissions to Unix domain socket file.
 ///
 /// On Unix systems: Sets permissions to the specified octal value
@@ -127,6 +149,13 @@ fn create_unix_socket_listener(socket_path: &str, permissions: u32) -> Result<Un
 /// new connections until the cancellation token is triggered. Each connection is
 /// handled in a separate async task.
 ///
+/// Once the token is triggered the listeners close and open connections drain: each
+/// finishes its in-flight request and closes. Connections still open after the
+/// configured shutdown grace period, or after a forced shutdown, are dropped.
+///
+/// When `MaxConcurrentConnections` is configured, connections past the limit wait in a
+/// backlog of `ConnectionBacklog` connections and are closed once that is full as well.
+///
 /// # Arguments
 ///
 /// * `service_context` - The service configuration and context
@@ -175,6 +204,28 @@ where
             None
         };
 
+    let shutdown = ConnectionShutdown {
+        draining: token.clone(),
+        close: CancellationToken::new(),
+    };
+
+    // Readiness reports the drain, so the probes are served until connections are closed.
+    if let Some(port) = service_context.setup_configuration().health_check_port() {
+        create_health_listener(
+            service_context.setup_configuration().use_local_host(),
+            port,
+            Arc::new(service_context.clone()),
+            token.clone(),
+            shutdown.close.clone(),
+        )?;
+    }
+    let gate = ConnectionGate::new(
+        service_context
+            .setup_configuration()
+            .max_concurrent_connections(),
+        service_context.setup_configuration().connection_backlog(),
+    );
+
     // Listen for new tcp and unix socket connections
     loop {
         tokio::select! {
@@ -185,7 +236,7 @@ where
                     None => std::future::pending().await,
                 }
             }, if ipv4_listener.is_some() => {
-                spawn_tcp_handler::<T>(result, service_context.clone(), telemetry.clone(), "IPv4");
+                spawn_tcp_handler::<T>(result, &gate, service_context.clone(), telemetry.clone(), shutdown.clone(), "IPv4");
             }
             // Handle IPv6 TCP connections
             result = async {
@@ -194,7 +245,7 @@ where
                     None => std::future::pending().await,
                 }
             }, if ipv6_listener.is_some() => {
-                spawn_tcp_handler::<T>(result, service_context.clone(), telemetry.clone(), "IPv6");
+                spawn_tcp_handler::<T>(result, &gate, service_context.clone(), telemetry.clone(), shutdown.clone(), "IPv6");
             }
             // Handle Unix socket connections
             result = async {
@@ -203,29 +254,98 @@ where
                     None => std::future::pending().await,
                 }
             }, if unix_listener.is_some() => {
-                spawn_unix_handler::<T>(result, service_context.clone(), telemetry.clone());
-            }
-            () = token.cancelled() => {
-                return Ok(())
+                spawn_unix_handler::<T>(result, &gate, service_context.clone(), telemetry.clone(), shutdown.clone());
             }
+            () = token.cancelled() => break,
         }
     }
+
+    // Stop accepting new connections before draining the open ones.
+    drop((ipv4_listener, ipv6_listener, unix_listener));
+    drain_connections(&service_context, &shutdown.close).await;
+    Ok(())
+}
+
+async fn drain_connections(service_context: &ServiceContext, close: &CancellationToken) {
+    // Drivers awaiting a hello learn of the shutdown without waiting for their next heartbeat.
+    processor::bump_topology_version();
+
+    let grace = Duration::from_millis(service_context.setup_configuration().shutdown_grace_ms());
+    tracing::info!(
+        "Shutdown requested, draining connections for up to {}ms.",
+        grace.as_millis()
+    );
+
+    match shutdown_controller::drain(
+        service_context.operation_registry(),
+        grace,
+        &SHUTDOWN_CONTROLLER.force_token(),
+    )
+    .await
+    {
+        DrainOutcome::Drained => tracing::info!("All connections drained."),
+        DrainOutcome::DeadlineExceeded {
+            connections,
+            requests,
+        } => tracing::warn!(
+            "Shutdown grace period elapsed, closing {connections} connection(s) with {requests} request(s) in flight."
+        ),
+        DrainOutcome::Forced {
+            connections,
+            requests,
+        } => tracing::warn!(
+            "Shutdown forced, closing {connections} connection(s) with {requests} request(s) in flight."
+        ),
+    }
+    close.cancel();
+}
+
+/// Admits an accepted connection through `gate`. A rejected connection is closed as soon
+/// as its stream is dropped, before anything is read from it.
+fn admit_connection(gate: &ConnectionGate, protocol: &str) -> Option<Admission> {
+    let admission = gate.admit();
+    match &admission {
+        None => tracing::warn!(
+            "Rejected a {protocol} connection: {} connections are open and the connection backlog is full.",
+            gate.active_connections()
+        ),
+        Some(admission) if admission.is_queued() => tracing::info!(
+            "Queued a {protocol} connection until one of {} open connections closes.",
+            gate.active_connections()
+        ),
+        Some(_) => {}
+    }
+    admission
 }
 
 /// Spawns an async task to handle a TCP connection.
 fn spawn_tcp_handler<T>(
     stream_and_address: std::io::Result<(TcpStream, std::net::SocketAddr)>,
+    gate: &ConnectionGate,
     service_context: ServiceContext,
     telemetry: Option<Box<dyn TelemetryProvider>>,
+    shutdown: ConnectionShutdown,
     protocol: &'static str,
 ) where
     T: PgDataClient,
 {
+    let Some(admission) = admit_connection(gate, protocol) else {
+        return;
+    };
+    let admission_start = Instant::now();
     tokio::spawn(async move {
-        if let Err(err) =
-            handle_connection::<T>(stream_and_address, service_context, telemetry).await
-        {
-            tracing::error!("Failed to accept a TCP connection ({protocol}): {err:?}.");
+        let _permit = tokio::select! {
+            permit = admission.ready() => permit,
+            () = shutdown.draining.cancelled() => return,
+        };
+        let admission_wait = admission_start.elapsed();
+        tokio::select! {
+            result = handle_connection::<T>(stream_and_address, service_context, telemetry, admission_wait, &shutdown.draining) => {
+                if let Err(err) = result {
+                    tracing::error!("Failed to accept a TCP connection ({protocol}): {err:?}.");
+                }
+            }
+            () = shutdown.close.cancelled() => {}
         }
     });
 }
@@ -233,16 +353,30 @@ fn spawn_tcp_handler<T>(
 /// Spawns an async task to handle a Unix socket connection.
 fn spawn_unix_handler<T>(
     stream_result: std::io::Result<(UnixStream, UnixSocketAddr)>,
+    gate: &ConnectionGate,
     service_context: ServiceContext,
     telemetry: Option<Box<dyn TelemetryProvider>>,
+    shutdown: ConnectionShutdown,
 ) where
     T: PgDataClient,
 {
+    let Some(admission) = admit_connection(gate, "Unix socket") else {
+        return;
+    };
+    let admission_start = Instant::now();
     tokio::spawn(async move {
-        if let Err(err) =
-            handle_unix_connection::<T>(stream_result, service_context, telemetry).await
-        {
-            tracing::error!("Failed to accept a Unix socket connection: {err:?}.");
+        let _permit = tokio::select! {
+            permit = admission.ready() => permit,
+            () = shutdown.draining.cancelled() => return,
+        };
+        let admission_wait = admission_start.elapsed();
+        tokio::select! {
+            result = handle_unix_connection::<T>(stream_result, service_context, telemetry, admission_wait, &shutdown.draining) => {
+                if let Err(err) = result {
+                    tracing::error!("Failed to accept a Unix socket connection: {err:?}.");
+                }
+            }
+            () = shutdown.close.cancelled() => {}
         }
     });
 }
@@ -335,6 +469,8 @@ async fn detect_tls_handshake(tcp_stream: &TcpStream, connection_id: Uuid) -> Re
 /// * `stream_and_address` - Result containing the TCP stream and peer address from `accept()`
 /// * `service_context` - Service configuration and shared state
 /// * `telemetry` - Optional telemetry provider for metrics collection
+/// * `admission_wait` - How long the connection waited for the connection gate
+/// * `draining` - Cancelled at shutdown; the connection closes between requests
 ///
 /// # Returns
 ///
@@ -353,6 +489,8 @@ async fn handle_connection<T>(
     stream_and_address: std::result::Result<(TcpStream, std::net::SocketAddr), std::io::Error>,
     service_context: ServiceContext,
     telemetry: Option<Box<dyn TelemetryProvider>>,
+    admission_wait: Duration,
+    draining: &CancellationToken,
 ) -> Result<()>
 where
     T: PgDataClient,
@@ -365,13 +503,7 @@ where
         "Accepted new TCP connection"
     );
 
-    // Configure TCP stream
-    tcp_stream.set_nodelay(true)?;
-    let tcp_keepalive = TcpKeepalive::new()
-        .with_time(Duration::from_secs(TCP_KEEPALIVE_TIME_SECS))
-        .with_interval(Duration::from_secs(TCP_KEEPALIVE_INTERVAL_SECS));
-
-    socket2::SockRef::from(&tcp_stream).set_tcp_keepalive(&tcp_keepalive)?;
+    configure_client_socket(&tcp_stream, service_context.setup_configuration())?;
 
     // Detect TLS handshake by peeking at the first bytes
     let is_tls = if service_context.setup_configuration().enforce_tls() {
@@ -427,7 +559,7 @@ where
             "TLS TCP connection established - Connection Id {connection_id}, client IP {ip_address}"
         );
 
-        handle_stream::<T, _>(buffered_stream, conn_ctx).await;
+        handle_stream::<T, _>(buffered_stream, conn_ctx, admission_wait, draining).await;
     } else {
         // Non-TLS path
         let conn_ctx = ConnectionContext::new(
@@ -452,7 +584,7 @@ where
             "Non-TLS TCP connection established - Connection Id {connection_id}, client IP {ip_address}"
         );
 
-        handle_stream::<T, _>(buffered_stream, conn_ctx).await;
+        handle_stream::<T, _>(buffered_stream, conn_ctx, admission_wait, draining).await;
     }
 
     Ok(())
@@ -468,6 +600,7 @@ where
 /// * `stream_result` - Result containing the Unix stream from `accept()`
 /// * `service_context` - Service configuration and shared state
 /// * `telemetry` - Optional telemetry provider for metrics collection
+/// * `draining` - Cancelled at shutdown; the connection closes between requests
 ///
 /// # Returns
 ///
@@ -477,6 +610,8 @@ async fn handle_unix_connection<T>(
     stream_result: std::result::Result<(UnixStream, UnixSocketAddr), std::io::Error>,
     service_context: ServiceContext,
     telemetry: Option<Box<dyn TelemetryProvider>>,
+    admission_wait: Duration,
+    draining: &CancellationToken,
 ) -> Result<()>
 where
     T: PgDataClient,
@@ -513,32 +648,151 @@ where
         "Unix socket connection established - Connection Id {connection_id}"
     );
 
-    handle_stream::<T, _>(buffered_stream, connection_context).await;
+    handle_stream::<T, _>(
+        buffered_stream,
+        connection_context,
+        admission_wait,
+        draining,
+    )
+    .await;
     Ok(())
 }
 
-async fn handle_stream<T, S>(mut stream: S, mut connection_context: ConnectionContext)
+#[expect(
+    clippy::too_many_lines,
+    reason = "request loop with per-outcome close handling"
+)]
+async fn handle_stream<T, S>(
+    mut stream: S,
+    mut connection_context: ConnectionContext,
+    admission_wait: Duration,
+    draining: &CancellationToken,
+) -> ConnectionSummary
 where
     T: PgDataClient,
-    S: AsyncRead + AsyncWrite + Unpin,
+    S: AsyncBufRead + AsyncRead + AsyncWrite + Unpin,
 {
     let connection_activity_id = connection_context.connection_id.to_string();
     let connection_activity_id_as_str = connection_activity_id.as_str();
+    let _connection = connection_context
+        .service_context
+        .operation_registry()
+        .register_connection(
+            connection_context.connection_id,
+            connection_context.ip_address.clone(),
+        );
 
-    loop {
-        match protocol::reader::read_header(&mut stream).await {
+    let idle_timeout = match connection_context
+        .service_context
+        .setup_configuration()
+        .client_idle_timeout_ms()
+    {
+        0 => None,
+        ms => Some(Duration::from_millis(ms)),
+    };
+
+    // When each request pipelined behind the one being handled was first seen buffered.
+    let mut pipelined_since = VecDeque::new();
+    // The connection's first request also waited for the connection to be admitted.
+    let mut admission_wait = Some(admission_wait);
+
+    let close_reason = loop {
+        // A request already being read or handled runs to completion; the connection
+        // only closes for shutdown or idleness while it waits for the next one.
+        let header = tokio::select! {
+            header = protocol::reader::read_header(&mut stream) => header,
+            () = draining.cancelled() => {
+                tracing::info!(
+                    activity_id = connection_activity_id_as_str,
+                    "Closing connection for shutdown."
+                );
+                break ConnectionCloseReason::ServerShutdown;
+            }
+            () = wait_idle(idle_timeout) => {
+                tracing::info!(
+                    activity_id = connection_activity_id_as_str,
+                    "Closing connection idle for longer than {idle_timeout:?}."
+                );
+                release_idle_connection(&mut connection_context).await;
+                break ConnectionCloseReason::IdleTimeout;
+            }
+        };
+        match header {
             Ok(Some(header)) => {
                 let request_activity_id =
                     connection_context.generate_request_activity_id(header.request_id);
 
-                if let Err(e) = handle_message::<T, S>(
-                    &mut connection_context,
-                    &header,
-                    &mut stream,
+                let queue_wait = admission_wait.take().unwrap_or_default()
+                    + pipelined_since
+                        .pop_front()
+                        .map_or(Duration::ZERO, |since: Instant| since.elapsed());
+                let queued_requests = protocol::reader::queued_requests(&mut stream, &header);
+                if queued_requests > pipelined_since.len() {
+                    pipelined_since.resize(queued_requests, Instant::now());
+                }
+                connection_context.stats.begin_request(
+                    usize::try_from(header.length).unwrap_or_default(),
+                    queued_requests,
+                );
+
+                let Some(result) = catch_handler_panic(
+                    handle_message::<T, S>(
+                        &mut connection_context,
+                        &header,
+                        &mut stream,
+                        &request_activity_id,
+                        queue_wait,
+                        draining,
+                    ),
                     &request_activity_id,
                 )
                 .await
-                {
+                else {
+                    // The panic may have left the connection's state inconsistent, so the
+                    // client is told if possible and only this connection is closed.
+                    let error =
+                        DocumentDBError::internal_error("The request handler panicked".to_owned());
+                    if let Err(e) = log_and_write_error::<S>(
+                        &connection_context,
+                        &header,
+                        &error,
+                        None,
+                        &mut stream,
+                        None,
+                        &RequestTracker::new(),
+                        &request_activity_id,
+                        None,
+                    )
+                    .await
+                    {
+                        tracing::warn!(
+                            activity_id = request_activity_id.as_str(),
+                            "Couldn't reply with error {e:?}."
+                        );
+                    }
+                    release_idle_connection(&mut connection_context).await;
+                    break ConnectionCloseReason::HandlerPanic;
+                };
+
+                if let Err(e) = result {
+                    if keepalive_expired(&e) {
+                        tracing::info!(
+                            activity_id = request_activity_id.as_str(),
+                            "Closing connection that stopped answering keepalive probes."
+                        );
+                        release_idle_connection(&mut connection_context).await;
+                        break ConnectionCloseReason::KeepaliveTimeout;
+                    }
+                    if client_aborted(&e) {
+                        break ConnectionCloseReason::ClientDisconnected;
+                    }
+                    if stream_is_unreadable(&e) {
+                        tracing::warn!(
+                            activity_id = request_activity_id.as_str(),
+                            "Closing connection after an invalid message: {e}"
+                        );
+                        break ConnectionCloseReason::InvalidMessage;
+                    }
                     if let Err(e) = log_and_write_error::<S>(
                         &connection_context,
                         &header,
@@ -556,7 +810,7 @@ where
                             activity_id = request_activity_id.as_str(),
                             "Couldn't reply with error {e:?}."
                         );
-                        break;
+                        break ConnectionCloseReason::WriteFailed;
                     }
                 }
             }
@@ -566,11 +820,22 @@ where
                     activity_id = connection_activity_id_as_str,
                     "Connection closed."
                 );
-                break;
+                break ConnectionCloseReason::ClientDisconnected;
+            }
+
+            Err(e) if keepalive_expired(&e) => {
+                tracing::info!(
+                    activity_id = connection_activity_id_as_str,
+                    "Closing connection that stopped answering keepalive probes."
+                );
+                release_idle_connection(&mut connection_context).await;
+                break ConnectionCloseReason::KeepaliveTimeout;
             }
 
             Err(e) => {
-                if let Err(e) = responses::writer::write_error_without_header(
+                // The stream is no longer split at message boundaries.
+                pipelined_since.clear();
+                match responses::writer::write_error_without_header(
                     &connection_context,
                     e,
                     &mut stream,
@@ -578,14 +843,66 @@ where
                 )
                 .await
                 {
-                    tracing::warn!(
-                        activity_id = connection_activity_id_as_str,
-                        "Couldn't reply with error {e:?}."
-                    );
-                    break;
+                    Ok(written) => connection_context.stats.record_response(written),
+                    Err(e) => {
+                        tracing::warn!(
+                            activity_id = connection_activity_id_as_str,
+                            "Couldn't reply with error {e:?}."
+                        );
+                        break ConnectionCloseReason::WriteFailed;
+                    }
                 }
             }
         }
+    };
+
+    connection_context.log_close_summary(close_reason);
+
+    let summary = connection_context
+        .stats
+        .summary(connection_context.start_time, close_reason);
+    if connection_context.request_metrics_enabled() {
+        record_connection_metrics(&summary);
+    }
+    summary
+}
+
+/// Completes once `idle_timeout` has elapsed, or never if there is none.
+async fn wait_idle(idle_timeout: Option<Duration>) {
+    match idle_timeout {
+        Some(idle_timeout) => tokio::time::sleep(idle_timeout).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Releases the cursors and the transaction an idle, unreachable or failed client left open,
+/// so that their backend connections go back to the pool instead of waiting for the cursor
+/// timeout.
+async fn release_idle_connection(connection_context: &mut ConnectionContext) {
+    let connection_id = connection_context.connection_id;
+    let cursor_ids = connection_context
+        .service_context
+        .cursor_store()
+        .invalidate_cursors_by_connection(connection_id);
+    if !cursor_ids.is_empty() {
+        tracing::info!(
+            activity_id = connection_id.to_string().as_str(),
+            "Released cursors {cursor_ids:?} of idle connection."
+        );
+    }
+
+    if let Some((session_id, _)) = connection_context.transaction.take() {
+        if let Err(e) = connection_context
+            .service_context
+            .transaction_store()
+            .remove_transaction_by_session(&session_id)
+            .await
+        {
+            tracing::warn!(
+                activity_id = connection_id.to_string().as_str(),
+                "Failed to abort the transaction of idle connection: {e}"
+            );
+        }
     }
 }
 
@@ -611,8 +928,24 @@ where
         return Ok(response);
     }
 
+    auth::check_role_command_allowed(
+        request_context.payload.request_type(),
+        connection_context.auth_state.roles(),
+        &connection_context
+            .dynamic_configuration()
+            .role_command_allowlist(),
+    )?;
+
+    if request_context.payload.request_type().gateway_only() {
+        return processor::process_gateway_only(request_context, connection_context);
+    }
+
     let service_context = Arc::clone(&connection_context.service_context);
-    let data_client = T::new_authorized(&service_context, &connection_context.auth_state)?;
+    let data_client = T::new_authorized(
+        &service_context,
+        &connection_context.auth_state,
+        routing_database(request_context),
+    )?;
 
     // Process the actual request
     let response =
@@ -621,28 +954,79 @@ where
     Ok(response)
 }
 
+/// Returns the database whose backend serves the request. `renameCollection` runs against
+/// `admin` but acts on its source namespace.
+fn routing_database<'a>(request_context: &'a RequestContext<'_>) -> Option<&'a str> {
+    if request_context.payload.request_type() == RequestType::RenameCollection {
+        return request_context
+            .info
+            .collection()
+            .ok()
+            .and_then(|namespace| namespace.split_once('.'))
+            .map(|(db, _)| db);
+    }
+    request_context.info.db().ok()
+}
+
+#[expect(
+    clippy::too_many_lines,
+    reason = "request parsing with per-phase timing"
+)]
 async fn handle_message<T, S>(
     connection_context: &mut ConnectionContext,
     header: &Header,
     stream: &mut S,
     activity_id: &str,
+    queue_wait: Duration,
+    draining: &CancellationToken,
 ) -> Result<()>
 where
     T: PgDataClient,
-    S: AsyncRead + AsyncWrite + Unpin,
+    S: AsyncBufRead + AsyncRead + AsyncWrite + Unpin,
 {
     let request_tracker = RequestTracker::new();
+    request_tracker.record_elapsed(RequestIntervalKind::QueueWait, queue_wait);
 
     // Read the request message off the stream
     let read_request_start = Instant::now();
-    let message = protocol::reader::read_request(header, stream).await?;
+    let message = protocol::reader::read_request(
+        header,
+        stream,
+        connection_context
+            .service_context
+            .setup_configuration()
+            .max_message_size_bytes(),
+    )
+    .await?;
     request_tracker.record_duration(RequestIntervalKind::ReadRequest, read_request_start);
 
+    let request_memory = RequestMemory::new(
+        connection_context
+            .service_context
+            .setup_configuration()
+            .max_request_memory_bytes(),
+    );
+    let request_body = request_memory.charge(message.request.len())?;
+
+    // Replies to OP_COMPRESSED are sent uncompressed, in the form of the opcode it carried.
+    let decompressed_header;
+    let header = if message.op_code == header.op_code {
+        header
+    } else {
+        decompressed_header = Header {
+            length: header.length,
+            request_id: header.request_id,
+            response_to: header.response_to,
+            op_code: message.op_code,
+        };
+        &decompressed_header
+    };
+
     // HandleMessage captures the overall duration needed by the server to handle/process
     // a user operation message/request. Client-to-Gateway networking latency should be
     // excluded from HandleMessage; therefore, ReadRequest is closed before this starts,
     // and WriteResponse starts measuring only after HandleMessage is closed.
-    let handle_message_start = Instant::now();
+    let mut handle_message_start = Instant::now();
     if connection_context
         .dynamic_configuration()
         .send_shutdown_responses()
@@ -656,16 +1040,60 @@ where
     let format_request_start = Instant::now();
     let request =
         protocol::reader::parse_request(&message, &mut connection_context.requires_response)?;
+    let gateway_timing_request = request.take_gateway_timing()?;
+    let (request, gateway_timing) = match &gateway_timing_request {
+        Some((document, enabled)) => (request.with_document(document), *enabled),
+        None => (request, false),
+    };
     request_tracker.record_duration(RequestIntervalKind::FormatRequest, format_request_start);
 
+    let setup_configuration = connection_context.service_context.setup_configuration();
+    telemetry::redaction::log_command_received(
+        activity_id,
+        request.document(),
+        setup_configuration.redacted_command_fields(),
+        setup_configuration.log_command_user_data(),
+    );
+
     let request_info = request.extract_common()?;
+    if let Some(session_id) = &request_info.session_id {
+        connection_context.service_context.session_store().touch(
+            session_id,
+            request_info
+                .transaction_info
+                .as_ref()
+                .map(|info| info.transaction_number),
+        );
+    }
     validation::validate_request(connection_context, &request_info, &request)?;
+    unknown_options::check_unknown_options(
+        &request,
+        connection_context
+            .dynamic_configuration()
+            .unknown_option_policy(),
+    )?;
+    query_limits::check_query_limits(
+        &request,
+        connection_context.dynamic_configuration().query_limits(),
+    )?;
+
+    let awaitable_hello = AwaitableHello::from_request(&request)?;
+    if let Some(awaitable_hello) = &awaitable_hello {
+        awaitable_hello.wait().await;
+        // Awaiting a topology change is not part of handling the request.
+        handle_message_start = Instant::now();
+    }
+    // With exhaustAllowed, every reply to an awaitable hello is sent with moreToCome
+    // and followed by the next one, until the connection closes.
+    let streaming = awaitable_hello.is_some() && protocol::reader::exhaust_allowed(&message);
+    let next_hello = awaitable_hello.map(AwaitableHello::next);
 
     let request_context = RequestContext {
         activity_id,
         payload: &request,
         info: &request_info,
         tracker: &request_tracker,
+        memory: &request_memory,
     };
 
     let request_result = handle_request::<T, S>(
@@ -674,17 +1102,23 @@ where
         &request_context,
         stream,
         handle_message_start,
+        gateway_timing,
+        streaming,
     )
     .await;
 
     // Errors in request handling are handled explicitly so that telemetry can have access to the request
     // Returns Ok afterwards so that higher level error telemetry is not invoked.
-    if let Err(e) = request_result {
+    if let Err(e) = &request_result {
+        // A client that went away gets no reply; the connection closes instead.
+        if client_aborted(e) {
+            return request_result;
+        }
         let collection = request_context.info.collection().unwrap_or("").to_owned();
         match log_and_write_error::<S>(
             connection_context,
             header,
-            &e,
+            e,
             Some(request_context.payload),
             stream,
             Some(collection),
@@ -704,6 +1138,133 @@ where
         }
     }
 
+    drop(request_body);
+    if connection_context.request_metrics_enabled() {
+        record_request_memory_high_water_mark(request_memory.high_water_mark());
+    }
+
+    if let (true, Ok(()), Some(next_hello)) = (streaming, request_result, next_hello) {
+        return stream_hello_replies::<T, S>(
+            connection_context,
+            header,
+            &request_context,
+            stream,
+            draining,
+            next_hello,
+        )
+        .await;
+    }
+
+    Ok(())
+}
+
+/// Sends the rest of an exhaust hello stream, one reply with moreToCome each time the
+/// topology changes or the await time elapses. The stream ends when a reply can't be
+/// written, which is how a client that stopped listening is noticed, or at shutdown.
+async fn stream_hello_replies<T, S>(
+    connection_context: &mut ConnectionContext,
+    header: &Header,
+    request_context: &RequestContext<'_>,
+    stream: &mut S,
+    draining: &CancellationToken,
+    mut awaitable_hello: AwaitableHello,
+) -> Result<()>
+where
+    T: PgDataClient,
+    S: AsyncBufRead + AsyncRead + AsyncWrite + Unpin,
+{
+    while !draining.is_cancelled() {
+        awaitable_hello.wait().await;
+        let next_hello = awaitable_hello.next();
+
+        let request_tracker = RequestTracker::new();
+        let reply_context = RequestContext {
+            tracker: &request_tracker,
+            ..*request_context
+        };
+        handle_request::<T, S>(
+            connection_context,
+            header,
+            &reply_context,
+            stream,
+            Instant::now(),
+            false,
+            true,
+        )
+        .await?;
+        awaitable_hello = next_hello;
+    }
+    Ok(())
+}
+
+/// Whether `error` means the rest of the client stream cannot be parsed, such as a
+/// compressed message that fails the decompression guard.
+fn stream_is_unreadable(error: &DocumentDBError) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::IoError(e, _) if e.kind() == std::io::ErrorKind::InvalidData
+    )
+}
+
+/// Whether `error` means the client stopped answering TCP keepalive probes, which the
+/// kernel reports as a timed out read or write.
+fn keepalive_expired(error: &DocumentDBError) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::IoError(e, _) if e.kind() == std::io::ErrorKind::TimedOut
+    )
+}
+
+/// Whether `error` means the client disconnected while its request was in flight.
+fn client_aborted(error: &DocumentDBError) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::IoError(e, _) if e.kind() == std::io::ErrorKind::ConnectionAborted
+    )
+}
+
+/// The "db.collection" namespace a request targets, or just the database for database commands.
+fn request_namespace(request_context: &RequestContext<'_>) -> String {
+    let db = request_context.info.db().unwrap_or("");
+    match request_context.info.collection() {
+        Ok(collection) => format!("{db}.{collection}"),
+        Err(_) => db.to_owned(),
+    }
+}
+
+/// Describes the request for `currentOp` while it is in flight.
+fn operation_info(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+) -> OperationInfo {
+    OperationInfo {
+        comment: request_context.info.comment.clone(),
+        ..OperationInfo::new(
+            request_context.payload.request_type(),
+            request_namespace(request_context),
+            connection_context.connection_id,
+            connection_context.ip_address.clone(),
+        )
+    }
+}
+
+/// Backend pages are not split, so a batch too large for one wire message is an error.
+fn check_response_size(connection_context: &ConnectionContext, response: &Response) -> Result<()> {
+    let max_message_size_bytes = connection_context
+        .service_context
+        .setup_configuration()
+        .max_message_size_bytes();
+    let response_size = response.as_raw_document()?.as_bytes().len();
+    if usize::try_from(max_message_size_bytes)
+        .is_ok_and(|max| response_size + RESPONSE_MESSAGE_OVERHEAD > max)
+    {
+        return Err(DocumentDBError::documentdb_error(
+            ErrorCode::BsonObjectTooLarge,
+            format!(
+                "Response of {response_size} bytes exceeds the maximum message size of {max_message_size_bytes} bytes"
+            ),
+        ));
+    }
     Ok(())
 }
 
@@ -713,25 +1274,54 @@ async fn handle_request<T, S>(
     request_context: &RequestContext<'_>,
     stream: &mut S,
     handle_message_start: tokio::time::Instant,
+    gateway_timing: bool,
+    more_to_come: bool,
 ) -> Result<()>
 where
     T: PgDataClient,
-    S: AsyncRead + AsyncWrite + Unpin,
+    S: AsyncBufRead + AsyncRead + AsyncWrite + Unpin,
 {
     // Process the request
     let handle_request_start = Instant::now();
-    let response_result = get_response::<T>(request_context, connection_context).await;
+    let operation = connection_context
+        .service_context
+        .operation_registry()
+        .register(operation_info(request_context, connection_context));
+    let response_result = tokio::select! {
+        result = get_response::<T>(request_context, connection_context) => result,
+        () = operation.killed() => Err(DocumentDBError::documentdb_error(
+            ErrorCode::ExceededTimeLimit,
+            format!("Operation {} was interrupted by killOp.", operation.op_id()),
+        )),
+        // Dropping the request cancels its backend query, if one is running.
+        () = protocol::reader::wait_for_disconnect(stream) => {
+            tracing::info!(
+                activity_id = request_context.activity_id,
+                "Client disconnected during operation {}, abandoning it.",
+                operation.op_id()
+            );
+            record_request_client_aborted(&request_context.payload.request_type().to_string());
+            Err(DocumentDBError::from(std::io::Error::from(
+                std::io::ErrorKind::ConnectionAborted,
+            )))
+        }
+    };
+    drop(operation);
     request_context
         .tracker
         .record_duration(RequestIntervalKind::HandleRequest, handle_request_start);
 
-    let response = match response_result {
-        Ok(response) => response,
-        // For the error case, the error handling code will close HandleMessage.
-        Err(e) => {
-            return Err(e);
-        }
-    };
+    // For the error case, the error handling code will close HandleMessage.
+    let mut response = response_result?;
+    let _response_batch = request_context
+        .memory
+        .charge(response.response_byte_len())?;
+    if gateway_timing {
+        response = response.with_field(
+            GATEWAY_TIMING_FIELD,
+            request_context.tracker.gateway_timing(),
+        )?;
+    }
 
     // Write the response back to the stream
     request_context
@@ -739,40 +1329,109 @@ where
         .record_duration(RequestIntervalKind::HandleMessage, handle_message_start);
 
     if connection_context.requires_response {
+        check_response_size(connection_context, &response)?;
+
         let write_response_start = Instant::now();
-        responses::writer::write(header, &response, stream).await?;
+        let written = if more_to_come {
+            responses::writer::write_more_to_come(header, &response, stream).await?
+        } else {
+            responses::writer::write(header, &response, stream).await?
+        };
+        connection_context.stats.record_response(written);
         request_context
             .tracker
             .record_duration(RequestIntervalKind::WriteResponse, write_response_start);
     }
 
+    record_request_telemetry(
+        connection_context,
+        header,
+        Some(request_context.payload),
+        Left(&response),
+        request_context.info.collection().unwrap_or(""),
+        request_context.tracker,
+        request_context.activity_id,
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Records a request answered with `outcome` in its span, the gateway metrics, the audit log,
+/// the latency log and the telemetry provider's events.
+async fn record_request_telemetry(
+    connection_context: &ConnectionContext,
+    header: &Header,
+    request: Option<&Request<'_>>,
+    outcome: Either<&Response, (&CommandError, usize)>,
+    collection: &str,
+    request_tracker: &RequestTracker,
+    activity_id: &str,
+) {
+    // The span is emitted first so that metrics can link to its trace.
+    let tenant = connection_context.tenant();
+    let span_context = record_request_span(
+        request,
+        outcome,
+        collection,
+        request_tracker,
+        activity_id,
+        connection_context.client_information.as_deref(),
+        connection_context.app_name(),
+        tenant.as_deref(),
+    );
+
     if connection_context.request_metrics_enabled() {
         record_gateway_metrics(
             header,
-            Some(request_context.payload),
-            Left(&response),
-            request_context.info.collection().unwrap_or(""),
-            request_context.tracker,
+            request,
+            outcome,
+            collection,
+            connection_context.app_name(),
+            tenant.as_deref(),
+            request_tracker,
+            span_context.as_ref(),
         );
     }
 
+    telemetry::audit::audit_request(
+        connection_context,
+        request,
+        collection,
+        outcome.right().map(|(error, _)| *error.code() as i32),
+    );
+
+    let response_length = match outcome {
+        Left(response) => response
+            .as_raw_document()
+            .map_or(0, |document| document.as_bytes().len()),
+        Right((_, length)) => length,
+    };
+    telemetry::try_log_verbose_latency(
+        connection_context,
+        request,
+        collection,
+        request_tracker,
+        activity_id,
+        outcome.right().map(|(error, _)| error),
+        i64::from(header.length),
+        i64::try_from(response_length).unwrap_or(i64::MAX),
+    );
+
     if let Some(telemetry) = connection_context.telemetry_provider.as_ref() {
-        let collection = request_context.info.collection().unwrap_or("").to_owned();
         telemetry
             .emit_request_event(
                 connection_context,
                 header,
-                Some(request_context.payload),
-                Left(&response),
-                collection,
-                request_context.tracker,
-                request_context.activity_id,
+                request,
+                outcome,
+                collection.to_owned(),
+                request_tracker,
+                activity_id,
                 &parse_client_info(connection_context.client_information.as_ref()),
             )
             .await;
     }
-
-    Ok(())
 }
 
 #[expect(
@@ -801,38 +1460,147 @@ where
     }
 
     let write_response_start = Instant::now();
-    responses::writer::write_and_flush(header, &response, stream).await?;
+    let written = responses::writer::write_and_flush(header, &response, stream).await?;
     request_tracker.record_duration(RequestIntervalKind::WriteResponse, write_response_start);
+    connection_context.stats.record_response(written);
 
     // telemetry can block so do it after write and flush.
     telemetry::log_request_failure(error, connection_context, activity_id, request);
 
-    let collection = collection.unwrap_or_default();
+    record_request_telemetry(
+        connection_context,
+        header,
+        request,
+        Right((&command_error, response.as_bytes().len())),
+        collection.as_deref().unwrap_or_default(),
+        request_tracker,
+        activity_id,
+    )
+    .await;
 
-    if connection_context.request_metrics_enabled() {
-        record_gateway_metrics(
-            header,
-            request,
-            Right((&command_error, response.as_bytes().len())),
-            &collection,
-            request_tracker,
-        );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use ::bson::rawdoc;
+    use async_trait::async_trait;
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::testing::{MapConfiguration, StubDataClient};
+
+    /// How long [`QueueWaits`] holds up the first request it sees.
+    const HOLD: Duration = Duration::from_millis(50);
+
+    /// Records the queue wait of each request, holding up the first one by [`HOLD`].
+    #[derive(Debug, Clone, Default)]
+    struct QueueWaits(Arc<Mutex<Vec<(i32, Duration)>>>);
+
+    #[async_trait]
+    impl TelemetryProvider for QueueWaits {
+        async fn emit_request_event(
+            &self,
+            _: &ConnectionContext,
+            header: &Header,
+            _: Option<&Request<'_>>,
+            _: Either<&Response, (&CommandError, usize)>,
+            _: String,
+            request_tracker: &RequestTracker,
+            _: &str,
+            _: &str,
+        ) {
+            let queue_wait = Duration::from_nanos(
+                request_tracker
+                    .get_interval_elapsed_time(RequestIntervalKind::QueueWait)
+                    .cast_unsigned(),
+            );
+            let first = {
+                let mut waits = self.0.lock().unwrap();
+                waits.push((header.request_id, queue_wait));
+                waits.len() == 1
+            };
+            if first {
+                tokio::time::sleep(HOLD).await;
+            }
+        }
     }
 
-    if let Some(telemetry) = connection_context.telemetry_provider.as_ref() {
-        telemetry
-            .emit_request_event(
+    fn ping(request_id: i32) -> Vec<u8> {
+        testing::op_msg(request_id, &rawdoc! { "ping": 1, "$db": "admin" })
+    }
+
+    #[tokio::test]
+    async fn peak_concurrency_counts_pipelined_requests() {
+        let service_context = testing::service_context(MapConfiguration::default()).await;
+        let connection_context = testing::connection_context(service_context, "user");
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+
+        // Three requests sent before any reply, then one sent after the replies.
+        let pipelined: Vec<u8> = (1..=3).flat_map(ping).collect();
+        client.write_all(&pipelined).await.unwrap();
+        let client_side = async move {
+            for _ in 0..3 {
+                testing::read_message(&mut client).await;
+            }
+            client.write_all(&ping(4)).await.unwrap();
+            testing::read_message(&mut client).await;
+        };
+
+        let draining = CancellationToken::new();
+        let (summary, ()) = tokio::join!(
+            handle_stream::<StubDataClient, _>(
+                BufStream::new(server),
                 connection_context,
-                header,
-                request,
-                Right((&command_error, response.as_bytes().len())),
-                collection,
-                request_tracker,
-                activity_id,
-                &parse_client_info(connection_context.client_information.as_ref()),
-            )
-            .await;
+                Duration::ZERO,
+                &draining,
+            ),
+            client_side,
+        );
+
+        assert_eq!(summary.requests, 4);
+        assert_eq!(summary.peak_concurrent_requests, 3);
+        assert_eq!(
+            summary.close_reason,
+            ConnectionCloseReason::ClientDisconnected
+        );
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn pipelined_request_waits_for_the_one_ahead_of_it() {
+        let service_context = testing::service_context(MapConfiguration::default()).await;
+        let mut connection_context = testing::connection_context(service_context, "user");
+        let waits = QueueWaits::default();
+        connection_context.telemetry_provider = Some(Box::new(waits.clone()));
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+
+        // The second request is sent before the first, which is held up, is answered.
+        let pipelined: Vec<u8> = (1..=2).flat_map(ping).collect();
+        client.write_all(&pipelined).await.unwrap();
+        let client_side = async move {
+            for _ in 0..2 {
+                testing::read_message(&mut client).await;
+            }
+        };
+
+        let draining = CancellationToken::new();
+        let admission_wait = Duration::from_millis(5);
+        tokio::join!(
+            handle_stream::<StubDataClient, _>(
+                BufStream::new(server),
+                connection_context,
+                admission_wait,
+                &draining,
+            ),
+            client_side,
+        );
+
+        let waits = waits.0.lock().unwrap().clone();
+        assert_eq!(waits.len(), 2);
+        assert_eq!(waits[0], (1, admission_wait));
+        assert_eq!(waits[1].0, 2);
+        assert!(waits[1].1 >= HOLD, "{waits:?}");
+    }
 }
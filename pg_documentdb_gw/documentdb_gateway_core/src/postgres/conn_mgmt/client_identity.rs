@@ -0,0 +1,92 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/postgres/conn_mgmt/client_identity.rs
+ *
+ * The client behind a request, shown as the `application_name` of the
+ * backend session serving it so that `pg_stat_activity` can be traced back
+ * to gateway clients.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+/// Restores the `application_name` the pool connected with.
+pub const RESET_CLIENT_IDENTITY: &str = "RESET application_name";
+
+/// The backend truncates longer application names (`NAMEDATALEN` - 1).
+const MAX_APPLICATION_NAME_BYTES: usize = 63;
+
+/// The `application_name` for a request: the client's `appName`, shortened so the whole
+/// name fits, followed by the request's activity id.
+///
+/// The backend only shows printable ASCII, so other characters, and backslashes, become
+/// `?` here, where they are counted against the length limit.
+#[must_use]
+pub fn client_application_name(app_name: &str, activity_id: &str) -> String {
+    let printable = |c: char| {
+        if matches!(c, ' '..='~') && c != '\\' {
+            c
+        } else {
+            '?'
+        }
+    };
+    let app_name_budget = MAX_APPLICATION_NAME_BYTES.saturating_sub(activity_id.len() + 1);
+
+    let mut application_name = app_name
+        .chars()
+        .map(printable)
+        .take(app_name_budget)
+        .collect::<String>();
+    application_name.push('/');
+    application_name.extend(activity_id.chars().map(printable));
+    application_name.truncate(MAX_APPLICATION_NAME_BYTES);
+    application_name
+}
+
+/// The statement setting the session's `application_name`, or only the current
+/// transaction's when `local`. The name includes the client-chosen `appName`, so it is
+/// quoted as a literal rather than trusted.
+#[must_use]
+pub fn set_application_name_statement(application_name: &str, local: bool) -> String {
+    let scope = if local { "LOCAL " } else { "" };
+    let quoted = application_name.replace('\'', "''");
+    format!("SET {scope}application_name = '{quoted}'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACTIVITY_ID: &str = "0f5e3a52-6c1b-4c5e-9d0a-00000000002a";
+
+    #[test]
+    fn statement_names_the_client_and_request() {
+        let application_name = client_application_name("reports", ACTIVITY_ID);
+        assert_eq!(application_name, format!("reports/{ACTIVITY_ID}"));
+
+        assert_eq!(
+            set_application_name_statement(&application_name, false),
+            format!("SET application_name = 'reports/{ACTIVITY_ID}'")
+        );
+        assert_eq!(
+            set_application_name_statement(&application_name, true),
+            format!("SET LOCAL application_name = 'reports/{ACTIVITY_ID}'")
+        );
+    }
+
+    #[test]
+    fn client_chosen_app_name_is_quoted_and_bounded() {
+        let application_name = client_application_name(
+            "it's'; RESET ALL; --ünïcode and a very long name",
+            ACTIVITY_ID,
+        );
+        assert_eq!(application_name.len(), MAX_APPLICATION_NAME_BYTES);
+        assert!(application_name.ends_with(ACTIVITY_ID));
+        assert!(application_name.starts_with("it's'; RESET ALL; --?n?cod"));
+
+        assert_eq!(
+            set_application_name_statement(&application_name, false),
+            format!("SET application_name = 'it''s''; RESET ALL; --?n?cod/{ACTIVITY_ID}'")
+        );
+    }
+}
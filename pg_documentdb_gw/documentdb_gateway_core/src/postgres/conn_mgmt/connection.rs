@@ -11,12 +11,27 @@ use std::{
     time::Duration,
 };
 
+use deadpool::managed;
 use tokio_postgres::{
     types::{ToSql, Type},
     Row,
 };
 
-use crate::postgres::{conn_mgmt::PoolConnection, PgDocument};
+use crate::postgres::{
+    conn_mgmt::{
+        client_identity::{set_application_name_statement, RESET_CLIENT_IDENTITY},
+        connection_pool::backend_tls,
+        statement_cache::is_plan_invalidation,
+        PoolConnection,
+    },
+    PgDocument,
+};
+
+fn is_plan_invalidated(error: &tokio_postgres::Error) -> bool {
+    error
+        .as_db_error()
+        .is_some_and(|db_error| is_plan_invalidation(db_error.code(), db_error.message()))
+}
 
 // Provides functions which coerce bson to BYTEA. Any statement binding a PgDocument should use query_typed and not query
 // WrongType { postgres: Other(Other { name: "bson", oid: 18934, kind: Simple, schema: "schema_name" }), rust: "document_gateway::postgres::document::PgDocument" })
@@ -64,6 +79,60 @@ impl Connection {
             .is_ok()
     }
 
+    /// Detaches the connection from its pool so it is closed instead of being reused.
+    pub fn evict(self) {
+        drop(managed::Object::take(self.pool_connection));
+    }
+
+    /// Closes the connection instead of returning it to the pool once the last holder
+    /// releases it, for a connection that cannot be evicted because it is still shared.
+    pub fn evict_when_released(&self) {
+        self.pool_connection.mark_discarded();
+    }
+
+    /// Asks the backend to cancel the query running on this connection, if any.
+    ///
+    /// The cancel request goes over a separate connection, so it may arrive after the query
+    /// has finished; callers should not reuse the connection afterwards.
+    ///
+    /// # Errors
+    /// Returns a [`tokio_postgres::Error`] if the cancel request could not be sent.
+    pub async fn cancel_query(&self) -> std::result::Result<(), tokio_postgres::Error> {
+        self.pool_connection
+            .cancel_token()
+            .cancel_query(backend_tls())
+            .await
+    }
+
+    /// Shows `application_name`, such as from [`client_application_name`], as the name of
+    /// the backend session until [`Self::reset_client_identity`], or only until the current
+    /// transaction ends when `local`.
+    ///
+    /// [`client_application_name`]: super::client_application_name
+    ///
+    /// # Errors
+    /// Returns a [`tokio_postgres::Error`] if the name could not be set.
+    pub async fn set_client_identity(
+        &self,
+        application_name: &str,
+        local: bool,
+    ) -> std::result::Result<(), tokio_postgres::Error> {
+        self.pool_connection.mark_client_identity(!local);
+        self.batch_execute(&set_application_name_statement(application_name, local))
+            .await
+    }
+
+    /// Restores the session name the pool connected with.
+    ///
+    /// # Errors
+    /// Returns a [`tokio_postgres::Error`] if the name could not be reset, in which case the
+    /// pool resets it before the connection is reused.
+    pub async fn reset_client_identity(&self) -> std::result::Result<(), tokio_postgres::Error> {
+        self.batch_execute(RESET_CLIENT_IDENTITY).await?;
+        self.pool_connection.mark_client_identity(false);
+        Ok(())
+    }
+
     /// Executes a parameterized query and returns all resulting rows.
     ///
     /// # Errors
@@ -76,10 +145,27 @@ impl Connection {
     ) -> std::result::Result<Vec<Row>, tokio_postgres::Error> {
         let statement = self
             .pool_connection
-            .prepare_typed_cached(query, parameter_types)
+            .prepare_statement(query, parameter_types)
             .await?;
 
-        self.pool_connection.query(&statement, params).await
+        match self.pool_connection.query(&statement, params).await {
+            Err(error) if is_plan_invalidated(&error) => {
+                self.pool_connection.invalidate_statement(query);
+                // A failed statement aborts the transaction, so it cannot be retried within it.
+                if self.in_transaction() {
+                    return Err(error);
+                }
+                tracing::debug!(
+                    "Preparing a statement again after its plan was invalidated: {error}"
+                );
+                let statement = self
+                    .pool_connection
+                    .prepare_statement(query, parameter_types)
+                    .await?;
+                self.pool_connection.query(&statement, params).await
+            }
+            result => result,
+        }
     }
 
     /// # Errors
@@ -152,6 +238,9 @@ pub struct QueryOptions {
     /// If false, uses session-level SET (for cursor ops that outlive a transaction).
     /// Only relevant when `supports_backend_timeout` is false.
     supports_transaction_timeout: bool,
+    /// If true, the query modifies documents and is not retried after a connection
+    /// reset, since the backend may already have applied it.
+    write: bool,
 }
 
 impl Default for QueryOptions {
@@ -161,6 +250,7 @@ impl Default for QueryOptions {
             retry_deadlock: false,
             supports_backend_timeout: false,
             supports_transaction_timeout: true,
+            write: false,
         }
     }
 }
@@ -199,6 +289,12 @@ impl QueryOptionsBuilder {
         self
     }
 
+    #[must_use]
+    pub const fn write(mut self, value: bool) -> Self {
+        self.inner.write = value;
+        self
+    }
+
     #[must_use]
     pub const fn build(self) -> QueryOptions {
         self.inner
@@ -233,4 +329,8 @@ impl QueryOptions {
     pub const fn supports_transaction_timeout(self) -> bool {
         self.supports_transaction_timeout
     }
+    #[must_use]
+    pub const fn write(self) -> bool {
+        self.write
+    }
 }
@@ -12,7 +12,8 @@ use std::{
     sync::atomic::{AtomicU64, Ordering},
 };
 
-use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod, Runtime, Status};
+use deadpool::managed;
+use deadpool_postgres::{Manager, ManagerConfig, RecyclingMethod, Runtime, Status};
 use tokio::{
     task::JoinHandle,
     time::{Duration, Instant},
@@ -20,17 +21,27 @@ use tokio::{
 use tokio_postgres::NoTls;
 
 use crate::{
-    configuration::SetupConfiguration,
+    configuration::{BackendTarget, SetupConfiguration},
     error::Result,
-    postgres::{conn_mgmt::PgPoolSettings, QueryCatalog},
+    postgres::{
+        conn_mgmt::{PgPoolSettings, StatementCachingManager},
+        QueryCatalog,
+    },
 };
 
+/// The TLS connector backend connections are made with. Cancel requests open a connection
+/// of their own, so they use it too.
+pub const fn backend_tls() -> NoTls {
+    NoTls
+}
+
 fn pg_configuration(
     setup_configuration: &dyn SetupConfiguration,
     query_catalog: &QueryCatalog,
     user: &str,
     password: Option<&str>,
     application_name: &str,
+    backend: Option<&BackendTarget>,
 ) -> tokio_postgres::Config {
     let mut config = tokio_postgres::Config::new();
 
@@ -44,15 +55,29 @@ fn pg_configuration(
             .as_millis()
             .to_string();
 
+    let (host_name, port) = backend.map_or_else(
+        || {
+            (
+                setup_configuration.postgres_host_name(),
+                setup_configuration.postgres_port(),
+            )
+        },
+        |backend| (backend.host_name.as_str(), backend.port),
+    );
+
+    let mut options =
+        query_catalog.set_search_path_and_timeout(&command_timeout_ms, &transaction_timeout_ms);
+    if setup_configuration.enable_collation() {
+        options.push_str(query_catalog.enable_collation());
+    }
+
     config
-        .host(setup_configuration.postgres_host_name())
-        .port(setup_configuration.postgres_port())
+        .host(host_name)
+        .port(port)
         .dbname(setup_configuration.postgres_database())
         .user(user)
         .application_name(application_name)
-        .options(
-            query_catalog.set_search_path_and_timeout(&command_timeout_ms, &transaction_timeout_ms),
-        );
+        .options(&options);
 
     if let Some(pass) = password {
         config.password(pass);
@@ -61,7 +86,12 @@ fn pg_configuration(
     config
 }
 
-pub type PoolConnection = deadpool_postgres::Object;
+type Pool = managed::Pool<StatementCachingManager>;
+
+pub type PoolConnection = managed::Object<StatementCachingManager>;
+
+/// Maintenance commands such as compact run one at a time per pool.
+pub const MAINTENANCE_MAX_CONNECTIONS: usize = 1;
 
 #[derive(Debug)]
 pub struct ConnectionPoolStatus {
@@ -102,6 +132,14 @@ fn u64_to_instant(nanos: u64) -> Instant {
     epoch() + Duration::from_nanos(nanos)
 }
 
+/// Which of a [`ConnectionPool`]'s pools a connection is acquired from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolKind {
+    Primary,
+    Timeout,
+    Maintenance,
+}
+
 #[derive(Debug)]
 pub struct ConnectionPool {
     pool: Pool,
@@ -110,6 +148,9 @@ pub struct ConnectionPool {
     /// `RecyclingMethod::Clean` to reset all session state when a
     /// connection is returned
     timeout_pool: Pool,
+    /// Pool for long-running maintenance commands, kept apart so that they
+    /// never occupy the connections serving regular requests.
+    maintenance_pool: Pool,
     /// Nanosecond offset from `EPOCH` of the last `acquire_connection` call.
     /// Uses `AtomicU64` instead of `RwLock<Instant>` to avoid async lock
     /// overhead on the hot acquire path.
@@ -129,6 +170,31 @@ impl ConnectionPool {
         password: Option<&str>,
         application_name: &str,
         pool_settings: PgPoolSettings,
+    ) -> Result<Self> {
+        Self::new_for_backend(
+            setup_configuration,
+            query_catalog,
+            user,
+            password,
+            application_name,
+            pool_settings,
+            None,
+        )
+    }
+
+    /// Creates a pool connecting to `backend`, or to the default backend when it is `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the operation fails.
+    pub fn new_for_backend(
+        setup_configuration: &dyn SetupConfiguration,
+        query_catalog: &QueryCatalog,
+        user: &str,
+        password: Option<&str>,
+        application_name: &str,
+        pool_settings: PgPoolSettings,
+        backend: Option<&BackendTarget>,
     ) -> Result<Self> {
         let config = pg_configuration(
             setup_configuration,
@@ -136,15 +202,21 @@ impl ConnectionPool {
             user,
             password,
             application_name,
+            backend,
         );
 
-        let build_pool = |pg_config: tokio_postgres::Config, recycling_method: RecyclingMethod| {
-            let manager =
-                Manager::from_config(pg_config, NoTls, ManagerConfig { recycling_method });
+        let build_pool = |pg_config: tokio_postgres::Config,
+                          recycling_method: RecyclingMethod,
+                          max_size: usize| {
+            let manager = StatementCachingManager::new(
+                Manager::from_config(pg_config, backend_tls(), ManagerConfig { recycling_method }),
+                setup_configuration.postgres_statement_cache_size(),
+                application_name.to_owned(),
+            );
 
             Pool::builder(manager)
                 .runtime(Runtime::Tokio1)
-                .max_size(pool_settings.adjusted_max_connections())
+                .max_size(max_size)
                 .wait_timeout(Some(Duration::from_secs(
                     setup_configuration.postgres_command_timeout_secs(),
                 )))
@@ -152,15 +224,29 @@ impl ConnectionPool {
         };
 
         // Primary pool — RecyclingMethod::Fast (no state reset on return)
-        let pool = build_pool(config.clone(), RecyclingMethod::Fast)?;
+        let pool = build_pool(
+            config.clone(),
+            RecyclingMethod::Fast,
+            pool_settings.adjusted_max_connections(),
+        )?;
 
         // Timeout pool — RecyclingMethod::Clean (resets session state on return)
         // Used for requests that SET statement_timeout at session level.
-        let timeout_pool = build_pool(config, RecyclingMethod::Clean)?;
+        let timeout_pool = build_pool(
+            config.clone(),
+            RecyclingMethod::Clean,
+            pool_settings.adjusted_max_connections(),
+        )?;
+
+        // Maintenance pool — RecyclingMethod::Clean, as maintenance commands may
+        // also set session-level timeouts.
+        let maintenance_pool =
+            build_pool(config, RecyclingMethod::Clean, MAINTENANCE_MAX_CONNECTIONS)?;
 
         // `Pool` is internally `Arc`-wrapped, so cloning shares state with the pruner.
         let pool_copy = pool.clone();
         let timeout_pool_copy = timeout_pool.clone();
+        let maintenance_pool_copy = maintenance_pool.clone();
         // Timeout pool connections are pruned more aggressively on idleness
         // to free slots back to the primary pool for general use.
         let timeout_idle_lifetime =
@@ -183,6 +269,11 @@ impl ConnectionPool {
                     conn_metrics.last_used() < timeout_idle_lifetime
                         && conn_metrics.age() < pool_settings.connection_lifetime()
                 });
+
+                maintenance_pool_copy.retain(|_, conn_metrics| {
+                    conn_metrics.last_used() < timeout_idle_lifetime
+                        && conn_metrics.age() < pool_settings.connection_lifetime()
+                });
             }
         });
 
@@ -197,6 +288,7 @@ impl ConnectionPool {
         Ok(Self {
             pool,
             timeout_pool,
+            maintenance_pool,
             last_used_nanos: AtomicU64::new(instant_to_u64(Instant::now())),
             identifier: pool_identifier,
             prune_task,
@@ -233,6 +325,32 @@ impl ConnectionPool {
         self.timeout_pool.get().await
     }
 
+    /// Acquires a connection from the maintenance pool.
+    ///
+    /// # Errors
+    /// Returns a [`deadpool_postgres::PoolError`] if another maintenance command
+    /// holds the connection past the wait timeout, or the connection cannot be established.
+    pub async fn acquire_maintenance_connection(
+        &self,
+    ) -> std::result::Result<PoolConnection, deadpool_postgres::PoolError> {
+        self.last_used_nanos
+            .store(instant_to_u64(Instant::now()), Ordering::Relaxed);
+        self.maintenance_pool.get().await
+    }
+
+    /// Whether acquiring from the `kind` pool would wait for a connection to be returned,
+    /// since none is idle and the pool is at its maximum size.
+    #[must_use]
+    pub fn is_exhausted(&self, kind: PoolKind) -> bool {
+        let pool = match kind {
+            PoolKind::Primary => &self.pool,
+            PoolKind::Timeout => &self.timeout_pool,
+            PoolKind::Maintenance => &self.maintenance_pool,
+        };
+        let status = pool.status();
+        status.available == 0 && status.size >= status.max_size
+    }
+
     pub fn last_used(&self) -> Instant {
         u64_to_instant(self.last_used_nanos.load(Ordering::Relaxed))
     }
@@ -240,14 +358,15 @@ impl ConnectionPool {
     pub fn status(&self) -> ConnectionPoolStatus {
         let primary = self.pool.status();
         let timeout = self.timeout_pool.status();
+        let maintenance = self.maintenance_pool.status();
 
         ConnectionPoolStatus {
             identifier: self.identifier.clone(),
             status: Status {
-                max_size: primary.max_size + timeout.max_size,
-                size: primary.size + timeout.size,
-                available: primary.available + timeout.available,
-                waiting: primary.waiting + timeout.waiting,
+                max_size: primary.max_size + timeout.max_size + maintenance.max_size,
+                size: primary.size + timeout.size + maintenance.size,
+                available: primary.available + timeout.available + maintenance.available,
+                waiting: primary.waiting + timeout.waiting + maintenance.waiting,
             },
         }
     }
@@ -351,7 +470,11 @@ mod tests {
         .await;
 
         let status = pool.status();
-        assert_eq!(status.status().max_size, 10 * 2); // accounts for both primary and timeout pools
+        // accounts for the primary, timeout and maintenance pools
+        assert_eq!(
+            status.status().max_size,
+            10 * 2 + MAINTENANCE_MAX_CONNECTIONS
+        );
         assert_eq!(status.status().size, 0);
         assert_eq!(status.status().available, 0);
     }
@@ -364,7 +487,7 @@ mod tests {
         let pool = test_pool(&setup_config, &setup_config.postgres_system_user.clone(), 5).await;
 
         let status = pool.status();
-        assert_eq!(status.status().max_size, 10);
+        assert_eq!(status.status().max_size, 10 + MAINTENANCE_MAX_CONNECTIONS);
     }
 
     #[tokio::test]
@@ -486,7 +609,14 @@ mod tests {
         let setup_config = setup_configuration();
         let query_catalog = create_query_catalog();
 
-        let config = pg_configuration(&setup_config, &query_catalog, "user", Some("secret"), "app");
+        let config = pg_configuration(
+            &setup_config,
+            &query_catalog,
+            "user",
+            Some("secret"),
+            "app",
+            None,
+        );
         let password = config.get_password().expect("password should be set");
         assert_eq!(password, b"secret");
     }
@@ -496,7 +626,31 @@ mod tests {
         let setup_config = setup_configuration();
         let query_catalog = create_query_catalog();
 
-        let config = pg_configuration(&setup_config, &query_catalog, "user", None, "app");
+        let config = pg_configuration(&setup_config, &query_catalog, "user", None, "app", None);
         assert!(config.get_password().is_none());
     }
+
+    #[test]
+    fn test_pg_configuration_with_backend_overrides_host_and_port() {
+        let setup_config = setup_configuration();
+        let query_catalog = create_query_catalog();
+        let backend = BackendTarget {
+            host_name: "tenant-host".to_owned(),
+            port: 5433,
+        };
+
+        let config = pg_configuration(
+            &setup_config,
+            &query_catalog,
+            "user",
+            None,
+            "app",
+            Some(&backend),
+        );
+        assert_eq!(
+            config.get_hosts(),
+            &[tokio_postgres::config::Host::Tcp("tenant-host".to_owned())]
+        );
+        assert_eq!(config.get_ports(), &[5433]);
+    }
 }
@@ -6,15 +6,20 @@
  *-------------------------------------------------------------------------
  */
 
+mod client_identity;
 mod connection;
 mod connection_pool;
 mod pool_manager;
 mod pool_settings;
 mod query_dispatch;
 mod retry_policies;
+mod statement_cache;
 
+pub use client_identity::client_application_name;
 pub use connection::{Connection, QueryOptions, QueryOptionsBuilder, RequestOptions};
-pub use connection_pool::{ConnectionPool, ConnectionPoolStatus, PoolConnection};
+pub use connection_pool::{
+    ConnectionPool, ConnectionPoolStatus, PoolConnection, PoolKind, MAINTENANCE_MAX_CONNECTIONS,
+};
 pub use pool_manager::{
     clean_unused_pools, create_connection_pool_manager, PoolManager,
     AUTHENTICATION_MAX_CONNECTIONS, SYSTEM_REQUESTS_MAX_CONNECTIONS,
@@ -22,4 +27,7 @@ pub use pool_manager::{
 pub use pool_settings::{
     PgPoolSettings, CONN_IDLE_LIFETIME_SECS, CONN_LIFETIME_SECS, CONN_PRUNE_INTERVAL_SECS,
 };
-pub use query_dispatch::{run_request_with_retries, ConnectionSource, PullConnection};
+pub use query_dispatch::{
+    run_request_with_retries, ConnectionSource, OperationTimeout, PullConnection,
+};
+pub use statement_cache::{PooledClient, StatementCachingManager};
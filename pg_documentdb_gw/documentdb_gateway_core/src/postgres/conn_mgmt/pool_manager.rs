@@ -12,7 +12,7 @@ use dashmap::{mapref::entry::Entry, DashMap};
 use tokio::time::{interval, Duration};
 
 use crate::{
-    configuration::{DynamicConfiguration, SetupConfiguration},
+    configuration::{BackendTarget, DynamicConfiguration, SetupConfiguration},
     context::ServiceContext,
     error::{DocumentDBError, Result},
     postgres::{
@@ -23,7 +23,7 @@ use crate::{
     telemetry::event_id::EventId,
 };
 
-type ClientKey = (String, PgPoolSettings);
+type ClientKey = (String, PgPoolSettings, Option<BackendTarget>);
 
 pub const SYSTEM_REQUESTS_MAX_CONNECTIONS: usize = 2;
 pub const AUTHENTICATION_MAX_CONNECTIONS: usize = 5;
@@ -41,7 +41,7 @@ pub struct PoolManager {
     system_requests_pool: ConnectionPool,
     system_auth_pool: ConnectionPool,
 
-    // Maps user credentials to their respective connection pools
+    // Maps user credentials and backend to their respective connection pools
     // We need Arc on the ConnectionPool to allow sharing across threads from different connections
     user_data_pools: DashMap<ClientKey, Arc<ConnectionPool>>,
     shared_data_pools: DashMap<PgPoolSettings, Arc<ConnectionPool>>,
@@ -82,6 +82,9 @@ impl PoolManager {
         ))
     }
 
+    /// Creates the user's pools for the default backend and every routed backend, since the
+    /// password is only known while authenticating.
+    ///
     /// # Errors
     /// Returns error if the operation fails.
     pub fn allocate_data_pool(
@@ -91,32 +94,55 @@ impl PoolManager {
         dynamic_configuration: &dyn DynamicConfiguration,
     ) -> Result<()> {
         let settings = PgPoolSettings::from_configuration(dynamic_configuration);
-        let key = (username.to_owned(), settings);
-
-        let user_data_pool = Arc::new(ConnectionPool::new_with_user(
-            self.setup_configuration.as_ref(),
-            &self.query_catalog,
-            username,
-            Some(password),
-            &format!("{}-UserData", self.setup_configuration.application_name()),
-            settings,
-        )?);
+        let application_name = self.setup_configuration.application_name();
+        let routed_backends = self.setup_configuration.backend_router().targets();
+
+        for backend in std::iter::once(None).chain(routed_backends.into_iter().map(Some)) {
+            let pool_name = backend.map_or_else(
+                || format!("{application_name}-UserData"),
+                |backend| {
+                    format!(
+                        "{application_name}-UserData-{}:{}",
+                        backend.host_name, backend.port
+                    )
+                },
+            );
 
-        self.user_data_pools.insert(key, user_data_pool);
+            let user_data_pool = Arc::new(ConnectionPool::new_for_backend(
+                self.setup_configuration.as_ref(),
+                &self.query_catalog,
+                username,
+                Some(password),
+                &pool_name,
+                settings,
+                backend,
+            )?);
+
+            self.user_data_pools.insert(
+                (username.to_owned(), settings, backend.cloned()),
+                user_data_pool,
+            );
+        }
 
         Ok(())
     }
 
+    /// Returns the user's pool for `backend`, or for the default backend when it is `None`.
+    ///
     /// # Errors
     /// Returns error if the operation fails.
     pub fn get_data_pool(
         &self,
         username: &str,
+        backend: Option<&BackendTarget>,
         dynamic_configuration: &dyn DynamicConfiguration,
     ) -> Result<Arc<ConnectionPool>> {
         let settings = PgPoolSettings::from_configuration(dynamic_configuration);
 
-        match self.user_data_pools.get(&(username.to_owned(), settings)) {
+        match self
+            .user_data_pools
+            .get(&(username.to_owned(), settings, backend.cloned()))
+        {
             None => Err(DocumentDBError::internal_error(
                 "Connection pool missing for user.".to_owned(),
             )),
@@ -171,6 +197,11 @@ impl PoolManager {
         clean(&self.shared_data_pools, max_age);
     }
 
+    #[must_use]
+    pub fn system_requests_pool_status(&self) -> ConnectionPoolStatus {
+        self.system_requests_pool.status()
+    }
+
     pub fn report_pool_stats(&self) -> Vec<ConnectionPoolStatus> {
         fn report<K>(map: &DashMap<K, Arc<ConnectionPool>>, reports: &mut Vec<ConnectionPoolStatus>)
         where
@@ -282,9 +313,12 @@ pub async fn create_connection_pool_manager(
 mod tests {
     use super::*;
     use crate::{
-        configuration::{CertInputType, CertificateOptions, DocumentDBSetupConfiguration},
+        configuration::{
+            BackendRoute, BackendRouter, CertInputType, CertificateOptions,
+            DocumentDBSetupConfiguration,
+        },
         error::{ErrorCode, ErrorKind},
-        postgres::create_query_catalog,
+        postgres::{conn_mgmt::MAINTENANCE_MAX_CONNECTIONS, create_query_catalog},
     };
     use bson::{rawbson, RawBson};
     use std::sync::atomic::{AtomicUsize, Ordering};
@@ -389,12 +423,15 @@ mod tests {
     }
 
     fn test_pool_manager() -> PoolManager {
+        test_pool_manager_with(&setup_configuration())
+    }
+
+    fn test_pool_manager_with(setup_config: &DocumentDBSetupConfiguration) -> PoolManager {
         let query_catalog = create_query_catalog();
-        let setup_config = setup_configuration();
         let postgres_system_user = setup_config.postgres_system_user();
 
         let system_requests_pool = ConnectionPool::new_with_user(
-            &setup_config,
+            setup_config,
             &query_catalog,
             postgres_system_user,
             None,
@@ -404,7 +441,7 @@ mod tests {
         .expect("Failed to create system requests pool");
 
         let authentication_pool = ConnectionPool::new_with_user(
-            &setup_config,
+            setup_config,
             &query_catalog,
             postgres_system_user,
             None,
@@ -448,11 +485,11 @@ mod tests {
             );
 
             let shared_pool = shared_pool_result.unwrap();
-            // max_size is the combined capacity of the primary + timeout pools
+            // max_size is the combined capacity of the primary, timeout and maintenance pools
             assert_eq!(
-                dynamic_configuration.max_conn() * 2,
+                dynamic_configuration.max_conn() * 2 + MAINTENANCE_MAX_CONNECTIONS,
                 shared_pool.status().status().max_size,
-                "Should have the combined size of primary and timeout pools"
+                "Should have the combined size of primary, timeout and maintenance pools"
             );
 
             assert_eq!(
@@ -563,7 +600,7 @@ mod tests {
         let pool_manager = test_pool_manager();
 
         let err = pool_manager
-            .get_data_pool("missing-user", &dynamic_configuration)
+            .get_data_pool("missing-user", None, &dynamic_configuration)
             .unwrap_err();
 
         assert!(matches!(
@@ -589,11 +626,11 @@ mod tests {
             .unwrap();
 
         let user_pool = pool_manager
-            .get_data_pool("user", &dynamic_configuration)
+            .get_data_pool("user", None, &dynamic_configuration)
             .unwrap();
 
         assert_eq!(
-            dynamic_configuration.max_conn() * 2,
+            dynamic_configuration.max_conn() * 2 + MAINTENANCE_MAX_CONNECTIONS,
             user_pool.status().status().max_size
         );
     }
@@ -625,4 +662,58 @@ mod tests {
         // only 2 system pools should remain since user and shared pools are expired
         assert_eq!(2, pool_manager.report_pool_stats().len());
     }
+
+    #[tokio::test]
+    async fn test_routed_backends_get_their_own_user_pools() {
+        // We still need an async context to create the connection pool (see ConnectionPool::new_with_user),
+        // but the test itself doesn't need to be async since we are not awaiting anything after the pool creation,
+        // so we can use yield_now to just get into async context and then proceed with sync code.
+        yield_now().await;
+
+        let dynamic_configuration = MaxConnectionConfig {
+            max_conn: 100.into(),
+        };
+        let backend_router = BackendRouter::new(
+            &[
+                BackendRoute {
+                    database: Some("billing".to_owned()),
+                    host_name: "billing-host".to_owned(),
+                    ..Default::default()
+                },
+                BackendRoute {
+                    database_prefix: Some("bill".to_owned()),
+                    host_name: "billing-host".to_owned(),
+                    ..Default::default()
+                },
+            ],
+            9712,
+        )
+        .unwrap();
+        let pool_manager = test_pool_manager_with(&DocumentDBSetupConfiguration {
+            backend_router: backend_router.clone(),
+            ..setup_configuration()
+        });
+
+        pool_manager
+            .allocate_data_pool("user", "password", &dynamic_configuration)
+            .unwrap();
+
+        assert_eq!(
+            4,
+            pool_manager.report_pool_stats().len(),
+            "2 system pools + default and routed user pools"
+        );
+
+        let default_pool = pool_manager
+            .get_data_pool("user", None, &dynamic_configuration)
+            .unwrap();
+        let routed_pool = pool_manager
+            .get_data_pool(
+                "user",
+                backend_router.resolve("billing"),
+                &dynamic_configuration,
+            )
+            .unwrap();
+        assert!(!Arc::ptr_eq(&default_pool, &routed_pool));
+    }
 }
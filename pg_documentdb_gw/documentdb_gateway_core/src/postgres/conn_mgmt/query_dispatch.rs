@@ -15,11 +15,11 @@ use tokio::time::{Duration, Instant};
 use tokio_postgres::error::SqlState;
 
 use crate::{
-    error::{DocumentDBError, ErrorCode, ErrorKind, Result},
+    error::{DocumentDBError, ErrorCode, ErrorKind, Result, RETRYABLE_WRITE_ERROR_LABEL},
     postgres::conn_mgmt::{
         connection::{Connection, QueryOptions, RequestOptions},
         retry_policies::{LongRetryPolicy, RetryPolicyBuilder, ShortRetryPolicy},
-        ConnectionPool,
+        ConnectionPool, PoolKind,
     },
     requests::{request_tracker::RequestTracker, RequestIntervalKind},
 };
@@ -31,6 +31,8 @@ pub enum PullConnection {
     PoolOrTransaction,
     /// Reuse a pinned cursor connection.
     Cursor(Arc<Connection>),
+    /// Acquire from the pool's dedicated maintenance connection (or use an active transaction).
+    Maintenance,
 }
 
 /// Describes where to obtain a connection on each retry iteration.
@@ -38,12 +40,44 @@ pub enum PullConnection {
 pub enum ConnectionSource<'a> {
     /// Acquire a fresh connection from the pool on each retry attempt.
     Pool(&'a ConnectionPool),
+    /// Acquire the pool's maintenance connection on each retry attempt.
+    Maintenance(&'a ConnectionPool),
     /// Reuse the provided cursor connection on each retry attempt.
     Cursor(Arc<Connection>),
     /// Use the transaction connection; retry is suppressed.
     Transaction(Arc<Connection>),
 }
 
+/// The time limit a request runs under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationTimeout {
+    /// The `maxTimeMS` of the command, where 0 means no limit. Queries that support it
+    /// enforce this in the backend.
+    Client(i64),
+    /// The configured default, applied by the gateway when the command has no `maxTimeMS`.
+    Default(i64),
+}
+
+impl OperationTimeout {
+    /// An explicit `maxTimeMS` always wins over the default; a default of 0 disables it.
+    #[must_use]
+    pub fn resolve(max_time_ms: Option<i64>, default_timeout_ms: u64) -> Option<Self> {
+        match max_time_ms {
+            Some(max_time_ms) => Some(Self::Client(max_time_ms)),
+            None if default_timeout_ms > 0 => Some(Self::Default(
+                i64::try_from(default_timeout_ms).unwrap_or(i64::MAX),
+            )),
+            None => None,
+        }
+    }
+
+    const fn as_millis(self) -> i64 {
+        match self {
+            Self::Client(ms) | Self::Default(ms) => ms,
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 enum Retry {
     Long,
@@ -51,9 +85,23 @@ enum Retry {
     None,
 }
 
+/// What to do when a query fails because its backend connection was reset.
+#[derive(Debug, Eq, PartialEq)]
+enum ResetAction {
+    /// Retry at once on a fresh connection from the pool.
+    RetryOnFreshConnection,
+    /// Fail with a `RetryableWriteError` label, since the write may already have been
+    /// applied and only the driver can retry it safely.
+    RetryableWrite,
+    /// Leave the error to the regular retry policy.
+    Classify,
+}
+
 struct RetryContext {
     stopwatch: Instant,
     retry_count: u32,
+    /// Whether the request was already retried after a connection reset.
+    reset_retried: bool,
     short_retry_policy: Option<ShortRetryPolicy>,
     long_retry_policy: Option<LongRetryPolicy>,
 }
@@ -83,6 +131,128 @@ fn is_connectivity_error(error: &tokio_postgres::Error) -> bool {
     false
 }
 
+/// I/O errors raised when the backend drops a connection mid-query.
+const fn is_reset_io_error(kind: io::ErrorKind) -> bool {
+    matches!(
+        kind,
+        io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
+/// SQL states the backend reports before it terminates a connection.
+fn is_reset_error_code(code: &str) -> bool {
+    code == SqlState::ADMIN_SHUTDOWN.code()
+        || code == SqlState::CRASH_SHUTDOWN.code()
+        || code == SqlState::CONNECTION_FAILURE.code()
+        || code == SqlState::CONNECTION_EXCEPTION.code()
+}
+
+fn io_error_kind(error: &tokio_postgres::Error) -> Option<io::ErrorKind> {
+    use std::error::Error;
+
+    let mut source = error.source();
+    while let Some(err) = source {
+        if let Some(io_err) = err.downcast_ref::<io::Error>() {
+            return Some(io_err.kind());
+        }
+        source = err.source();
+    }
+    None
+}
+
+/// Whether `error` means the backend connection was reset or terminated, so the
+/// connection is unusable.
+fn is_connection_reset(error: &tokio_postgres::Error) -> bool {
+    error.is_closed()
+        || error
+            .code()
+            .is_some_and(|state| is_reset_error_code(state.code()))
+        || io_error_kind(error).is_some_and(is_reset_io_error)
+}
+
+/// Reads are retried once on a fresh connection; writes are left to the driver.
+const fn classify_reset(
+    query_options: QueryOptions,
+    fresh_connections: bool,
+    reset_retried: bool,
+) -> ResetAction {
+    if query_options.write() {
+        ResetAction::RetryableWrite
+    } else if fresh_connections && query_options.retry_request() && !reset_retried {
+        ResetAction::RetryOnFreshConnection
+    } else {
+        ResetAction::Classify
+    }
+}
+
+/// Takes a connection whose backend went away out of the pool rather than returning it.
+/// Connections still shared elsewhere, such as cursor or transaction connections, are
+/// left to their owners.
+fn evict_if_reset(connection: Arc<Connection>, error: &tokio_postgres::Error) {
+    if is_connection_reset(error) {
+        if let Ok(connection) = Arc::try_unwrap(connection) {
+            connection.evict();
+        }
+    }
+}
+
+/// Cancels the backend query of a request abandoned mid-query, such as when the client
+/// disconnects or `killOp` interrupts it, unless disarmed once the query completes.
+///
+/// Dropping the query future leaves the statement running on the backend, so a cancel
+/// request is sent for it. The connection is then taken out of the pool, since a cancel
+/// that arrives late would interrupt the next query run on it. A gateway transaction
+/// left open by the abandoned query is rolled back first.
+struct CancelOnDrop {
+    connection: Option<Arc<Connection>>,
+    in_gateway_txn: bool,
+}
+
+impl CancelOnDrop {
+    fn new(connection: &Arc<Connection>, in_gateway_txn: bool) -> Self {
+        Self {
+            connection: Some(Arc::clone(connection)),
+            in_gateway_txn,
+        }
+    }
+
+    fn disarm(mut self) {
+        self.connection = None;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        let Some(connection) = self.connection.take() else {
+            return;
+        };
+        let in_gateway_txn = self.in_gateway_txn;
+        tokio::spawn(async move {
+            if let Err(e) = connection.cancel_query().await {
+                tracing::warn!("Failed to cancel the backend query of an abandoned request: {e}");
+            }
+            if in_gateway_txn {
+                if let Err(e) = connection.batch_execute("ROLLBACK").await {
+                    tracing::debug!(
+                        "Failed to roll back the transaction of an abandoned request: {e}"
+                    );
+                }
+                connection.set_in_transaction(false);
+            }
+            // A connection still shared elsewhere, such as a cursor or transaction
+            // connection, is closed once its last holder releases it.
+            match Arc::try_unwrap(connection) {
+                Ok(connection) => connection.evict(),
+                Err(connection) => connection.evict_when_released(),
+            }
+        });
+    }
+}
+
 fn is_timeout_error(error: &tokio_postgres::Error) -> bool {
     use std::error::Error;
 
@@ -213,23 +383,36 @@ fn get_retry_interval(retry: &Retry, retry_context: &mut RetryContext) -> Option
     }
 }
 
+/// Whether the gateway has to set the statement timeout for `timeout` itself.
+///
+/// A client `maxTimeMS` is left to the backend for queries that enforce it. The default
+/// timeout is never part of the command, so it is set on every fresh pool connection
+/// outside a user transaction; pinned cursor connections keep the timeout they started with.
+const fn needs_gateway_timeout(
+    timeout: Option<OperationTimeout>,
+    from_pool: bool,
+    in_transaction: bool,
+    query_options: QueryOptions,
+) -> bool {
+    match timeout {
+        Some(OperationTimeout::Client(_)) => {
+            !in_transaction && !query_options.supports_backend_timeout()
+        }
+        Some(OperationTimeout::Default(_)) => from_pool,
+        None => false,
+    }
+}
+
 /// Sets the `PostgreSQL` statement timeout
 ///
 /// Returns `true` if a gateway transaction was started (caller must COMMIT/ROLLBACK).
 async fn set_statement_timeout(
     connection: &Connection,
-    max_time_ms: Option<i64>,
+    max_time_ms: i64,
     query_options: &QueryOptions,
-    in_user_transaction: bool,
     request_tracker: &RequestTracker,
 ) -> std::result::Result<bool, tokio_postgres::Error> {
-    let max_time_ms = match max_time_ms {
-        Some(ms) if !in_user_transaction && !query_options.supports_backend_timeout() => ms,
-        _ => return Ok(false),
-    };
-
-    // Determine whether to wrap in a gateway transaction.
-    // Since in_user_transaction == false here (checked above), we either
+    // Callers only set a timeout outside user transactions, so either
     // use BEGIN + SET LOCAL (supports_transaction_timeout) or session-level SET.
     let use_transaction = query_options.supports_transaction_timeout();
 
@@ -266,6 +449,8 @@ async fn set_statement_timeout(
 ///
 /// - Resolves a connection via [`ConnectionSource`]
 /// - Applies gateway timeout if needed
+/// - Names the backend session after `client_identity` on connections checked out for the
+///   request, resetting it once the query completes
 /// - Executes the query closure
 /// - Commits gateway transaction if one was started
 /// - On error, classifies and retries
@@ -281,7 +466,8 @@ pub async fn run_request_with_retries<T, F, Fut>(
     source: ConnectionSource<'_>,
     query_options: QueryOptions,
     request_options: RequestOptions,
-    max_time_ms: Option<i64>,
+    timeout: Option<OperationTimeout>,
+    client_identity: Option<&str>,
     request_tracker: &RequestTracker,
     run_func: F,
 ) -> Result<T>
@@ -289,14 +475,17 @@ where
     F: Fn(Arc<Connection>) -> Fut,
     Fut: Future<Output = std::result::Result<T, tokio_postgres::Error>>,
 {
-    let command_timeout = max_time_ms.map_or_else(
-        || request_options.command_timeout(),
-        |ms| Duration::from_millis(ms.cast_unsigned()),
-    );
+    let command_timeout = match timeout {
+        Some(timeout) if timeout.as_millis() > 0 => {
+            Duration::from_millis(timeout.as_millis().cast_unsigned())
+        }
+        _ => request_options.command_timeout(),
+    };
 
     let mut retry_context = RetryContext {
         stopwatch: Instant::now(),
         retry_count: 0,
+        reset_retried: false,
         short_retry_policy: None,
         long_retry_policy: None,
     };
@@ -305,8 +494,15 @@ where
 
     // Pre-compute whether set_statement_timeout can ever apply. When false
     // (the common path) we skip the function call entirely on every iteration.
-    let needs_gateway_timeout =
-        max_time_ms.is_some() && !in_transaction && !query_options.supports_backend_timeout();
+    let from_pool = matches!(source, ConnectionSource::Pool(_));
+    let fresh_connections = matches!(
+        source,
+        ConnectionSource::Pool(_) | ConnectionSource::Maintenance(_)
+    );
+    let gateway_timeout_ms = timeout
+        .filter(|_| needs_gateway_timeout(timeout, from_pool, in_transaction, query_options))
+        .map(OperationTimeout::as_millis);
+    let needs_gateway_timeout = gateway_timeout_ms.is_some();
 
     // Use the timeout pool only when session-level SET statement_timeout will
     // be issued (no transaction wrapping). SET LOCAL auto-reverts on COMMIT so
@@ -319,17 +515,31 @@ where
                 // Use the timeout pool when session-level
                 // SET statement_timeout will be issued, so the connection state
                 // is reset on return and won't leak the setting to other requests.
-                ConnectionSource::Pool(pool) => {
-                    let open_backend_connection_start = Instant::now();
-                    let acquire = if needs_timeout_pool {
-                        pool.acquire_timeout_connection().await
+                ConnectionSource::Pool(pool) | ConnectionSource::Maintenance(pool) => {
+                    let pool_kind = if matches!(source, ConnectionSource::Maintenance(_)) {
+                        PoolKind::Maintenance
+                    } else if needs_timeout_pool {
+                        PoolKind::Timeout
                     } else {
-                        pool.acquire_connection().await
+                        PoolKind::Primary
+                    };
+                    let exhausted = pool.is_exhausted(pool_kind);
+                    let open_backend_connection_start = Instant::now();
+                    let acquire = match pool_kind {
+                        PoolKind::Maintenance => pool.acquire_maintenance_connection().await,
+                        PoolKind::Timeout => pool.acquire_timeout_connection().await,
+                        PoolKind::Primary => pool.acquire_connection().await,
                     };
                     request_tracker.record_duration(
                         RequestIntervalKind::OpenBackendConnection,
                         open_backend_connection_start,
                     );
+                    if exhausted {
+                        request_tracker.record_duration(
+                            RequestIntervalKind::PoolCheckout,
+                            open_backend_connection_start,
+                        );
+                    }
 
                     match acquire {
                         Ok(pool_conn) => Arc::new(Connection::new(pool_conn, false)),
@@ -353,12 +563,11 @@ where
             };
 
             // Set statement timeout (only when needed)
-            let in_gateway_txn = if needs_gateway_timeout {
+            let in_gateway_txn = if let Some(max_time_ms) = gateway_timeout_ms {
                 match set_statement_timeout(
                     &connection,
                     max_time_ms,
                     &query_options,
-                    in_transaction,
                     request_tracker,
                 )
                 .await
@@ -375,12 +584,36 @@ where
                 false
             };
 
+            // Cursor and transaction connections keep the name they were checked out with.
+            let client_identity_set = match client_identity.filter(|_| fresh_connections) {
+                Some(application_name) => {
+                    if let Err(e) = connection
+                        .set_client_identity(application_name, in_gateway_txn)
+                        .await
+                    {
+                        if in_gateway_txn {
+                            let _ = connection.batch_execute("ROLLBACK").await;
+                            connection.set_in_transaction(false);
+                        }
+                        break 'attempt Err(DocumentDBError::new(ErrorKind::PostgresError(
+                            e,
+                            Backtrace::capture(),
+                        )));
+                    }
+                    // A name set within the gateway transaction ends with it.
+                    !in_gateway_txn
+                }
+                None => false,
+            };
+
             // Execute the query
             let request_start = Instant::now();
             if in_gateway_txn {
                 // Gateway transaction active — clone Arc because we need
                 // the connection afterwards for COMMIT/ROLLBACK.
+                let cancel_on_drop = CancelOnDrop::new(&connection, true);
                 let query_result = run_func(Arc::clone(&connection)).await;
+                cancel_on_drop.disarm();
                 request_tracker.record_duration(RequestIntervalKind::ProcessRequest, request_start);
 
                 match query_result {
@@ -408,6 +641,7 @@ where
                     Err(e) => {
                         let _ = connection.batch_execute("ROLLBACK").await;
                         connection.set_in_transaction(false);
+                        evict_if_reset(connection, &e);
                         Err(DocumentDBError::new(ErrorKind::PostgresError(
                             e,
                             Backtrace::capture(),
@@ -415,11 +649,19 @@ where
                     }
                 }
             } else {
-                // No gateway transaction -> move the Arc directly into the
-                // closure call, avoiding an Arc::clone + drop pair.
-                let query_result = run_func(connection).await;
+                // No gateway transaction -> the Arc is only kept to evict the
+                // connection if its backend went away.
+                let cancel_on_drop = CancelOnDrop::new(&connection, false);
+                let query_result = run_func(Arc::clone(&connection)).await;
+                cancel_on_drop.disarm();
                 request_tracker.record_duration(RequestIntervalKind::ProcessRequest, request_start);
+                if client_identity_set {
+                    if let Err(e) = connection.reset_client_identity().await {
+                        tracing::debug!("Failed to reset the backend application_name: {e}");
+                    }
+                }
                 query_result.map_err(|e| {
+                    evict_if_reset(connection, &e);
                     DocumentDBError::new(ErrorKind::PostgresError(e, Backtrace::capture()))
                 })
             }
@@ -437,6 +679,34 @@ where
                 // - we haven't exhausted retries
                 // - it is retriable on a transient error, which means that it's not in a transaction.
                 if let Some(pg_error) = extract_pg_error(&error) {
+                    if !in_transaction && is_connection_reset(pg_error) {
+                        match classify_reset(
+                            query_options,
+                            fresh_connections,
+                            retry_context.reset_retried,
+                        ) {
+                            ResetAction::RetryOnFreshConnection => {
+                                retry_context.reset_retried = true;
+                                retry_context.retry_count += 1;
+                                tracing::warn!(
+                                    "Retrying request on a fresh connection after a connection reset: {error}"
+                                );
+                                continue;
+                            }
+                            ResetAction::RetryableWrite => {
+                                tracing::warn!(
+                                    "Connection reset during a write, returning a retryable write error: {error}"
+                                );
+                                return Err(DocumentDBError::documentdb_error(
+                                    ErrorCode::HostUnreachable,
+                                    "Connection to the backend was reset while the write was in progress".to_owned(),
+                                )
+                                .with_error_label(RETRYABLE_WRITE_ERROR_LABEL));
+                            }
+                            ResetAction::Classify => {}
+                        }
+                    }
+
                     let retry = retry_policy(pg_error, query_options, request_options);
 
                     if in_transaction {
@@ -484,7 +754,18 @@ where
 
 #[cfg(test)]
 mod tests {
+    use bson::rawdoc;
+
     use super::*;
+    use crate::{
+        configuration::DocumentDBSetupConfiguration,
+        postgres::{
+            conn_mgmt::PgPoolSettings, create_query_catalog,
+            documentdb_data_client::aggregate_query_options,
+        },
+        responses::CommandError,
+        testing::{self, MapConfiguration, ScriptedBackend},
+    };
 
     fn default_query_context() -> QueryOptions {
         QueryOptions::default()
@@ -502,6 +783,82 @@ mod tests {
         RequestOptions::new(true, 30)
     }
 
+    // ── OperationTimeout ──────────────────────────────────────────────
+
+    #[test]
+    fn test_operation_timeout_with_client_max_time_ignores_default() {
+        assert_eq!(
+            OperationTimeout::resolve(Some(500), 1_000),
+            Some(OperationTimeout::Client(500))
+        );
+        assert_eq!(
+            OperationTimeout::resolve(Some(500), 0),
+            Some(OperationTimeout::Client(500))
+        );
+    }
+
+    #[test]
+    fn test_operation_timeout_with_client_zero_stays_unlimited() {
+        assert_eq!(
+            OperationTimeout::resolve(Some(0), 1_000),
+            Some(OperationTimeout::Client(0))
+        );
+    }
+
+    #[test]
+    fn test_operation_timeout_without_max_time_uses_default() {
+        assert_eq!(
+            OperationTimeout::resolve(None, 1_000),
+            Some(OperationTimeout::Default(1_000))
+        );
+        assert_eq!(OperationTimeout::resolve(None, 0), None);
+    }
+
+    #[test]
+    fn test_needs_gateway_timeout_with_default_on_backend_timeout_query() {
+        let options = QueryOptions::builder()
+            .supports_backend_timeout(true)
+            .build();
+
+        // The backend enforces maxTimeMS from the command, but never sees the default.
+        assert!(!needs_gateway_timeout(
+            Some(OperationTimeout::Client(500)),
+            true,
+            false,
+            options
+        ));
+        assert!(needs_gateway_timeout(
+            Some(OperationTimeout::Default(500)),
+            true,
+            false,
+            options
+        ));
+    }
+
+    #[test]
+    fn test_needs_gateway_timeout_with_default_on_pinned_connection_returns_false() {
+        let options = default_query_context();
+
+        assert!(!needs_gateway_timeout(
+            Some(OperationTimeout::Default(500)),
+            false,
+            false,
+            options
+        ));
+        assert!(!needs_gateway_timeout(
+            Some(OperationTimeout::Default(500)),
+            false,
+            true,
+            options
+        ));
+        assert!(needs_gateway_timeout(
+            Some(OperationTimeout::Client(500)),
+            false,
+            false,
+            options
+        ));
+    }
+
     // ── is_transient_io_error ──────────────────────────────────────────
 
     #[test]
@@ -863,4 +1220,147 @@ mod tests {
         );
         assert_eq!(result, Retry::Short);
     }
+
+    // ── connection resets ─────────────────────────────────────────────
+
+    #[test]
+    fn test_is_reset_io_error_excludes_timeouts() {
+        assert!(is_reset_io_error(io::ErrorKind::ConnectionReset));
+        assert!(is_reset_io_error(io::ErrorKind::UnexpectedEof));
+        assert!(!is_reset_io_error(io::ErrorKind::TimedOut));
+        assert!(!is_reset_io_error(io::ErrorKind::PermissionDenied));
+    }
+
+    #[test]
+    fn test_is_reset_error_code_with_terminated_backend_returns_true() {
+        assert!(is_reset_error_code(SqlState::ADMIN_SHUTDOWN.code()));
+        assert!(is_reset_error_code(SqlState::CRASH_SHUTDOWN.code()));
+        assert!(is_reset_error_code(SqlState::CONNECTION_FAILURE.code()));
+        assert!(!is_reset_error_code(SqlState::T_R_DEADLOCK_DETECTED.code()));
+    }
+
+    #[test]
+    fn test_classify_reset_with_read_retries_once_on_fresh_connection() {
+        let options = default_query_context();
+
+        assert_eq!(
+            classify_reset(options, true, false),
+            ResetAction::RetryOnFreshConnection
+        );
+        // Later resets fall back to the regular retry policy.
+        assert_eq!(classify_reset(options, true, true), ResetAction::Classify);
+        // A pinned cursor connection cannot be replaced.
+        assert_eq!(classify_reset(options, false, false), ResetAction::Classify);
+    }
+
+    #[test]
+    fn test_classify_reset_with_write_returns_retryable_write() {
+        let options = QueryOptions::builder().write(true).build();
+
+        assert_eq!(
+            classify_reset(options, true, false),
+            ResetAction::RetryableWrite
+        );
+        assert_eq!(
+            classify_reset(
+                QueryOptions::builder()
+                    .write(true)
+                    .retry_request(false)
+                    .build(),
+                true,
+                false
+            ),
+            ResetAction::RetryableWrite
+        );
+    }
+
+    #[test]
+    fn test_classify_reset_with_merge_aggregate_returns_retryable_write() {
+        let merge = rawdoc! {
+            "aggregate": "src",
+            "pipeline": [{ "$match": {} }, { "$merge": { "into": "dst" } }],
+            "cursor": {},
+        };
+        assert_eq!(
+            classify_reset(aggregate_query_options(&merge).unwrap(), true, false),
+            ResetAction::RetryableWrite
+        );
+
+        let read = rawdoc! { "aggregate": "src", "pipeline": [{ "$match": {} }], "cursor": {} };
+        assert_eq!(
+            classify_reset(aggregate_query_options(&read).unwrap(), true, false),
+            ResetAction::RetryOnFreshConnection
+        );
+    }
+
+    // ── run_request_with_retries: connection resets ───────────────────
+
+    fn backend_pool(backend: &ScriptedBackend) -> ConnectionPool {
+        ConnectionPool::new_for_backend(
+            &DocumentDBSetupConfiguration::default(),
+            &create_query_catalog(),
+            "user",
+            None,
+            "test-app",
+            PgPoolSettings::system_pool_settings(1),
+            Some(backend.target()),
+        )
+        .unwrap()
+    }
+
+    async fn run_select(pool: &ConnectionPool, query_options: QueryOptions) -> Result<()> {
+        run_request_with_retries(
+            ConnectionSource::Pool(pool),
+            query_options,
+            non_replica_options(),
+            None,
+            None,
+            &RequestTracker::new(),
+            |connection| async move { connection.batch_execute("SELECT 1").await },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_run_request_with_retries_with_reset_read_retries_on_fresh_connection() {
+        let backend = ScriptedBackend::start(vec![SqlState::ADMIN_SHUTDOWN]).await;
+        let pool = backend_pool(&backend);
+
+        run_select(&pool, default_query_context()).await.unwrap();
+
+        assert_eq!(backend.queries(), 2);
+        // The reset connection is evicted, so the retry has to open another one.
+        assert_eq!(backend.connections(), 2);
+        assert_eq!(pool.status().status().size, 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_request_with_retries_with_reset_write_returns_retryable_write_error() {
+        let backend = ScriptedBackend::start(vec![SqlState::ADMIN_SHUTDOWN]).await;
+        let pool = backend_pool(&backend);
+
+        let error = run_select(&pool, QueryOptions::builder().write(true).build())
+            .await
+            .unwrap_err();
+
+        assert_eq!(backend.queries(), 1);
+        assert_eq!(pool.status().status().size, 0);
+        let connection_context = testing::connection_context(
+            testing::service_context(MapConfiguration::new(&[])).await,
+            "user",
+        );
+        let response =
+            CommandError::from_error(&connection_context, &error, "test").to_raw_document_buf();
+        assert_eq!(
+            response.get_i32("code").unwrap(),
+            ErrorCode::HostUnreachable as i32
+        );
+        let labels: Vec<&str> = response
+            .get_array("errorLabels")
+            .unwrap()
+            .into_iter()
+            .map(|label| label.unwrap().as_str().unwrap())
+            .collect();
+        assert_eq!(labels, [RETRYABLE_WRITE_ERROR_LABEL]);
+    }
 }
@@ -0,0 +1,381 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/postgres/conn_mgmt/statement_cache.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, PoisonError,
+    },
+    time::Instant,
+};
+
+use async_trait::async_trait;
+use deadpool::managed::{self, Metrics, RecycleError, RecycleResult};
+use deadpool_postgres::{ClientWrapper, Manager};
+use tokio_postgres::{error::SqlState, types::Type, Statement};
+
+use crate::{
+    postgres::conn_mgmt::client_identity::RESET_CLIENT_IDENTITY,
+    telemetry::metrics::{record_connection_create_duration, record_statement_cache_lookup},
+};
+
+/// A backend connection together with the statements prepared on it.
+#[derive(Debug)]
+pub struct PooledClient {
+    client: ClientWrapper,
+    statements: StatementCache<Statement>,
+    /// Whether the session's `application_name` names a request's client.
+    client_identity: AtomicBool,
+    /// Whether the connection must be closed instead of reused.
+    discarded: AtomicBool,
+}
+
+impl PooledClient {
+    /// Prepares `query`, reusing the statement already prepared on this connection for the
+    /// same SQL text and parameter types.
+    ///
+    /// # Errors
+    /// Returns a [`tokio_postgres::Error`] if the statement has to be prepared and that fails.
+    pub async fn prepare_statement(
+        &self,
+        query: &str,
+        parameter_types: &[Type],
+    ) -> Result<Statement, tokio_postgres::Error> {
+        self.statements
+            .get_or_prepare(query, parameter_types, || {
+                self.client.prepare_typed(query, parameter_types)
+            })
+            .await
+    }
+
+    /// Drops the cached statement for `query`, so that it is prepared again on next use.
+    pub fn invalidate_statement(&self, query: &str) {
+        self.statements.remove(query);
+    }
+
+    /// Records whether the session's `application_name` names a request's client, so that
+    /// a name left behind by an abandoned request is reset before the connection is reused.
+    pub fn mark_client_identity(&self, value: bool) {
+        self.client_identity.store(value, Ordering::Relaxed);
+    }
+
+    /// Closes the connection instead of reusing it once it is back in the pool, for a
+    /// connection that cannot be detached because it is still shared.
+    pub fn mark_discarded(&self) {
+        self.discarded.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Deref for PooledClient {
+    type Target = ClientWrapper;
+
+    fn deref(&self) -> &ClientWrapper {
+        &self.client
+    }
+}
+
+impl DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut ClientWrapper {
+        &mut self.client
+    }
+}
+
+/// Creates and recycles connections like [`Manager`], giving each one a statement cache
+/// of `statement_cache_size` entries. Connection setup time is recorded under `pool_name`.
+#[derive(Debug)]
+pub struct StatementCachingManager {
+    manager: Manager,
+    statement_cache_size: usize,
+    pool_name: String,
+}
+
+impl StatementCachingManager {
+    #[must_use]
+    pub const fn new(manager: Manager, statement_cache_size: usize, pool_name: String) -> Self {
+        Self {
+            manager,
+            statement_cache_size,
+            pool_name,
+        }
+    }
+}
+
+#[async_trait]
+impl managed::Manager for StatementCachingManager {
+    type Type = PooledClient;
+    type Error = tokio_postgres::Error;
+
+    async fn create(&self) -> Result<PooledClient, tokio_postgres::Error> {
+        // Covers connecting, authentication and startup, but not the wait for a pool slot.
+        let start = Instant::now();
+        let client = self.manager.create().await?;
+        record_connection_create_duration(&self.pool_name, start.elapsed());
+        Ok(PooledClient {
+            client,
+            statements: StatementCache::new(self.statement_cache_size),
+            client_identity: AtomicBool::new(false),
+            discarded: AtomicBool::new(false),
+        })
+    }
+
+    async fn recycle(
+        &self,
+        obj: &mut PooledClient,
+        metrics: &Metrics,
+    ) -> RecycleResult<tokio_postgres::Error> {
+        if obj.discarded.load(Ordering::Relaxed) {
+            return Err(RecycleError::StaticMessage("connection was discarded"));
+        }
+        if obj.client_identity.swap(false, Ordering::Relaxed) {
+            obj.client
+                .batch_execute(RESET_CLIENT_IDENTITY)
+                .await
+                .map_err(RecycleError::Backend)?;
+        }
+        self.manager.recycle(&mut obj.client, metrics).await
+    }
+
+    fn detach(&self, obj: &mut PooledClient) {
+        self.manager.detach(&mut obj.client);
+    }
+}
+
+/// Whether the backend rejected a cached statement because its plan is no longer valid,
+/// for instance after the result type of the query changed or the statement was deallocated.
+#[must_use]
+pub fn is_plan_invalidation(code: &SqlState, message: &str) -> bool {
+    *code == SqlState::INVALID_SQL_STATEMENT_NAME
+        || (*code == SqlState::FEATURE_NOT_SUPPORTED && message.starts_with("cached plan"))
+}
+
+#[derive(Debug)]
+struct CachedStatement<S> {
+    parameter_types: Vec<Type>,
+    statement: S,
+    last_used: u64,
+}
+
+#[derive(Debug)]
+struct CachedStatements<S> {
+    statements: HashMap<String, CachedStatement<S>>,
+    clock: u64,
+}
+
+/// Least recently used cache of prepared statements keyed by SQL text. Evicted statements
+/// are closed on the backend once the last request using them drops them.
+#[derive(Debug)]
+struct StatementCache<S> {
+    capacity: usize,
+    cached: Mutex<CachedStatements<S>>,
+}
+
+impl<S: Clone> StatementCache<S> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            cached: Mutex::new(CachedStatements {
+                statements: HashMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    async fn get_or_prepare<F, Fut, E>(
+        &self,
+        query: &str,
+        parameter_types: &[Type],
+        prepare: F,
+    ) -> Result<S, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<S, E>>,
+    {
+        if let Some(statement) = self.get(query, parameter_types) {
+            record_statement_cache_lookup(true);
+            return Ok(statement);
+        }
+        record_statement_cache_lookup(false);
+
+        let statement = prepare().await?;
+        self.insert(query, parameter_types, statement.clone());
+        Ok(statement)
+    }
+
+    fn get(&self, query: &str, parameter_types: &[Type]) -> Option<S> {
+        let mut cached = self.cached.lock().unwrap_or_else(PoisonError::into_inner);
+        cached.clock += 1;
+        let clock = cached.clock;
+        let statement = cached
+            .statements
+            .get_mut(query)
+            .filter(|entry| entry.parameter_types == parameter_types)
+            .map(|entry| {
+                entry.last_used = clock;
+                entry.statement.clone()
+            });
+        drop(cached);
+        statement
+    }
+
+    fn insert(&self, query: &str, parameter_types: &[Type], statement: S) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut cached = self.cached.lock().unwrap_or_else(PoisonError::into_inner);
+        if !cached.statements.contains_key(query) {
+            while cached.statements.len() >= self.capacity {
+                let Some(oldest) = cached
+                    .statements
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(query, _)| query.clone())
+                else {
+                    break;
+                };
+                cached.statements.remove(&oldest);
+            }
+        }
+
+        cached.clock += 1;
+        let last_used = cached.clock;
+        cached.statements.insert(
+            query.to_owned(),
+            CachedStatement {
+                parameter_types: parameter_types.to_vec(),
+                statement,
+                last_used,
+            },
+        );
+    }
+
+    fn remove(&self, query: &str) {
+        self.cached
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .statements
+            .remove(query);
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.cached
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .statements
+            .len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    /// Looks up `query`, "preparing" it as the next value of `prepared` on a miss.
+    async fn lookup(cache: &StatementCache<usize>, query: &str, prepared: &AtomicUsize) -> usize {
+        cache
+            .get_or_prepare(query, &[Type::TEXT], || async {
+                Ok::<_, ()>(prepared.fetch_add(1, Ordering::Relaxed))
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn same_query_is_prepared_once() {
+        let cache = StatementCache::new(4);
+        let prepared = AtomicUsize::new(0);
+
+        let first = lookup(&cache, "SELECT 1", &prepared).await;
+        let second = lookup(&cache, "SELECT 1", &prepared).await;
+
+        assert_eq!(first, second);
+        assert_eq!(prepared.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn different_parameter_types_are_prepared_again() {
+        let cache = StatementCache::new(4);
+        let prepared = AtomicUsize::new(0);
+
+        lookup(&cache, "SELECT $1", &prepared).await;
+        cache
+            .get_or_prepare("SELECT $1", &[Type::BYTEA], || async { Ok::<_, ()>(9) })
+            .await
+            .unwrap();
+
+        assert_eq!(cache.get("SELECT $1", &[Type::BYTEA]), Some(9));
+        assert_eq!(cache.get("SELECT $1", &[Type::TEXT]), None);
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_statement_is_evicted() {
+        let cache = StatementCache::new(2);
+        let prepared = AtomicUsize::new(0);
+
+        lookup(&cache, "a", &prepared).await;
+        lookup(&cache, "b", &prepared).await;
+        // Using "a" again makes "b" the least recently used.
+        lookup(&cache, "a", &prepared).await;
+        lookup(&cache, "c", &prepared).await;
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("a", &[Type::TEXT]).is_some());
+        assert!(cache.get("b", &[Type::TEXT]).is_none());
+        assert!(cache.get("c", &[Type::TEXT]).is_some());
+    }
+
+    #[tokio::test]
+    async fn invalidated_statement_is_prepared_again() {
+        let cache = StatementCache::new(4);
+        let prepared = AtomicUsize::new(0);
+
+        lookup(&cache, "SELECT 1", &prepared).await;
+        cache.remove("SELECT 1");
+        lookup(&cache, "SELECT 1", &prepared).await;
+
+        assert_eq!(prepared.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn zero_capacity_disables_caching() {
+        let cache = StatementCache::new(0);
+        let prepared = AtomicUsize::new(0);
+
+        lookup(&cache, "SELECT 1", &prepared).await;
+        lookup(&cache, "SELECT 1", &prepared).await;
+
+        assert_eq!(prepared.load(Ordering::Relaxed), 2);
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn plan_invalidation_errors_are_recognized() {
+        assert!(is_plan_invalidation(
+            &SqlState::FEATURE_NOT_SUPPORTED,
+            "cached plan must not change result type"
+        ));
+        assert!(is_plan_invalidation(
+            &SqlState::INVALID_SQL_STATEMENT_NAME,
+            "prepared statement \"s1\" does not exist"
+        ));
+        assert!(!is_plan_invalidation(
+            &SqlState::FEATURE_NOT_SUPPORTED,
+            "unsupported operator"
+        ));
+        assert!(!is_plan_invalidation(
+            &SqlState::QUERY_CANCELED,
+            "cached plan"
+        ));
+    }
+}
@@ -15,13 +15,15 @@ use tokio_postgres::Row;
 
 use crate::{
     auth::AuthState,
+    configuration::BackendTarget,
     context::{ConnectionContext, Cursor, RequestContext, ServiceContext},
     error::Result,
     explain::Verbosity,
     postgres::{
         conn_mgmt::{
-            run_request_with_retries, Connection, ConnectionPool, ConnectionSource, PoolConnection,
-            PullConnection, QueryOptions, RequestOptions,
+            client_application_name, run_request_with_retries, Connection, ConnectionPool,
+            ConnectionSource, OperationTimeout, PoolConnection, PullConnection, QueryOptions,
+            RequestOptions,
         },
         PgDocument,
     },
@@ -30,12 +32,17 @@ use crate::{
 
 #[async_trait]
 pub trait PgDataClient: Send + Sync {
-    /// Creates a new client authorized with the given [`AuthState`].
+    /// Creates a new client authorized with the given [`AuthState`], connected to the
+    /// backend serving `db`.
     ///
     /// # Errors
     /// Returns an error if the client cannot be constructed (e.g. missing
     /// connection pool for the authorized user).
-    fn new_authorized(service_context: &ServiceContext, authorization: &AuthState) -> Result<Self>
+    fn new_authorized(
+        service_context: &ServiceContext,
+        authorization: &AuthState,
+        db: Option<&str>,
+    ) -> Result<Self>
     where
         Self: Sized;
 
@@ -57,6 +64,11 @@ pub trait PgDataClient: Send + Sync {
         Ok(Connection::new(pool_connection, in_transaction))
     }
 
+    /// Returns the routed backend this client connects to, or `None` for the default backend.
+    fn backend(&self) -> Option<&BackendTarget> {
+        None
+    }
+
     /// Returns the underlying connection pool.
     ///
     /// # Errors
@@ -124,27 +136,31 @@ pub trait PgDataClient: Send + Sync {
         connection_context: &ConnectionContext,
     ) -> Result<Vec<Row>>;
 
+    /// Returns the backend's distinct response document as-is, so each entry in
+    /// `values` keeps its original BSON type.
     async fn execute_distinct_query(
         &self,
         request_context: &RequestContext<'_>,
         connection_context: &ConnectionContext,
     ) -> Result<Response>;
 
+    /// Returns whether the collection existed.
     async fn execute_drop_collection(
         &self,
         request_context: &RequestContext<'_>,
         db: &str,
         collection: &str,
         connection_context: &ConnectionContext,
-    ) -> Result<()>;
+    ) -> Result<bool>;
 
+    /// Returns whether the collection existed.
     async fn execute_drop_collection_when_readonly(
         &self,
         request_context: &RequestContext<'_>,
         db: &str,
         collection: &str,
         connection_context: &ConnectionContext,
-    ) -> Result<()>;
+    ) -> Result<bool>;
 
     async fn execute_drop_database(
         &self,
@@ -189,6 +205,14 @@ pub trait PgDataClient: Send + Sync {
         connection_context: &ConnectionContext,
     ) -> Result<Vec<Row>>;
 
+    /// Returns a row per valid index of the collection: its name, key, scans and the epoch
+    /// milliseconds the scans are counted from.
+    async fn execute_index_stats(
+        &self,
+        request_context: &RequestContext<'_>,
+        connection_context: &ConnectionContext,
+    ) -> Result<Vec<Row>>;
+
     async fn execute_insert(
         &self,
         request_context: &RequestContext<'_>,
@@ -442,18 +466,34 @@ pub trait PgDataClient: Send + Sync {
                 PullConnection::PoolOrTransaction => {
                     ConnectionSource::Pool(self.connection_pool()?)
                 }
+                PullConnection::Maintenance => {
+                    ConnectionSource::Maintenance(self.connection_pool()?)
+                }
             }
         };
 
-        let (_, request_info, request_tracker) = request_context.get_components();
-        let max_time_ms = request_info.max_time_ms;
+        let (request, request_info, request_tracker) = request_context.get_components();
+        let timeout = OperationTimeout::resolve(
+            request_info.max_time_ms,
+            self.service_context()
+                .dynamic_configuration()
+                .default_operation_timeout_ms_for(request.request_type().category()),
+        );
         let req_opts = self.request_options();
+        let client_identity = self
+            .service_context()
+            .dynamic_configuration()
+            .enable_client_application_name()
+            .then(|| {
+                client_application_name(connection_context.app_name(), request_context.activity_id)
+            });
 
         run_request_with_retries(
             source,
             query_options,
             req_opts,
-            max_time_ms,
+            timeout,
+            client_identity.as_deref(),
             request_tracker,
             run_func,
         )
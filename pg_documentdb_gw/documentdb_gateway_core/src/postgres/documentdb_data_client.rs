@@ -14,6 +14,7 @@ use tokio_postgres::{error::SqlState, types::Type, Row};
 
 use crate::{
     auth::AuthState,
+    configuration::BackendTarget,
     context::{ConnectionContext, Cursor, RequestContext, ServiceContext},
     error::{DocumentDBError, ErrorKind, Result},
     explain::Verbosity,
@@ -21,9 +22,30 @@ use crate::{
         conn_mgmt::{Connection, ConnectionPool, PoolConnection, PullConnection, QueryOptions},
         PgDataClient, PgDocument, ScopedTransaction,
     },
+    processor,
     responses::{PgResponse, Response},
 };
 
+/// Reads the result of `drop_collection`, which is false when the collection didn't exist.
+fn collection_dropped(rows: &[Row]) -> Result<bool> {
+    rows.first()
+        .map_or(Ok(false), |row| row.try_get::<_, bool>(0))
+        .map_err(DocumentDBError::from)
+}
+
+/// The options of an aggregate. One ending in `$out` or `$merge` writes, so after a connection
+/// reset it is left to the driver to retry rather than run again.
+///
+/// # Errors
+/// Returns an error if the pipeline is malformed.
+pub fn aggregate_query_options(command: &RawDocument) -> Result<QueryOptions> {
+    Ok(QueryOptions::builder()
+        .supports_backend_timeout(true)
+        .supports_transaction_timeout(false)
+        .write(processor::has_output_stage(command)?)
+        .build())
+}
+
 /// Remaps a `DocumentDBError::PostgresError` based of its `sql_state`, to a more meaningful and accurate `DocumentDBError`
 ///
 /// For example, if we get a `PostgresError` with `sql_state` of "42704" (undefined object),
@@ -48,6 +70,7 @@ pub fn remap_error(
 #[derive(Debug)]
 pub struct DocumentDBDataClient {
     connection_pool: Option<Arc<ConnectionPool>>,
+    backend: Option<BackendTarget>,
     service_context: ServiceContext,
 }
 
@@ -146,18 +169,30 @@ impl DocumentDBDataClient {
 
 #[async_trait]
 impl PgDataClient for DocumentDBDataClient {
-    fn new_authorized(service_context: &ServiceContext, authorization: &AuthState) -> Result<Self> {
+    fn new_authorized(
+        service_context: &ServiceContext,
+        authorization: &AuthState,
+        db: Option<&str>,
+    ) -> Result<Self> {
         let user = authorization.username()?;
         let dynamic_configuration = service_context.dynamic_configuration();
-
-        let connection_pool = Some(
+        let backend = db.and_then(|db| {
             service_context
-                .connection_pool_manager()
-                .get_data_pool(user, dynamic_configuration.as_ref())?,
-        );
+                .setup_configuration()
+                .backend_router()
+                .resolve(db)
+                .cloned()
+        });
+
+        let connection_pool = Some(service_context.connection_pool_manager().get_data_pool(
+            user,
+            backend.as_ref(),
+            dynamic_configuration.as_ref(),
+        )?);
 
         Ok(Self {
             connection_pool,
+            backend,
             service_context: service_context.clone(),
         })
     }
@@ -165,10 +200,15 @@ impl PgDataClient for DocumentDBDataClient {
     fn new_unauthorized(service_context: &ServiceContext) -> Result<Self> {
         Ok(Self {
             connection_pool: None,
+            backend: None,
             service_context: service_context.clone(),
         })
     }
 
+    fn backend(&self) -> Option<&BackendTarget> {
+        self.backend.as_ref()
+    }
+
     async fn acquire_pool_connection(&self) -> Result<PoolConnection> {
         self.connection_pool
             .as_ref()
@@ -203,10 +243,7 @@ impl PgDataClient for DocumentDBDataClient {
             self.service_context
                 .query_catalog()
                 .aggregate_cursor_first_page(),
-            QueryOptions::builder()
-                .supports_backend_timeout(true)
-                .supports_transaction_timeout(false)
-                .build(),
+            aggregate_query_options(request_context.payload.document())?,
         )
         .await
     }
@@ -274,6 +311,7 @@ impl PgDataClient for DocumentDBDataClient {
                 .create_collection_view(),
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
         )
         .await
@@ -294,6 +332,7 @@ impl PgDataClient for DocumentDBDataClient {
             QueryOptions::builder()
                 .supports_backend_timeout(false)
                 .supports_transaction_timeout(false)
+                .write(true)
                 .build(),
         )
         .await
@@ -355,6 +394,7 @@ impl PgDataClient for DocumentDBDataClient {
             PullConnection::PoolOrTransaction,
             QueryOptions::builder()
                 .supports_backend_timeout(true)
+                .write(true)
                 .build(),
             run_delete,
         )
@@ -395,6 +435,7 @@ impl PgDataClient for DocumentDBDataClient {
             PullConnection::PoolOrTransaction,
             QueryOptions::builder()
                 .supports_backend_timeout(true)
+                .write(true)
                 .build(),
             run_delete_readonly,
         )
@@ -423,7 +464,7 @@ impl PgDataClient for DocumentDBDataClient {
         db: &str,
         collection: &str,
         connection_context: &ConnectionContext,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         let run_drop_collection = |conn: Arc<Connection>| async move {
             conn.query(
                 self.service_context.query_catalog().drop_collection(),
@@ -433,18 +474,20 @@ impl PgDataClient for DocumentDBDataClient {
             .await
         };
 
-        self.run_query(
-            request_context,
-            connection_context,
-            PullConnection::PoolOrTransaction,
-            QueryOptions::builder()
-                .supports_backend_timeout(false)
-                .build(),
-            run_drop_collection,
-        )
-        .await?;
+        let rows = self
+            .run_query(
+                request_context,
+                connection_context,
+                PullConnection::PoolOrTransaction,
+                QueryOptions::builder()
+                    .supports_backend_timeout(false)
+                    .write(true)
+                    .build(),
+                run_drop_collection,
+            )
+            .await?;
 
-        Ok(())
+        collection_dropped(&rows)
     }
 
     async fn execute_drop_collection_when_readonly(
@@ -453,7 +496,7 @@ impl PgDataClient for DocumentDBDataClient {
         db: &str,
         collection: &str,
         connection_context: &ConnectionContext,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         let run_drop_collection_readonly = |conn: Arc<Connection>| async move {
             let mut txn = ScopedTransaction::start_if_necessary(&conn).await?;
             conn.batch_execute(self.service_context.query_catalog().set_allow_write())
@@ -471,18 +514,20 @@ impl PgDataClient for DocumentDBDataClient {
             rows
         };
 
-        self.run_query(
-            request_context,
-            connection_context,
-            PullConnection::PoolOrTransaction,
-            QueryOptions::builder()
-                .supports_backend_timeout(false)
-                .build(),
-            run_drop_collection_readonly,
-        )
-        .await?;
+        let rows = self
+            .run_query(
+                request_context,
+                connection_context,
+                PullConnection::PoolOrTransaction,
+                QueryOptions::builder()
+                    .supports_backend_timeout(false)
+                    .write(true)
+                    .build(),
+                run_drop_collection_readonly,
+            )
+            .await?;
 
-        Ok(())
+        collection_dropped(&rows)
     }
 
     async fn execute_drop_database(
@@ -506,6 +551,7 @@ impl PgDataClient for DocumentDBDataClient {
             PullConnection::PoolOrTransaction,
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
             run_drop_db,
         )
@@ -543,6 +589,7 @@ impl PgDataClient for DocumentDBDataClient {
             PullConnection::PoolOrTransaction,
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
             run_drop_db_readonly,
         )
@@ -661,6 +708,7 @@ impl PgDataClient for DocumentDBDataClient {
             self.service_context.query_catalog().find_and_modify(),
             QueryOptions::builder()
                 .supports_backend_timeout(true)
+                .write(true)
                 .build(),
         )
         .await
@@ -699,6 +747,37 @@ impl PgDataClient for DocumentDBDataClient {
         .await
     }
 
+    async fn execute_index_stats(
+        &self,
+        request_context: &RequestContext<'_>,
+        connection_context: &ConnectionContext,
+    ) -> Result<Vec<Row>> {
+        let request_info = request_context.info();
+
+        let db = request_info.db()?;
+        let coll = request_info.collection()?;
+
+        let run_index_stats = |conn: Arc<Connection>| async move {
+            conn.query(
+                self.service_context.query_catalog().index_stats(),
+                &[Type::TEXT, Type::TEXT],
+                &[&db, &coll],
+            )
+            .await
+        };
+
+        self.run_query(
+            request_context,
+            connection_context,
+            PullConnection::PoolOrTransaction,
+            QueryOptions::builder()
+                .supports_backend_timeout(true)
+                .build(),
+            run_index_stats,
+        )
+        .await
+    }
+
     async fn execute_insert(
         &self,
         request_context: &RequestContext<'_>,
@@ -723,7 +802,9 @@ impl PgDataClient for DocumentDBDataClient {
         let doc = request.document();
         let extra = request.extra();
 
-        let mut query_options_builder = QueryOptions::builder().supports_backend_timeout(true);
+        let mut query_options_builder = QueryOptions::builder()
+            .supports_backend_timeout(true)
+            .write(true);
         if is_batch_commit {
             query_options_builder = query_options_builder.retry_request(false);
         }
@@ -841,7 +922,8 @@ impl PgDataClient for DocumentDBDataClient {
         let mut query_str: &str = self.service_context.query_catalog().process_update();
         let mut query_options_builder = QueryOptions::builder()
             .supports_backend_timeout(true)
-            .retry_deadlock(true);
+            .retry_deadlock(true)
+            .write(true);
 
         if is_batch_commit {
             query_str = self.service_context.query_catalog().update_bulk();
@@ -903,6 +985,7 @@ impl PgDataClient for DocumentDBDataClient {
                 self.service_context.query_catalog().drop_indexes(),
                 QueryOptions::builder()
                     .supports_backend_timeout(false)
+                    .write(true)
                     .build(),
             )
             .await?;
@@ -933,6 +1016,7 @@ impl PgDataClient for DocumentDBDataClient {
             PullConnection::PoolOrTransaction,
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
             run_shard,
         )
@@ -970,6 +1054,7 @@ impl PgDataClient for DocumentDBDataClient {
             QueryOptions::builder()
                 .supports_backend_timeout(false)
                 .supports_transaction_timeout(false)
+                .write(true)
                 .build(),
             run_reindex,
         )
@@ -1038,6 +1123,7 @@ impl PgDataClient for DocumentDBDataClient {
             PullConnection::PoolOrTransaction,
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
             run_coll_mod,
         )
@@ -1132,6 +1218,7 @@ impl PgDataClient for DocumentDBDataClient {
             PullConnection::PoolOrTransaction,
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
             run_rename,
         )
@@ -1149,6 +1236,7 @@ impl PgDataClient for DocumentDBDataClient {
             self.service_context.query_catalog().create_user(),
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
         )
         .await
@@ -1173,6 +1261,7 @@ impl PgDataClient for DocumentDBDataClient {
             self.service_context.query_catalog().drop_user(),
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
         )
         .await
@@ -1197,6 +1286,7 @@ impl PgDataClient for DocumentDBDataClient {
             self.service_context.query_catalog().update_user(),
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
         )
         .await
@@ -1241,6 +1331,7 @@ impl PgDataClient for DocumentDBDataClient {
             self.service_context.query_catalog().unshard_collection(),
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
         )
         .await?;
@@ -1308,13 +1399,27 @@ impl PgDataClient for DocumentDBDataClient {
         request_context: &RequestContext<'_>,
         connection_context: &ConnectionContext,
     ) -> Result<Response> {
-        self.run_doc_only(
+        let doc = request_context.payload().document();
+        let query = self.service_context.query_catalog().compact();
+
+        let run_compact = |conn: Arc<Connection>| async move {
+            let rows = conn
+                .query(query, &[Type::BYTEA], &[&PgDocument(doc)])
+                .await?;
+            Ok(Response::Pg(PgResponse::new(rows)))
+        };
+
+        // Compaction can take a long time, so it runs on the maintenance
+        // connection rather than one serving regular requests.
+        self.run_query(
             request_context,
             connection_context,
-            self.service_context.query_catalog().compact(),
+            PullConnection::Maintenance,
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
+            run_compact,
         )
         .await
     }
@@ -1360,6 +1465,7 @@ impl PgDataClient for DocumentDBDataClient {
             self.service_context.query_catalog().create_role(),
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
         )
         .await
@@ -1384,6 +1490,7 @@ impl PgDataClient for DocumentDBDataClient {
             self.service_context.query_catalog().update_role(),
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
         )
         .await
@@ -1408,6 +1515,7 @@ impl PgDataClient for DocumentDBDataClient {
             self.service_context.query_catalog().drop_role(),
             QueryOptions::builder()
                 .supports_backend_timeout(false)
+                .write(true)
                 .build(),
         )
         .await
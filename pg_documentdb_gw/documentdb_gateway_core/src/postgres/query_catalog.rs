@@ -47,6 +47,7 @@ pub struct QueryCatalog {
 
     // client.rs
     pub set_search_path_and_timeout: String,
+    pub enable_collation: String,
 
     // cursor.rs
     pub cursor_get_more: String,
@@ -91,12 +92,17 @@ pub struct QueryCatalog {
     pub balancer_stop: String,
     pub move_collection: String,
 
+    // write_concern.rs
+    pub wal_insert_lsn: String,
+    pub write_concern_progress: String,
+
     // indexing.rs
     pub create_indexes_background: String,
     pub check_build_index_status: String,
     pub re_index: String,
     pub drop_indexes: String,
     pub list_indexes_cursor_first_page: String,
+    pub index_stats: String,
 
     // user.rs
     pub create_user: String,
@@ -259,6 +265,11 @@ impl QueryCatalog {
             .replace("{transaction_timeout}", transaction_timeout)
     }
 
+    #[must_use]
+    pub fn enable_collation(&self) -> &str {
+        &self.enable_collation
+    }
+
     // Cursor getters
     #[must_use]
     pub fn cursor_get_more(&self) -> &str {
@@ -312,6 +323,11 @@ impl QueryCatalog {
         &self.list_indexes_cursor_first_page
     }
 
+    #[must_use]
+    pub fn index_stats(&self) -> &str {
+        &self.index_stats
+    }
+
     // Process getters
     #[must_use]
     pub fn find_cursor_first_page(&self) -> &str {
@@ -531,6 +547,17 @@ impl QueryCatalog {
     pub fn balancer_stop(&self) -> &str {
         &self.balancer_stop
     }
+
+    // Write concern getters
+    #[must_use]
+    pub fn wal_insert_lsn(&self) -> &str {
+        &self.wal_insert_lsn
+    }
+
+    #[must_use]
+    pub fn write_concern_progress(&self) -> &str {
+        &self.write_concern_progress
+    }
 }
 
 #[must_use]
@@ -571,6 +598,7 @@ pub fn create_query_catalog() -> QueryCatalog {
 
             // client.rs
             set_search_path_and_timeout: "-c search_path=documentdb_api_catalog,documentdb_api,public -c statement_timeout={timeout} -c idle_in_transaction_session_timeout={transaction_timeout}".to_owned(),
+            enable_collation: " -c documentdb_core.enableCollation=on".to_owned(),
 
             // data_description.rs
             drop_database: "SELECT documentdb_api.drop_database($1)".to_owned(),
@@ -613,12 +641,30 @@ pub fn create_query_catalog() -> QueryCatalog {
             compact: "SELECT documentdb_api.compact($1)".to_owned(),
             kill_op: "SELECT documentdb_api.kill_op($1)".to_owned(),
 
+            // write_concern.rs
+            wal_insert_lsn: "SELECT pg_current_wal_insert_lsn()::text".to_owned(),
+            write_concern_progress: "SELECT pg_current_wal_flush_lsn() >= $1::pg_lsn, count(*) FILTER (WHERE flush_lsn >= $1::pg_lsn), count(*) FROM pg_stat_replication WHERE state = 'streaming'".to_owned(),
+
             // indexing.rs
             create_indexes_background: "SELECT * FROM documentdb_api.create_indexes_background($1, $2)".to_owned(),
             check_build_index_status: "SELECT * FROM documentdb_api_internal.check_build_index_status($1)".to_owned(),
             re_index: "CALL documentdb_api.re_index($1, $2)".to_owned(),
             drop_indexes: "CALL documentdb_api.drop_indexes($1, $2)".to_owned(),
             list_indexes_cursor_first_page: "SELECT cursorPage, continuation, persistConnection, cursorId FROM documentdb_api.list_indexes_cursor_first_page($1, $2)".to_owned(),
+            // The _id index is the collection's primary key; the others are named by index_id.
+            // Scans are counted since the database's statistics were last reset, or else since
+            // the server started.
+            index_stats: "SELECT (i.index_spec).index_name, (i.index_spec).index_key, COALESCE(s.idx_scan, 0)::int8,
+                                 (EXTRACT(EPOCH FROM COALESCE(d.stats_reset, pg_postmaster_start_time())) * 1000)::int8
+                          FROM documentdb_api_catalog.collections c
+                          JOIN documentdb_api_catalog.collection_indexes i ON i.collection_id = c.collection_id
+                          LEFT JOIN pg_stat_all_indexes s ON s.schemaname = 'documentdb_data'
+                              AND s.indexrelname = CASE WHEN (i.index_spec).index_name = '_id_'
+                                                        THEN 'collection_pk_' || c.collection_id
+                                                        ELSE 'documents_rum_index_' || i.index_id END
+                          LEFT JOIN pg_stat_database d ON d.datname = current_database()
+                          WHERE c.database_name = $1 AND c.collection_name = $2 AND i.index_is_valid
+                          ORDER BY i.index_id".to_owned(),
 
             // user.rs
             create_user: "SELECT documentdb_api.create_user($1)".to_owned(),
@@ -0,0 +1,128 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/processor/conn_pool_stats.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::{rawdoc, RawArrayBuf, RawDocumentBuf};
+
+use crate::{
+    context::{ConnectionContext, RequestContext},
+    error::{DocumentDBError, ErrorCode, Result},
+    postgres::conn_mgmt::ConnectionPoolStatus,
+    protocol::OK_SUCCEEDED,
+    responses::{RawResponse, Response},
+};
+
+fn count(value: usize) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+/// Reports each pool with gateway-wide totals. A pool's `created` connections are the ones
+/// it currently holds, either `available` or `inUse`.
+fn conn_pool_stats_document(pools: &[ConnectionPoolStatus]) -> RawDocumentBuf {
+    let mut entries = RawArrayBuf::new();
+    let (mut total_available, mut total_in_use, mut total_created, mut total_waiting) =
+        (0, 0, 0, 0);
+    for pool in pools {
+        let status = pool.status();
+        let in_use = status.size.saturating_sub(status.available);
+        entries.push(rawdoc! {
+            "name": pool.identifier(),
+            "available": count(status.available),
+            "inUse": count(in_use),
+            "created": count(status.size),
+            "waiting": count(status.waiting),
+            "maxPoolSize": count(status.max_size),
+        });
+        total_available += status.available;
+        total_in_use += in_use;
+        total_created += status.size;
+        total_waiting += status.waiting;
+    }
+    rawdoc! {
+        "totalAvailable": count(total_available),
+        "totalInUse": count(total_in_use),
+        "totalCreated": count(total_created),
+        "totalWaiting": count(total_waiting),
+        "pools": entries,
+        "ok": OK_SUCCEEDED,
+    }
+}
+
+/// Reports the state of the backend connection pools: the system and pre-authentication
+/// pools, then the data pools, from the same snapshot as `PoolManager::report_pool_stats`.
+pub fn process_conn_pool_stats(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+) -> Result<Response> {
+    if request_context.info.db()? != "admin" {
+        return Err(DocumentDBError::documentdb_error(
+            ErrorCode::Unauthorized,
+            "connPoolStats may only be run against the admin database.".to_owned(),
+        ));
+    }
+
+    let pools = connection_context
+        .service_context
+        .connection_pool_manager()
+        .report_pool_stats();
+    Ok(Response::Raw(RawResponse(conn_pool_stats_document(&pools))))
+}
+
+#[cfg(test)]
+mod tests {
+    use deadpool_postgres::Status;
+
+    use super::*;
+
+    fn pool(name: &str, size: usize, available: usize, waiting: usize) -> ConnectionPoolStatus {
+        ConnectionPoolStatus::new(
+            name.to_owned(),
+            Status {
+                max_size: 10,
+                size,
+                available,
+                waiting,
+            },
+        )
+    }
+
+    #[test]
+    fn every_pool_has_an_entry() {
+        let document = conn_pool_stats_document(&[
+            pool("app-PreAuthRequests", 1, 1, 0),
+            pool("app-SystemRequests", 2, 0, 3),
+            pool("app-user", 4, 1, 0),
+        ]);
+
+        let pools = document.get_array("pools").unwrap();
+        let names: Vec<&str> = pools
+            .into_iter()
+            .map(|entry| {
+                entry
+                    .unwrap()
+                    .as_document()
+                    .unwrap()
+                    .get_str("name")
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            names,
+            ["app-PreAuthRequests", "app-SystemRequests", "app-user"]
+        );
+
+        let system = pools.get_document(1).unwrap();
+        assert_eq!(system.get_i64("inUse").unwrap(), 2);
+        assert_eq!(system.get_i64("waiting").unwrap(), 3);
+        assert_eq!(system.get_i64("maxPoolSize").unwrap(), 10);
+
+        assert_eq!(document.get_i64("totalAvailable").unwrap(), 2);
+        assert_eq!(document.get_i64("totalInUse").unwrap(), 5);
+        assert_eq!(document.get_i64("totalCreated").unwrap(), 7);
+        assert_eq!(document.get_i64("totalWaiting").unwrap(), 3);
+    }
+}
@@ -6,18 +6,16 @@
  *-------------------------------------------------------------------------
  */
 
-use std::{
-    sync::Arc,
-    time::{SystemTime, UNIX_EPOCH},
-};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use bson::{rawdoc, RawDocumentBuf};
 
 use crate::{
-    configuration::DynamicConfiguration,
+    configuration::{DynamicConfiguration, ServerDescription, SetupConfiguration},
     context::{ConnectionContext, RequestContext},
     error::{DocumentDBError, ErrorCode, Result},
-    protocol::{self, OK_SUCCEEDED},
+    protocol::OK_SUCCEEDED,
+    requests::RequestType,
     responses::{RawResponse, Response},
 };
 
@@ -27,17 +25,36 @@ pub fn ok_response() -> Response {
     }))
 }
 
-pub fn process_build_info(dynamic_config: &Arc<dyn DynamicConfiguration>) -> Response {
-    let version = dynamic_config.server_version();
+pub fn process_build_info(
+    setup_config: &dyn SetupConfiguration,
+    dynamic_config: &dyn DynamicConfiguration,
+) -> Response {
+    let description = ServerDescription::advertised(setup_config, dynamic_config);
     Response::Raw(RawResponse(rawdoc! {
-        "version": version.as_str(),
-        "versionArray": version.as_bson_array(),
+        "version": description.version(),
+        "versionArray": description.version_bson_array(),
         "bits": 64,
-        "maxBsonObjectSize": protocol::MAX_BSON_OBJECT_SIZE,
+        "maxBsonObjectSize": setup_config.max_bson_object_size(),
         "ok":OK_SUCCEEDED,
     }))
 }
 
+/// Replies to the stateless gateway-only commands.
+pub fn process_gateway_static(
+    request_type: RequestType,
+    setup_config: &dyn SetupConfiguration,
+    dynamic_config: &dyn DynamicConfiguration,
+) -> Result<Response> {
+    match request_type {
+        RequestType::Ping => Ok(ok_response()),
+        RequestType::BuildInfo => Ok(process_build_info(setup_config, dynamic_config)),
+        other => Err(DocumentDBError::internal_error(format!(
+            "Command '{}' can't be answered by the gateway alone.",
+            other.to_command_str()
+        ))),
+    }
+}
+
 pub fn process_get_cmd_line_opts() -> Response {
     Response::Raw(RawResponse(rawdoc! {
         "argv": [],
@@ -160,7 +177,7 @@ struct CommandInfo {
     secondary_override_ok: Option<bool>,
 }
 
-static SUPPORTED_COMMANDS : [CommandInfo; 62] = [
+static SUPPORTED_COMMANDS : [CommandInfo; 63] = [
 	CommandInfo {
 		command_name: "abortTransaction",
 		admin_only: true,
@@ -561,6 +578,14 @@ static SUPPORTED_COMMANDS : [CommandInfo; 62] = [
 		requires_auth: false,
 		secondary_override_ok: None,
 	},
+	CommandInfo {
+		command_name: "refreshSessions",
+		admin_only: false,
+		help: "Keep multiple sessions from expiring.",
+		secondary_ok: false,
+		requires_auth: true,
+		secondary_override_ok: None,
+	},
 	CommandInfo {
 		command_name: "reIndex",
 		admin_only: false,
@@ -681,3 +706,119 @@ pub fn list_commands() -> Response {
         "ok": OK_SUCCEEDED,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use bson::RawBson;
+
+    use super::*;
+    use crate::configuration::{DocumentDBSetupConfiguration, ServerDescriptionOptions};
+
+    /// Fails the test if a reply reads any configuration.
+    #[derive(Debug)]
+    struct UnreachableConfig;
+
+    impl DynamicConfiguration for UnreachableConfig {
+        fn get_str(&self, _: &str) -> Option<String> {
+            unreachable!()
+        }
+
+        fn get_bool(&self, _: &str, _: bool) -> bool {
+            unreachable!()
+        }
+
+        fn get_i32(&self, _: &str, _: i32) -> i32 {
+            unreachable!()
+        }
+
+        fn get_u64(&self, _: &str, _: u64) -> u64 {
+            unreachable!()
+        }
+
+        fn equals_value(&self, _: &str, _: &str) -> bool {
+            unreachable!()
+        }
+
+        fn topology(&self) -> RawBson {
+            unreachable!()
+        }
+
+        fn enable_developer_explain(&self) -> bool {
+            unreachable!()
+        }
+
+        fn max_connections(&self) -> usize {
+            unreachable!()
+        }
+
+        fn allow_transaction_snapshot(&self) -> bool {
+            unreachable!()
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    #[test]
+    fn ping_is_answered_without_backend() {
+        assert!(RequestType::Ping.gateway_only());
+
+        let response = process_gateway_static(
+            RequestType::Ping,
+            &DocumentDBSetupConfiguration::default(),
+            &UnreachableConfig,
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.as_raw_document().unwrap(),
+            rawdoc! { "ok": OK_SUCCEEDED }.as_ref()
+        );
+    }
+
+    #[test]
+    fn build_info_reports_configured_version() {
+        let setup_configuration = DocumentDBSetupConfiguration {
+            advertised_server_description: Some(
+                ServerDescription::new(&ServerDescriptionOptions {
+                    version: "6.0.14".to_owned(),
+                    ..ServerDescriptionOptions::default()
+                })
+                .unwrap(),
+            ),
+            ..DocumentDBSetupConfiguration::default()
+        };
+
+        let response = process_gateway_static(
+            RequestType::BuildInfo,
+            &setup_configuration,
+            &UnreachableConfig,
+        )
+        .unwrap();
+        let build_info = response.as_raw_document().unwrap();
+
+        assert_eq!(build_info.get_str("version").unwrap(), "6.0.14");
+        let version_array: Vec<i32> = build_info
+            .get_array("versionArray")
+            .unwrap()
+            .into_iter()
+            .map(|v| v.unwrap().as_i32().unwrap())
+            .collect();
+        assert_eq!(version_array, [6, 0, 14, 0]);
+    }
+
+    #[test]
+    fn only_heartbeat_commands_are_gateway_only() {
+        for request_type in [
+            RequestType::Ping,
+            RequestType::Hello,
+            RequestType::IsMaster,
+            RequestType::BuildInfo,
+        ] {
+            assert!(request_type.gateway_only(), "{request_type:?}");
+        }
+        assert!(!RequestType::Find.gateway_only());
+        assert!(!RequestType::ServerStatus.gateway_only());
+    }
+}
@@ -0,0 +1,365 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/processor/current_op.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::cmp::Ordering;
+
+use bson::{rawdoc, RawArrayBuf, RawBsonRef, RawDocument, RawDocumentBuf};
+use uuid::Uuid;
+
+use crate::{
+    bson::{convert_to_bool, convert_to_f64},
+    context::{ConnectionContext, OperationSnapshot, RequestContext},
+    error::{DocumentDBError, Result},
+    postgres::PgDataClient,
+    processor::data_description::GENERIC_COMMAND_FIELDS,
+    responses::{RawResponse, Response},
+};
+
+/// Top-level currentOp fields that are options rather than filters.
+const CURRENT_OP_OPTIONS: [&str; 3] = ["currentOp", "$all", "$ownOps"];
+
+/// The options of a currentOp command.
+#[derive(Debug, Default)]
+struct CurrentOpOptions {
+    /// Set by `$all`, includes idle connections.
+    all: bool,
+    /// The remaining top-level fields, matched against each op entry.
+    filter: RawDocumentBuf,
+}
+
+fn parse_current_op(command: &RawDocument) -> Result<CurrentOpOptions> {
+    let mut options = CurrentOpOptions::default();
+    for entry in command {
+        let (key, value) = entry?;
+        if key == "$all" {
+            options.all = convert_to_bool(value).ok_or(DocumentDBError::type_mismatch(
+                "$all must be a boolean".to_owned(),
+            ))?;
+        } else if !CURRENT_OP_OPTIONS.contains(&key) && !GENERIC_COMMAND_FIELDS.contains(&key) {
+            options.filter.append(key, value.to_raw_bson());
+        }
+    }
+    Ok(options)
+}
+
+/// Reports backend activity merged with the requests in flight on this gateway.
+///
+/// The backend applies the filter to its own entries; gateway entries are filtered here.
+pub async fn process_current_op(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+    pg_data_client: &impl PgDataClient,
+) -> Result<Response> {
+    let options = parse_current_op(request_context.payload.document())?;
+
+    let response = pg_data_client
+        .execute_current_op(request_context, connection_context)
+        .await?;
+
+    let registry = connection_context.service_context.operation_registry();
+    let mut gateway_ops: Vec<RawDocumentBuf> = registry
+        .operations()
+        .iter()
+        .map(gateway_operation)
+        .collect();
+    if options.all {
+        gateway_ops.extend(
+            registry
+                .idle_connections()
+                .into_iter()
+                .map(|(connection_id, client)| idle_connection(connection_id, &client)),
+        );
+    }
+
+    let mut matching = Vec::with_capacity(gateway_ops.len());
+    for op in gateway_ops {
+        if matches_filter(&op, &options.filter)? {
+            matching.push(op);
+        }
+    }
+
+    merge_inprog(response.as_raw_document()?, matching)
+}
+
+fn connection_desc(connection_id: Uuid) -> String {
+    format!("conn{connection_id}")
+}
+
+fn gateway_operation(operation: &OperationSnapshot) -> RawDocumentBuf {
+    let mut op = rawdoc! {
+        "shard": "gateway",
+        "active": true,
+        "type": "op",
+        "opid": operation.op_id,
+        "op": operation.info.op,
+        "ns": operation.info.ns.as_str(),
+        "secs_running": i64::try_from(operation.running.as_secs()).unwrap_or(i64::MAX),
+        "microsecs_running": i64::try_from(operation.running.as_micros()).unwrap_or(i64::MAX),
+        "client": operation.info.client.as_str(),
+        "desc": operation
+            .info
+            .desc
+            .clone()
+            .unwrap_or_else(|| connection_desc(operation.info.connection_id)),
+    };
+    if let Some(comment) = &operation.info.comment {
+        op.append("command", rawdoc! { "comment": comment.as_str() });
+    }
+    op
+}
+
+fn idle_connection(connection_id: Uuid, client: &str) -> RawDocumentBuf {
+    rawdoc! {
+        "shard": "gateway",
+        "active": false,
+        "type": "op",
+        "client": client,
+        "desc": connection_desc(connection_id),
+    }
+}
+
+/// Fills in `microsecs_running` on backend entries, which only report whole seconds.
+fn enrich_backend_operation(op: &RawDocument) -> Result<RawDocumentBuf> {
+    let mut enriched = op.to_raw_document_buf();
+    if op.get("microsecs_running")?.is_none() {
+        if let Some(secs) = op.get("secs_running")?.and_then(RawBsonRef::as_i64) {
+            enriched.append("microsecs_running", secs.saturating_mul(1_000_000));
+        }
+    }
+    Ok(enriched)
+}
+
+fn merge_inprog(backend: &RawDocument, gateway_ops: Vec<RawDocumentBuf>) -> Result<Response> {
+    let mut merged = RawDocumentBuf::new();
+    let mut gateway_ops = Some(gateway_ops);
+    for entry in backend {
+        let (key, value) = entry?;
+        if key == "inprog" {
+            let mut inprog = RawArrayBuf::new();
+            for op in value.as_array().into_iter().flatten() {
+                match op? {
+                    RawBsonRef::Document(op) => inprog.push(enrich_backend_operation(op)?),
+                    other => inprog.push(other.to_raw_bson()),
+                }
+            }
+            for op in gateway_ops.take().into_iter().flatten() {
+                inprog.push(op);
+            }
+            merged.append(key, inprog);
+        } else {
+            merged.append(key, value.to_raw_bson());
+        }
+    }
+    Ok(Response::Raw(RawResponse(merged)))
+}
+
+/// Matches a currentOp entry against a filter document.
+///
+/// Supports equality and the comparison operators; the backend evaluates the full
+/// query language for its own entries, so gateway entries never match other operators.
+fn matches_filter(op: &RawDocument, filter: &RawDocument) -> Result<bool> {
+    for entry in filter {
+        let (field, condition) = entry?;
+        let value = get_path(op, field)?;
+        let matched = match condition {
+            RawBsonRef::Document(operators)
+                if operators
+                    .into_iter()
+                    .next()
+                    .transpose()?
+                    .is_some_and(|(k, _)| k.starts_with('$')) =>
+            {
+                let mut all = true;
+                for operator in operators {
+                    let (operator, operand) = operator?;
+                    all &= matches_operator(value, operator, operand);
+                }
+                all
+            }
+            _ => value.and_then(|v| compare(v, condition)) == Some(Ordering::Equal),
+        };
+        if !matched {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Looks up a field by its dotted path, such as `command.comment`.
+fn get_path<'a>(op: &'a RawDocument, path: &str) -> Result<Option<RawBsonRef<'a>>> {
+    let mut document = op;
+    let mut fields = path.split('.').peekable();
+    while let Some(field) = fields.next() {
+        let value = document.get(field)?;
+        if fields.peek().is_none() {
+            return Ok(value);
+        }
+        match value {
+            Some(RawBsonRef::Document(nested)) => document = nested,
+            _ => return Ok(None),
+        }
+    }
+    Ok(None)
+}
+
+fn matches_operator(
+    value: Option<RawBsonRef<'_>>,
+    operator: &str,
+    operand: RawBsonRef<'_>,
+) -> bool {
+    let ordering = value.and_then(|v| compare(v, operand));
+    match operator {
+        "$exists" => value.is_some() == convert_to_bool(operand).unwrap_or(false),
+        "$eq" => ordering == Some(Ordering::Equal),
+        "$ne" => ordering != Some(Ordering::Equal),
+        "$gt" => ordering == Some(Ordering::Greater),
+        "$gte" => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        "$lt" => ordering == Some(Ordering::Less),
+        "$lte" => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        _ => false,
+    }
+}
+
+fn compare(left: RawBsonRef<'_>, right: RawBsonRef<'_>) -> Option<Ordering> {
+    if let (Some(left), Some(right)) = (convert_to_f64(left), convert_to_f64(right)) {
+        return left.partial_cmp(&right);
+    }
+    match (left, right) {
+        (RawBsonRef::String(left), RawBsonRef::String(right)) => Some(left.cmp(right)),
+        (RawBsonRef::Boolean(left), RawBsonRef::Boolean(right)) => Some(left.cmp(&right)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bson::rawdoc;
+
+    use super::*;
+    use crate::{context::OperationInfo, requests::RequestType};
+
+    fn operation(op_id: i64, running: Duration) -> RawDocumentBuf {
+        gateway_operation(&OperationSnapshot {
+            op_id,
+            info: OperationInfo::new(
+                RequestType::Find,
+                "db.coll".to_owned(),
+                Uuid::nil(),
+                "127.0.0.1".to_owned(),
+            ),
+            running,
+        })
+    }
+
+    fn filter(command: &RawDocument) -> RawDocumentBuf {
+        parse_current_op(command).unwrap().filter
+    }
+
+    #[test]
+    fn secs_running_filter_excludes_short_running_ops() {
+        let short = operation(1, Duration::from_millis(200));
+        let long = operation(2, Duration::from_secs(7));
+        let filter = filter(&rawdoc! {
+            "currentOp": 1,
+            "$all": true,
+            "$db": "admin",
+            "secs_running": { "$gte": 5 },
+        });
+
+        assert!(!matches_filter(&short, &filter).unwrap());
+        assert!(matches_filter(&long, &filter).unwrap());
+        assert_eq!(long.get_i64("microsecs_running").unwrap(), 7_000_000);
+    }
+
+    #[test]
+    fn standard_filters_match_gateway_ops() {
+        let op = operation(1, Duration::from_secs(1));
+        for (condition, expected) in [
+            (rawdoc! { "active": true }, true),
+            (rawdoc! { "active": false }, false),
+            (rawdoc! { "ns": "db.coll" }, true),
+            (rawdoc! { "ns": "db.other" }, false),
+            (rawdoc! { "op": "query", "ns": { "$ne": "db.other" } }, true),
+            (rawdoc! { "op": { "$eq": "insert" } }, false),
+            (rawdoc! { "waitingForLock": true }, false),
+            (rawdoc! { "ns": { "$regex": "^db" } }, false),
+        ] {
+            assert_eq!(
+                matches_filter(&op, &condition).unwrap(),
+                expected,
+                "{condition:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn gateway_ops_are_appended_to_backend_inprog() {
+        let backend = rawdoc! {
+            "inprog": [{ "shard": "defaultShard", "opid": "10000000001:12", "secs_running": 3_i64 }],
+            "ok": 1.0,
+        };
+        let response = merge_inprog(&backend, vec![operation(1, Duration::ZERO)]).unwrap();
+        let response = response.as_raw_document().unwrap();
+        let inprog: Vec<_> = response
+            .get_array("inprog")
+            .unwrap()
+            .into_iter()
+            .map(|op| op.unwrap().as_document().unwrap().to_raw_document_buf())
+            .collect();
+
+        assert_eq!(inprog.len(), 2);
+        assert_eq!(inprog[0].get_i64("microsecs_running").unwrap(), 3_000_000);
+        assert_eq!(inprog[1].get_str("shard").unwrap(), "gateway");
+        assert!(response.get("ok").unwrap().is_some());
+    }
+
+    #[test]
+    fn plain_string_comment_is_reported_and_filterable() {
+        let op = gateway_operation(&OperationSnapshot {
+            op_id: 1,
+            info: OperationInfo {
+                comment: Some("nightly report".to_owned()),
+                ..OperationInfo::new(
+                    RequestType::Find,
+                    "db.coll".to_owned(),
+                    Uuid::nil(),
+                    "127.0.0.1".to_owned(),
+                )
+            },
+            running: Duration::ZERO,
+        });
+
+        assert_eq!(
+            op.get_document("command")
+                .unwrap()
+                .get_str("comment")
+                .unwrap(),
+            "nightly report"
+        );
+        assert!(matches_filter(&op, &rawdoc! { "command.comment": "nightly report" }).unwrap());
+        assert!(!matches_filter(&op, &rawdoc! { "command.comment": "other" }).unwrap());
+        assert!(!matches_filter(
+            &operation(2, Duration::ZERO),
+            &rawdoc! { "command.comment": "nightly report" }
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn all_option_is_parsed() {
+        assert!(
+            parse_current_op(&rawdoc! { "currentOp": 1, "$all": true })
+                .unwrap()
+                .all
+        );
+        assert!(!parse_current_op(&rawdoc! { "currentOp": 1 }).unwrap().all);
+        parse_current_op(&rawdoc! { "currentOp": 1, "$all": "yes" }).unwrap_err();
+    }
+}
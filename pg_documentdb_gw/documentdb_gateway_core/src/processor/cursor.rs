@@ -8,13 +8,16 @@
 
 use std::{sync::Arc, time::Duration};
 
-use bson::{rawdoc, RawArrayBuf};
+use bson::{rawdoc, RawArrayBuf, RawBsonRef, RawDocument, RawDocumentBuf};
 
 use crate::{
+    bson::convert_to_f64,
     context::{ConnectionContext, Cursor, CursorId, CursorStoreEntry, RequestContext},
     error::{DocumentDBError, ErrorCode, Result},
     postgres::{conn_mgmt::PullConnection, PgDataClient, PgDocument},
+    processor::tailable,
     protocol::OK_SUCCEEDED,
+    requests::{Request, RequestType},
     responses::{PgResponse, RawResponse, Response},
 };
 
@@ -78,25 +81,68 @@ pub async fn process_kill_cursors(
     })))
 }
 
-pub async fn process_get_more(
-    request_context: &RequestContext<'_>,
-    connection_context: &ConnectionContext,
-    pg_data_client: &impl PgDataClient,
-) -> Result<Response> {
-    let request = request_context.payload;
-
+/// Reads the cursor id and collection name of a getMore request.
+fn parse_get_more(request: &Request<'_>) -> Result<(i64, String)> {
     let mut id = None;
+    let mut requested_collection = None;
     request.extract_fields(|k, v| {
-        if k == "getMore" {
-            id = Some(v.as_i64().ok_or(DocumentDBError::bad_value(
-                "getMore value should be an i64".to_owned(),
-            ))?);
+        match k {
+            "getMore" => {
+                id = Some(v.as_i64().ok_or(DocumentDBError::bad_value(
+                    "getMore value should be an i64".to_owned(),
+                ))?);
+            }
+            "collection" => {
+                requested_collection = Some(
+                    v.as_str()
+                        .ok_or(DocumentDBError::documentdb_error(
+                            ErrorCode::TypeMismatch,
+                            "getMore collection should be a string".to_owned(),
+                        ))?
+                        .to_owned(),
+                );
+            }
+            _ => {}
         }
         Ok(())
     })?;
     let id = id.ok_or(DocumentDBError::bad_value(
         "getMore not present in document".to_owned(),
     ))?;
+    let requested_collection = requested_collection.ok_or(DocumentDBError::documentdb_error(
+        ErrorCode::FailedToParse,
+        "BSON field 'getMore.collection' is missing but a required field".to_owned(),
+    ))?;
+    Ok((id, requested_collection))
+}
+
+#[expect(
+    clippy::too_many_lines,
+    reason = "getMore dispatches tailable, bounded and plain cursors"
+)]
+pub async fn process_get_more(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+    pg_data_client: &impl PgDataClient,
+) -> Result<Response> {
+    let defaulted_get_more = with_default_batch_size(
+        request_context.payload.document(),
+        RequestType::GetMore,
+        connection_context
+            .service_context
+            .dynamic_configuration()
+            .default_get_more_batch_size(),
+    )?;
+    let defaulted_request = defaulted_get_more
+        .as_deref()
+        .map(|document| request_context.rewritten_payload(document));
+    let request_context = &request_context.with_payload(defaulted_request.as_ref());
+    let request = request_context.payload;
+
+    let (id, requested_collection) = parse_get_more(request)?;
+    let requested_db = request_context.info.db()?;
+
+    let username = connection_context.auth_state.username()?;
     let CursorStoreEntry {
         conn: cursor_connection,
         cursor,
@@ -106,15 +152,51 @@ pub async fn process_get_more(
         mut cursor_timeout,
         ..
     } = connection_context
-        .get_cursor(id, connection_context.auth_state.username()?)
+        .get_cursor(id, username)
         .ok_or(DocumentDBError::documentdb_error(
             ErrorCode::CursorNotFound,
             "Provided cursor was not found.".to_owned(),
         ))?;
 
+    if let Err(e) =
+        validate_get_more_namespace(requested_db, &requested_collection, &db, &collection)
+    {
+        // The cursor stays usable from its own namespace.
+        connection_context.add_cursor(
+            cursor_connection,
+            cursor,
+            username,
+            &db,
+            &collection,
+            cursor_timeout,
+            session_id,
+        );
+        return Err(e);
+    }
+
+    if let Some(tailable) = cursor.tailable {
+        return tailable::process_tailable_get_more(
+            request_context,
+            connection_context,
+            cursor.cursor_id,
+            tailable,
+            &collection,
+            pg_data_client,
+        )
+        .await;
+    }
+
+    // The backend returns one batch per getMore, and the next one is only pulled after this
+    // response has been written and the client asks for it, so a slow client holds the backend
+    // back. Bounding the batch keeps what each pull buffers under the high-water mark.
+    let bounded_get_more = bound_get_more(request.document(), &cursor, connection_context)?;
+    let bounded_request = bounded_get_more
+        .as_deref()
+        .map(|document| request_context.rewritten_payload(document));
+
     let results = pg_data_client
         .execute_cursor_get_more(
-            request_context,
+            &request_context.with_payload(bounded_request.as_ref()),
             &db,
             &cursor,
             match &cursor_connection {
@@ -138,7 +220,8 @@ pub async fn process_get_more(
         );
     }
 
-    if let Some(row) = results.first() {
+    let response = PgResponse::new(results);
+    if let Ok(row) = response.first() {
         let continuation: Option<PgDocument> = row.try_get(1)?;
         if let Some(continuation) = continuation {
             connection_context.add_cursor(
@@ -146,6 +229,10 @@ pub async fn process_get_more(
                 Cursor {
                     cursor_id: CursorId::from(id),
                     continuation: continuation.0.to_raw_document_buf(),
+                    tailable: None,
+                    bytes_per_document: response
+                        .bytes_per_batch_document()
+                        .or(cursor.bytes_per_document),
                 },
                 connection_context.auth_state.username()?,
                 &db,
@@ -156,5 +243,531 @@ pub async fn process_get_more(
         }
     }
 
-    Ok(Response::Pg(PgResponse::new(results)))
+    Ok(Response::Pg(response))
+}
+
+/// Returns the getMore with a smaller `batchSize` if the requested batch of the cursor could
+/// exceed the high-water mark, or `None` if it can be sent as is.
+fn bound_get_more(
+    document: &RawDocument,
+    cursor: &Cursor,
+    connection_context: &ConnectionContext,
+) -> Result<Option<RawDocumentBuf>> {
+    let high_water_mark = connection_context
+        .service_context
+        .dynamic_configuration()
+        .cursor_batch_high_water_mark_bytes();
+    cursor
+        .bytes_per_document
+        .and_then(|bytes_per_document| {
+            bounded_batch_size(
+                requested_batch_size(document),
+                bytes_per_document,
+                high_water_mark,
+            )
+        })
+        .map(|batch_size| with_batch_size(document, batch_size))
+        .transpose()
+}
+
+/// The batch size asked for by a command, or `None` if it leaves it to the server.
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "batch sizes are whole numbers well within i64"
+)]
+fn requested_batch_size(document: &RawDocument) -> Option<i64> {
+    let batch_size = document.get("batchSize").ok().flatten()?;
+    Some(convert_to_f64(batch_size)? as i64).filter(|batch_size| *batch_size > 0)
+}
+
+/// Returns the batch size to ask the backend for instead of the requested one, if documents of
+/// `bytes_per_document` would otherwise fill more than `high_water_mark` bytes.
+/// A high-water mark of 0 disables the bound; at least one document is always returned.
+fn bounded_batch_size(
+    requested: Option<i64>,
+    bytes_per_document: usize,
+    high_water_mark: u64,
+) -> Option<i64> {
+    if high_water_mark == 0 || bytes_per_document == 0 {
+        return None;
+    }
+    let bytes_per_document = u64::try_from(bytes_per_document).unwrap_or(u64::MAX);
+    let bound = i64::try_from(high_water_mark / bytes_per_document)
+        .unwrap_or(i64::MAX)
+        .max(1);
+    match requested {
+        Some(requested) if requested <= bound => None,
+        _ => Some(bound),
+    }
+}
+
+/// Copies a command with its `batchSize` replaced.
+fn with_batch_size(document: &RawDocument, batch_size: i64) -> Result<RawDocumentBuf> {
+    let mut bounded = RawDocumentBuf::new();
+    for entry in document {
+        let (k, v) = entry?;
+        if k != "batchSize" {
+            bounded.append(k, v.to_raw_bson());
+        }
+    }
+    bounded.append("batchSize", batch_size);
+    Ok(bounded)
+}
+
+/// Returns `command` with `default` as its batch size if the client left it to the server,
+/// or `None` if it can be sent as is. A find or getMore takes `batchSize` at the top level,
+/// an aggregate in its `cursor` document; aggregates without one, such as explains, are left
+/// alone. A default of 0 keeps the backend's own.
+///
+/// Like a requested batch size, the default is a count: the backend still ends a batch early
+/// rather than exceed the maximum message size.
+pub fn with_default_batch_size(
+    command: &RawDocument,
+    request_type: RequestType,
+    default: u64,
+) -> Result<Option<RawDocumentBuf>> {
+    let Ok(default) = i64::try_from(default) else {
+        return Ok(None);
+    };
+    if default == 0 {
+        return Ok(None);
+    }
+    if request_type != RequestType::Aggregate {
+        if command.get("batchSize")?.is_some() {
+            return Ok(None);
+        }
+        return with_batch_size(command, default).map(Some);
+    }
+
+    let Some(cursor) = command.get("cursor")?.and_then(RawBsonRef::as_document) else {
+        return Ok(None);
+    };
+    if cursor.get("batchSize")?.is_some() {
+        return Ok(None);
+    }
+    with_cursor_batch_size(command, cursor, default).map(Some)
+}
+
+/// Returns a find or aggregate `command` with a smaller batch size if its first batch could
+/// exceed the high-water mark, or `None` if it can be sent as is. Like a getMore, the batch
+/// is bounded by the size of the documents last seen on the namespace; before any are seen
+/// the maximum object size is assumed. Single batches and batch sizes of 0 are left alone.
+///
+/// # Errors
+/// Returns an error if the command cannot be read.
+pub fn bound_first_batch(
+    command: &RawDocument,
+    request_type: RequestType,
+    db: &str,
+    collection: &str,
+    connection_context: &ConnectionContext,
+) -> Result<Option<RawDocumentBuf>> {
+    let service_context = &connection_context.service_context;
+    let bytes_per_document = service_context
+        .cursor_store()
+        .document_size_estimate(db, collection)
+        .unwrap_or_else(|| {
+            usize::try_from(service_context.setup_configuration().max_bson_object_size())
+                .unwrap_or_default()
+        });
+    with_first_batch_bound(
+        command,
+        request_type,
+        bytes_per_document,
+        service_context
+            .dynamic_configuration()
+            .cursor_batch_high_water_mark_bytes(),
+    )
+}
+
+fn with_first_batch_bound(
+    command: &RawDocument,
+    request_type: RequestType,
+    bytes_per_document: usize,
+    high_water_mark: u64,
+) -> Result<Option<RawDocumentBuf>> {
+    if command.get_bool("singleBatch").unwrap_or(false) {
+        return Ok(None);
+    }
+    let cursor = if request_type == RequestType::Aggregate {
+        let Some(cursor) = command.get("cursor")?.and_then(RawBsonRef::as_document) else {
+            return Ok(None);
+        };
+        Some(cursor)
+    } else {
+        None
+    };
+    let holder = cursor.unwrap_or(command);
+    if holder
+        .get("batchSize")?
+        .and_then(convert_to_f64)
+        .is_some_and(|batch_size| batch_size == 0.0)
+    {
+        return Ok(None);
+    }
+
+    let Some(batch_size) = bounded_batch_size(
+        requested_batch_size(holder),
+        bytes_per_document,
+        high_water_mark,
+    ) else {
+        return Ok(None);
+    };
+    match cursor {
+        Some(cursor) => with_cursor_batch_size(command, cursor, batch_size).map(Some),
+        None => with_batch_size(command, batch_size).map(Some),
+    }
+}
+
+/// Copies an aggregate `command` with the `batchSize` of its `cursor` document replaced.
+fn with_cursor_batch_size(
+    command: &RawDocument,
+    cursor: &RawDocument,
+    batch_size: i64,
+) -> Result<RawDocumentBuf> {
+    let mut rewritten = RawDocumentBuf::new();
+    for entry in command {
+        let (k, v) = entry?;
+        if k == "cursor" {
+            rewritten.append(k, with_batch_size(cursor, batch_size)?);
+        } else {
+            rewritten.append(k, v.to_raw_bson());
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Returns an aggregate `command` without the `batchSize` of its `cursor` document, or `None`
+/// if it has none. A pipeline ending in `$out` or `$merge` returns no documents, so its batch
+/// size means nothing, while a batch size of 0 would have the backend skip running the
+/// pipeline, and so its write, until a getMore that never comes.
+pub fn without_aggregate_batch_size(command: &RawDocument) -> Result<Option<RawDocumentBuf>> {
+    let Some(cursor) = command.get("cursor")?.and_then(RawBsonRef::as_document) else {
+        return Ok(None);
+    };
+    if cursor.get("batchSize")?.is_none() {
+        return Ok(None);
+    }
+
+    let mut unbatched_cursor = RawDocumentBuf::new();
+    for entry in cursor {
+        let (k, v) = entry?;
+        if k != "batchSize" {
+            unbatched_cursor.append(k, v.to_raw_bson());
+        }
+    }
+    let mut unbatched = RawDocumentBuf::new();
+    for entry in command {
+        let (k, v) = entry?;
+        if k == "cursor" {
+            unbatched.append(k, unbatched_cursor.clone());
+        } else {
+            unbatched.append(k, v.to_raw_bson());
+        }
+    }
+    Ok(Some(unbatched))
+}
+
+/// A cursor may only be continued from the namespace it was opened on.
+///
+/// The cursor of a database-level aggregate, such as `$currentOp`, is stored without a
+/// collection, while its reply names `$cmd.aggregate`, which drivers continue it on.
+fn validate_get_more_namespace(
+    requested_db: &str,
+    requested_collection: &str,
+    cursor_db: &str,
+    cursor_collection: &str,
+) -> Result<()> {
+    let requested_collection = match requested_collection {
+        "$cmd.aggregate" => "",
+        collection => collection,
+    };
+    if requested_db == cursor_db && requested_collection == cursor_collection {
+        Ok(())
+    } else {
+        Err(DocumentDBError::documentdb_error(
+            ErrorCode::Unauthorized,
+            format!(
+                "Requested getMore on namespace '{requested_db}.{requested_collection}', but cursor belongs to a different namespace {cursor_db}.{cursor_collection}"
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::RequestMemory,
+        processor::data_management,
+        requests::request_tracker::RequestTracker,
+        testing::{self, MapConfiguration, StubDataClient},
+    };
+
+    #[test]
+    fn get_more_on_cursor_namespace_is_allowed() {
+        validate_get_more_namespace("db", "coll", "db", "coll").unwrap();
+    }
+
+    #[test]
+    fn get_more_on_other_namespace_is_rejected() {
+        for (db, collection) in [("db", "other"), ("other", "coll")] {
+            let err = validate_get_more_namespace(db, collection, "db", "coll").unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+        }
+    }
+
+    /// Opens a cursor with `id` on `db.collection` for the test user.
+    fn open_cursor(
+        connection_context: &ConnectionContext,
+        id: i64,
+        db: &str,
+        collection: &str,
+        bytes_per_document: Option<usize>,
+    ) {
+        connection_context.add_cursor(
+            None,
+            Cursor {
+                continuation: rawdoc! {},
+                cursor_id: id.into(),
+                tailable: None,
+                bytes_per_document,
+            },
+            "user",
+            db,
+            collection,
+            Duration::from_secs(60),
+            None,
+        );
+    }
+
+    async fn get_more(
+        command: &RawDocument,
+        connection_context: &ConnectionContext,
+        client: &StubDataClient,
+    ) -> Result<Response> {
+        let request = Request::Raw(RequestType::GetMore, command, None);
+        let info = request.extract_common().unwrap();
+        let request_context = RequestContext {
+            activity_id: "test",
+            payload: &request,
+            info: &info,
+            tracker: &RequestTracker::new(),
+            memory: &RequestMemory::default(),
+        };
+        process_get_more(&request_context, connection_context, client).await
+    }
+
+    #[tokio::test]
+    async fn database_aggregate_cursor_continues_on_cmd_aggregate() {
+        let service_context = testing::service_context(MapConfiguration::default()).await;
+        let client = StubDataClient::new(service_context.clone());
+        let connection_context = testing::connection_context(service_context, "user");
+        open_cursor(&connection_context, 42, "admin", "", None);
+
+        let command = rawdoc! { "getMore": 42_i64, "collection": "$cmd.aggregate", "$db": "admin" };
+        // The stub fails the pull itself; the namespace check has let it through.
+        let err = get_more(&command, &connection_context, &client)
+            .await
+            .unwrap_err();
+        assert_ne!(
+            err.error_code_enum(),
+            Some(ErrorCode::Unauthorized),
+            "{err}"
+        );
+        assert_eq!(client.methods(), ["execute_cursor_get_more"]);
+
+        open_cursor(&connection_context, 43, "admin", "", None);
+        let command = rawdoc! { "getMore": 43_i64, "collection": "coll", "$db": "admin" };
+        let err = get_more(&command, &connection_context, &client)
+            .await
+            .unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+        assert_eq!(client.methods(), ["execute_cursor_get_more"]);
+    }
+
+    #[test]
+    fn omitted_batch_size_takes_the_configured_default() {
+        let find = with_default_batch_size(&rawdoc! { "find": "c" }, RequestType::Find, 50)
+            .unwrap()
+            .unwrap();
+        assert_eq!(find.get_i64("batchSize").unwrap(), 50);
+
+        let aggregate = with_default_batch_size(
+            &rawdoc! { "aggregate": "c", "pipeline": [], "cursor": {} },
+            RequestType::Aggregate,
+            50,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            aggregate
+                .get_document("cursor")
+                .unwrap()
+                .get_i64("batchSize")
+                .unwrap(),
+            50
+        );
+
+        let get_more = with_default_batch_size(
+            &rawdoc! { "getMore": 1_i64, "collection": "c" },
+            RequestType::GetMore,
+            20,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(get_more.get_i64("batchSize").unwrap(), 20);
+    }
+
+    #[test]
+    fn requested_batch_size_overrides_the_default() {
+        for (command, request_type) in [
+            (rawdoc! { "find": "c", "batchSize": 0 }, RequestType::Find),
+            (
+                rawdoc! { "aggregate": "c", "cursor": { "batchSize": 5 } },
+                RequestType::Aggregate,
+            ),
+            // Explains carry no cursor.
+            (
+                rawdoc! { "aggregate": "c", "explain": true },
+                RequestType::Aggregate,
+            ),
+            (
+                rawdoc! { "getMore": 1_i64, "collection": "c", "batchSize": 5 },
+                RequestType::GetMore,
+            ),
+        ] {
+            assert!(with_default_batch_size(&command, request_type, 50)
+                .unwrap()
+                .is_none());
+        }
+        assert!(
+            with_default_batch_size(&rawdoc! { "find": "c" }, RequestType::Find, 0)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn write_pipeline_batch_size_is_dropped() {
+        let out = rawdoc! {
+            "aggregate": "c",
+            "pipeline": [{ "$out": "target" }],
+            "cursor": { "batchSize": 0 },
+            "$db": "db",
+        };
+        assert_eq!(
+            without_aggregate_batch_size(&out).unwrap().unwrap(),
+            rawdoc! {
+                "aggregate": "c",
+                "pipeline": [{ "$out": "target" }],
+                "cursor": {},
+                "$db": "db",
+            }
+        );
+
+        for command in [
+            rawdoc! { "aggregate": "c", "pipeline": [], "cursor": {} },
+            rawdoc! { "aggregate": "c", "pipeline": [], "explain": true },
+        ] {
+            assert!(without_aggregate_batch_size(&command).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn batch_size_is_bounded_by_high_water_mark() {
+        // Without a high-water mark or a known document size the request is sent as is.
+        assert_eq!(bounded_batch_size(Some(1000), 1024, 0), None);
+        assert_eq!(bounded_batch_size(None, 0, 16 * 1024), None);
+
+        assert_eq!(bounded_batch_size(Some(1000), 1024, 16 * 1024), Some(16));
+        assert_eq!(bounded_batch_size(None, 1024, 16 * 1024), Some(16));
+        assert_eq!(bounded_batch_size(Some(8), 1024, 16 * 1024), None);
+
+        // A document larger than the high-water mark is still returned on its own.
+        assert_eq!(bounded_batch_size(None, 64 * 1024, 16 * 1024), Some(1));
+    }
+
+    /// A service whose cursor batches are bounded to 64KiB.
+    async fn bounded_service() -> (ConnectionContext, StubDataClient) {
+        let service_context = testing::service_context(MapConfiguration::new(&[(
+            "cursorBatchHighWaterMarkBytes",
+            "65536",
+        )]))
+        .await;
+        let client = StubDataClient::new(service_context.clone());
+        (testing::connection_context(service_context, "user"), client)
+    }
+
+    #[tokio::test]
+    async fn slow_consumer_pulls_bounded_batches() {
+        let (connection_context, client) = bounded_service().await;
+        open_cursor(&connection_context, 42, "db", "coll", Some(10 * 1024));
+
+        // A client that never sets a batch size drains the cursor one getMore at a time;
+        // each pull asks for no more documents than fit under the high-water mark.
+        let command = rawdoc! { "getMore": 42_i64, "collection": "coll", "$db": "db" };
+        get_more(&command, &connection_context, &client)
+            .await
+            .unwrap_err();
+        let calls = client.calls();
+        assert_eq!(calls[0].method, "execute_cursor_get_more");
+        assert_eq!(calls[0].command.get_i64("batchSize").unwrap(), 6);
+    }
+
+    async fn find(
+        command: &RawDocument,
+        connection_context: &ConnectionContext,
+        client: &StubDataClient,
+    ) -> Option<i64> {
+        let request = Request::Raw(RequestType::Find, command, None);
+        let info = request.extract_common().unwrap();
+        let request_context = RequestContext {
+            activity_id: "test",
+            payload: &request,
+            info: &info,
+            tracker: &RequestTracker::new(),
+            memory: &RequestMemory::default(),
+        };
+        let _ = data_management::process_find(&request_context, connection_context, client).await;
+        let call = client.calls().pop().unwrap();
+        assert_eq!(call.method, "execute_find");
+        requested_batch_size(&call.command)
+    }
+
+    #[tokio::test]
+    async fn first_batch_is_bounded_by_the_namespace_document_size() {
+        let (connection_context, client) = bounded_service().await;
+
+        // Before any document of the namespace is seen, each may be of the maximum size.
+        let command = rawdoc! { "find": "coll", "$db": "db" };
+        assert_eq!(find(&command, &connection_context, &client).await, Some(1));
+
+        open_cursor(&connection_context, 42, "db", "coll", Some(10 * 1024));
+        assert_eq!(find(&command, &connection_context, &client).await, Some(6));
+
+        let small = rawdoc! { "find": "coll", "batchSize": 4, "$db": "db" };
+        assert_eq!(find(&small, &connection_context, &client).await, Some(4));
+        let single = rawdoc! { "find": "coll", "batchSize": 50, "singleBatch": true, "$db": "db" };
+        assert_eq!(find(&single, &connection_context, &client).await, Some(50));
+    }
+
+    #[test]
+    fn batch_size_is_parsed_and_replaced() {
+        let get_more =
+            rawdoc! { "getMore": 5_i64, "collection": "coll", "batchSize": 1000, "$db": "db" };
+        assert_eq!(requested_batch_size(&get_more), Some(1000));
+        assert_eq!(
+            requested_batch_size(&rawdoc! { "batchSize": 10.0 }),
+            Some(10)
+        );
+        assert_eq!(requested_batch_size(&rawdoc! { "batchSize": 0 }), None);
+        assert_eq!(requested_batch_size(&rawdoc! { "getMore": 5_i64 }), None);
+
+        let bounded = with_batch_size(&get_more, 16).unwrap();
+        assert_eq!(
+            bounded,
+            rawdoc! { "getMore": 5_i64, "collection": "coll", "$db": "db", "batchSize": 16_i64 }
+        );
+    }
 }
@@ -8,27 +8,104 @@
 
 use std::sync::Arc;
 
-use bson::rawdoc;
+use bson::{rawdoc, RawDocument, RawDocumentBuf};
 
 use crate::{
     configuration::DynamicConfiguration,
     context::{ConnectionContext, RequestContext},
-    error::{DocumentDBError, Result},
+    error::{DocumentDBError, ErrorCode, Result},
     postgres::PgDataClient,
     protocol::{self, OK_SUCCEEDED},
+    requests::{Request, RequestType},
     responses::{RawResponse, Response},
 };
 
+/// collMod options the backend applies, alongside the generic command fields.
+const COLL_MOD_OPTIONS: [&str; 9] = [
+    "collMod",
+    "index",
+    "validator",
+    "validationLevel",
+    "validationAction",
+    "viewOn",
+    "pipeline",
+    "colocation",
+    "enableStats",
+];
+
+/// Command fields that are not collMod options but are accepted by every command.
+pub const GENERIC_COMMAND_FIELDS: [&str; 14] = [
+    "$db",
+    "$clusterTime",
+    "$readPreference",
+    "lsid",
+    "txnNumber",
+    "autocommit",
+    "startTransaction",
+    "maxTimeMS",
+    "readConcern",
+    "writeConcern",
+    "comment",
+    "apiVersion",
+    "apiStrict",
+    "apiDeprecationErrors",
+];
+
 pub async fn process_coll_mod(
     request_context: &RequestContext<'_>,
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
+    // The backend matches the index by name or keyPattern, applies the changes and reports
+    // the before/after values, or IndexNotFound.
+    let Some(supported) = strip_unsupported_coll_mod_options(
+        request_context.payload.document(),
+        request_context.activity_id,
+    )?
+    else {
+        return pg_data_client
+            .execute_coll_mod(request_context, connection_context)
+            .await;
+    };
+
+    let coll_mod_request = Request::Raw(
+        RequestType::CollMod,
+        &supported,
+        request_context.payload.extra(),
+    );
+    let coll_mod_request_context = request_context.with_payload(Some(&coll_mod_request));
     pg_data_client
-        .execute_coll_mod(request_context, connection_context)
+        .execute_coll_mod(&coll_mod_request_context, connection_context)
         .await
 }
 
+/// Returns the command without options the backend would reject, or `None` if it has none.
+/// Each dropped option is logged as a warning so that clients are not broken by them.
+fn strip_unsupported_coll_mod_options(
+    command: &RawDocument,
+    activity_id: &str,
+) -> Result<Option<RawDocumentBuf>> {
+    let is_supported =
+        |key: &str| COLL_MOD_OPTIONS.contains(&key) || GENERIC_COMMAND_FIELDS.contains(&key);
+
+    let mut supported = RawDocumentBuf::new();
+    let mut stripped = false;
+    for entry in command {
+        let (k, v) = entry?;
+        if is_supported(k) {
+            supported.append(k, v.to_raw_bson());
+        } else {
+            tracing::warn!(
+                activity_id = activity_id,
+                "Ignoring unsupported collMod option '{k}'."
+            );
+            stripped = true;
+        }
+    }
+
+    Ok(stripped.then_some(supported))
+}
+
 pub async fn process_create(
     request_context: &RequestContext<'_>,
     connection_context: &ConnectionContext,
@@ -98,7 +175,7 @@ pub async fn process_drop_collection(
     let is_read_only_for_disk_full =
         dynamic_config.is_read_only_for_disk_full() && connection_context.transaction.is_none();
 
-    if is_read_only_for_disk_full {
+    let dropped = if is_read_only_for_disk_full {
         pg_data_client
             .execute_drop_collection_when_readonly(
                 request_context,
@@ -106,17 +183,27 @@ pub async fn process_drop_collection(
                 coll_str,
                 connection_context,
             )
-            .await?;
+            .await?
     } else {
         pg_data_client
             .execute_drop_collection(request_context, db_str, coll_str, connection_context)
-            .await?;
-    }
+            .await?
+    };
 
-    Ok(Response::Raw(RawResponse(rawdoc! {
-        "ok": OK_SUCCEEDED,
-        "dropped": coll,
-    })))
+    Ok(Response::Raw(RawResponse(drop_collection_response(
+        db_str, coll_str, dropped,
+    ))))
+}
+
+/// Dropping a collection that doesn't exist succeeds without an `ns` rather than failing with
+/// "ns not found", so teardown scripts can drop collections unconditionally.
+fn drop_collection_response(db: &str, coll: &str, dropped: bool) -> RawDocumentBuf {
+    let mut response = RawDocumentBuf::new();
+    if dropped {
+        response.append("ns", format!("{db}.{coll}"));
+    }
+    response.append("ok", OK_SUCCEEDED);
+    response
 }
 
 pub async fn process_rename_collection(
@@ -124,12 +211,50 @@ pub async fn process_rename_collection(
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
+    let request_info = request_context.info;
+    if request_info.db()? != "admin" {
+        return Err(DocumentDBError::documentdb_error(
+            ErrorCode::Unauthorized,
+            "renameCollection may only be run against the admin database.".to_owned(),
+        ));
+    }
+
+    // The backend enforces same-database renames, dropTarget, and the
+    // NamespaceExists/NamespaceNotFound errors.
+    let document = request_context.payload.document();
+    let (source_db, source_collection) = split_namespace(request_info.collection()?)?;
+    let target = document
+        .get_str("to")
+        .map_err(DocumentDBError::parse_failure())?;
+    let (target_db, target_collection) = split_namespace(target)?;
+    connection_context
+        .service_context
+        .setup_configuration()
+        .backend_router()
+        .check_same_backend(source_db, target_db, "renameCollection")?;
+
     pg_data_client
         .execute_rename_collection(request_context, connection_context)
         .await?;
+
+    let cursor_store = connection_context.service_context.cursor_store();
+    cursor_store.invalidate_cursors_by_collection(source_db, source_collection);
+    cursor_store.invalidate_cursors_by_collection(target_db, target_collection);
+
     Ok(Response::ok())
 }
 
+/// Splits a fully-qualified `db.collection` namespace.
+fn split_namespace(namespace: &str) -> Result<(&str, &str)> {
+    match namespace.split_once('.') {
+        Some((db, collection)) if !db.is_empty() && !collection.is_empty() => Ok((db, collection)),
+        _ => Err(DocumentDBError::documentdb_error(
+            ErrorCode::InvalidNamespace,
+            format!("Invalid namespace specified '{namespace}'"),
+        )),
+    }
+}
+
 pub async fn process_shard_collection(
     request_context: &RequestContext<'_>,
     connection_context: &ConnectionContext,
@@ -230,3 +355,70 @@ pub async fn process_move_collection(
         .execute_move_collection(request_context, connection_context)
         .await
 }
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+
+    #[test]
+    fn drop_collection_reports_namespace_only_when_dropped() {
+        assert_eq!(
+            drop_collection_response("db", "c", true),
+            rawdoc! { "ns": "db.c", "ok": OK_SUCCEEDED }
+        );
+        assert_eq!(
+            drop_collection_response("db", "missing", false),
+            rawdoc! { "ok": OK_SUCCEEDED }
+        );
+    }
+
+    #[test]
+    fn coll_mod_keeps_supported_options() {
+        let command = rawdoc! {
+            "collMod": "c",
+            "index": { "name": "ttl_1", "expireAfterSeconds": 60 },
+            "validationLevel": "moderate",
+            "lsid": { "id": 1 },
+            "$db": "db",
+        };
+        assert_eq!(
+            strip_unsupported_coll_mod_options(&command, "activity").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn coll_mod_strips_unknown_options() {
+        let command = rawdoc! {
+            "collMod": "c",
+            "changeStreamPreAndPostImages": { "enabled": true },
+            "index": { "name": "ttl_1", "hidden": true },
+            "cappedSize": 1024,
+            "$db": "db",
+        };
+        assert_eq!(
+            strip_unsupported_coll_mod_options(&command, "activity").unwrap(),
+            Some(rawdoc! {
+                "collMod": "c",
+                "index": { "name": "ttl_1", "hidden": true },
+                "$db": "db",
+            })
+        );
+    }
+
+    #[test]
+    fn splits_fully_qualified_namespace() {
+        assert_eq!(split_namespace("db.coll").unwrap(), ("db", "coll"));
+        assert_eq!(
+            split_namespace("db.system.views").unwrap(),
+            ("db", "system.views")
+        );
+
+        for namespace in ["coll", ".coll", "db."] {
+            let err = split_namespace(namespace).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::InvalidNamespace));
+        }
+    }
+}
@@ -6,16 +6,26 @@
  *-------------------------------------------------------------------------
  */
 
-use bson::{spec::ElementType, RawBsonRef};
-use std::sync::Arc;
+use bson::{
+    rawdoc, spec::ElementType, RawArray, RawArrayBuf, RawBsonRef, RawDocument, RawDocumentBuf,
+};
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    bson::convert_to_bool,
-    configuration::DynamicConfiguration,
-    context::{ConnectionContext, RequestContext},
+    auth::{self, DatabaseAuthorizer},
+    bson::{convert_to_bool, convert_to_f64},
+    configuration::{BackendRouter, DynamicConfiguration},
+    context::{ConnectionContext, OperationInfo, RequestContext},
     error::{DocumentDBError, ErrorCode, Result},
     postgres::PgDataClient,
-    responses::{PgResponse, Response},
+    processor::{
+        cursor, index_stats, legacy_find, statement_collation, tailable,
+        write_batch::{self, WriteOptions},
+        write_concern,
+    },
+    protocol::OK_SUCCEEDED,
+    requests::{array_filters, collation::Collation, hint, let_variables, Request, RequestType},
+    responses::{PgResponse, RawResponse, Response},
 };
 
 pub async fn process_delete(
@@ -24,6 +34,30 @@ pub async fn process_delete(
     dynamic_config: &Arc<dyn DynamicConfiguration>,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
+    // The backend binds `let` for the `$expr` of each filter; reject undefined variables
+    // up front rather than per statement.
+    let_variables::check_write_variables(
+        request_context.payload.document(),
+        request_context.payload.extra(),
+        Some("deletes"),
+        "q",
+        None,
+    )?;
+
+    let collated = statement_collation::apply(
+        request_context.payload.document(),
+        request_context.payload.extra(),
+        "deletes",
+    )?;
+    let collated_request = collated.as_ref().map(|delete| {
+        Request::Raw(
+            RequestType::Delete,
+            &delete.command,
+            delete.extra.as_deref(),
+        )
+    });
+    let request_context = &request_context.with_payload(collated_request.as_ref());
+
     // Nested transactions not allowed when database is in read-only mode
     let is_read_only_for_disk_full =
         dynamic_config.is_read_only_for_disk_full() && connection_context.transaction.is_none();
@@ -38,68 +72,602 @@ pub async fn process_delete(
             .await?
     };
 
-    PgResponse::new(delete_rows)
-        .transform_write_errors(connection_context, request_context.activity_id)
+    let response = PgResponse::new(delete_rows)
+        .transform_write_errors(connection_context, request_context.activity_id)?;
+    write_concern::acknowledge_write(request_context, connection_context, response).await
 }
 
+/// Runs a find on the backend. `sort`, `skip` and `limit` always travel in the spec passed
+/// to the backend, which evaluates them; the gateway only relays cursor pages, so it never
+/// sorts documents or buffers more than one batch. Rewrites of the spec must keep them.
 pub async fn process_find(
     request_context: &RequestContext<'_>,
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
+    // The collation travels to the backend with the command; reject bad ones up front.
+    Collation::from_command(request_context.payload.document())?;
+
+    // So does the hint, which the backend plans the find with; it rejects hints naming
+    // no existing index with `BadValue`.
+    let unhinted_find = hint::without_empty_hint(request_context.payload.document())?;
+    let unhinted_request = unhinted_find
+        .as_deref()
+        .map(|document| request_context.rewritten_payload(document));
+    let request_context = &request_context.with_payload(unhinted_request.as_ref());
+
+    let defaulted_find = cursor::with_default_batch_size(
+        request_context.payload.document(),
+        RequestType::Find,
+        connection_context
+            .service_context
+            .dynamic_configuration()
+            .default_first_batch_size(),
+    )?;
+    let defaulted_request = defaulted_find
+        .as_deref()
+        .map(|document| request_context.rewritten_payload(document));
+    let request_context = &request_context.with_payload(defaulted_request.as_ref());
+
+    let bounded_find = cursor::bound_first_batch(
+        request_context.payload.document(),
+        RequestType::Find,
+        request_context.info.db()?,
+        request_context.info.collection().unwrap_or_default(),
+        connection_context,
+    )?;
+    let bounded_request = bounded_find
+        .as_deref()
+        .map(|document| request_context.rewritten_payload(document));
+    let request_context = &request_context.with_payload(bounded_request.as_ref());
+
+    let legacy_find = legacy_find::rewrite_legacy_find(request_context.payload.document())?;
+    let legacy_request = legacy_find
+        .as_deref()
+        .map(|document| request_context.rewritten_payload(document));
+    let request_context = &request_context.with_payload(legacy_request.as_ref());
+
+    if let Some(find) = tailable::parse_tailable_find(request_context.payload.document())? {
+        return tailable::process_tailable_find(
+            request_context,
+            connection_context,
+            find,
+            pg_data_client,
+        )
+        .await;
+    }
+
+    if let Some(spec) = natural_hint_find(request_context.payload.document())? {
+        let find_request = request_context.rewritten_payload(&spec);
+        let find_request_context = request_context.with_payload(Some(&find_request));
+        return pg_data_client
+            .execute_find(&find_request_context, connection_context)
+            .await;
+    }
+
     pg_data_client
         .execute_find(request_context, connection_context)
         .await
 }
 
+/// Rewrites a find hinted with `{$natural: 1}` or `{$natural: -1}` to sort by `$natural`
+/// instead, which the backend serves with a collection scan in insertion order or its
+/// reverse. Returns `None` for finds without a `$natural` hint, or that already sort by it.
+fn natural_hint_find(document: &RawDocument) -> Result<Option<RawDocumentBuf>> {
+    let Some(hint) = document.get("hint")?.and_then(RawBsonRef::as_document) else {
+        return Ok(None);
+    };
+    let Some(Ok(("$natural", direction))) = hint.into_iter().next() else {
+        return Ok(None);
+    };
+    let direction = match convert_to_f64(direction) {
+        Some(1.0) => 1,
+        Some(-1.0) => -1,
+        _ => {
+            return Err(DocumentDBError::bad_value(
+                "$natural hint must be 1 or -1".to_owned(),
+            ))
+        }
+    };
+
+    if let Some(sort) = document.get("sort")?.and_then(RawBsonRef::as_document) {
+        for entry in sort {
+            let (field, _) = entry?;
+            if field != "$natural" {
+                return Err(DocumentDBError::bad_value(format!(
+                    "$natural hint cannot be combined with a sort on '{field}'"
+                )));
+            }
+        }
+        if !sort.is_empty() {
+            return Ok(None);
+        }
+    }
+
+    let mut spec = RawDocumentBuf::new();
+    for entry in document {
+        let (key, value) = entry?;
+        if key != "hint" && key != "sort" {
+            spec.append(key, value.to_raw_bson());
+        }
+    }
+    spec.append("sort", rawdoc! { "$natural": direction });
+    Ok(Some(spec))
+}
+
+/// Runs an insert batch on the backend, which honours `ordered`: an ordered batch stops at
+/// the first failing document, an unordered batch attempts every document. Either way `n`
+/// counts the documents inserted and each failure is reported in `writeErrors` under its
+/// batch `index`. Large batches outside a transaction are committed in chunks, see
+/// [`write_batch::process_write_batch`].
 pub async fn process_insert(
     request_context: &RequestContext<'_>,
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
-    enable_write_procedures: bool,
-    enable_write_procedures_with_batch_commit: bool,
+    options: WriteOptions,
 ) -> Result<Response> {
-    let insert_rows = pg_data_client
-        .execute_insert(
-            request_context,
-            connection_context,
-            enable_write_procedures,
-            enable_write_procedures_with_batch_commit,
-        )
-        .await?;
-
-    PgResponse::new(insert_rows)
-        .transform_write_errors(connection_context, request_context.activity_id)
+    let response = write_batch::process_write_batch(
+        request_context,
+        connection_context,
+        pg_data_client,
+        options,
+    )
+    .await?;
+    write_concern::acknowledge_write(request_context, connection_context, response).await
 }
 
+/// Runs an aggregate pipeline on the backend. A leading `$collStats` is evaluated there
+/// too, from the same statistics as the `collStats` command, and its output feeds later
+/// stages. `latencyStats` is rejected, since operation latencies are only exported as
+/// metrics and not kept per collection. `$indexStats` is answered from the backend's index
+/// usage statistics, see [`index_stats::process_index_stats`].
 pub async fn process_aggregate(
     request_context: &RequestContext<'_>,
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
+    let setup_configuration = connection_context.service_context.setup_configuration();
+    check_pipeline_stages(
+        request_context.payload.document(),
+        setup_configuration.aggregation_stage_allowlist(),
+        setup_configuration.aggregation_stage_denylist(),
+    )?;
+
+    let db = request_context.info.db()?;
+    let authorizer = match connection_context.service_context.database_authorizer() {
+        Some(authorizer) => Some((authorizer, connection_context.auth_state.username()?)),
+        None => None,
+    };
+    check_cross_database_writes(request_context.payload.document(), db, authorizer)?;
+
+    let stage = output_stage(request_context.payload.document(), db)?;
+    if let Some(stage) = &stage {
+        check_output_target(
+            stage,
+            connection_context.auth_state.roles(),
+            &connection_context
+                .dynamic_configuration()
+                .role_command_allowlist(),
+            authorizer,
+        )?;
+    }
+
+    if index_stats::is_index_stats(request_context.payload.document())? {
+        return index_stats::process_index_stats(
+            request_context,
+            connection_context,
+            pg_data_client,
+        )
+        .await;
+    }
+
+    check_pipeline_backends(
+        request_context.payload.document(),
+        db,
+        setup_configuration.backend_router(),
+    )?;
+
+    let Some(stage) = stage else {
+        let defaulted_aggregate = cursor::with_default_batch_size(
+            request_context.payload.document(),
+            RequestType::Aggregate,
+            connection_context
+                .service_context
+                .dynamic_configuration()
+                .default_first_batch_size(),
+        )?;
+        let defaulted_request = defaulted_aggregate
+            .as_deref()
+            .map(|document| request_context.rewritten_payload(document));
+        let request_context = &request_context.with_payload(defaulted_request.as_ref());
+
+        let bounded_aggregate = cursor::bound_first_batch(
+            request_context.payload.document(),
+            RequestType::Aggregate,
+            db,
+            request_context.info.collection().unwrap_or_default(),
+            connection_context,
+        )?;
+        let bounded_request = bounded_aggregate
+            .as_deref()
+            .map(|document| request_context.rewritten_payload(document));
+        return pg_data_client
+            .execute_aggregate(
+                &request_context.with_payload(bounded_request.as_ref()),
+                connection_context,
+            )
+            .await;
+    };
+
+    // The backend performs the write, including $merge's whenMatched/whenNotMatched modes,
+    // and $out replaces the target atomically, so the results are not streamed back. A
+    // missing target is created, so with whenNotMatched: "fail" every document fails.
+    // The write runs when the cursor is opened, whatever batch size the client asked for.
+    let unbatched_aggregate =
+        cursor::without_aggregate_batch_size(request_context.payload.document())?;
+    let unbatched_request = unbatched_aggregate
+        .as_deref()
+        .map(|document| request_context.rewritten_payload(document));
     pg_data_client
-        .execute_aggregate(request_context, connection_context)
-        .await
+        .execute_aggregate(
+            &request_context.with_payload(unbatched_request.as_ref()),
+            connection_context,
+        )
+        .await?;
+
+    if let OutputStage::Out(Some((target_db, target_collection))) = stage {
+        connection_context
+            .service_context
+            .cursor_store()
+            .invalidate_cursors_by_collection(target_db, target_collection);
+    }
+
+    let collection = match request_context.info.collection()? {
+        "" => "$cmd.aggregate",
+        collection => collection,
+    };
+    Ok(Response::Raw(RawResponse(rawdoc! {
+        "cursor": {
+            "firstBatch": [],
+            "id": 0_i64,
+            "ns": format!("{db}.{collection}"),
+        },
+        "ok": OK_SUCCEEDED,
+    })))
+}
+
+/// Rejects an aggregate whose pipeline uses a stage that is not in `allowlist`, when one is
+/// configured, or a stage or operator that is in `denylist`.
+///
+/// The allowlist names stages only. Denied operators, such as `$where` in a `$match` or
+/// `$function` in a `$project`, are looked for at any depth of each stage's spec.
+/// Malformed pipelines are left for the backend to reject.
+fn check_pipeline_stages(
+    command: &RawDocument,
+    allowlist: Option<&[String]>,
+    denylist: &[String],
+) -> Result<()> {
+    if allowlist.is_none() && denylist.is_empty() {
+        return Ok(());
+    }
+    let Some(pipeline) = command.get("pipeline")?.and_then(RawBsonRef::as_array) else {
+        return Ok(());
+    };
+    check_stages(
+        pipeline,
+        &|stage| {
+            allowlist.is_none_or(|allowed| allowed.iter().any(|s| s == stage))
+                && !denylist.iter().any(|s| s == stage)
+        },
+        denylist,
+    )
+}
+
+fn check_stages(
+    pipeline: &RawArray,
+    is_allowed: &impl Fn(&str) -> bool,
+    denylist: &[String],
+) -> Result<()> {
+    for stage in pipeline {
+        let Some((name, spec)) = stage?
+            .as_document()
+            .and_then(|stage| stage.into_iter().next())
+            .transpose()?
+        else {
+            continue;
+        };
+        if !is_allowed(name) {
+            return Err(DocumentDBError::unauthorized(format!(
+                "Aggregation stage '{name}' is not allowed"
+            )));
+        }
+
+        // Sub-pipelines first, so that a denied stage within one is reported as a stage.
+        match (name, spec) {
+            ("$lookup" | "$unionWith", RawBsonRef::Document(spec)) => {
+                if let Some(pipeline) = spec.get("pipeline")?.and_then(RawBsonRef::as_array) {
+                    check_stages(pipeline, is_allowed, denylist)?;
+                }
+            }
+            ("$facet", RawBsonRef::Document(facets)) => {
+                for facet in facets {
+                    if let Some(pipeline) = facet?.1.as_array() {
+                        check_stages(pipeline, is_allowed, denylist)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        check_operators(spec, denylist)?;
+    }
+    Ok(())
+}
+
+fn check_operators(value: RawBsonRef<'_>, denylist: &[String]) -> Result<()> {
+    match value {
+        RawBsonRef::Document(document) => {
+            for element in document {
+                let (key, value) = element?;
+                if denylist.iter().any(|s| s == key) {
+                    return Err(DocumentDBError::unauthorized(format!(
+                        "Aggregation operator '{key}' is not allowed"
+                    )));
+                }
+                check_operators(value, denylist)?;
+            }
+        }
+        RawBsonRef::Array(array) => {
+            for value in array {
+                check_operators(value?, denylist)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Rejects an aggregate whose `$merge` or `$out` writes into a database other than `db`,
+/// when an authorizer is given with the username and the user holds no privilege on that
+/// database, so a pipeline can't carry one tenant's data into another's.
+fn check_cross_database_writes(
+    command: &RawDocument,
+    db: &str,
+    authorizer: Option<(&dyn DatabaseAuthorizer, &str)>,
+) -> Result<()> {
+    let Some((authorizer, username)) = authorizer else {
+        return Ok(());
+    };
+    if authorizer.is_admin(username) {
+        return Ok(());
+    }
+    let Some(pipeline) = command.get("pipeline")?.and_then(RawBsonRef::as_array) else {
+        return Ok(());
+    };
+    let mut databases = Vec::new();
+    referenced_databases(pipeline, &mut databases)?;
+    for (stage, target_db) in databases {
+        if matches!(stage, "$merge" | "$out")
+            && target_db != db
+            && !authorizer.is_authorized(username, target_db)
+        {
+            return Err(DocumentDBError::unauthorized(format!(
+                "Aggregation stage '{stage}' may not write to database '{target_db}'"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Rejects an aggregate reading from or writing to a database served by a different backend
+/// than `db`.
+fn check_pipeline_backends(command: &RawDocument, db: &str, router: &BackendRouter) -> Result<()> {
+    let Some(pipeline) = command.get("pipeline")?.and_then(RawBsonRef::as_array) else {
+        return Ok(());
+    };
+    let mut databases = Vec::new();
+    referenced_databases(pipeline, &mut databases)?;
+    for (stage, other_db) in databases {
+        router.check_same_backend(db, other_db, stage)?;
+    }
+    Ok(())
+}
+
+/// Collects the databases named by cross-database stages, e.g. `$lookup: { from: { db, coll } }`.
+fn referenced_databases<'a>(
+    pipeline: &'a RawArray,
+    databases: &mut Vec<(&'a str, &'a str)>,
+) -> Result<()> {
+    for stage in pipeline {
+        let Some((name, RawBsonRef::Document(spec))) = stage?
+            .as_document()
+            .and_then(|stage| stage.into_iter().next())
+            .transpose()?
+        else {
+            continue;
+        };
+
+        let target = match name {
+            "$lookup" | "$graphLookup" => spec.get("from")?,
+            "$unionWith" => spec.get("coll")?,
+            "$merge" => spec.get("into")?,
+            "$out" => Some(RawBsonRef::Document(spec)),
+            _ => None,
+        };
+        if let Some(db) = target
+            .and_then(RawBsonRef::as_document)
+            .and_then(|target| target.get_str("db").ok())
+        {
+            databases.push((name, db));
+        }
+
+        match name {
+            "$lookup" | "$unionWith" => {
+                if let Some(pipeline) = spec.get("pipeline")?.and_then(RawBsonRef::as_array) {
+                    referenced_databases(pipeline, databases)?;
+                }
+            }
+            "$facet" => {
+                for facet in spec {
+                    if let Some(pipeline) = facet?.1.as_array() {
+                        referenced_databases(pipeline, databases)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// A terminal pipeline stage that writes its results into a collection.
+#[derive(Debug, PartialEq, Eq)]
+enum OutputStage<'a> {
+    /// `$out` replaces the target collection, given as database and collection.
+    Out(Option<(&'a str, &'a str)>),
+    /// `$merge` writes into the target collection, given as database and collection.
+    Merge(Option<(&'a str, &'a str)>),
+}
+
+impl<'a> OutputStage<'a> {
+    const fn target(&self) -> Option<(&'a str, &'a str)> {
+        match *self {
+            Self::Out(target) | Self::Merge(target) => target,
+        }
+    }
+
+    /// The write commands a user must be allowed to run to write the stage's target:
+    /// `$out` inserts into a replacement of the target, `$merge` inserts or updates.
+    const fn write_commands(&self) -> [RequestType; 2] {
+        match self {
+            Self::Out(_) => [RequestType::Insert, RequestType::Delete],
+            Self::Merge(_) => [RequestType::Insert, RequestType::Update],
+        }
+    }
+}
+
+/// Rejects an aggregate whose `$out` or `$merge` target the user may not write, before the
+/// pipeline reads anything: the user's roles must be allowed the stage's write commands
+/// under `allowlist`, and, when an authorizer is given with the username, the user must hold
+/// a privilege on the target collection. A missing target is created by the stage, so it
+/// only needs to be writable.
+fn check_output_target(
+    stage: &OutputStage<'_>,
+    roles: &[String],
+    allowlist: &HashMap<String, Vec<String>>,
+    authorizer: Option<(&dyn DatabaseAuthorizer, &str)>,
+) -> Result<()> {
+    for command in stage.write_commands() {
+        auth::check_role_command_allowed(command, roles, allowlist)?;
+    }
+
+    let (Some((authorizer, username)), Some((db, collection))) = (authorizer, stage.target())
+    else {
+        return Ok(());
+    };
+    if authorizer.is_admin(username)
+        || authorizer.is_collection_authorized(username, db, collection)
+    {
+        return Ok(());
+    }
+    Err(DocumentDBError::unauthorized(format!(
+        "Not authorized to write to {db}.{collection}"
+    )))
+}
+
+/// Returns whether an aggregate command ends in a `$out` or `$merge` stage, which writes.
+///
+/// # Errors
+/// Returns an error if the pipeline is malformed.
+pub fn has_output_stage(command: &RawDocument) -> Result<bool> {
+    Ok(output_stage(command, "")?.is_some())
+}
+
+/// Returns the terminal `$out` or `$merge` stage of an aggregate command, if it has one.
+///
+/// Malformed stages are left for the backend to reject.
+fn output_stage<'a>(command: &'a RawDocument, db: &'a str) -> Result<Option<OutputStage<'a>>> {
+    let Some(pipeline) = command.get("pipeline")?.and_then(RawBsonRef::as_array) else {
+        return Ok(None);
+    };
+    let Some(last) = pipeline.into_iter().last().transpose()? else {
+        return Ok(None);
+    };
+    let Some((stage, spec)) = last
+        .as_document()
+        .and_then(|stage| stage.into_iter().next())
+        .transpose()?
+    else {
+        return Ok(None);
+    };
+
+    Ok(match stage {
+        "$out" => {
+            let target = match spec {
+                RawBsonRef::String(collection) => Some((db, collection)),
+                RawBsonRef::Document(target) => {
+                    target.get_str("db").ok().zip(target.get_str("coll").ok())
+                }
+                _ => None,
+            };
+            Some(OutputStage::Out(target))
+        }
+        "$merge" => {
+            let target = match spec
+                .as_document()
+                .map(|spec| spec.get("into"))
+                .transpose()?
+            {
+                Some(Some(RawBsonRef::String(collection))) => Some((db, collection)),
+                Some(Some(RawBsonRef::Document(target))) => target
+                    .get_str("coll")
+                    .ok()
+                    .map(|collection| (target.get_str("db").unwrap_or(db), collection)),
+                _ => None,
+            };
+            Some(OutputStage::Merge(target))
+        }
+        _ => None,
+    })
 }
 
 pub async fn process_update(
     request_context: &RequestContext<'_>,
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
-    enable_write_procedures: bool,
-    enable_write_procedures_with_batch_commit: bool,
+    options: WriteOptions,
 ) -> Result<Response> {
-    let update_rows = pg_data_client
-        .execute_update(
-            request_context,
-            connection_context,
-            enable_write_procedures,
-            enable_write_procedures_with_batch_commit,
+    // The backend binds `let` for the `$expr` of each filter and for pipeline updates;
+    // reject undefined variables up front rather than per statement.
+    let_variables::check_write_variables(
+        request_context.payload.document(),
+        request_context.payload.extra(),
+        Some("updates"),
+        "q",
+        Some("u"),
+    )?;
+
+    let collated = statement_collation::apply(
+        request_context.payload.document(),
+        request_context.payload.extra(),
+        "updates",
+    )?;
+    let collated_request = collated.as_ref().map(|update| {
+        Request::Raw(
+            RequestType::Update,
+            &update.command,
+            update.extra.as_deref(),
         )
-        .await?;
+    });
+    let request_context = &request_context.with_payload(collated_request.as_ref());
 
-    PgResponse::new(update_rows)
-        .transform_write_errors(connection_context, request_context.activity_id)
+    let response = write_batch::process_write_batch(
+        request_context,
+        connection_context,
+        pg_data_client,
+        options,
+    )
+    .await?;
+    write_concern::acknowledge_write(request_context, connection_context, response).await
 }
 
 pub async fn process_list_databases(
@@ -107,19 +675,242 @@ pub async fn process_list_databases(
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
-    pg_data_client
+    let authorized_databases =
+        parse_authorized_option(request_context.payload(), "authorizedDatabases")?;
+
+    let Some(authorizer) = connection_context.service_context.database_authorizer() else {
+        return pg_data_client
+            .execute_list_databases(request_context, connection_context)
+            .await;
+    };
+
+    let username = connection_context.auth_state.username()?;
+    let restrict =
+        restrict_to_authorized_databases(authorized_databases, authorizer.is_admin(username))?;
+
+    let response = pg_data_client
         .execute_list_databases(request_context, connection_context)
-        .await
+        .await?;
+    if !restrict {
+        return Ok(response);
+    }
+
+    filter_authorized_databases(&response, authorizer, username)
+}
+
+/// Reads a bool option such as `authorizedDatabases` or `authorizedCollections`.
+fn parse_authorized_option(request: &Request<'_>, field: &str) -> Result<Option<bool>> {
+    match request.document().get(field)? {
+        Some(value) => convert_to_bool(value).map(Some).ok_or_else(|| {
+            DocumentDBError::type_mismatch(format!(
+                "{field} should be a bool, but got {:?}",
+                value.element_type()
+            ))
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Decides whether `listDatabases` must be limited to the databases the user is authorized for.
+///
+/// Admins see every database unless they ask for `authorizedDatabases: true`; other users
+/// always get the filtered list and may not request the full one.
+fn restrict_to_authorized_databases(
+    authorized_databases: Option<bool>,
+    is_admin: bool,
+) -> Result<bool> {
+    match authorized_databases {
+        Some(true) => Ok(true),
+        Some(false) if !is_admin => Err(DocumentDBError::documentdb_error(
+            ErrorCode::Unauthorized,
+            "Not authorized to list all databases; set authorizedDatabases to true.".to_owned(),
+        )),
+        Some(false) => Ok(false),
+        None => Ok(!is_admin),
+    }
 }
 
+/// Keeps the databases the authorizer grants `username`, with `totalSize` and `totalSizeMb`
+/// recomputed from their `sizeOnDisk` so that the sizes of hidden databases don't show.
+fn filter_authorized_databases(
+    response: &Response,
+    authorizer: &dyn DatabaseAuthorizer,
+    username: &str,
+) -> Result<Response> {
+    let response = response.as_raw_document()?;
+    let databases = match response.get("databases")? {
+        Some(RawBsonRef::Array(databases)) => {
+            retain_named(databases, |name| authorizer.is_authorized(username, name))?
+        }
+        _ => RawArrayBuf::new(),
+    };
+    let mut total_size = 0_i64;
+    for database in &databases {
+        if let Some(size) = database?
+            .as_document()
+            .and_then(|database| database.get("sizeOnDisk").ok().flatten())
+            .and_then(convert_to_f64)
+        {
+            #[expect(
+                clippy::cast_possible_truncation,
+                reason = "database sizes are whole numbers of bytes"
+            )]
+            let size = size as i64;
+            total_size += size;
+        }
+    }
+
+    let mut filtered = RawDocumentBuf::new();
+    for element in response {
+        let (key, value) = element?;
+        match key {
+            "databases" => filtered.append(key, databases.clone()),
+            "totalSize" => filtered.append(key, total_size),
+            "totalSizeMb" => filtered.append(key, total_size / (1024 * 1024)),
+            _ => filtered.append(key, value.to_raw_bson()),
+        }
+    }
+
+    Ok(Response::Raw(RawResponse(filtered)))
+}
+
+/// Keeps the documents of `entries` whose `name` satisfies `keep`.
+fn retain_named(entries: &RawArray, keep: impl Fn(&str) -> bool) -> Result<RawArrayBuf> {
+    let mut retained = RawArrayBuf::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.as_document().and_then(|doc| doc.get_str("name").ok());
+        if name.is_some_and(&keep) {
+            retained.push(entry.to_raw_bson());
+        }
+    }
+    Ok(retained)
+}
+
+/// Lists the collections of a database. `filter` and `nameOnly` travel in the spec and are
+/// applied by the backend, which returns every collection in the first batch.
+///
+/// With `authorizedCollections: true`, users other than admins only see the collections
+/// the configured authorizer grants them.
 pub async fn process_list_collections(
     request_context: &RequestContext<'_>,
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
-    pg_data_client
+    let authorized_collections =
+        parse_authorized_option(request_context.payload(), "authorizedCollections")?;
+
+    let response = pg_data_client
         .execute_list_collections(request_context, connection_context)
-        .await
+        .await?;
+
+    let Some(authorizer) = connection_context.service_context.database_authorizer() else {
+        return Ok(response);
+    };
+    let username = connection_context.auth_state.username()?;
+    if authorized_collections != Some(true) || authorizer.is_admin(username) {
+        return Ok(response);
+    }
+
+    filter_authorized_collections(&response, authorizer, username, request_context.info.db()?)
+}
+
+fn filter_authorized_collections(
+    response: &Response,
+    authorizer: &dyn DatabaseAuthorizer,
+    username: &str,
+    db: &str,
+) -> Result<Response> {
+    let mut filtered = RawDocumentBuf::new();
+    for element in response.as_raw_document()? {
+        let (key, value) = element?;
+        match (key, value) {
+            ("cursor", RawBsonRef::Document(cursor)) => {
+                let mut filtered_cursor = RawDocumentBuf::new();
+                for cursor_element in cursor {
+                    let (cursor_key, cursor_value) = cursor_element?;
+                    match (cursor_key, cursor_value) {
+                        ("firstBatch", RawBsonRef::Array(collections)) => {
+                            filtered_cursor.append(
+                                cursor_key,
+                                retain_named(collections, |name| {
+                                    authorizer.is_collection_authorized(username, db, name)
+                                })?,
+                            );
+                        }
+                        _ => filtered_cursor.append(cursor_key, cursor_value.to_raw_bson()),
+                    }
+                }
+                filtered.append(key, filtered_cursor);
+            }
+            _ => filtered.append(key, value.to_raw_bson()),
+        }
+    }
+
+    Ok(Response::Raw(RawResponse(filtered)))
+}
+
+/// The mode flags of a validate command.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ValidateOptions {
+    full: bool,
+    repair: bool,
+    metadata: bool,
+}
+
+fn parse_validate_options(command: &RawDocument) -> Result<ValidateOptions> {
+    let mut options = ValidateOptions::default();
+    for entry in command {
+        let (key, value) = entry?;
+        let flag = match key {
+            "full" => &mut options.full,
+            "repair" => &mut options.repair,
+            "metadata" => &mut options.metadata,
+            _ => continue,
+        };
+        *flag = convert_to_bool(value).ok_or(DocumentDBError::type_mismatch(format!(
+            "BSON field 'validate.{key}' is the wrong type '{:?}', expected types '[bool, long, int, decimal, double]'",
+            value.element_type()
+        )))?;
+    }
+    Ok(options)
+}
+
+/// Repairing rewrites collection data, so it is an administrative operation.
+fn validate_repair_allowed(options: &ValidateOptions, db: &str) -> Result<()> {
+    if options.repair && db != "admin" {
+        return Err(DocumentDBError::documentdb_error(
+            ErrorCode::Unauthorized,
+            "validate with repair may only be run against the admin database.".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Adds `nrecords` and `keysPerIndex` to the backend validate response.
+///
+/// The backend does not count index keys, so each index is reported with one key per record.
+fn shape_validate_response(validate: &RawDocument, nrecords: i64) -> Result<RawDocumentBuf> {
+    let mut keys_per_index = RawDocumentBuf::new();
+    if let Some(index_details) = validate
+        .get("indexDetails")?
+        .and_then(RawBsonRef::as_document)
+    {
+        for index in index_details {
+            keys_per_index.append(index?.0, nrecords);
+        }
+    }
+
+    let mut shaped = RawDocumentBuf::new();
+    for entry in validate {
+        let (key, value) = entry?;
+        shaped.append(key, value.to_raw_bson());
+        if key == "nIndexes" {
+            shaped.append("nrecords", nrecords);
+            shaped.append("keysPerIndex", keys_per_index.clone());
+        }
+    }
+    Ok(shaped)
 }
 
 pub async fn process_validate(
@@ -127,9 +918,30 @@ pub async fn process_validate(
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
-    pg_data_client
+    let options = parse_validate_options(request_context.payload.document())?;
+    validate_repair_allowed(&options, request_context.info.db()?)?;
+
+    // The backend reads the same flags from the command document.
+    let response = pg_data_client
         .execute_validate(request_context, connection_context)
-        .await
+        .await?;
+    if options.metadata {
+        return Ok(response);
+    }
+
+    let coll_stats = pg_data_client
+        .execute_coll_stats(request_context, 1.0, connection_context)
+        .await?;
+    let nrecords = match coll_stats.as_raw_document()?.get("count")? {
+        Some(RawBsonRef::Int32(count)) => i64::from(count),
+        Some(RawBsonRef::Int64(count)) => count,
+        _ => 0,
+    };
+
+    Ok(Response::Raw(RawResponse(shape_validate_response(
+        response.as_raw_document()?,
+        nrecords,
+    )?)))
 }
 
 pub async fn process_find_and_modify(
@@ -137,9 +949,21 @@ pub async fn process_find_and_modify(
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
-    pg_data_client
+    // The backend applies arrayFilters and upserts, returning the inserted document for
+    // `new: true`; reject identifiers it would accept but MongoDB doesn't.
+    array_filters::from_command(request_context.payload.document())?;
+    let_variables::check_write_variables(
+        request_context.payload.document(),
+        None,
+        None,
+        "query",
+        Some("update"),
+    )?;
+
+    let response = pg_data_client
         .execute_find_and_modify(request_context, connection_context)
-        .await
+        .await?;
+    write_concern::acknowledge_write(request_context, connection_context, response).await
 }
 
 pub async fn process_distinct(
@@ -147,11 +971,66 @@ pub async fn process_distinct(
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
+    // The collation travels to the backend with the command; reject bad ones up front.
+    Collation::from_command(request_context.payload.document())?;
+
     pg_data_client
         .execute_distinct_query(request_context, connection_context)
         .await
 }
 
+/// Whether a count asks for `estimate: true`, which answers from the collection's statistics
+/// instead of scanning it. An estimate covers the whole collection, so it can't be combined
+/// with a query, skip or limit.
+fn count_estimate_requested(command: &RawDocument) -> Result<bool> {
+    let Some(estimate) = command.get("estimate")? else {
+        return Ok(false);
+    };
+    let estimate = convert_to_bool(estimate).ok_or_else(|| {
+        DocumentDBError::type_mismatch(format!(
+            "Expected estimate to be a boolean but got {:?}",
+            estimate.element_type()
+        ))
+    })?;
+    if !estimate {
+        return Ok(false);
+    }
+
+    let has_query = command
+        .get_document("query")
+        .is_ok_and(|query| !query.is_empty());
+    let has_bound = ["skip", "limit"].iter().any(|key| {
+        command
+            .get(key)
+            .ok()
+            .flatten()
+            .and_then(convert_to_f64)
+            .is_some_and(|value| value != 0.0)
+    });
+    if has_query || has_bound {
+        return Err(DocumentDBError::bad_value(
+            "estimate cannot be combined with a query, skip or limit".to_owned(),
+        ));
+    }
+    Ok(true)
+}
+
+/// Shapes a count response from the collection's statistics, flagged as an estimate.
+fn estimated_count_response(coll_stats: &RawDocument) -> Result<RawDocumentBuf> {
+    let n = match coll_stats.get("count")? {
+        Some(RawBsonRef::Int32(count)) => i64::from(count),
+        Some(RawBsonRef::Int64(count)) => count,
+        _ => 0,
+    };
+    let mut response = i32::try_from(n).map_or_else(|_| rawdoc! { "n": n }, |n| rawdoc! { "n": n });
+    response.append("estimated", true);
+    response.append("ok", OK_SUCCEEDED);
+    Ok(response)
+}
+
+/// Counts the documents matching a count command. The count runs under the request's
+/// `maxTimeMS`, or the default operation timeout for reads, and fails with `ExceededTimeLimit` when
+/// it runs out rather than returning a partial count.
 pub async fn process_count(
     request_context: &RequestContext<'_>,
     connection_context: &ConnectionContext,
@@ -160,6 +1039,22 @@ pub async fn process_count(
     // we need to ensure that the collection is correctly set up before we can execute the count query
     request_context.info.collection()?;
 
+    // The hint travels to the backend, which plans the count with the hinted index.
+    let unhinted_count = hint::without_empty_hint(request_context.payload.document())?;
+    let unhinted_request = unhinted_count
+        .as_deref()
+        .map(|document| request_context.rewritten_payload(document));
+    let request_context = &request_context.with_payload(unhinted_request.as_ref());
+
+    if count_estimate_requested(request_context.payload.document())? {
+        let coll_stats = pg_data_client
+            .execute_coll_stats(request_context, 1.0, connection_context)
+            .await?;
+        return Ok(Response::Raw(RawResponse(estimated_count_response(
+            coll_stats.as_raw_document()?,
+        )?)));
+    }
+
     pg_data_client
         .execute_count_query(request_context, connection_context)
         .await
@@ -188,6 +1083,17 @@ fn convert_to_scale(scale: RawBsonRef) -> Result<f64> {
     }
 }
 
+/// Every size in the collStats response is divided by the scale, so it must be at least 1
+/// once truncated.
+fn validate_coll_stats_scale(scale: f64) -> Result<f64> {
+    if scale.trunc() < 1.0 {
+        return Err(DocumentDBError::bad_value(format!(
+            "The BSON field 'scale' must have a value of at least 1, but the provided value is '{scale}'."
+        )));
+    }
+    Ok(scale)
+}
+
 pub async fn process_coll_stats(
     request_context: &RequestContext<'_>,
     connection_context: &ConnectionContext,
@@ -195,7 +1101,7 @@ pub async fn process_coll_stats(
 ) -> Result<Response> {
     // allow floats and ints, the backend will truncate
     let scale = if let Some(scale) = request_context.payload.document().get("scale")? {
-        convert_to_scale(scale)?
+        validate_coll_stats_scale(convert_to_scale(scale)?)?
     } else {
         1.0
     };
@@ -222,14 +1128,35 @@ pub async fn process_db_stats(
         .await
 }
 
-pub async fn process_current_op(
-    request_context: &RequestContext<'_>,
-    connection_context: &ConnectionContext,
-    pg_data_client: &impl PgDataClient,
-) -> Result<Response> {
-    pg_data_client
-        .execute_current_op(request_context, connection_context)
-        .await
+/// The operation a `killOp` request targets.
+#[derive(Debug, PartialEq)]
+enum KillOpTarget {
+    /// A numeric opid assigned by this gateway at request intake.
+    Gateway(i64),
+    /// A "shardid:opid" string identifying a backend operation.
+    Backend(String),
+}
+
+/// Largest integer a double represents exactly, 2^53.
+const MAX_SAFE_DOUBLE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+fn parse_kill_op_target(value: RawBsonRef<'_>) -> Result<KillOpTarget> {
+    match value {
+        RawBsonRef::String(op) => Ok(KillOpTarget::Backend(op.to_owned())),
+        RawBsonRef::Int32(op) => Ok(KillOpTarget::Gateway(i64::from(op))),
+        RawBsonRef::Int64(op) => Ok(KillOpTarget::Gateway(op)),
+        #[expect(
+            clippy::cast_possible_truncation,
+            reason = "only integral values exactly representable as f64 are accepted"
+        )]
+        RawBsonRef::Double(op) if op.fract() == 0.0 && op.abs() <= MAX_SAFE_DOUBLE_INTEGER => {
+            Ok(KillOpTarget::Gateway(op as i64))
+        }
+        _ => Err(DocumentDBError::type_mismatch(format!(
+            "Expected \"op\" field to be a string or an integer, but got {:?}",
+            value.element_type()
+        ))),
+    }
 }
 
 pub async fn process_kill_op(
@@ -239,23 +1166,15 @@ pub async fn process_kill_op(
 ) -> Result<Response> {
     let (request, request_info, _) = request_context.get_components();
 
-    let mut operation_id: Option<String> = None;
+    let mut target = None;
     request.extract_fields(|key, value| {
         if key == "op" {
-            // The "op" field contains the operation ID to kill
-            if let Some(op_str) = value.as_str() {
-                operation_id = Some(op_str.to_owned());
-            } else {
-                return Err(DocumentDBError::type_mismatch(format!(
-                    "Expected \"op\" field to be a string, but got {:?}",
-                    value.element_type()
-                )));
-            }
+            target = Some(parse_kill_op_target(value)?);
         }
         Ok(())
     })?;
 
-    let op_id = operation_id
+    let target = target
         .ok_or_else(|| DocumentDBError::bad_value("Did not provide \"op\" field".to_owned()))?;
 
     // Validate that the command is run against the admin database
@@ -266,107 +1185,973 @@ pub async fn process_kill_op(
         ));
     }
 
-    pg_data_client
-        .execute_kill_op(request_context, &op_id, connection_context)
-        .await
+    match target {
+        KillOpTarget::Gateway(op_id) => {
+            let found = connection_context
+                .service_context
+                .operation_registry()
+                .kill(op_id);
+            Ok(Response::Raw(RawResponse(rawdoc! {
+                "info": "attempting to kill op",
+                "target": "gateway",
+                "found": found,
+                "ok": OK_SUCCEEDED,
+            })))
+        }
+        KillOpTarget::Backend(op_id) => {
+            let response = pg_data_client
+                .execute_kill_op(request_context, &op_id, connection_context)
+                .await?;
+            let mut acknowledgment = response.as_raw_document()?.to_raw_document_buf();
+            acknowledgment.append("target", "backend");
+            Ok(Response::Raw(RawResponse(acknowledgment)))
+        }
+    }
 }
 
-async fn get_parameter(
-    connection_context: &ConnectionContext,
-    request_context: &RequestContext<'_>,
-    all: bool,
-    show_details: bool,
-    params: Vec<String>,
-    pg_data_client: &impl PgDataClient,
-) -> Result<Response> {
-    pg_data_client
-        .execute_get_parameter(
-            request_context,
-            all,
-            show_details,
-            params,
-            connection_context,
-        )
-        .await
+/// Compaction blocks the collection, so like Mongo on a primary it must be forced.
+/// A dry run only estimates the freed space and needs no force.
+fn validate_compact_force(command: &RawDocument) -> Result<()> {
+    let mut force = false;
+    let mut dry_run = false;
+    for entry in command {
+        let (key, value) = entry?;
+        let flag = match key {
+            "force" => &mut force,
+            "dryRun" => &mut dry_run,
+            _ => continue,
+        };
+        *flag = value
+            .as_bool()
+            .ok_or(DocumentDBError::type_mismatch(format!(
+                "BSON field 'compact.{key}' is the wrong type '{:?}', expected type 'bool'",
+                value.element_type()
+            )))?;
+    }
+
+    if force || dry_run {
+        Ok(())
+    } else {
+        Err(DocumentDBError::documentdb_error(
+            ErrorCode::IllegalOperation,
+            "will not run compact on an active replica set primary as this is a slow blocking operation. use force:true to force".to_owned(),
+        ))
+    }
 }
 
-pub async fn process_get_parameter(
+pub async fn process_compact(
     request_context: &RequestContext<'_>,
     connection_context: &ConnectionContext,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
-    let (request, request_info, _) = request_context.get_components();
+    validate_compact_force(request_context.payload.document())?;
 
-    let mut all_parameters = false;
-    let mut show_details = false;
-    let mut star = false;
-    let mut params = Vec::new();
-    request.extract_fields(|k, v| {
-        match k {
-            "getParameter" => {
-                if v.as_str().is_some_and(|s| s == "*") {
-                    star = true;
-                } else if let Some(doc) = v.as_document() {
-                    for pair in doc {
-                        let (k, v) = pair?;
-                        match k {
-                            "allParameters" => {
-                                all_parameters =
-                                    convert_to_bool(v).ok_or(DocumentDBError::type_mismatch(
-                                        "allParameters should be a bool".to_owned(),
-                                    ))?;
-                            }
-                            "showDetails" => {
-                                show_details =
-                                    convert_to_bool(v).ok_or(DocumentDBError::type_mismatch(
-                                        "showDetails should be convertible to a bool".to_owned(),
-                                    ))?;
-                            }
-                            _ => {}
-                        }
-                    }
+    let ns = format!(
+        "{}.{}",
+        request_context.info.db()?,
+        request_context.info.collection()?
+    );
+    let compaction = connection_context
+        .service_context
+        .operation_registry()
+        .register(
+            OperationInfo::new(
+                RequestType::Compact,
+                ns.clone(),
+                connection_context.connection_id,
+                connection_context.ip_address.clone(),
+            )
+            .with_desc(format!("compact {ns} on maintenance connection")),
+        );
+
+    tokio::select! {
+        response = pg_data_client.execute_compact(request_context, connection_context) => response,
+        () = compaction.killed() => Err(DocumentDBError::documentdb_error(
+            ErrorCode::ExceededTimeLimit,
+            format!("Compaction of {ns} was interrupted by killOp."),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+    use crate::{
+        configuration::BackendRoute,
+        context::RequestMemory,
+        protocol::{header::Header, opcode::OpCode, reader::parse_cmd},
+        requests::{request_tracker::RequestTracker, special_values::SpecialValuePolicy},
+        responses,
+        testing::{self, MapConfiguration, StubCall, StubDataClient},
+    };
+
+    #[test]
+    fn natural_hint_becomes_sort_direction() {
+        for (hint, direction) in [(1, 1), (-1, -1)] {
+            let spec = natural_hint_find(&rawdoc! {
+                "find": "c",
+                "filter": { "a": 1 },
+                "hint": { "$natural": hint },
+                "$db": "db",
+            })
+            .unwrap()
+            .unwrap();
+
+            assert_eq!(
+                spec,
+                rawdoc! {
+                    "find": "c",
+                    "filter": { "a": 1 },
+                    "$db": "db",
+                    "sort": { "$natural": direction },
                 }
+            );
+        }
+    }
+
+    #[test]
+    fn natural_hint_keeps_skip_and_limit_for_the_backend() {
+        let spec = natural_hint_find(&rawdoc! {
+            "find": "c",
+            "hint": { "$natural": 1 },
+            "skip": 5,
+            "limit": 10,
+            "batchSize": 2,
+        })
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(spec.get_i32("skip").unwrap(), 5);
+        assert_eq!(spec.get_i32("limit").unwrap(), 10);
+        assert_eq!(spec.get_i32("batchSize").unwrap(), 2);
+        assert_eq!(
+            spec.get_document("sort").unwrap(),
+            rawdoc! { "$natural": 1 }.as_ref()
+        );
+    }
+
+    #[test]
+    fn natural_hint_with_sort_on_other_field_is_rejected() {
+        let error = natural_hint_find(&rawdoc! {
+            "find": "c",
+            "hint": { "$natural": -1 },
+            "sort": { "a": 1 },
+        })
+        .unwrap_err();
+
+        assert_eq!(error.error_code_enum(), Some(ErrorCode::BadValue));
+    }
+
+    #[test]
+    fn find_without_natural_hint_is_unchanged() {
+        for document in [
+            rawdoc! { "find": "c" },
+            rawdoc! { "find": "c", "hint": { "a": 1 } },
+            rawdoc! { "find": "c", "hint": "a_1" },
+            rawdoc! { "find": "c", "hint": { "$natural": 1 }, "sort": { "$natural": 1 } },
+        ] {
+            assert!(
+                natural_hint_find(&document).unwrap().is_none(),
+                "{document:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn kill_op_accepts_numeric_opid() {
+        for value in [
+            RawBsonRef::Int32(7),
+            RawBsonRef::Int64(7),
+            RawBsonRef::Double(7.0),
+        ] {
+            assert_eq!(
+                parse_kill_op_target(value).unwrap(),
+                KillOpTarget::Gateway(7)
+            );
+        }
+        assert_eq!(
+            parse_kill_op_target(RawBsonRef::String("shard1:7")).unwrap(),
+            KillOpTarget::Backend("shard1:7".to_owned())
+        );
+    }
+
+    #[test]
+    fn kill_op_rejects_non_integral_opid() {
+        for value in [
+            RawBsonRef::Double(7.5),
+            RawBsonRef::Double(f64::NAN),
+            RawBsonRef::Boolean(true),
+        ] {
+            let err = parse_kill_op_target(value).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::TypeMismatch));
+        }
+    }
+
+    #[test]
+    fn compact_requires_force() {
+        let err = validate_compact_force(&rawdoc! { "compact": "coll" }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::IllegalOperation));
+        let err =
+            validate_compact_force(&rawdoc! { "compact": "coll", "force": false }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::IllegalOperation));
+        let err = validate_compact_force(&rawdoc! { "compact": "coll", "force": 1 }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::TypeMismatch));
+
+        validate_compact_force(&rawdoc! { "compact": "coll", "force": true }).unwrap();
+        validate_compact_force(&rawdoc! { "compact": "coll", "dryRun": true }).unwrap();
+    }
+
+    #[test]
+    fn validate_repair_requires_admin() {
+        let options = parse_validate_options(&rawdoc! {
+            "validate": "coll",
+            "full": true,
+            "repair": 1,
+        })
+        .unwrap();
+        assert_eq!(
+            options,
+            ValidateOptions {
+                full: true,
+                repair: true,
+                metadata: false,
             }
-            _ => params.push(k.to_owned()),
+        );
+
+        let err = validate_repair_allowed(&options, "sales").unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+        validate_repair_allowed(&options, "admin").unwrap();
+
+        let full_only =
+            parse_validate_options(&rawdoc! { "validate": "coll", "full": true }).unwrap();
+        validate_repair_allowed(&full_only, "sales").unwrap();
+
+        let err =
+            parse_validate_options(&rawdoc! { "validate": "coll", "repair": "yes" }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::TypeMismatch));
+    }
+
+    #[test]
+    fn validate_response_reports_records_and_keys() {
+        let backend = rawdoc! {
+            "ns": "db.coll",
+            "nIndexes": 2_i64,
+            "indexDetails": { "_id_": { "valid": true }, "a_1": { "valid": true } },
+            "valid": true,
+            "ok": 1.0,
+        };
+        let shaped = shape_validate_response(&backend, 5).unwrap();
+
+        assert_eq!(shaped.get_i64("nrecords").unwrap(), 5);
+        let keys = shaped.get_document("keysPerIndex").unwrap();
+        assert_eq!(keys.get_i64("_id_").unwrap(), 5);
+        assert_eq!(keys.get_i64("a_1").unwrap(), 5);
+        assert!(shaped.get_bool("valid").unwrap());
+    }
+
+    #[derive(Debug)]
+    struct StubAuthorizer;
+
+    impl DatabaseAuthorizer for StubAuthorizer {
+        fn is_admin(&self, username: &str) -> bool {
+            username == "root"
+        }
+
+        fn is_authorized(&self, username: &str, database: &str) -> bool {
+            username == "root" || database == "sales"
+        }
+
+        fn is_collection_authorized(
+            &self,
+            username: &str,
+            database: &str,
+            collection: &str,
+        ) -> bool {
+            self.is_authorized(username, database) && collection != "salaries"
         }
-        Ok(())
-    })?;
-    if request_info.db()? != "admin" {
-        return Err(DocumentDBError::documentdb_error(
-            ErrorCode::Unauthorized,
-            "getParameter may only be run against the admin database.".to_owned(),
-        ));
     }
 
-    if star {
-        return get_parameter(
-            connection_context,
-            request_context,
-            true,
-            false,
-            vec![],
-            pg_data_client,
+    fn list_databases_response() -> Response {
+        Response::Raw(RawResponse(rawdoc! {
+            "databases": [
+                { "name": "admin", "sizeOnDisk": 4096_i64 },
+                { "name": "sales", "sizeOnDisk": 3_145_728_i64 },
+                { "name": "hr", "sizeOnDisk": 8192.0 },
+            ],
+            "totalSize": 3_158_016_i64,
+            "totalSizeMb": 3_i64,
+            "ok": 1.0,
+        }))
+    }
+
+    fn database_names(response: &Response) -> Vec<String> {
+        response
+            .as_raw_document()
+            .unwrap()
+            .get_array("databases")
+            .unwrap()
+            .into_iter()
+            .map(|db| {
+                db.unwrap()
+                    .as_document()
+                    .unwrap()
+                    .get_str("name")
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect()
+    }
+
+    fn parse(command: &RawDocumentBuf) -> Result<Option<bool>> {
+        parse_authorized_option(
+            &Request::Raw(RequestType::ListDatabases, command, None),
+            "authorizedDatabases",
+        )
+    }
+
+    #[test]
+    fn authorized_databases_filters_through_authorizer() {
+        let restrict = restrict_to_authorized_databases(Some(true), false).unwrap();
+        assert!(restrict);
+
+        let filtered =
+            filter_authorized_databases(&list_databases_response(), &StubAuthorizer, "alice")
+                .unwrap();
+        assert_eq!(database_names(&filtered), ["sales"]);
+        let filtered = filtered.as_raw_document().unwrap();
+        assert_eq!(filtered.get_i64("totalSize").unwrap(), 3_145_728);
+        assert_eq!(filtered.get_i64("totalSizeMb").unwrap(), 3);
+        assert!(filtered.get("ok").unwrap().is_some());
+    }
+
+    #[test]
+    fn admin_sees_all_databases() {
+        assert!(!restrict_to_authorized_databases(None, true).unwrap());
+        assert!(!restrict_to_authorized_databases(Some(false), true).unwrap());
+
+        let filtered =
+            filter_authorized_databases(&list_databases_response(), &StubAuthorizer, "root")
+                .unwrap();
+        assert_eq!(database_names(&filtered), ["admin", "sales", "hr"]);
+        assert_eq!(
+            filtered
+                .as_raw_document()
+                .unwrap()
+                .get_i64("totalSize")
+                .unwrap(),
+            3_158_016
+        );
+    }
+
+    #[test]
+    fn non_admin_defaults_to_authorized_and_cannot_list_all() {
+        assert!(restrict_to_authorized_databases(None, false).unwrap());
+
+        let err = restrict_to_authorized_databases(Some(false), false).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+    }
+
+    async fn list_databases(user: &str) -> Vec<String> {
+        let service_context = testing::service_context_with_authorizer(
+            MapConfiguration::default(),
+            Some(Box::new(StubAuthorizer)),
         )
         .await;
+        let client = StubDataClient::new(service_context.clone());
+        client.reply_with(
+            "execute_list_databases",
+            list_databases_response()
+                .as_raw_document()
+                .unwrap()
+                .to_raw_document_buf(),
+        );
+        let connection_context = testing::connection_context(service_context, user);
+        let command = rawdoc! { "listDatabases": 1, "$db": "admin" };
+        let request = parse_cmd(&command, None).unwrap();
+        let info = request.extract_common().unwrap();
+        let request_context = RequestContext {
+            activity_id: "test",
+            payload: &request,
+            info: &info,
+            tracker: &RequestTracker::new(),
+            memory: &RequestMemory::default(),
+        };
+
+        let response = process_list_databases(&request_context, &connection_context, &client)
+            .await
+            .unwrap();
+        database_names(&response)
     }
 
-    get_parameter(
-        connection_context,
-        request_context,
-        all_parameters,
-        show_details,
-        params,
-        pg_data_client,
-    )
-    .await
-}
+    #[tokio::test]
+    async fn configured_authorizer_filters_listed_databases() {
+        assert_eq!(list_databases("alice").await, ["sales"]);
+        assert_eq!(list_databases("root").await, ["admin", "sales", "hr"]);
+    }
 
-pub async fn process_compact(
-    request_context: &RequestContext<'_>,
-    connection_context: &ConnectionContext,
-    pg_data_client: &impl PgDataClient,
-) -> Result<Response> {
-    pg_data_client
-        .execute_compact(request_context, connection_context)
-        .await
+    #[test]
+    fn authorized_collections_filters_first_batch() {
+        let response = Response::Raw(RawResponse(rawdoc! {
+            "cursor": {
+                "id": 0_i64,
+                "ns": "sales.$cmd.listCollections",
+                "firstBatch": [
+                    { "name": "orders", "type": "collection" },
+                    { "name": "salaries", "type": "collection" },
+                ],
+            },
+            "ok": 1.0,
+        }));
+
+        let filtered =
+            filter_authorized_collections(&response, &StubAuthorizer, "alice", "sales").unwrap();
+        let cursor = filtered
+            .as_raw_document()
+            .unwrap()
+            .get_document("cursor")
+            .unwrap();
+        let names: Vec<_> = cursor
+            .get_array("firstBatch")
+            .unwrap()
+            .into_iter()
+            .map(|collection| {
+                collection
+                    .unwrap()
+                    .as_document()
+                    .unwrap()
+                    .get_str("name")
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect();
+        assert_eq!(names, ["orders"]);
+        assert_eq!(cursor.get_str("ns").unwrap(), "sales.$cmd.listCollections");
+    }
+
+    #[test]
+    fn detects_terminal_output_stage() {
+        let out = rawdoc! {
+            "aggregate": "src",
+            "pipeline": [{ "$match": {} }, { "$out": "dst" }],
+        };
+        assert_eq!(
+            output_stage(&out, "db").unwrap(),
+            Some(OutputStage::Out(Some(("db", "dst"))))
+        );
+
+        let out_other_db = rawdoc! {
+            "aggregate": "src",
+            "pipeline": [{ "$out": { "db": "other", "coll": "dst" } }],
+        };
+        assert_eq!(
+            output_stage(&out_other_db, "db").unwrap(),
+            Some(OutputStage::Out(Some(("other", "dst"))))
+        );
+
+        let merge = rawdoc! {
+            "aggregate": "src",
+            "pipeline": [{ "$merge": { "into": "dst", "whenMatched": "merge" } }],
+        };
+        assert_eq!(
+            output_stage(&merge, "db").unwrap(),
+            Some(OutputStage::Merge(Some(("db", "dst"))))
+        );
+
+        let merge_other_db = rawdoc! {
+            "aggregate": "src",
+            "pipeline": [{ "$merge": { "into": { "db": "other", "coll": "dst" } } }],
+        };
+        assert_eq!(
+            output_stage(&merge_other_db, "db").unwrap(),
+            Some(OutputStage::Merge(Some(("other", "dst"))))
+        );
+    }
+
+    #[test]
+    fn unauthorized_output_target_is_rejected() {
+        let roles = vec!["app_role".to_owned()];
+        let no_allowlist = HashMap::<String, Vec<String>>::new();
+        let authorizer: &dyn DatabaseAuthorizer = &StubAuthorizer;
+
+        let salaries = OutputStage::Merge(Some(("sales", "salaries")));
+        let err = check_output_target(
+            &salaries,
+            &roles,
+            &no_allowlist,
+            Some((authorizer, "alice")),
+        )
+        .unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+        assert!(err.to_string().contains("sales.salaries"), "{err}");
+        check_output_target(&salaries, &roles, &no_allowlist, Some((authorizer, "root"))).unwrap();
+
+        let orders = OutputStage::Out(Some(("sales", "orders")));
+        check_output_target(&orders, &roles, &no_allowlist, Some((authorizer, "alice"))).unwrap();
+        check_output_target(&salaries, &roles, &no_allowlist, None).unwrap();
+
+        let read_only = HashMap::from([(
+            "app_role".to_owned(),
+            vec!["aggregate".to_owned(), "insert".to_owned()],
+        )]);
+        let err = check_output_target(&orders, &roles, &read_only, None).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+        assert!(err.to_string().contains("delete"), "{err}");
+    }
+
+    #[test]
+    fn ordinary_pipelines_have_no_output_stage() {
+        for command in [
+            rawdoc! { "aggregate": "src", "pipeline": [{ "$match": {} }] },
+            rawdoc! { "aggregate": "src", "pipeline": [] },
+            rawdoc! { "aggregate": "src", "pipeline": [{ "$out": "dst" }, { "$match": {} }] },
+        ] {
+            assert_eq!(output_stage(&command, "db").unwrap(), None);
+        }
+    }
+
+    fn stages(names: &[&str]) -> Vec<String> {
+        names.iter().map(|&name| name.to_owned()).collect()
+    }
+
+    #[test]
+    fn denied_top_level_stage_is_rejected() {
+        let command = rawdoc! {
+            "aggregate": "src",
+            "pipeline": [{ "$match": {} }, { "$merge": { "into": "dst" } }],
+        };
+
+        let err = check_pipeline_stages(&command, None, &stages(&["$merge"])).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+        assert!(err.to_string().contains("$merge"));
+
+        check_pipeline_stages(&command, None, &[]).unwrap();
+        check_pipeline_stages(&command, None, &stages(&["$out"])).unwrap();
+    }
+
+    #[test]
+    fn denied_stage_nested_in_facet_is_rejected() {
+        let command = rawdoc! {
+            "aggregate": "src",
+            "pipeline": [{
+                "$facet": {
+                    "counts": [{ "$count": "n" }],
+                    "joined": [{
+                        "$lookup": {
+                            "from": "other",
+                            "as": "o",
+                            "pipeline": [{ "$match": {} }, { "$sample": { "size": 1 } }],
+                        },
+                    }],
+                },
+            }],
+        };
+
+        let err = check_pipeline_stages(&command, None, &stages(&["$sample"])).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+        assert!(err.to_string().contains("$sample"));
+    }
+
+    #[test]
+    fn denied_operators_are_rejected_inside_stage_specs() {
+        let denylist = stages(&["$where", "$function"]);
+        let function = rawdoc! { "body": "function() { return 1; }", "args": [], "lang": "js" };
+
+        for (command, operator) in [
+            (
+                rawdoc! { "aggregate": "src", "pipeline": [{ "$match": { "$where": "true" } }] },
+                "$where",
+            ),
+            (
+                rawdoc! {
+                    "aggregate": "src",
+                    "pipeline": [{ "$match": { "$expr": { "$eq": [{ "$function": function.clone() }, 1] } } }],
+                },
+                "$function",
+            ),
+            (
+                rawdoc! {
+                    "aggregate": "src",
+                    "pipeline": [{ "$facet": { "f": [{ "$project": { "x": { "$function": function } } }] } }],
+                },
+                "$function",
+            ),
+        ] {
+            let err = check_pipeline_stages(&command, None, &denylist).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+            assert!(
+                err.to_string()
+                    .contains(&format!("operator '{operator}' is not allowed")),
+                "{err}"
+            );
+            // The allowlist names stages, so it says nothing about operators.
+            check_pipeline_stages(
+                &command,
+                Some(&stages(&["$match", "$facet", "$project"])),
+                &[],
+            )
+            .unwrap();
+        }
+
+        // Field paths and literal strings are values, not operators.
+        let command = rawdoc! {
+            "aggregate": "src",
+            "pipeline": [{ "$project": { "w": "$where", "f": { "$literal": "$function" } } }],
+        };
+        check_pipeline_stages(&command, None, &denylist).unwrap();
+    }
+
+    #[test]
+    fn cross_database_writes_need_a_privilege_on_the_target_database() {
+        let authorizer: &dyn DatabaseAuthorizer = &StubAuthorizer;
+        let merge_into = |db: &str| {
+            rawdoc! {
+                "aggregate": "src",
+                "pipeline": [{ "$merge": { "into": { "db": db, "coll": "dst" } } }],
+            }
+        };
+        let out_to = |db: &str| {
+            rawdoc! { "aggregate": "src", "pipeline": [{ "$out": { "db": db, "coll": "dst" } }] }
+        };
+
+        for command in [merge_into("hr"), out_to("hr")] {
+            let err = check_cross_database_writes(&command, "sales", Some((authorizer, "alice")))
+                .unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::Unauthorized));
+            assert!(err.to_string().contains("'hr'"), "{err}");
+
+            check_cross_database_writes(&command, "sales", Some((authorizer, "root"))).unwrap();
+            check_cross_database_writes(&command, "sales", None).unwrap();
+            // Writing within the request's own database is left to the output target check.
+            check_cross_database_writes(&command, "hr", Some((authorizer, "alice"))).unwrap();
+        }
+        check_cross_database_writes(&merge_into("sales"), "hr", Some((authorizer, "alice")))
+            .unwrap();
+    }
+
+    #[test]
+    fn allowlist_permits_only_listed_stages() {
+        let command = rawdoc! {
+            "aggregate": "src",
+            "pipeline": [{ "$match": {} }, { "$unionWith": { "coll": "other", "pipeline": [{ "$project": { "a": 1 } }] } }],
+        };
+
+        let allowed = stages(&["$match", "$unionWith", "$project"]);
+        check_pipeline_stages(&command, Some(&allowed), &[]).unwrap();
+
+        let err = check_pipeline_stages(&command, Some(&allowed[..2]), &[]).unwrap_err();
+        assert!(err.to_string().contains("$project"));
+
+        let err =
+            check_pipeline_stages(&command, Some(&allowed), &stages(&["$unionWith"])).unwrap_err();
+        assert!(err.to_string().contains("$unionWith"));
+    }
+
+    #[test]
+    fn cross_backend_pipeline_stages_are_not_supported() {
+        let router = BackendRouter::new(
+            &[BackendRoute {
+                database_prefix: Some("tenant_".to_owned()),
+                host_name: "tenant-host".to_owned(),
+                ..Default::default()
+            }],
+            9712,
+        )
+        .unwrap();
+        let lookup = |db: &str| {
+            rawdoc! {
+                "aggregate": "src",
+                "pipeline": [{ "$facet": { "joined": [
+                    { "$lookup": { "from": { "db": db, "coll": "c" }, "as": "c" } },
+                ] } }],
+            }
+        };
+
+        check_pipeline_backends(&lookup("tenant_b"), "tenant_a", &router).unwrap();
+        let err = check_pipeline_backends(&lookup("test"), "tenant_a", &router).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::CommandNotSupported));
+
+        for command in [
+            rawdoc! { "aggregate": "src", "pipeline": [{ "$out": { "db": "tenant_a", "coll": "c" } }] },
+            rawdoc! { "aggregate": "src", "pipeline": [{ "$merge": { "into": { "db": "tenant_a", "coll": "c" } } }] },
+            rawdoc! { "aggregate": "src", "pipeline": [{ "$unionWith": { "coll": { "db": "tenant_a", "coll": "c" } } }] },
+        ] {
+            check_pipeline_backends(&command, "test", &router).unwrap_err();
+            check_pipeline_backends(&command, "tenant_b", &router).unwrap();
+        }
+        // Same-database forms never cross backends.
+        check_pipeline_backends(
+            &rawdoc! { "aggregate": "src", "pipeline": [{ "$lookup": { "from": "c", "as": "c" } }, { "$out": "c" }] },
+            "tenant_a",
+            &router,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn count_estimate_is_opt_in() {
+        assert!(!count_estimate_requested(&rawdoc! { "count": "c" }).unwrap());
+        assert!(!count_estimate_requested(&rawdoc! { "count": "c", "estimate": false }).unwrap());
+        assert!(count_estimate_requested(
+            &rawdoc! { "count": "c", "estimate": true, "query": {}, "skip": 0 }
+        )
+        .unwrap());
+
+        let err =
+            count_estimate_requested(&rawdoc! { "count": "c", "estimate": "yes" }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::TypeMismatch));
+    }
+
+    #[test]
+    fn count_estimate_covers_the_whole_collection() {
+        for command in [
+            rawdoc! { "count": "c", "estimate": true, "query": { "a": 1 } },
+            rawdoc! { "count": "c", "estimate": true, "limit": 5 },
+            rawdoc! { "count": "c", "estimate": true, "skip": 1 },
+        ] {
+            let err = count_estimate_requested(&command).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+        }
+    }
+
+    #[test]
+    fn estimated_count_is_flagged() {
+        let response =
+            estimated_count_response(&rawdoc! { "ns": "db.c", "count": 42_i64, "size": 1024 })
+                .unwrap();
+        assert_eq!(response.get_i32("n").unwrap(), 42);
+        assert!(response.get_bool("estimated").unwrap());
+        assert!((response.get_f64("ok").unwrap() - OK_SUCCEEDED).abs() < f64::EPSILON);
+
+        let response = estimated_count_response(&rawdoc! { "count": i64::MAX }).unwrap();
+        assert_eq!(response.get_i64("n").unwrap(), i64::MAX);
+    }
+
+    #[test]
+    fn coll_stats_scale_must_be_at_least_one() {
+        for scale in [1.0, 1.9, 1024.0] {
+            validate_coll_stats_scale(scale).unwrap();
+        }
+        for scale in [0.0, 0.5, -1.0, -1024.0] {
+            let err = validate_coll_stats_scale(scale).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+        }
+    }
+
+    #[test]
+    fn authorized_databases_must_be_boolean() {
+        assert_eq!(
+            parse(&rawdoc! { "listDatabases": 1, "authorizedDatabases": true }).unwrap(),
+            Some(true)
+        );
+        assert_eq!(parse(&rawdoc! { "listDatabases": 1 }).unwrap(), None);
+
+        let err = parse(&rawdoc! { "listDatabases": 1, "authorizedDatabases": "yes" }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::TypeMismatch));
+    }
+
+    async fn write(command: &RawDocument) -> Vec<StubCall> {
+        let service_context = testing::service_context(MapConfiguration::default()).await;
+        let client = StubDataClient::new(service_context.clone());
+        let connection_context = testing::connection_context(service_context, "user");
+        let request = parse_cmd(command, None).unwrap();
+        let info = request.extract_common().unwrap();
+        let request_context = RequestContext {
+            activity_id: "test",
+            payload: &request,
+            info: &info,
+            tracker: &RequestTracker::new(),
+            memory: &RequestMemory::default(),
+        };
+        let options = WriteOptions {
+            enable_write_procedures: false,
+            enable_write_procedures_with_batch_commit: false,
+            commit_size: 0,
+            special_values: SpecialValuePolicy::default(),
+        };
+
+        // The stub fails the write itself, after recording what was sent.
+        let result = match request.request_type() {
+            RequestType::Insert => {
+                process_insert(&request_context, &connection_context, &client, options).await
+            }
+            _ => process_update(&request_context, &connection_context, &client, options).await,
+        };
+        result.unwrap_err();
+        client.calls()
+    }
+
+    /// Runs `command` through `process_insert` with the write options `configuration` sets,
+    /// returning how many documents each insert sent to the backend held.
+    async fn insert_chunks(configuration: &[(&str, &str)], command: &RawDocument) -> Vec<usize> {
+        let service_context = testing::service_context(MapConfiguration::new(configuration)).await;
+        let client = StubDataClient::new(service_context.clone());
+        let connection_context = testing::connection_context(service_context, "user");
+        let request = parse_cmd(command, None).unwrap();
+        let info = request.extract_common().unwrap();
+        let request_context = RequestContext {
+            activity_id: "test",
+            payload: &request,
+            info: &info,
+            tracker: &RequestTracker::new(),
+            memory: &RequestMemory::default(),
+        };
+        let options =
+            WriteOptions::from_configuration(connection_context.dynamic_configuration().as_ref());
+
+        // The stub fails every insert outright, so the batch ends with its first chunk, whose
+        // error is returned as is since nothing was committed before it.
+        let err = process_insert(&request_context, &connection_context, &client, options)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not answered"), "{err}");
+        client
+            .calls()
+            .iter()
+            .map(|call| {
+                call.command
+                    .get_array("documents")
+                    .unwrap()
+                    .into_iter()
+                    .count()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn insert_is_split_by_the_configured_commit_size() {
+        let command = rawdoc! {
+            "insert": "c",
+            "documents": [{ "_id": 0 }, { "_id": 1 }, { "_id": 2 }, { "_id": 3 }, { "_id": 4 }],
+            "$db": "db",
+        };
+
+        assert_eq!(insert_chunks(&[], &command).await, [5]);
+        assert_eq!(
+            insert_chunks(&[("writeBatchCommitSize", "2")], &command).await,
+            [2]
+        );
+        assert_eq!(
+            insert_chunks(
+                &[("writeBatchCommitSize", "2"), ("writeBatchAtomic", "true")],
+                &command
+            )
+            .await,
+            [5]
+        );
+    }
+
+    #[tokio::test]
+    async fn distinct_values_reach_the_client_with_their_types() {
+        let values = rawdoc! {
+            "values": [
+                1_i32,
+                1_i64,
+                1.0_f64,
+                "1",
+                null,
+                { "a": { "b": [1_i32, "x"] } },
+                [1_i32, { "c": null }],
+            ],
+            "ok": 1.0,
+        };
+        let service_context = testing::service_context(MapConfiguration::default()).await;
+        let client = StubDataClient::new(service_context.clone());
+        client.reply_with("execute_distinct_query", values.clone());
+        let connection_context = testing::connection_context(service_context, "user");
+        let command = rawdoc! { "distinct": "c", "key": "a", "$db": "db" };
+        let request = parse_cmd(&command, None).unwrap();
+        let info = request.extract_common().unwrap();
+        let request_context = RequestContext {
+            activity_id: "test",
+            payload: &request,
+            info: &info,
+            tracker: &RequestTracker::new(),
+            memory: &RequestMemory::default(),
+        };
+
+        let response = process_distinct(&request_context, &connection_context, &client)
+            .await
+            .unwrap();
+        assert_eq!(client.methods(), ["execute_distinct_query"]);
+
+        let header = Header {
+            length: 0,
+            request_id: 2,
+            response_to: 1,
+            op_code: OpCode::Msg,
+        };
+        let (mut gateway, mut driver) = tokio::io::duplex(1024);
+        responses::writer::write(&header, &response, &mut gateway)
+            .await
+            .unwrap();
+        let message = testing::read_message(&mut driver).await;
+        // Skip the header, the OP_MSG flags and the section kind byte.
+        let body = RawDocumentBuf::from_bytes(message[Header::LENGTH + 5..].to_vec()).unwrap();
+        let received: Vec<_> = body
+            .get_array("values")
+            .unwrap()
+            .into_iter()
+            .map(std::result::Result::unwrap)
+            .collect();
+        let sent: Vec<_> = values
+            .get_array("values")
+            .unwrap()
+            .into_iter()
+            .map(std::result::Result::unwrap)
+            .collect();
+        assert_eq!(received, sent);
+        assert_eq!(
+            received
+                .iter()
+                .map(RawBsonRef::element_type)
+                .collect::<Vec<_>>(),
+            [
+                ElementType::Int32,
+                ElementType::Int64,
+                ElementType::Double,
+                ElementType::String,
+                ElementType::Null,
+                ElementType::EmbeddedDocument,
+                ElementType::Array,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn command_collation_is_applied_to_update_statements_only() {
+        let collation = rawdoc! { "locale": "en", "strength": 2 };
+
+        let update = rawdoc! {
+            "update": "c",
+            "updates": [{ "q": { "a": "x" }, "u": { "$set": { "b": 1 } } }],
+            "collation": collation.clone(),
+            "$db": "db",
+        };
+        let calls = write(&update).await;
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].method, "execute_update");
+        let statement = calls[0].command.get_array("updates").unwrap();
+        let statement = statement.into_iter().next().unwrap().unwrap();
+        assert_eq!(
+            statement
+                .as_document()
+                .unwrap()
+                .get_document("collation")
+                .unwrap(),
+            &*collation
+        );
+
+        let insert = rawdoc! {
+            "insert": "c",
+            "documents": [{ "a": "x" }],
+            "collation": collation.clone(),
+            "$db": "db",
+        };
+        let calls = write(&insert).await;
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].method, "execute_insert");
+        assert_eq!(calls[0].command, insert);
+    }
 }
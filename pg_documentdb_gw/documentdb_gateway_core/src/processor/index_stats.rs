@@ -0,0 +1,146 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/processor/index_stats.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::{rawdoc, DateTime, RawArrayBuf, RawBsonRef, RawDocument, RawDocumentBuf};
+
+use crate::{
+    context::{ConnectionContext, RequestContext},
+    error::{DocumentDBError, ErrorCode, Result},
+    postgres::{PgDataClient, PgDocument},
+    protocol::OK_SUCCEEDED,
+    responses::{RawResponse, Response},
+};
+
+/// Returns whether the aggregate's pipeline starts with `$indexStats`, which must then be its
+/// only stage and have an empty specification.
+///
+/// # Errors
+/// Returns an error if a leading `$indexStats` is malformed or followed by other stages.
+pub fn is_index_stats(command: &RawDocument) -> Result<bool> {
+    let Some(pipeline) = command.get("pipeline")?.and_then(RawBsonRef::as_array) else {
+        return Ok(false);
+    };
+    let mut stages = pipeline.into_iter();
+    let Some(first) = stages.next().transpose()?.and_then(RawBsonRef::as_document) else {
+        return Ok(false);
+    };
+    let Some(("$indexStats", spec)) = first.into_iter().next().transpose()? else {
+        return Ok(false);
+    };
+
+    if !spec.as_document().is_some_and(RawDocument::is_empty) {
+        return Err(DocumentDBError::bad_value(
+            "The $indexStats stage specification must be an empty object".to_owned(),
+        ));
+    }
+    if stages.next().is_some() {
+        return Err(DocumentDBError::documentdb_error(
+            ErrorCode::CommandNotSupported,
+            "$indexStats is only supported as the only stage of a pipeline".to_owned(),
+        ));
+    }
+    Ok(true)
+}
+
+/// The `$indexStats` output for one index. `ops` counts the backend's scans of the index
+/// since `since`, the last reset of its statistics.
+fn index_stats_document(name: &str, key: &RawDocument, ops: i64, since_ms: i64) -> RawDocumentBuf {
+    rawdoc! {
+        "name": name,
+        "key": key.to_raw_document_buf(),
+        "accesses": {
+            "ops": ops,
+            "since": DateTime::from_millis(since_ms),
+        },
+    }
+}
+
+/// Runs an aggregate of `[{$indexStats: {}}]`, returning a document per index in a single
+/// batch. Indexes the backend has no usage statistics for report no accesses.
+pub async fn process_index_stats(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+    pg_data_client: &impl PgDataClient,
+) -> Result<Response> {
+    let rows = pg_data_client
+        .execute_index_stats(request_context, connection_context)
+        .await?;
+
+    let mut first_batch = RawArrayBuf::new();
+    for row in &rows {
+        let name: String = row.try_get(0)?;
+        let key: PgDocument = row.try_get(1)?;
+        first_batch.push(index_stats_document(
+            &name,
+            key.0,
+            row.try_get(2)?,
+            row.try_get(3)?,
+        ));
+    }
+
+    let db = request_context.info.db()?;
+    let collection = request_context.info.collection()?;
+    Ok(Response::Raw(RawResponse(rawdoc! {
+        "cursor": {
+            "firstBatch": first_batch,
+            "id": 0_i64,
+            "ns": format!("{db}.{collection}"),
+        },
+        "ok": OK_SUCCEEDED,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leading_index_stats_is_detected() {
+        assert!(
+            is_index_stats(&rawdoc! { "aggregate": "c", "pipeline": [{ "$indexStats": {} }] })
+                .unwrap()
+        );
+        assert!(
+            !is_index_stats(&rawdoc! { "aggregate": "c", "pipeline": [{ "$match": {} }] }).unwrap()
+        );
+        assert!(!is_index_stats(&rawdoc! { "aggregate": "c", "pipeline": [] }).unwrap());
+    }
+
+    #[test]
+    fn malformed_index_stats_is_rejected() {
+        let err = is_index_stats(
+            &rawdoc! { "aggregate": "c", "pipeline": [{ "$indexStats": { "a": 1 } }] },
+        )
+        .unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+
+        let err = is_index_stats(&rawdoc! {
+            "aggregate": "c",
+            "pipeline": [{ "$indexStats": {} }, { "$match": {} }],
+        })
+        .unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::CommandNotSupported));
+    }
+
+    #[test]
+    fn index_stats_document_has_accesses() {
+        let document = index_stats_document("a_1", &rawdoc! { "a": 1 }, 0, 1_700_000_000_000);
+
+        assert_eq!(document.get_str("name").unwrap(), "a_1");
+        assert_eq!(
+            document.get_document("key").unwrap().to_raw_document_buf(),
+            rawdoc! { "a": 1 }
+        );
+        let accesses = document.get_document("accesses").unwrap();
+        assert_eq!(accesses.get_i64("ops").unwrap(), 0);
+        assert_eq!(
+            accesses.get_datetime("since").unwrap(),
+            DateTime::from_millis(1_700_000_000_000)
+        );
+    }
+}
@@ -7,22 +7,185 @@
  */
 
 use std::{
-    sync::Arc,
-    time::{SystemTime, UNIX_EPOCH},
+    sync::{Arc, LazyLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use bson::rawdoc;
+use bson::{oid::ObjectId, rawdoc, RawBsonRef, RawDocumentBuf};
+use tokio::sync::watch;
 
 use crate::{
-    configuration::DynamicConfiguration,
+    configuration::{DynamicConfiguration, ServerDescription, SetupConfiguration},
     context::{ConnectionContext, RequestContext},
     error::{DocumentDBError, ErrorCode, Result},
-    protocol::{MAX_BSON_OBJECT_SIZE, MAX_MESSAGE_SIZE_BYTES, OK_SUCCEEDED},
+    protocol::{self, OK_SUCCEEDED},
+    requests::{Request, RequestType},
     responses::{RawResponse, Response},
+    telemetry::client_info::ClientInformation,
 };
 
+pub const LOGICAL_SESSION_TIMEOUT_MINUTES: i32 = protocol::LOGICAL_SESSION_TIMEOUT_MINUTES as i32;
+
+/// Identifies this gateway process in `topologyVersion`; drivers treat a new one as a restart.
+static TOPOLOGY_PROCESS_ID: LazyLock<ObjectId> = LazyLock::new(ObjectId::new);
+
+/// Counts topology changes of this process, waking awaitable hellos on every change.
+static TOPOLOGY_COUNTER: LazyLock<watch::Sender<i64>> = LazyLock::new(|| watch::Sender::new(0));
+
+fn topology_version() -> RawDocumentBuf {
+    rawdoc! {
+        "processId": *TOPOLOGY_PROCESS_ID,
+        "counter": topology_counter(),
+    }
+}
+
+fn topology_counter() -> i64 {
+    *TOPOLOGY_COUNTER.borrow()
+}
+
+/// Advances the `topologyVersion` counter, answering every awaiting hello at once.
+pub fn bump_topology_version() {
+    TOPOLOGY_COUNTER.send_modify(|counter| *counter += 1);
+}
+
+/// A `hello` or `isMaster` carrying `topologyVersion` and `maxAwaitTimeMS`, which is
+/// answered once the topology changes or the await time elapses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AwaitableHello {
+    process_id: ObjectId,
+    counter: i64,
+    max_await: Duration,
+}
+
+impl AwaitableHello {
+    /// Returns the await parameters of `request`, or `None` if it is answered at once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if only one of `topologyVersion` and `maxAwaitTimeMS` is given,
+    /// or either is malformed.
+    pub fn from_request(request: &Request<'_>) -> Result<Option<Self>> {
+        if !matches!(
+            request.request_type(),
+            RequestType::Hello | RequestType::IsMaster
+        ) {
+            return Ok(None);
+        }
+
+        let document = request.document();
+        let (topology_version, max_await_time_ms) = match (
+            document.get("topologyVersion")?,
+            document.get("maxAwaitTimeMS")?,
+        ) {
+            (None, None) => return Ok(None),
+            (Some(topology_version), Some(max_await_time_ms)) => {
+                (topology_version, max_await_time_ms)
+            }
+            (Some(_), None) => {
+                return Err(DocumentDBError::bad_value(
+                    "A request with a 'topologyVersion' must include 'maxAwaitTimeMS'".to_owned(),
+                ))
+            }
+            (None, Some(_)) => {
+                return Err(DocumentDBError::bad_value(
+                    "A request with 'maxAwaitTimeMS' must include a 'topologyVersion'".to_owned(),
+                ))
+            }
+        };
+
+        let RawBsonRef::Document(topology_version) = topology_version else {
+            return Err(DocumentDBError::type_mismatch(
+                "'topologyVersion' must be a document".to_owned(),
+            ));
+        };
+        let max_await_time_ms = match max_await_time_ms {
+            RawBsonRef::Int32(ms) => i64::from(ms),
+            RawBsonRef::Int64(ms) => ms,
+            _ => {
+                return Err(DocumentDBError::type_mismatch(
+                    "'maxAwaitTimeMS' must be an integer".to_owned(),
+                ))
+            }
+        };
+        let Ok(max_await_time_ms) = u64::try_from(max_await_time_ms) else {
+            return Err(DocumentDBError::bad_value(
+                "'maxAwaitTimeMS' must be non-negative".to_owned(),
+            ));
+        };
+
+        Ok(Some(Self {
+            process_id: topology_version.get_object_id("processId")?,
+            counter: topology_version.get_i64("counter")?,
+            max_await: Duration::from_millis(max_await_time_ms),
+        }))
+    }
+
+    /// Waits until the topology differs from the client's version or the await time elapses.
+    pub async fn wait(self) {
+        let mut counter = TOPOLOGY_COUNTER.subscribe();
+        if self.process_id != *TOPOLOGY_PROCESS_ID || *counter.borrow_and_update() != self.counter {
+            return;
+        }
+
+        // The sender is a static and never dropped, so `changed` only returns on a change.
+        let _ = tokio::time::timeout(self.max_await, counter.changed()).await;
+    }
+
+    /// Returns the await for the next streamed reply. Taken before a reply is built, so a
+    /// change that races with the reply is answered again rather than missed.
+    #[must_use]
+    pub fn next(self) -> Self {
+        Self {
+            process_id: *TOPOLOGY_PROCESS_ID,
+            counter: topology_counter(),
+            ..self
+        }
+    }
+}
+
+/// Builds the hello reply from gateway state only.
 #[expect(clippy::cast_possible_truncation, reason = "timestamp fits in u32")]
 #[expect(clippy::cast_sign_loss, reason = "timestamp is always positive")]
+fn hello_response(
+    writeable_primary_field: &str,
+    setup_configuration: &dyn SetupConfiguration,
+    dynamic_configuration: &dyn DynamicConfiguration,
+    connection_id: i32,
+    local_time: i64,
+) -> RawDocumentBuf {
+    let description = ServerDescription::advertised(setup_configuration, dynamic_configuration);
+    let mut response_doc = rawdoc! {
+        writeable_primary_field: true,
+        "msg": "isdbgrid",
+        "topologyVersion": topology_version(),
+        "maxBsonObjectSize": setup_configuration.max_bson_object_size(),
+        "maxMessageSizeBytes": setup_configuration.max_message_size_bytes(),
+        "maxWriteBatchSize": dynamic_configuration.max_write_batch_size(),
+        "localTime": local_time,
+        "logicalSessionTimeoutMinutes": LOGICAL_SESSION_TIMEOUT_MINUTES,
+        "minWireVersion": description.min_wire_version(),
+        "maxWireVersion": description.max_wire_version(),
+        "readOnly": dynamic_configuration.read_only(),
+        "connectionId": connection_id,
+        "saslSupportedMechs": ["SCRAM-SHA-256"],
+        "internal": dynamic_configuration.topology(),
+        "ok": OK_SUCCEEDED,
+    };
+
+    // Add the operationTime field if change streams GUC is enabled
+    if dynamic_configuration.enable_change_streams() {
+        response_doc.append(
+            "operationTime",
+            bson::Timestamp {
+                time: (local_time / 1000) as u32,
+                increment: (local_time % 1000) as u32,
+            },
+        );
+    }
+
+    response_doc
+}
+
 pub fn process(
     request_context: &RequestContext<'_>,
     writeable_primary_field: &str,
@@ -51,36 +214,204 @@ pub fn process(
                 "Client metadata cannot be mutated".to_owned(),
             ));
         }
+        connection_context.app_name = ClientInformation::parse_from_client_document(client)
+            .application_name()
+            .map(str::to_owned);
         connection_context.client_information = Some(client.to_raw_document_buf());
     }
 
-    let mut response_doc = rawdoc! {
-        writeable_primary_field: true,
-        "msg": "isdbgrid",
-        "maxBsonObjectSize": MAX_BSON_OBJECT_SIZE,
-        "maxMessageSizeBytes": MAX_MESSAGE_SIZE_BYTES,
-        "maxWriteBatchSize": dynamic_configuration.max_write_batch_size(),
-        "localTime": local_time,
-        "logicalSessionTimeoutMinutes": 30,
-        "minWireVersion": 0,
-        "maxWireVersion": dynamic_configuration.server_version().max_wire_protocol(),
-        "readOnly": dynamic_configuration.read_only(),
-        "connectionId": connection_context.get_connection_id_hash(),
-        "saslSupportedMechs": ["SCRAM-SHA-256"],
-        "internal": dynamic_configuration.topology(),
-        "ok": OK_SUCCEEDED,
-    };
+    Ok(Response::Raw(RawResponse(hello_response(
+        writeable_primary_field,
+        connection_context.service_context.setup_configuration(),
+        dynamic_configuration.as_ref(),
+        connection_context.get_connection_id_hash(),
+        local_time,
+    ))))
+}
 
-    // Add the operationTime field if change streams GUC is enabled
-    if dynamic_configuration.enable_change_streams() {
-        response_doc.append(
-            "operationTime",
-            bson::Timestamp {
-                time: (local_time / 1000) as u32,
-                increment: (local_time % 1000) as u32,
-            },
+#[cfg(test)]
+mod tests {
+    use bson::{rawbson, RawBson};
+
+    use super::*;
+    use crate::configuration::{DocumentDBSetupConfiguration, ServerDescriptionOptions};
+
+    #[derive(Debug)]
+    struct DefaultConfig;
+
+    impl DynamicConfiguration for DefaultConfig {
+        fn get_str(&self, _: &str) -> Option<String> {
+            None
+        }
+
+        fn get_bool(&self, _: &str, default: bool) -> bool {
+            default
+        }
+
+        fn get_i32(&self, _: &str, default: i32) -> i32 {
+            default
+        }
+
+        fn get_u64(&self, _: &str, default: u64) -> u64 {
+            default
+        }
+
+        fn equals_value(&self, _: &str, _: &str) -> bool {
+            false
+        }
+
+        fn topology(&self) -> RawBson {
+            rawbson!({})
+        }
+
+        fn enable_developer_explain(&self) -> bool {
+            false
+        }
+
+        fn max_connections(&self) -> usize {
+            0
+        }
+
+        fn allow_transaction_snapshot(&self) -> bool {
+            false
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    #[test]
+    fn hello_reports_limits_and_topology_version() {
+        let setup_configuration = DocumentDBSetupConfiguration {
+            max_bson_object_size: Some(32 * 1024 * 1024),
+            max_message_size_bytes: Some(64 * 1024 * 1024),
+            ..DocumentDBSetupConfiguration::default()
+        };
+        let counter = topology_counter();
+        let hello = hello_response(
+            "isWritablePrimary",
+            &setup_configuration,
+            &DefaultConfig,
+            7,
+            1_000,
         );
+
+        assert!(hello.get_bool("isWritablePrimary").unwrap());
+        assert_eq!(
+            hello.get_i32("maxBsonObjectSize").unwrap(),
+            32 * 1024 * 1024
+        );
+        assert_eq!(
+            hello.get_i32("maxMessageSizeBytes").unwrap(),
+            64 * 1024 * 1024
+        );
+        assert_eq!(hello.get_i32("maxWriteBatchSize").unwrap(), 100_000);
+        assert_eq!(
+            hello.get_i32("logicalSessionTimeoutMinutes").unwrap(),
+            LOGICAL_SESSION_TIMEOUT_MINUTES
+        );
+
+        let topology_version = hello.get_document("topologyVersion").unwrap();
+        assert_eq!(
+            topology_version.get_object_id("processId").unwrap(),
+            *TOPOLOGY_PROCESS_ID
+        );
+        // Another test may change the topology concurrently, but never backwards.
+        assert!(topology_version.get_i64("counter").unwrap() >= counter);
     }
 
-    Ok(Response::Raw(RawResponse(response_doc)))
+    #[test]
+    fn hello_reports_configured_wire_range() {
+        let hello = hello_response(
+            "isWritablePrimary",
+            &DocumentDBSetupConfiguration::default(),
+            &DefaultConfig,
+            7,
+            1_000,
+        );
+        assert_eq!(hello.get_i32("minWireVersion").unwrap(), 0);
+        assert_eq!(hello.get_i32("maxWireVersion").unwrap(), 21);
+
+        let setup_configuration = DocumentDBSetupConfiguration {
+            advertised_server_description: Some(
+                ServerDescription::new(&ServerDescriptionOptions {
+                    version: "5.0.26".to_owned(),
+                    min_wire_version: Some(6),
+                    max_wire_version: None,
+                })
+                .unwrap(),
+            ),
+            ..DocumentDBSetupConfiguration::default()
+        };
+        let hello = hello_response(
+            "isWritablePrimary",
+            &setup_configuration,
+            &DefaultConfig,
+            7,
+            1_000,
+        );
+        assert_eq!(hello.get_i32("minWireVersion").unwrap(), 6);
+        assert_eq!(hello.get_i32("maxWireVersion").unwrap(), 13);
+    }
+
+    fn awaitable_hello(counter: i64, max_await_time_ms: i64) -> AwaitableHello {
+        let command = rawdoc! {
+            "hello": 1,
+            "topologyVersion": { "processId": *TOPOLOGY_PROCESS_ID, "counter": counter },
+            "maxAwaitTimeMS": max_await_time_ms,
+        };
+        AwaitableHello::from_request(&Request::Raw(RequestType::Hello, &command, None))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn awaitable_hello_requires_both_fields() {
+        let plain = rawdoc! { "hello": 1 };
+        assert!(
+            AwaitableHello::from_request(&Request::Raw(RequestType::Hello, &plain, None))
+                .unwrap()
+                .is_none()
+        );
+
+        let without_version = rawdoc! { "hello": 1, "maxAwaitTimeMS": 10 };
+        AwaitableHello::from_request(&Request::Raw(RequestType::Hello, &without_version, None))
+            .unwrap_err();
+
+        let negative = rawdoc! {
+            "isMaster": 1,
+            "topologyVersion": { "processId": *TOPOLOGY_PROCESS_ID, "counter": 0_i64 },
+            "maxAwaitTimeMS": -1,
+        };
+        AwaitableHello::from_request(&Request::Raw(RequestType::IsMaster, &negative, None))
+            .unwrap_err();
+    }
+
+    // The only test that changes the topology, so no other test can wake its waits early.
+    #[tokio::test]
+    async fn awaitable_hello_waits_for_deadline_or_topology_change() {
+        let max_await = Duration::from_millis(100);
+        let current = awaitable_hello(topology_counter(), 100);
+
+        let start = tokio::time::Instant::now();
+        current.wait().await;
+        assert!(start.elapsed() >= max_await);
+
+        let waiting = tokio::spawn(awaitable_hello(topology_counter(), 60_000).wait());
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiting.is_finished());
+        bump_topology_version();
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // A client that is behind is answered at once.
+        let stale = awaitable_hello(topology_counter() - 1, 60_000);
+        tokio::time::timeout(Duration::from_secs(1), stale.wait())
+            .await
+            .unwrap();
+        assert_eq!(stale.next(), awaitable_hello(topology_counter(), 60_000));
+    }
 }
@@ -0,0 +1,234 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/processor/legacy_find.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::{rawdoc, RawBsonRef, RawDocument, RawDocumentBuf};
+
+use crate::{
+    bson::{convert_to_bool, convert_to_f64},
+    error::{DocumentDBError, Result},
+};
+
+/// Rewrites a find using `returnKey`, `showRecordId`, `min` or `max` without them, or returns
+/// `None` if it uses none of them.
+///
+/// `returnKey` becomes a projection of the hinted key pattern, and `min`/`max` become range
+/// conditions on the hinted field.
+///
+/// # Errors
+/// Returns `BadValue` if an option can't be translated: `returnKey`, `min` and `max` need a
+/// key-pattern `hint`, and `min`/`max` a single-field one. `showRecordId` is refused, as the
+/// backend doesn't return the record id of a document.
+pub fn rewrite_legacy_find(document: &RawDocument) -> Result<Option<RawDocumentBuf>> {
+    let return_key = flag(document, "returnKey")?;
+    let min = bound(document, "min")?;
+    let max = bound(document, "max")?;
+    let has_legacy_option = ["returnKey", "showRecordId", "min", "max"]
+        .iter()
+        .any(|key| document.get(key).ok().flatten().is_some());
+    if !has_legacy_option {
+        return Ok(None);
+    }
+
+    if flag(document, "showRecordId")? {
+        return Err(DocumentDBError::bad_value(
+            "showRecordId is not supported, as record ids are not exposed by the backend"
+                .to_owned(),
+        ));
+    }
+
+    let hint = document.get("hint")?.and_then(RawBsonRef::as_document);
+    let projection = if return_key {
+        Some(key_projection(hint.ok_or_else(|| {
+            DocumentDBError::bad_value(
+                "returnKey requires a hint naming the index by its key pattern".to_owned(),
+            )
+        })?)?)
+    } else {
+        None
+    };
+    let range = if min.is_some() || max.is_some() {
+        Some(bound_filter(hint, min, max)?)
+    } else {
+        None
+    };
+
+    let mut command = RawDocumentBuf::new();
+    for entry in document {
+        let (key, value) = entry?;
+        match key {
+            "returnKey" | "showRecordId" | "min" | "max" => {}
+            "projection" if projection.is_some() => {}
+            "filter" if range.is_some() => {}
+            _ => command.append(key, value.to_raw_bson()),
+        }
+    }
+    if let Some(projection) = projection {
+        command.append("projection", projection);
+    }
+    if let Some(range) = range {
+        let filter = document
+            .get_document("filter")
+            .map_or_else(|_| RawDocumentBuf::new(), RawDocument::to_raw_document_buf);
+        command.append("filter", rawdoc! { "$and": [filter, range] });
+    }
+
+    Ok(Some(command))
+}
+
+fn flag(document: &RawDocument, key: &str) -> Result<bool> {
+    Ok(document
+        .get(key)?
+        .and_then(convert_to_bool)
+        .unwrap_or_default())
+}
+
+fn bound<'a>(document: &'a RawDocument, key: &str) -> Result<Option<&'a RawDocument>> {
+    match document.get(key)? {
+        None => Ok(None),
+        Some(RawBsonRef::Document(bound)) => Ok(Some(bound)),
+        Some(_) => Err(DocumentDBError::bad_value(format!(
+            "'{key}' must be a document"
+        ))),
+    }
+}
+
+/// Projects the fields of `key_pattern`, and `_id` only if it is one of them.
+fn key_projection(key_pattern: &RawDocument) -> Result<RawDocumentBuf> {
+    let mut projection = RawDocumentBuf::new();
+    let mut has_id = false;
+    for entry in key_pattern {
+        let (field, _) = entry?;
+        has_id |= field == "_id";
+        projection.append(field, 1);
+    }
+    if !has_id {
+        projection.append("_id", 0);
+    }
+    Ok(projection)
+}
+
+/// Translates `min` (inclusive) and `max` (exclusive) bounds on a single-field index to a
+/// range filter. Bounds are in index order, so they swap for a descending index.
+fn bound_filter(
+    hint: Option<&RawDocument>,
+    min: Option<&RawDocument>,
+    max: Option<&RawDocument>,
+) -> Result<RawDocumentBuf> {
+    let hint = hint.ok_or_else(|| {
+        DocumentDBError::bad_value(
+            "min and max require a hint naming the index by its key pattern".to_owned(),
+        )
+    })?;
+    let mut fields = hint.into_iter();
+    let (Some((field, direction)), None) = (fields.next().transpose()?, fields.next()) else {
+        return Err(DocumentDBError::bad_value(
+            "min and max are only supported with a single-field index".to_owned(),
+        ));
+    };
+    let descending = convert_to_f64(direction).is_some_and(|direction| direction < 0.0);
+
+    let mut condition = RawDocumentBuf::new();
+    for (bound, ascending_op, descending_op) in [(min, "$gte", "$lte"), (max, "$lt", "$gt")] {
+        let Some(bound) = bound else {
+            continue;
+        };
+        let mut keys = bound.into_iter();
+        let (Some((key, value)), None) = (keys.next().transpose()?, keys.next()) else {
+            return Err(DocumentDBError::bad_value(format!(
+                "min and max must have the fields of the hinted index {{{field}: ...}}"
+            )));
+        };
+        if key != field {
+            return Err(DocumentDBError::bad_value(format!(
+                "min and max must have the fields of the hinted index, found '{key}' for '{field}'"
+            )));
+        }
+        let op = if descending {
+            descending_op
+        } else {
+            ascending_op
+        };
+        condition.append(op, value.to_raw_bson());
+    }
+
+    let mut filter = RawDocumentBuf::new();
+    filter.append(field, condition);
+    Ok(filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn show_record_id_is_refused() {
+        let err = rewrite_legacy_find(&rawdoc! { "find": "c", "showRecordId": true }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+        assert!(err.to_string().contains("showRecordId is not supported"));
+
+        let find = rewrite_legacy_find(&rawdoc! { "find": "c", "showRecordId": false }).unwrap();
+        assert_eq!(find, Some(rawdoc! { "find": "c" }));
+    }
+
+    #[test]
+    fn return_key_projects_only_the_hinted_keys() {
+        let find = rewrite_legacy_find(&rawdoc! {
+            "find": "c",
+            "projection": { "b": 1 },
+            "hint": { "a": 1, "b.c": -1 },
+            "returnKey": true,
+        })
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            find,
+            rawdoc! {
+                "find": "c",
+                "hint": { "a": 1, "b.c": -1 },
+                "projection": { "a": 1, "b.c": 1, "_id": 0 },
+            }
+        );
+
+        let err = rewrite_legacy_find(&rawdoc! { "find": "c", "returnKey": true }).unwrap_err();
+        assert!(err.to_string().contains("returnKey requires a hint"));
+    }
+
+    #[test]
+    fn min_and_max_become_a_range_on_the_hinted_field() {
+        let find = rewrite_legacy_find(&rawdoc! {
+            "find": "c",
+            "filter": { "b": 1 },
+            "hint": { "a": -1 },
+            "min": { "a": 10 },
+            "max": { "a": 5 },
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            find.get_document("filter").unwrap().to_raw_document_buf(),
+            rawdoc! { "$and": [{ "b": 1 }, { "a": { "$lte": 10, "$gt": 5 } }] }
+        );
+
+        let err = rewrite_legacy_find(&rawdoc! {
+            "find": "c",
+            "hint": { "a": 1, "b": 1 },
+            "min": { "a": 1, "b": 1 },
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("single-field index"));
+    }
+
+    #[test]
+    fn finds_without_legacy_options_are_unchanged() {
+        assert!(rewrite_legacy_find(&rawdoc! { "find": "c", "filter": {} })
+            .unwrap()
+            .is_none());
+    }
+}
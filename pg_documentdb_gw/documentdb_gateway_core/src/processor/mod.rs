@@ -6,16 +6,29 @@
  *-------------------------------------------------------------------------
  */
 
+mod conn_pool_stats;
 mod constant;
+mod current_op;
 mod cursor;
 mod data_description;
 mod data_management;
+mod index_stats;
 mod indexing;
 mod ismaster;
+mod legacy_find;
+mod parameters;
 mod process;
 mod roles;
 mod session;
+mod statement_collation;
+mod tailable;
 mod transaction;
 mod users;
+mod write_batch;
+mod write_concern;
 
-pub use process::process_request;
+pub(crate) use cursor::process_get_more;
+pub(crate) use data_management::has_output_stage;
+pub use ismaster::{bump_topology_version, AwaitableHello};
+pub use process::{process_gateway_only, process_request};
+pub(crate) use write_batch::sequence_documents;
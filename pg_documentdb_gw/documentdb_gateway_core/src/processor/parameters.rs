@@ -0,0 +1,722 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/processor/parameters.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::sync::Arc;
+
+use bson::{rawdoc, RawBson, RawBsonRef, RawDocument, RawDocumentBuf};
+
+use crate::{
+    bson::{convert_to_bool, convert_to_f64},
+    configuration::DynamicConfiguration,
+    context::{ConnectionContext, RequestContext},
+    error::{DocumentDBError, ErrorCode, Result},
+    postgres::{conn_mgmt::MAINTENANCE_MAX_CONNECTIONS, PgDataClient},
+    processor::data_description::GENERIC_COMMAND_FIELDS,
+    protocol::OK_SUCCEEDED,
+    responses::{RawResponse, Response},
+    telemetry::{log_level, traces, TelemetryConfig, TelemetryManager},
+};
+
+/// The configuration a gateway parameter is read from.
+struct ParameterSources {
+    dynamic: Arc<dyn DynamicConfiguration>,
+    telemetry: TelemetryConfig,
+}
+
+impl ParameterSources {
+    fn new(connection_context: &ConnectionContext) -> Self {
+        let service_context = &connection_context.service_context;
+        Self {
+            dynamic: service_context.dynamic_configuration(),
+            telemetry: TelemetryConfig::new(
+                service_context.setup_configuration().telemetry_options(),
+            ),
+        }
+    }
+}
+
+/// A parameter owned by the gateway rather than the backend.
+struct GatewayParameter {
+    name: &'static str,
+    settable_at_startup: bool,
+    value: fn(&ParameterSources) -> RawBson,
+    /// Applies a new value; `None` if the parameter can't be changed at runtime.
+    set: Option<fn(&ParameterSources, RawBsonRef<'_>) -> Result<()>>,
+}
+
+impl GatewayParameter {
+    const fn settable_at_runtime(&self) -> bool {
+        self.set.is_some()
+    }
+}
+
+fn usize_value(value: usize) -> RawBson {
+    RawBson::Int64(i64::try_from(value).unwrap_or(i64::MAX))
+}
+
+fn u64_value(value: u64) -> RawBson {
+    RawBson::Int64(i64::try_from(value).unwrap_or(i64::MAX))
+}
+
+/// Accepts Mongo's numeric verbosity or filter directives such as "info,tokio=warn".
+fn set_log_level(_: &ParameterSources, value: RawBsonRef<'_>) -> Result<()> {
+    let directives = match value {
+        RawBsonRef::String(directives) => directives,
+        RawBsonRef::Int32(verbosity) => log_level::verbosity_directives(i64::from(verbosity)),
+        RawBsonRef::Int64(verbosity) => log_level::verbosity_directives(verbosity),
+        _ => {
+            return Err(DocumentDBError::bad_value(
+                "logLevel must be a number or a log filter string".to_owned(),
+            ))
+        }
+    };
+    log_level::set_log_level(directives).map(|_| ())
+}
+
+fn set_sampling_ratio(_: &ParameterSources, value: RawBsonRef<'_>) -> Result<()> {
+    traces::set_sampling_ratio(convert_to_f64(value).ok_or(DocumentDBError::bad_value(
+        "tracingSamplingRatio must be a number".to_owned(),
+    ))?)
+}
+
+fn non_negative_integer(name: &str, value: RawBsonRef<'_>) -> Result<u64> {
+    match value {
+        RawBsonRef::Int32(value) => u64::try_from(value).ok(),
+        RawBsonRef::Int64(value) => u64::try_from(value).ok(),
+        _ => None,
+    }
+    .ok_or(DocumentDBError::bad_value(format!(
+        "{name} must be a non-negative integer"
+    )))
+}
+
+/// Sets the slow query log threshold; 0 turns slow query logging off.
+fn set_slow_op_threshold(sources: &ParameterSources, value: RawBsonRef<'_>) -> Result<()> {
+    let threshold =
+        i32::try_from(non_negative_integer("slowOpThresholdMs", value)?).map_err(|_overflow| {
+            DocumentDBError::bad_value("slowOpThresholdMs is too large".to_owned())
+        })?;
+    set_override(
+        sources,
+        "slowQueryLogIntervalInMilliseconds",
+        threshold.to_string(),
+    )
+}
+
+fn set_default_operation_timeout(sources: &ParameterSources, value: RawBsonRef<'_>) -> Result<()> {
+    let timeout = non_negative_integer("defaultOperationTimeoutMs", value)?;
+    set_override(sources, "defaultOperationTimeoutMs", timeout.to_string())
+}
+
+fn set_read_only_for_disk_full(sources: &ParameterSources, value: RawBsonRef<'_>) -> Result<()> {
+    let read_only = convert_to_bool(value).ok_or(DocumentDBError::bad_value(
+        "readOnlyForDiskFull must be a boolean".to_owned(),
+    ))?;
+    set_override(
+        sources,
+        "default_transaction_read_only",
+        read_only.to_string(),
+    )
+}
+
+fn set_override(sources: &ParameterSources, key: &str, value: String) -> Result<()> {
+    if sources.dynamic.set_override(key, value) {
+        Ok(())
+    } else {
+        Err(DocumentDBError::bad_value(format!(
+            "{key} can't be changed at runtime by this configuration"
+        )))
+    }
+}
+
+const GATEWAY_PARAMETERS: [GatewayParameter; 10] = [
+    GatewayParameter {
+        name: "logLevel",
+        settable_at_startup: true,
+        value: |_| RawBson::String(log_level::log_level()),
+        set: Some(set_log_level),
+    },
+    GatewayParameter {
+        name: "tracingSamplingRatio",
+        settable_at_startup: true,
+        value: |sources| RawBson::Double(sources.telemetry.tracing().effective_sampling_ratio()),
+        set: Some(set_sampling_ratio),
+    },
+    GatewayParameter {
+        name: "slowOpThresholdMs",
+        settable_at_startup: true,
+        value: |sources| RawBson::Int64(i64::from(sources.dynamic.slow_query_log_interval_ms())),
+        set: Some(set_slow_op_threshold),
+    },
+    GatewayParameter {
+        name: "defaultOperationTimeoutMs",
+        settable_at_startup: true,
+        value: |sources| u64_value(sources.dynamic.default_operation_timeout_ms()),
+        set: Some(set_default_operation_timeout),
+    },
+    GatewayParameter {
+        name: "readOnlyForDiskFull",
+        settable_at_startup: true,
+        value: |sources| RawBson::Boolean(sources.dynamic.is_read_only_for_disk_full()),
+        set: Some(set_read_only_for_disk_full),
+    },
+    GatewayParameter {
+        name: "maxOpenCursors",
+        settable_at_startup: true,
+        value: |sources| u64_value(sources.dynamic.max_open_cursors()),
+        set: None,
+    },
+    GatewayParameter {
+        name: "cursorBatchHighWaterMarkBytes",
+        settable_at_startup: true,
+        value: |sources| u64_value(sources.dynamic.cursor_batch_high_water_mark_bytes()),
+        set: None,
+    },
+    GatewayParameter {
+        name: "connectionPoolMaxConnections",
+        settable_at_startup: true,
+        value: |sources| usize_value(sources.dynamic.max_connections()),
+        set: None,
+    },
+    GatewayParameter {
+        name: "connectionPoolSystemConnectionBudget",
+        settable_at_startup: true,
+        value: |sources| usize_value(sources.dynamic.system_connection_budget()),
+        set: None,
+    },
+    GatewayParameter {
+        name: "connectionPoolMaintenanceMaxConnections",
+        settable_at_startup: false,
+        value: |_| usize_value(MAINTENANCE_MAX_CONNECTIONS),
+        set: None,
+    },
+];
+
+fn gateway_parameter(name: &str) -> Option<&'static GatewayParameter> {
+    GATEWAY_PARAMETERS.iter().find(|p| p.name == name)
+}
+
+/// The options of a getParameter command.
+#[derive(Debug, Default)]
+struct GetParameterOptions {
+    /// Set by `getParameter: "*"` or `allParameters: true`.
+    all: bool,
+    show_details: bool,
+    /// Restricts the result to parameters whose name starts with this, e.g. "connectionPool".
+    prefix: Option<String>,
+    /// The parameters requested by name as top-level fields.
+    names: Vec<String>,
+}
+
+impl GetParameterOptions {
+    const fn selects_all(&self) -> bool {
+        self.all || self.prefix.is_some()
+    }
+
+    fn selects(&self, name: &str) -> bool {
+        let requested = self.selects_all() || self.names.iter().any(|n| n == name);
+        requested && self.matches_prefix(name)
+    }
+
+    fn matches_prefix(&self, name: &str) -> bool {
+        self.prefix
+            .as_ref()
+            .is_none_or(|prefix| name.starts_with(prefix.as_str()))
+    }
+}
+
+fn parse_get_parameter(command: &RawDocument) -> Result<GetParameterOptions> {
+    let mut options = GetParameterOptions::default();
+    for entry in command {
+        let (key, value) = entry?;
+        if key == "getParameter" {
+            if value.as_str().is_some_and(|s| s == "*") {
+                options.all = true;
+            } else if let Some(doc) = value.as_document() {
+                parse_get_parameter_selection(doc, &mut options)?;
+            }
+        } else if !GENERIC_COMMAND_FIELDS.contains(&key) {
+            options.names.push(key.to_owned());
+        }
+    }
+    Ok(options)
+}
+
+fn parse_get_parameter_selection(
+    selection: &RawDocument,
+    options: &mut GetParameterOptions,
+) -> Result<()> {
+    for pair in selection {
+        let (k, v) = pair?;
+        match k {
+            "allParameters" => {
+                options.all = convert_to_bool(v).ok_or(DocumentDBError::type_mismatch(
+                    "allParameters should be a bool".to_owned(),
+                ))?;
+            }
+            "showDetails" => {
+                options.show_details = convert_to_bool(v).ok_or(DocumentDBError::type_mismatch(
+                    "showDetails should be convertible to a bool".to_owned(),
+                ))?;
+            }
+            "prefix" => {
+                options.prefix = Some(
+                    v.as_str()
+                        .ok_or(DocumentDBError::type_mismatch(
+                            "prefix should be a string".to_owned(),
+                        ))?
+                        .to_owned(),
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The selected gateway parameters, as values or detail documents when `showDetails` is set.
+fn gateway_parameters(options: &GetParameterOptions, sources: &ParameterSources) -> RawDocumentBuf {
+    let mut parameters = RawDocumentBuf::new();
+    for parameter in GATEWAY_PARAMETERS
+        .iter()
+        .filter(|p| options.selects(p.name))
+    {
+        let value = (parameter.value)(sources);
+        if options.show_details {
+            parameters.append(
+                parameter.name,
+                rawdoc! {
+                    "value": value,
+                    "settableAtRuntime": parameter.settable_at_runtime(),
+                    "settableAtStartup": parameter.settable_at_startup,
+                },
+            );
+        } else {
+            parameters.append(parameter.name, value);
+        }
+    }
+    parameters
+}
+
+/// Adds the gateway parameters to the backend reply ahead of `ok`, applying the prefix to both.
+fn merge_parameters(
+    backend: &RawDocument,
+    options: &GetParameterOptions,
+    gateway: &RawDocument,
+) -> Result<Response> {
+    let mut merged = RawDocumentBuf::new();
+    let mut ok = None;
+    for entry in backend {
+        let (key, value) = entry?;
+        if key == "ok" {
+            ok = Some(value.to_raw_bson());
+        } else if options.matches_prefix(key) {
+            merged.append(key, value.to_raw_bson());
+        }
+    }
+    for entry in gateway {
+        let (key, value) = entry?;
+        merged.append(key, value.to_raw_bson());
+    }
+    merged.append("ok", ok.unwrap_or(RawBson::Double(OK_SUCCEEDED)));
+    Ok(Response::Raw(RawResponse(merged)))
+}
+
+fn require_admin(request_context: &RequestContext<'_>, command: &str) -> Result<()> {
+    if request_context.info.db()? == "admin" {
+        Ok(())
+    } else {
+        Err(DocumentDBError::documentdb_error(
+            ErrorCode::Unauthorized,
+            format!("{command} may only be run against the admin database."),
+        ))
+    }
+}
+
+/// Reports backend parameters alongside the ones owned by the gateway.
+///
+/// Requests that only name gateway parameters are answered without the backend.
+pub async fn process_get_parameter(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+    pg_data_client: &impl PgDataClient,
+) -> Result<Response> {
+    let options = parse_get_parameter(request_context.payload.document())?;
+    require_admin(request_context, "getParameter")?;
+
+    let sources = ParameterSources::new(connection_context);
+    let gateway = gateway_parameters(&options, &sources);
+
+    let backend_names: Vec<String> = options
+        .names
+        .iter()
+        .filter(|name| gateway_parameter(name).is_none())
+        .cloned()
+        .collect();
+    if !options.selects_all() && !options.names.is_empty() && backend_names.is_empty() {
+        return merge_parameters(&RawDocumentBuf::new(), &options, &gateway);
+    }
+
+    let response = pg_data_client
+        .execute_get_parameter(
+            request_context,
+            options.selects_all(),
+            options.show_details,
+            backend_names,
+            connection_context,
+        )
+        .await?;
+    merge_parameters(response.as_raw_document()?, &options, &gateway)
+}
+
+/// Applies the parameters named in a setParameter command, returning the reply.
+///
+/// Like Mongo, every parameter is checked before any is applied, and `was` holds the
+/// prior value of the first one.
+fn set_parameters(command: &RawDocument, sources: &ParameterSources) -> Result<RawDocumentBuf> {
+    let mut changes = Vec::new();
+    for entry in command {
+        let (key, value) = entry?;
+        if key == "setParameter" || GENERIC_COMMAND_FIELDS.contains(&key) {
+            continue;
+        }
+        let parameter = gateway_parameter(key).ok_or_else(|| {
+            DocumentDBError::bad_value(format!(
+                "attempted to set unrecognized parameter [{key}], use help:true to see options"
+            ))
+        })?;
+        let set = parameter.set.ok_or_else(|| {
+            DocumentDBError::bad_value(format!("not allowed to change [{key}] at runtime"))
+        })?;
+        changes.push((parameter, set, value));
+    }
+    if changes.is_empty() {
+        return Err(DocumentDBError::bad_value(
+            "no option found to set, use help:true to see options".to_owned(),
+        ));
+    }
+
+    let mut was = None;
+    for (parameter, set, value) in changes {
+        let previous = (parameter.value)(sources);
+        set(sources, value)?;
+        tracing::info!(
+            "setParameter changed {} from {previous:?} to {value:?}.",
+            parameter.name
+        );
+        was.get_or_insert(previous);
+    }
+
+    let mut reply = RawDocumentBuf::new();
+    if let Some(was) = was {
+        reply.append("was", was);
+    }
+    reply.append("ok", OK_SUCCEEDED);
+    Ok(reply)
+}
+
+/// Changes gateway parameters for subsequent requests without a restart.
+pub fn process_set_parameter(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+) -> Result<Response> {
+    require_admin(request_context, "setParameter")?;
+    let sources = ParameterSources::new(connection_context);
+    Ok(Response::Raw(RawResponse(set_parameters(
+        request_context.payload.document(),
+        &sources,
+    )?)))
+}
+
+/// The `otlpEndpoint` a rotateTelemetry command re-points the exporters at, if any.
+fn parse_rotate_telemetry(command: &RawDocument) -> Result<Option<&str>> {
+    match command.get("otlpEndpoint")? {
+        None => Ok(None),
+        Some(RawBsonRef::String(endpoint)) => Ok(Some(endpoint)),
+        Some(_) => Err(DocumentDBError::bad_value(
+            "otlpEndpoint must be a string".to_owned(),
+        )),
+    }
+}
+
+/// Flushes the telemetry providers and, given an `otlpEndpoint`, re-points their OTLP
+/// exporters at it. What was buffered is flushed to the previous endpoint first; a failed
+/// flush doesn't prevent re-pointing, as the old collector may be the one that is down.
+pub async fn process_rotate_telemetry(request_context: &RequestContext<'_>) -> Result<Response> {
+    require_admin(request_context, "rotateTelemetry")?;
+    let endpoint = parse_rotate_telemetry(request_context.payload.document())?;
+    let manager = TelemetryManager::active().ok_or(DocumentDBError::documentdb_error(
+        ErrorCode::IllegalOperation,
+        "rotateTelemetry requires telemetry to be enabled.".to_owned(),
+    ))?;
+
+    // Flushing waits on the export threads.
+    let flush_manager = Arc::clone(&manager);
+    let flushed = tokio::task::spawn_blocking(move || flush_manager.force_flush())
+        .await
+        .map_err(|e| DocumentDBError::internal_error(format!("Telemetry flush failed: {e}")))?;
+
+    match endpoint {
+        None => flushed?,
+        Some(endpoint) => {
+            if let Err(e) = flushed {
+                tracing::warn!("Re-pointing telemetry to {endpoint} after a failed flush: {e}");
+            }
+            manager.reconfigure(manager.config().with_otlp_endpoint(endpoint))?;
+            tracing::info!("Telemetry exporters re-pointed to {endpoint}");
+        }
+    }
+    Ok(Response::Raw(RawResponse(rawdoc! { "ok": OK_SUCCEEDED })))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use bson::rawbson;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    /// Configuration holding only the values set at runtime.
+    #[derive(Debug, Default)]
+    struct TestConfig(Mutex<HashMap<String, String>>);
+
+    impl TestConfig {
+        fn value(&self, key: &str) -> Option<String> {
+            self.0.lock().unwrap().get(key).cloned()
+        }
+    }
+
+    impl DynamicConfiguration for TestConfig {
+        fn get_str(&self, key: &str) -> Option<String> {
+            self.value(key)
+        }
+
+        fn get_bool(&self, key: &str, default: bool) -> bool {
+            self.value(key).map_or(default, |v| v.parse().unwrap())
+        }
+
+        fn get_i32(&self, key: &str, default: i32) -> i32 {
+            self.value(key).map_or(default, |v| v.parse().unwrap())
+        }
+
+        fn get_u64(&self, key: &str, default: u64) -> u64 {
+            self.value(key).map_or(default, |v| v.parse().unwrap())
+        }
+
+        fn equals_value(&self, _: &str, _: &str) -> bool {
+            false
+        }
+
+        fn topology(&self) -> RawBson {
+            rawbson!({})
+        }
+
+        fn enable_developer_explain(&self) -> bool {
+            false
+        }
+
+        fn max_connections(&self) -> usize {
+            100
+        }
+
+        fn allow_transaction_snapshot(&self) -> bool {
+            false
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn set_override(&self, key: &str, value: String) -> bool {
+            self.0.lock().unwrap().insert(key.to_owned(), value);
+            true
+        }
+    }
+
+    fn sources() -> ParameterSources {
+        ParameterSources {
+            dynamic: Arc::new(TestConfig::default()),
+            telemetry: TelemetryConfig::new(None),
+        }
+    }
+
+    fn selected(command: &RawDocument) -> RawDocumentBuf {
+        gateway_parameters(&parse_get_parameter(command).unwrap(), &sources())
+    }
+
+    fn names(parameters: &RawDocument) -> Vec<String> {
+        parameters
+            .into_iter()
+            .map(|entry| entry.unwrap().0.to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn wildcard_includes_every_gateway_parameter() {
+        let parameters = selected(&rawdoc! { "getParameter": "*", "$db": "admin" });
+
+        assert_eq!(names(&parameters).len(), GATEWAY_PARAMETERS.len());
+        assert_eq!(
+            parameters.get_i64("connectionPoolMaxConnections").unwrap(),
+            100
+        );
+        parameters.get_str("logLevel").unwrap();
+    }
+
+    #[test]
+    fn gateway_parameter_is_returned_by_name() {
+        let parameters = selected(&rawdoc! {
+            "getParameter": 1,
+            "tracingSamplingRatio": 1,
+            "featureCompatibilityVersion": 1,
+            "$db": "admin",
+        });
+
+        assert_eq!(names(&parameters), ["tracingSamplingRatio"]);
+        parameters.get_f64("tracingSamplingRatio").unwrap();
+    }
+
+    #[test]
+    fn prefix_selects_a_family() {
+        let parameters = selected(&rawdoc! { "getParameter": { "prefix": "connectionPool" } });
+
+        assert_eq!(
+            names(&parameters),
+            [
+                "connectionPoolMaxConnections",
+                "connectionPoolSystemConnectionBudget",
+                "connectionPoolMaintenanceMaxConnections",
+            ]
+        );
+    }
+
+    #[test]
+    fn show_details_describes_each_parameter() {
+        let parameters = selected(&rawdoc! {
+            "getParameter": { "showDetails": true },
+            "slowOpThresholdMs": 1,
+        });
+        let details = parameters.get_document("slowOpThresholdMs").unwrap();
+
+        assert_eq!(details.get_i64("value").unwrap(), -1);
+        assert!(details.get_bool("settableAtRuntime").unwrap());
+        assert!(details.get_bool("settableAtStartup").unwrap());
+    }
+
+    #[test]
+    fn merge_filters_backend_parameters_by_prefix() {
+        let options =
+            parse_get_parameter(&rawdoc! { "getParameter": { "prefix": "connectionPool" } })
+                .unwrap();
+        let backend = rawdoc! { "featureCompatibilityVersion": "7.0", "ok": 1.0 };
+        let gateway = rawdoc! { "connectionPoolMaxConnections": 100_i64 };
+
+        let response = merge_parameters(&backend, &options, &gateway).unwrap();
+        let response = response.as_raw_document().unwrap();
+
+        assert_eq!(names(response), ["connectionPoolMaxConnections", "ok"]);
+        assert_eq!(
+            response.get("ok").unwrap(),
+            Some(RawBsonRef::Double(OK_SUCCEEDED))
+        );
+    }
+
+    #[test]
+    fn set_log_level_and_sampling_ratio() {
+        let _subscriber = tracing_subscriber::registry().with(log_level::reloadable_log_filter());
+        let sources = sources();
+
+        let reply = set_parameters(
+            &rawdoc! { "setParameter": 1, "logLevel": "debug" },
+            &sources,
+        )
+        .unwrap();
+        reply.get_str("was").unwrap();
+        assert_eq!(log_level::log_level(), "debug");
+
+        // The traces tests also only ever set the process-wide ratio to 0.
+        let reply = set_parameters(
+            &rawdoc! { "setParameter": 1, "tracingSamplingRatio": 0.0, "$db": "admin" },
+            &sources,
+        )
+        .unwrap();
+        reply.get_f64("was").unwrap();
+        assert_eq!(
+            selected(&rawdoc! { "getParameter": 1, "tracingSamplingRatio": 1 })
+                .get("tracingSamplingRatio")
+                .unwrap(),
+            Some(RawBsonRef::Double(0.0))
+        );
+    }
+
+    #[test]
+    fn set_parameter_returns_prior_value() {
+        let sources = sources();
+
+        let reply = set_parameters(
+            &rawdoc! { "setParameter": 1, "slowOpThresholdMs": 250 },
+            &sources,
+        )
+        .unwrap();
+
+        assert_eq!(reply.get_i64("was").unwrap(), -1);
+        assert_eq!(sources.dynamic.slow_query_log_interval_ms(), 250);
+    }
+
+    #[test]
+    fn default_operation_timeout_is_settable_at_runtime() {
+        let sources = sources();
+
+        let reply = set_parameters(
+            &rawdoc! { "setParameter": 1, "defaultOperationTimeoutMs": 30_000 },
+            &sources,
+        )
+        .unwrap();
+
+        assert_eq!(reply.get_i64("was").unwrap(), 0);
+        assert_eq!(sources.dynamic.default_operation_timeout_ms(), 30_000);
+    }
+
+    #[test]
+    fn unknown_or_read_only_parameter_is_rejected() {
+        let sources = sources();
+        for command in [
+            rawdoc! { "setParameter": 1, "notAParameter": 1 },
+            rawdoc! { "setParameter": 1, "slowOpThresholdMs": 250, "maxOpenCursors": 5 },
+            rawdoc! { "setParameter": 1 },
+            rawdoc! { "setParameter": 1, "slowOpThresholdMs": -1 },
+            rawdoc! { "setParameter": 1, "slowOpThresholdMs": i64::MAX },
+        ] {
+            let error = set_parameters(&command, &sources).unwrap_err();
+            assert_eq!(
+                error.error_code_enum(),
+                Some(ErrorCode::BadValue),
+                "{command:?}"
+            );
+        }
+        // Nothing is applied unless every parameter is valid.
+        assert_eq!(sources.dynamic.slow_query_log_interval_ms(), -1);
+    }
+
+    #[test]
+    fn rotate_telemetry_endpoint_must_be_a_string() {
+        let command = rawdoc! { "rotateTelemetry": 1 };
+        assert_eq!(parse_rotate_telemetry(&command).unwrap(), None);
+
+        let command = rawdoc! { "rotateTelemetry": 1, "otlpEndpoint": "http://collector:4317" };
+        assert_eq!(
+            parse_rotate_telemetry(&command).unwrap(),
+            Some("http://collector:4317")
+        );
+
+        let command = rawdoc! { "rotateTelemetry": 1, "otlpEndpoint": 4317 };
+        parse_rotate_telemetry(&command).unwrap_err();
+    }
+}
@@ -12,13 +12,46 @@ use crate::{
     explain,
     postgres::PgDataClient,
     processor::{
-        constant, cursor, data_description, data_management, indexing, ismaster, roles, session,
-        transaction, users,
+        conn_pool_stats, constant, current_op, cursor, data_description, data_management, indexing,
+        ismaster, parameters, roles, session, transaction, users, write_batch::WriteOptions,
     },
     requests::RequestType,
     responses::Response,
+    telemetry,
 };
 
+/// Answers commands that need only gateway state. Drivers and health checks send these
+/// at high frequency, so they never take a data client or wait on a pool connection.
+///
+/// # Errors
+///
+/// Returns an error if the command is not gateway-only or the reply can't be built.
+pub fn process_gateway_only(
+    request_context: &RequestContext<'_>,
+    connection_context: &mut ConnectionContext,
+) -> Result<Response> {
+    let dynamic_config = connection_context.dynamic_configuration();
+    match request_context.payload.request_type() {
+        RequestType::Hello => ismaster::process(
+            request_context,
+            "isWritablePrimary",
+            connection_context,
+            &dynamic_config,
+        ),
+        RequestType::IsMaster => ismaster::process(
+            request_context,
+            "ismaster",
+            connection_context,
+            &dynamic_config,
+        ),
+        request_type => constant::process_gateway_static(
+            request_type,
+            connection_context.service_context.setup_configuration(),
+            dynamic_config.as_ref(),
+        ),
+    }
+}
+
 #[expect(
     clippy::too_many_lines,
     reason = "complex logic that would lose clarity if split"
@@ -31,6 +64,10 @@ pub async fn process_request(
     connection_context: &mut ConnectionContext,
     pg_data_client: &impl PgDataClient,
 ) -> Result<Response> {
+    if request_context.payload.request_type().gateway_only() {
+        return process_gateway_only(request_context, connection_context);
+    }
+
     let dynamic_config = connection_context.dynamic_configuration();
 
     transaction::handle(request_context, connection_context, pg_data_client).await?;
@@ -40,7 +77,6 @@ pub async fn process_request(
             data_management::process_aggregate(request_context, connection_context, pg_data_client)
                 .await
         }
-        RequestType::BuildInfo => Ok(constant::process_build_info(&dynamic_config)),
         RequestType::CollStats => {
             data_management::process_coll_stats(request_context, connection_context, pg_data_client)
                 .await
@@ -49,6 +85,9 @@ pub async fn process_request(
             data_management::process_compact(request_context, connection_context, pg_data_client)
                 .await
         }
+        RequestType::ConnPoolStats => {
+            conn_pool_stats::process_conn_pool_stats(request_context, connection_context)
+        }
         RequestType::ConnectionStatus => {
             if dynamic_config.enable_connection_status() {
                 users::process_connection_status(
@@ -130,30 +169,17 @@ pub async fn process_request(
         RequestType::GetMore => {
             cursor::process_get_more(request_context, connection_context, pg_data_client).await
         }
-        RequestType::Hello => ismaster::process(
-            request_context,
-            "isWritablePrimary",
-            connection_context,
-            &dynamic_config,
-        ),
         RequestType::HostInfo => constant::process_host_info(),
         RequestType::Insert => {
             data_management::process_insert(
                 request_context,
                 connection_context,
                 pg_data_client,
-                dynamic_config.enable_write_procedures(),
-                dynamic_config.enable_write_procedures_with_batch_commit(),
+                WriteOptions::from_configuration(dynamic_config.as_ref()),
             )
             .await
         }
         RequestType::Isdbgrid => Ok(constant::process_is_db_grid(connection_context)),
-        RequestType::IsMaster => ismaster::process(
-            request_context,
-            "ismaster",
-            connection_context,
-            &dynamic_config,
-        ),
         RequestType::ListCollections => {
             data_management::process_list_collections(
                 request_context,
@@ -174,7 +200,6 @@ pub async fn process_request(
             indexing::process_list_indexes(request_context, connection_context, pg_data_client)
                 .await
         }
-        RequestType::Ping => Ok(constant::ok_response()),
         RequestType::SaslContinue | RequestType::SaslStart | RequestType::Logout => Err(
             DocumentDBError::internal_error("Command should have been handled by Auth".to_owned()),
         ),
@@ -183,8 +208,7 @@ pub async fn process_request(
                 request_context,
                 connection_context,
                 pg_data_client,
-                dynamic_config.enable_write_procedures(),
-                dynamic_config.enable_write_procedures_with_batch_commit(),
+                WriteOptions::from_configuration(dynamic_config.as_ref()),
             )
             .await
         }
@@ -209,7 +233,7 @@ pub async fn process_request(
             indexing::process_reindex(request_context, connection_context, pg_data_client).await
         }
         RequestType::CurrentOp => {
-            data_management::process_current_op(request_context, connection_context, pg_data_client)
+            current_op::process_current_op(request_context, connection_context, pg_data_client)
                 .await
         }
         RequestType::KillOp => {
@@ -221,13 +245,13 @@ pub async fn process_request(
                 .await
         }
         RequestType::GetParameter => {
-            data_management::process_get_parameter(
-                request_context,
-                connection_context,
-                pg_data_client,
-            )
-            .await
+            parameters::process_get_parameter(request_context, connection_context, pg_data_client)
+                .await
+        }
+        RequestType::SetParameter => {
+            parameters::process_set_parameter(request_context, connection_context)
         }
+        RequestType::RotateTelemetry => parameters::process_rotate_telemetry(request_context).await,
         RequestType::KillCursors => {
             cursor::process_kill_cursors(request_context, connection_context, pg_data_client).await
         }
@@ -250,6 +274,9 @@ pub async fn process_request(
         RequestType::EndSessions | RequestType::KillSessions => {
             session::end_or_kill_sessions(request_context, connection_context, pg_data_client).await
         }
+        RequestType::RefreshSessions => {
+            session::refresh_sessions(request_context, connection_context)
+        }
         RequestType::ReshardCollection => {
             data_description::process_shard_collection(
                 request_context,
@@ -340,13 +367,14 @@ pub async fn process_request(
             )
             .await
         }
-        _ => Err(DocumentDBError::documentdb_error(
-            ErrorCode::CommandNotSupported,
-            format!(
-                "Command '{}' not supported.",
-                request_context.payload.request_type().to_command_str()
-            ),
-        )),
+        _ => {
+            let command = request_context.payload.request_type().to_command_str();
+            telemetry::record_unsupported_command(command, true);
+            Err(DocumentDBError::documentdb_error(
+                ErrorCode::CommandNotSupported,
+                format!("Command '{command}' not supported."),
+            ))
+        }
     };
 
     if connection_context.transaction.is_some() {
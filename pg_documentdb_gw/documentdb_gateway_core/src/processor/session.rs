@@ -43,14 +43,11 @@ async fn terminate_sessions(
 ) -> Result<()> {
     let session_ids = parse_session_ids(sessions_field)?;
 
-    let transaction_store = connection_context.service_context.transaction_store();
-
     for session_id in &session_ids {
-        // Remove all cursors for the session
         let cursor_ids = connection_context
             .service_context
-            .cursor_store()
-            .invalidate_cursors_by_session(session_id);
+            .end_session(session_id)
+            .await?;
 
         if !cursor_ids.is_empty() {
             if let Err(e) = pg_data_client
@@ -60,11 +57,6 @@ async fn terminate_sessions(
                 tracing::warn!("Error killing cursors for session {:?}: {}", session_id, e);
             }
         }
-
-        // Best effort to remove any transaction for the session
-        let _ = transaction_store
-            .remove_transaction_by_session(session_id)
-            .await?;
     }
 
     Ok(())
@@ -98,3 +90,22 @@ pub async fn end_or_kill_sessions(
 
     Ok(Response::ok())
 }
+
+/// Keeps the listed sessions from being reaped.
+pub fn refresh_sessions(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+) -> Result<Response> {
+    let sessions_field = request_context
+        .payload
+        .document()
+        .get_array("refreshSessions")
+        .map_err(DocumentDBError::parse_failure())?;
+
+    let session_store = connection_context.service_context.session_store();
+    for session_id in parse_session_ids(sessions_field)? {
+        session_store.refresh(&session_id);
+    }
+
+    Ok(Response::ok())
+}
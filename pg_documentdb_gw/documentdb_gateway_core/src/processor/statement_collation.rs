@@ -0,0 +1,162 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/processor/statement_collation.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::{RawArrayBuf, RawBson, RawBsonRef, RawDocument, RawDocumentBuf};
+
+use crate::{error::Result, processor::write_batch, requests::collation::Collation};
+
+/// An update or delete with its command-level `collation` moved into its statements.
+#[derive(Debug)]
+pub struct StatementCollation {
+    pub command: RawDocumentBuf,
+    pub extra: Option<Vec<u8>>,
+}
+
+/// The statement with `collation` added, unless it already has its own.
+fn with_collation(statement: RawBsonRef<'_>, collation: &RawDocument) -> Result<RawBson> {
+    let Some(statement) = statement.as_document() else {
+        // Statements that are not documents are left for the backend to reject.
+        return Ok(statement.to_raw_bson());
+    };
+    if Collation::from_command(statement)?.is_some() {
+        return Ok(RawBson::Document(statement.to_raw_document_buf()));
+    }
+    let mut rewritten = statement.to_raw_document_buf();
+    rewritten.append("collation", collation.to_raw_document_buf());
+    Ok(RawBson::Document(rewritten))
+}
+
+/// Moves the command-level `collation` of an update or delete into each statement under
+/// `key` that has none of its own, whether the statements are held in the command or in
+/// the `extra` document sequence. The backend reads a write's collation per statement.
+///
+/// Returns `None` when the command has no command-level collation.
+///
+/// # Errors
+/// Returns an error if a collation is malformed or its locale is not supported.
+pub fn apply(
+    command: &RawDocument,
+    extra: Option<&[u8]>,
+    key: &str,
+) -> Result<Option<StatementCollation>> {
+    if Collation::from_command(command)?.is_none() {
+        return Ok(None);
+    }
+    let collation = command.get_document("collation")?;
+
+    if let Some(sequence) = extra {
+        let mut statements = Vec::with_capacity(sequence.len());
+        for document in write_batch::sequence_documents(sequence)? {
+            let statement = RawDocument::from_bytes(document)?;
+            if let RawBson::Document(statement) =
+                with_collation(RawBsonRef::Document(statement), collation)?
+            {
+                statements.extend_from_slice(statement.as_bytes());
+            }
+        }
+        return Ok(Some(StatementCollation {
+            command: without_collation(command, None)?,
+            extra: Some(statements),
+        }));
+    }
+
+    let mut statements = RawArrayBuf::new();
+    if let Some(array) = command.get(key)?.and_then(RawBsonRef::as_array) {
+        for statement in array {
+            statements.push(with_collation(statement?, collation)?);
+        }
+    }
+    Ok(Some(StatementCollation {
+        command: without_collation(command, Some((key, &statements)))?,
+        extra: None,
+    }))
+}
+
+/// The command without its `collation`, with the statements under `key` replaced if given.
+fn without_collation(
+    command: &RawDocument,
+    statements: Option<(&str, &RawArrayBuf)>,
+) -> Result<RawDocumentBuf> {
+    let mut rewritten = RawDocumentBuf::new();
+    for entry in command {
+        let (k, v) = entry?;
+        if k == "collation" {
+            continue;
+        }
+        match statements {
+            Some((key, statements)) if k == key => rewritten.append(k, statements.clone()),
+            _ => rewritten.append(k, v.to_raw_bson()),
+        }
+    }
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn command_collation_moves_into_statements() {
+        let command = rawdoc! {
+            "delete": "c",
+            "deletes": [
+                { "q": { "a": "abc" }, "limit": 0 },
+                { "q": { "a": "x" }, "limit": 1, "collation": { "locale": "fr" } },
+            ],
+            "collation": { "locale": "en", "strength": 2 },
+            "$db": "db",
+        };
+
+        let rewritten = apply(&command, None, "deletes").unwrap().unwrap();
+
+        assert_eq!(
+            rewritten.command,
+            rawdoc! {
+                "delete": "c",
+                "deletes": [
+                    {
+                        "q": { "a": "abc" },
+                        "limit": 0,
+                        "collation": { "locale": "en", "strength": 2 },
+                    },
+                    { "q": { "a": "x" }, "limit": 1, "collation": { "locale": "fr" } },
+                ],
+                "$db": "db",
+            }
+        );
+        assert!(rewritten.extra.is_none());
+    }
+
+    #[test]
+    fn command_collation_moves_into_document_sequence() {
+        let command = rawdoc! { "update": "c", "collation": { "locale": "en" } };
+        let extra = rawdoc! { "q": {}, "u": { "$set": { "b": 1 } } }.into_bytes();
+
+        let rewritten = apply(&command, Some(&extra), "updates").unwrap().unwrap();
+
+        assert_eq!(rewritten.command, rawdoc! { "update": "c" });
+        let statement = RawDocument::from_bytes(rewritten.extra.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            statement.get_document("collation").unwrap(),
+            rawdoc! { "locale": "en" }.as_ref()
+        );
+    }
+
+    #[test]
+    fn commands_without_collation_are_unchanged() {
+        let command = rawdoc! { "delete": "c", "deletes": [{ "q": {}, "limit": 0 }] };
+        assert!(apply(&command, None, "deletes").unwrap().is_none());
+
+        let invalid = rawdoc! { "delete": "c", "deletes": [], "collation": { "locale": "xx" } };
+        let err = apply(&invalid, None, "deletes").unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+    }
+}
@@ -0,0 +1,555 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/processor/tailable.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::time::Duration;
+
+use bson::{rawdoc, RawArrayBuf, RawBsonRef, RawDocument, RawDocumentBuf};
+use tokio::time::Instant;
+
+use crate::{
+    bson::{convert_to_bool, convert_to_f64},
+    context::{ConnectionContext, Cursor, CursorId, RequestContext, TailableCursor},
+    error::{DocumentDBError, ErrorCode, Result},
+    postgres::PgDataClient,
+    protocol::OK_SUCCEEDED,
+    requests::{Request, RequestType},
+    responses::{RawResponse, Response},
+};
+
+/// Batch size used when neither the find nor the getMore asks for one.
+const DEFAULT_BATCH_SIZE: i64 = 101;
+
+/// How long an awaitData getMore waits for new documents when it has no `maxTimeMS`.
+const DEFAULT_AWAIT_DATA_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often an awaitData getMore re-queries the collection while waiting.
+const AWAIT_DATA_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The most `_id`s a tailable cursor excludes from its queries. A cursor that has returned
+/// this many documents is closed, and the client must start a new one.
+const MAX_RETURNED_IDS: usize = 10_000;
+
+/// Find options carried over unchanged to every query of a tailable cursor.
+const PASSTHROUGH_OPTIONS: [&str; 3] = ["projection", "collation", "let"];
+
+/// A tailable find as requested by the client.
+#[derive(Debug)]
+pub struct TailableFind {
+    pub collection: String,
+    pub cursor: TailableCursor,
+    pub batch_size: Option<i64>,
+    pub skip: Option<i64>,
+}
+
+/// Parses the tailable options of a find, returning `None` for an ordinary find.
+///
+/// # Errors
+/// Returns an error if the options are malformed or cannot be combined with a tailable cursor.
+pub fn parse_tailable_find(document: &RawDocument) -> Result<Option<TailableFind>> {
+    let mut tailable = false;
+    let mut await_data = false;
+    let mut collection = None;
+    let mut filter = RawDocumentBuf::new();
+    let mut options = RawDocumentBuf::new();
+    let mut limit = None;
+    let mut batch_size = None;
+    let mut skip = None;
+    let mut single_batch = false;
+    let mut sort = None;
+
+    for entry in document {
+        let (k, v) = entry?;
+        match k {
+            "find" => collection = v.as_str(),
+            "tailable" => tailable = parse_flag(k, v)?,
+            "awaitData" => await_data = parse_flag(k, v)?,
+            "singleBatch" => single_batch = parse_flag(k, v)?,
+            "filter" => {
+                filter = v
+                    .as_document()
+                    .ok_or_else(|| {
+                        DocumentDBError::type_mismatch("filter must be an object".to_owned())
+                    })?
+                    .to_owned();
+            }
+            "sort" => sort = v.as_document(),
+            "limit" => limit = Some(parse_count(k, v)?).filter(|limit| *limit > 0),
+            "batchSize" => batch_size = Some(parse_count(k, v)?),
+            "skip" => skip = Some(parse_count(k, v)?),
+            key if PASSTHROUGH_OPTIONS.contains(&key) => options.append(key, v.to_raw_bson()),
+            _ => {}
+        }
+    }
+
+    if await_data && !tailable {
+        return Err(DocumentDBError::documentdb_error(
+            ErrorCode::FailedToParse,
+            "Cannot set 'awaitData' without also setting 'tailable'".to_owned(),
+        ));
+    }
+    if !tailable {
+        return Ok(None);
+    }
+    if single_batch {
+        return Err(DocumentDBError::bad_value(
+            "cannot use tailable option with the 'singleBatch' option".to_owned(),
+        ));
+    }
+    if let Some(sort) = sort {
+        validate_tailable_sort(sort)?;
+    }
+    if let Ok(projection) = options.get_document("projection") {
+        if let Ok(Some(id)) = projection.get("_id") {
+            if convert_to_bool(id) == Some(false) {
+                return Err(DocumentDBError::bad_value(
+                    "Tailable cursors cannot exclude _id from the projection".to_owned(),
+                ));
+            }
+        }
+    }
+
+    let collection = collection.ok_or_else(|| {
+        DocumentDBError::documentdb_error(
+            ErrorCode::InvalidNamespace,
+            "Invalid namespace".to_owned(),
+        )
+    })?;
+
+    Ok(Some(TailableFind {
+        collection: collection.to_owned(),
+        cursor: TailableCursor {
+            filter,
+            options,
+            returned_ids: Vec::new(),
+            remaining: limit,
+            await_data,
+        },
+        batch_size,
+        skip,
+    }))
+}
+
+fn parse_flag(key: &str, value: RawBsonRef) -> Result<bool> {
+    value.as_bool().ok_or_else(|| {
+        DocumentDBError::type_mismatch(format!("Field '{key}' should be a boolean value"))
+    })
+}
+
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "counts are whole numbers well within i64"
+)]
+fn parse_count(key: &str, value: RawBsonRef) -> Result<i64> {
+    let count = convert_to_f64(value).ok_or_else(|| {
+        DocumentDBError::type_mismatch(format!("Field '{key}' should be numeric"))
+    })?;
+    if count < 0.0 {
+        return Err(DocumentDBError::bad_value(format!(
+            "{key} value must be non-negative, but received: {count}"
+        )));
+    }
+    Ok(count as i64)
+}
+
+/// Tailable cursors return documents in insertion order, so only a `$natural` ascending sort is allowed.
+fn validate_tailable_sort(sort: &RawDocument) -> Result<()> {
+    let mut keys = sort.iter();
+    let is_natural = match keys.next().transpose()? {
+        None => true,
+        Some((key, value)) => {
+            key == "$natural" && convert_to_f64(value) == Some(1.0) && keys.next().is_none()
+        }
+    };
+    if is_natural {
+        Ok(())
+    } else {
+        Err(DocumentDBError::bad_value(
+            "cannot use tailable option with a sort other than {$natural: 1}".to_owned(),
+        ))
+    }
+}
+
+/// Builds the single-batch find that reads the next documents of a tailable cursor, in `_id`
+/// order and without those it returned.
+fn tailable_find_spec(
+    collection: &str,
+    db: &str,
+    cursor: &TailableCursor,
+    limit: i64,
+    skip: Option<i64>,
+) -> RawDocumentBuf {
+    let filter = if cursor.returned_ids.is_empty() {
+        cursor.filter.clone()
+    } else {
+        let returned_ids: RawArrayBuf = cursor.returned_ids.iter().cloned().collect();
+        rawdoc! {
+            "$and": [cursor.filter.clone(), { "_id": { "$nin": returned_ids } }],
+        }
+    };
+
+    let mut spec = rawdoc! {
+        "find": collection,
+        "filter": filter,
+        "sort": { "_id": 1 },
+        "limit": limit,
+        "singleBatch": true,
+    };
+    if let Some(skip) = skip {
+        spec.append("skip", skip);
+    }
+    for (key, value) in cursor.options.iter().flatten() {
+        spec.append(key, value.to_raw_bson());
+    }
+    spec.append("$db", db);
+    spec
+}
+
+/// The number of documents the next query of a tailable cursor may return.
+fn next_batch_limit(batch_size: Option<i64>, remaining: Option<i64>) -> i64 {
+    let batch_size = batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+    remaining.map_or(batch_size, |remaining| batch_size.min(remaining))
+}
+
+/// Runs one query for the tailable cursor and excludes the returned documents from later ones.
+async fn read_batch(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+    pg_data_client: &impl PgDataClient,
+    collection: &str,
+    cursor: &mut TailableCursor,
+    batch_size: Option<i64>,
+    skip: Option<i64>,
+) -> Result<RawArrayBuf> {
+    let limit = next_batch_limit(batch_size, cursor.remaining);
+    let mut batch = RawArrayBuf::new();
+    if limit == 0 {
+        return Ok(batch);
+    }
+
+    let spec = tailable_find_spec(collection, request_context.info.db()?, cursor, limit, skip);
+    let find_request = Request::Raw(RequestType::Find, &spec, None);
+    let find_request_context = request_context.with_payload(Some(&find_request));
+    let response = pg_data_client
+        .execute_find(&find_request_context, connection_context)
+        .await?;
+
+    let documents = response
+        .as_raw_document()?
+        .get_document("cursor")?
+        .get_array("firstBatch")?;
+    let mut count = 0;
+    for document in documents {
+        let document = document?;
+        if let Some(id) = document
+            .as_document()
+            .and_then(|d| d.get("_id").ok().flatten())
+        {
+            cursor.returned_ids.push(id.to_raw_bson());
+        }
+        batch.push(document.to_raw_bson());
+        count += 1;
+    }
+    if let Some(remaining) = cursor.remaining.as_mut() {
+        *remaining -= count;
+    }
+    Ok(batch)
+}
+
+/// A tailable cursor stays open once it runs out of documents, and only closes when its limit
+/// is reached or it can exclude no more `_id`s.
+const fn is_exhausted(cursor: &TailableCursor) -> bool {
+    matches!(cursor.remaining, Some(remaining) if remaining <= 0)
+        || cursor.returned_ids.len() >= MAX_RETURNED_IDS
+}
+
+fn cursor_response(
+    cursor_id: i64,
+    db: &str,
+    collection: &str,
+    batch_key: &str,
+    batch: RawArrayBuf,
+) -> Response {
+    let mut cursor = rawdoc! {};
+    cursor.append(batch_key, batch);
+    cursor.append("id", cursor_id);
+    cursor.append("ns", format!("{db}.{collection}"));
+    Response::Raw(RawResponse(rawdoc! {
+        "cursor": cursor,
+        "ok": OK_SUCCEEDED,
+    }))
+}
+
+fn cursor_timeout(connection_context: &ConnectionContext) -> Duration {
+    Duration::from_secs(
+        connection_context
+            .service_context
+            .dynamic_configuration()
+            .default_cursor_idle_timeout_sec(),
+    )
+}
+
+/// Serves a tailable find from the gateway: the first batch is read here, and the cursor is
+/// kept open afterwards so that later getMores can return documents inserted since.
+///
+/// # Errors
+/// Returns an error if the query fails.
+pub async fn process_tailable_find(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+    find: TailableFind,
+    pg_data_client: &impl PgDataClient,
+) -> Result<Response> {
+    let TailableFind {
+        collection,
+        mut cursor,
+        batch_size,
+        skip,
+    } = find;
+    let db = request_context.info.db()?;
+
+    let batch = read_batch(
+        request_context,
+        connection_context,
+        pg_data_client,
+        &collection,
+        &mut cursor,
+        batch_size,
+        skip,
+    )
+    .await?;
+
+    if is_exhausted(&cursor) {
+        return Ok(cursor_response(0, db, &collection, "firstBatch", batch));
+    }
+
+    let cursor_id = connection_context
+        .service_context
+        .cursor_store()
+        .allocate_cursor_id();
+    connection_context.add_cursor(
+        None,
+        Cursor {
+            continuation: RawDocumentBuf::new(),
+            cursor_id,
+            tailable: Some(cursor),
+            bytes_per_document: None,
+        },
+        connection_context.auth_state.username()?,
+        db,
+        &collection,
+        cursor_timeout(connection_context),
+        request_context.info.session_id.clone(),
+    );
+    Ok(cursor_response(
+        i64::from(cursor_id),
+        db,
+        &collection,
+        "firstBatch",
+        batch,
+    ))
+}
+
+/// Continues a tailable cursor. An awaitData cursor with no new documents re-queries until
+/// `maxTimeMS` elapses; either way the cursor stays valid when the batch comes back empty.
+///
+/// # Errors
+/// Returns an error if the getMore options are invalid for the cursor or the query fails.
+pub async fn process_tailable_get_more(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+    cursor_id: CursorId,
+    mut cursor: TailableCursor,
+    collection: &str,
+    pg_data_client: &impl PgDataClient,
+) -> Result<Response> {
+    let db = request_context.info.db()?;
+    let batch_size = match request_context.payload.document().get("batchSize")? {
+        Some(value) => Some(parse_count("batchSize", value)?),
+        None => None,
+    };
+    let wait = match (cursor.await_data, request_context.info.max_time_ms) {
+        (false, Some(_)) => {
+            return Err(DocumentDBError::bad_value(
+                "cannot set maxTimeMS on getMore command for a non-awaitData cursor".to_owned(),
+            ))
+        }
+        (false, None) => Duration::ZERO,
+        (true, Some(max_time_ms)) => Duration::from_millis(max_time_ms.try_into().unwrap_or(0)),
+        (true, None) => DEFAULT_AWAIT_DATA_TIMEOUT,
+    };
+
+    let deadline = Instant::now() + wait;
+    let batch = loop {
+        let batch = read_batch(
+            request_context,
+            connection_context,
+            pg_data_client,
+            collection,
+            &mut cursor,
+            batch_size,
+            None,
+        )
+        .await?;
+        let now = Instant::now();
+        if !batch.is_empty() || is_exhausted(&cursor) || now >= deadline {
+            break batch;
+        }
+        tokio::time::sleep(AWAIT_DATA_POLL_INTERVAL.min(deadline - now)).await;
+    };
+
+    if is_exhausted(&cursor) {
+        return Ok(cursor_response(0, db, collection, "nextBatch", batch));
+    }
+
+    connection_context.add_cursor(
+        None,
+        Cursor {
+            continuation: RawDocumentBuf::new(),
+            cursor_id,
+            tailable: Some(cursor),
+            bytes_per_document: None,
+        },
+        connection_context.auth_state.username()?,
+        db,
+        collection,
+        cursor_timeout(connection_context),
+        request_context.info.session_id.clone(),
+    );
+    Ok(cursor_response(
+        i64::from(cursor_id),
+        db,
+        collection,
+        "nextBatch",
+        batch,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bson::RawBson;
+
+    fn parse(document: &RawDocument) -> Result<Option<TailableFind>> {
+        parse_tailable_find(document)
+    }
+
+    #[test]
+    fn ordinary_find_is_not_tailable() {
+        assert!(parse(&rawdoc! { "find": "coll", "$db": "db" })
+            .unwrap()
+            .is_none());
+        assert!(parse(&rawdoc! { "find": "coll", "tailable": false })
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn await_data_requires_tailable() {
+        let err = parse(&rawdoc! { "find": "coll", "awaitData": true }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::FailedToParse));
+    }
+
+    #[test]
+    fn tailable_find_is_parsed() {
+        let find = parse(&rawdoc! {
+            "find": "coll",
+            "filter": { "a": 1 },
+            "projection": { "a": 1 },
+            "tailable": true,
+            "awaitData": true,
+            "limit": 5,
+            "batchSize": 2,
+            "sort": { "$natural": 1 },
+            "$db": "db",
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(find.collection, "coll");
+        assert_eq!(find.batch_size, Some(2));
+        assert_eq!(find.cursor.remaining, Some(5));
+        assert!(find.cursor.await_data);
+        assert_eq!(find.cursor.filter, rawdoc! { "a": 1 });
+        assert_eq!(find.cursor.options, rawdoc! { "projection": { "a": 1 } });
+    }
+
+    #[test]
+    fn tailable_find_rejects_incompatible_options() {
+        for document in [
+            rawdoc! { "find": "coll", "tailable": true, "sort": { "a": 1 } },
+            rawdoc! { "find": "coll", "tailable": true, "sort": { "$natural": -1 } },
+            rawdoc! { "find": "coll", "tailable": true, "singleBatch": true },
+            rawdoc! { "find": "coll", "tailable": true, "projection": { "_id": 0 } },
+        ] {
+            let err = parse(&document).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+        }
+    }
+
+    #[test]
+    fn spec_excludes_returned_ids() {
+        let mut cursor = parse(&rawdoc! {
+            "find": "coll",
+            "filter": { "a": 1 },
+            "projection": { "a": 1 },
+            "tailable": true,
+        })
+        .unwrap()
+        .unwrap()
+        .cursor;
+
+        let first = tailable_find_spec("coll", "db", &cursor, 101, Some(3));
+        assert_eq!(
+            first,
+            rawdoc! {
+                "find": "coll",
+                "filter": { "a": 1 },
+                "sort": { "_id": 1 },
+                "limit": 101_i64,
+                "singleBatch": true,
+                "skip": 3_i64,
+                "projection": { "a": 1 },
+                "$db": "db",
+            }
+        );
+
+        // A later insert may carry a smaller `_id` than one already returned.
+        cursor.returned_ids = vec![RawBson::Int32(7), RawBson::Int32(3)];
+        let next = tailable_find_spec("coll", "db", &cursor, 2, None);
+        assert_eq!(
+            next.get_document("filter").unwrap().to_raw_document_buf(),
+            rawdoc! { "$and": [{ "a": 1 }, { "_id": { "$nin": [7, 3] } }] }
+        );
+        assert!(next.get("skip").unwrap().is_none());
+    }
+
+    #[test]
+    fn batch_limit_respects_remaining() {
+        assert_eq!(next_batch_limit(None, None), DEFAULT_BATCH_SIZE);
+        assert_eq!(next_batch_limit(Some(10), None), 10);
+        assert_eq!(next_batch_limit(Some(10), Some(4)), 4);
+        assert_eq!(next_batch_limit(None, Some(0)), 0);
+    }
+
+    #[test]
+    fn tailable_cursor_outlives_an_empty_batch() {
+        let mut cursor = parse(&rawdoc! { "find": "coll", "tailable": true })
+            .unwrap()
+            .unwrap()
+            .cursor;
+        assert!(!is_exhausted(&cursor));
+
+        cursor.remaining = Some(1);
+        assert!(!is_exhausted(&cursor));
+        cursor.remaining = Some(0);
+        assert!(is_exhausted(&cursor));
+
+        cursor.remaining = None;
+        cursor.returned_ids = vec![RawBson::Null; MAX_RETURNED_IDS];
+        assert!(is_exhausted(&cursor));
+    }
+}
@@ -7,7 +7,7 @@
  */
 
 use crate::{
-    context::{ConnectionContext, RequestContext},
+    context::{ConnectionContext, RequestContext, SessionId, TransactionNumber},
     error::{DocumentDBError, ErrorCode, ErrorKind, Result},
     postgres::PgDataClient,
     requests::RequestType,
@@ -25,6 +25,14 @@ pub async fn handle(
     connection_context.transaction = None;
 
     if let Some(request_transaction_info) = &request_info.transaction_info {
+        if let Some(session_id) = &request_info.session_id {
+            check_transaction_number(
+                connection_context,
+                session_id,
+                request_transaction_info.transaction_number,
+            )?;
+        }
+
         if request_transaction_info.auto_commit {
             return Ok(());
         }
@@ -61,12 +69,43 @@ pub async fn handle(
             };
         }
 
+        // Commit and abort run against admin and finish the transaction wherever it runs.
+        if !matches!(
+            request.request_type(),
+            RequestType::CommitTransaction | RequestType::AbortTransaction
+        ) {
+            store.check_backend(&session_id, pg_data_client.backend())?;
+        }
+
         connection_context.transaction =
             Some((session_id, request_transaction_info.transaction_number));
     }
     Ok(())
 }
 
+/// A session's `txnNumber` only moves forward: a transaction or retryable write numbered
+/// below the highest the session has seen is too old to run.
+fn check_transaction_number(
+    connection_context: &ConnectionContext,
+    session_id: &SessionId,
+    transaction_number: TransactionNumber,
+) -> Result<()> {
+    let highest = connection_context
+        .service_context
+        .session_store()
+        .get(session_id)
+        .and_then(|session| session.transaction_number);
+    match highest {
+        Some(highest) if highest > transaction_number => Err(DocumentDBError::documentdb_error(
+            ErrorCode::TransactionTooOld,
+            format!(
+                "Cannot start transaction {transaction_number} on session {session_id} because a newer transaction {highest} has already started."
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
 pub async fn process_commit(context: &ConnectionContext) -> Result<Response> {
     if let Some((session_id, _)) = context.transaction.as_ref() {
         let store = context.service_context.transaction_store();
@@ -87,3 +126,56 @@ pub async fn process_abort(context: &ConnectionContext) -> Result<Response> {
     store.abort(session_id).await?;
     Ok(Response::ok())
 }
+
+#[cfg(test)]
+mod tests {
+    use bson::{rawdoc, spec::BinarySubtype, Binary};
+
+    use super::*;
+    use crate::{
+        context::RequestMemory,
+        requests::{request_tracker::RequestTracker, Request},
+        testing::{self, MapConfiguration, StubDataClient},
+    };
+
+    #[tokio::test]
+    async fn retryable_write_below_the_session_txn_number_is_too_old() {
+        let service_context = testing::service_context(MapConfiguration::default()).await;
+        let client = StubDataClient::new(service_context.clone());
+        let mut connection_context = testing::connection_context(service_context, "user");
+        let session_id = SessionId::new(vec![1; 16]);
+        connection_context
+            .service_context
+            .session_store()
+            .touch(&session_id, Some(TransactionNumber::new(5)));
+
+        for (transaction_number, too_old) in [(4_i64, true), (5, false), (6, false)] {
+            let command = rawdoc! {
+                "insert": "coll",
+                "documents": [{ "a": 1 }],
+                "lsid": { "id": Binary { subtype: BinarySubtype::Uuid, bytes: vec![1; 16] } },
+                "txnNumber": transaction_number,
+                "$db": "db",
+            };
+            let request = Request::Raw(RequestType::Insert, &command, None);
+            let info = request.extract_common().unwrap();
+            let request_context = RequestContext {
+                activity_id: "test",
+                payload: &request,
+                info: &info,
+                tracker: &RequestTracker::new(),
+                memory: &RequestMemory::default(),
+            };
+
+            let result = handle(&request_context, &mut connection_context, &client).await;
+            if too_old {
+                assert_eq!(
+                    result.unwrap_err().error_code_enum(),
+                    Some(ErrorCode::TransactionTooOld)
+                );
+            } else {
+                result.unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,831 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/processor/write_batch.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::future::Future;
+
+use bson::{rawdoc, RawArrayBuf, RawBson, RawBsonRef, RawDocument, RawDocumentBuf};
+
+use crate::{
+    bson::convert_to_bool,
+    configuration::DynamicConfiguration,
+    context::{ConnectionContext, RequestContext},
+    error::{DocumentDBError, Result},
+    postgres::PgDataClient,
+    protocol::OK_SUCCEEDED,
+    requests::{
+        special_values::{self, SpecialValuePolicy},
+        Request, RequestType,
+    },
+    responses::{CommandError, PgResponse, RawResponse, Response},
+};
+
+/// The procedure flags an insert or update batch runs with.
+#[derive(Debug, Clone, Copy)]
+pub struct WriteOptions {
+    pub enable_write_procedures: bool,
+    pub enable_write_procedures_with_batch_commit: bool,
+    /// Entries committed together when a batch outside a transaction is split; 0 never splits.
+    pub commit_size: usize,
+    /// How NaN and infinite numbers in inserted documents and update specs are handled.
+    pub special_values: SpecialValuePolicy,
+}
+
+impl WriteOptions {
+    /// The options the dynamic configuration sets. `writeBatchAtomic` turns splitting off.
+    pub fn from_configuration(dynamic_config: &dyn DynamicConfiguration) -> Self {
+        let commit_size = if dynamic_config.write_batch_atomic() {
+            0
+        } else {
+            usize::try_from(dynamic_config.write_batch_commit_size()).unwrap_or(usize::MAX)
+        };
+        Self {
+            enable_write_procedures: dynamic_config.enable_write_procedures(),
+            enable_write_procedures_with_batch_commit: dynamic_config
+                .enable_write_procedures_with_batch_commit(),
+            commit_size,
+            special_values: dynamic_config.special_value_policy(),
+        }
+    }
+}
+
+/// A slice of a write batch, with its entries either in the `extra` document sequence or in
+/// a rewritten `command`.
+#[derive(Debug)]
+struct WriteChunk<'a> {
+    /// Index of the chunk's first entry in the whole batch.
+    offset: usize,
+    command: Option<RawDocumentBuf>,
+    extra: Option<&'a [u8]>,
+}
+
+/// The field of the command holding the batch's entries.
+const fn entries_key(request_type: RequestType) -> &'static str {
+    match request_type {
+        RequestType::Update => "updates",
+        _ => "documents",
+    }
+}
+
+/// Splits a document sequence into the byte ranges of its documents.
+///
+/// # Errors
+/// Returns `BadValue` if a document's length runs past the end of the sequence.
+pub fn sequence_documents(sequence: &[u8]) -> Result<Vec<&[u8]>> {
+    let mut documents = Vec::new();
+    let mut rest = sequence;
+    while !rest.is_empty() {
+        let length = rest
+            .get(..4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(i32::from_le_bytes)
+            .and_then(|length| usize::try_from(length).ok())
+            .filter(|length| (5..=rest.len()).contains(length))
+            .ok_or_else(|| {
+                DocumentDBError::bad_value("Invalid document in document sequence".to_owned())
+            })?;
+        let (document, tail) = rest.split_at(length);
+        documents.push(document);
+        rest = tail;
+    }
+    Ok(documents)
+}
+
+/// The part of a batch entry holding values to be stored: the whole document of an insert,
+/// the `u` of an update. Update filters may match on NaN and are left alone.
+fn stored_values(
+    entry: &RawDocument,
+    request_type: RequestType,
+) -> Result<Option<(&'static str, RawBsonRef<'_>)>> {
+    if request_type == RequestType::Update {
+        return Ok(entry.get("u")?.map(|u| ("u", u)));
+    }
+    Ok(Some(("", RawBsonRef::Document(entry))))
+}
+
+fn coerce_entry(entry: &RawDocument, request_type: RequestType) -> Result<RawDocumentBuf> {
+    if request_type != RequestType::Update {
+        return special_values::coerce_document(entry);
+    }
+    let mut coerced = RawDocumentBuf::new();
+    for field in entry {
+        let (key, value) = field?;
+        if key == "u" {
+            coerced.append(key, special_values::coerce(value)?);
+        } else {
+            coerced.append(key, value.to_raw_bson());
+        }
+    }
+    Ok(coerced)
+}
+
+/// Applies `policy` to the entries of a batch, held either under `key` of `command` or in
+/// the `extra` document sequence.
+///
+/// Returns the rewritten command and sequence when `Coerce` replaced any value, or `None`
+/// when the batch can be sent as is. `Reject` fails on the first NaN or infinity with
+/// `BadValue`, naming the field by its path in the batch, e.g. `documents.2.price`.
+fn apply_special_value_policy(
+    policy: SpecialValuePolicy,
+    request_type: RequestType,
+    command: &RawDocument,
+    extra: Option<&[u8]>,
+    key: &str,
+) -> Result<Option<(RawDocumentBuf, Option<Vec<u8>>)>> {
+    if policy == SpecialValuePolicy::Allow {
+        return Ok(None);
+    }
+
+    // Entries that are not documents are left for the backend to reject.
+    let mut entries = Vec::new();
+    if let Some(sequence) = extra {
+        for document in sequence_documents(sequence)? {
+            entries.push(RawBsonRef::Document(RawDocument::from_bytes(document)?));
+        }
+    } else if let Some(array) = command.get(key)?.and_then(RawBsonRef::as_array) {
+        for entry in array {
+            entries.push(entry?);
+        }
+    }
+
+    if policy == SpecialValuePolicy::Reject {
+        for (i, entry) in entries.into_iter().enumerate() {
+            let Some(entry) = entry.as_document() else {
+                continue;
+            };
+            if let Some((field, values)) = stored_values(entry, request_type)? {
+                let path = match field {
+                    "" => format!("{key}.{i}"),
+                    field => format!("{key}.{i}.{field}"),
+                };
+                special_values::reject(values, &path)?;
+            }
+        }
+        return Ok(None);
+    }
+
+    let mut coerced = RawArrayBuf::new();
+    let mut changed = false;
+    for entry in entries {
+        match entry.as_document() {
+            Some(document) => {
+                let document_coerced = coerce_entry(document, request_type)?;
+                changed |= document_coerced.as_bytes() != document.as_bytes();
+                coerced.push(document_coerced);
+            }
+            None => coerced.push(entry.to_raw_bson()),
+        }
+    }
+    if !changed {
+        return Ok(None);
+    }
+
+    if extra.is_some() {
+        let mut sequence = Vec::new();
+        for entry in &*coerced {
+            if let Some(document) = entry?.as_document() {
+                sequence.extend_from_slice(document.as_bytes());
+            }
+        }
+        return Ok(Some((command.to_raw_document_buf(), Some(sequence))));
+    }
+
+    let mut coerced_command = RawDocumentBuf::new();
+    for field in command {
+        let (k, v) = field?;
+        if k == key {
+            coerced_command.append(k, coerced.clone());
+        } else {
+            coerced_command.append(k, v.to_raw_bson());
+        }
+    }
+    Ok(Some((coerced_command, None)))
+}
+
+/// Splits a batch into chunks of at most `commit_size` entries, or returns `None` if it
+/// fits in one.
+fn split_write_batch<'a>(
+    command: &RawDocument,
+    extra: Option<&'a [u8]>,
+    key: &str,
+    commit_size: usize,
+) -> Result<Option<Vec<WriteChunk<'a>>>> {
+    if commit_size == 0 {
+        return Ok(None);
+    }
+
+    if let Some(sequence) = extra {
+        let documents = sequence_documents(sequence)?;
+        if documents.len() <= commit_size {
+            return Ok(None);
+        }
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        for (i, documents) in documents.chunks(commit_size).enumerate() {
+            let length: usize = documents.iter().map(|document| document.len()).sum();
+            chunks.push(WriteChunk {
+                offset: i * commit_size,
+                command: None,
+                extra: Some(&sequence[start..start + length]),
+            });
+            start += length;
+        }
+        return Ok(Some(chunks));
+    }
+
+    let Some(entries) = command.get(key)?.and_then(RawBsonRef::as_array) else {
+        return Ok(None);
+    };
+    let entries = entries
+        .into_iter()
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    if entries.len() <= commit_size {
+        return Ok(None);
+    }
+
+    let mut chunks = Vec::new();
+    for (i, entries) in entries.chunks(commit_size).enumerate() {
+        let mut array = RawArrayBuf::new();
+        for entry in entries {
+            array.push(entry.to_raw_bson());
+        }
+        let mut chunk_command = RawDocumentBuf::new();
+        for field in command {
+            let (k, v) = field?;
+            if k == key {
+                chunk_command.append(k, array.clone());
+            } else {
+                chunk_command.append(k, v.to_raw_bson());
+            }
+        }
+        chunks.push(WriteChunk {
+            offset: i * commit_size,
+            command: Some(chunk_command),
+            extra: None,
+        });
+    }
+    Ok(Some(chunks))
+}
+
+/// Combines the responses of a batch's chunks into the response for the whole batch.
+#[derive(Debug, Default)]
+struct WriteBatchResult {
+    n: i64,
+    n_modified: Option<i64>,
+    upserted: Vec<RawDocumentBuf>,
+    write_errors: Vec<RawDocumentBuf>,
+}
+
+fn as_i64(value: Option<RawBsonRef>) -> i64 {
+    match value {
+        Some(RawBsonRef::Int32(value)) => i64::from(value),
+        Some(RawBsonRef::Int64(value)) => value,
+        _ => 0,
+    }
+}
+
+/// A count or index as the backend reports it: an int32 unless it doesn't fit.
+fn count(value: i64) -> RawBson {
+    i32::try_from(value).map_or(RawBson::Int64(value), RawBson::Int32)
+}
+
+/// Copies `entries` with their `index` moved from the chunk to the batch.
+fn offset_entries(entries: Option<RawBsonRef>, offset: usize) -> Result<Vec<RawDocumentBuf>> {
+    let Some(entries) = entries.and_then(RawBsonRef::as_array) else {
+        return Ok(Vec::new());
+    };
+    let offset = i64::try_from(offset).unwrap_or(i64::MAX);
+    entries
+        .into_iter()
+        .map(|entry| {
+            let entry = entry?.as_document().ok_or_else(|| {
+                DocumentDBError::internal_error("Write result entry is not a document".to_owned())
+            })?;
+            let mut moved = RawDocumentBuf::new();
+            for field in entry {
+                let (key, value) = field?;
+                if key == "index" {
+                    moved.append(key, count(as_i64(Some(value)).saturating_add(offset)));
+                } else {
+                    moved.append(key, value.to_raw_bson());
+                }
+            }
+            Ok(moved)
+        })
+        .collect()
+}
+
+impl WriteBatchResult {
+    /// Adds the response of the chunk starting at `offset`, returning whether it had write errors.
+    fn merge(&mut self, response: &RawDocument, offset: usize) -> Result<bool> {
+        self.n += as_i64(response.get("n")?);
+        if let Some(n_modified) = response.get("nModified")? {
+            *self.n_modified.get_or_insert(0) += as_i64(Some(n_modified));
+        }
+        self.upserted
+            .extend(offset_entries(response.get("upserted")?, offset)?);
+        let write_errors = offset_entries(response.get("writeErrors")?, offset)?;
+        let failed = !write_errors.is_empty();
+        self.write_errors.extend(write_errors);
+        Ok(failed)
+    }
+
+    /// Reports the chunk starting at `offset`, which failed outright with `error`, as a
+    /// write error of its first entry.
+    fn fail(&mut self, error: &CommandError, offset: usize) {
+        self.write_errors.push(rawdoc! {
+            "index": count(i64::try_from(offset).unwrap_or(i64::MAX)),
+            "code": *error.code() as i32,
+            "errmsg": error.message(),
+        });
+    }
+
+    fn into_document(self) -> RawDocumentBuf {
+        let mut response = rawdoc! { "n": count(self.n) };
+        if let Some(n_modified) = self.n_modified {
+            response.append("nModified", count(n_modified));
+        }
+        for (key, entries) in [
+            ("upserted", self.upserted),
+            ("writeErrors", self.write_errors),
+        ] {
+            if !entries.is_empty() {
+                let mut array = RawArrayBuf::new();
+                for entry in entries {
+                    array.push(entry);
+                }
+                response.append(key, array);
+            }
+        }
+        response.append("ok", OK_SUCCEEDED);
+        response
+    }
+}
+
+/// Runs `chunks` in order, stopping after the first chunk with write errors when `ordered`.
+///
+/// A chunk that fails outright ends the batch. Unless it is the first, the chunks before it
+/// have committed, so the result so far is returned with the failure as a write error at
+/// the chunk's offset.
+async fn run_write_chunks<'a, F, Fut>(
+    connection_context: &ConnectionContext,
+    activity_id: &str,
+    chunks: Vec<WriteChunk<'a>>,
+    ordered: bool,
+    mut execute: F,
+) -> Result<RawDocumentBuf>
+where
+    F: FnMut(WriteChunk<'a>) -> Fut,
+    Fut: Future<Output = Result<Response>>,
+{
+    let mut result = WriteBatchResult::default();
+    for chunk in chunks {
+        let offset = chunk.offset;
+        let response = match execute(chunk).await {
+            Ok(response) => response,
+            Err(e) if offset == 0 => return Err(e),
+            Err(e) => {
+                result.fail(
+                    &CommandError::from_error(connection_context, &e, activity_id),
+                    offset,
+                );
+                break;
+            }
+        };
+        if result.merge(response.as_raw_document()?, offset)? && ordered {
+            break;
+        }
+    }
+    Ok(result.into_document())
+}
+
+async fn execute_write(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+    pg_data_client: &impl PgDataClient,
+    options: WriteOptions,
+) -> Result<Response> {
+    let rows = if request_context.payload.request_type() == RequestType::Update {
+        pg_data_client
+            .execute_update(
+                request_context,
+                connection_context,
+                options.enable_write_procedures,
+                options.enable_write_procedures_with_batch_commit,
+            )
+            .await?
+    } else {
+        pg_data_client
+            .execute_insert(
+                request_context,
+                connection_context,
+                options.enable_write_procedures,
+                options.enable_write_procedures_with_batch_commit,
+            )
+            .await?
+    };
+    PgResponse::new(rows).transform_write_errors(connection_context, request_context.activity_id)
+}
+
+/// Runs an insert or update batch, committing it in chunks of `commit_size` entries when it
+/// is larger and not part of a transaction. This bounds the size of each backend transaction
+/// and the WAL it holds back.
+///
+/// Chunks keep the batch's `ordered` semantics: an ordered batch stops at the first failing
+/// entry and an unordered batch attempts every entry, with `writeErrors` and `upserted`
+/// indexed across the whole batch. The chunks before a failure stay committed; batches
+/// that must not be committed in parts run whole under `writeBatchAtomic`, or in a
+/// transaction, whose batches are never split.
+///
+/// # Errors
+/// Returns an error if the batch, or its first chunk, fails outright.
+pub async fn process_write_batch(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+    pg_data_client: &impl PgDataClient,
+    options: WriteOptions,
+) -> Result<Response> {
+    let request = request_context.payload;
+    let Some((command, extra)) = apply_special_value_policy(
+        options.special_values,
+        request.request_type(),
+        request.document(),
+        request.extra(),
+        entries_key(request.request_type()),
+    )?
+    else {
+        return write_batch(request_context, connection_context, pg_data_client, options).await;
+    };
+
+    // The coerced copy is held alongside the original until the batch has run.
+    let _coerced_copy = request_context
+        .memory
+        .charge(command.as_bytes().len() + extra.as_ref().map_or(0, Vec::len))?;
+    let coerced_request = Request::Raw(request.request_type(), &command, extra.as_deref());
+    let coerced_context = request_context.with_payload(Some(&coerced_request));
+    write_batch(
+        &coerced_context,
+        connection_context,
+        pg_data_client,
+        options,
+    )
+    .await
+}
+
+async fn write_batch(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+    pg_data_client: &impl PgDataClient,
+    options: WriteOptions,
+) -> Result<Response> {
+    let request = request_context.payload;
+    let chunks = if connection_context.transaction.is_none() {
+        split_write_batch(
+            request.document(),
+            request.extra(),
+            entries_key(request.request_type()),
+            options.commit_size,
+        )?
+    } else {
+        None
+    };
+    let Some(chunks) = chunks else {
+        return execute_write(request_context, connection_context, pg_data_client, options).await;
+    };
+    // The split copies of the command are held until the whole batch has run.
+    let _chunk_commands = request_context.memory.charge(
+        chunks
+            .iter()
+            .filter_map(|chunk| chunk.command.as_ref())
+            .map(|command| command.as_bytes().len())
+            .sum(),
+    )?;
+
+    let ordered = request
+        .document()
+        .get("ordered")?
+        .and_then(convert_to_bool)
+        .unwrap_or(true);
+    let document = request.document();
+    let response = run_write_chunks(
+        connection_context,
+        request_context.activity_id,
+        chunks,
+        ordered,
+        |chunk| async move {
+            let chunk_request = Request::Raw(
+                request.request_type(),
+                chunk.command.as_deref().unwrap_or(document),
+                chunk.extra,
+            );
+            let chunk_context = request_context.with_payload(Some(&chunk_request));
+            execute_write(&chunk_context, connection_context, pg_data_client, options).await
+        },
+    )
+    .await?;
+    Ok(Response::Raw(RawResponse(response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::ErrorCode,
+        testing::{self, MapConfiguration},
+    };
+
+    fn sequence(count: i32) -> Vec<u8> {
+        (0..count)
+            .flat_map(|i| rawdoc! { "_id": i }.into_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn small_batches_are_not_split() {
+        let command = rawdoc! { "insert": "c", "documents": [{ "_id": 1 }, { "_id": 2 }] };
+        assert!(split_write_batch(&command, None, "documents", 2)
+            .unwrap()
+            .is_none());
+        assert!(split_write_batch(&command, None, "documents", 0)
+            .unwrap()
+            .is_none());
+        assert!(split_write_batch(
+            &rawdoc! { "insert": "c" },
+            Some(&sequence(3)),
+            "documents",
+            3
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    #[test]
+    fn splits_command_array_and_document_sequence() {
+        let command = rawdoc! {
+            "update": "c",
+            "updates": [{ "q": { "a": 1 } }, { "q": { "a": 2 } }, { "q": { "a": 3 } }],
+            "ordered": false,
+        };
+        let chunks = split_write_batch(&command, None, "updates", 2)
+            .unwrap()
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].offset, 2);
+        let second = chunks[1].command.as_ref().unwrap();
+        assert_eq!(second.get_array("updates").unwrap().into_iter().count(), 1);
+        assert!(!second.get_bool("ordered").unwrap());
+
+        let extra = sequence(5);
+        let chunks = split_write_batch(&rawdoc! { "insert": "c" }, Some(&extra), "documents", 2)
+            .unwrap()
+            .unwrap();
+        let ids: Vec<Vec<i32>> = chunks
+            .iter()
+            .map(|chunk| {
+                sequence_documents(chunk.extra.unwrap())
+                    .unwrap()
+                    .into_iter()
+                    .map(|document| {
+                        RawDocument::from_bytes(document)
+                            .unwrap()
+                            .get_i32("_id")
+                            .unwrap()
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(ids, vec![vec![0, 1], vec![2, 3], vec![4]]);
+        assert_eq!(chunks[2].offset, 4);
+    }
+
+    /// Runs five inserts in chunks of two, where the second chunk fails: at its first
+    /// document, or outright when `outright`.
+    async fn run_failing_batch(ordered: bool, outright: bool) -> (RawDocumentBuf, Vec<usize>) {
+        let service_context = testing::service_context(MapConfiguration::default()).await;
+        let connection_context = testing::connection_context(service_context, "user");
+        let extra = sequence(5);
+        let chunks = split_write_batch(&rawdoc! { "insert": "c" }, Some(&extra), "documents", 2)
+            .unwrap()
+            .unwrap();
+        let mut executed = Vec::new();
+        let response = run_write_chunks(&connection_context, "test", chunks, ordered, |chunk| {
+            executed.push(chunk.offset);
+            let count = sequence_documents(chunk.extra.unwrap()).unwrap().len();
+            let response = match chunk.offset {
+                2 if outright => Err(DocumentDBError::documentdb_error(
+                    ErrorCode::ExceededTimeLimit,
+                    "operation exceeded time limit".to_owned(),
+                )),
+                2 => {
+                    // An ordered chunk stops at its failing document.
+                    let n = if ordered { 0 } else { count - 1 };
+                    Ok(rawdoc! {
+                        "n": i32::try_from(n).unwrap(),
+                        "writeErrors": [{ "index": 0, "code": 11000, "errmsg": "duplicate key" }],
+                        "ok": 1.0,
+                    })
+                }
+                _ => Ok(rawdoc! { "n": i32::try_from(count).unwrap(), "ok": 1.0 }),
+            };
+            async move { response.map(|response| Response::Raw(RawResponse(response))) }
+        })
+        .await
+        .unwrap();
+        (response, executed)
+    }
+
+    #[tokio::test]
+    async fn ordered_batch_stops_at_the_failing_chunk() {
+        let (response, executed) = run_failing_batch(true, false).await;
+        assert_eq!(executed, vec![0, 2]);
+        assert_eq!(response.get_i32("n").unwrap(), 2);
+
+        let errors = response.get_array("writeErrors").unwrap();
+        let error = errors.get_document(0).unwrap();
+        assert_eq!(error.get_i32("index").unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn unordered_batch_runs_every_chunk() {
+        let (response, executed) = run_failing_batch(false, false).await;
+        assert_eq!(executed, vec![0, 2, 4]);
+        assert_eq!(response.get_i32("n").unwrap(), 4);
+        assert_eq!(
+            response
+                .get_array("writeErrors")
+                .unwrap()
+                .into_iter()
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn chunk_failing_outright_reports_the_committed_chunks() {
+        for ordered in [true, false] {
+            let (response, executed) = run_failing_batch(ordered, true).await;
+            assert_eq!(executed, vec![0, 2]);
+            assert_eq!(response.get_i32("n").unwrap(), 2);
+
+            let errors = response.get_array("writeErrors").unwrap();
+            let error = errors.get_document(0).unwrap();
+            assert_eq!(error.get_i32("index").unwrap(), 2);
+            assert_eq!(
+                error.get_i32("code").unwrap(),
+                ErrorCode::ExceededTimeLimit as i32
+            );
+            assert_eq!(
+                error.get_str("errmsg").unwrap(),
+                "operation exceeded time limit"
+            );
+        }
+    }
+
+    #[test]
+    fn upserted_indexes_are_offset() {
+        let mut result = WriteBatchResult::default();
+        result
+            .merge(
+                &rawdoc! { "n": 1, "nModified": 0, "upserted": [{ "index": 0, "_id": 7 }] },
+                3,
+            )
+            .unwrap();
+        let response = result.into_document();
+        assert_eq!(response.get_i32("nModified").unwrap(), 0);
+        let upserted = response.get_array("upserted").unwrap();
+        assert_eq!(
+            upserted.get_document(0).unwrap().get_i32("index").unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn allowed_special_values_are_sent_as_is() {
+        let command = rawdoc! { "insert": "c", "documents": [{ "x": f64::NAN }] };
+        assert!(apply_special_value_policy(
+            SpecialValuePolicy::Allow,
+            RequestType::Insert,
+            &command,
+            None,
+            "documents"
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    #[test]
+    fn rejected_special_values_name_the_entry() {
+        let command = rawdoc! { "insert": "c", "documents": [{ "x": 1 }, { "x": f64::INFINITY }] };
+        let err = apply_special_value_policy(
+            SpecialValuePolicy::Reject,
+            RequestType::Insert,
+            &command,
+            None,
+            "documents",
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Field 'documents.1.x' holds Infinity"));
+
+        let extra = rawdoc! { "x": f64::NAN }.into_bytes();
+        let err = apply_special_value_policy(
+            SpecialValuePolicy::Reject,
+            RequestType::Insert,
+            &rawdoc! { "insert": "c" },
+            Some(&extra),
+            "documents",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Field 'documents.0.x' holds NaN"));
+    }
+
+    #[test]
+    fn update_filters_may_hold_special_values() {
+        let command = rawdoc! {
+            "update": "c",
+            "updates": [{ "q": { "x": f64::NAN }, "u": { "$set": { "y": f64::NAN } } }],
+        };
+        let err = apply_special_value_policy(
+            SpecialValuePolicy::Reject,
+            RequestType::Update,
+            &command,
+            None,
+            "updates",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Field 'updates.0.u.$set.y'"));
+
+        let (coerced, extra) = apply_special_value_policy(
+            SpecialValuePolicy::Coerce,
+            RequestType::Update,
+            &command,
+            None,
+            "updates",
+        )
+        .unwrap()
+        .unwrap();
+        assert!(extra.is_none());
+        let update = coerced
+            .get_array("updates")
+            .unwrap()
+            .get_document(0)
+            .unwrap();
+        assert!(update
+            .get_document("q")
+            .unwrap()
+            .get_f64("x")
+            .unwrap()
+            .is_nan());
+        assert_eq!(
+            update
+                .get_document("u")
+                .unwrap()
+                .get_document("$set")
+                .unwrap()
+                .get("y")
+                .unwrap(),
+            Some(RawBsonRef::Null)
+        );
+    }
+
+    #[test]
+    fn coerced_sequences_are_rewritten() {
+        let extra = [rawdoc! { "_id": 1 }, rawdoc! { "_id": 2, "x": f64::NAN }]
+            .into_iter()
+            .flat_map(RawDocumentBuf::into_bytes)
+            .collect::<Vec<_>>();
+        let command = rawdoc! { "insert": "c" };
+
+        let (coerced, extra) = apply_special_value_policy(
+            SpecialValuePolicy::Coerce,
+            RequestType::Insert,
+            &command,
+            Some(&extra),
+            "documents",
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(coerced, command);
+        let extra = extra.unwrap();
+        let documents = sequence_documents(&extra).unwrap();
+        assert_eq!(documents.len(), 2);
+        let second = RawDocument::from_bytes(documents[1]).unwrap();
+        assert_eq!(second.get("x").unwrap(), Some(RawBsonRef::Null));
+
+        assert!(apply_special_value_policy(
+            SpecialValuePolicy::Coerce,
+            RequestType::Insert,
+            &command,
+            Some(&sequence(2)),
+            "documents"
+        )
+        .unwrap()
+        .is_none());
+    }
+}
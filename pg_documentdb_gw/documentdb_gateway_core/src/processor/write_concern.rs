@@ -0,0 +1,323 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/processor/write_concern.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{future::Future, time::Duration};
+
+use bson::{rawdoc, RawDocumentBuf};
+use tokio::time::Instant;
+
+use crate::{
+    context::{ConnectionContext, RequestContext},
+    error::Result,
+    requests::write_concern::{WriteAcknowledgment, WriteConcern},
+    responses::Response,
+};
+
+/// Reported when the write did not reach the requested nodes before `wtimeout`.
+const WRITE_CONCERN_FAILED: (i32, &str) = (64, "WriteConcernFailed");
+
+/// Reported for a `w` naming a custom write concern mode.
+const UNKNOWN_REPL_WRITE_CONCERN: (i32, &str) = (79, "UnknownReplWriteConcern");
+
+/// How often the backend is asked how far the WAL has been flushed and replicated.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How far the WAL has been flushed, locally and on the streaming replicas, relative to a write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ReplicationProgress {
+    local_flushed: bool,
+    replicas_flushed: u32,
+    replicas: u32,
+}
+
+impl ReplicationProgress {
+    const fn satisfies(&self, write_concern: &WriteConcern) -> bool {
+        (self.local_flushed || !write_concern.journal)
+            && self.replicas_flushed >= write_concern.required_replicas(self.replicas)
+    }
+}
+
+/// Polls `progress` until it satisfies `write_concern`, returning false if `deadline` passes first.
+async fn wait_for_acknowledgment<F, Fut>(
+    write_concern: &WriteConcern,
+    deadline: Instant,
+    mut progress: F,
+) -> Result<bool>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<ReplicationProgress>>,
+{
+    loop {
+        if progress().await?.satisfies(write_concern) {
+            return Ok(true);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(false);
+        }
+        tokio::time::sleep(POLL_INTERVAL.min(deadline - now)).await;
+    }
+}
+
+fn write_concern_error((code, code_name): (i32, &str), errmsg: String) -> RawDocumentBuf {
+    rawdoc! {
+        "code": code,
+        "codeName": code_name,
+        "errmsg": errmsg,
+    }
+}
+
+/// A `WriteConcernFailed` error; `errInfo.wtimeout` tells a timeout from a failed check.
+fn replication_error(timed_out: bool, errmsg: String) -> RawDocumentBuf {
+    let mut error = write_concern_error(WRITE_CONCERN_FAILED, errmsg);
+    error.append("errInfo", rawdoc! { "wtimeout": timed_out });
+    error
+}
+
+/// Holds back the response to an insert, update, delete or findAndModify until the write
+/// satisfies the client's `writeConcern`. The write has already been applied, so a concern
+/// that can't be satisfied, or whose progress the backend can't report, is reported in
+/// `writeConcernError` next to the write's own results rather than failing the command.
+///
+/// Writes in a transaction are acknowledged by `commitTransaction` instead.
+///
+/// # Errors
+/// Returns an error if `writeConcernError` can't be added to the response.
+pub async fn acknowledge_write(
+    request_context: &RequestContext<'_>,
+    connection_context: &ConnectionContext,
+    response: Response,
+) -> Result<Response> {
+    let Some(write_concern) = request_context.info.write_concern() else {
+        return Ok(response);
+    };
+    if connection_context.transaction.is_some() {
+        return Ok(response);
+    }
+
+    match &write_concern.w {
+        WriteAcknowledgment::Unacknowledged => return Ok(Response::ok()),
+        WriteAcknowledgment::Tag(mode) => {
+            return response.with_field(
+                "writeConcernError",
+                write_concern_error(
+                    UNKNOWN_REPL_WRITE_CONCERN,
+                    format!(
+                        "No write concern mode named '{mode}' found in replica set configuration"
+                    ),
+                ),
+            )
+        }
+        WriteAcknowledgment::Nodes(_) | WriteAcknowledgment::Majority => {}
+    }
+    if !write_concern.requires_wait() {
+        return Ok(response);
+    }
+
+    let replicated = wait_for_replication(connection_context, write_concern).await;
+    with_replication_outcome(response, replicated, request_context.activity_id)
+}
+
+/// Waits for the write to satisfy `write_concern`, returning false if `wtimeout` passes first.
+async fn wait_for_replication(
+    connection_context: &ConnectionContext,
+    write_concern: &WriteConcern,
+) -> Result<bool> {
+    let service_context = &connection_context.service_context;
+    let query_catalog = service_context.query_catalog();
+    let connection = service_context
+        .connection_pool_manager()
+        .system_requests_connection()
+        .await?;
+
+    // Anything inserted into the WAL by now includes the commit of the write.
+    let target_lsn: String = connection
+        .query(query_catalog.wal_insert_lsn(), &[], &[])
+        .await?
+        .first()
+        .map(|row| row.get(0))
+        .unwrap_or_default();
+
+    let timeout = write_concern.wtimeout.unwrap_or_else(|| {
+        Duration::from_secs(
+            service_context
+                .setup_configuration()
+                .postgres_command_timeout_secs(),
+        )
+    });
+    wait_for_acknowledgment(write_concern, Instant::now() + timeout, || async {
+        let rows = connection
+            .query(
+                query_catalog.write_concern_progress(),
+                &[tokio_postgres::types::Type::TEXT],
+                &[&target_lsn],
+            )
+            .await?;
+        Ok(rows.first().map_or(
+            ReplicationProgress {
+                local_flushed: false,
+                replicas_flushed: 0,
+                replicas: 0,
+            },
+            |row| ReplicationProgress {
+                local_flushed: row.get(0),
+                replicas_flushed: u32::try_from(row.get::<_, i64>(1)).unwrap_or(0),
+                replicas: u32::try_from(row.get::<_, i64>(2)).unwrap_or(0),
+            },
+        ))
+    })
+    .await
+}
+
+/// Reports the outcome of waiting for replication next to the write's results. The write
+/// stays committed either way, so neither a timeout nor a failure to check replication fails
+/// the command: the response keeps `ok: 1` and carries a `writeConcernError`.
+fn with_replication_outcome(
+    response: Response,
+    replicated: Result<bool>,
+    activity_id: &str,
+) -> Result<Response> {
+    let error = match replicated {
+        Ok(true) => return Ok(response),
+        Ok(false) => replication_error(true, "waiting for replication timed out".to_owned()),
+        Err(e) => {
+            tracing::warn!(
+                activity_id = activity_id,
+                "Couldn't confirm replication of a committed write: {e}"
+            );
+            replication_error(
+                false,
+                format!("could not confirm replication of the write: {e}"),
+            )
+        }
+    };
+    response.with_field("writeConcernError", error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use bson::{rawdoc, RawBsonRef, RawDocument};
+
+    use super::*;
+    use crate::{error::DocumentDBError, responses::RawResponse};
+
+    fn write_concern(document: &RawDocument) -> WriteConcern {
+        WriteConcern::parse(document).unwrap()
+    }
+
+    #[tokio::test]
+    async fn wtimeout_produces_write_concern_error() {
+        let write_concern = write_concern(&rawdoc! { "w": 2, "wtimeout": 30 });
+        let deadline = Instant::now() + write_concern.wtimeout.unwrap();
+
+        // No replica ever flushes the write.
+        let satisfied = wait_for_acknowledgment(&write_concern, deadline, || async {
+            Ok(ReplicationProgress {
+                local_flushed: true,
+                replicas_flushed: 0,
+                replicas: 1,
+            })
+        })
+        .await
+        .unwrap();
+        assert!(!satisfied);
+        assert!(Instant::now() >= deadline);
+
+        let response = with_replication_outcome(
+            Response::Raw(RawResponse(rawdoc! { "n": 1, "ok": 1.0 })),
+            Ok(satisfied),
+            "activity",
+        )
+        .unwrap();
+        let document = response.as_raw_document().unwrap();
+        assert_eq!(document.get_i32("n").unwrap(), 1);
+        assert_eq!(document.get("ok").unwrap(), Some(RawBsonRef::Double(1.0)));
+        let error = document.get_document("writeConcernError").unwrap();
+        assert_eq!(error.get_i32("code").unwrap(), 64);
+        assert_eq!(error.get_str("codeName").unwrap(), "WriteConcernFailed");
+        assert!(error
+            .get_document("errInfo")
+            .unwrap()
+            .get_bool("wtimeout")
+            .unwrap());
+    }
+
+    #[test]
+    fn failed_replication_check_keeps_the_write_successful() {
+        let response = with_replication_outcome(
+            Response::Raw(RawResponse(rawdoc! { "n": 1, "ok": 1.0 })),
+            Err(DocumentDBError::internal_error(
+                "connection reset".to_owned(),
+            )),
+            "activity",
+        )
+        .unwrap();
+
+        let document = response.as_raw_document().unwrap();
+        assert_eq!(document.get("ok").unwrap(), Some(RawBsonRef::Double(1.0)));
+        let error = document.get_document("writeConcernError").unwrap();
+        assert_eq!(error.get_str("codeName").unwrap(), "WriteConcernFailed");
+        assert!(!error
+            .get_document("errInfo")
+            .unwrap()
+            .get_bool("wtimeout")
+            .unwrap());
+    }
+
+    #[test]
+    fn satisfied_write_concern_leaves_the_response_alone() {
+        let response = with_replication_outcome(
+            Response::Raw(RawResponse(rawdoc! { "n": 1, "ok": 1.0 })),
+            Ok(true),
+            "activity",
+        )
+        .unwrap();
+        assert!(response
+            .as_raw_document()
+            .unwrap()
+            .get("writeConcernError")
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn waits_until_replicas_catch_up() {
+        let write_concern = write_concern(&rawdoc! { "w": "majority", "j": true });
+        let polls = AtomicU32::new(0);
+
+        let satisfied = wait_for_acknowledgment(
+            &write_concern,
+            Instant::now() + Duration::from_secs(10),
+            || async {
+                let poll = polls.fetch_add(1, Ordering::Relaxed);
+                Ok(ReplicationProgress {
+                    local_flushed: true,
+                    replicas_flushed: poll,
+                    replicas: 2,
+                })
+            },
+        )
+        .await
+        .unwrap();
+        assert!(satisfied);
+        assert_eq!(polls.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn journal_requires_local_flush() {
+        let progress = ReplicationProgress {
+            local_flushed: false,
+            replicas_flushed: 0,
+            replicas: 0,
+        };
+        assert!(!progress.satisfies(&write_concern(&rawdoc! { "j": true })));
+        assert!(progress.satisfies(&write_concern(&rawdoc! { "w": "majority" })));
+    }
+}
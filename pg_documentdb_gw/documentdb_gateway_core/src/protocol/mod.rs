@@ -11,14 +11,17 @@ use crate::error::{DocumentDBError, Result};
 pub mod bson_writer;
 pub mod header;
 pub mod message;
+pub mod op_compressed;
 pub mod op_insert;
 pub mod op_query;
 pub mod opcode;
 pub mod reader;
 pub mod util;
 
-pub const MAX_BSON_OBJECT_SIZE: i32 = 16 * 1024 * 1024;
-pub const MAX_MESSAGE_SIZE_BYTES: i32 = 48_000_000;
+/// Default for `SetupConfiguration::max_bson_object_size`.
+pub const DEFAULT_MAX_BSON_OBJECT_SIZE: i32 = 16 * 1024 * 1024;
+/// Default for `SetupConfiguration::max_message_size_bytes`.
+pub const DEFAULT_MAX_MESSAGE_SIZE_BYTES: i32 = 48_000_000;
 pub const LOGICAL_SESSION_TIMEOUT_MINUTES: u8 = 30;
 
 pub const OK_SUCCEEDED: f64 = 1.0;
@@ -0,0 +1,229 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/protocol/op_compressed.rs
+ *
+ * Decoder for the OP_COMPRESSED wire protocol message. The decompressed
+ * size is bounded by the configured maximum message size while inflating,
+ * independently of the size the client declares.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::io::{self, Read};
+
+use bytes::Buf;
+use flate2::read::ZlibDecoder;
+
+use crate::{
+    error::{DocumentDBError, Result},
+    protocol::{header::Header, opcode::OpCode},
+    requests::RequestMessage,
+};
+
+const COMPRESSOR_NOOP: u8 = 0;
+const COMPRESSOR_SNAPPY: u8 = 1;
+const COMPRESSOR_ZLIB: u8 = 2;
+const COMPRESSOR_ZSTD: u8 = 3;
+
+/// originalOpcode, uncompressedSize and compressorId
+const PREFIX_LENGTH: usize = 4 + 4 + 1;
+
+/// Unwraps an `OP_COMPRESSED` body into the message it carries.
+///
+/// The decompressed message, header included, may not exceed `max_message_size_bytes`.
+/// Output is never allocated beyond that limit, whatever `uncompressedSize` claims.
+///
+/// # Errors
+/// Returns an `InvalidData` I/O error if the payload is corrupt, inflates past the limit
+/// or disagrees with its declared size; the connection cannot be trusted after such a
+/// message. Returns `BadValue` for an unsupported compressor.
+pub fn decompress(
+    compressed: &RequestMessage,
+    max_message_size_bytes: i32,
+) -> Result<RequestMessage> {
+    let mut buf = compressed.request.as_slice();
+    if buf.remaining() < PREFIX_LENGTH {
+        return Err(invalid_data(
+            "OP_COMPRESSED message too short for its prefix".to_owned(),
+        ));
+    }
+    let original_op_code = OpCode::from_value(buf.get_i32_le());
+    let declared_size = buf.get_i32_le();
+    let compressor_id = buf.get_u8();
+
+    let max_body_size = usize::try_from(max_message_size_bytes)
+        .unwrap_or_default()
+        .saturating_sub(Header::LENGTH);
+    let declared_size = usize::try_from(declared_size).map_err(|e| {
+        invalid_data(format!(
+            "OP_COMPRESSED declared size {declared_size} is invalid: {e}"
+        ))
+    })?;
+    if declared_size > max_body_size {
+        return Err(invalid_data(format!(
+            "OP_COMPRESSED declared size {declared_size} exceeds the maximum message size of {max_message_size_bytes} bytes"
+        )));
+    }
+
+    let request = match compressor_id {
+        COMPRESSOR_NOOP => bounded_read(buf, declared_size, max_body_size)?,
+        COMPRESSOR_ZLIB => bounded_read(ZlibDecoder::new(buf), declared_size, max_body_size)?,
+        COMPRESSOR_SNAPPY | COMPRESSOR_ZSTD => {
+            return Err(DocumentDBError::bad_value(format!(
+                "OP_COMPRESSED compressor {compressor_id} is not supported"
+            )))
+        }
+        _ => {
+            return Err(DocumentDBError::bad_value(format!(
+                "Unknown OP_COMPRESSED compressor {compressor_id}"
+            )))
+        }
+    };
+
+    if request.len() != declared_size {
+        return Err(invalid_data(format!(
+            "OP_COMPRESSED payload decompressed to {} bytes but declared {declared_size}",
+            request.len()
+        )));
+    }
+
+    Ok(RequestMessage {
+        request,
+        op_code: original_op_code,
+        request_id: compressed.request_id,
+        response_to: compressed.response_to,
+    })
+}
+
+/// Reads `reader` to the end, failing as soon as the output passes `max_size`.
+fn bounded_read(reader: impl Read, declared_size: usize, max_size: usize) -> Result<Vec<u8>> {
+    // The declared size is already capped at `max_size`, so it bounds the allocation.
+    let mut output = Vec::with_capacity(declared_size);
+    reader
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut output)
+        .map_err(|e| {
+            invalid_data(format!(
+                "OP_COMPRESSED payload could not be decompressed: {e}"
+            ))
+        })?;
+
+    if output.len() > max_size {
+        return Err(invalid_data(format!(
+            "OP_COMPRESSED payload decompresses past the maximum message size of {} bytes",
+            max_size + Header::LENGTH
+        )));
+    }
+    Ok(output)
+}
+
+fn invalid_data(message: String) -> DocumentDBError {
+    io::Error::new(io::ErrorKind::InvalidData, message).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use super::*;
+
+    const MAX_MESSAGE_SIZE: i32 = 64 * 1024;
+
+    fn compressed_message(declared_size: i32, compressor_id: u8, payload: &[u8]) -> RequestMessage {
+        let mut request = Vec::new();
+        request.extend_from_slice(&(OpCode::Msg as i32).to_le_bytes());
+        request.extend_from_slice(&declared_size.to_le_bytes());
+        request.push(compressor_id);
+        request.extend_from_slice(payload);
+        RequestMessage {
+            request,
+            op_code: OpCode::Compressed,
+            request_id: 5,
+            response_to: 0,
+        }
+    }
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn assert_invalid_data(error: &DocumentDBError, expected: &str) {
+        match error.kind() {
+            crate::error::ErrorKind::IoError(e, _) => {
+                assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+                assert!(e.to_string().contains(expected), "{e}");
+            }
+            _ => panic!("expected an InvalidData error, got {error}"),
+        }
+    }
+
+    #[test]
+    fn zlib_payload_is_unwrapped() {
+        let body = b"original OP_MSG body".to_vec();
+        let message = compressed_message(
+            i32::try_from(body.len()).unwrap(),
+            COMPRESSOR_ZLIB,
+            &zlib(&body),
+        );
+
+        let decompressed = decompress(&message, MAX_MESSAGE_SIZE).unwrap();
+
+        assert_eq!(decompressed.op_code, OpCode::Msg);
+        assert_eq!(decompressed.request_id, 5);
+        assert_eq!(decompressed.request, body);
+    }
+
+    #[test]
+    fn oversized_declared_size_is_rejected_before_inflating() {
+        let message = compressed_message(i32::MAX, COMPRESSOR_ZLIB, &zlib(b"tiny"));
+
+        let error = decompress(&message, MAX_MESSAGE_SIZE).unwrap_err();
+
+        assert_invalid_data(
+            &error,
+            "declared size 2147483647 exceeds the maximum message size",
+        );
+    }
+
+    #[test]
+    fn zip_bomb_is_aborted_at_the_limit() {
+        // A few hundred bytes that inflate to 64 MiB, declared as a small message.
+        let bomb = zlib(&vec![0_u8; 64 * 1024 * 1024]);
+        assert!(bomb.len() < 128 * 1024);
+        let message = compressed_message(1024, COMPRESSOR_ZLIB, &bomb);
+
+        let error = decompress(&message, MAX_MESSAGE_SIZE).unwrap_err();
+
+        assert_invalid_data(
+            &error,
+            "decompresses past the maximum message size of 65536 bytes",
+        );
+    }
+
+    #[test]
+    fn understated_declared_size_is_rejected() {
+        let body = vec![1_u8; 2048];
+        let message = compressed_message(1024, COMPRESSOR_ZLIB, &zlib(&body));
+
+        let error = decompress(&message, MAX_MESSAGE_SIZE).unwrap_err();
+
+        assert_invalid_data(&error, "decompressed to 2048 bytes but declared 1024");
+    }
+
+    #[test]
+    fn unsupported_compressor_is_a_bad_value() {
+        let message = compressed_message(4, COMPRESSOR_SNAPPY, b"data");
+
+        let error = decompress(&message, MAX_MESSAGE_SIZE).unwrap_err();
+
+        assert!(matches!(
+            error.kind(),
+            crate::error::ErrorKind::DocumentDBError(crate::error::ErrorCode::BadValue, ..)
+        ));
+    }
+}
@@ -15,17 +15,19 @@ use std::{
 };
 
 use bson::RawDocument;
-use tokio::io::{AsyncRead, AsyncReadExt};
+use futures::FutureExt;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt};
 
 use crate::{
     error::{DocumentDBError, ErrorKind as DocumentDBErrorKind, Result},
     protocol::{
         header::Header,
         message::{self, Message, MessageSection},
-        op_insert, op_query,
+        op_compressed, op_insert, op_query,
         opcode::OpCode,
     },
     requests::{Request, RequestMessage, RequestType},
+    telemetry,
 };
 
 /// Read a standard message header from the client stream
@@ -60,12 +62,76 @@ where
     }
 }
 
+/// Completes once the client closes or resets its side of the stream while a request is
+/// being handled.
+///
+/// Nothing is consumed: bytes the client already sent, such as a pipelined request, stay
+/// buffered for the next read, and the client can no longer be watched until they are.
+/// Other read errors are left for the next read of the request loop to report.
+pub async fn wait_for_disconnect<S>(stream: &mut S)
+where
+    S: AsyncBufRead + Unpin,
+{
+    match stream.fill_buf().await {
+        Ok([]) => {}
+        Err(e) if is_disconnect(e.kind()) => {}
+        Ok(_) | Err(_) => std::future::pending().await,
+    }
+}
+
+const fn is_disconnect(kind: ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::UnexpectedEof | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted
+    )
+}
+
+/// Counts the requests the client has sent behind the one whose header was just read, without
+/// waiting for them to be answered, from the bytes already buffered after its body.
+///
+/// Nothing is consumed and nothing waits for more bytes, so requests that do not fit in the
+/// buffer yet are not counted.
+pub fn queued_requests<S>(stream: &mut S, header: &Header) -> usize
+where
+    S: AsyncBufRead + Unpin,
+{
+    let body_size = usize::try_from(header.length)
+        .unwrap_or_default()
+        .saturating_sub(Header::LENGTH);
+    match stream.fill_buf().now_or_never() {
+        Some(Ok(buffer)) => count_messages(buffer.get(body_size..).unwrap_or_default()),
+        _ => 0,
+    }
+}
+
+/// Counts the messages starting in `buffer`, each counted once its length is in.
+fn count_messages(mut buffer: &[u8]) -> usize {
+    let mut count = 0;
+    while let Some(length) = buffer.first_chunk::<4>() {
+        let length = usize::try_from(i32::from_le_bytes(*length)).unwrap_or_default();
+        if length < Header::LENGTH {
+            break;
+        }
+        count += 1;
+        buffer = buffer.get(length..).unwrap_or_default();
+    }
+    count
+}
+
 /// Given an already read header, read the remaining message bytes into a `RequestMessage`
 ///
+/// Messages longer than `max_message_size_bytes` are discarded from the stream unread, so the
+/// connection stays usable for the next request. `OP_COMPRESSED` messages are returned
+/// decompressed, under the same limit.
+///
 /// # Errors
 ///
-/// Returns an error if the operation fails.
-pub async fn read_request<S>(header: &Header, stream: &mut S) -> Result<RequestMessage>
+/// Returns an error if the message exceeds `max_message_size_bytes` or cannot be read.
+pub async fn read_request<S>(
+    header: &Header,
+    stream: &mut S,
+    max_message_size_bytes: i32,
+) -> Result<RequestMessage>
 where
     S: AsyncRead + Unpin,
 {
@@ -75,16 +141,39 @@ where
     })?;
 
     // 16 bytes of the message were already used by the headers
-    let mut message: Vec<u8> = vec![0; message_size - Header::LENGTH];
+    let body_size = message_size.checked_sub(Header::LENGTH).ok_or_else(|| {
+        DocumentDBError::bad_value(format!(
+            "Message length {} is shorter than the message header",
+            header.length
+        ))
+    })?;
+
+    if header.length > max_message_size_bytes {
+        tokio::io::copy(
+            &mut (&mut *stream).take(body_size as u64),
+            &mut tokio::io::sink(),
+        )
+        .await?;
+        return Err(DocumentDBError::bad_value(format!(
+            "Message size {} exceeds the maximum message size of {max_message_size_bytes} bytes",
+            header.length
+        )));
+    }
+
+    let mut message: Vec<u8> = vec![0; body_size];
 
     stream.read_exact(&mut message).await?;
 
-    Ok(RequestMessage {
+    let message = RequestMessage {
         request: message,
         op_code: header.op_code,
         request_id: header.request_id,
         response_to: header.response_to,
-    })
+    };
+    if header.op_code == OpCode::Compressed {
+        return op_compressed::decompress(&message, max_message_size_bytes);
+    }
+    Ok(message)
 }
 
 /// Parse a request message into a typed Request
@@ -116,6 +205,17 @@ pub fn parse_request<'a>(
     Ok(request)
 }
 
+/// Whether the client set `exhaustAllowed` on an `OP_MSG`, accepting replies with
+/// `moreToCome` until it closes the connection.
+#[must_use]
+pub fn exhaust_allowed(message: &RequestMessage) -> bool {
+    message.op_code == OpCode::Msg
+        && message.request.first_chunk::<4>().is_some_and(|flags| {
+            message::MessageFlags::from_bits_truncate(u32::from_le_bytes(*flags))
+                .contains(message::MessageFlags::EXHAUST_ALLOWED)
+        })
+}
+
 /// Read from a byte array until a nul terminator, parse using utf-8
 ///
 /// # Errors
@@ -186,7 +286,9 @@ pub fn parse_cmd<'a>(command: &'a RawDocument, extra: Option<&'a [u8]>) -> Resul
             return Ok(Request::Raw(RequestType::Explain, command, extra));
         }
 
-        let request_type = RequestType::from_str(cmd_name)?;
+        let request_type = RequestType::from_str(cmd_name).inspect_err(|_| {
+            telemetry::record_unsupported_command(cmd_name, false);
+        })?;
         Ok(Request::Raw(request_type, command, extra))
     } else {
         Err(DocumentDBError::bad_value(
@@ -194,3 +296,147 @@ pub fn parse_cmd<'a>(command: &'a RawDocument, extra: Option<&'a [u8]>) -> Resul
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncWriteExt, BufReader};
+
+    use super::*;
+
+    fn header(length: i32) -> Header {
+        Header {
+            length,
+            request_id: 1,
+            response_to: 0,
+            op_code: OpCode::Msg,
+        }
+    }
+
+    #[test]
+    fn queued_messages_are_counted_once_their_length_is_in() {
+        let mut buffer = Vec::new();
+        for length in [20_i32, 16] {
+            buffer.extend_from_slice(&length.to_le_bytes());
+            buffer.resize(buffer.len() + usize::try_from(length).unwrap() - 4, 0);
+        }
+        assert_eq!(count_messages(&buffer), 2);
+
+        // A third message whose length has arrived but not its body.
+        buffer.extend_from_slice(&100_i32.to_le_bytes());
+        assert_eq!(count_messages(&buffer), 3);
+        buffer.extend_from_slice(&[0; 20]);
+        assert_eq!(count_messages(&buffer), 3);
+
+        assert_eq!(count_messages(&[0, 0]), 0);
+    }
+
+    #[tokio::test]
+    async fn read_request_rejects_message_over_limit() {
+        let next_request = [7_u8; 4];
+        let mut wire = vec![0_u8; 1024 - Header::LENGTH];
+        wire.extend_from_slice(&next_request);
+        let mut stream = wire.as_slice();
+
+        let error = read_request(&header(1024), &mut stream, 512)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error.kind(),
+            DocumentDBErrorKind::DocumentDBError(crate::error::ErrorCode::BadValue, ..)
+        ));
+        assert!(error
+            .to_string()
+            .contains("Message size 1024 exceeds the maximum message size of 512 bytes"));
+        // The oversized body was consumed, leaving the stream at the next request.
+        assert_eq!(stream, next_request);
+    }
+
+    #[tokio::test]
+    async fn read_request_accepts_message_at_limit() {
+        let wire = vec![1_u8; 512 - Header::LENGTH];
+        let mut stream = wire.as_slice();
+
+        let message = read_request(&header(512), &mut stream, 512).await.unwrap();
+
+        assert_eq!(message.request, wire);
+    }
+
+    #[tokio::test]
+    async fn read_request_rejects_length_shorter_than_header() {
+        let mut stream: &[u8] = &[];
+
+        let error = read_request(&header(8), &mut stream, 512)
+            .await
+            .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("shorter than the message header"));
+    }
+
+    #[tokio::test]
+    async fn wait_for_disconnect_completes_when_client_closes() {
+        let (client, server) = tokio::io::duplex(64);
+        let mut stream = BufReader::new(server);
+        let disconnect = tokio::spawn(async move { wait_for_disconnect(&mut stream).await });
+
+        drop(client);
+
+        tokio::time::timeout(Duration::from_secs(5), disconnect)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn wait_for_disconnect_keeps_pipelined_bytes() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut stream = BufReader::new(server);
+        client.write_all(&[7_u8; 4]).await.unwrap();
+
+        let waited =
+            tokio::time::timeout(Duration::from_millis(50), wait_for_disconnect(&mut stream)).await;
+
+        assert!(waited.is_err());
+        let mut next_request = [0_u8; 4];
+        stream.read_exact(&mut next_request).await.unwrap();
+        assert_eq!(next_request, [7_u8; 4]);
+    }
+
+    struct FailingRead(ErrorKind);
+
+    impl AsyncRead for FailingRead {
+        fn poll_read(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            _buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Err(self.0.into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn wait_for_disconnect_completes_only_on_disconnect_errors() {
+        for kind in [
+            ErrorKind::UnexpectedEof,
+            ErrorKind::ConnectionReset,
+            ErrorKind::ConnectionAborted,
+        ] {
+            let mut stream = BufReader::new(FailingRead(kind));
+            tokio::time::timeout(Duration::from_secs(5), wait_for_disconnect(&mut stream))
+                .await
+                .unwrap();
+        }
+
+        for kind in [ErrorKind::Interrupted, ErrorKind::InvalidData] {
+            let mut stream = BufReader::new(FailingRead(kind));
+            let waited =
+                tokio::time::timeout(Duration::from_millis(50), wait_for_disconnect(&mut stream))
+                    .await;
+            assert!(waited.is_err(), "{kind:?} is not a disconnect");
+        }
+    }
+}
@@ -0,0 +1,171 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/requests/array_filters.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::{RawBsonRef, RawDocument};
+
+use crate::error::{DocumentDBError, ErrorCode, Result};
+
+/// Parses the `arrayFilters` of `command`, returning the identifier each filter binds.
+///
+/// The identifiers name the `$[<identifier>]` positional updates. The backend applies the
+/// filters, but accepts identifiers `MongoDB` rejects, so they are checked here.
+///
+/// # Errors
+/// Returns `BadValue` for an identifier that is not alphanumeric or doesn't begin with a
+/// lowercase letter, `FailedToParse` for an empty filter, one with several identifiers or
+/// two filters binding the same one, or `TypeMismatch` if the filters are not documents.
+pub fn from_command(command: &RawDocument) -> Result<Vec<&str>> {
+    let command_name = command
+        .into_iter()
+        .next()
+        .transpose()?
+        .map_or("", |(name, _)| name);
+    let filters = match command.get("arrayFilters")? {
+        None | Some(RawBsonRef::Null) => return Ok(Vec::new()),
+        Some(RawBsonRef::Array(filters)) => filters,
+        Some(value) => {
+            return Err(DocumentDBError::documentdb_error(
+                ErrorCode::TypeMismatch,
+                format!(
+                    "BSON field '{command_name}.arrayFilters' is the wrong type '{:?}', expected type 'array'",
+                    value.element_type()
+                ),
+            ))
+        }
+    };
+
+    let mut identifiers = Vec::new();
+    for filter in filters {
+        let filter = filter?;
+        let RawBsonRef::Document(filter) = filter else {
+            return Err(DocumentDBError::documentdb_error(
+                ErrorCode::TypeMismatch,
+                format!(
+                    "BSON field '{command_name}.arrayFilters' has an element of type '{:?}', expected type 'object'",
+                    filter.element_type()
+                ),
+            ));
+        };
+
+        if filter.is_empty() {
+            return Err(DocumentDBError::documentdb_error(
+                ErrorCode::FailedToParse,
+                "Cannot use an expression without a top-level field name in arrayFilters"
+                    .to_owned(),
+            ));
+        }
+        let Some(identifier) = filter_identifier(filter)? else {
+            continue;
+        };
+        if identifiers.contains(&identifier) {
+            return Err(DocumentDBError::documentdb_error(
+                ErrorCode::FailedToParse,
+                format!(
+                    "Found multiple array filters with the same top-level field name {identifier}"
+                ),
+            ));
+        }
+        identifiers.push(identifier);
+    }
+    Ok(identifiers)
+}
+
+/// The identifier every field of `filter` starts with, such as `elem` for
+/// `{ "elem.grade": { "$gte": 85 } }`. Logical operators such as `$or` are left to the
+/// backend, so a filter of only those has no identifier here.
+fn filter_identifier(filter: &RawDocument) -> Result<Option<&str>> {
+    let mut identifier = None;
+    for entry in filter {
+        let (path, _) = entry?;
+        if path.starts_with('$') {
+            continue;
+        }
+        let field = path.split('.').next().unwrap_or(path);
+        let mut chars = field.chars();
+        let valid = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+            && chars.all(|c| c.is_ascii_alphanumeric());
+        if !valid {
+            return Err(DocumentDBError::bad_value(format!(
+                "Error parsing array filter :: caused by :: The top-level field name must be an alphanumeric string beginning with a lowercase letter, found '{field}'"
+            )));
+        }
+        match identifier {
+            Some(identifier) if identifier != field => {
+                return Err(DocumentDBError::documentdb_error(
+                    ErrorCode::FailedToParse,
+                    format!(
+                        "Error parsing array filter :: caused by :: Expected a single top-level field name, found {identifier} and {field}"
+                    ),
+                ))
+            }
+            _ => identifier = Some(field),
+        }
+    }
+
+    Ok(identifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+
+    #[test]
+    fn returns_the_identifier_of_each_filter() {
+        let command = rawdoc! {
+            "findAndModify": "c",
+            "update": { "$set": { "grades.$[elem].passed": true, "tags.$[t]": "x" } },
+            "arrayFilters": [
+                { "elem.grade": { "$gte": 85 }, "elem.mean": { "$lt": 90 } },
+                { "t": "old" },
+                { "$or": [{ "o.a": 1 }, { "o.b": 1 }] },
+            ],
+        };
+        assert_eq!(from_command(&command).unwrap(), ["elem", "t"]);
+        assert!(from_command(&rawdoc! { "findAndModify": "c" })
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn malformed_identifiers_are_bad_values() {
+        for identifier in ["Elem", "1elem", "el_em", "el-em"] {
+            let command = rawdoc! {
+                "findAndModify": "c",
+                "arrayFilters": [{ format!("{identifier}.grade"): 1 }],
+            };
+            let err = from_command(&command).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+            assert!(
+                err.to_string().contains(&format!("found '{identifier}'")),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_ambiguous_filters() {
+        let two_identifiers = rawdoc! {
+            "findAndModify": "c",
+            "arrayFilters": [{ "a": 1, "b": 1 }],
+        };
+        let duplicate = rawdoc! {
+            "findAndModify": "c",
+            "arrayFilters": [{ "a": 1 }, { "a.b": 2 }],
+        };
+        let empty = rawdoc! { "findAndModify": "c", "arrayFilters": [{}] };
+        for command in [two_identifiers, duplicate, empty] {
+            let err = from_command(&command).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::FailedToParse));
+        }
+
+        let err = from_command(&rawdoc! { "findAndModify": "c", "arrayFilters": [1] }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::TypeMismatch));
+    }
+}
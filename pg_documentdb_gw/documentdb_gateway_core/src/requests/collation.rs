@@ -0,0 +1,277 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/requests/collation.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::RawDocument;
+
+use crate::error::{DocumentDBError, ErrorCode, Result};
+
+/// ICU locales accepted in a collation, matching the set supported by Mongo.
+const SUPPORTED_LOCALES: &[&str] = &[
+    "simple",
+    "af",
+    "am",
+    "ar",
+    "as",
+    "az",
+    "be",
+    "bg",
+    "bn",
+    "bo",
+    "bs",
+    "bs_Cyrl",
+    "ca",
+    "chr",
+    "cs",
+    "cy",
+    "da",
+    "de",
+    "de_AT",
+    "dsb",
+    "dz",
+    "ee",
+    "el",
+    "en",
+    "en_US",
+    "en_US_POSIX",
+    "eo",
+    "es",
+    "et",
+    "fa",
+    "fa_AF",
+    "fi",
+    "fi_FI",
+    "fil",
+    "fo",
+    "fr",
+    "fr_CA",
+    "ga",
+    "gl",
+    "gu",
+    "ha",
+    "haw",
+    "he",
+    "hi",
+    "hr",
+    "hsb",
+    "hu",
+    "hy",
+    "id",
+    "ig",
+    "is",
+    "it",
+    "ja",
+    "ka",
+    "kk",
+    "kl",
+    "km",
+    "kn",
+    "ko",
+    "kok",
+    "ky",
+    "lb",
+    "li",
+    "lkt",
+    "ln",
+    "lo",
+    "lt",
+    "lv",
+    "mk",
+    "ml",
+    "mn",
+    "mr",
+    "ms",
+    "mt",
+    "my",
+    "nb",
+    "ne",
+    "nl",
+    "nn",
+    "om",
+    "or",
+    "pa",
+    "pl",
+    "ps",
+    "pt",
+    "ro",
+    "ru",
+    "se",
+    "si",
+    "sk",
+    "sl",
+    "smn",
+    "sq",
+    "sr",
+    "sr_Latn",
+    "sv",
+    "sw",
+    "ta",
+    "te",
+    "th",
+    "tk",
+    "to",
+    "tr",
+    "ug",
+    "uk",
+    "ur",
+    "vi",
+    "wae",
+    "yi",
+    "yo",
+    "zh",
+    "zh_Hant",
+    "zu",
+    "ee_TG",
+    "es_419",
+    "he_IL",
+    "pt_PT",
+    "uz",
+];
+
+/// The default strength, which compares base letters, accents and case.
+const DEFAULT_STRENGTH: i32 = 3;
+
+/// A command's `collation`, which controls how strings are compared and deduplicated.
+///
+/// The command document is forwarded to the backend as is, so only the fields the
+/// gateway needs are kept; the rest are validated for type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collation {
+    pub locale: String,
+    pub strength: i32,
+}
+
+impl Collation {
+    /// Returns the validated `collation` of a command, if it has one.
+    ///
+    /// # Errors
+    /// Returns an error if the collation is malformed or its locale is not supported.
+    pub fn from_command(command: &RawDocument) -> Result<Option<Self>> {
+        match command.get("collation")? {
+            None => Ok(None),
+            Some(value) => {
+                let collation = value
+                    .as_document()
+                    .ok_or(DocumentDBError::documentdb_error(
+                        ErrorCode::TypeMismatch,
+                        format!(
+                        "BSON field 'collation' is the wrong type '{:?}', expected type 'object'",
+                        value.element_type()
+                    ),
+                    ))?;
+                Self::parse(collation).map(Some)
+            }
+        }
+    }
+
+    /// # Errors
+    /// Returns an error if the collation is malformed or its locale is not supported.
+    pub fn parse(collation: &RawDocument) -> Result<Self> {
+        let mut locale = None;
+        let mut strength = DEFAULT_STRENGTH;
+
+        for entry in collation {
+            let (k, v) = entry?;
+            match k {
+                "locale" => locale = Some(expect_type(k, v.as_str())?),
+                "strength" => {
+                    strength = expect_type(k, v.as_i32())?;
+                    if !(1..=5).contains(&strength) {
+                        return Err(DocumentDBError::bad_value(format!(
+                            "collation strength must be an integer from 1 to 5, got {strength}"
+                        )));
+                    }
+                }
+                "caseLevel" | "numericOrdering" | "backwards" | "normalization" => {
+                    expect_type(k, v.as_bool())?;
+                }
+                "caseFirst" | "alternate" | "maxVariable" => {
+                    expect_type(k, v.as_str())?;
+                }
+                "version" => {}
+                _ => {
+                    return Err(DocumentDBError::documentdb_error(
+                        ErrorCode::FailedToParse,
+                        format!("Unknown collation field '{k}'"),
+                    ))
+                }
+            }
+        }
+
+        let locale = locale.ok_or(DocumentDBError::documentdb_error(
+            ErrorCode::FailedToParse,
+            "Missing required collation field 'locale'".to_owned(),
+        ))?;
+        if !SUPPORTED_LOCALES.contains(&locale) {
+            return Err(DocumentDBError::bad_value(format!(
+                "Unsupported collation locale: '{locale}'"
+            )));
+        }
+
+        Ok(Self {
+            locale: locale.to_owned(),
+            strength,
+        })
+    }
+
+    /// The `simple` locale compares strings by their binary representation.
+    #[must_use]
+    pub fn is_simple(&self) -> bool {
+        self.locale == "simple"
+    }
+}
+
+fn expect_type<T>(field: &str, value: Option<T>) -> Result<T> {
+    value.ok_or(DocumentDBError::documentdb_error(
+        ErrorCode::TypeMismatch,
+        format!("collation field '{field}' has the wrong type"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+
+    #[test]
+    fn parses_locale_and_strength() {
+        let command = rawdoc! {
+            "distinct": "c",
+            "key": "a",
+            "collation": { "locale": "en", "strength": 2, "caseLevel": false },
+        };
+        let collation = Collation::from_command(&command).unwrap().unwrap();
+        assert_eq!(collation.locale, "en");
+        assert_eq!(collation.strength, 2);
+        assert!(!collation.is_simple());
+
+        assert_eq!(
+            Collation::from_command(&rawdoc! { "distinct": "c" }).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_locale() {
+        let err = Collation::parse(&rawdoc! { "locale": "xx_YY" }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+        assert!(err.to_string().contains("xx_YY"));
+    }
+
+    #[test]
+    fn rejects_malformed_collation() {
+        for collation in [
+            rawdoc! { "strength": 2 },
+            rawdoc! { "locale": "en", "strength": 6 },
+            rawdoc! { "locale": "en", "strength": "2" },
+            rawdoc! { "locale": "en", "unknown": 1 },
+        ] {
+            Collation::parse(&collation).unwrap_err();
+        }
+    }
+}
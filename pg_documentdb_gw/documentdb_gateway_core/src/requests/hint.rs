@@ -0,0 +1,142 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/requests/hint.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::{RawBsonRef, RawDocument, RawDocumentBuf};
+
+use crate::{
+    bson::convert_to_f64,
+    error::{DocumentDBError, ErrorCode, Result},
+};
+
+/// The `hint` of a find or count, naming the index the backend must plan the query with.
+///
+/// The backend resolves the hint against the collection's ready indexes and fails with
+/// `BadValue` when none matches, as `MongoDB` does.
+#[derive(Debug, PartialEq)]
+pub enum IndexHint<'a> {
+    /// The index with this name, such as `"a_1_b_-1"`.
+    Name(&'a str),
+    /// The index with this key pattern, such as `{ a: 1, b: -1 }`, or `{ $natural: 1 }`
+    /// for a collection scan.
+    KeyPattern(&'a RawDocument),
+}
+
+impl<'a> IndexHint<'a> {
+    /// Parses the `hint` field of `command`. An empty key pattern is no hint.
+    ///
+    /// # Errors
+    /// Returns an error if the hint is neither a string nor a document, or if its key
+    /// pattern has a field that is not a non-zero number or an index type.
+    pub fn from_command(command: &'a RawDocument) -> Result<Option<Self>> {
+        match command.get("hint")? {
+            None => Ok(None),
+            Some(RawBsonRef::String(name)) => Ok(Some(Self::Name(name))),
+            Some(RawBsonRef::Document(key_pattern)) if key_pattern.is_empty() => Ok(None),
+            Some(RawBsonRef::Document(key_pattern)) => {
+                for entry in key_pattern {
+                    let (field, direction) = entry?;
+                    let valid = match direction {
+                        RawBsonRef::String(index_type) => !index_type.is_empty(),
+                        direction => convert_to_f64(direction).is_some_and(|d| d != 0.0),
+                    };
+                    if !valid {
+                        return Err(DocumentDBError::bad_value(format!(
+                            "hint key pattern field '{field}' must be a non-zero number or an index type"
+                        )));
+                    }
+                }
+                Ok(Some(Self::KeyPattern(key_pattern)))
+            }
+            Some(value) => Err(DocumentDBError::documentdb_error(
+                ErrorCode::TypeMismatch,
+                format!(
+                    "BSON field 'hint' is the wrong type '{:?}', expected types '[string, object]'",
+                    value.element_type()
+                ),
+            )),
+        }
+    }
+}
+
+/// Validates the `hint` of a find or count, which travels to the backend as is.
+///
+/// Returns the command without its `hint` when that is an empty key pattern, which
+/// `MongoDB` treats as no hint but the backend would look up as an index, or `None` to
+/// send it unchanged.
+///
+/// # Errors
+/// Returns an error if the hint is malformed, see [`IndexHint::from_command`].
+pub fn without_empty_hint(command: &RawDocument) -> Result<Option<RawDocumentBuf>> {
+    if IndexHint::from_command(command)?.is_some() || command.get("hint")?.is_none() {
+        return Ok(None);
+    }
+
+    let mut rewritten = RawDocumentBuf::new();
+    for entry in command {
+        let (k, v) = entry?;
+        if k != "hint" {
+            rewritten.append(k, v.to_raw_bson());
+        }
+    }
+    Ok(Some(rewritten))
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+
+    #[test]
+    fn parses_name_and_key_pattern_hints() {
+        let named = rawdoc! { "find": "c", "hint": "a_1_b_-1" };
+        assert_eq!(
+            IndexHint::from_command(&named).unwrap(),
+            Some(IndexHint::Name("a_1_b_-1"))
+        );
+
+        let compound = rawdoc! { "count": "c", "hint": { "a": 1, "b": -1.0, "c": "text" } };
+        assert_eq!(
+            IndexHint::from_command(&compound).unwrap(),
+            Some(IndexHint::KeyPattern(
+                rawdoc! { "a": 1, "b": -1.0, "c": "text" }.as_ref()
+            ))
+        );
+
+        assert!(without_empty_hint(&named).unwrap().is_none());
+        assert!(without_empty_hint(&rawdoc! { "find": "c" })
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn empty_key_pattern_is_no_hint() {
+        let command = rawdoc! { "find": "c", "hint": {}, "filter": { "a": 1 } };
+        assert_eq!(IndexHint::from_command(&command).unwrap(), None);
+        assert_eq!(
+            without_empty_hint(&command).unwrap(),
+            Some(rawdoc! { "find": "c", "filter": { "a": 1 } })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_hints() {
+        let err = IndexHint::from_command(&rawdoc! { "find": "c", "hint": 1 }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::TypeMismatch));
+
+        for hint in [
+            rawdoc! { "a": 0 },
+            rawdoc! { "a": true },
+            rawdoc! { "a": "" },
+        ] {
+            let err = IndexHint::from_command(&rawdoc! { "find": "c", "hint": hint }).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+            assert!(err.to_string().contains("'a'"), "{err}");
+        }
+    }
+}
@@ -0,0 +1,328 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/requests/let_variables.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::{RawBsonRef, RawDocument};
+
+use crate::{
+    error::{DocumentDBError, ErrorCode, Result},
+    processor::sequence_documents,
+};
+
+/// Variables every expression can reference without binding them.
+const SYSTEM_VARIABLES: &[&str] = &[
+    "NOW",
+    "CLUSTER_TIME",
+    "ROOT",
+    "CURRENT",
+    "REMOVE",
+    "DESCEND",
+    "PRUNE",
+    "KEEP",
+    "SEARCH_META",
+    "USER_ROLES",
+];
+
+/// The variables a write command binds with its top-level `let`, which the backend makes
+/// available as `$$name` to the `$expr` of its filters and to its pipeline updates.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LetVariables<'a> {
+    names: Vec<&'a str>,
+}
+
+impl<'a> LetVariables<'a> {
+    /// Parses the `let` of `command`; a missing or null `let` binds nothing.
+    ///
+    /// # Errors
+    /// Returns an error if `let` is not a document or binds a name a user variable can't have.
+    pub fn from_command(command: &'a RawDocument) -> Result<Self> {
+        let command_name = command
+            .into_iter()
+            .next()
+            .transpose()?
+            .map_or("", |(name, _)| name);
+        let variables = match command.get("let")? {
+            None | Some(RawBsonRef::Null | RawBsonRef::Undefined) => return Ok(Self::default()),
+            Some(RawBsonRef::Document(variables)) => variables,
+            Some(value) => {
+                return Err(DocumentDBError::documentdb_error(
+                    ErrorCode::TypeMismatch,
+                    format!(
+                        "BSON field '{command_name}.let' is the wrong type '{:?}', expected type 'object'",
+                        value.element_type()
+                    ),
+                ))
+            }
+        };
+
+        let mut names = Vec::new();
+        for entry in variables {
+            let (name, _) = entry?;
+            validate_name(name)?;
+            names.push(name);
+        }
+        Ok(Self { names })
+    }
+
+    /// Checks that the `$expr` clauses of `filter` only reference bound variables.
+    ///
+    /// # Errors
+    /// Returns `FailedToParse` naming the first undefined variable.
+    pub fn check_filter(&self, filter: &RawDocument) -> Result<()> {
+        for entry in filter {
+            match entry? {
+                ("$expr", expression) => self.check_expression(expression)?,
+                ("$and" | "$or" | "$nor", RawBsonRef::Array(clauses)) => {
+                    for clause in clauses {
+                        if let RawBsonRef::Document(clause) = clause? {
+                            self.check_filter(clause)?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `expression`, or the stages of a pipeline update, only reference bound
+    /// variables, including those bound inside it by `$let`, `$map`, `$filter` and `$reduce`.
+    ///
+    /// # Errors
+    /// Returns `FailedToParse` naming the first undefined variable.
+    pub fn check_expression(&self, expression: RawBsonRef<'_>) -> Result<()> {
+        let mut scope = self.names.clone();
+        check_in_scope(expression, &mut scope)
+    }
+}
+
+/// Checks the `let` of a write command and the variables its statements reference.
+///
+/// Each statement's filter under `filter_key` is checked and, for pipeline updates, the
+/// pipeline under `update_key`. Statements are the entries of `statements_key`, or of the
+/// document sequence `extra`, or the command itself when `statements_key` is `None`, as
+/// for findAndModify.
+///
+/// # Errors
+/// Returns an error if `let` is malformed or a statement references an undefined variable.
+pub fn check_write_variables(
+    command: &RawDocument,
+    extra: Option<&[u8]>,
+    statements_key: Option<&str>,
+    filter_key: &str,
+    update_key: Option<&str>,
+) -> Result<()> {
+    let variables = LetVariables::from_command(command)?;
+    let check_statement = |statement: &RawDocument| -> Result<()> {
+        if let Some(RawBsonRef::Document(filter)) = statement.get(filter_key)? {
+            variables.check_filter(filter)?;
+        }
+        if let Some(pipeline @ RawBsonRef::Array(_)) = update_key
+            .map(|key| statement.get(key))
+            .transpose()?
+            .flatten()
+        {
+            variables.check_expression(pipeline)?;
+        }
+        Ok(())
+    };
+
+    let Some(statements_key) = statements_key else {
+        return check_statement(command);
+    };
+    if let Some(sequence) = extra {
+        for statement in sequence_documents(sequence)? {
+            check_statement(RawDocument::from_bytes(statement)?)?;
+        }
+    }
+    if let Some(RawBsonRef::Array(statements)) = command.get(statements_key)? {
+        for statement in statements {
+            if let RawBsonRef::Document(statement) = statement? {
+                check_statement(statement)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// User variable names start with a lowercase letter or a non-ASCII character, followed by
+/// letters, digits, underscores or non-ASCII characters.
+fn validate_name(name: &str) -> Result<()> {
+    let invalid = |reason: &str| {
+        Err(DocumentDBError::documentdb_error(
+            ErrorCode::FailedToParse,
+            format!("'{name}' {reason} for a user variable name"),
+        ))
+    };
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return Err(DocumentDBError::documentdb_error(
+            ErrorCode::FailedToParse,
+            "empty variable names are not allowed".to_owned(),
+        ));
+    };
+    if first.is_ascii() && !first.is_ascii_lowercase() {
+        return invalid("starts with an invalid character");
+    }
+    if !chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()) {
+        return invalid("contains an invalid character");
+    }
+    Ok(())
+}
+
+fn check_in_scope<'a>(expression: RawBsonRef<'a>, scope: &mut Vec<&'a str>) -> Result<()> {
+    match expression {
+        RawBsonRef::String(path) => {
+            let Some(variable) = path.strip_prefix("$$") else {
+                return Ok(());
+            };
+            let name = variable.split('.').next().unwrap_or(variable);
+            if SYSTEM_VARIABLES.contains(&name) || scope.contains(&name) {
+                return Ok(());
+            }
+            Err(DocumentDBError::documentdb_error(
+                ErrorCode::FailedToParse,
+                format!("Use of undefined variable: {name}"),
+            ))
+        }
+        RawBsonRef::Array(elements) => {
+            for element in elements {
+                check_in_scope(element?, scope)?;
+            }
+            Ok(())
+        }
+        RawBsonRef::Document(document) => {
+            for entry in document {
+                match entry? {
+                    ("$literal", _) => {}
+                    (
+                        operator @ ("$let" | "$map" | "$filter" | "$reduce"),
+                        RawBsonRef::Document(spec),
+                    ) => check_binding(operator, spec, scope)?,
+                    (_, value) => check_in_scope(value, scope)?,
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks an operator that binds variables for part of its arguments: the `vars` of `$let`,
+/// the `as` of `$map` and `$filter`, which defaults to `this`, and `this` and `value` in
+/// `$reduce`.
+fn check_binding<'a>(
+    operator: &str,
+    spec: &'a RawDocument,
+    scope: &mut Vec<&'a str>,
+) -> Result<()> {
+    let mut bound = Vec::new();
+    match operator {
+        "$let" => {
+            if let Some(RawBsonRef::Document(vars)) = spec.get("vars")? {
+                for entry in vars {
+                    let (name, value) = entry?;
+                    check_in_scope(value, scope)?;
+                    bound.push(name);
+                }
+            }
+        }
+        "$reduce" => bound.extend(["this", "value"]),
+        _ => bound.push(spec.get_str("as").unwrap_or("this")),
+    }
+
+    for entry in spec {
+        let (key, value) = entry?;
+        match key {
+            "vars" | "as" => {}
+            "in" | "cond" => {
+                let outer = scope.len();
+                scope.extend(&bound);
+                let result = check_in_scope(value, scope);
+                scope.truncate(outer);
+                result?;
+            }
+            _ => check_in_scope(value, scope)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+
+    #[test]
+    fn bound_variables_may_be_referenced() {
+        let update = rawdoc! {
+            "update": "c",
+            "updates": [{
+                "q": { "$expr": { "$eq": ["$a", "$$target"] } },
+                "u": [{ "$set": {
+                    "b": "$$newValue.x",
+                    "c": { "$map": { "input": "$arr", "as": "e", "in": { "$add": ["$$e", "$$ROOT.n"] } } },
+                    "d": { "$let": { "vars": { "t": "$$target" }, "in": "$$t" } },
+                } }],
+            }],
+            "let": { "target": 1, "newValue": { "x": 2 } },
+        };
+        check_write_variables(&update, None, Some("updates"), "q", Some("u")).unwrap();
+    }
+
+    #[test]
+    fn undefined_variable_is_named() {
+        let update = rawdoc! {
+            "update": "c",
+            "updates": [{ "q": {}, "u": [{ "$set": { "b": "$$missing" } }] }],
+            "let": { "target": 1 },
+        };
+        let err =
+            check_write_variables(&update, None, Some("updates"), "q", Some("u")).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::FailedToParse));
+        assert!(
+            err.to_string().contains("undefined variable: missing"),
+            "{err}"
+        );
+
+        // A variable bound by $map is out of scope in its input.
+        let find_and_modify = rawdoc! {
+            "findAndModify": "c",
+            "query": { "$expr": { "$map": { "input": "$$e", "as": "e", "in": "$$e" } } },
+        };
+        let err = check_write_variables(&find_and_modify, None, None, "query", Some("update"))
+            .unwrap_err();
+        assert!(err.to_string().contains("undefined variable: e"), "{err}");
+    }
+
+    #[test]
+    fn modifier_updates_and_literals_are_not_expressions() {
+        let update = rawdoc! {
+            "update": "c",
+            "updates": [
+                { "q": { "a": "$$notAVariable" }, "u": { "$set": { "b": "$$notAVariable" } } },
+                { "q": {}, "u": [{ "$set": { "b": { "$literal": "$$notAVariable" } } }] },
+            ],
+        };
+        check_write_variables(&update, None, Some("updates"), "q", Some("u")).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_let() {
+        let err = LetVariables::from_command(&rawdoc! { "delete": "c", "let": 1 }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::TypeMismatch));
+        assert!(err.to_string().contains("'delete.let'"), "{err}");
+
+        for name in ["Upper", "_under", "has-dash"] {
+            let command = rawdoc! { "delete": "c", "let": { name: 1 } };
+            let err = LetVariables::from_command(&command).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::FailedToParse));
+        }
+        LetVariables::from_command(&rawdoc! { "delete": "c", "let": null }).unwrap();
+    }
+}
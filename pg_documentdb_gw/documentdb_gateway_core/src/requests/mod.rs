@@ -6,21 +6,29 @@
  *-------------------------------------------------------------------------
  */
 
+pub mod array_filters;
+pub mod collation;
+pub mod hint;
+pub mod let_variables;
+pub mod query_limits;
 pub mod read_concern;
 pub mod read_preference;
 pub mod request_tracker;
 pub mod request_type;
+pub mod special_values;
+pub mod unknown_options;
 pub mod validation;
+pub mod write_concern;
 
 use std::{fmt::Debug, str::FromStr};
 
-use bson::{spec::ElementType, Document, RawBsonRef, RawDocument, RawDocumentBuf};
+use bson::{spec::ElementType, Bson, Document, RawBsonRef, RawDocument, RawDocumentBuf};
 use read_concern::ReadConcern;
 use read_preference::ReadPreference;
-use tokio_postgres::IsolationLevel;
+use write_concern::WriteConcern;
 
 use crate::{
-    bson::convert_to_f64,
+    bson::{convert_to_bool, convert_to_f64},
     context::{RequestTransactionInfo, SessionId, TransactionNumber},
     error::{DocumentDBError, ErrorCode, Result},
     protocol::opcode::OpCode,
@@ -29,6 +37,10 @@ use crate::{
 pub use request_tracker::RequestIntervalKind;
 pub use request_type::RequestType;
 
+/// Command field a client sets to `true` to have the gateway's timing of the request
+/// appended to the response under the same name.
+pub const GATEWAY_TIMING_FIELD: &str = "$gatewayTiming";
+
 /// The `RequestMessage` holds ownership to the whole client message.
 ///
 /// Other objects, like the `Request` will only hold references to it.
@@ -54,6 +66,9 @@ pub struct RequestInfo<'a> {
     collection: Option<&'a str>,
     pub session_id: Option<SessionId>,
     read_concern: ReadConcern,
+    write_concern: Option<WriteConcern>,
+    /// The client's `comment`, see [`comment_text`].
+    pub comment: Option<String>,
 }
 
 impl RequestInfo<'_> {
@@ -66,6 +81,8 @@ impl RequestInfo<'_> {
             collection: None,
             session_id: None,
             read_concern: ReadConcern::default(),
+            write_concern: None,
+            comment: None,
         }
     }
 
@@ -90,6 +107,25 @@ impl RequestInfo<'_> {
     pub const fn read_concern(&self) -> &ReadConcern {
         &self.read_concern
     }
+
+    /// The `writeConcern` the client sent, if any.
+    #[must_use]
+    pub const fn write_concern(&self) -> Option<&WriteConcern> {
+        self.write_concern.as_ref()
+    }
+}
+
+/// A command's `comment` as text: strings are kept as they are and any other value is
+/// rendered as extended JSON, so that a comment of any type can be reported and logged.
+#[must_use]
+pub fn comment_text(comment: RawBsonRef<'_>) -> String {
+    match comment {
+        RawBsonRef::String(text) => text.to_owned(),
+        other => Bson::try_from(other.to_raw_bson()).map_or_else(
+            |_| String::new(),
+            |bson| bson.into_relaxed_extjson().to_string(),
+        ),
+    }
 }
 
 impl<'a> Request<'a> {
@@ -133,6 +169,74 @@ impl<'a> Request<'a> {
             .map_err(DocumentDBError::parse_failure())
     }
 
+    /// Returns the client's `comment`, see [`comment_text`].
+    #[must_use]
+    pub fn comment(&self) -> Option<String> {
+        match self.document().get("comment") {
+            Ok(Some(value)) => Some(comment_text(value)),
+            _ => None,
+        }
+    }
+
+    /// Returns the `readConcern` level the client asked for, if any.
+    #[must_use]
+    pub fn read_concern_level(&self) -> Option<ReadConcern> {
+        let level = self
+            .document()
+            .get_document("readConcern")
+            .ok()?
+            .get_str("level")
+            .ok()?;
+        ReadConcern::from_str(level)
+            .ok()
+            .filter(|level| *level != ReadConcern::Unspecified)
+    }
+
+    /// Returns the client-requested `maxTimeMS`, or `None` if absent or not numeric.
+    #[must_use]
+    pub fn requested_max_time_ms(&self) -> Option<i64> {
+        match self.document().get("maxTimeMS") {
+            Ok(Some(value)) => Self::to_i64(value).ok(),
+            _ => None,
+        }
+    }
+
+    /// Splits off the `$gatewayTiming` flag, which the gateway handles itself and never
+    /// forwards. When the flag is present, returns the command without it and whether
+    /// timing was requested.
+    ///
+    /// # Errors
+    /// Returns error if the document is malformed or the flag is not a boolean.
+    pub fn take_gateway_timing(&self) -> Result<Option<(RawDocumentBuf, bool)>> {
+        let Some(flag) = self.document().get(GATEWAY_TIMING_FIELD)? else {
+            return Ok(None);
+        };
+        let enabled = convert_to_bool(flag).ok_or(DocumentDBError::type_mismatch(format!(
+            "{GATEWAY_TIMING_FIELD} should be a bool"
+        )))?;
+
+        let mut document = RawDocumentBuf::new();
+        for entry in self.document() {
+            let (k, v) = entry?;
+            if k != GATEWAY_TIMING_FIELD {
+                document.append(k, v.to_raw_bson());
+            }
+        }
+        Ok(Some((document, enabled)))
+    }
+
+    /// The same request with its command replaced by `document`.
+    #[must_use]
+    pub fn with_document<'b>(self, document: &'b RawDocument) -> Request<'b>
+    where
+        'a: 'b,
+    {
+        match self {
+            Self::Raw(t, _, extra) => Request::Raw(t, document, extra),
+            Self::RawBuf(t, _) => Request::Raw(t, document, None),
+        }
+    }
+
     /// # Errors
     /// Returns error if field extraction fails.
     pub fn extract_fields<F>(&self, mut f: F) -> Result<()>
@@ -213,6 +317,8 @@ impl<'a> Request<'a> {
         let mut isolation_level = None;
         let mut collection = None;
         let mut read_concern = ReadConcern::default();
+        let mut write_concern = None;
+        let mut comment = None;
 
         let collection_field = self.collection_field();
         for entry in self.document() {
@@ -268,10 +374,15 @@ impl<'a> Request<'a> {
                         .get_str("level")
                         .unwrap_or("");
                     read_concern = ReadConcern::from_str(level).unwrap_or(ReadConcern::default());
-                    if read_concern == ReadConcern::Snapshot {
-                        isolation_level = Some(IsolationLevel::RepeatableRead);
+                    isolation_level = read_concern.isolation_level();
+                }
+                // The backend ignores a writeConcern that is not a document, and so do we.
+                "writeConcern" => {
+                    if let Some(document) = v.as_document() {
+                        write_concern = Some(WriteConcern::parse(document)?);
                     }
                 }
+                "comment" => comment = Some(comment_text(v)),
                 "$readPreference" => ReadPreference::parse(v.as_document())?,
                 key if collection_field.contains(&key) => {
                     // Aggregate needs special handling because having '1' as a collection is valid
@@ -309,6 +420,8 @@ impl<'a> Request<'a> {
             collection,
             session_id,
             read_concern,
+            write_concern,
+            comment,
         })
     }
 
@@ -0,0 +1,253 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/requests/query_limits.rs
+ *
+ * Bounds on the shape of filters and pipelines, checked before a request
+ * reaches the backend, whose planner recurses over them.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::{RawArray, RawBsonRef, RawDocument};
+
+use crate::{
+    error::{DocumentDBError, Result},
+    processor::sequence_documents,
+    requests::{Request, RequestType},
+};
+
+/// Operators whose array operand the backend expands into one clause per element.
+const LIST_OPERATORS: &[&str] = &["$in", "$nin", "$or", "$and", "$nor", "$all"];
+
+/// Limits on the filters and pipelines of a request, each the most allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryLimits {
+    /// Stages in a pipeline, counted separately for each sub-pipeline of `$facet`, `$lookup`
+    /// and `$unionWith`.
+    pub pipeline_stages: usize,
+    /// Levels of documents and arrays nested in a filter or pipeline.
+    pub nesting_depth: usize,
+    /// Elements in the array of a `$in`, `$nin`, `$or`, `$and`, `$nor` or `$all`.
+    pub list_length: usize,
+}
+
+impl Default for QueryLimits {
+    /// `MongoDB`'s own pipeline and BSON depth limits; long `$in` lists are common, so their
+    /// default only stops lists approaching the size of a whole message.
+    fn default() -> Self {
+        Self {
+            pipeline_stages: 1000,
+            nesting_depth: 200,
+            list_length: 500_000,
+        }
+    }
+}
+
+/// Checks the filters and pipelines `request` carries against `limits`.
+///
+/// # Errors
+///
+/// Returns `BadValue` naming the limit a filter or pipeline exceeds, or an error if the
+/// command is malformed.
+pub fn check_query_limits(request: &Request<'_>, limits: QueryLimits) -> Result<()> {
+    let command = request.document();
+    let (statements, filter) = match request.request_type() {
+        RequestType::Aggregate => {
+            return match command.get("pipeline")? {
+                Some(RawBsonRef::Array(pipeline)) => check_pipeline(pipeline, 1, limits),
+                _ => Ok(()),
+            };
+        }
+        RequestType::Find => return check_field(command, "filter", limits),
+        RequestType::Count | RequestType::Distinct | RequestType::FindAndModify => {
+            return check_field(command, "query", limits);
+        }
+        RequestType::Delete => ("deletes", "q"),
+        RequestType::Update => ("updates", "q"),
+        _ => return Ok(()),
+    };
+
+    if let Some(sequence) = request.extra() {
+        for statement in sequence_documents(sequence)? {
+            check_field(RawDocument::from_bytes(statement)?, filter, limits)?;
+        }
+    }
+    if let Some(RawBsonRef::Array(array)) = command.get(statements)? {
+        for statement in array {
+            if let RawBsonRef::Document(statement) = statement? {
+                check_field(statement, filter, limits)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_field(document: &RawDocument, field: &str, limits: QueryLimits) -> Result<()> {
+    document
+        .get(field)?
+        .map_or(Ok(()), |value| check_value(value, 1, limits))
+}
+
+fn check_pipeline(pipeline: &RawArray, depth: usize, limits: QueryLimits) -> Result<()> {
+    let mut stages = 0;
+    for stage in pipeline {
+        stages += 1;
+        if stages > limits.pipeline_stages {
+            return Err(DocumentDBError::bad_value(format!(
+                "Pipeline length must be no longer than {} stages",
+                limits.pipeline_stages
+            )));
+        }
+        check_value(stage?, depth + 1, limits)?;
+    }
+    Ok(())
+}
+
+fn check_value(value: RawBsonRef, depth: usize, limits: QueryLimits) -> Result<()> {
+    if !matches!(value, RawBsonRef::Document(_) | RawBsonRef::Array(_)) {
+        return Ok(());
+    }
+    if depth > limits.nesting_depth {
+        return Err(DocumentDBError::bad_value(format!(
+            "Query or pipeline is nested deeper than the maximum of {} levels",
+            limits.nesting_depth
+        )));
+    }
+
+    match value {
+        RawBsonRef::Array(array) => {
+            for element in array {
+                check_value(element?, depth + 1, limits)?;
+            }
+        }
+        RawBsonRef::Document(document) => {
+            for entry in document {
+                check_entry(entry?, depth, limits)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Checks a field of a document at `depth`, which holds a sub-pipeline or an operator list
+/// if it is named for one.
+fn check_entry((key, value): (&str, RawBsonRef), depth: usize, limits: QueryLimits) -> Result<()> {
+    match (key, value) {
+        ("pipeline", RawBsonRef::Array(pipeline)) => check_pipeline(pipeline, depth + 1, limits),
+        ("$facet", RawBsonRef::Document(facets)) => {
+            for facet in facets {
+                if let (_, RawBsonRef::Array(pipeline)) = facet? {
+                    check_pipeline(pipeline, depth + 2, limits)?;
+                }
+            }
+            Ok(())
+        }
+        (operator, RawBsonRef::Array(list)) if LIST_OPERATORS.contains(&operator) => {
+            let length = list.into_iter().count();
+            if length > limits.list_length {
+                return Err(DocumentDBError::bad_value(format!(
+                    "{operator} array has {length} elements, more than the maximum of {}",
+                    limits.list_length
+                )));
+            }
+            check_value(value, depth + 1, limits)
+        }
+        _ => check_value(value, depth + 1, limits),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::{rawdoc, RawArrayBuf, RawDocumentBuf};
+
+    use super::*;
+    use crate::error::ErrorCode;
+
+    const LIMITS: QueryLimits = QueryLimits {
+        pipeline_stages: 3,
+        nesting_depth: 6,
+        list_length: 4,
+    };
+
+    fn nested_filter(levels: usize) -> RawDocumentBuf {
+        let mut filter = rawdoc! { "a": 1 };
+        for _ in 1..levels {
+            filter = rawdoc! { "$and": [filter] };
+        }
+        filter
+    }
+
+    fn check(request_type: RequestType, command: &RawDocument) -> Result<()> {
+        check_query_limits(&Request::Raw(request_type, command, None), LIMITS)
+    }
+
+    #[test]
+    fn nesting_deeper_than_the_limit_is_rejected() {
+        // Each $and adds a document and an array around the filter below it.
+        let find = rawdoc! { "find": "c", "filter": nested_filter(3) };
+        check(RequestType::Find, &find).unwrap();
+
+        let find = rawdoc! { "find": "c", "filter": nested_filter(4) };
+        let err = check(RequestType::Find, &find).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+        assert!(err.to_string().contains("maximum of 6 levels"), "{err}");
+
+        let aggregate = rawdoc! {
+            "aggregate": "c",
+            "pipeline": [{ "$facet": { "f": [{ "$match": nested_filter(2) }] } }],
+        };
+        let err = check(RequestType::Aggregate, &aggregate).unwrap_err();
+        assert!(err.to_string().contains("maximum of 6 levels"), "{err}");
+    }
+
+    #[test]
+    fn lists_longer_than_the_limit_are_rejected() {
+        let list = |length: i32| {
+            (0..length)
+                .map(bson::RawBson::Int32)
+                .collect::<RawArrayBuf>()
+        };
+
+        let count = rawdoc! { "count": "c", "query": { "a": { "$in": list(4) } } };
+        check(RequestType::Count, &count).unwrap();
+
+        let count = rawdoc! { "count": "c", "query": { "a": { "$in": list(5) } } };
+        let err = check(RequestType::Count, &count).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+        assert!(
+            err.to_string().contains("$in array has 5 elements"),
+            "{err}"
+        );
+
+        let clauses = (0..5).map(|i| rawdoc! { "a": i }).collect::<RawArrayBuf>();
+        let delete =
+            rawdoc! { "delete": "c", "deletes": [{ "q": { "$or": clauses }, "limit": 0 }] };
+        let err = check(RequestType::Delete, &delete).unwrap_err();
+        assert!(
+            err.to_string().contains("$or array has 5 elements"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn each_pipeline_is_limited_to_its_own_stages() {
+        let aggregate = rawdoc! {
+            "aggregate": "c",
+            "pipeline": [
+                { "$match": {} },
+                { "$lookup": { "from": "d", "as": "d", "pipeline": [{ "$match": {} }, { "$limit": 1 }] } },
+                { "$limit": 1 },
+            ],
+        };
+        check(RequestType::Aggregate, &aggregate).unwrap();
+
+        let aggregate = rawdoc! {
+            "aggregate": "c",
+            "pipeline": [{ "$unionWith": { "coll": "d", "pipeline": [{}, {}, {}, {}] } }],
+        };
+        let err = check(RequestType::Aggregate, &aggregate).unwrap_err();
+        assert!(err.to_string().contains("no longer than 3 stages"), "{err}");
+    }
+}
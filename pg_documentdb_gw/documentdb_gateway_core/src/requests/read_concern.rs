@@ -8,6 +8,8 @@
 
 use std::str::FromStr;
 
+use tokio_postgres::IsolationLevel;
+
 #[derive(Debug, Default, PartialEq, Eq)]
 pub enum ReadConcern {
     /// Read concern is not specified.
@@ -47,3 +49,86 @@ impl FromStr for ReadConcern {
         }
     }
 }
+
+impl ReadConcern {
+    /// The level as clients name it.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "unspecified",
+            Self::Local => "local",
+            Self::Available => "available",
+            Self::Majority => "majority",
+            Self::Linearizable => "linearizable",
+            Self::Snapshot => "snapshot",
+        }
+    }
+
+    /// The isolation level a transaction with this read concern runs at on the backend,
+    /// or `None` to use the default. A single node has no replication lag, so `majority`
+    /// reads the same committed data as `local`.
+    #[must_use]
+    pub const fn isolation_level(&self) -> Option<IsolationLevel> {
+        match self {
+            Self::Snapshot => Some(IsolationLevel::RepeatableRead),
+            Self::Local | Self::Available | Self::Majority => Some(IsolationLevel::ReadCommitted),
+            Self::Unspecified | Self::Linearizable => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+    use crate::{
+        context::RequestTransactionInfo,
+        requests::{Request, RequestType},
+    };
+
+    fn transaction_isolation(level: &str) -> Option<IsolationLevel> {
+        transaction_info(level).isolation_level
+    }
+
+    fn transaction_info(level: &str) -> RequestTransactionInfo {
+        let command = rawdoc! {
+            "find": "c",
+            "$db": "db",
+            "lsid": { "id": bson::Binary { subtype: bson::spec::BinarySubtype::Uuid, bytes: vec![0; 16] } },
+            "txnNumber": 1_i64,
+            "autocommit": false,
+            "startTransaction": true,
+            "readConcern": { "level": level },
+        };
+        let request = Request::Raw(RequestType::Find, &command, None);
+        request.extract_common().unwrap().transaction_info.unwrap()
+    }
+
+    #[test]
+    fn snapshot_runs_at_repeatable_read() {
+        assert!(matches!(
+            transaction_isolation("snapshot"),
+            Some(IsolationLevel::RepeatableRead)
+        ));
+    }
+
+    #[test]
+    fn local_available_and_majority_run_at_read_committed() {
+        for level in ["local", "available", "majority"] {
+            assert!(
+                matches!(
+                    transaction_isolation(level),
+                    Some(IsolationLevel::ReadCommitted)
+                ),
+                "{level}"
+            );
+        }
+    }
+
+    #[test]
+    fn unspecified_level_uses_the_default_isolation() {
+        assert!(transaction_isolation("").is_none());
+        assert!(ReadConcern::Linearizable.isolation_level().is_none());
+    }
+}
@@ -6,10 +6,15 @@
  *-------------------------------------------------------------------------
  */
 
-use std::sync::atomic::{AtomicI64, Ordering};
-use tokio::time::Instant;
+use std::{
+    sync::atomic::{AtomicI64, Ordering},
+    time::SystemTime,
+};
 
-#[derive(Debug)]
+use bson::{rawdoc, RawDocumentBuf};
+use tokio::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy)]
 pub enum RequestIntervalKind {
     /// Time spent reading stream from request body.
     ReadRequest,
@@ -37,9 +42,19 @@ pub enum RequestIntervalKind {
     /// Time spent committing a Postgres transaction.
     PostgresCommitTransaction,
 
-    /// Time spent acquiring a connection from the Postgres connection pool.
+    /// Time spent acquiring a connection from the Postgres connection pool, including
+    /// opening a new one.
     OpenBackendConnection,
 
+    /// The part of `OpenBackendConnection` spent on a pool with no idle connection and no
+    /// room for another, waiting for a connection to be returned.
+    PoolCheckout,
+
+    /// Time the request waited before the gateway started reading it: behind the requests the
+    /// client pipelined ahead of it, from when its bytes were first seen buffered, and for a
+    /// connection's first request, for the connection gate to admit the connection.
+    QueueWait,
+
     /// Time spent writing the response to the stream.
     WriteResponse,
 
@@ -50,6 +65,10 @@ pub enum RequestIntervalKind {
 #[derive(Debug)]
 pub struct RequestTracker {
     pub request_interval_metrics_array: [AtomicI64; RequestIntervalKind::MaxUnused as usize],
+
+    /// Wall-clock time at which the tracker was created, right before `ReadRequest` starts.
+    /// Used to anchor exported spans, since the intervals themselves are monotonic durations.
+    start_time: SystemTime,
 }
 
 impl Default for RequestTracker {
@@ -63,12 +82,22 @@ impl RequestTracker {
     pub fn new() -> Self {
         Self {
             request_interval_metrics_array: std::array::from_fn(|_| AtomicI64::new(0)),
+            start_time: SystemTime::now(),
         }
     }
 
-    #[expect(clippy::cast_possible_truncation, reason = "nanoseconds fit in i64")]
+    #[must_use]
+    pub const fn start_time(&self) -> SystemTime {
+        self.start_time
+    }
+
     pub fn record_duration(&self, interval: RequestIntervalKind, start_time: Instant) {
-        let elapsed = start_time.elapsed();
+        self.record_elapsed(interval, start_time.elapsed());
+    }
+
+    /// Adds `elapsed` to `interval`, for time measured before the tracker was created.
+    #[expect(clippy::cast_possible_truncation, reason = "nanoseconds fit in i64")]
+    pub fn record_elapsed(&self, interval: RequestIntervalKind, elapsed: Duration) {
         self.request_interval_metrics_array[interval as usize]
             .fetch_add(elapsed.as_nanos() as i64, Ordering::Relaxed);
     }
@@ -80,4 +109,18 @@ impl RequestTracker {
     pub fn get_interval_elapsed_time_ms(&self, interval: RequestIntervalKind) -> i64 {
         self.get_interval_elapsed_time(interval) / 1_000_000
     }
+
+    pub fn get_interval_elapsed_time_us(&self, interval: RequestIntervalKind) -> i64 {
+        self.get_interval_elapsed_time(interval) / 1_000
+    }
+
+    /// The backend phases of the request in microseconds, as returned to clients that set `$gatewayTiming`.
+    #[must_use]
+    pub fn gateway_timing(&self) -> RawDocumentBuf {
+        rawdoc! {
+            "beginTransactionMicros": self.get_interval_elapsed_time_us(RequestIntervalKind::PostgresBeginTransaction),
+            "executionMicros": self.get_interval_elapsed_time_us(RequestIntervalKind::ProcessRequest),
+            "commitMicros": self.get_interval_elapsed_time_us(RequestIntervalKind::PostgresCommitTransaction),
+        }
+    }
 }
@@ -195,6 +195,7 @@ pub enum RequestType {
     RevokeRolesFromRole,
     RevokeRolesFromUser,
     RolesInfo,
+    RotateTelemetry,
     SaslContinue,
     SaslStart,
     ServerStatus,
@@ -224,12 +225,30 @@ pub enum RequestType {
     Writebacklisten,
 }
 
+/// Broad class of a command, used to pick per-category defaults.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+pub enum CommandCategory {
+    Read,
+    Write,
+    Admin,
+    Ddl,
+}
+
 impl RequestType {
     #[must_use]
     pub const fn handle_with_auth(self) -> bool {
         matches!(&self, Self::Logout | Self::SaslContinue | Self::SaslStart)
     }
 
+    /// Commands answered from gateway state alone, without a data client or a pool.
+    #[must_use]
+    pub const fn gateway_only(self) -> bool {
+        matches!(
+            &self,
+            Self::IsMaster | Self::Hello | Self::Ping | Self::BuildInfo
+        )
+    }
+
     #[must_use]
     pub const fn allowed_unauthorized(self) -> bool {
         matches!(
@@ -432,6 +451,7 @@ impl RequestType {
             Self::RevokeRolesFromRole => "revokeRolesFromRole",
             Self::RevokeRolesFromUser => "revokeRolesFromUser",
             Self::RolesInfo => "rolesInfo",
+            Self::RotateTelemetry => "rotateTelemetry",
             Self::SaslContinue => "saslContinue",
             Self::SaslStart => "saslStart",
             Self::ServerStatus => "serverStatus",
@@ -462,6 +482,51 @@ impl RequestType {
         }
     }
 
+    /// Returns the category of this command. Commands that neither read, write nor change
+    /// the schema of user data are `Admin`.
+    #[must_use]
+    pub const fn category(self) -> CommandCategory {
+        match self {
+            Self::Aggregate
+            | Self::Count
+            | Self::Distinct
+            | Self::Explain
+            | Self::Find
+            | Self::GeoNear
+            | Self::GeoSearch
+            | Self::GetMore
+            | Self::ListCollections
+            | Self::ListDatabases
+            | Self::ListIndexes
+            | Self::MapReduce
+            | Self::ParallelCollectionScan => CommandCategory::Read,
+            Self::AbortTransaction
+            | Self::CommitTransaction
+            | Self::Delete
+            | Self::FindAndModify
+            | Self::Insert
+            | Self::Update => CommandCategory::Write,
+            Self::CloneCollectionAsCapped
+            | Self::CollMod
+            | Self::Compact
+            | Self::ConvertToCapped
+            | Self::Create
+            | Self::CreateIndex
+            | Self::CreateIndexes
+            | Self::DeleteIndexes
+            | Self::Drop
+            | Self::DropDatabase
+            | Self::DropIndexes
+            | Self::MoveCollection
+            | Self::ReIndex
+            | Self::RenameCollection
+            | Self::ReshardCollection
+            | Self::ShardCollection
+            | Self::UnshardCollection => CommandCategory::Ddl,
+            _ => CommandCategory::Admin,
+        }
+    }
+
     /// Returns `true` if this command is unconditionally blocked inside
     /// a multi-document transaction.
     #[must_use]
@@ -677,6 +742,7 @@ impl FromStr for RequestType {
             "revokeRolesFromRole" => Ok(Self::RevokeRolesFromRole),
             "revokeRolesFromUser" => Ok(Self::RevokeRolesFromUser),
             "rolesInfo" => Ok(Self::RolesInfo),
+            "rotateTelemetry" => Ok(Self::RotateTelemetry),
             "saslContinue" => Ok(Self::SaslContinue),
             "saslStart" => Ok(Self::SaslStart),
             "serverStatus" => Ok(Self::ServerStatus),
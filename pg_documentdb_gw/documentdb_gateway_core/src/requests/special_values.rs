@@ -0,0 +1,175 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/requests/special_values.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::{Decimal128, RawArray, RawArrayBuf, RawBson, RawBsonRef, RawDocument, RawDocumentBuf};
+
+use crate::error::{DocumentDBError, Result};
+
+/// How inserts and updates treat NaN and infinite numbers, which some backends reject.
+/// Finite `Decimal128` values are always stored as sent.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialValuePolicy {
+    /// Values are passed to the backend as sent.
+    #[default]
+    Allow,
+    /// The write fails with `BadValue`, naming the first offending field.
+    Reject,
+    /// Offending values are stored as null.
+    Coerce,
+}
+
+impl SpecialValuePolicy {
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "allow" => Some(Self::Allow),
+            "reject" => Some(Self::Reject),
+            "coerce" => Some(Self::Coerce),
+            _ => None,
+        }
+    }
+}
+
+/// Names the special value `value` holds, if any.
+fn special_value(value: RawBsonRef<'_>) -> Option<&'static str> {
+    match value {
+        RawBsonRef::Double(d) if d.is_nan() => Some("NaN"),
+        RawBsonRef::Double(d) if d.is_infinite() => {
+            Some(if d > 0.0 { "Infinity" } else { "-Infinity" })
+        }
+        RawBsonRef::Decimal128(d) => special_decimal(d),
+        _ => None,
+    }
+}
+
+/// Reads the combination field of an IEEE 754 decimal128, whose bytes are little-endian.
+fn special_decimal(value: Decimal128) -> Option<&'static str> {
+    let high = value.bytes()[15];
+    match (high & 0x7C, high & 0x80 != 0) {
+        (0x7C, _) => Some("Decimal128 NaN"),
+        (0x78, false) => Some("Decimal128 Infinity"),
+        (0x78, true) => Some("Decimal128 -Infinity"),
+        _ => None,
+    }
+}
+
+/// Fails with `BadValue` on the first special value in `value`, naming its path under `path`.
+///
+/// # Errors
+///
+/// Returns `BadValue` if `value` holds NaN or an infinity, or an error if it is malformed.
+pub fn reject(value: RawBsonRef<'_>, path: &str) -> Result<()> {
+    if let Some(kind) = special_value(value) {
+        return Err(DocumentDBError::bad_value(format!(
+            "Field '{path}' holds {kind}, which this server does not accept"
+        )));
+    }
+    match value {
+        RawBsonRef::Document(document) => {
+            for element in document {
+                let (key, value) = element?;
+                reject(value, &format!("{path}.{key}"))?;
+            }
+        }
+        RawBsonRef::Array(array) => {
+            for (i, value) in array.into_iter().enumerate() {
+                reject(value?, &format!("{path}.{i}"))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Copies `value` with its special values replaced by null.
+///
+/// # Errors
+///
+/// Returns an error if `value` is malformed.
+pub fn coerce(value: RawBsonRef<'_>) -> Result<RawBson> {
+    if special_value(value).is_some() {
+        return Ok(RawBson::Null);
+    }
+    Ok(match value {
+        RawBsonRef::Document(document) => RawBson::Document(coerce_document(document)?),
+        RawBsonRef::Array(array) => RawBson::Array(coerce_array(array)?),
+        value => value.to_raw_bson(),
+    })
+}
+
+/// Copies `document` with its special values replaced by null.
+///
+/// # Errors
+///
+/// Returns an error if `document` is malformed.
+pub fn coerce_document(document: &RawDocument) -> Result<RawDocumentBuf> {
+    let mut coerced = RawDocumentBuf::new();
+    for element in document {
+        let (key, value) = element?;
+        coerced.append(key, coerce(value)?);
+    }
+    Ok(coerced)
+}
+
+fn coerce_array(array: &RawArray) -> Result<RawArrayBuf> {
+    let mut coerced = RawArrayBuf::new();
+    for value in array {
+        coerced.push(coerce(value?)?);
+    }
+    Ok(coerced)
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+
+    fn decimal(high: u8) -> Decimal128 {
+        let mut bytes = [0; 16];
+        bytes[15] = high;
+        Decimal128::from_bytes(bytes)
+    }
+
+    #[test]
+    fn recognizes_special_decimals() {
+        assert_eq!(special_decimal(decimal(0x7C)), Some("Decimal128 NaN"));
+        assert_eq!(special_decimal(decimal(0x78)), Some("Decimal128 Infinity"));
+        assert_eq!(special_decimal(decimal(0xF8)), Some("Decimal128 -Infinity"));
+        // Zeros with ordinary exponents.
+        assert_eq!(special_decimal(decimal(0x30)), None);
+        assert_eq!(special_decimal(decimal(0x00)), None);
+    }
+
+    #[test]
+    fn reject_names_the_nested_field() {
+        let document = rawdoc! { "a": { "b": [1.0, f64::NAN] } };
+
+        let err = reject(RawBsonRef::Document(&document), "documents.0").unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("Field 'documents.0.a.b.1' holds NaN"));
+    }
+
+    #[test]
+    fn coerce_keeps_finite_decimals() {
+        let finite = decimal(0x30);
+        let document = rawdoc! { "nan": f64::NAN, "inf": f64::NEG_INFINITY, "d": finite, "n": 2.5 };
+
+        let coerced = coerce_document(&document).unwrap();
+
+        assert_eq!(coerced.get("nan").unwrap(), Some(RawBsonRef::Null));
+        assert_eq!(coerced.get("inf").unwrap(), Some(RawBsonRef::Null));
+        let Some(RawBsonRef::Decimal128(d)) = coerced.get("d").unwrap() else {
+            panic!("decimal was not kept");
+        };
+        assert_eq!(d.bytes(), finite.bytes());
+        assert_eq!(coerced.get("n").unwrap(), Some(RawBsonRef::Double(2.5)));
+    }
+}
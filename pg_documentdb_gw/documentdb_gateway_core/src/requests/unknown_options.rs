@@ -0,0 +1,258 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/requests/unknown_options.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    collections::HashSet,
+    sync::{LazyLock, Mutex, PoisonError},
+};
+
+use crate::{
+    error::{DocumentDBError, Result},
+    requests::{Request, RequestType},
+};
+
+/// Options the gateway accepts on every command, whatever its type.
+const GENERIC_OPTIONS: &[&str] = &[
+    "$db",
+    "$clusterTime",
+    "$readPreference",
+    "$trace",
+    "apiDeprecationErrors",
+    "apiStrict",
+    "apiVersion",
+    "autocommit",
+    "comment",
+    "lsid",
+    "maxTimeMS",
+    "readConcern",
+    "startTransaction",
+    "txnNumber",
+    "writeConcern",
+];
+
+/// Option names already logged under [`UnknownOptionPolicy::Warn`].
+static WARNED: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Mutex::default);
+
+/// How many option names [`WARNED`] holds. Clients can send any number of distinct names,
+/// so once it is full no further unknown options are logged.
+const MAX_WARNED_OPTIONS: usize = 1024;
+
+/// How commands carrying options the gateway doesn't recognize are handled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownOptionPolicy {
+    /// Unknown options are passed to the backend as sent.
+    Ignore,
+    /// As `Ignore`, logging a warning the first time each option name is seen.
+    #[default]
+    Warn,
+    /// The command fails with `BadValue`, naming the first unknown option.
+    Reject,
+}
+
+impl UnknownOptionPolicy {
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ignore" => Some(Self::Ignore),
+            "warn" => Some(Self::Warn),
+            "reject" => Some(Self::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// The options a command accepts besides its name and [`GENERIC_OPTIONS`], or `None` for
+/// commands whose options aren't checked.
+const fn command_options(request_type: RequestType) -> Option<&'static [&'static str]> {
+    Some(match request_type {
+        RequestType::Aggregate => &[
+            "allowDiskUse",
+            "bypassDocumentValidation",
+            "collation",
+            "cursor",
+            "explain",
+            "hint",
+            "let",
+            "pipeline",
+        ],
+        RequestType::Count => &["collation", "hint", "limit", "query", "skip"],
+        RequestType::Delete => &["collation", "deletes", "let", "ordered"],
+        RequestType::Distinct => &["collation", "hint", "key", "query"],
+        RequestType::Find => &[
+            "allowDiskUse",
+            "allowPartialResults",
+            "awaitData",
+            "batchSize",
+            "collation",
+            "filter",
+            "hint",
+            "let",
+            "limit",
+            "max",
+            "min",
+            "noCursorTimeout",
+            "projection",
+            "returnKey",
+            "showRecordId",
+            "singleBatch",
+            "skip",
+            "sort",
+            "tailable",
+        ],
+        RequestType::FindAndModify => &[
+            "arrayFilters",
+            "bypassDocumentValidation",
+            "collation",
+            "fields",
+            "hint",
+            "let",
+            "new",
+            "query",
+            "remove",
+            "sort",
+            "update",
+            "upsert",
+        ],
+        RequestType::Insert => &["bypassDocumentValidation", "documents", "ordered"],
+        RequestType::Update => &[
+            "bypassDocumentValidation",
+            "collation",
+            "let",
+            "ordered",
+            "updates",
+        ],
+        _ => return None,
+    })
+}
+
+/// Returns true the first time `option` is seen, so each unknown option is logged once.
+fn first_sighting(option: &str) -> bool {
+    record_sighting(
+        &mut WARNED.lock().unwrap_or_else(PoisonError::into_inner),
+        option,
+    )
+}
+
+fn record_sighting(warned: &mut HashSet<String>, option: &str) -> bool {
+    if warned.contains(option) {
+        return false;
+    }
+    if warned.len() >= MAX_WARNED_OPTIONS {
+        return false;
+    }
+    warned.insert(option.to_owned());
+    if warned.len() == MAX_WARNED_OPTIONS {
+        tracing::warn!(
+            "Logged {MAX_WARNED_OPTIONS} distinct unknown options; further ones are not logged"
+        );
+    }
+    true
+}
+
+/// Applies `policy` to the options of `request` that its command doesn't recognize.
+///
+/// # Errors
+///
+/// Returns `BadValue` under [`UnknownOptionPolicy::Reject`] if the command carries an
+/// unknown option, or an error if the command is malformed.
+pub fn check_unknown_options(request: &Request<'_>, policy: UnknownOptionPolicy) -> Result<()> {
+    let Some(options) = command_options(request.request_type()) else {
+        return Ok(());
+    };
+    if policy == UnknownOptionPolicy::Ignore {
+        return Ok(());
+    }
+
+    // The first field is the command name.
+    let mut entries = request.document().into_iter();
+    let Some((command, _)) = entries.next().transpose()? else {
+        return Ok(());
+    };
+    for entry in entries {
+        let (key, _) = entry?;
+        if options.contains(&key) || GENERIC_OPTIONS.contains(&key) {
+            continue;
+        }
+        if policy == UnknownOptionPolicy::Reject {
+            return Err(DocumentDBError::bad_value(format!(
+                "BSON field '{command}.{key}' is an unknown field."
+            )));
+        }
+        if first_sighting(key) {
+            tracing::warn!("Passing unknown option '{key}' of {command} to the backend");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+    use crate::error::ErrorCode;
+
+    fn insert_with_bogus_option() -> bson::RawDocumentBuf {
+        rawdoc! {
+            "insert": "c",
+            "documents": [{ "a": 1 }],
+            "ordered": true,
+            "bogusInsertOption": 1,
+            "$db": "db",
+        }
+    }
+
+    #[test]
+    fn ignore_and_warn_accept_unknown_options() {
+        let command = insert_with_bogus_option();
+        let request = Request::Raw(RequestType::Insert, &command, None);
+
+        check_unknown_options(&request, UnknownOptionPolicy::Ignore).unwrap();
+        assert!(!WARNED.lock().unwrap().contains("bogusInsertOption"));
+
+        check_unknown_options(&request, UnknownOptionPolicy::Warn).unwrap();
+        assert!(!first_sighting("bogusInsertOption"));
+    }
+
+    #[test]
+    fn sightings_stop_once_the_set_is_full() {
+        let mut warned = HashSet::new();
+        for i in 0..MAX_WARNED_OPTIONS {
+            assert!(record_sighting(&mut warned, &format!("option{i}")));
+        }
+
+        assert!(!record_sighting(&mut warned, "option0"));
+        assert!(!record_sighting(&mut warned, "oneTooMany"));
+        assert_eq!(warned.len(), MAX_WARNED_OPTIONS);
+    }
+
+    #[test]
+    fn reject_names_the_unknown_option() {
+        let command = insert_with_bogus_option();
+        let request = Request::Raw(RequestType::Insert, &command, None);
+
+        let err = check_unknown_options(&request, UnknownOptionPolicy::Reject).unwrap_err();
+
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BadValue));
+        assert!(err.to_string().contains("insert.bogusInsertOption"));
+    }
+
+    #[test]
+    fn known_and_generic_options_are_accepted() {
+        let command = rawdoc! {
+            "insert": "c",
+            "documents": [],
+            "writeConcern": { "w": 1 },
+            "lsid": { "id": 1 },
+            "$db": "db",
+        };
+        let request = Request::Raw(RequestType::Insert, &command, None);
+
+        check_unknown_options(&request, UnknownOptionPolicy::Reject).unwrap();
+    }
+}
@@ -6,22 +6,39 @@
  *-------------------------------------------------------------------------
  */
 
+use bson::RawBsonRef;
+
 use crate::{
     context::ConnectionContext,
     error::{DocumentDBError, ErrorCode, Result},
+    processor::sequence_documents,
     requests::{read_concern::ReadConcern, Request, RequestInfo, RequestType},
 };
 
+/// Room a command has beyond the object size limit for its own fields, so that
+/// a command carrying a document of the maximum size still fits.
+const COMMAND_OVERHEAD_BYTES: usize = 16 * 1024;
+
 /// Validates that the given request is consistent with the current connection and
-/// transaction state.
+/// transaction state, and that its documents fit the object size limit.
 ///
 /// # Errors
-/// Returns an error if the request violates transaction or session constraints.
+/// Returns an error if the request violates transaction or session constraints, or
+/// `BSONObjectTooLarge` if a document is over the limit.
 pub fn validate_request(
     connection_context: &ConnectionContext,
     request_info: &RequestInfo,
     request: &Request<'_>,
 ) -> Result<()> {
+    validate_object_sizes(
+        request,
+        connection_context
+            .service_context
+            .setup_configuration()
+            .max_bson_object_size(),
+    )?;
+    validate_snapshot_outside_transaction(request_info)?;
+
     let Some(request_transaction_info) = request_info.transaction_info.as_ref() else {
         return Ok(());
     };
@@ -106,6 +123,71 @@ pub fn validate_request(
         ));
     }
 
+    validate_transaction_read_concern(connection_context, request_info)
+}
+
+/// Rejects a command over `max_bson_object_size`, allowing for its own fields, and any
+/// document it carries in a document sequence or inserts that is over the limit itself.
+fn validate_object_sizes(request: &Request<'_>, max_bson_object_size: i32) -> Result<()> {
+    let max_size = usize::try_from(max_bson_object_size).unwrap_or_default();
+    let command = request.document();
+    let command_size = command.as_bytes().len();
+    if command_size > max_size + COMMAND_OVERHEAD_BYTES {
+        return Err(object_too_large(format!(
+            "BSONObj size: {command_size} is invalid. Size must be between 0 and {}",
+            max_size + COMMAND_OVERHEAD_BYTES
+        )));
+    }
+
+    let mut sizes = Vec::new();
+    if let Some(sequence) = request.extra() {
+        sizes.extend(
+            sequence_documents(sequence)?
+                .iter()
+                .map(|document| document.len()),
+        );
+    }
+    if request.request_type() == RequestType::Insert {
+        if let Some(documents) = command.get("documents")?.and_then(RawBsonRef::as_array) {
+            for document in documents {
+                if let RawBsonRef::Document(document) = document? {
+                    sizes.push(document.as_bytes().len());
+                }
+            }
+        }
+    }
+    match sizes.into_iter().find(|size| *size > max_size) {
+        Some(size) => Err(object_too_large(format!(
+            "object to insert too large. size in bytes: {size}, max size: {max_size}"
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn object_too_large(message: String) -> DocumentDBError {
+    DocumentDBError::documentdb_error(ErrorCode::BsonObjectTooLarge, message)
+}
+
+/// Snapshot reads need the transaction's REPEATABLE READ snapshot on the backend, which a
+/// standalone command doesn't have.
+fn validate_snapshot_outside_transaction(request_info: &RequestInfo) -> Result<()> {
+    let in_transaction = request_info
+        .transaction_info
+        .as_ref()
+        .is_some_and(|info| !info.auto_commit);
+    if !in_transaction && request_info.read_concern() == &ReadConcern::Snapshot {
+        return Err(DocumentDBError::documentdb_error(
+            ErrorCode::InvalidOptions,
+            "readConcern level snapshot is only valid in multi-statement transactions".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_transaction_read_concern(
+    connection_context: &ConnectionContext,
+    request_info: &RequestInfo,
+) -> Result<()> {
     // you need a dynamic_configuration, so would split the parsing logic in 2 stages,
     // one is parsing the request and extract the transaction information,
     // the other is validating the transaction information and create the transaction if necessary. This is because some of the validation requires dynamic configuration, which is only accessible in the processing stage.
@@ -138,3 +220,52 @@ pub fn validate_request(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use bson::{rawdoc, RawDocumentBuf};
+
+    use super::*;
+
+    const MAX_SIZE: i32 = 1024;
+
+    fn document_of(size: usize) -> RawDocumentBuf {
+        // An empty document is 5 bytes, and a string field adds 7 bytes and its name.
+        rawdoc! { "s": "x".repeat(size - 5 - 7 - 1) }
+    }
+
+    #[test]
+    fn inserted_document_over_the_limit_is_rejected() {
+        let fits = rawdoc! { "insert": "c", "documents": [document_of(1024)], "$db": "db" };
+        let request = Request::Raw(RequestType::Insert, &fits, None);
+        validate_object_sizes(&request, MAX_SIZE).unwrap();
+
+        let too_large = rawdoc! { "insert": "c", "documents": [document_of(1025)], "$db": "db" };
+        let request = Request::Raw(RequestType::Insert, &too_large, None);
+        let err = validate_object_sizes(&request, MAX_SIZE).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BsonObjectTooLarge));
+    }
+
+    #[test]
+    fn sequence_document_over_the_limit_is_rejected() {
+        let command = rawdoc! { "insert": "c", "$db": "db" };
+        let mut sequence = document_of(20).into_bytes();
+        sequence.extend_from_slice(document_of(1025).as_bytes());
+        let request = Request::Raw(RequestType::Insert, &command, Some(&sequence));
+
+        let err = validate_object_sizes(&request, MAX_SIZE).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BsonObjectTooLarge));
+    }
+
+    #[test]
+    fn command_may_exceed_the_limit_by_its_overhead_only() {
+        let within =
+            rawdoc! { "find": "c", "filter": document_of(1024 + COMMAND_OVERHEAD_BYTES - 100) };
+        validate_object_sizes(&Request::Raw(RequestType::Find, &within, None), MAX_SIZE).unwrap();
+
+        let over = rawdoc! { "find": "c", "filter": document_of(1024 + COMMAND_OVERHEAD_BYTES) };
+        let err = validate_object_sizes(&Request::Raw(RequestType::Find, &over, None), MAX_SIZE)
+            .unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::BsonObjectTooLarge));
+    }
+}
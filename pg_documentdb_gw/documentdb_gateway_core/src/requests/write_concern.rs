@@ -0,0 +1,225 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/requests/write_concern.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::time::Duration;
+
+use bson::{RawBsonRef, RawDocument};
+
+use crate::{
+    bson::{convert_to_bool, convert_to_f64},
+    error::{DocumentDBError, ErrorCode, Result},
+};
+
+/// How many nodes must have a write before it is acknowledged (`w`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteAcknowledgment {
+    /// `w: 0`, the client does not wait for the write.
+    Unacknowledged,
+
+    /// `w: <n>`, the primary and `n - 1` replicas.
+    Nodes(u32),
+
+    /// `w: "majority"`, a majority of the primary and its replicas.
+    Majority,
+
+    /// A custom write concern mode, which the gateway has no configuration for.
+    Tag(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteConcern {
+    pub w: WriteAcknowledgment,
+
+    /// `j: true`, the write must be flushed to the WAL before it is acknowledged.
+    pub journal: bool,
+
+    /// How long to wait for acknowledgment, unbounded when not set or 0.
+    pub wtimeout: Option<Duration>,
+}
+
+impl Default for WriteConcern {
+    fn default() -> Self {
+        Self {
+            w: WriteAcknowledgment::Nodes(1),
+            journal: false,
+            wtimeout: None,
+        }
+    }
+}
+
+impl WriteConcern {
+    /// # Errors
+    /// Returns error if a field of the write concern has the wrong type or value.
+    pub fn parse(document: &RawDocument) -> Result<Self> {
+        let mut write_concern = Self::default();
+        for entry in document {
+            let (k, v) = entry?;
+            match k {
+                "w" => write_concern.w = parse_w(v)?,
+                "j" => {
+                    write_concern.journal = convert_to_bool(v).ok_or_else(|| {
+                        DocumentDBError::type_mismatch(format!(
+                            "Expected writeConcern.j to be a boolean but got {:?}",
+                            v.element_type()
+                        ))
+                    })?;
+                }
+                "wtimeout" => {
+                    let wtimeout = parse_non_negative(k, v)?;
+                    write_concern.wtimeout =
+                        (wtimeout > 0).then(|| Duration::from_millis(wtimeout));
+                }
+                _ => {}
+            }
+        }
+
+        if write_concern.w == WriteAcknowledgment::Unacknowledged && write_concern.journal {
+            return Err(DocumentDBError::documentdb_error(
+                ErrorCode::FailedToParse,
+                "Cannot set j: true with w: 0".to_owned(),
+            ));
+        }
+        Ok(write_concern)
+    }
+
+    /// Whether acknowledging a write with this concern needs anything beyond the local commit,
+    /// which is already flushed when the backend runs with its default `synchronous_commit`.
+    #[must_use]
+    pub const fn requires_wait(&self) -> bool {
+        match self.w {
+            WriteAcknowledgment::Unacknowledged => false,
+            WriteAcknowledgment::Nodes(nodes) => self.journal || nodes > 1,
+            WriteAcknowledgment::Majority | WriteAcknowledgment::Tag(_) => true,
+        }
+    }
+
+    /// The number of replicas that must have flushed a write, out of `replicas` connected ones.
+    #[must_use]
+    pub const fn required_replicas(&self, replicas: u32) -> u32 {
+        match self.w {
+            WriteAcknowledgment::Unacknowledged | WriteAcknowledgment::Tag(_) => 0,
+            WriteAcknowledgment::Nodes(nodes) => nodes.saturating_sub(1),
+            // A majority of the replicas plus the primary, less the primary.
+            WriteAcknowledgment::Majority => replicas.div_ceil(2),
+        }
+    }
+}
+
+fn parse_w(value: RawBsonRef) -> Result<WriteAcknowledgment> {
+    if let Some(mode) = value.as_str() {
+        return Ok(if mode == "majority" {
+            WriteAcknowledgment::Majority
+        } else {
+            WriteAcknowledgment::Tag(mode.to_owned())
+        });
+    }
+
+    let nodes = parse_non_negative("w", value)?;
+    Ok(match u32::try_from(nodes) {
+        Ok(0) => WriteAcknowledgment::Unacknowledged,
+        Ok(nodes) => WriteAcknowledgment::Nodes(nodes),
+        Err(_) => {
+            return Err(DocumentDBError::documentdb_error(
+                ErrorCode::FailedToParse,
+                format!("w has to be a reasonable number of nodes, but received: {nodes}"),
+            ))
+        }
+    })
+}
+
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "the value is checked to be a non-negative whole number"
+)]
+fn parse_non_negative(key: &str, value: RawBsonRef) -> Result<u64> {
+    let number = convert_to_f64(value).ok_or_else(|| {
+        DocumentDBError::type_mismatch(format!(
+            "Expected writeConcern.{key} to be a number but got {:?}",
+            value.element_type()
+        ))
+    })?;
+    if number < 0.0 || number.fract() != 0.0 {
+        return Err(DocumentDBError::documentdb_error(
+            ErrorCode::FailedToParse,
+            format!("writeConcern.{key} must be a non-negative integer, but received: {number}"),
+        ));
+    }
+    Ok(number as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::{rawdoc, RawDocumentBuf};
+
+    use super::*;
+
+    #[test]
+    fn parses_write_concern_fields() {
+        let write_concern =
+            WriteConcern::parse(&rawdoc! { "w": "majority", "j": true, "wtimeout": 250 }).unwrap();
+        assert_eq!(
+            write_concern,
+            WriteConcern {
+                w: WriteAcknowledgment::Majority,
+                journal: true,
+                wtimeout: Some(Duration::from_millis(250)),
+            }
+        );
+
+        let write_concern = WriteConcern::parse(&rawdoc! { "w": 0, "wtimeout": 0 }).unwrap();
+        assert_eq!(write_concern.w, WriteAcknowledgment::Unacknowledged);
+        assert_eq!(write_concern.wtimeout, None);
+    }
+
+    #[test]
+    fn rejects_invalid_write_concern() {
+        for document in [
+            rawdoc! { "w": -1 },
+            rawdoc! { "w": 1.5 },
+            rawdoc! { "wtimeout": -5 },
+            rawdoc! { "w": 0, "j": true },
+        ] {
+            let err = WriteConcern::parse(&document).unwrap_err();
+            assert_eq!(err.error_code_enum(), Some(ErrorCode::FailedToParse));
+        }
+
+        let err = WriteConcern::parse(&rawdoc! { "j": "yes" }).unwrap_err();
+        assert_eq!(err.error_code_enum(), Some(ErrorCode::TypeMismatch));
+    }
+
+    #[test]
+    fn only_replicated_or_journaled_writes_wait() {
+        let wait =
+            |document: RawDocumentBuf| WriteConcern::parse(&document).unwrap().requires_wait();
+        assert!(!wait(rawdoc! {}));
+        assert!(!wait(rawdoc! { "w": 0 }));
+        assert!(!wait(rawdoc! { "w": 1 }));
+        assert!(wait(rawdoc! { "w": 1, "j": true }));
+        assert!(wait(rawdoc! { "w": 2 }));
+        assert!(wait(rawdoc! { "w": "majority" }));
+    }
+
+    #[test]
+    fn majority_counts_the_primary() {
+        let majority = WriteConcern {
+            w: WriteAcknowledgment::Majority,
+            ..WriteConcern::default()
+        };
+        assert_eq!(majority.required_replicas(0), 0);
+        assert_eq!(majority.required_replicas(1), 1);
+        assert_eq!(majority.required_replicas(2), 1);
+        assert_eq!(majority.required_replicas(4), 2);
+
+        let nodes = WriteConcern {
+            w: WriteAcknowledgment::Nodes(3),
+            ..WriteConcern::default()
+        };
+        assert_eq!(nodes.required_replicas(0), 2);
+    }
+}
@@ -6,7 +6,7 @@
  *-------------------------------------------------------------------------
  */
 
-use bson::{raw::ValueAccessErrorKind, RawDocumentBuf};
+use bson::{raw::ValueAccessErrorKind, RawArrayBuf, RawDocumentBuf};
 use deadpool_postgres::PoolError;
 
 use crate::{
@@ -26,6 +26,9 @@ pub struct CommandError {
 
     /// A human-readable description of the error, sent to the client.
     message: String,
+
+    /// Labels sent as `errorLabels`, such as `RetryableWriteError`.
+    error_labels: Vec<&'static str>,
 }
 
 impl CommandError {
@@ -35,6 +38,7 @@ impl CommandError {
             ok: OK_FAILED,
             code,
             message: msg,
+            error_labels: Vec::new(),
         }
     }
 
@@ -53,6 +57,11 @@ impl CommandError {
         &self.message
     }
 
+    #[must_use]
+    pub fn error_labels(&self) -> &[&'static str] {
+        &self.error_labels
+    }
+
     /// Converts the `CommandError` into a `RawDocumentBuf` that can be sent to the client.
     #[must_use = "This constructs the actual error response to be sent to the client."]
     pub fn to_raw_document_buf(&self) -> RawDocumentBuf {
@@ -62,6 +71,13 @@ impl CommandError {
         doc.append("code", self.code as i32);
         doc.append("codeName", self.code.as_ref().to_owned());
         doc.append("errmsg", self.message.clone());
+        if !self.error_labels.is_empty() {
+            let mut labels = RawArrayBuf::new();
+            for label in &self.error_labels {
+                labels.push(*label);
+            }
+            doc.append("errorLabels", labels);
+        }
         doc
     }
 
@@ -76,6 +92,16 @@ impl CommandError {
         connection_context: &ConnectionContext,
         err: &DocumentDBError,
         activity_id: &str,
+    ) -> Self {
+        let mut command_error = Self::from_error_kind(connection_context, err, activity_id);
+        command_error.error_labels = err.error_labels().to_vec();
+        command_error
+    }
+
+    fn from_error_kind(
+        connection_context: &ConnectionContext,
+        err: &DocumentDBError,
+        activity_id: &str,
     ) -> Self {
         match err.kind() {
             ErrorKind::PostgresError(e, _) | ErrorKind::PoolError(PoolError::Backend(e), _) => {
@@ -170,3 +196,31 @@ impl CommandError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RETRYABLE_WRITE_ERROR_LABEL;
+
+    #[test]
+    fn error_labels_are_sent_only_when_present() {
+        let error = CommandError::new(ErrorCode::BadValue, "bad".to_owned());
+        assert!(error
+            .to_raw_document_buf()
+            .get("errorLabels")
+            .unwrap()
+            .is_none());
+
+        let mut error = CommandError::new(ErrorCode::HostUnreachable, "reset".to_owned());
+        error.error_labels = vec![RETRYABLE_WRITE_ERROR_LABEL];
+        let doc = error.to_raw_document_buf();
+        let labels: Vec<&str> = doc
+            .get_array("errorLabels")
+            .unwrap()
+            .into_iter()
+            .map(|label| label.unwrap().as_str().unwrap())
+            .collect();
+        assert_eq!(labels, ["RetryableWriteError"]);
+        assert_eq!(doc.get_i32("code").unwrap(), 6);
+    }
+}
@@ -6,7 +6,7 @@
  *-------------------------------------------------------------------------
  */
 
-use bson::{rawdoc, Document, RawDocument};
+use bson::{rawdoc, Document, RawBson, RawDocument};
 
 use crate::{error::Result, protocol::OK_SUCCEEDED};
 
@@ -57,6 +57,16 @@ impl Response {
         Ok(Document::try_from(self.as_raw_document()?)?)
     }
 
+    /// Returns the response with `key` appended to its document.
+    ///
+    /// # Errors
+    /// Returns an error if the response document can't be read.
+    pub fn with_field(self, key: &str, value: impl Into<RawBson>) -> Result<Self> {
+        let mut document = self.as_raw_document()?.to_raw_document_buf();
+        document.append(key, value);
+        Ok(Self::Raw(RawResponse(document)))
+    }
+
     #[must_use]
     pub fn ok() -> Self {
         Self::Raw(RawResponse(rawdoc! {
@@ -71,6 +81,18 @@ mod tests {
 
     use crate::responses::{raw::RawResponse, Response};
 
+    #[test]
+    fn with_field_appends_to_the_response() {
+        let response = Response::ok()
+            .with_field("$gatewayTiming", rawdoc! { "executionMicros": 5_i64 })
+            .unwrap();
+
+        assert_eq!(
+            response.as_raw_document().unwrap(),
+            rawdoc! { "ok": 1.0, "$gatewayTiming": { "executionMicros": 5_i64 } }.as_ref()
+        );
+    }
+
     #[test]
     fn raw_response_byte_len_matches_bson_bytes() {
         // An empty BSON document is exactly 5 bytes: 4-byte i32 size + 1 null terminator.
@@ -605,6 +605,19 @@ impl PgResponse {
             .sum()
     }
 
+    /// Returns the average size of the documents in the cursor batch of the response,
+    /// or `None` if it has no cursor or the batch is empty.
+    #[must_use]
+    pub fn bytes_per_batch_document(&self) -> Option<usize> {
+        let cursor = self.as_raw_document().ok()?.get_document("cursor").ok()?;
+        let batch = cursor
+            .get_array("firstBatch")
+            .or_else(|_| cursor.get_array("nextBatch"))
+            .ok()?;
+        let documents = batch.into_iter().count();
+        (documents > 0).then(|| batch.as_bytes().len().div_ceil(documents))
+    }
+
     /// # Errors
     /// Returns an error if the result columns cannot be read or deserialized.
     pub fn get_cursor(&self) -> Result<Option<(bool, Cursor)>> {
@@ -622,6 +635,8 @@ impl PgResponse {
                                 Cursor {
                                     continuation: continuation.0.to_raw_document_buf(),
                                     cursor_id: CursorId::from(cursor_id),
+                                    tailable: None,
+                                    bytes_per_document: self.bytes_per_batch_document(),
                                 },
                             )))
                         }
@@ -9,23 +9,47 @@
 use crate::{
     context::ConnectionContext,
     error::{DocumentDBError, Result},
-    protocol::{header::Header, opcode::OpCode},
+    protocol::{header::Header, message::MessageFlags, opcode::OpCode},
     CommandError, Response,
 };
 use bson::RawDocument;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
-/// Write a server response to the client stream
+/// Write a server response to the client stream, returning the number of bytes written.
 /// # Errors
 /// Returns error if the operation fails.
-pub async fn write<S>(header: &Header, response: &Response, stream: &mut S) -> Result<()>
+pub async fn write<S>(header: &Header, response: &Response, stream: &mut S) -> Result<usize>
 where
     S: AsyncWrite + Unpin,
 {
     write_and_flush(header, response.as_raw_document()?, stream).await
 }
 
-/// Write a raw BSON object to the client stream
+/// Write a server response with `moreToCome` set, telling the client that further
+/// responses follow without another request. Only `OP_MSG` carries the flag. Returns the
+/// number of bytes written.
+/// # Errors
+/// Returns error if the operation fails.
+pub async fn write_more_to_come<S>(
+    header: &Header,
+    response: &Response,
+    stream: &mut S,
+) -> Result<usize>
+where
+    S: AsyncWrite + Unpin,
+{
+    let length = write_message_with_flags(
+        header,
+        response.as_raw_document()?,
+        MessageFlags::MORE_TO_COME,
+        stream,
+    )
+    .await?;
+    stream.flush().await?;
+    Ok(length)
+}
+
+/// Write a raw BSON object to the client stream, returning the number of bytes written.
 /// # Errors
 /// Returns error if the operation fails.
 #[expect(clippy::cast_possible_truncation, reason = "message size fits in i32")]
@@ -34,12 +58,12 @@ pub async fn write_and_flush<S>(
     header: &Header,
     response: &RawDocument,
     stream: &mut S,
-) -> Result<()>
+) -> Result<usize>
 where
     S: AsyncWrite + Unpin,
 {
     // The format of the response will depend on the OP which the client sent
-    match header.op_code {
+    let length = match header.op_code {
         OpCode::Command => unimplemented!(),
 
         // Messages are always responded to with messages
@@ -51,10 +75,11 @@ where
             reason = "OP_QUERY is still supported for legacy clients and testing"
         )]
         OpCode::Query => {
+            // Total size of the response is the bytes + standard header + reply header
+            let length = response.as_bytes().len() + Header::LENGTH + 20;
             // Write the header
             let header = Header {
-                // Total size of the response is the bytes + standard header + reply header
-                length: (response.as_bytes().len() + Header::LENGTH + 20) as i32,
+                length: length as i32,
                 request_id: header.request_id,
                 response_to: header.request_id,
                 op_code: OpCode::Reply,
@@ -67,7 +92,7 @@ where
             stream.write_i32_le(1).await?; // numberReturned
 
             stream.write_all(response.as_bytes()).await?;
-            Ok(())
+            Ok(length)
         }
 
         // Insert has no response
@@ -75,7 +100,7 @@ where
             deprecated,
             reason = "OP_INSERT is still supported for legacy clients and testing"
         )]
-        OpCode::Insert => Ok(()),
+        OpCode::Insert => Ok(0),
         _ => Err(DocumentDBError::internal_error(format!(
             "Unexpected response opcode: {:?}",
             header.op_code
@@ -84,15 +109,31 @@ where
 
     stream.flush().await?;
 
-    Ok(())
+    Ok(length)
 }
 
-/// Serializes the Message to bytes and writes them to `writer`.
+/// Serializes the Message to bytes and writes them to `writer`, returning their number.
 /// # Errors
 /// Returns error if the operation fails.
+pub async fn write_message<S>(
+    header: &Header,
+    response: &RawDocument,
+    writer: &mut S,
+) -> Result<usize>
+where
+    S: AsyncWrite + Unpin,
+{
+    write_message_with_flags(header, response, MessageFlags::NONE, writer).await
+}
+
 #[expect(clippy::cast_possible_truncation, reason = "message size fits in i32")]
 #[expect(clippy::cast_possible_wrap, reason = "message size is always positive")]
-pub async fn write_message<S>(header: &Header, response: &RawDocument, writer: &mut S) -> Result<()>
+async fn write_message_with_flags<S>(
+    header: &Header,
+    response: &RawDocument,
+    flags: MessageFlags,
+    writer: &mut S,
+) -> Result<usize>
 where
     S: AsyncWrite + Unpin,
 {
@@ -110,16 +151,18 @@ where
     header.write_to(writer).await?;
 
     // Write Flags
-    writer.write_u32_le(0).await?;
+    writer.write_u32_le(flags.bits()).await?;
 
     // Write payload type + section
     writer.write_u8(0).await?;
 
     writer.write_all(response.as_bytes()).await?;
 
-    Ok(())
+    Ok(total_length)
 }
 
+/// Writes `err` in reply to a message whose header couldn't be read, returning the number of
+/// bytes written.
 /// # Errors
 /// Returns error if the operation fails.
 #[expect(clippy::cast_possible_truncation, reason = "message size fits in i32")]
@@ -129,7 +172,7 @@ pub async fn write_error_without_header<S>(
     err: DocumentDBError,
     stream: &mut S,
     activity_id: &str,
-) -> Result<()>
+) -> Result<usize>
 where
     S: AsyncWrite + Unpin,
 {
@@ -143,7 +186,178 @@ where
         op_code: OpCode::Msg,
     };
 
-    write_and_flush(&header, &response, stream).await?;
+    write_and_flush(&header, &response, stream).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bson::{rawdoc, spec::ElementType, RawDocumentBuf};
+
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+    use crate::{
+        protocol::{
+            message::{Message, MessageSection},
+            reader,
+        },
+        responses::RawResponse,
+    };
+
+    fn msg_header() -> Header {
+        Header {
+            length: 0,
+            request_id: 7,
+            response_to: 0,
+            op_code: OpCode::Msg,
+        }
+    }
+
+    #[tokio::test]
+    async fn distinct_values_keep_bson_types_on_the_wire() {
+        let response = Response::Raw(RawResponse(rawdoc! {
+            "values": [1_i32, 2_i64, 2.5_f64, "2", true, bson::Decimal128::from_bytes([0; 16])],
+            "ok": 1.0,
+        }));
+
+        let mut wire = Vec::new();
+        let written = write(&msg_header(), &response, &mut wire).await.unwrap();
+        assert_eq!(written, wire.len());
+
+        // Skip the header, the OP_MSG flags and the section kind byte.
+        let body = &wire[Header::LENGTH + std::mem::size_of::<u32>() + 1..];
+        let document = RawDocumentBuf::from_bytes(body.to_vec()).unwrap();
+        let types: Vec<ElementType> = document
+            .get_array("values")
+            .unwrap()
+            .into_iter()
+            .map(|value| value.unwrap().element_type())
+            .collect();
+
+        assert_eq!(
+            types,
+            [
+                ElementType::Int32,
+                ElementType::Int64,
+                ElementType::Double,
+                ElementType::String,
+                ElementType::Boolean,
+                ElementType::Decimal128,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn written_message_reads_back_off_the_stream() {
+        let response = Response::Raw(RawResponse(rawdoc! {
+            "values": [1_i32, 2_i64, 2.5_f64, "2"],
+            "ok": 1.0,
+        }));
+        let (mut gateway, mut client) = tokio::io::duplex(1024);
+
+        write(&msg_header(), &response, &mut gateway).await.unwrap();
+
+        let header = reader::read_header(&mut client).await.unwrap().unwrap();
+        assert_eq!(header.op_code, OpCode::Msg);
+        assert_eq!(header.response_to, 7);
+        let message = reader::read_request(&header, &mut client, i32::MAX)
+            .await
+            .unwrap();
+        let message =
+            Message::read_from_op_msg(Cursor::new(&message.request), header.response_to).unwrap();
+        assert_eq!(message.flags, MessageFlags::NONE);
+        let [MessageSection::Document(document)] = message.sections.as_slice() else {
+            panic!("expected a single document section");
+        };
+        assert_eq!(*document, response.as_raw_document().unwrap());
+        let types: Vec<ElementType> = document
+            .get_array("values")
+            .unwrap()
+            .into_iter()
+            .map(|value| value.unwrap().element_type())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                ElementType::Int32,
+                ElementType::Int64,
+                ElementType::Double,
+                ElementType::String,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn distinct_values_keep_bson_types_in_op_reply() {
+        #[expect(deprecated, reason = "OP_QUERY is still supported for legacy clients")]
+        let header = Header {
+            op_code: OpCode::Query,
+            ..msg_header()
+        };
+        let response = rawdoc! { "values": [3_i32, 3.0_f64, "3"], "ok": 1.0 };
+
+        let mut wire = Vec::new();
+        let written = write_and_flush(&header, &response, &mut wire)
+            .await
+            .unwrap();
+        assert_eq!(written, wire.len());
+
+        // Skip the header and the 20-byte OP_REPLY prefix.
+        let body = &wire[Header::LENGTH + 20..];
+        let document = RawDocumentBuf::from_bytes(body.to_vec()).unwrap();
+        let values: Vec<_> = document
+            .get_array("values")
+            .unwrap()
+            .into_iter()
+            .map(|value| value.unwrap().element_type())
+            .collect();
+
+        assert_eq!(
+            values,
+            [ElementType::Int32, ElementType::Double, ElementType::String]
+        );
+    }
+
+    #[tokio::test]
+    async fn more_to_come_sets_the_op_msg_flag() {
+        let response = Response::Raw(RawResponse(rawdoc! { "ok": 1.0 }));
+
+        let mut wire = Vec::new();
+        write(&msg_header(), &response, &mut wire).await.unwrap();
+        assert_eq!(wire[Header::LENGTH..Header::LENGTH + 4], [0, 0, 0, 0]);
+
+        wire.clear();
+        write_more_to_come(&msg_header(), &response, &mut wire)
+            .await
+            .unwrap();
+        assert_eq!(wire[Header::LENGTH..Header::LENGTH + 4], [2, 0, 0, 0]);
+    }
+
+    #[tokio::test]
+    async fn slow_consumer_applies_backpressure() {
+        const PIPE_CAPACITY: usize = 4 * 1024;
+        let response = Response::Raw(RawResponse(rawdoc! {
+            "cursor": { "firstBatch": ["x".repeat(256 * 1024)], "id": 0_i64, "ns": "db.coll" },
+            "ok": 1.0,
+        }));
+        let (mut gateway, mut client) = tokio::io::duplex(PIPE_CAPACITY);
+
+        // The client is not reading, so the write stalls once the pipe is full
+        // instead of the gateway buffering the rest of the response.
+        let header = msg_header();
+        let write = write(&header, &response, &mut gateway);
+        tokio::pin!(write);
+        tokio::time::timeout(std::time::Duration::from_millis(50), &mut write)
+            .await
+            .expect_err("the write should wait for the client to read");
 
-    Ok(())
+        let expected =
+            Header::LENGTH + std::mem::size_of::<u32>() + 1 + response.response_byte_len();
+        let mut wire = vec![0; expected];
+        let (written, read) = tokio::join!(write, client.read_exact(&mut wire));
+        written.unwrap();
+        assert_eq!(read.unwrap(), expected);
+    }
 }
@@ -0,0 +1,195 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/service/connection_gate.rs
+ *
+ * Bounds the client connections the gateway serves at once, so that a
+ * connection storm is turned away at accept time instead of exhausting
+ * memory and backend connections.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::sync::{
+    atomic::{AtomicI64, Ordering},
+    Arc,
+};
+
+use opentelemetry::metrics::ObservableUpDownCounter;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::telemetry::metrics::{record_connection_rejected, register_active_connections_gauge};
+
+/// Admits accepted connections up to `MaxConcurrentConnections`.
+///
+/// Past the limit a connection waits in a backlog of `ConnectionBacklog` connections for
+/// one to close, and is rejected once the backlog is full too.
+#[derive(Debug)]
+pub struct ConnectionGate {
+    active: Arc<AtomicI64>,
+    /// Free connection slots, or `None` when connections are not limited.
+    slots: Option<Arc<Semaphore>>,
+    backlog: Arc<Semaphore>,
+    _active_gauge: ObservableUpDownCounter<i64>,
+}
+
+impl ConnectionGate {
+    #[must_use]
+    pub fn new(max_concurrent_connections: Option<usize>, backlog: usize) -> Self {
+        let active = Arc::new(AtomicI64::new(0));
+        let gauge_active = Arc::downgrade(&active);
+        Self {
+            _active_gauge: register_active_connections_gauge(move || {
+                gauge_active
+                    .upgrade()
+                    .map_or(0, |active| active.load(Ordering::Relaxed))
+            }),
+            active,
+            slots: max_concurrent_connections.map(|max| Arc::new(Semaphore::new(max))),
+            backlog: Arc::new(Semaphore::new(backlog)),
+        }
+    }
+
+    /// Admits a newly accepted connection, or returns `None` when it must be rejected.
+    #[must_use]
+    pub fn admit(&self) -> Option<Admission> {
+        let state = match &self.slots {
+            None => AdmissionState::Admitted(None),
+            Some(slots) => {
+                if let Ok(slot) = Arc::clone(slots).try_acquire_owned() {
+                    AdmissionState::Admitted(Some(slot))
+                } else if let Ok(queue_slot) = Arc::clone(&self.backlog).try_acquire_owned() {
+                    AdmissionState::Queued {
+                        slots: Arc::clone(slots),
+                        _queue_slot: queue_slot,
+                    }
+                } else {
+                    record_connection_rejected();
+                    return None;
+                }
+            }
+        };
+
+        Some(Admission {
+            active: Arc::clone(&self.active),
+            state,
+        })
+    }
+
+    /// Returns the number of connections currently being served.
+    #[must_use]
+    pub fn active_connections(&self) -> i64 {
+        self.active.load(Ordering::Relaxed)
+    }
+}
+
+/// A connection accepted by the gate, possibly still waiting in the backlog.
+#[derive(Debug)]
+pub struct Admission {
+    active: Arc<AtomicI64>,
+    state: AdmissionState,
+}
+
+#[derive(Debug)]
+enum AdmissionState {
+    Admitted(Option<OwnedSemaphorePermit>),
+    Queued {
+        slots: Arc<Semaphore>,
+        _queue_slot: OwnedSemaphorePermit,
+    },
+}
+
+impl Admission {
+    #[must_use]
+    pub const fn is_queued(&self) -> bool {
+        matches!(self.state, AdmissionState::Queued { .. })
+    }
+
+    /// Waits for a connection slot, which is held until the returned permit is dropped.
+    /// A queued connection leaves the backlog once it has a slot.
+    pub async fn ready(self) -> ConnectionPermit {
+        let slot = match self.state {
+            AdmissionState::Admitted(slot) => slot,
+            // The semaphore is never closed, so acquiring only fails if it were.
+            AdmissionState::Queued { slots, _queue_slot } => slots.acquire_owned().await.ok(),
+        };
+        self.active.fetch_add(1, Ordering::Relaxed);
+        ConnectionPermit {
+            active: self.active,
+            _slot: slot,
+        }
+    }
+}
+
+/// Counts a connection as active for as long as it is held.
+#[derive(Debug)]
+pub struct ConnectionPermit {
+    active: Arc<AtomicI64>,
+    _slot: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn rejects_past_the_limit_without_a_backlog() {
+        let gate = ConnectionGate::new(Some(2), 0);
+
+        let first = gate.admit().unwrap().ready().await;
+        let second = gate.admit().unwrap().ready().await;
+        assert_eq!(gate.active_connections(), 2);
+        assert!(gate.admit().is_none());
+
+        drop(first);
+        assert_eq!(gate.active_connections(), 1);
+        let third = gate.admit().unwrap();
+        assert!(!third.is_queued());
+        drop((second, third));
+    }
+
+    #[tokio::test]
+    #[expect(
+        clippy::significant_drop_tightening,
+        reason = "the queued connection holds its backlog slot until it is served"
+    )]
+    async fn queues_up_to_the_backlog_until_a_connection_closes() {
+        let gate = ConnectionGate::new(Some(1), 1);
+
+        let first = gate.admit().unwrap().ready().await;
+        let queued = gate.admit().unwrap();
+        assert!(queued.is_queued());
+        let waiting = tokio::spawn(queued.ready());
+        assert!(gate.admit().is_none());
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiting.is_finished());
+
+        drop(first);
+        let second = tokio::time::timeout(Duration::from_secs(1), waiting).await;
+        assert_eq!(gate.active_connections(), 1);
+
+        // The queued connection left the backlog once it was served.
+        assert!(gate.admit().unwrap().is_queued());
+        drop(second.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn unlimited_gate_admits_every_connection() {
+        let gate = ConnectionGate::new(None, 0);
+
+        let permits: Vec<ConnectionPermit> =
+            futures::future::join_all((0..100).map(|_| gate.admit().unwrap().ready())).await;
+        assert_eq!(gate.active_connections(), 100);
+        drop(permits);
+        assert_eq!(gate.active_connections(), 0);
+    }
+}
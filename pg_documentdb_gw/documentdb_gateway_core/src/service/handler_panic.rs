@@ -0,0 +1,76 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/service/handler_panic.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{any::Any, future::Future, panic::AssertUnwindSafe};
+
+use futures::FutureExt;
+
+use crate::telemetry::metrics::record_handler_panic;
+
+/// The message a panic was raised with, if it was a string.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+/// Runs `handler`, returning `None` if it panicked instead of unwinding into the caller.
+///
+/// The panic is logged under `activity_id` and counted in `gateway.handler.panics`. State the
+/// handler borrowed may be left inconsistent, so the caller should close the connection.
+pub async fn catch_handler_panic<F>(handler: F, activity_id: &str) -> Option<F::Output>
+where
+    F: Future,
+{
+    match AssertUnwindSafe(handler).catch_unwind().await {
+        Ok(output) => Some(output),
+        Err(payload) => {
+            tracing::error!(
+                activity_id = activity_id,
+                "Request handler panicked: {}",
+                panic_message(payload.as_ref())
+            );
+            record_handler_panic();
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn handle(request: i32) -> i32 {
+        tokio::task::yield_now().await;
+        assert!(request >= 0, "negative request {request}");
+        request * 2
+    }
+
+    #[tokio::test]
+    async fn panicking_handler_is_isolated() {
+        assert_eq!(catch_handler_panic(handle(-1), "panicking").await, None);
+
+        // The task that caught the panic, and others, keep serving.
+        assert_eq!(catch_handler_panic(handle(2), "next").await, Some(4));
+        let other = tokio::spawn(catch_handler_panic(handle(3), "other"));
+        assert_eq!(other.await.unwrap(), Some(6));
+    }
+
+    #[test]
+    fn panic_messages_are_extracted() {
+        let literal: Box<dyn Any + Send> = Box::new("literal");
+        let formatted: Box<dyn Any + Send> = Box::new(String::from("formatted"));
+        let other: Box<dyn Any + Send> = Box::new(1);
+
+        assert_eq!(panic_message(literal.as_ref()), "literal");
+        assert_eq!(panic_message(formatted.as_ref()), "formatted");
+        assert_eq!(panic_message(other.as_ref()), "non-string panic payload");
+    }
+}
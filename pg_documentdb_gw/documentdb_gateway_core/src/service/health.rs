@@ -0,0 +1,273 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/service/health.rs
+ *
+ * HTTP liveness and readiness endpoints for orchestrator probes.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use deadpool_postgres::Status;
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    context::ServiceContext,
+    error::Result,
+    service::http_server::{start_http_server, HttpHandler, HttpRequest, HttpResponse},
+};
+
+const CONTENT_TYPE: &str = "application/json";
+
+/// How long `/readyz` waits for a system connection and `SELECT 1`.
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The backend checks behind `/readyz`.
+#[async_trait]
+pub trait ReadinessProbe: Send + Sync {
+    /// Acquires a system requests connection and runs `SELECT 1` on it.
+    async fn ping_backend(&self) -> Result<()>;
+
+    /// State of the system requests pool, reported in the probe body.
+    fn pool_status(&self) -> Status;
+}
+
+#[async_trait]
+impl ReadinessProbe for ServiceContext {
+    async fn ping_backend(&self) -> Result<()> {
+        let connection = self
+            .connection_pool_manager()
+            .system_requests_connection()
+            .await?;
+        connection.batch_execute("SELECT 1").await?;
+        Ok(())
+    }
+
+    fn pool_status(&self) -> Status {
+        self.connection_pool_manager()
+            .system_requests_pool_status()
+            .status()
+    }
+}
+
+/// Serves `/healthz` and `/readyz` on `port`, bound like the gateway's own listener, until
+/// `shutdown` is cancelled.
+///
+/// `/healthz` answers 200 while the process is serving. `/readyz` answers 200 only if
+/// `draining` has not been cancelled and the probe reaches the backend in time, and 503
+/// otherwise.
+///
+/// # Errors
+///
+/// Returns an error if the port cannot be bound.
+pub fn create_health_listener(
+    use_local_host: bool,
+    port: u16,
+    probe: Arc<dyn ReadinessProbe>,
+    draining: CancellationToken,
+    shutdown: CancellationToken,
+) -> Result<SocketAddr> {
+    let host = if use_local_host {
+        "127.0.0.1"
+    } else {
+        "0.0.0.0"
+    };
+    let local_addr = start_http_server(
+        &format!("{host}:{port}"),
+        "health check",
+        Arc::new(HealthHandler { probe, draining }),
+        shutdown,
+    )?;
+    tracing::info!("Serving health checks on http://{local_addr}/healthz and /readyz");
+
+    Ok(local_addr)
+}
+
+struct HealthHandler {
+    probe: Arc<dyn ReadinessProbe>,
+    draining: CancellationToken,
+}
+
+#[async_trait]
+impl HttpHandler for HealthHandler {
+    async fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        let (status, body) = match request.get_path() {
+            Some(b"/healthz") => ("200 OK", json!({ "status": "alive" }).to_string()),
+            Some(b"/readyz") => {
+                readiness(self.probe.as_ref(), &self.draining, READINESS_TIMEOUT).await
+            }
+            _ => ("404 Not Found", String::new()),
+        };
+        HttpResponse {
+            status,
+            content_type: CONTENT_TYPE,
+            body,
+        }
+    }
+}
+
+/// Evaluates readiness, returning the HTTP status line and JSON body.
+async fn readiness(
+    probe: &dyn ReadinessProbe,
+    draining: &CancellationToken,
+    timeout: Duration,
+) -> (&'static str, String) {
+    let failure = if draining.is_cancelled() {
+        Some("shutting down".to_owned())
+    } else {
+        match tokio::time::timeout(timeout, probe.ping_backend()).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(format!("backend unavailable: {e}")),
+            Err(_) => Some(format!(
+                "backend did not respond within {}ms",
+                timeout.as_millis()
+            )),
+        }
+    };
+
+    let pool = probe.pool_status();
+    let pool = json!({
+        "maxSize": pool.max_size,
+        "size": pool.size,
+        "available": pool.available,
+        "waiting": pool.waiting,
+    });
+    match failure {
+        None => (
+            "200 OK",
+            json!({ "status": "ready", "pool": pool }).to_string(),
+        ),
+        Some(reason) => (
+            "503 Service Unavailable",
+            json!({ "status": "not ready", "reason": reason, "pool": pool }).to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    use super::*;
+    use crate::error::DocumentDBError;
+
+    /// Stands in for the system requests pool.
+    struct StubPool {
+        reachable: bool,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl ReadinessProbe for StubPool {
+        async fn ping_backend(&self) -> Result<()> {
+            tokio::time::sleep(self.delay).await;
+            if self.reachable {
+                Ok(())
+            } else {
+                Err(DocumentDBError::internal_error(
+                    "connection refused".to_owned(),
+                ))
+            }
+        }
+
+        fn pool_status(&self) -> Status {
+            Status {
+                max_size: 2,
+                size: 1,
+                available: 1,
+                waiting: 0,
+            }
+        }
+    }
+
+    const fn stub(reachable: bool) -> StubPool {
+        StubPool {
+            reachable,
+            delay: Duration::ZERO,
+        }
+    }
+
+    async fn check(probe: &StubPool, draining: &CancellationToken) -> (&'static str, Value) {
+        let (status, body) = readiness(probe, draining, Duration::from_millis(100)).await;
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn ready_when_backend_answers() {
+        let (status, body) = check(&stub(true), &CancellationToken::new()).await;
+
+        assert_eq!(status, "200 OK");
+        assert_eq!(body["status"], "ready");
+        assert_eq!(body["pool"]["maxSize"], 2);
+        assert_eq!(body["pool"]["available"], 1);
+    }
+
+    #[tokio::test]
+    async fn not_ready_when_backend_fails() {
+        let (status, body) = check(&stub(false), &CancellationToken::new()).await;
+
+        assert_eq!(status, "503 Service Unavailable");
+        assert_eq!(body["status"], "not ready");
+        assert!(body["reason"]
+            .as_str()
+            .unwrap()
+            .contains("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn not_ready_when_backend_is_slow() {
+        let probe = StubPool {
+            reachable: true,
+            delay: Duration::from_secs(60),
+        };
+        let (status, body) = check(&probe, &CancellationToken::new()).await;
+
+        assert_eq!(status, "503 Service Unavailable");
+        assert!(body["reason"].as_str().unwrap().contains("100ms"));
+    }
+
+    #[tokio::test]
+    async fn not_ready_while_draining() {
+        let draining = CancellationToken::new();
+        draining.cancel();
+        let (status, body) = check(&stub(true), &draining).await;
+
+        assert_eq!(status, "503 Service Unavailable");
+        assert_eq!(body["reason"], "shutting down");
+    }
+
+    #[tokio::test]
+    async fn listener_routes_probes() {
+        let addr = create_health_listener(
+            true,
+            0,
+            Arc::new(stub(false)),
+            CancellationToken::new(),
+            CancellationToken::new(),
+        )
+        .unwrap();
+
+        for (path, expected) in [
+            ("/healthz", "HTTP/1.1 200 OK"),
+            ("/readyz", "HTTP/1.1 503 Service Unavailable"),
+            ("/metrics", "HTTP/1.1 404 Not Found"),
+        ] {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(format!("GET {path} HTTP/1.1\r\n\r\n").as_bytes())
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            assert!(response.starts_with(expected), "{path}: {response}");
+        }
+    }
+}
@@ -0,0 +1,243 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/service/http_server.rs
+ *
+ * Minimal HTTP/1.1 server behind the health check and Prometheus endpoints.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{DocumentDBError, Result};
+
+const MAX_REQUEST_HEAD_BYTES: usize = 8192;
+
+/// How long a client has to send its request, and then to take the response.
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The head of a request: its request line and headers.
+#[derive(Debug)]
+pub struct HttpRequest {
+    head: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// The path of a `GET` request, without its query string, or `None` for other methods.
+    #[must_use]
+    pub fn get_path(&self) -> Option<&[u8]> {
+        let request_line = self.head.split(|&b| b == b'\r').next().unwrap_or_default();
+        let mut parts = request_line.split(|&b| b == b' ');
+        match (parts.next(), parts.next()) {
+            (Some(b"GET"), Some(path)) => path.split(|&b| b == b'?').next(),
+            _ => None,
+        }
+    }
+
+    /// Whether the headers mention `content_type`, as an `Accept` header listing it does.
+    #[must_use]
+    pub fn mentions(&self, content_type: &str) -> bool {
+        String::from_utf8_lossy(&self.head)
+            .to_ascii_lowercase()
+            .contains(content_type)
+    }
+}
+
+/// A response, always sent with `Connection: close`.
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+/// Answers the requests of a server started with [`start_http_server`].
+#[async_trait]
+pub trait HttpHandler: Send + Sync + 'static {
+    async fn handle(&self, request: &HttpRequest) -> HttpResponse;
+}
+
+/// Binds `address` and serves `handler` on it until `shutdown` is cancelled, returning the
+/// bound address. `purpose` names the endpoint in logs and errors.
+///
+/// The listener is bound before returning so that an unavailable address fails startup.
+/// A client that doesn't send its request or take the response within a few seconds is
+/// disconnected.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound.
+pub fn start_http_server(
+    address: &str,
+    purpose: &'static str,
+    handler: Arc<dyn HttpHandler>,
+    shutdown: CancellationToken,
+) -> Result<SocketAddr> {
+    start_with_timeout(address, purpose, handler, shutdown, IO_TIMEOUT)
+}
+
+fn start_with_timeout(
+    address: &str,
+    purpose: &'static str,
+    handler: Arc<dyn HttpHandler>,
+    shutdown: CancellationToken,
+    io_timeout: Duration,
+) -> Result<SocketAddr> {
+    let bind_error = |e: std::io::Error| {
+        DocumentDBError::internal_error(format!(
+            "Failed to bind {purpose} listener on {address}: {e}"
+        ))
+    };
+    let listener = std::net::TcpListener::bind(address).map_err(bind_error)?;
+    listener.set_nonblocking(true).map_err(bind_error)?;
+    let local_addr = listener.local_addr().map_err(bind_error)?;
+    let listener = TcpListener::from_std(listener).map_err(bind_error)?;
+
+    tokio::spawn(serve(listener, purpose, handler, shutdown, io_timeout));
+    Ok(local_addr)
+}
+
+async fn serve(
+    listener: TcpListener,
+    purpose: &'static str,
+    handler: Arc<dyn HttpHandler>,
+    shutdown: CancellationToken,
+    io_timeout: Duration,
+) {
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            () = shutdown.cancelled() => return,
+        };
+        match accepted {
+            Ok((stream, _)) => {
+                let handler = Arc::clone(&handler);
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, handler.as_ref(), io_timeout).await {
+                        tracing::debug!("{purpose} request failed: {e}");
+                    }
+                });
+            }
+            Err(e) => tracing::warn!("Failed to accept {purpose} connection: {e}"),
+        }
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    handler: &dyn HttpHandler,
+    io_timeout: Duration,
+) -> std::io::Result<()> {
+    let head = tokio::time::timeout(io_timeout, read_head(&mut stream))
+        .await
+        .map_err(|_elapsed| std::io::ErrorKind::TimedOut)??;
+    let response = handler.handle(&HttpRequest { head }).await;
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    );
+    tokio::time::timeout(io_timeout, async {
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    })
+    .await
+    .map_err(|_elapsed| std::io::ErrorKind::TimedOut)?
+}
+
+async fn read_head(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    let mut buf = [0_u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_HEAD_BYTES {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..read]);
+    }
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo;
+
+    #[async_trait]
+    impl HttpHandler for Echo {
+        async fn handle(&self, request: &HttpRequest) -> HttpResponse {
+            HttpResponse {
+                status: "200 OK",
+                content_type: "text/plain",
+                body: String::from_utf8_lossy(request.get_path().unwrap_or(b"-")).into_owned(),
+            }
+        }
+    }
+
+    async fn get(addr: SocketAddr, request: &[u8]) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn request_path_is_passed_without_query() {
+        let addr = start_http_server(
+            "127.0.0.1:0",
+            "test",
+            Arc::new(Echo),
+            CancellationToken::new(),
+        )
+        .unwrap();
+
+        let response = get(addr, b"GET /metrics?x=1 HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("\r\n\r\n/metrics"));
+        assert!(get(addr, b"POST /metrics HTTP/1.1\r\n\r\n")
+            .await
+            .ends_with("\r\n\r\n-"));
+    }
+
+    #[tokio::test]
+    async fn silent_client_is_disconnected() {
+        let addr = start_with_timeout(
+            "127.0.0.1:0",
+            "test",
+            Arc::new(Echo),
+            CancellationToken::new(),
+            Duration::from_millis(50),
+        )
+        .unwrap();
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let mut response = Vec::new();
+        let read = stream.read_to_end(&mut response).await.unwrap();
+        assert_eq!(read, 0);
+    }
+
+    #[tokio::test]
+    async fn server_stops_on_shutdown() {
+        let shutdown = CancellationToken::new();
+        let addr =
+            start_http_server("127.0.0.1:0", "test", Arc::new(Echo), shutdown.clone()).unwrap();
+
+        shutdown.cancel();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        TcpStream::connect(addr).await.unwrap_err();
+    }
+}
@@ -6,9 +6,17 @@
  *-------------------------------------------------------------------------
  */
 
+mod connection_gate;
 mod docdb_openssl;
+mod handler_panic;
+mod health;
+mod http_server;
 mod tcp_listener;
 mod tls;
 
-pub use tcp_listener::create_tcp_listeners;
+pub use connection_gate::{Admission, ConnectionGate, ConnectionPermit};
+pub use handler_panic::catch_handler_panic;
+pub use health::{create_health_listener, ReadinessProbe};
+pub use http_server::{start_http_server, HttpHandler, HttpRequest, HttpResponse};
+pub use tcp_listener::{configure_client_socket, create_tcp_listeners};
 pub use tls::TlsProvider;
@@ -5,17 +5,24 @@
  *
  * documentdb_gateway_core/src/service/tcp_listener.rs
  *
- * TCP listener creation utilities with cross-platform IPv4/IPv6 support.
+ * TCP listener creation utilities with cross-platform IPv4/IPv6 support, and the
+ * options set on accepted client sockets.
  *
  *-------------------------------------------------------------------------
  */
 
-use std::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::{
+    net::{Ipv6Addr, SocketAddr, SocketAddrV6},
+    time::Duration,
+};
 
-use socket2::{Domain, Protocol, Socket, Type};
-use tokio::net::TcpListener;
+use socket2::{Domain, Protocol, SockRef, Socket, TcpKeepalive, Type};
+use tokio::net::{TcpListener, TcpStream};
 
-use crate::error::{DocumentDBError, Result};
+use crate::{
+    configuration::SetupConfiguration,
+    error::{DocumentDBError, Result},
+};
 
 /// Creates TCP listeners bound to the appropriate addresses.
 ///
@@ -112,11 +119,88 @@ fn create_ipv6_only_listener(port: u16) -> std::io::Result<TcpListener> {
     TcpListener::from_std(std_listener)
 }
 
+/// Sets the options of an accepted client socket.
+///
+/// `TCP_NODELAY` is set so that small responses are sent without waiting to be coalesced.
+/// Unless disabled, `SO_KEEPALIVE` is set with the configured idle time, probe interval and
+/// probe count, so that a client that vanished without closing its connection is detected
+/// and the connection's resources are released.
+///
+/// # Errors
+/// Returns an error if a socket option cannot be set.
+pub fn configure_client_socket(
+    stream: &TcpStream,
+    setup_configuration: &dyn SetupConfiguration,
+) -> std::io::Result<()> {
+    stream.set_nodelay(true)?;
+
+    let socket = SockRef::from(stream);
+    if !setup_configuration.tcp_keepalive_enabled() {
+        return socket.set_keepalive(false);
+    }
+    let keepalive = TcpKeepalive::new()
+        .with_time(Duration::from_secs(
+            setup_configuration.tcp_keepalive_idle_secs(),
+        ))
+        .with_interval(Duration::from_secs(
+            setup_configuration.tcp_keepalive_interval_secs(),
+        ))
+        .with_retries(setup_configuration.tcp_keepalive_probes());
+    socket.set_tcp_keepalive(&keepalive)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::configuration::DocumentDBSetupConfiguration;
     use std::net::IpAddr;
 
+    /// Accepts a loopback connection and returns the gateway's side of it, along with the
+    /// client's side, which has to stay open.
+    async fn accepted_stream() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (accepted, _) = listener.accept().await.unwrap();
+        (accepted, client)
+    }
+
+    #[tokio::test]
+    async fn client_socket_gets_nodelay_and_keepalive() {
+        let (stream, _client) = accepted_stream().await;
+        let setup_configuration = DocumentDBSetupConfiguration {
+            tcp_keepalive_idle_secs: Some(30),
+            tcp_keepalive_interval_secs: Some(5),
+            tcp_keepalive_probes: Some(3),
+            ..DocumentDBSetupConfiguration::default()
+        };
+
+        configure_client_socket(&stream, &setup_configuration).unwrap();
+
+        assert!(stream.nodelay().unwrap());
+        let socket = SockRef::from(&stream);
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(socket.keepalive_time().unwrap(), Duration::from_secs(30));
+        assert_eq!(socket.keepalive_interval().unwrap(), Duration::from_secs(5));
+        assert_eq!(socket.keepalive_retries().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn client_socket_keepalive_can_be_disabled() {
+        let (stream, _client) = accepted_stream().await;
+        SockRef::from(&stream).set_keepalive(true).unwrap();
+        let setup_configuration = DocumentDBSetupConfiguration {
+            tcp_keepalive_enabled: Some(false),
+            ..DocumentDBSetupConfiguration::default()
+        };
+
+        configure_client_socket(&stream, &setup_configuration).unwrap();
+
+        assert!(stream.nodelay().unwrap());
+        assert!(!SockRef::from(&stream).keepalive().unwrap());
+    }
+
     #[tokio::test]
     async fn test_create_tcp_listeners_localhost_binds_to_ipv4_loopback_only() {
         let (ipv4_listener, ipv6_listener) = create_tcp_listeners(true, 0).await.unwrap();
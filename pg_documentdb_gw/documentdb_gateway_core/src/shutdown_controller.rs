@@ -7,26 +7,200 @@
  */
 
 use std::sync::LazyLock;
+use tokio::time::{Duration, Instant};
 use tokio_util::sync::CancellationToken;
 
+use crate::context::OperationRegistry;
+
+/// How often draining checks whether the remaining connections have closed.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often draining logs the connections and requests still active.
+const DRAIN_LOG_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Debug)]
 pub struct ShutdownController {
     token: CancellationToken,
+    force_token: CancellationToken,
 }
 
 // Global singleton
 pub static SHUTDOWN_CONTROLLER: LazyLock<ShutdownController> =
-    LazyLock::new(|| ShutdownController {
-        token: CancellationToken::new(),
-    });
+    LazyLock::new(ShutdownController::new);
 
 impl ShutdownController {
+    fn new() -> Self {
+        Self {
+            token: CancellationToken::new(),
+            force_token: CancellationToken::new(),
+        }
+    }
+
+    /// Cancelled by the first shutdown request, which starts draining.
     #[must_use]
     pub fn token(&self) -> CancellationToken {
         self.token.clone()
     }
 
+    /// Cancelled by a second shutdown request, which skips the rest of the grace period.
+    #[must_use]
+    pub fn force_token(&self) -> CancellationToken {
+        self.force_token.clone()
+    }
+
+    /// Starts a graceful shutdown, or forces it if one has already started.
     pub fn shutdown(&self) {
-        self.token.cancel();
+        if self.token.is_cancelled() {
+            self.force_token.cancel();
+        } else {
+            self.token.cancel();
+        }
+    }
+}
+
+/// How draining ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrainOutcome {
+    /// Every connection closed within the grace period.
+    Drained,
+    /// The grace period ran out with connections still open.
+    DeadlineExceeded { connections: usize, requests: usize },
+    /// A forced shutdown cut the grace period short.
+    Forced { connections: usize, requests: usize },
+}
+
+/// Waits up to `grace` for open connections to close once the listeners have stopped.
+///
+/// Connections close after finishing their in-flight request, so this resolves as soon
+/// as the last one does, or early if `force` is cancelled.
+pub async fn drain(
+    registry: &OperationRegistry,
+    grace: Duration,
+    force: &CancellationToken,
+) -> DrainOutcome {
+    let deadline = Instant::now() + grace;
+    let mut next_log = Instant::now();
+    loop {
+        let connections = registry.connection_count();
+        let requests = registry.len();
+        if connections == 0 {
+            return DrainOutcome::Drained;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return DrainOutcome::DeadlineExceeded {
+                connections,
+                requests,
+            };
+        }
+        if now >= next_log {
+            tracing::info!(
+                "Draining: {connections} connection(s) and {requests} request(s) still active, {}ms of grace left.",
+                deadline.duration_since(now).as_millis()
+            );
+            next_log = now + DRAIN_LOG_INTERVAL;
+        }
+
+        tokio::select! {
+            () = tokio::time::sleep(DRAIN_POLL_INTERVAL.min(deadline - now)) => {}
+            () = force.cancelled() => {
+                return DrainOutcome::Forced {
+                    connections: registry.connection_count(),
+                    requests: registry.len(),
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{context::OperationInfo, requests::RequestType};
+
+    /// Opens a connection running one request that finishes after `duration`.
+    fn slow_request(registry: &Arc<OperationRegistry>, duration: Duration) {
+        let connection_id = Uuid::new_v4();
+        let connection = registry.register_connection(connection_id, "127.0.0.1".to_owned());
+        let operation = registry.register(OperationInfo::new(
+            RequestType::Find,
+            "db.coll".to_owned(),
+            connection_id,
+            "127.0.0.1".to_owned(),
+        ));
+        tokio::spawn(async move {
+            tokio::time::sleep(duration).await;
+            drop(operation);
+            drop(connection);
+        });
+    }
+
+    #[tokio::test]
+    async fn drain_waits_for_slow_request() {
+        let registry = Arc::new(OperationRegistry::default());
+        slow_request(&registry, Duration::from_millis(200));
+
+        let outcome = drain(&registry, Duration::from_secs(5), &CancellationToken::new()).await;
+
+        assert_eq!(outcome, DrainOutcome::Drained);
+        assert!(registry.is_empty());
+    }
+
+    #[tokio::test]
+    async fn drain_stops_at_deadline() {
+        let registry = Arc::new(OperationRegistry::default());
+        slow_request(&registry, Duration::from_secs(60));
+
+        let outcome = drain(
+            &registry,
+            Duration::from_millis(100),
+            &CancellationToken::new(),
+        )
+        .await;
+
+        assert_eq!(
+            outcome,
+            DrainOutcome::DeadlineExceeded {
+                connections: 1,
+                requests: 1
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn second_shutdown_forces_drain() {
+        let controller = ShutdownController::new();
+        let registry = Arc::new(OperationRegistry::default());
+        slow_request(&registry, Duration::from_secs(60));
+
+        controller.shutdown();
+        assert!(controller.token().is_cancelled());
+        assert!(!controller.force_token().is_cancelled());
+
+        let force = controller.force_token();
+        let draining = tokio::spawn({
+            let registry = Arc::clone(&registry);
+            async move { drain(&registry, Duration::from_secs(60), &force).await }
+        });
+        controller.shutdown();
+
+        assert_eq!(
+            draining.await.unwrap(),
+            DrainOutcome::Forced {
+                connections: 1,
+                requests: 1
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn drain_with_no_connections_is_immediate() {
+        let registry = OperationRegistry::default();
+        let outcome = drain(&registry, Duration::ZERO, &CancellationToken::new()).await;
+        assert_eq!(outcome, DrainOutcome::Drained);
     }
 }
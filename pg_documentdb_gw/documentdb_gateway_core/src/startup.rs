@@ -6,25 +6,231 @@
  *-------------------------------------------------------------------------
  */
 
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
+use openssl::{pkey::PKey, x509::X509};
 use tokio::time::{Duration, Instant};
 
 use crate::{
-    configuration::{DynamicConfiguration, SetupConfiguration},
+    auth::DatabaseAuthorizer,
+    configuration::{
+        AsyncRuntimeFlavor, CertInputType, CertificateOptions, DynamicConfiguration,
+        SetupConfiguration,
+    },
     context::ServiceContext,
-    error::Result,
+    error::{DocumentDBError, Result},
     postgres::conn_mgmt::{self, PoolManager},
     responses::CustomPostgresErrorMapper,
     service::TlsProvider,
+    telemetry::{audit::AuditLog, metrics::MetricsExporter, TelemetryConfig},
 };
 
+/// Returns a builder for the async runtime of the configured flavor.
+#[must_use]
+pub fn runtime_builder(setup_configuration: &dyn SetupConfiguration) -> tokio::runtime::Builder {
+    let mut builder = match setup_configuration.async_runtime_flavor() {
+        AsyncRuntimeFlavor::MultiThread => {
+            let worker_threads = setup_configuration.async_runtime_worker_threads();
+            tracing::info!("Using a multi-thread runtime with {worker_threads} worker threads");
+            let mut builder = tokio::runtime::Builder::new_multi_thread();
+            builder.worker_threads(worker_threads);
+            builder
+        }
+        AsyncRuntimeFlavor::CurrentThread => {
+            tracing::info!("Using a current-thread runtime; AsyncRuntimeWorkerThreads is ignored");
+            tokio::runtime::Builder::new_current_thread()
+        }
+    };
+    builder.enable_all();
+    builder
+}
+
+/// Validates the setup configuration before any runtime resources are created.
+///
+/// Every problem found is returned, so a misconfigured deployment can be fixed in one
+/// pass instead of one failed start per mistake. An empty result means startup may proceed.
+#[must_use]
+pub fn preflight(setup_configuration: &dyn SetupConfiguration) -> Vec<DocumentDBError> {
+    let mut problems = Vec::new();
+
+    if setup_configuration.async_runtime_flavor() == AsyncRuntimeFlavor::MultiThread
+        && setup_configuration.async_runtime_worker_threads() == 0
+    {
+        problems.push("AsyncRuntimeWorkerThreads must be greater than 0.".to_owned());
+    }
+    if setup_configuration.stream_read_buffer_size() == 0 {
+        problems.push("StreamReadBufferSize must be greater than 0.".to_owned());
+    }
+    if setup_configuration.stream_write_buffer_size() == 0 {
+        problems.push("StreamWriteBufferSize must be greater than 0.".to_owned());
+    }
+    let max_bson_object_size = setup_configuration.max_bson_object_size();
+    let max_message_size_bytes = setup_configuration.max_message_size_bytes();
+    if max_bson_object_size <= 0 {
+        problems.push("MaxBsonObjectSize must be greater than 0.".to_owned());
+    }
+    if max_message_size_bytes < max_bson_object_size {
+        problems.push(format!(
+            "MaxMessageSizeBytes {max_message_size_bytes} must be at least MaxBsonObjectSize {max_bson_object_size}."
+        ));
+    }
+    if setup_configuration.postgres_command_timeout_secs() == 0 {
+        problems.push("PostgresCommandTimeoutSecs must be greater than 0.".to_owned());
+    }
+    if setup_configuration.health_check_port() == Some(setup_configuration.gateway_listen_port()) {
+        problems.push(format!(
+            "HealthCheckPort {} conflicts with GatewayListenPort.",
+            setup_configuration.gateway_listen_port()
+        ));
+    }
+
+    check_certificates(setup_configuration.certificate_options(), &mut problems);
+
+    if let Some(audit_options) = setup_configuration.audit_options() {
+        if audit_options.file_path.trim().is_empty() {
+            problems.push("AuditOptions.FilePath must not be empty.".to_owned());
+        } else if let Err(e) = AuditLog::open(audit_options) {
+            problems.push(e.to_string());
+        }
+    }
+
+    let telemetry = TelemetryConfig::new(setup_configuration.telemetry_options());
+    if telemetry.metrics().metrics_enabled()
+        && telemetry.metrics().exporter() == MetricsExporter::Otlp
+    {
+        check_endpoint(
+            "TelemetryOptions.Metrics.OtlpEndpoint",
+            &telemetry.metrics().otlp_endpoint(),
+            &mut problems,
+        );
+    }
+    if telemetry.tracing().tracing_enabled() {
+        check_endpoint(
+            "TelemetryOptions.Tracing.OtlpEndpoint",
+            &telemetry.tracing().otlp_endpoint(),
+            &mut problems,
+        );
+    }
+
+    problems
+        .into_iter()
+        .map(DocumentDBError::internal_error)
+        .collect()
+}
+
+/// Checks that `PemFile` certificates exist, parse, and that the key belongs to the certificate.
+/// Auto-generated certificates are created at startup and need no checks.
+fn check_certificates(options: &CertificateOptions, problems: &mut Vec<String>) {
+    if options.cert_type != CertInputType::PemFile {
+        return;
+    }
+
+    let certificate = match options.file_path.as_deref() {
+        None => {
+            problems.push("CertificateOptions.FilePath is required for PemFile.".to_owned());
+            None
+        }
+        Some(path) => read_pem("CertificateOptions.FilePath", path, problems, |pem| {
+            X509::from_pem(pem)
+        }),
+    };
+    let private_key = match options.key_file_path.as_deref() {
+        None => {
+            problems.push("CertificateOptions.KeyFilePath is required for PemFile.".to_owned());
+            None
+        }
+        Some(path) => read_pem("CertificateOptions.KeyFilePath", path, problems, |pem| {
+            PKey::private_key_from_pem(pem)
+        }),
+    };
+    if let Some(ca_path) = options.ca_path.as_deref() {
+        read_pem("CertificateOptions.CaPath", ca_path, problems, |pem| {
+            X509::stack_from_pem(pem)
+        });
+    }
+
+    if let (Some(certificate), Some(private_key)) = (certificate, private_key) {
+        let matches = certificate
+            .public_key()
+            .is_ok_and(|public_key| public_key.public_eq(&private_key));
+        if !matches {
+            problems.push(format!(
+                "CertificateOptions.KeyFilePath '{}' does not match the certificate in '{}'.",
+                options.key_file_path.as_deref().unwrap_or_default(),
+                options.file_path.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+}
+
+fn read_pem<T>(
+    setting: &str,
+    path: &str,
+    problems: &mut Vec<String>,
+    parse: impl FnOnce(&[u8]) -> std::result::Result<T, openssl::error::ErrorStack>,
+) -> Option<T> {
+    if !Path::new(path).is_file() {
+        problems.push(format!("{setting} '{path}' does not exist."));
+        return None;
+    }
+    match std::fs::read(path) {
+        Ok(pem) => match parse(&pem) {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                problems.push(format!("{setting} '{path}' is not valid PEM: {e}"));
+                None
+            }
+        },
+        Err(e) => {
+            problems.push(format!("{setting} '{path}' cannot be read: {e}"));
+            None
+        }
+    }
+}
+
+/// Checks that `endpoint` is an absolute `http` or `https` URL with a host and valid port.
+fn check_endpoint(setting: &str, endpoint: &str, problems: &mut Vec<String>) {
+    let authority = endpoint
+        .strip_prefix("http://")
+        .or_else(|| endpoint.strip_prefix("https://"))
+        .map(|rest| rest.split(['/', '?', '#']).next().unwrap_or_default());
+
+    let valid = authority.is_some_and(|authority| {
+        let (host, port) = if let Some(ipv6) = authority.strip_prefix('[') {
+            match ipv6.split_once(']') {
+                Some((host, rest)) => (host, rest.strip_prefix(':')),
+                None => return false,
+            }
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            }
+        };
+        !host.is_empty()
+            && !host.contains(char::is_whitespace)
+            && port.is_none_or(|port| port.parse::<u16>().is_ok())
+    });
+
+    if !valid {
+        problems.push(format!(
+            "{setting} '{endpoint}' is not a valid URL; expected http(s)://host[:port]."
+        ));
+    }
+}
+
+/// Builds the service context and starts cleaning up its unused pools.
+///
+/// `database_authorizer` restricts what `listDatabases` and `listCollections` return. The
+/// gateway binary and the host background worker pass `None`, so that filtering stays off
+/// until an embedder provides an authorizer.
 pub fn get_service_context(
     setup_configuration: Box<dyn SetupConfiguration>,
     dynamic_configuration: Arc<dyn DynamicConfiguration>,
     connection_pool_manager: Arc<PoolManager>,
     tls_provider: TlsProvider,
     custom_pg_error_mapper: Option<Box<dyn CustomPostgresErrorMapper>>,
+    database_authorizer: Option<Box<dyn DatabaseAuthorizer>>,
 ) -> ServiceContext {
     tracing::info!("Initial dynamic configuration: {dynamic_configuration:?}");
 
@@ -34,6 +240,7 @@ pub fn get_service_context(
         connection_pool_manager,
         tls_provider,
         custom_pg_error_mapper,
+        database_authorizer,
     );
 
     conn_mgmt::clean_unused_pools(service_context.clone());
@@ -69,3 +276,152 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        configuration::DocumentDBSetupConfiguration,
+        telemetry::{audit::AuditOptions, config::TelemetryOptions, traces::TracingOptions},
+    };
+
+    fn valid_configuration() -> DocumentDBSetupConfiguration {
+        DocumentDBSetupConfiguration {
+            async_runtime_worker_threads: Some(2),
+            certificate_options: CertificateOptions {
+                cert_type: CertInputType::PemAutoGenerated,
+                ..CertificateOptions::default()
+            },
+            telemetry_options: Some(TelemetryOptions::default()),
+            ..DocumentDBSetupConfiguration::default()
+        }
+    }
+
+    fn with_tracing_endpoint(endpoint: &str) -> DocumentDBSetupConfiguration {
+        DocumentDBSetupConfiguration {
+            telemetry_options: Some(TelemetryOptions {
+                tracing: Some(TracingOptions {
+                    enabled: Some(true),
+                    otlp_endpoint: Some(endpoint.to_owned()),
+                    ..TracingOptions::default()
+                }),
+                ..TelemetryOptions::default()
+            }),
+            ..valid_configuration()
+        }
+    }
+
+    fn messages(setup_configuration: &DocumentDBSetupConfiguration) -> Vec<String> {
+        preflight(setup_configuration)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn preflight_accepts_valid_configuration() {
+        assert!(messages(&with_tracing_endpoint("http://collector:4317")).is_empty());
+        assert!(messages(&with_tracing_endpoint("https://[::1]:4317/v1")).is_empty());
+    }
+
+    #[test]
+    fn preflight_reports_missing_certificate_file() {
+        let setup_configuration = DocumentDBSetupConfiguration {
+            certificate_options: CertificateOptions {
+                cert_type: CertInputType::PemFile,
+                file_path: Some("/nonexistent/cert.pem".to_owned()),
+                key_file_path: None,
+                ca_path: None,
+            },
+            ..valid_configuration()
+        };
+
+        let problems = messages(&setup_configuration);
+
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0]
+            .contains("CertificateOptions.FilePath '/nonexistent/cert.pem' does not exist"));
+        assert!(problems[1].contains("CertificateOptions.KeyFilePath is required for PemFile"));
+    }
+
+    #[test]
+    fn preflight_reports_invalid_endpoint() {
+        for endpoint in ["collector:4317", "http://", "http://collector:99999"] {
+            let problems = messages(&with_tracing_endpoint(endpoint));
+
+            assert_eq!(problems.len(), 1, "{endpoint}: {problems:?}");
+            assert!(
+                problems[0].contains(&format!(
+                    "TelemetryOptions.Tracing.OtlpEndpoint '{endpoint}' is not a valid URL"
+                )),
+                "{problems:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn preflight_reports_unusable_audit_file() {
+        let configuration = DocumentDBSetupConfiguration {
+            audit_options: Some(AuditOptions {
+                file_path: "/nonexistent-directory/audit.log".to_owned(),
+                commands: None,
+            }),
+            ..valid_configuration()
+        };
+
+        let problems = messages(&configuration);
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert!(problems[0].contains("audit log file"), "{problems:?}");
+    }
+
+    #[test]
+    fn runtime_builder_uses_configured_flavor() {
+        let flavors = [
+            (
+                AsyncRuntimeFlavor::MultiThread,
+                tokio::runtime::RuntimeFlavor::MultiThread,
+            ),
+            (
+                AsyncRuntimeFlavor::CurrentThread,
+                tokio::runtime::RuntimeFlavor::CurrentThread,
+            ),
+        ];
+        for (flavor, expected) in flavors {
+            let setup_configuration = DocumentDBSetupConfiguration {
+                async_runtime_flavor: Some(flavor),
+                ..valid_configuration()
+            };
+
+            let runtime = runtime_builder(&setup_configuration).build().unwrap();
+
+            assert_eq!(runtime.handle().runtime_flavor(), expected);
+        }
+    }
+
+    #[test]
+    fn current_thread_runtime_ignores_worker_threads() {
+        let setup_configuration = DocumentDBSetupConfiguration {
+            async_runtime_flavor: Some(AsyncRuntimeFlavor::CurrentThread),
+            async_runtime_worker_threads: Some(0),
+            ..valid_configuration()
+        };
+
+        assert!(messages(&setup_configuration).is_empty());
+    }
+
+    #[test]
+    fn preflight_reports_all_problems() {
+        let setup_configuration = DocumentDBSetupConfiguration {
+            async_runtime_worker_threads: Some(0),
+            stream_read_buffer_size: Some(0),
+            ..with_tracing_endpoint("not a url")
+        };
+
+        let problems = messages(&setup_configuration);
+
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert!(problems[0].contains("AsyncRuntimeWorkerThreads"));
+        assert!(problems[1].contains("StreamReadBufferSize"));
+        assert!(problems[2].contains("OtlpEndpoint"));
+    }
+}
@@ -0,0 +1,293 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/telemetry/audit.rs
+ *
+ * Audit trail of privileged commands, written to its own file so it can be
+ * retained apart from application logs.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    collections::HashSet,
+    fs::OpenOptions,
+    io::Write,
+    sync::{Mutex, PoisonError},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    context::ConnectionContext,
+    error::{DocumentDBError, Result},
+    requests::Request,
+};
+
+/// Commands audited when `AuditOptions.Commands` is not configured.
+pub const DEFAULT_AUDITED_COMMANDS: &[&str] = &[
+    "authenticate",
+    "saslStart",
+    "saslContinue",
+    "logout",
+    "dropDatabase",
+    "createUser",
+    "updateUser",
+    "dropUser",
+    "createRole",
+    "updateRole",
+    "dropRole",
+    "killOp",
+    "killSessions",
+    "setParameter",
+    "compact",
+];
+
+/// JSON configuration for the audit log (matches SetupConfiguration.json `AuditOptions`).
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct AuditOptions {
+    /// File audit records are appended to, one JSON document per line.
+    pub file_path: String,
+    /// Command names to audit, replacing the default set.
+    pub commands: Option<Vec<String>>,
+}
+
+/// One audited command. Only the command name and namespace are taken from the request,
+/// so credentials in its body never reach the audit log.
+#[derive(Debug, Serialize)]
+pub struct AuditRecord<'a> {
+    pub timestamp: String,
+    pub user: Option<&'a str>,
+    pub source_ip: &'a str,
+    pub command: &'a str,
+    pub namespace: String,
+    pub outcome: AuditOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+/// Writes audit records for the configured commands to a dedicated sink.
+pub struct AuditLog {
+    commands: HashSet<String>,
+    sink: Mutex<Box<dyn Write + Send>>,
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("commands", &self.commands)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AuditLog {
+    #[must_use]
+    pub fn new(commands: &[String], sink: Box<dyn Write + Send>) -> Self {
+        Self {
+            commands: commands.iter().cloned().collect(),
+            sink: Mutex::new(sink),
+        }
+    }
+
+    /// Opens the configured audit file for appending, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened.
+    pub fn open(options: &AuditOptions) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&options.file_path)
+            .map_err(|e| {
+                DocumentDBError::internal_error(format!(
+                    "Failed to open audit log file '{}': {e}",
+                    options.file_path
+                ))
+            })?;
+
+        let commands = options.commands.clone().unwrap_or_else(|| {
+            DEFAULT_AUDITED_COMMANDS
+                .iter()
+                .map(|&command| command.to_owned())
+                .collect()
+        });
+        Ok(Self::new(&commands, Box::new(file)))
+    }
+
+    #[must_use]
+    pub fn is_audited(&self, command: &str) -> bool {
+        self.commands.contains(command)
+    }
+
+    /// Appends `record` as one line if its command is audited, returning whether it was
+    /// written. A failed write is logged, not returned, so auditing never fails the command
+    /// it describes.
+    pub fn record(&self, record: &AuditRecord<'_>) -> bool {
+        if !self.is_audited(record.command) {
+            return false;
+        }
+
+        let mut line = match serde_json::to_vec(record) {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::error!("Failed to serialize audit record: {e}");
+                return false;
+            }
+        };
+        line.push(b'\n');
+
+        let written = {
+            let mut sink = self.sink.lock().unwrap_or_else(PoisonError::into_inner);
+            sink.write_all(&line).and_then(|()| sink.flush())
+        };
+        if let Err(e) = written {
+            tracing::error!("Failed to write audit record: {e}");
+            return false;
+        }
+        true
+    }
+}
+
+/// Records `request` in the service's audit log, when one is configured and audits its
+/// command; `error_code` is set when the command failed.
+pub fn audit_request(
+    connection_context: &ConnectionContext,
+    request: Option<&Request<'_>>,
+    collection: &str,
+    error_code: Option<i32>,
+) {
+    let (Some(audit_log), Some(request)) =
+        (connection_context.service_context.audit_log(), request)
+    else {
+        return;
+    };
+    let command = request.request_type().to_command_str();
+    if !audit_log.is_audited(command) {
+        return;
+    }
+
+    let db = request.db().unwrap_or_default();
+    audit_log.record(&AuditRecord {
+        timestamp: bson::DateTime::now()
+            .try_to_rfc3339_string()
+            .unwrap_or_default(),
+        user: connection_context.auth_state.username().ok(),
+        source_ip: &connection_context.ip_address,
+        command,
+        namespace: if collection.is_empty() {
+            db.to_owned()
+        } else {
+            format!("{db}.{collection}")
+        },
+        outcome: if error_code.is_some() {
+            AuditOutcome::Failure
+        } else {
+            AuditOutcome::Success
+        },
+        error_code,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct CapturedAudit(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedAudit {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .lock()
+                .map_err(|e| std::io::Error::other(e.to_string()))?
+                .extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn audit_log(commands: &[&str]) -> (AuditLog, CapturedAudit) {
+        let captured = CapturedAudit::default();
+        let commands: Vec<String> = commands.iter().map(|&c| c.to_owned()).collect();
+        (
+            AuditLog::new(&commands, Box::new(captured.clone())),
+            captured,
+        )
+    }
+
+    fn record(command: &'static str) -> AuditRecord<'static> {
+        AuditRecord {
+            timestamp: "2024-01-01T00:00:00Z".to_owned(),
+            user: Some("admin"),
+            source_ip: "10.0.0.1",
+            command,
+            namespace: "admin".to_owned(),
+            outcome: AuditOutcome::Success,
+            error_code: None,
+        }
+    }
+
+    #[test]
+    fn configured_command_is_audited() {
+        let (audit_log, captured) = audit_log(&["dropDatabase"]);
+
+        assert!(audit_log.record(&AuditRecord {
+            namespace: "sales".to_owned(),
+            outcome: AuditOutcome::Failure,
+            error_code: Some(13),
+            ..record("dropDatabase")
+        }));
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let line: serde_json::Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(line["command"], "dropDatabase");
+        assert_eq!(line["namespace"], "sales");
+        assert_eq!(line["user"], "admin");
+        assert_eq!(line["source_ip"], "10.0.0.1");
+        assert_eq!(line["outcome"], "failure");
+        assert_eq!(line["error_code"], 13);
+    }
+
+    #[test]
+    fn unconfigured_command_is_not_audited() {
+        let (audit_log, captured) = audit_log(&["dropDatabase"]);
+
+        assert!(!audit_log.record(&record("find")));
+        assert!(!audit_log.record(&record("createUser")));
+        assert!(captured.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn default_commands_apply_when_not_configured() {
+        let path = std::env::temp_dir().join(format!("audit-{}.log", uuid::Uuid::new_v4()));
+        let audit_log = AuditLog::open(&AuditOptions {
+            file_path: path.to_string_lossy().into_owned(),
+            commands: None,
+        })
+        .unwrap();
+
+        for command in DEFAULT_AUDITED_COMMANDS {
+            assert!(audit_log.is_audited(command));
+        }
+        assert!(audit_log.record(&record("saslStart")));
+
+        let output = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(output.lines().count(), 1);
+        assert!(!output.contains("payload"));
+    }
+}
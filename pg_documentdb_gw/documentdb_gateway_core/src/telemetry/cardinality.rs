@@ -0,0 +1,159 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * src/telemetry/cardinality.rs
+ *
+ * Caps the number of distinct collection names, client application names and
+ * tenants used as metric attributes. Workloads that create many short-lived
+ * collections, or clients that set unique appNames, would otherwise grow a
+ * new time series per value until the collector runs out of memory.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    sync::{LazyLock, PoisonError, RwLock},
+};
+
+/// Label that replaces collection names beyond the cardinality limit by default.
+pub(crate) const DEFAULT_COLLECTION_OVERFLOW_LABEL: &str = "__other__";
+
+/// Distinct client application names recorded by default. Clients choose their `appName`,
+/// so unlike collections it is capped unless configured otherwise.
+pub(crate) const DEFAULT_APP_NAME_CARDINALITY_LIMIT: usize = 100;
+
+/// Distinct tenants recorded by default. Tenants come from the configured mapping, so the
+/// cap only guards against a mapping that grows without bound.
+pub(crate) const DEFAULT_TENANT_CARDINALITY_LIMIT: usize = 100;
+
+static COLLECTION_CARDINALITY: LazyLock<CardinalityLimiter> =
+    LazyLock::new(|| CardinalityLimiter::new(0, DEFAULT_COLLECTION_OVERFLOW_LABEL));
+
+static APP_NAME_CARDINALITY: LazyLock<CardinalityLimiter> = LazyLock::new(|| {
+    CardinalityLimiter::new(
+        DEFAULT_APP_NAME_CARDINALITY_LIMIT,
+        DEFAULT_COLLECTION_OVERFLOW_LABEL,
+    )
+});
+
+static TENANT_CARDINALITY: LazyLock<CardinalityLimiter> = LazyLock::new(|| {
+    CardinalityLimiter::new(
+        DEFAULT_TENANT_CARDINALITY_LIMIT,
+        DEFAULT_COLLECTION_OVERFLOW_LABEL,
+    )
+});
+
+/// Admits the first `limit` distinct values it sees and maps every later one to an
+/// overflow label. Admitted values stay admitted for the life of the process.
+#[derive(Debug)]
+pub struct CardinalityLimiter {
+    state: RwLock<LimiterState>,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    /// Distinct values admitted; 0 admits all of them.
+    limit: usize,
+    overflow_label: String,
+    seen: HashSet<String>,
+}
+
+impl CardinalityLimiter {
+    #[must_use]
+    pub fn new(limit: usize, overflow_label: &str) -> Self {
+        Self {
+            state: RwLock::new(LimiterState {
+                limit,
+                overflow_label: overflow_label.to_owned(),
+                seen: HashSet::new(),
+            }),
+        }
+    }
+
+    /// Changes the limit and overflow label, keeping the values already admitted.
+    pub fn configure(&self, limit: usize, overflow_label: &str) {
+        let mut state = self.state.write().unwrap_or_else(PoisonError::into_inner);
+        state.limit = limit;
+        overflow_label.clone_into(&mut state.overflow_label);
+    }
+
+    /// Returns `value` if it is admitted, or the overflow label once the limit is reached.
+    pub fn admit<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        {
+            let state = self.state.read().unwrap_or_else(PoisonError::into_inner);
+            if state.limit == 0 || state.seen.contains(value) {
+                return Cow::Borrowed(value);
+            }
+            if state.seen.len() >= state.limit {
+                return Cow::Owned(state.overflow_label.clone());
+            }
+        }
+
+        let mut state = self.state.write().unwrap_or_else(PoisonError::into_inner);
+        if state.seen.len() < state.limit || state.seen.contains(value) {
+            state.seen.insert(value.to_owned());
+            Cow::Borrowed(value)
+        } else {
+            Cow::Owned(state.overflow_label.clone())
+        }
+    }
+}
+
+/// Returns the collection name to record in metric attributes.
+pub(crate) fn metric_collection_name(collection: &str) -> Cow<'_, str> {
+    COLLECTION_CARDINALITY.admit(collection)
+}
+
+pub(crate) fn set_collection_cardinality_limit(limit: usize, overflow_label: &str) {
+    COLLECTION_CARDINALITY.configure(limit, overflow_label);
+}
+
+/// Returns the client application name to record in metric attributes.
+pub(crate) fn metric_app_name(app_name: &str) -> Cow<'_, str> {
+    APP_NAME_CARDINALITY.admit(app_name)
+}
+
+pub(crate) fn set_app_name_cardinality_limit(limit: usize, overflow_label: &str) {
+    APP_NAME_CARDINALITY.configure(limit, overflow_label);
+}
+
+/// Returns the tenant to record in metric attributes.
+pub(crate) fn metric_tenant(tenant: &str) -> Cow<'_, str> {
+    TENANT_CARDINALITY.admit(tenant)
+}
+
+pub(crate) fn set_tenant_cardinality_limit(limit: usize, overflow_label: &str) {
+    TENANT_CARDINALITY.configure(limit, overflow_label);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collections_past_the_limit_use_the_overflow_label() {
+        let limiter = CardinalityLimiter::new(2, "__other__");
+
+        assert_eq!(limiter.admit("a"), "a");
+        assert_eq!(limiter.admit("b"), "b");
+        assert_eq!(limiter.admit("c"), "__other__");
+        assert_eq!(limiter.admit("d"), "__other__");
+        // Collections seen before the limit keep their name.
+        assert_eq!(limiter.admit("a"), "a");
+
+        limiter.configure(2, "overflow");
+        assert_eq!(limiter.admit("c"), "overflow");
+    }
+
+    #[test]
+    fn zero_limit_admits_every_collection() {
+        let limiter = CardinalityLimiter::new(0, "__other__");
+
+        for i in 0..100 {
+            let name = format!("c{i}");
+            assert_eq!(limiter.admit(&name), name.as_str());
+        }
+    }
+}
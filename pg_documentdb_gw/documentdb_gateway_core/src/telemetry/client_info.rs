@@ -10,6 +10,9 @@ use std::fmt;
 
 use bson::raw::{RawDocument, RawDocumentBuf};
 
+/// Application name recorded for clients that sent no `application.name` in the handshake.
+pub const UNKNOWN_APP_NAME: &str = "unknown";
+
 /// Represents metadata about a client connecting to the server.
 ///
 /// This struct contains optional fields for application, driver, and operating system information:
@@ -54,6 +57,12 @@ impl ClientInformation {
         }
     }
 
+    /// The `appName` the driver sent as `application.name`.
+    #[must_use]
+    pub fn application_name(&self) -> Option<&str> {
+        self.application_name.as_deref()
+    }
+
     #[inline]
     #[must_use]
     pub const fn is_empty(&self) -> bool {
@@ -194,6 +203,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_application_name_from_handshake() {
+        let raw = make_raw_doc(&doc! {
+            "application": { "name": "billing-service" },
+            "driver": { "name": "test-driver" },
+        });
+        assert_eq!(
+            ClientInformation::parse_from_client_document(&raw).application_name(),
+            Some("billing-service")
+        );
+
+        let raw = make_raw_doc(&doc! { "driver": { "name": "test-driver" } });
+        assert_eq!(
+            ClientInformation::parse_from_client_document(&raw).application_name(),
+            None
+        );
+    }
+
     #[test]
     fn test_parse_client_info_none() {
         let result = parse_client_info(None);
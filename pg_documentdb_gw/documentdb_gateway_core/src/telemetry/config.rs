@@ -6,15 +6,84 @@
  * Shared telemetry configuration types and helpers.
  * Follows SetupConfiguration pattern: accessor methods resolve JSON > env > default.
  *
+ * SetupConfiguration.json `TelemetryOptions` section and environment fallbacks:
+ *
+ *   ServiceName                   OTEL_SERVICE_NAME
+ *   ServiceVersion                OTEL_SERVICE_VERSION
+ *   Metrics.Enabled               OTEL_METRICS_ENABLED (default false)
+ *   Metrics.OtlpEndpoint          OTEL_EXPORTER_OTLP_METRICS_ENDPOINT, OTEL_EXPORTER_OTLP_ENDPOINT
+ *   Metrics.ExportIntervalMs      OTEL_METRIC_EXPORT_INTERVAL (default 15000)
+ *   Metrics.ExportTimeoutMs       OTEL_EXPORTER_OTLP_METRICS_TIMEOUT, OTEL_EXPORTER_OTLP_TIMEOUT
+ *   Metrics.Compression           OTEL_EXPORTER_OTLP_METRICS_COMPRESSION, OTEL_EXPORTER_OTLP_COMPRESSION (default none)
+ *   Metrics.Temporality           OTEL_EXPORTER_OTLP_METRICS_TEMPORALITY_PREFERENCE (delta|cumulative|lowmemory, default delta)
+ *   Metrics.Exporter              OTEL_METRICS_EXPORTER (otlp|prometheus, default otlp)
+ *   Metrics.PrometheusHost        OTEL_EXPORTER_PROMETHEUS_HOST (default localhost)
+ *   Metrics.PrometheusPort        OTEL_EXPORTER_PROMETHEUS_PORT (default 9464)
+ *   Metrics.ExemplarsEnabled      OTEL_METRICS_EXEMPLAR_FILTER (trace_based|always_off, default off)
+ *   Tracing.Enabled               OTEL_TRACING_ENABLED (default false)
+ *   Tracing.OtlpEndpoint          OTEL_EXPORTER_OTLP_TRACES_ENDPOINT, OTEL_EXPORTER_OTLP_ENDPOINT
+ *   Tracing.ExportTimeoutMs       OTEL_EXPORTER_OTLP_TRACES_TIMEOUT, OTEL_EXPORTER_OTLP_TIMEOUT
+ *   Tracing.Compression           OTEL_EXPORTER_OTLP_TRACES_COMPRESSION, OTEL_EXPORTER_OTLP_COMPRESSION (default gzip)
+ *   Tracing.SamplingRatio         OTEL_TRACES_SAMPLER_ARG (default 1.0)
+ *   Tracing.OperationSamplingRatios  (JSON only) per-command ratios, e.g. {"find": 0.01}
+ *
+ * Endpoints default to http://localhost:4317 (OTLP/gRPC); timeouts default to 10000 ms.
+ * Compression accepts "gzip" or "none"; unrecognized values fall through to the next source.
+ *
+ * Transport security is shared by all exporters:
+ *
+ *   Tls.CaFile                    OTEL_EXPORTER_OTLP_CERTIFICATE
+ *   Tls.ClientCertFile            OTEL_EXPORTER_OTLP_CLIENT_CERTIFICATE
+ *   Tls.ClientKeyFile             OTEL_EXPORTER_OTLP_CLIENT_KEY
+ *   Tls.Insecure                  OTEL_EXPORTER_OTLP_INSECURE (default false)
+ *
+ * TLS is used for https endpoints or whenever a certificate file is configured, unless
+ * Insecure is set. Without CaFile the system trust roots verify the collector.
+ *
+ * Transient export failures (e.g. gRPC Unavailable, DeadlineExceeded) are retried:
+ *
+ *   Retry.MaxRetries              OTEL_EXPORTER_OTLP_RETRY_MAX_RETRIES (default 3, 0 disables)
+ *   Retry.InitialBackoffMs        OTEL_EXPORTER_OTLP_RETRY_INITIAL_BACKOFF_MS (default 1000)
+ *   Retry.MaxBackoffMs            OTEL_EXPORTER_OTLP_RETRY_MAX_BACKOFF_MS (default 5000)
+ *   Retry.Jitter                  OTEL_EXPORTER_OTLP_RETRY_JITTER (fraction in [0, 1], default 0.2)
+ *
+ * Spans wait in a bounded queue for the next batch export. When the queue is full, e.g.
+ * during a collector outage, the overflow policy picks what is dropped; every drop is
+ * counted in telemetry.export.dropped, tagged with the signal:
+ *
+ *   ExportQueue.Overflow          OTEL_EXPORTER_OTLP_QUEUE_OVERFLOW (drop_oldest|drop_newest|block, default drop_newest)
+ *   ExportQueue.BlockTimeoutMs    OTEL_EXPORTER_OTLP_QUEUE_BLOCK_TIMEOUT_MS (default 100, at most 1000)
+ *   ExportQueue.MaxQueueSize      OTEL_BSP_MAX_QUEUE_SIZE (default 2048)
+ *   ExportQueue.MaxExportBatchSize  OTEL_BSP_MAX_EXPORT_BATCH_SIZE (default 512)
+ *   ExportQueue.ScheduledDelayMs  OTEL_BSP_SCHEDULE_DELAY (default 5000)
+ *
+ * With block, the emitting thread waits up to BlockTimeoutMs for room and then drops
+ * the item, so a stalled exporter can't hold up requests indefinitely.
+ *
+ * When tracing is enabled, each request is exported as a server span with one child
+ * span per recorded request phase. With exemplars enabled, sampled requests link their
+ * trace to the duration histogram bucket they land in (served over OpenMetrics).
+ *
  *-------------------------------------------------------------------------
  */
 
-use std::env;
+use std::{env, fs, str::FromStr, time::Duration};
 
 use opentelemetry::KeyValue;
+use opentelemetry_otlp::{
+    tonic_types::transport::{Certificate, ClientTlsConfig, Identity},
+    Compression, WithTonicConfig,
+};
 use serde::Deserialize;
 
-use crate::telemetry::metrics::{MetricsConfig, MetricsOptions};
+use crate::{
+    error::{DocumentDBError, Result},
+    telemetry::{
+        metrics::{MetricsConfig, MetricsOptions},
+        retry::RetryPolicy,
+        traces::{TracingConfig, TracingOptions},
+    },
+};
 
 // ============================================================================
 // Shared Constants
@@ -22,6 +91,16 @@ use crate::telemetry::metrics::{MetricsConfig, MetricsOptions};
 
 pub(crate) const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4317";
 pub(crate) const DEFAULT_EXPORT_TIMEOUT_MS: u64 = 10000;
+const DEFAULT_RETRY_MAX_RETRIES: u32 = 3;
+const DEFAULT_RETRY_INITIAL_BACKOFF_MS: u64 = 1000;
+const DEFAULT_RETRY_MAX_BACKOFF_MS: u64 = 5000;
+const DEFAULT_RETRY_JITTER: f64 = 0.2;
+const DEFAULT_QUEUE_OVERFLOW: QueueOverflow = QueueOverflow::DropNewest;
+const DEFAULT_QUEUE_BLOCK_TIMEOUT_MS: u64 = 100;
+const MAX_QUEUE_BLOCK_TIMEOUT_MS: u64 = 1000;
+const DEFAULT_MAX_QUEUE_SIZE: usize = 2048;
+const DEFAULT_MAX_EXPORT_BATCH_SIZE: usize = 512;
+const DEFAULT_SCHEDULED_DELAY_MS: u64 = 5000;
 const DEFAULT_SERVICE_NAME: &str = env!("CARGO_CRATE_NAME");
 const DEFAULT_SERVICE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -34,6 +113,88 @@ pub(crate) fn env_var<T: std::str::FromStr>(var: &str) -> Option<T> {
     env::var(var).ok().and_then(|v| v.parse().ok())
 }
 
+/// Payload compression applied by an OTLP/gRPC exporter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtlpCompression {
+    None,
+    Gzip,
+}
+
+impl FromStr for OtlpCompression {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "gzip" => Ok(Self::Gzip),
+            other => Err(format!("unsupported OTLP compression: {other}")),
+        }
+    }
+}
+
+/// What an export queue does with a new item when it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueOverflow {
+    /// Evicts the oldest queued item to make room.
+    DropOldest,
+    /// Discards the new item.
+    DropNewest,
+    /// Waits a bounded time for room, then discards the new item.
+    Block,
+}
+
+impl FromStr for QueueOverflow {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "drop_oldest" => Ok(Self::DropOldest),
+            "drop_newest" => Ok(Self::DropNewest),
+            "block" => Ok(Self::Block),
+            other => Err(format!("unsupported export queue overflow: {other}")),
+        }
+    }
+}
+
+/// Resolve a compression setting: JSON > each env var in order > `default`.
+pub(crate) fn resolve_compression(
+    json: Option<&str>,
+    env_vars: &[&str],
+    default: OtlpCompression,
+) -> OtlpCompression {
+    json.and_then(|v| v.parse().ok())
+        .or_else(|| env_vars.iter().find_map(|var| env_var(var)))
+        .unwrap_or(default)
+}
+
+/// Enable `compression` on a tonic exporter builder.
+pub(crate) fn with_compression<B: WithTonicConfig>(builder: B, compression: OtlpCompression) -> B {
+    match compression {
+        OtlpCompression::None => builder,
+        OtlpCompression::Gzip => builder.with_compression(Compression::Gzip),
+    }
+}
+
+/// Enable TLS on a tonic exporter builder when `tls` requires it for `endpoint`.
+pub(crate) fn with_tls<B: WithTonicConfig>(
+    builder: B,
+    tls: &OtlpTlsConfig,
+    endpoint: &str,
+) -> Result<B> {
+    Ok(match tls.create_client_tls_config(endpoint)? {
+        Some(tls_config) => builder.with_tls_config(tls_config),
+        None => builder,
+    })
+}
+
+fn read_tls_file(kind: &str, path: &str) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| {
+        DocumentDBError::internal_error(format!(
+            "Failed to read OTLP TLS {kind} file '{path}': {e}"
+        ))
+    })
+}
+
 /// Parse `OTEL_RESOURCE_ATTRIBUTES` into `KeyValue` pairs.
 #[cfg_attr(
     not(test),
@@ -70,6 +231,58 @@ pub struct TelemetryOptions {
     pub service_version: Option<String>,
     /// Metrics configuration
     pub metrics: Option<MetricsOptions>,
+    /// Tracing configuration
+    pub tracing: Option<TracingOptions>,
+    /// TLS configuration shared by all OTLP exporters
+    pub tls: Option<TlsOptions>,
+    /// Retry policy shared by all OTLP exporters
+    pub retry: Option<RetryOptions>,
+    /// Export queue shared by all batched signals
+    pub export_queue: Option<ExportQueueOptions>,
+}
+
+/// JSON configuration for OTLP transport security (matches SetupConfiguration.json TelemetryOptions.Tls)
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct TlsOptions {
+    /// PEM bundle used to verify the collector instead of the system roots
+    pub ca_file: Option<String>,
+    /// PEM client certificate presented for mutual TLS
+    pub client_cert_file: Option<String>,
+    /// PEM private key for `client_cert_file`
+    pub client_key_file: Option<String>,
+    /// Disables TLS for all exporters
+    pub insecure: Option<bool>,
+}
+
+/// JSON configuration for OTLP export retries (matches SetupConfiguration.json TelemetryOptions.Retry)
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct RetryOptions {
+    /// Retries after the first failed attempt; 0 disables retrying
+    pub max_retries: Option<u32>,
+    /// Backoff before the first retry, doubled on each further retry
+    pub initial_backoff_ms: Option<u64>,
+    /// Upper bound on the backoff between retries
+    pub max_backoff_ms: Option<u64>,
+    /// Fraction in `[0, 1]` by which each backoff is randomized
+    pub jitter: Option<f64>,
+}
+
+/// JSON configuration for batched export queues (matches SetupConfiguration.json TelemetryOptions.ExportQueue)
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct ExportQueueOptions {
+    /// What happens to new items when the queue is full: `"drop_oldest"`, `"drop_newest"` or `"block"`
+    pub overflow: Option<String>,
+    /// How long `block` waits for room before dropping the item
+    pub block_timeout_ms: Option<u64>,
+    /// Items held while waiting for export
+    pub max_queue_size: Option<usize>,
+    /// Items sent in one export
+    pub max_export_batch_size: Option<usize>,
+    /// Delay between exports of a partial batch
+    pub scheduled_delay_ms: Option<u64>,
 }
 
 // ============================================================================
@@ -82,6 +295,10 @@ pub struct TelemetryConfig {
     service_name: Option<String>,
     service_version: Option<String>,
     metrics: MetricsConfig,
+    tracing: TracingConfig,
+    tls: OtlpTlsConfig,
+    retry: OtlpRetryConfig,
+    export_queue: ExportQueueConfig,
 }
 
 impl TelemetryConfig {
@@ -93,6 +310,10 @@ impl TelemetryConfig {
             service_name: json.service_name,
             service_version: json.service_version,
             metrics: MetricsConfig::new(json.metrics.as_ref()),
+            tracing: TracingConfig::new(json.tracing.as_ref()),
+            tls: OtlpTlsConfig::new(json.tls.as_ref()),
+            retry: OtlpRetryConfig::new(json.retry.as_ref()),
+            export_queue: ExportQueueConfig::new(json.export_queue.as_ref()),
         }
     }
 
@@ -117,10 +338,286 @@ impl TelemetryConfig {
         &self.metrics
     }
 
+    #[must_use]
+    pub const fn tracing(&self) -> &TracingConfig {
+        &self.tracing
+    }
+
+    #[must_use]
+    pub const fn tls(&self) -> &OtlpTlsConfig {
+        &self.tls
+    }
+
+    #[must_use]
+    pub const fn retry(&self) -> &OtlpRetryConfig {
+        &self.retry
+    }
+
+    #[must_use]
+    pub const fn export_queue(&self) -> &ExportQueueConfig {
+        &self.export_queue
+    }
+
     /// Returns true if any telemetry signal is enabled.
     #[must_use]
     pub fn any_signal_enabled(&self) -> bool {
-        self.metrics.metrics_enabled()
+        self.metrics.metrics_enabled() || self.tracing.tracing_enabled()
+    }
+
+    /// The same configuration, with every OTLP exporter pointed at `endpoint`.
+    #[must_use]
+    pub fn with_otlp_endpoint(mut self, endpoint: &str) -> Self {
+        self.metrics = self.metrics.with_otlp_endpoint(endpoint);
+        self.tracing = self.tracing.with_otlp_endpoint(endpoint);
+        self
+    }
+}
+
+/// Runtime TLS configuration for OTLP exporters. Fallback: JSON > environment variable > default.
+#[derive(Debug, Clone)]
+pub struct OtlpTlsConfig {
+    ca_file: Option<String>,
+    client_cert_file: Option<String>,
+    client_key_file: Option<String>,
+    insecure: Option<bool>,
+}
+
+impl OtlpTlsConfig {
+    #[must_use]
+    pub fn new(json_config: Option<&TlsOptions>) -> Self {
+        let json = json_config.cloned().unwrap_or_default();
+
+        Self {
+            ca_file: json.ca_file,
+            client_cert_file: json.client_cert_file,
+            client_key_file: json.client_key_file,
+            insecure: json.insecure,
+        }
+    }
+
+    /// CA bundle path. Fallback: JSON > `OTEL_EXPORTER_OTLP_CERTIFICATE`.
+    #[must_use]
+    pub fn ca_file(&self) -> Option<String> {
+        self.ca_file
+            .clone()
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_CERTIFICATE"))
+    }
+
+    /// Client certificate path. Fallback: JSON > `OTEL_EXPORTER_OTLP_CLIENT_CERTIFICATE`.
+    #[must_use]
+    pub fn client_cert_file(&self) -> Option<String> {
+        self.client_cert_file
+            .clone()
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_CLIENT_CERTIFICATE"))
+    }
+
+    /// Client key path. Fallback: JSON > `OTEL_EXPORTER_OTLP_CLIENT_KEY`.
+    #[must_use]
+    pub fn client_key_file(&self) -> Option<String> {
+        self.client_key_file
+            .clone()
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_CLIENT_KEY"))
+    }
+
+    /// Whether TLS is disabled. Fallback: JSON > `OTEL_EXPORTER_OTLP_INSECURE` > false.
+    #[must_use]
+    pub fn insecure(&self) -> bool {
+        self.insecure
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_INSECURE"))
+            .unwrap_or(false)
+    }
+
+    /// Builds the tonic TLS configuration for `endpoint`.
+    ///
+    /// Returns `None` when `insecure` is set, or when the endpoint is not https and no
+    /// certificate files are configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a configured certificate or key file cannot be read, or if
+    /// only one of the client certificate and key is configured.
+    pub fn create_client_tls_config(&self, endpoint: &str) -> Result<Option<ClientTlsConfig>> {
+        if self.insecure() {
+            return Ok(None);
+        }
+
+        let ca_file = self.ca_file();
+        let client_identity = match (self.client_cert_file(), self.client_key_file()) {
+            (Some(cert), Some(key)) => Some(Identity::from_pem(
+                read_tls_file("client certificate", &cert)?,
+                read_tls_file("client key", &key)?,
+            )),
+            (None, None) => None,
+            _ => {
+                return Err(DocumentDBError::internal_error(
+                    "OTLP TLS client certificate and client key must be configured together"
+                        .to_owned(),
+                ))
+            }
+        };
+
+        let is_https = endpoint
+            .get(..8)
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case("https://"));
+        if !is_https && ca_file.is_none() && client_identity.is_none() {
+            return Ok(None);
+        }
+
+        let mut tls_config = match ca_file {
+            Some(path) => ClientTlsConfig::new()
+                .ca_certificate(Certificate::from_pem(read_tls_file("CA", &path)?)),
+            None => ClientTlsConfig::new().with_native_roots(),
+        };
+        if let Some(identity) = client_identity {
+            tls_config = tls_config.identity(identity);
+        }
+
+        Ok(Some(tls_config))
+    }
+}
+
+/// Runtime retry configuration for OTLP exporters. Fallback: JSON > environment variable > default.
+#[derive(Debug, Clone)]
+pub struct OtlpRetryConfig {
+    max_retries: Option<u32>,
+    initial_backoff_ms: Option<u64>,
+    max_backoff_ms: Option<u64>,
+    jitter: Option<f64>,
+}
+
+impl OtlpRetryConfig {
+    #[must_use]
+    pub fn new(json_config: Option<&RetryOptions>) -> Self {
+        let json = json_config.cloned().unwrap_or_default();
+
+        Self {
+            max_retries: json.max_retries,
+            initial_backoff_ms: json.initial_backoff_ms,
+            max_backoff_ms: json.max_backoff_ms,
+            jitter: json.jitter,
+        }
+    }
+
+    /// Retries per batch. Fallback: JSON > `OTEL_EXPORTER_OTLP_RETRY_MAX_RETRIES` > 3.
+    #[must_use]
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_RETRY_MAX_RETRIES"))
+            .unwrap_or(DEFAULT_RETRY_MAX_RETRIES)
+    }
+
+    /// Initial backoff in ms. Fallback: JSON > `OTEL_EXPORTER_OTLP_RETRY_INITIAL_BACKOFF_MS` > 1000.
+    #[must_use]
+    pub fn initial_backoff_ms(&self) -> u64 {
+        self.initial_backoff_ms
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_RETRY_INITIAL_BACKOFF_MS"))
+            .unwrap_or(DEFAULT_RETRY_INITIAL_BACKOFF_MS)
+    }
+
+    /// Maximum backoff in ms, never below the initial backoff.
+    /// Fallback: JSON > `OTEL_EXPORTER_OTLP_RETRY_MAX_BACKOFF_MS` > 5000.
+    #[must_use]
+    pub fn max_backoff_ms(&self) -> u64 {
+        self.max_backoff_ms
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_RETRY_MAX_BACKOFF_MS"))
+            .unwrap_or(DEFAULT_RETRY_MAX_BACKOFF_MS)
+            .max(self.initial_backoff_ms())
+    }
+
+    /// Backoff jitter fraction; values outside `[0, 1]` fall through to the next source.
+    /// Fallback: JSON > `OTEL_EXPORTER_OTLP_RETRY_JITTER` > 0.2.
+    #[must_use]
+    pub fn jitter(&self) -> f64 {
+        let valid = |jitter: &f64| (0.0..=1.0).contains(jitter);
+        self.jitter
+            .filter(valid)
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_RETRY_JITTER").filter(valid))
+            .unwrap_or(DEFAULT_RETRY_JITTER)
+    }
+
+    #[must_use]
+    pub fn policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.max_retries(),
+            initial_backoff: Duration::from_millis(self.initial_backoff_ms()),
+            max_backoff: Duration::from_millis(self.max_backoff_ms()),
+            jitter: self.jitter(),
+        }
+    }
+}
+
+/// Runtime configuration for batched export queues. Fallback: JSON > environment variable > default.
+#[derive(Debug, Clone)]
+pub struct ExportQueueConfig {
+    overflow: Option<String>,
+    block_timeout_ms: Option<u64>,
+    max_queue_size: Option<usize>,
+    max_export_batch_size: Option<usize>,
+    scheduled_delay_ms: Option<u64>,
+}
+
+impl ExportQueueConfig {
+    #[must_use]
+    pub fn new(json_config: Option<&ExportQueueOptions>) -> Self {
+        let json = json_config.cloned().unwrap_or_default();
+
+        Self {
+            overflow: json.overflow,
+            block_timeout_ms: json.block_timeout_ms,
+            max_queue_size: json.max_queue_size,
+            max_export_batch_size: json.max_export_batch_size,
+            scheduled_delay_ms: json.scheduled_delay_ms,
+        }
+    }
+
+    /// Overflow policy; unrecognized values fall through to the next source.
+    /// Fallback: JSON > `OTEL_EXPORTER_OTLP_QUEUE_OVERFLOW` > `drop_newest`.
+    #[must_use]
+    pub fn overflow(&self) -> QueueOverflow {
+        self.overflow
+            .as_deref()
+            .and_then(|v| v.parse().ok())
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_QUEUE_OVERFLOW"))
+            .unwrap_or(DEFAULT_QUEUE_OVERFLOW)
+    }
+
+    /// Longest wait for room under `block`, in ms, capped at 1000 so a stalled exporter
+    /// can't stall the request path. Fallback: JSON > `OTEL_EXPORTER_OTLP_QUEUE_BLOCK_TIMEOUT_MS` > 100.
+    #[must_use]
+    pub fn block_timeout_ms(&self) -> u64 {
+        self.block_timeout_ms
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_QUEUE_BLOCK_TIMEOUT_MS"))
+            .unwrap_or(DEFAULT_QUEUE_BLOCK_TIMEOUT_MS)
+            .min(MAX_QUEUE_BLOCK_TIMEOUT_MS)
+    }
+
+    /// Queue capacity; 0 falls through to the next source.
+    /// Fallback: JSON > `OTEL_BSP_MAX_QUEUE_SIZE` > 2048.
+    #[must_use]
+    pub fn max_queue_size(&self) -> usize {
+        self.max_queue_size
+            .filter(|size| *size > 0)
+            .or_else(|| env_var("OTEL_BSP_MAX_QUEUE_SIZE").filter(|size| *size > 0))
+            .unwrap_or(DEFAULT_MAX_QUEUE_SIZE)
+    }
+
+    /// Batch size, never above the queue size; 0 falls through to the next source.
+    /// Fallback: JSON > `OTEL_BSP_MAX_EXPORT_BATCH_SIZE` > 512.
+    #[must_use]
+    pub fn max_export_batch_size(&self) -> usize {
+        self.max_export_batch_size
+            .filter(|size| *size > 0)
+            .or_else(|| env_var("OTEL_BSP_MAX_EXPORT_BATCH_SIZE").filter(|size| *size > 0))
+            .unwrap_or(DEFAULT_MAX_EXPORT_BATCH_SIZE)
+            .min(self.max_queue_size())
+    }
+
+    /// Delay between exports in ms. Fallback: JSON > `OTEL_BSP_SCHEDULE_DELAY` > 5000.
+    #[must_use]
+    pub fn scheduled_delay_ms(&self) -> u64 {
+        self.scheduled_delay_ms
+            .or_else(|| env_var("OTEL_BSP_SCHEDULE_DELAY"))
+            .unwrap_or(DEFAULT_SCHEDULED_DELAY_MS)
     }
 }
 
@@ -136,6 +633,32 @@ mod tests {
         assert_eq!(env_var::<u64>("TEST_MISSING"), None);
     }
 
+    #[test]
+    fn test_otlp_compression_parses_known_values() {
+        assert_eq!("gzip".parse(), Ok(OtlpCompression::Gzip));
+        assert_eq!(" GZIP ".parse(), Ok(OtlpCompression::Gzip));
+        assert_eq!("none".parse(), Ok(OtlpCompression::None));
+        "zstd".parse::<OtlpCompression>().unwrap_err();
+    }
+
+    #[test]
+    fn test_resolve_compression_skips_invalid_values() {
+        let _guard = EnvGuard::set_many([
+            ("TEST_SIGNAL_COMPRESSION", "brotli"),
+            ("TEST_GENERIC_COMPRESSION", "gzip"),
+        ]);
+        let env_vars = ["TEST_SIGNAL_COMPRESSION", "TEST_GENERIC_COMPRESSION"];
+
+        assert_eq!(
+            resolve_compression(Some("snappy"), &env_vars, OtlpCompression::None),
+            OtlpCompression::Gzip
+        );
+        assert_eq!(
+            resolve_compression(None, &[], OtlpCompression::None),
+            OtlpCompression::None
+        );
+    }
+
     #[test]
     fn test_parse_resource_attributes() {
         let _guard = EnvGuard::set("OTEL_RESOURCE_ATTRIBUTES", "key1=val1,key2=val2");
@@ -190,6 +713,10 @@ mod tests {
                 enabled: Some(false),
                 ..Default::default()
             }),
+            tracing: None,
+            tls: None,
+            retry: None,
+            export_queue: None,
         };
         let config = TelemetryConfig::new(Some(&json_config));
         assert_eq!(config.service_name(), "json-service");
@@ -210,4 +737,248 @@ mod tests {
         assert_eq!(config.service_name(), "partial-service");
         assert_eq!(config.metrics().export_interval_ms(), 90000);
     }
+
+    #[test]
+    fn test_any_signal_enabled_with_tracing_only() {
+        let _guard = EnvGuard::remove("OTEL_METRICS_ENABLED");
+        let json_config = TelemetryOptions {
+            tracing: Some(TracingOptions {
+                enabled: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let config = TelemetryConfig::new(Some(&json_config));
+        assert!(!config.metrics().metrics_enabled());
+        assert!(config.any_signal_enabled());
+    }
+
+    fn write_temp_pem(name: &str) -> String {
+        let path = env::temp_dir().join(format!("{}-{name}", uuid::Uuid::new_v4()));
+        fs::write(
+            &path,
+            format!("-----BEGIN {name}-----\n-----END {name}-----\n"),
+        )
+        .unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn tls_options(
+        ca_file: Option<String>,
+        cert: Option<String>,
+        key: Option<String>,
+    ) -> TlsOptions {
+        TlsOptions {
+            ca_file,
+            client_cert_file: cert,
+            client_key_file: key,
+            insecure: Some(false),
+        }
+    }
+
+    #[test]
+    fn test_tls_config_built_from_provided_paths() {
+        let ca = write_temp_pem("CA");
+        let cert = write_temp_pem("CERT");
+        let key = write_temp_pem("KEY");
+        let json = tls_options(Some(ca.clone()), Some(cert.clone()), Some(key.clone()));
+        let config = OtlpTlsConfig::new(Some(&json));
+
+        assert_eq!(config.ca_file().as_deref(), Some(ca.as_str()));
+        assert_eq!(config.client_cert_file().as_deref(), Some(cert.as_str()));
+        assert_eq!(config.client_key_file().as_deref(), Some(key.as_str()));
+        assert!(config
+            .create_client_tls_config("http://collector:4317")
+            .unwrap()
+            .is_some());
+
+        for path in [ca, cert, key] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_tls_config_fails_on_unreadable_file() {
+        let json = tls_options(Some("/nonexistent/otlp-ca.pem".to_owned()), None, None);
+        let err = OtlpTlsConfig::new(Some(&json))
+            .create_client_tls_config("https://collector:4317")
+            .unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/otlp-ca.pem"));
+    }
+
+    #[test]
+    fn test_tls_config_requires_cert_and_key_together() {
+        let cert = write_temp_pem("CERT");
+        let json = tls_options(None, Some(cert.clone()), None);
+        OtlpTlsConfig::new(Some(&json))
+            .create_client_tls_config("https://collector:4317")
+            .unwrap_err();
+        fs::remove_file(cert).unwrap();
+    }
+
+    #[test]
+    fn test_tls_config_skipped_for_plaintext_and_insecure() {
+        let _guard = EnvGuard::remove_many([
+            "OTEL_EXPORTER_OTLP_CERTIFICATE",
+            "OTEL_EXPORTER_OTLP_CLIENT_CERTIFICATE",
+            "OTEL_EXPORTER_OTLP_CLIENT_KEY",
+            "OTEL_EXPORTER_OTLP_INSECURE",
+        ]);
+        let config = OtlpTlsConfig::new(None);
+        assert!(config
+            .create_client_tls_config("http://localhost:4317")
+            .unwrap()
+            .is_none());
+        assert!(config
+            .create_client_tls_config("https://collector:4317")
+            .unwrap()
+            .is_some());
+
+        let insecure = OtlpTlsConfig::new(Some(&TlsOptions {
+            ca_file: Some("/nonexistent/otlp-ca.pem".to_owned()),
+            insecure: Some(true),
+            ..Default::default()
+        }));
+        assert!(insecure
+            .create_client_tls_config("https://collector:4317")
+            .unwrap()
+            .is_none());
+    }
+
+    const RETRY_ENV_VARS: [&str; 4] = [
+        "OTEL_EXPORTER_OTLP_RETRY_MAX_RETRIES",
+        "OTEL_EXPORTER_OTLP_RETRY_INITIAL_BACKOFF_MS",
+        "OTEL_EXPORTER_OTLP_RETRY_MAX_BACKOFF_MS",
+        "OTEL_EXPORTER_OTLP_RETRY_JITTER",
+    ];
+
+    #[test]
+    fn test_retry_config_defaults() {
+        let _guard = EnvGuard::remove_many(RETRY_ENV_VARS);
+        let policy = OtlpRetryConfig::new(None).policy();
+
+        assert_eq!(policy.max_retries, 3);
+        assert_eq!(policy.initial_backoff, Duration::from_millis(1000));
+        assert_eq!(policy.max_backoff, Duration::from_millis(5000));
+        assert!((policy.jitter - 0.2).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_retry_config_json_overrides_env() {
+        let _guard = EnvGuard::set_many(RETRY_ENV_VARS.into_iter().zip(["7", "50", "800", "0.5"]));
+
+        let config = OtlpRetryConfig::new(None);
+        assert_eq!(config.max_retries(), 7);
+        assert_eq!(config.initial_backoff_ms(), 50);
+        assert_eq!(config.max_backoff_ms(), 800);
+        assert!((config.jitter() - 0.5).abs() < f64::EPSILON);
+
+        let json_config = RetryOptions {
+            max_retries: Some(0),
+            initial_backoff_ms: Some(10),
+            max_backoff_ms: Some(20),
+            jitter: Some(0.0),
+        };
+        let policy = OtlpRetryConfig::new(Some(&json_config)).policy();
+        assert_eq!(policy.max_retries, 0);
+        assert_eq!(policy.initial_backoff, Duration::from_millis(10));
+        assert_eq!(policy.max_backoff, Duration::from_millis(20));
+        assert!(policy.jitter.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_retry_config_rejects_invalid_values() {
+        let _guard = EnvGuard::set_many([
+            ("OTEL_EXPORTER_OTLP_RETRY_MAX_RETRIES", "-1"),
+            ("OTEL_EXPORTER_OTLP_RETRY_INITIAL_BACKOFF_MS", "2000"),
+            ("OTEL_EXPORTER_OTLP_RETRY_MAX_BACKOFF_MS", "500"),
+            ("OTEL_EXPORTER_OTLP_RETRY_JITTER", "0.3"),
+        ]);
+        let json_config = RetryOptions {
+            jitter: Some(1.5),
+            ..Default::default()
+        };
+        let config = OtlpRetryConfig::new(Some(&json_config));
+
+        assert_eq!(config.max_retries(), 3);
+        // The maximum never undercuts the initial backoff.
+        assert_eq!(config.max_backoff_ms(), 2000);
+        assert!((config.jitter() - 0.3).abs() < f64::EPSILON);
+    }
+
+    const EXPORT_QUEUE_ENV_VARS: [&str; 5] = [
+        "OTEL_EXPORTER_OTLP_QUEUE_OVERFLOW",
+        "OTEL_EXPORTER_OTLP_QUEUE_BLOCK_TIMEOUT_MS",
+        "OTEL_BSP_MAX_QUEUE_SIZE",
+        "OTEL_BSP_MAX_EXPORT_BATCH_SIZE",
+        "OTEL_BSP_SCHEDULE_DELAY",
+    ];
+
+    #[test]
+    fn test_queue_overflow_parses_each_mode() {
+        assert_eq!("drop_oldest".parse(), Ok(QueueOverflow::DropOldest));
+        assert_eq!("drop_newest".parse(), Ok(QueueOverflow::DropNewest));
+        assert_eq!(" BLOCK ".parse(), Ok(QueueOverflow::Block));
+        "drop".parse::<QueueOverflow>().unwrap_err();
+    }
+
+    #[test]
+    fn test_export_queue_config_defaults() {
+        let _guard = EnvGuard::remove_many(EXPORT_QUEUE_ENV_VARS);
+        let config = ExportQueueConfig::new(None);
+
+        assert_eq!(config.overflow(), QueueOverflow::DropNewest);
+        assert_eq!(config.block_timeout_ms(), 100);
+        assert_eq!(config.max_queue_size(), 2048);
+        assert_eq!(config.max_export_batch_size(), 512);
+        assert_eq!(config.scheduled_delay_ms(), 5000);
+    }
+
+    #[test]
+    fn test_export_queue_config_json_overrides_env() {
+        let _guard = EnvGuard::set_many(EXPORT_QUEUE_ENV_VARS.into_iter().zip([
+            "drop_oldest",
+            "20",
+            "64",
+            "16",
+            "250",
+        ]));
+
+        let config = ExportQueueConfig::new(None);
+        assert_eq!(config.overflow(), QueueOverflow::DropOldest);
+        assert_eq!(config.block_timeout_ms(), 20);
+        assert_eq!(config.max_queue_size(), 64);
+        assert_eq!(config.max_export_batch_size(), 16);
+        assert_eq!(config.scheduled_delay_ms(), 250);
+
+        let config = ExportQueueConfig::new(Some(&ExportQueueOptions {
+            overflow: Some("block".to_owned()),
+            block_timeout_ms: Some(50),
+            ..Default::default()
+        }));
+        assert_eq!(config.overflow(), QueueOverflow::Block);
+        assert_eq!(config.block_timeout_ms(), 50);
+        assert_eq!(config.max_queue_size(), 64);
+    }
+
+    #[test]
+    fn test_export_queue_config_rejects_invalid_values() {
+        let _guard = EnvGuard::set_many([
+            ("OTEL_EXPORTER_OTLP_QUEUE_OVERFLOW", "drop_newest"),
+            ("OTEL_EXPORTER_OTLP_QUEUE_BLOCK_TIMEOUT_MS", "60000"),
+            ("OTEL_BSP_MAX_QUEUE_SIZE", "0"),
+            ("OTEL_BSP_MAX_EXPORT_BATCH_SIZE", "4096"),
+        ]);
+        let config = ExportQueueConfig::new(Some(&ExportQueueOptions {
+            overflow: Some("drop_everything".to_owned()),
+            ..Default::default()
+        }));
+
+        assert_eq!(config.overflow(), QueueOverflow::DropNewest);
+        // Blocking is always bounded.
+        assert_eq!(config.block_timeout_ms(), 1000);
+        assert_eq!(config.max_queue_size(), 2048);
+        // A batch never exceeds the queue.
+        assert_eq!(config.max_export_batch_size(), 2048);
+    }
 }
@@ -13,6 +13,9 @@ pub enum EventId {
     Probe = 2000,
     RequestTrace = 2001,
     ConnectionPool = 2002,
+    ConnectionClose = 2003,
+    SlowOperation = 2004,
+    UnsupportedCommand = 2005,
     // Values 2101 to 2199 are reserved for different types of user request failures.
     RequestFailure = 2101,
 }
@@ -0,0 +1,189 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * src/telemetry/exemplars.rs
+ *
+ * Exemplars linking the request duration histogram to sampled traces.
+ * The OpenTelemetry SDK does not record exemplars yet, so the gateway keeps
+ * the most recent sampled trace for each histogram bucket itself and serves
+ * them from the Prometheus scrape endpoint.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        LazyLock, Mutex,
+    },
+};
+
+use opentelemetry::{
+    trace::{SpanContext, SpanId, TraceId},
+    KeyValue,
+};
+
+/// Name of the histogram that exemplars are recorded for.
+pub(crate) const OPERATION_DURATION_METRIC: &str = "db.client.operation.duration";
+
+/// Bucket boundaries, in seconds, of the request duration histogram.
+pub(crate) const OPERATION_DURATION_BOUNDARIES: [f64; 14] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0, 10.0,
+];
+
+static EXEMPLARS_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Latest sampled exemplar per bucket of the request duration histogram.
+pub(crate) static OPERATION_DURATION_EXEMPLARS: LazyLock<ExemplarReservoir> =
+    LazyLock::new(|| ExemplarReservoir::new(&OPERATION_DURATION_BOUNDARIES));
+
+/// Returns true if sampled requests attach exemplars to the duration histogram.
+#[must_use]
+pub fn exemplars_enabled() -> bool {
+    EXEMPLARS_ENABLED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_exemplars_enabled(enabled: bool) {
+    EXEMPLARS_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// A recorded measurement together with the trace that produced it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Exemplar {
+    pub value: f64,
+    pub trace_id: TraceId,
+    pub span_id: SpanId,
+}
+
+impl Exemplar {
+    /// Returns an exemplar for `value` if `span_context` belongs to a sampled trace.
+    #[must_use]
+    pub fn from_sampled(span_context: &SpanContext, value: f64) -> Option<Self> {
+        (span_context.is_valid() && span_context.is_sampled()).then(|| Self {
+            value,
+            trace_id: span_context.trace_id(),
+            span_id: span_context.span_id(),
+        })
+    }
+}
+
+/// Keeps the most recent exemplar for each bucket of each attribute set of a histogram.
+#[derive(Debug)]
+pub struct ExemplarReservoir {
+    boundaries: &'static [f64],
+    series: Mutex<HashMap<String, Vec<Option<Exemplar>>>>,
+}
+
+impl ExemplarReservoir {
+    #[must_use]
+    pub fn new(boundaries: &'static [f64]) -> Self {
+        Self {
+            boundaries,
+            series: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Stores `exemplar` as the latest one for the bucket its value falls in.
+    pub fn offer(&self, attributes: &[KeyValue], exemplar: Exemplar) {
+        let bucket = self.bucket_index(exemplar.value);
+        if let Ok(mut series) = self.series.lock() {
+            let buckets = series
+                .entry(series_key(attributes.iter()))
+                .or_insert_with(|| vec![None; self.boundaries.len() + 1]);
+            buckets[bucket] = Some(exemplar);
+        }
+    }
+
+    /// Returns the latest exemplar of `bucket`, where the last bucket is `+Inf`.
+    pub fn get<'a>(
+        &self,
+        attributes: impl Iterator<Item = &'a KeyValue>,
+        bucket: usize,
+    ) -> Option<Exemplar> {
+        let series = self.series.lock().ok()?;
+        series
+            .get(&series_key(attributes))
+            .and_then(|buckets| buckets.get(bucket).copied().flatten())
+    }
+
+    /// Histogram buckets are upper-inclusive, matching the SDK aggregation.
+    fn bucket_index(&self, value: f64) -> usize {
+        self.boundaries
+            .iter()
+            .position(|&bound| value <= bound)
+            .unwrap_or(self.boundaries.len())
+    }
+}
+
+/// Identifies an attribute set independently of attribute order.
+fn series_key<'a>(attributes: impl Iterator<Item = &'a KeyValue>) -> String {
+    let mut pairs: Vec<String> = attributes
+        .map(|kv| format!("{}={}", kv.key, kv.value))
+        .collect();
+    pairs.sort_unstable();
+    pairs.join("\u{1f}")
+}
+
+/// Returns the exemplar recorded for a bucket of `metric_name`, if the metric keeps exemplars.
+pub(crate) fn exemplar_for<'a>(
+    metric_name: &str,
+    attributes: impl Iterator<Item = &'a KeyValue>,
+    bucket: usize,
+) -> Option<Exemplar> {
+    (metric_name == OPERATION_DURATION_METRIC)
+        .then(|| OPERATION_DURATION_EXEMPLARS.get(attributes, bucket))
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::{TraceFlags, TraceState};
+
+    use super::*;
+
+    fn span_context(flags: TraceFlags) -> SpanContext {
+        SpanContext::new(
+            TraceId::from(0x0af7_6519_16cd_43dd_8448_eb21_1c80_319c),
+            SpanId::from(0x00f0_67aa_0ba9_02b7),
+            flags,
+            false,
+            TraceState::default(),
+        )
+    }
+
+    #[test]
+    fn only_sampled_spans_produce_exemplars() {
+        let exemplar = Exemplar::from_sampled(&span_context(TraceFlags::SAMPLED), 0.2).unwrap();
+        assert_eq!(
+            exemplar.trace_id.to_string(),
+            "0af7651916cd43dd8448eb211c80319c"
+        );
+        assert_eq!(exemplar.span_id.to_string(), "00f067aa0ba902b7");
+
+        assert!(Exemplar::from_sampled(&span_context(TraceFlags::default()), 0.2).is_none());
+        assert!(Exemplar::from_sampled(&SpanContext::empty_context(), 0.2).is_none());
+    }
+
+    #[test]
+    fn reservoir_keeps_latest_exemplar_per_bucket() {
+        static BOUNDARIES: [f64; 2] = [0.1, 1.0];
+        let reservoir = ExemplarReservoir::new(&BOUNDARIES);
+        let attrs = [
+            KeyValue::new("db.operation.name", "find"),
+            KeyValue::new("db.collection.name", "c"),
+        ];
+        let reordered = [attrs[1].clone(), attrs[0].clone()];
+        let exemplar =
+            |value| Exemplar::from_sampled(&span_context(TraceFlags::SAMPLED), value).unwrap();
+
+        reservoir.offer(&attrs, exemplar(0.05));
+        reservoir.offer(&attrs, exemplar(0.1));
+        reservoir.offer(&attrs, exemplar(30.0));
+
+        assert_eq!(reservoir.get(reordered.iter(), 0), Some(exemplar(0.1)));
+        assert_eq!(reservoir.get(attrs.iter(), 1), None);
+        assert_eq!(reservoir.get(attrs.iter(), 2), Some(exemplar(30.0)));
+        assert_eq!(reservoir.get(attrs[..1].iter(), 0), None);
+    }
+}
@@ -0,0 +1,425 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * src/telemetry/export_queue.rs
+ *
+ * Bounded queue between the threads emitting telemetry and the thread
+ * exporting it in batches, with a configurable policy for when it is full.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Condvar, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use opentelemetry::Context;
+use opentelemetry_sdk::{
+    error::{OTelSdkError, OTelSdkResult},
+    trace::{Span, SpanData, SpanExporter, SpanProcessor},
+    Resource,
+};
+
+use crate::{
+    error::{DocumentDBError, Result},
+    telemetry::{
+        config::{ExportQueueConfig, QueueOverflow},
+        metrics,
+    },
+};
+
+/// How long `force_flush` waits for the queued items to be exported.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+struct QueueState<T> {
+    items: VecDeque<T>,
+    /// Notified once everything queued before the request has been exported.
+    flush_requests: Vec<mpsc::Sender<()>>,
+    closed: bool,
+}
+
+/// The items taken for one export, and what to do once they are exported.
+#[derive(Debug)]
+struct Batch<T> {
+    items: Vec<T>,
+    flushed: Vec<mpsc::Sender<()>>,
+    closed: bool,
+}
+
+/// A bounded queue of `signal` items waiting for export.
+#[derive(Debug)]
+pub struct ExportQueue<T> {
+    signal: &'static str,
+    overflow: QueueOverflow,
+    block_timeout: Duration,
+    capacity: usize,
+    batch_size: usize,
+    state: Mutex<QueueState<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    dropped: AtomicU64,
+}
+
+impl<T> ExportQueue<T> {
+    #[must_use]
+    pub fn new(signal: &'static str, config: &ExportQueueConfig) -> Self {
+        Self {
+            signal,
+            overflow: config.overflow(),
+            block_timeout: Duration::from_millis(config.block_timeout_ms()),
+            capacity: config.max_queue_size(),
+            batch_size: config.max_export_batch_size(),
+            state: Mutex::new(QueueState {
+                items: VecDeque::new(),
+                flush_requests: Vec::new(),
+                closed: false,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Items dropped because the queue was full, over the life of the queue.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn record_drop(&self) {
+        if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+            tracing::warn!(
+                "OTLP {} export queue is full, dropping items with policy {:?}; \
+                 further drops are only counted in telemetry.export.dropped",
+                self.signal,
+                self.overflow
+            );
+        }
+        metrics::record_export_dropped(self.signal, 1);
+    }
+
+    /// Queues `item`, applying the overflow policy if the queue is full. Under `Block`
+    /// this waits at most the configured timeout.
+    pub fn push(&self, item: T) {
+        let mut state = self.lock();
+        if state.closed {
+            return;
+        }
+
+        if state.items.len() >= self.capacity {
+            match self.overflow {
+                QueueOverflow::DropOldest => {
+                    state.items.pop_front();
+                    self.record_drop();
+                }
+                QueueOverflow::DropNewest => {
+                    drop(state);
+                    self.record_drop();
+                    return;
+                }
+                QueueOverflow::Block => {
+                    state = self
+                        .not_full
+                        .wait_timeout_while(state, self.block_timeout, |state| {
+                            state.items.len() >= self.capacity && !state.closed
+                        })
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0;
+                    if state.closed {
+                        return;
+                    }
+                    if state.items.len() >= self.capacity {
+                        drop(state);
+                        self.record_drop();
+                        return;
+                    }
+                }
+            }
+        }
+
+        state.items.push_back(item);
+        let batch_ready = state.items.len() >= self.batch_size;
+        drop(state);
+        if batch_ready {
+            self.not_empty.notify_one();
+        }
+    }
+
+    /// Waits up to `delay` for a full batch, a flush or shutdown, then takes up to one batch.
+    fn next_batch(&self, delay: Duration) -> Batch<T> {
+        let deadline = Instant::now() + delay;
+        let mut state = self.lock();
+        loop {
+            if state.items.len() >= self.batch_size
+                || !state.flush_requests.is_empty()
+                || state.closed
+            {
+                break;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            state = self
+                .not_empty
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+
+        let count = state.items.len().min(self.batch_size);
+        let items: Vec<T> = state.items.drain(..count).collect();
+        let drained = state.items.is_empty();
+        let batch = Batch {
+            items,
+            flushed: if drained {
+                std::mem::take(&mut state.flush_requests)
+            } else {
+                Vec::new()
+            },
+            closed: drained && state.closed,
+        };
+        drop(state);
+        self.not_full.notify_all();
+        batch
+    }
+
+    /// Asks for everything queued so far to be exported, notifying `done` afterwards.
+    /// Returns false if the queue is closed.
+    fn request_flush(&self, done: mpsc::Sender<()>) -> bool {
+        let mut state = self.lock();
+        if state.closed {
+            return false;
+        }
+        state.flush_requests.push(done);
+        drop(state);
+        self.not_empty.notify_one();
+        true
+    }
+
+    /// Stops accepting items and asks for the remaining ones to be exported, notifying
+    /// `done` afterwards. Returns false if the queue was already closed.
+    fn close(&self, done: mpsc::Sender<()>) -> bool {
+        let mut state = self.lock();
+        if state.closed {
+            return false;
+        }
+        state.closed = true;
+        state.flush_requests.push(done);
+        drop(state);
+        self.not_empty.notify_one();
+        self.not_full.notify_all();
+        true
+    }
+}
+
+/// Exports finished spans in batches from a dedicated thread, through an [`ExportQueue`].
+///
+/// Stands in for the SDK's `BatchSpanProcessor`, whose queue always drops new spans
+/// when full and only reports how many at shutdown.
+#[derive(Debug)]
+pub struct QueuedSpanProcessor<E> {
+    queue: Arc<ExportQueue<SpanData>>,
+    exporter: Arc<Mutex<E>>,
+}
+
+impl<E: SpanExporter + 'static> QueuedSpanProcessor<E> {
+    /// # Errors
+    ///
+    /// Returns an error if the export thread cannot be started.
+    pub fn new(exporter: E, config: &ExportQueueConfig) -> Result<Self> {
+        let queue = Arc::new(ExportQueue::new("traces", config));
+        let exporter = Arc::new(Mutex::new(exporter));
+        let delay = Duration::from_millis(config.scheduled_delay_ms());
+
+        let worker_queue = Arc::clone(&queue);
+        let worker_exporter = Arc::clone(&exporter);
+        thread::Builder::new()
+            .name("otlp-span-export".to_owned())
+            .spawn(move || export_loop(&worker_queue, &worker_exporter, delay))
+            .map_err(|e| {
+                DocumentDBError::internal_error(format!("Failed to start span export thread: {e}"))
+            })?;
+
+        Ok(Self { queue, exporter })
+    }
+}
+
+fn export_loop<E: SpanExporter>(
+    queue: &ExportQueue<SpanData>,
+    exporter: &Mutex<E>,
+    delay: Duration,
+) {
+    loop {
+        let batch = queue.next_batch(delay);
+        if !batch.items.is_empty() {
+            let exporter = exporter.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Err(e) = futures::executor::block_on(exporter.export(batch.items)) {
+                tracing::debug!("Failed to export OTLP {} batch: {e}", queue.signal);
+            }
+        }
+        for done in batch.flushed {
+            // The requester may have stopped waiting.
+            let _ = done.send(());
+        }
+        if batch.closed {
+            return;
+        }
+    }
+}
+
+fn export_wait_error(error: mpsc::RecvTimeoutError, timeout: Duration) -> OTelSdkError {
+    match error {
+        mpsc::RecvTimeoutError::Timeout => OTelSdkError::Timeout(timeout),
+        mpsc::RecvTimeoutError::Disconnected => {
+            OTelSdkError::InternalFailure("span export thread has stopped".to_owned())
+        }
+    }
+}
+
+impl<E: SpanExporter + 'static> SpanProcessor for QueuedSpanProcessor<E> {
+    fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+
+    fn on_end(&self, span: SpanData) {
+        self.queue.push(span);
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        let (done, flushed) = mpsc::channel();
+        if !self.queue.request_flush(done) {
+            return Err(OTelSdkError::AlreadyShutdown);
+        }
+        flushed
+            .recv_timeout(FLUSH_TIMEOUT)
+            .map_err(|e| export_wait_error(e, FLUSH_TIMEOUT))
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        let (done, drained) = mpsc::channel();
+        if !self.queue.close(done) {
+            return Err(OTelSdkError::AlreadyShutdown);
+        }
+        let dropped = self.queue.dropped();
+        if dropped > 0 {
+            tracing::warn!(
+                "OTLP {} export queue dropped {dropped} items while full",
+                self.queue.signal
+            );
+        }
+
+        // The export thread holds the exporter until the last batch is exported.
+        drained
+            .recv_timeout(timeout)
+            .map_err(|e| export_wait_error(e, timeout))?;
+        self.exporter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .shutdown_with_timeout(timeout)
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.exporter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .set_resource(resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::{Tracer, TracerProvider};
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+
+    use super::*;
+    use crate::telemetry::config::ExportQueueOptions;
+
+    fn queue(overflow: &str, capacity: usize, block_timeout_ms: u64) -> ExportQueue<u32> {
+        ExportQueue::new(
+            "test",
+            &ExportQueueConfig::new(Some(&ExportQueueOptions {
+                overflow: Some(overflow.to_owned()),
+                block_timeout_ms: Some(block_timeout_ms),
+                max_queue_size: Some(capacity),
+                max_export_batch_size: Some(capacity),
+                scheduled_delay_ms: Some(0),
+            })),
+        )
+    }
+
+    fn fill(queue: &ExportQueue<u32>, items: u32) -> Vec<u32> {
+        for item in 0..items {
+            queue.push(item);
+        }
+        queue.next_batch(Duration::ZERO).items
+    }
+
+    #[test]
+    fn drop_oldest_keeps_the_newest_items() {
+        let queue = queue("drop_oldest", 3, 20);
+        assert_eq!(fill(&queue, 5), vec![2, 3, 4]);
+        assert_eq!(queue.dropped(), 2);
+    }
+
+    #[test]
+    fn drop_newest_keeps_the_oldest_items() {
+        let queue = queue("drop_newest", 3, 20);
+        assert_eq!(fill(&queue, 5), vec![0, 1, 2]);
+        assert_eq!(queue.dropped(), 2);
+    }
+
+    #[test]
+    fn block_gives_up_after_the_timeout() {
+        let queue = queue("block", 2, 20);
+        let start = Instant::now();
+        assert_eq!(fill(&queue, 3), vec![0, 1]);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(queue.dropped(), 1);
+    }
+
+    #[test]
+    fn block_waits_for_room() {
+        let queue = Arc::new(queue("block", 1, 1000));
+        queue.push(0);
+
+        let consumer = Arc::clone(&queue);
+        let drained = thread::spawn(move || consumer.next_batch(Duration::ZERO).items);
+        queue.push(1);
+
+        assert_eq!(drained.join().unwrap(), vec![0]);
+        assert_eq!(queue.next_batch(Duration::ZERO).items, vec![1]);
+        assert_eq!(queue.dropped(), 0);
+    }
+
+    #[test]
+    fn processor_exports_spans_on_flush() {
+        let exporter = InMemorySpanExporter::default();
+        let processor =
+            QueuedSpanProcessor::new(exporter.clone(), &ExportQueueConfig::new(None)).unwrap();
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(processor)
+            .build();
+        let tracer = provider.tracer("test");
+
+        tracer.in_span("first", |_| {});
+        provider.force_flush().unwrap();
+        assert_eq!(exporter.get_finished_spans().unwrap().len(), 1);
+
+        tracer.in_span("second", |_| {});
+        provider.force_flush().unwrap();
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[1].name, "second");
+
+        provider.shutdown().unwrap();
+    }
+}
@@ -0,0 +1,158 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/telemetry/exporter_slot.rs
+ *
+ * Exporters that can be replaced while their provider keeps running, so that
+ * telemetry can be re-pointed without rebuilding instruments or tracers.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use arc_swap::ArcSwapOption;
+use opentelemetry_sdk::{
+    error::{OTelSdkError, OTelSdkResult},
+    metrics::{data::ResourceMetrics, exporter::PushMetricExporter, Temporality},
+    trace::{SpanData, SpanExporter},
+    Resource,
+};
+
+/// Holds the exporter a provider exports through. Clones share the exporter, so the
+/// [`TelemetryManager`](super::TelemetryManager) keeps one to swap in a new exporter.
+#[derive(Debug)]
+pub struct ExporterSlot<E> {
+    exporter: Arc<ArcSwapOption<E>>,
+}
+
+impl<E> Clone for ExporterSlot<E> {
+    fn clone(&self) -> Self {
+        Self {
+            exporter: Arc::clone(&self.exporter),
+        }
+    }
+}
+
+impl<E> ExporterSlot<E> {
+    #[must_use]
+    pub fn new(exporter: E) -> Self {
+        Self {
+            exporter: Arc::new(ArcSwapOption::from_pointee(exporter)),
+        }
+    }
+
+    /// Replaces the exporter for the exports that follow. An export already running
+    /// finishes on the previous exporter, which is dropped once it is done.
+    pub fn replace(&self, exporter: E) {
+        self.exporter.store(Some(Arc::new(exporter)));
+    }
+
+    fn current(&self) -> Option<Arc<E>> {
+        self.exporter.load_full()
+    }
+
+    /// Removes the exporter, returning it if no export is still using it.
+    fn take(&self) -> Option<E> {
+        self.exporter
+            .swap(None)
+            .and_then(|exporter| Arc::try_unwrap(exporter).ok())
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for ExporterSlot<E> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        match self.current() {
+            Some(exporter) => exporter.export(batch).await,
+            None => Err(OTelSdkError::AlreadyShutdown),
+        }
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.take().map_or(Ok(()), |mut exporter| {
+            exporter.shutdown_with_timeout(timeout)
+        })
+    }
+
+    /// Span exporters send each batch as it is handed over, so there is nothing to flush;
+    /// the span processor flushes its own queue.
+    fn force_flush(&mut self) -> OTelSdkResult {
+        Ok(())
+    }
+
+    /// Called when the provider is built, before any export.
+    fn set_resource(&mut self, resource: &Resource) {
+        if let Some(mut exporter) = self.take() {
+            exporter.set_resource(resource);
+            self.replace(exporter);
+        }
+    }
+}
+
+impl<E: PushMetricExporter> PushMetricExporter for ExporterSlot<E> {
+    async fn export(&self, metrics: &ResourceMetrics) -> OTelSdkResult {
+        match self.current() {
+            Some(exporter) => exporter.export(metrics).await,
+            None => Err(OTelSdkError::AlreadyShutdown),
+        }
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.current()
+            .map_or(Ok(()), |exporter| exporter.force_flush())
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.exporter
+            .swap(None)
+            .map_or(Ok(()), |exporter| exporter.shutdown_with_timeout(timeout))
+    }
+
+    fn temporality(&self) -> Temporality {
+        self.current()
+            .map_or_else(Temporality::default, |exporter| exporter.temporality())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::{Tracer, TracerProvider};
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SimpleSpanProcessor};
+
+    use super::*;
+
+    #[test]
+    fn replaced_exporter_receives_later_spans() {
+        let first = InMemorySpanExporter::default();
+        let second = InMemorySpanExporter::default();
+        let slot = ExporterSlot::new(first.clone());
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(SimpleSpanProcessor::new(slot.clone()))
+            .build();
+        let tracer = provider.tracer("test");
+
+        tracer.in_span("before", |_| {});
+        slot.replace(second.clone());
+        tracer.in_span("after", |_| {});
+
+        let names = |exporter: &InMemorySpanExporter| {
+            exporter
+                .get_finished_spans()
+                .unwrap()
+                .into_iter()
+                .map(|span| span.name.into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&first), ["before"]);
+        assert_eq!(names(&second), ["after"]);
+    }
+
+    #[test]
+    fn shut_down_slot_rejects_exports() {
+        let mut slot = ExporterSlot::new(InMemorySpanExporter::default());
+        slot.shutdown_with_timeout(Duration::from_secs(1)).unwrap();
+
+        let result = futures::executor::block_on(SpanExporter::export(&slot, Vec::new()));
+        assert!(matches!(result, Err(OTelSdkError::AlreadyShutdown)));
+    }
+}
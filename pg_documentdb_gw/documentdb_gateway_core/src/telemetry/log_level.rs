@@ -0,0 +1,124 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/telemetry/log_level.rs
+ *
+ * Runtime control of the gateway's log filter.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{env, sync::OnceLock};
+
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::error::{DocumentDBError, Result};
+
+/// Filter used when `RUST_LOG` is not set.
+pub const DEFAULT_LOG_FILTER: &str = "info";
+
+static LOG_FILTER: OnceLock<LogFilterHandle> = OnceLock::new();
+
+/// Swaps the filter of a reloadable log layer.
+#[derive(Debug)]
+pub struct LogFilterHandle(reload::Handle<EnvFilter, Registry>);
+
+impl LogFilterHandle {
+    #[must_use]
+    pub const fn new(handle: reload::Handle<EnvFilter, Registry>) -> Self {
+        Self(handle)
+    }
+
+    fn current(&self) -> Option<String> {
+        self.0.with_current(ToString::to_string).ok()
+    }
+
+    /// Replaces the filter, returning the previous one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BadValue` if the directives don't parse or the log layer is gone.
+    pub fn set(&self, directives: &str) -> Result<String> {
+        let filter = EnvFilter::try_new(directives).map_err(|e| {
+            DocumentDBError::bad_value(format!("Invalid logLevel '{directives}': {e}"))
+        })?;
+        let was = self.current().unwrap_or_default();
+        self.0
+            .reload(filter)
+            .map_err(|e| DocumentDBError::bad_value(format!("Failed to change logLevel: {e}")))?;
+        Ok(was)
+    }
+}
+
+/// Builds the gateway's log filter from `RUST_LOG`, wrapped so `setParameter` can change it.
+///
+/// The handle is kept for [`set_log_level`]; only the first call in a process installs one.
+#[must_use]
+pub fn reloadable_log_filter() -> reload::Layer<EnvFilter, Registry> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    let (layer, handle) = reload::Layer::new(filter);
+    let _ = LOG_FILTER.set(LogFilterHandle::new(handle));
+    layer
+}
+
+/// The active log filter directives.
+#[must_use]
+pub fn log_level() -> String {
+    LOG_FILTER
+        .get()
+        .and_then(LogFilterHandle::current)
+        .or_else(|| env::var("RUST_LOG").ok())
+        .unwrap_or_else(|| DEFAULT_LOG_FILTER.to_owned())
+}
+
+/// Changes the log filter for the rest of the process, returning the previous directives.
+///
+/// # Errors
+///
+/// Returns `BadValue` if the directives don't parse or the gateway was started without
+/// a reloadable log filter.
+pub fn set_log_level(directives: &str) -> Result<String> {
+    LOG_FILTER
+        .get()
+        .ok_or(DocumentDBError::bad_value(
+            "logLevel cannot be changed: the log filter is not reloadable.".to_owned(),
+        ))?
+        .set(directives)
+}
+
+/// Maps a Mongo numeric verbosity to filter directives; 0 is the default level.
+#[must_use]
+pub const fn verbosity_directives(verbosity: i64) -> &'static str {
+    match verbosity {
+        ..=0 => DEFAULT_LOG_FILTER,
+        1 => "debug",
+        _ => "trace",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn set_replaces_filter_and_returns_previous() {
+        let (layer, handle) = reload::Layer::new(EnvFilter::new("info"));
+        let _subscriber = tracing_subscriber::registry().with(layer);
+        let handle = LogFilterHandle::new(handle);
+
+        assert_eq!(handle.set("debug").unwrap(), "info");
+        assert_eq!(handle.current().unwrap(), "debug");
+        handle.set("not a [valid filter").unwrap_err();
+        assert_eq!(handle.current().unwrap(), "debug");
+    }
+
+    #[test]
+    fn verbosity_maps_to_levels() {
+        assert_eq!(verbosity_directives(0), "info");
+        assert_eq!(verbosity_directives(1), "debug");
+        assert_eq!(verbosity_directives(5), "trace");
+    }
+}
@@ -6,23 +6,49 @@
  *-------------------------------------------------------------------------
  */
 
-use std::{sync::LazyLock, time::Duration};
+use std::{collections::HashMap, env, net::IpAddr, str::FromStr, sync::LazyLock, time::Duration};
 
 use either::Either;
-use opentelemetry::{global, metrics::Counter, KeyValue};
+use opentelemetry::{
+    global,
+    metrics::{Counter, Histogram, Meter, ObservableUpDownCounter},
+    trace::SpanContext,
+    KeyValue,
+};
 use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::{
     metrics::{PeriodicReader, SdkMeterProvider, Temporality},
     Resource,
 };
 use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
+    context::ConnectionSummary,
     error::{DocumentDBError, Result},
     protocol::header::Header,
     requests::{request_tracker::RequestTracker, Request, RequestIntervalKind, RequestType},
     responses::{CommandError, Response},
-    telemetry::config::{env_var, DEFAULT_EXPORT_TIMEOUT_MS, DEFAULT_OTLP_ENDPOINT},
+    telemetry::{
+        cardinality::{
+            metric_app_name, metric_collection_name, metric_tenant, set_app_name_cardinality_limit,
+            set_collection_cardinality_limit, set_tenant_cardinality_limit,
+            DEFAULT_APP_NAME_CARDINALITY_LIMIT, DEFAULT_COLLECTION_OVERFLOW_LABEL,
+            DEFAULT_TENANT_CARDINALITY_LIMIT,
+        },
+        config::{
+            env_var, resolve_compression, with_compression, with_tls, OtlpCompression,
+            OtlpRetryConfig, OtlpTlsConfig, DEFAULT_EXPORT_TIMEOUT_MS, DEFAULT_OTLP_ENDPOINT,
+        },
+        exemplars::{
+            exemplars_enabled, set_exemplars_enabled, Exemplar, ExemplarReservoir,
+            OPERATION_DURATION_BOUNDARIES, OPERATION_DURATION_EXEMPLARS, OPERATION_DURATION_METRIC,
+        },
+        exporter_slot::ExporterSlot,
+        prometheus::create_prometheus_provider,
+        retry::RetryMetricExporter,
+        tenant::TENANT_ATTRIBUTE,
+    },
 };
 
 // ============================================================================
@@ -31,6 +57,46 @@ use crate::{
 
 const DEFAULT_METRICS_ENABLED: bool = false;
 const DEFAULT_COLLECTION_INTERVAL_MS: u64 = 15000;
+const DEFAULT_METRICS_COMPRESSION: OtlpCompression = OtlpCompression::None;
+
+const DEFAULT_METRICS_TEMPORALITY: Temporality = Temporality::Delta;
+const DEFAULT_EXEMPLARS_ENABLED: bool = false;
+const DEFAULT_COLLECTION_CARDINALITY_LIMIT: usize = 0;
+const DEFAULT_PROMETHEUS_HOST: &str = "localhost";
+const DEFAULT_PROMETHEUS_PORT: u16 = 9464;
+
+/// Attribute carrying the client's handshake `appName`.
+pub(crate) const APP_NAME_ATTRIBUTE: &str = "db.documentdb.app_name";
+
+/// Bucket boundaries for the peak number of concurrent requests on a connection.
+const CONNECTION_PEAK_CONCURRENCY_BOUNDARIES: [f64; 9] =
+    [1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0];
+
+/// Bucket boundaries, in seconds, for establishing a backend connection, from a local
+/// socket up to a backend that is barely accepting connections.
+const CONNECTION_CREATE_DURATION_BOUNDARIES: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0,
+];
+
+/// Bucket boundaries for requested `maxTimeMS`. The first bucket holds requests that
+/// left it unset (or set it to 0), i.e. that rely on the server default.
+const REQUESTED_MAX_TIME_MS_BOUNDARIES: [f64; 9] = [
+    0.0, 100.0, 500.0, 1000.0, 5000.0, 15000.0, 30000.0, 60000.0, 300_000.0,
+];
+
+/// Bucket boundaries, in bytes, for the BSON a request buffers at its peak, from a small
+/// command up to a response batch near the maximum message size.
+const REQUEST_MEMORY_BOUNDARIES: [f64; 9] = [
+    4_096.0,
+    16_384.0,
+    65_536.0,
+    262_144.0,
+    1_048_576.0,
+    4_194_304.0,
+    16_777_216.0,
+    50_331_648.0,
+    100_663_296.0,
+];
 
 // ============================================================================
 // JSON Configuration
@@ -48,6 +114,70 @@ pub struct MetricsOptions {
     pub export_interval_ms: Option<u64>,
     /// Export timeout in milliseconds
     pub export_timeout_ms: Option<u64>,
+    /// OTLP payload compression, `"gzip"` or `"none"`
+    pub compression: Option<String>,
+    /// Aggregation temporality, `"delta"`, `"cumulative"` or `"lowmemory"`
+    pub temporality: Option<String>,
+    /// Metrics pipeline, `"otlp"` (push) or `"prometheus"` (pull)
+    pub exporter: Option<String>,
+    /// Host the Prometheus `/metrics` endpoint listens on
+    pub prometheus_host: Option<String>,
+    /// Port the Prometheus `/metrics` endpoint listens on
+    pub prometheus_port: Option<u16>,
+    /// Whether sampled requests attach their trace as an exemplar to the duration histogram
+    pub exemplars_enabled: Option<bool>,
+    /// Distinct `db.collection.name` values recorded before further collections share
+    /// `CollectionOverflowLabel`; 0 disables the limit
+    pub collection_cardinality_limit: Option<usize>,
+    /// `db.collection.name` recorded for collections beyond `CollectionCardinalityLimit`
+    pub collection_overflow_label: Option<String>,
+    /// Distinct client `appName` values recorded before further ones share
+    /// `CollectionOverflowLabel`; 0 disables the limit
+    pub app_name_cardinality_limit: Option<usize>,
+    /// Distinct tenants recorded before further ones share `CollectionOverflowLabel`;
+    /// 0 disables the limit
+    pub tenant_cardinality_limit: Option<usize>,
+}
+
+/// Metrics pipeline selected by `MetricsOptions.Exporter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsExporter {
+    /// Periodic push to an OTLP/gRPC endpoint.
+    Otlp,
+    /// Scrape endpoint serving the Prometheus text format.
+    Prometheus,
+}
+
+impl FromStr for MetricsExporter {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "otlp" => Ok(Self::Otlp),
+            "prometheus" => Ok(Self::Prometheus),
+            other => Err(format!("unsupported metrics exporter: {other}")),
+        }
+    }
+}
+
+/// Parses an `OTEL_METRICS_EXEMPLAR_FILTER` value. Only sampled requests ever carry a
+/// trace to link, so `always_on` behaves like `trace_based`.
+fn parse_exemplar_filter(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "always_off" => Some(false),
+        "trace_based" | "always_on" => Some(true),
+        _ => None,
+    }
+}
+
+/// Parses a temporality preference as named by the OTLP exporter specification.
+fn parse_temporality(value: &str) -> Option<Temporality> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "delta" => Some(Temporality::Delta),
+        "cumulative" => Some(Temporality::Cumulative),
+        "lowmemory" => Some(Temporality::LowMemory),
+        _ => None,
+    }
 }
 
 // ============================================================================
@@ -64,6 +194,16 @@ pub struct MetricsConfig {
     otlp_endpoint: Option<String>,
     export_interval_ms: Option<u64>,
     export_timeout_ms: Option<u64>,
+    compression: Option<String>,
+    temporality: Option<String>,
+    exporter: Option<String>,
+    prometheus_host: Option<String>,
+    prometheus_port: Option<u16>,
+    exemplars_enabled: Option<bool>,
+    collection_cardinality_limit: Option<usize>,
+    collection_overflow_label: Option<String>,
+    app_name_cardinality_limit: Option<usize>,
+    tenant_cardinality_limit: Option<usize>,
 }
 
 impl MetricsConfig {
@@ -77,6 +217,16 @@ impl MetricsConfig {
             otlp_endpoint: json.otlp_endpoint,
             export_interval_ms: json.export_interval_ms,
             export_timeout_ms: json.export_timeout_ms,
+            compression: json.compression,
+            temporality: json.temporality,
+            exporter: json.exporter,
+            prometheus_host: json.prometheus_host,
+            prometheus_port: json.prometheus_port,
+            exemplars_enabled: json.exemplars_enabled,
+            collection_cardinality_limit: json.collection_cardinality_limit,
+            collection_overflow_label: json.collection_overflow_label,
+            app_name_cardinality_limit: json.app_name_cardinality_limit,
+            tenant_cardinality_limit: json.tenant_cardinality_limit,
         }
     }
 
@@ -98,6 +248,13 @@ impl MetricsConfig {
             .unwrap_or_else(|| DEFAULT_OTLP_ENDPOINT.to_owned())
     }
 
+    /// The same configuration, exporting to `endpoint`.
+    #[must_use]
+    pub fn with_otlp_endpoint(mut self, endpoint: &str) -> Self {
+        self.otlp_endpoint = Some(endpoint.to_owned());
+        self
+    }
+
     /// Export interval in ms. Fallback: JSON > `OTEL_METRIC_EXPORT_INTERVAL` > 15000.
     #[must_use]
     pub fn export_interval_ms(&self) -> u64 {
@@ -115,6 +272,109 @@ impl MetricsConfig {
             .unwrap_or(DEFAULT_EXPORT_TIMEOUT_MS)
     }
 
+    /// OTLP payload compression. Fallback: JSON > `OTEL_EXPORTER_OTLP_METRICS_COMPRESSION` > `OTEL_EXPORTER_OTLP_COMPRESSION` > none.
+    #[must_use]
+    pub fn compression(&self) -> OtlpCompression {
+        resolve_compression(
+            self.compression.as_deref(),
+            &[
+                "OTEL_EXPORTER_OTLP_METRICS_COMPRESSION",
+                "OTEL_EXPORTER_OTLP_COMPRESSION",
+            ],
+            DEFAULT_METRICS_COMPRESSION,
+        )
+    }
+
+    /// Aggregation temporality. Fallback: JSON > `OTEL_EXPORTER_OTLP_METRICS_TEMPORALITY_PREFERENCE` > delta.
+    #[must_use]
+    pub fn temporality(&self) -> Temporality {
+        self.temporality
+            .as_deref()
+            .and_then(parse_temporality)
+            .or_else(|| {
+                env::var("OTEL_EXPORTER_OTLP_METRICS_TEMPORALITY_PREFERENCE")
+                    .ok()
+                    .as_deref()
+                    .and_then(parse_temporality)
+            })
+            .unwrap_or(DEFAULT_METRICS_TEMPORALITY)
+    }
+
+    /// Metrics pipeline. Fallback: JSON > `OTEL_METRICS_EXPORTER` > otlp.
+    #[must_use]
+    pub fn exporter(&self) -> MetricsExporter {
+        self.exporter
+            .as_deref()
+            .and_then(|v| v.parse().ok())
+            .or_else(|| env_var("OTEL_METRICS_EXPORTER"))
+            .unwrap_or(MetricsExporter::Otlp)
+    }
+
+    /// Prometheus listen address. Fallback: JSON > `OTEL_EXPORTER_PROMETHEUS_HOST`/`OTEL_EXPORTER_PROMETHEUS_PORT` > localhost:9464.
+    #[must_use]
+    pub fn prometheus_listen_address(&self) -> String {
+        let host = self
+            .prometheus_host
+            .clone()
+            .or_else(|| env_var("OTEL_EXPORTER_PROMETHEUS_HOST"))
+            .unwrap_or_else(|| DEFAULT_PROMETHEUS_HOST.to_owned());
+        let port = self
+            .prometheus_port
+            .or_else(|| env_var("OTEL_EXPORTER_PROMETHEUS_PORT"))
+            .unwrap_or(DEFAULT_PROMETHEUS_PORT);
+        format!("{host}:{port}")
+    }
+
+    /// Whether sampled requests record exemplars. Fallback: JSON > `OTEL_METRICS_EXEMPLAR_FILTER` > false.
+    #[must_use]
+    pub fn exemplars_enabled(&self) -> bool {
+        self.exemplars_enabled
+            .or_else(|| {
+                env::var("OTEL_METRICS_EXEMPLAR_FILTER")
+                    .ok()
+                    .as_deref()
+                    .and_then(parse_exemplar_filter)
+            })
+            .unwrap_or(DEFAULT_EXEMPLARS_ENABLED)
+    }
+
+    /// Distinct collection names recorded in metrics, 0 for no limit.
+    /// Fallback: JSON > `OTEL_METRICS_COLLECTION_CARDINALITY_LIMIT` > 0.
+    #[must_use]
+    pub fn collection_cardinality_limit(&self) -> usize {
+        self.collection_cardinality_limit
+            .or_else(|| env_var("OTEL_METRICS_COLLECTION_CARDINALITY_LIMIT"))
+            .unwrap_or(DEFAULT_COLLECTION_CARDINALITY_LIMIT)
+    }
+
+    /// Collection name recorded past the cardinality limit.
+    /// Fallback: JSON > `OTEL_METRICS_COLLECTION_OVERFLOW_LABEL` > `__other__`.
+    #[must_use]
+    pub fn collection_overflow_label(&self) -> String {
+        self.collection_overflow_label
+            .clone()
+            .or_else(|| env_var("OTEL_METRICS_COLLECTION_OVERFLOW_LABEL"))
+            .unwrap_or_else(|| DEFAULT_COLLECTION_OVERFLOW_LABEL.to_owned())
+    }
+
+    /// Distinct client application names recorded in metrics, 0 for no limit.
+    /// Fallback: JSON > `OTEL_METRICS_APP_NAME_CARDINALITY_LIMIT` > 100.
+    #[must_use]
+    pub fn app_name_cardinality_limit(&self) -> usize {
+        self.app_name_cardinality_limit
+            .or_else(|| env_var("OTEL_METRICS_APP_NAME_CARDINALITY_LIMIT"))
+            .unwrap_or(DEFAULT_APP_NAME_CARDINALITY_LIMIT)
+    }
+
+    /// Distinct tenants recorded in metrics, 0 for no limit.
+    /// Fallback: JSON > `OTEL_METRICS_TENANT_CARDINALITY_LIMIT` > 100.
+    #[must_use]
+    pub fn tenant_cardinality_limit(&self) -> usize {
+        self.tenant_cardinality_limit
+            .or_else(|| env_var("OTEL_METRICS_TENANT_CARDINALITY_LIMIT"))
+            .unwrap_or(DEFAULT_TENANT_CARDINALITY_LIMIT)
+    }
+
     /// Creates an OTLP export configuration for metrics.
     #[must_use]
     pub fn create_export_config(&self) -> opentelemetry_otlp::ExportConfig {
@@ -130,33 +390,66 @@ impl MetricsConfig {
 // Provider Creation
 // ============================================================================
 
-/// Creates an OpenTelemetry meter provider with periodic OTLP export.
+/// The OTLP metric exporter, with retries, that a meter provider exports through.
+pub type OtlpMetricExporter = RetryMetricExporter<opentelemetry_otlp::MetricExporter>;
+
+/// Builds the OTLP metric exporter for `config`.
 ///
-/// Returns `None` if metrics are disabled in config.
+/// # Errors
+///
+/// Returns an error if the TLS configuration is invalid or the exporter fails to build.
+pub fn create_metrics_exporter(
+    config: &MetricsConfig,
+    tls: &OtlpTlsConfig,
+    retry: &OtlpRetryConfig,
+) -> Result<OtlpMetricExporter> {
+    // Delta temporality (the default) emits the change since the last export and relies
+    // on the OTel Collector to accumulate; backends such as Prometheus need cumulative.
+    let builder = opentelemetry_otlp::MetricExporter::builder()
+        .with_temporality(config.temporality())
+        .with_tonic()
+        .with_export_config(config.create_export_config());
+    let builder = with_tls(builder, tls, &config.otlp_endpoint())?;
+    let exporter = with_compression(builder, config.compression())
+        .build()
+        .map_err(|e| {
+            DocumentDBError::internal_error(format!("Failed to build metrics exporter: {e}"))
+        })?;
+    Ok(RetryMetricExporter::new(exporter, retry.policy()))
+}
+
+/// Creates an OpenTelemetry meter provider with periodic OTLP export, or serving a
+/// Prometheus scrape endpoint until `shutdown` is cancelled when the `prometheus` exporter
+/// is configured.
+///
+/// Returns `None` if metrics are disabled in config. The provider is returned with the
+/// slot of its OTLP exporter, which a Prometheus provider doesn't have.
 ///
 /// # Errors
 ///
-/// Returns an error if the OTLP metrics exporter fails to build.
+/// Returns an error if the TLS configuration is invalid, the OTLP metrics exporter fails
+/// to build, or the Prometheus listen address cannot be bound.
 pub fn create_metrics_provider(
     config: &MetricsConfig,
+    tls: &OtlpTlsConfig,
+    retry: &OtlpRetryConfig,
     resource: Resource,
-) -> Result<Option<SdkMeterProvider>> {
+    shutdown: CancellationToken,
+) -> Result<Option<(SdkMeterProvider, Option<ExporterSlot<OtlpMetricExporter>>)>> {
     if !config.metrics_enabled() {
         return Ok(None);
     }
 
-    // Delta temporality: counters emit deltas (change since last export).
-    // The OTel Collector aggregates deltas into cumulative for Prometheus.
-    let exporter = opentelemetry_otlp::MetricExporter::builder()
-        .with_temporality(Temporality::Delta)
-        .with_tonic()
-        .with_export_config(config.create_export_config())
-        .build()
-        .map_err(|e| {
-            DocumentDBError::internal_error(format!("Failed to build metrics exporter: {e}"))
-        })?;
+    apply_recording_options(config);
+
+    if config.exporter() == MetricsExporter::Prometheus {
+        let (meter_provider, _) =
+            create_prometheus_provider(resource, &config.prometheus_listen_address(), shutdown)?;
+        return Ok(Some((meter_provider, None)));
+    }
 
-    let reader = PeriodicReader::builder(exporter)
+    let exporter = ExporterSlot::new(create_metrics_exporter(config, tls, retry)?);
+    let reader = PeriodicReader::builder(exporter.clone())
         .with_interval(Duration::from_millis(config.export_interval_ms()))
         .build();
 
@@ -165,7 +458,24 @@ pub fn create_metrics_provider(
         .with_reader(reader)
         .build();
 
-    Ok(Some(meter_provider))
+    Ok(Some((meter_provider, Some(exporter))))
+}
+
+/// Applies the exemplar and attribute cardinality settings of `config` to recording.
+fn apply_recording_options(config: &MetricsConfig) {
+    set_exemplars_enabled(config.exemplars_enabled());
+    set_collection_cardinality_limit(
+        config.collection_cardinality_limit(),
+        &config.collection_overflow_label(),
+    );
+    set_app_name_cardinality_limit(
+        config.app_name_cardinality_limit(),
+        &config.collection_overflow_label(),
+    );
+    set_tenant_cardinality_limit(
+        config.tenant_cardinality_limit(),
+        &config.collection_overflow_label(),
+    );
 }
 
 // ============================================================================
@@ -177,6 +487,7 @@ pub fn create_metrics_provider(
 /// Uses `global::meter()` which returns a no-op meter if no `MeterProvider` is
 /// registered, making these calls zero-cost when telemetry is disabled.
 struct GatewayMetrics {
+    operation_duration: Histogram<f64>,
     operation_duration_total: Counter<f64>,
     operations_count: Counter<u64>,
     request_size_total: Counter<u64>,
@@ -185,54 +496,343 @@ struct GatewayMetrics {
     documents_inserted: Counter<u64>,
     documents_updated: Counter<u64>,
     documents_deleted: Counter<u64>,
+    requested_max_time_ms: Histogram<u64>,
+    connection_peak_concurrency: Histogram<u64>,
+    connection_create_duration: Histogram<f64>,
+    cursors_evicted: Counter<u64>,
+    statement_cache_lookups: Counter<u64>,
+    auth_failures: Counter<u64>,
+    auth_successes: Counter<u64>,
+    unsupported_commands: Counter<u64>,
+    export_dropped: Counter<u64>,
+    connections_rejected: Counter<u64>,
+    requests_client_aborted: Counter<u64>,
+    handler_panics: Counter<u64>,
+    request_memory_high_water_mark: Histogram<u64>,
+}
+
+fn connection_peak_concurrency_histogram(meter: &Meter) -> Histogram<u64> {
+    meter
+        .u64_histogram("db.client.connection.peak_concurrency")
+        .with_description(
+            "Peak number of requests a client connection had sent and not yet had answered",
+        )
+        .with_unit("{request}")
+        .with_boundaries(CONNECTION_PEAK_CONCURRENCY_BOUNDARIES.to_vec())
+        .build()
 }
 
-static GATEWAY_METRICS: LazyLock<GatewayMetrics> = LazyLock::new(|| {
-    let meter = global::meter("documentdb_gateway");
-
-    GatewayMetrics {
-        operation_duration_total: meter
-            .f64_counter("db.client.operation.duration.total")
-            .with_description("Total duration of database client operations (sum)")
-            .with_unit("s")
-            .build(),
-        operations_count: meter
-            .u64_counter("db.client.operations")
-            .with_description("Count of database client operations")
-            .with_unit("{operation}")
-            .build(),
-        request_size_total: meter
-            .u64_counter("db.client.request.size.total")
-            .with_description("Total size of database client request payloads")
-            .with_unit("By")
-            .build(),
-        response_size_total: meter
-            .u64_counter("db.client.response.size.total")
-            .with_description("Total size of database client response payloads")
-            .with_unit("By")
-            .build(),
-        documents_returned: meter
-            .u64_counter("db.client.documents.returned")
-            .with_description("Documents returned by read operations")
-            .with_unit("{document}")
-            .build(),
-        documents_inserted: meter
-            .u64_counter("db.client.documents.inserted")
-            .with_description("Documents inserted")
-            .with_unit("{document}")
-            .build(),
-        documents_updated: meter
-            .u64_counter("db.client.documents.updated")
-            .with_description("Documents updated")
-            .with_unit("{document}")
-            .build(),
-        documents_deleted: meter
-            .u64_counter("db.client.documents.deleted")
-            .with_description("Documents deleted")
-            .with_unit("{document}")
-            .build(),
-    }
-});
+fn document_counter(meter: &Meter, name: &'static str, description: &'static str) -> Counter<u64> {
+    meter
+        .u64_counter(name)
+        .with_description(description)
+        .with_unit("{document}")
+        .build()
+}
+
+fn requested_max_time_ms_histogram(meter: &Meter) -> Histogram<u64> {
+    meter
+        .u64_histogram("db.client.requested_max_time_ms")
+        .with_description("maxTimeMS requested by clients; 0 when unset")
+        .with_unit("ms")
+        .with_boundaries(REQUESTED_MAX_TIME_MS_BOUNDARIES.to_vec())
+        .build()
+}
+
+fn request_memory_histogram(meter: &Meter) -> Histogram<u64> {
+    meter
+        .u64_histogram("gateway.request.memory.high_water_mark")
+        .with_description("Most BSON a request held buffered in the gateway at once")
+        .with_unit("By")
+        .with_boundaries(REQUEST_MEMORY_BOUNDARIES.to_vec())
+        .build()
+}
+
+/// Records the request duration, linking it to the request's trace when `exemplars` is set
+/// and the trace is sampled.
+fn record_operation_duration(
+    histogram: &Histogram<f64>,
+    exemplars: Option<&ExemplarReservoir>,
+    seconds: f64,
+    attrs: &[KeyValue],
+    span_context: Option<&SpanContext>,
+) {
+    histogram.record(seconds, attrs);
+
+    if let Some(reservoir) = exemplars {
+        if let Some(exemplar) = span_context.and_then(|cx| Exemplar::from_sampled(cx, seconds)) {
+            reservoir.offer(attrs, exemplar);
+        }
+    }
+}
+
+/// Records the request's `maxTimeMS`, using 0 when it is unset or not positive.
+fn record_requested_max_time(
+    histogram: &Histogram<u64>,
+    request: Option<&Request<'_>>,
+    attrs: &[KeyValue],
+) {
+    let max_time_ms = request
+        .and_then(Request::requested_max_time_ms)
+        .map_or(0, |ms| ms.max(0).cast_unsigned());
+    histogram.record(max_time_ms, attrs);
+}
+
+static GATEWAY_METRICS: LazyLock<GatewayMetrics> =
+    LazyLock::new(|| GatewayMetrics::new(&global::meter("documentdb_gateway")));
+
+impl GatewayMetrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            operation_duration: meter
+                .f64_histogram(OPERATION_DURATION_METRIC)
+                .with_description("Duration of database client operations")
+                .with_unit("s")
+                .with_boundaries(OPERATION_DURATION_BOUNDARIES.to_vec())
+                .build(),
+            operation_duration_total: meter
+                .f64_counter("db.client.operation.duration.total")
+                .with_description("Total duration of database client operations (sum)")
+                .with_unit("s")
+                .build(),
+            operations_count: meter
+                .u64_counter("db.client.operations")
+                .with_description("Count of database client operations")
+                .with_unit("{operation}")
+                .build(),
+            request_size_total: meter
+                .u64_counter("db.client.request.size.total")
+                .with_description("Total size of database client request payloads")
+                .with_unit("By")
+                .build(),
+            response_size_total: meter
+                .u64_counter("db.client.response.size.total")
+                .with_description("Total size of database client response payloads")
+                .with_unit("By")
+                .build(),
+            documents_returned: document_counter(meter, "db.client.documents.returned", "Documents returned by read operations"),
+            documents_inserted: document_counter(meter, "db.client.documents.inserted", "Documents inserted"),
+            documents_updated: document_counter(meter, "db.client.documents.updated", "Documents updated"),
+            documents_deleted: document_counter(meter, "db.client.documents.deleted", "Documents deleted"),
+            requested_max_time_ms: requested_max_time_ms_histogram(meter),
+            connection_peak_concurrency: connection_peak_concurrency_histogram(meter),
+            connection_create_duration: meter
+                .f64_histogram("db.client.connection.create.duration")
+                .with_description(
+                    "Time to establish a new backend connection, excluding the wait for a pool slot",
+                )
+                .with_unit("s")
+                .with_boundaries(CONNECTION_CREATE_DURATION_BOUNDARIES.to_vec())
+                .build(),
+            cursors_evicted: meter
+                .u64_counter("db.client.cursors.evicted")
+                .with_description("Cursors evicted because the open cursor limit was reached")
+                .with_unit("{cursor}")
+                .build(),
+            statement_cache_lookups: meter
+                .u64_counter("db.client.statement_cache.lookups")
+                .with_description("Prepared statement cache lookups, by hit or miss")
+                .with_unit("{lookup}")
+                .build(),
+            auth_failures: meter
+                .u64_counter("gateway.auth.failures")
+                .with_description("Failed saslStart and saslContinue commands")
+                .with_unit("{attempt}")
+                .build(),
+            auth_successes: meter
+                .u64_counter("gateway.auth.successes")
+                .with_description("Completed authentications")
+                .with_unit("{attempt}")
+                .build(),
+            unsupported_commands: meter
+                .u64_counter("gateway.commands.unsupported")
+                .with_description("Commands rejected because the gateway has no handler for them")
+                .with_unit("{command}")
+                .build(),
+            export_dropped: meter
+                .u64_counter("telemetry.export.dropped")
+                .with_description("Telemetry items dropped because the export queue was full")
+                .with_unit("{item}")
+                .build(),
+            connections_rejected: meter
+                .u64_counter("gateway.connections.rejected")
+                .with_description(
+                    "Client connections closed because the connection limit and backlog were full",
+                )
+                .with_unit("{connection}")
+                .build(),
+            requests_client_aborted: meter
+                .u64_counter("gateway.requests.client_aborted")
+                .with_description("Requests abandoned because the client disconnected mid-operation")
+                .with_unit("{request}")
+                .build(),
+            handler_panics: meter
+                .u64_counter("gateway.handler.panics")
+                .with_description("Requests whose handler panicked, closing their connection")
+                .with_unit("{request}")
+                .build(),
+            request_memory_high_water_mark: request_memory_histogram(meter),
+        }
+    }
+}
+
+/// Records connection-level metrics when a client connection closes.
+pub fn record_connection_metrics(summary: &ConnectionSummary) {
+    GATEWAY_METRICS
+        .connection_peak_concurrency
+        .record(summary.peak_concurrent_requests, &[]);
+}
+
+fn record_connection_create(metrics: &GatewayMetrics, pool_name: &str, duration: Duration) {
+    metrics.connection_create_duration.record(
+        duration.as_secs_f64(),
+        &[KeyValue::new(
+            "db.client.connection.pool.name",
+            pool_name.to_owned(),
+        )],
+    );
+}
+
+/// Records how long it took pool `pool_name` to establish a new backend connection.
+pub(crate) fn record_connection_create_duration(pool_name: &str, duration: Duration) {
+    record_connection_create(&GATEWAY_METRICS, pool_name, duration);
+}
+
+/// Records a cursor of `db` being evicted to stay under the open cursor limit.
+pub(crate) fn record_cursor_evicted(db: &str) {
+    GATEWAY_METRICS
+        .cursors_evicted
+        .add(1, &[KeyValue::new("db.namespace", db.to_owned())]);
+}
+
+/// Records a lookup in the prepared statement cache of a backend connection; the hit ratio
+/// is the share of lookups with `result` set to `hit`.
+pub(crate) fn record_statement_cache_lookup(hit: bool) {
+    GATEWAY_METRICS.statement_cache_lookups.add(
+        1,
+        &[KeyValue::new("result", if hit { "hit" } else { "miss" })],
+    );
+}
+
+/// Groups client addresses by /24 for IPv4 and /64 for IPv6, so that attempts from one
+/// network can be told apart without a series per client.
+fn source_ip_bucket(ip_address: &str) -> String {
+    match ip_address.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            let [a, b, c, _] = ip.octets();
+            format!("{a}.{b}.{c}.0/24")
+        }
+        Ok(IpAddr::V6(ip)) => {
+            let [a, b, c, d, ..] = ip.segments();
+            format!("{a:x}:{b:x}:{c:x}:{d:x}::/64")
+        }
+        Err(_) => ip_address.to_owned(),
+    }
+}
+
+fn record_auth(metrics: &GatewayMetrics, succeeded: bool, mechanism: &str, ip_address: &str) {
+    let attrs = [
+        KeyValue::new("auth.mechanism", mechanism.to_owned()),
+        KeyValue::new("client.address.bucket", source_ip_bucket(ip_address)),
+    ];
+    if succeeded {
+        metrics.auth_successes.add(1, &attrs);
+    } else {
+        metrics.auth_failures.add(1, &attrs);
+    }
+}
+
+/// Records a completed authentication, or a failed SASL step from the client at `ip_address`.
+pub(crate) fn record_auth_attempt(succeeded: bool, mechanism: &str, ip_address: &str) {
+    record_auth(&GATEWAY_METRICS, succeeded, mechanism, ip_address);
+}
+
+fn record_unsupported(metrics: &GatewayMetrics, command: &str) {
+    metrics
+        .unsupported_commands
+        .add(1, &[KeyValue::new("db.operation.name", command.to_owned())]);
+}
+
+/// Records a command the gateway has no handler for, tagged with its name.
+pub(crate) fn record_unsupported_command(command: &str) {
+    record_unsupported(&GATEWAY_METRICS, command);
+}
+
+fn record_dropped(metrics: &GatewayMetrics, signal: &'static str, count: u64) {
+    metrics
+        .export_dropped
+        .add(count, &[KeyValue::new("signal", signal)]);
+}
+
+/// Records `count` items of `signal` (e.g. `traces`) dropped from a full export queue.
+pub(crate) fn record_export_dropped(signal: &'static str, count: u64) {
+    record_dropped(&GATEWAY_METRICS, signal, count);
+}
+
+/// Records a client connection rejected by the connection limit.
+pub(crate) fn record_connection_rejected() {
+    GATEWAY_METRICS.connections_rejected.add(1, &[]);
+}
+
+/// Records a `command` request abandoned because its client disconnected before the reply.
+pub(crate) fn record_request_client_aborted(command: &str) {
+    GATEWAY_METRICS
+        .requests_client_aborted
+        .add(1, &[KeyValue::new("db.operation.name", command.to_owned())]);
+}
+
+/// Records a request handler panicking.
+pub(crate) fn record_handler_panic() {
+    GATEWAY_METRICS.handler_panics.add(1, &[]);
+}
+
+/// Records the most memory a request held buffered at once.
+pub(crate) fn record_request_memory_high_water_mark(bytes: usize) {
+    GATEWAY_METRICS
+        .request_memory_high_water_mark
+        .record(u64::try_from(bytes).unwrap_or(u64::MAX), &[]);
+}
+
+/// Registers the `gateway.connections.active` gauge, reporting `active_connections` on
+/// each collection.
+pub(crate) fn register_active_connections_gauge<F>(
+    active_connections: F,
+) -> ObservableUpDownCounter<i64>
+where
+    F: Fn() -> i64 + Send + Sync + 'static,
+{
+    global::meter("documentdb_gateway")
+        .i64_observable_up_down_counter("gateway.connections.active")
+        .with_description("Client connections currently being served")
+        .with_unit("{connection}")
+        .with_callback(move |observer| observer.observe(active_connections(), &[]))
+        .build()
+}
+
+/// Registers the `db.client.cursors.open` gauge, reporting the counts returned by
+/// `open_cursors_by_db` on each collection.
+pub(crate) fn register_open_cursors_gauge<F>(open_cursors_by_db: F) -> ObservableUpDownCounter<i64>
+where
+    F: Fn() -> HashMap<String, i64> + Send + Sync + 'static,
+{
+    open_cursors_gauge(&global::meter("documentdb_gateway"), open_cursors_by_db)
+}
+
+fn open_cursors_gauge<F>(meter: &Meter, open_cursors_by_db: F) -> ObservableUpDownCounter<i64>
+where
+    F: Fn() -> HashMap<String, i64> + Send + Sync + 'static,
+{
+    meter
+        .i64_observable_up_down_counter("db.client.cursors.open")
+        .with_description("Cursors currently saved by the gateway")
+        .with_unit("{cursor}")
+        .with_callback(move |observer| {
+            for (db, count) in open_cursors_by_db() {
+                observer.observe(count, &[KeyValue::new("db.namespace", db)]);
+            }
+        })
+        .build()
+}
 
 /// Records request-level metrics directly in the request handling path.
 ///
@@ -242,35 +842,49 @@ static GATEWAY_METRICS: LazyLock<GatewayMetrics> = LazyLock::new(|| {
 /// is registered, all counters are no-ops with negligible overhead.
 ///
 /// Aggregation (averages, percentiles) is delegated to the collector.
+///
+/// `span_context` is the request span, attached as an exemplar to the duration
+/// histogram when exemplars are enabled and the span is sampled.
+#[expect(
+    clippy::too_many_arguments,
+    reason = "each argument is a separate dimension of the request's metrics"
+)]
 pub fn record_gateway_metrics(
     header: &Header,
     request: Option<&Request<'_>>,
     response: Either<&Response, (&CommandError, usize)>,
     collection: &str,
+    app_name: &str,
+    tenant: Option<&str>,
     request_tracker: &RequestTracker,
+    span_context: Option<&SpanContext>,
 ) {
     let metrics = &*GATEWAY_METRICS;
 
-    let operation = request.map_or_else(|| "unknown".to_owned(), |r| r.request_type().to_string());
-
-    let db_name = request.and_then(|r| r.db().ok()).unwrap_or("unknown");
-
-    let duration_to_secs =
-        |ns: i64| -> f64 { Duration::from_nanos(ns.max(0).cast_unsigned()).as_secs_f64() };
-
     let duration_ns = request_tracker.get_interval_elapsed_time(RequestIntervalKind::HandleRequest);
 
-    let mut base_attrs: Vec<KeyValue> = vec![
-        KeyValue::new("db.system.name", "documentdb"),
-        KeyValue::new("db.operation.name", operation),
-        KeyValue::new("db.collection.name", collection.to_owned()),
-        KeyValue::new("db.namespace", db_name.to_owned()),
-    ];
-    if let Either::Right((err, _)) = &response {
-        base_attrs.push(KeyValue::new("error.type", err.code().to_string()));
+    // Spans and logs keep the real name; only metrics share the overflow label.
+    let mut base_attrs =
+        operation_attributes(request, &response, &metric_collection_name(collection));
+    base_attrs.push(KeyValue::new(
+        APP_NAME_ATTRIBUTE,
+        metric_app_name(app_name).into_owned(),
+    ));
+    if let Some(tenant) = tenant {
+        base_attrs.push(KeyValue::new(
+            TENANT_ATTRIBUTE,
+            metric_tenant(tenant).into_owned(),
+        ));
     }
 
     metrics.operations_count.add(1, &base_attrs);
+    record_operation_duration(
+        &metrics.operation_duration,
+        exemplars_enabled().then(|| &*OPERATION_DURATION_EXEMPLARS),
+        duration_to_secs(duration_ns),
+        &base_attrs,
+        span_context,
+    );
     metrics
         .operation_duration_total
         .add(duration_to_secs(duration_ns), &base_attrs);
@@ -279,6 +893,8 @@ pub fn record_gateway_metrics(
         .request_size_total
         .add(u64::from(header.length.max(0).cast_unsigned()), &base_attrs);
 
+    record_requested_max_time(&metrics.requested_max_time_ms, request, &base_attrs);
+
     let response_size_bytes = match &response {
         Either::Left(resp) => resp
             .as_raw_document()
@@ -297,32 +913,69 @@ pub fn record_gateway_metrics(
         }
     }
 
-    // Record PostgreSQL phase breakdown (duration totals).
-    let mut phase_attrs = Vec::with_capacity(base_attrs.len() + 1);
+    record_phase_durations(metrics, &base_attrs, request_tracker);
+}
 
-    let mut record_phase = |phase: &'static str, ns: i64| {
+fn duration_to_secs(ns: i64) -> f64 {
+    Duration::from_nanos(ns.max(0).cast_unsigned()).as_secs_f64()
+}
+
+/// Adds the request's phases to the operation duration total, each under its own
+/// `db.operation.phase`. The gateway phases tell an overloaded gateway from a slow backend.
+fn record_phase_durations(
+    metrics: &GatewayMetrics,
+    base_attrs: &[KeyValue],
+    request_tracker: &RequestTracker,
+) {
+    let mut phase_attrs = Vec::with_capacity(base_attrs.len() + 1);
+    for (phase, kind) in [
+        ("queue_wait", RequestIntervalKind::QueueWait),
+        ("pool_checkout", RequestIntervalKind::PoolCheckout),
+        (
+            "postgres_begin_transaction",
+            RequestIntervalKind::PostgresBeginTransaction,
+        ),
+        ("postgres_execution", RequestIntervalKind::ProcessRequest),
+        (
+            "postgres_commit",
+            RequestIntervalKind::PostgresCommitTransaction,
+        ),
+    ] {
+        let ns = request_tracker.get_interval_elapsed_time(kind);
         if ns > 0 {
             phase_attrs.clear();
-            phase_attrs.extend_from_slice(&base_attrs);
+            phase_attrs.extend_from_slice(base_attrs);
             phase_attrs.push(KeyValue::new("db.operation.phase", phase));
             metrics
                 .operation_duration_total
                 .add(duration_to_secs(ns), &phase_attrs);
         }
-    };
+    }
+}
 
-    record_phase(
-        "postgres_begin_transaction",
-        request_tracker.get_interval_elapsed_time(RequestIntervalKind::PostgresBeginTransaction),
-    );
-    record_phase(
-        "postgres_execution",
-        request_tracker.get_interval_elapsed_time(RequestIntervalKind::ProcessRequest),
-    );
-    record_phase(
-        "postgres_commit",
-        request_tracker.get_interval_elapsed_time(RequestIntervalKind::PostgresCommitTransaction),
-    );
+/// Builds the semantic-convention attributes shared by all per-request telemetry.
+///
+/// See: <https://opentelemetry.io/docs/specs/semconv/database/database-spans/>
+pub(crate) fn operation_attributes(
+    request: Option<&Request<'_>>,
+    response: &Either<&Response, (&CommandError, usize)>,
+    collection: &str,
+) -> Vec<KeyValue> {
+    let operation = request.map_or_else(|| "unknown".to_owned(), |r| r.request_type().to_string());
+
+    let db_name = request.and_then(|r| r.db().ok()).unwrap_or("unknown");
+
+    let mut attrs = vec![
+        KeyValue::new("db.system.name", "documentdb"),
+        KeyValue::new("db.operation.name", operation),
+        KeyValue::new("db.collection.name", collection.to_owned()),
+        KeyValue::new("db.namespace", db_name.to_owned()),
+    ];
+    if let Either::Right((err, _)) = response {
+        attrs.push(KeyValue::new("error.type", err.code().to_string()));
+    }
+
+    attrs
 }
 
 /// Extract document counts from the response based on operation type.
@@ -380,6 +1033,15 @@ fn record_document_counts(
 
 #[cfg(test)]
 mod tests {
+    use bson::rawdoc;
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry::trace::{SpanId, TraceFlags, TraceId, TraceState};
+    use opentelemetry_sdk::metrics::{
+        data::{AggregatedMetrics, MetricData, ResourceMetrics, ScopeMetrics, SumDataPoint},
+        InMemoryMetricExporter,
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
     use super::*;
     use crate::testing::EnvGuard;
 
@@ -412,6 +1074,268 @@ mod tests {
         assert_eq!(config.otlp_endpoint(), "http://env:4317");
     }
 
+    #[test]
+    fn test_metrics_compression_defaults_to_none() {
+        let _guard = EnvGuard::remove_many([
+            "OTEL_EXPORTER_OTLP_METRICS_COMPRESSION",
+            "OTEL_EXPORTER_OTLP_COMPRESSION",
+        ]);
+        assert_eq!(
+            MetricsConfig::new(None).compression(),
+            OtlpCompression::None
+        );
+    }
+
+    #[test]
+    fn test_metrics_compression_precedence() {
+        let _guard = EnvGuard::set_many([
+            ("OTEL_EXPORTER_OTLP_METRICS_COMPRESSION", "none"),
+            ("OTEL_EXPORTER_OTLP_COMPRESSION", "gzip"),
+        ]);
+        assert_eq!(
+            MetricsConfig::new(None).compression(),
+            OtlpCompression::None
+        );
+
+        let json_config = MetricsOptions {
+            compression: Some("gzip".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            MetricsConfig::new(Some(&json_config)).compression(),
+            OtlpCompression::Gzip
+        );
+    }
+
+    #[test]
+    fn test_parse_temporality_maps_each_preference() {
+        assert_eq!(parse_temporality("delta"), Some(Temporality::Delta));
+        assert_eq!(
+            parse_temporality("cumulative"),
+            Some(Temporality::Cumulative)
+        );
+        assert_eq!(parse_temporality("LowMemory"), Some(Temporality::LowMemory));
+        assert_eq!(parse_temporality("sometimes"), None);
+    }
+
+    #[test]
+    fn test_metrics_temporality_precedence() {
+        let _guard = EnvGuard::set(
+            "OTEL_EXPORTER_OTLP_METRICS_TEMPORALITY_PREFERENCE",
+            "cumulative",
+        );
+        assert_eq!(
+            MetricsConfig::new(None).temporality(),
+            Temporality::Cumulative
+        );
+
+        let json_config = MetricsOptions {
+            temporality: Some("lowmemory".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            MetricsConfig::new(Some(&json_config)).temporality(),
+            Temporality::LowMemory
+        );
+    }
+
+    #[test]
+    fn test_metrics_temporality_defaults_to_delta() {
+        let _guard = EnvGuard::set("OTEL_EXPORTER_OTLP_METRICS_TEMPORALITY_PREFERENCE", "bogus");
+        assert_eq!(MetricsConfig::new(None).temporality(), Temporality::Delta);
+    }
+
+    #[test]
+    fn test_metrics_exporter_selection() {
+        let _guard = EnvGuard::set_many([
+            ("OTEL_METRICS_EXPORTER", "prometheus"),
+            ("OTEL_EXPORTER_PROMETHEUS_PORT", "9100"),
+        ]);
+        let config = MetricsConfig::new(None);
+        assert_eq!(config.exporter(), MetricsExporter::Prometheus);
+        assert_eq!(config.prometheus_listen_address(), "localhost:9100");
+
+        let json_config = MetricsOptions {
+            exporter: Some("otlp".to_owned()),
+            prometheus_host: Some("0.0.0.0".to_owned()),
+            prometheus_port: Some(9464),
+            ..Default::default()
+        };
+        let config = MetricsConfig::new(Some(&json_config));
+        assert_eq!(config.exporter(), MetricsExporter::Otlp);
+        assert_eq!(config.prometheus_listen_address(), "0.0.0.0:9464");
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_endpoint_serves_gateway_metrics() {
+        let (provider, addr) = create_prometheus_provider(
+            Resource::builder().build(),
+            "127.0.0.1:0",
+            CancellationToken::new(),
+        )
+        .unwrap();
+        let metrics = GatewayMetrics::new(&provider.meter("documentdb_gateway"));
+        metrics
+            .operations_count
+            .add(2, &[KeyValue::new("db.operation.name", "insert")]);
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("# TYPE db_client_operations_total counter"));
+        assert!(response.contains("db_client_operations_total{db_operation_name=\"insert\"} 2"));
+    }
+
+    #[test]
+    fn test_metrics_exemplars_precedence() {
+        let _guard = EnvGuard::set("OTEL_METRICS_EXEMPLAR_FILTER", "trace_based");
+        assert!(MetricsConfig::new(None).exemplars_enabled());
+
+        let json_config = MetricsOptions {
+            exemplars_enabled: Some(false),
+            ..Default::default()
+        };
+        assert!(!MetricsConfig::new(Some(&json_config)).exemplars_enabled());
+    }
+
+    #[test]
+    fn test_metrics_collection_cardinality_precedence() {
+        let _guard = EnvGuard::set_many([
+            ("OTEL_METRICS_COLLECTION_CARDINALITY_LIMIT", "500"),
+            ("OTEL_METRICS_COLLECTION_OVERFLOW_LABEL", "other"),
+        ]);
+        let config = MetricsConfig::new(None);
+        assert_eq!(config.collection_cardinality_limit(), 500);
+        assert_eq!(config.collection_overflow_label(), "other");
+
+        let json_config = MetricsOptions {
+            collection_cardinality_limit: Some(10),
+            collection_overflow_label: Some("_overflow".to_owned()),
+            ..Default::default()
+        };
+        let config = MetricsConfig::new(Some(&json_config));
+        assert_eq!(config.collection_cardinality_limit(), 10);
+        assert_eq!(config.collection_overflow_label(), "_overflow");
+    }
+
+    #[test]
+    fn test_metrics_collection_cardinality_unlimited_by_default() {
+        let _guard = EnvGuard::remove_many([
+            "OTEL_METRICS_COLLECTION_CARDINALITY_LIMIT",
+            "OTEL_METRICS_COLLECTION_OVERFLOW_LABEL",
+        ]);
+        let config = MetricsConfig::new(None);
+        assert_eq!(config.collection_cardinality_limit(), 0);
+        assert_eq!(config.collection_overflow_label(), "__other__");
+    }
+
+    #[test]
+    fn test_metrics_app_name_cardinality_precedence() {
+        let guard = EnvGuard::set("OTEL_METRICS_APP_NAME_CARDINALITY_LIMIT", "20");
+        assert_eq!(MetricsConfig::new(None).app_name_cardinality_limit(), 20);
+
+        let json_config = MetricsOptions {
+            app_name_cardinality_limit: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            MetricsConfig::new(Some(&json_config)).app_name_cardinality_limit(),
+            0
+        );
+
+        drop(guard);
+        let _guard = EnvGuard::remove("OTEL_METRICS_APP_NAME_CARDINALITY_LIMIT");
+        assert_eq!(MetricsConfig::new(None).app_name_cardinality_limit(), 100);
+    }
+
+    #[test]
+    fn test_metrics_exemplars_disabled_by_default() {
+        let _guard = EnvGuard::remove("OTEL_METRICS_EXEMPLAR_FILTER");
+        assert!(!MetricsConfig::new(None).exemplars_enabled());
+    }
+
+    fn span_context(flags: TraceFlags) -> SpanContext {
+        SpanContext::new(
+            TraceId::from(0x4bf9_2f35_77b3_4da6_a3ce_929d_0e0e_4736),
+            SpanId::from(0x00f0_67aa_0ba9_02b7),
+            flags,
+            false,
+            TraceState::default(),
+        )
+    }
+
+    #[test]
+    fn test_sampled_request_attaches_exemplar() {
+        let provider = SdkMeterProvider::builder().build();
+        let metrics = GatewayMetrics::new(&provider.meter("test"));
+        let reservoir = ExemplarReservoir::new(&OPERATION_DURATION_BOUNDARIES);
+        let sampled = [KeyValue::new("db.operation.name", "find")];
+        let unsampled = [KeyValue::new("db.operation.name", "insert")];
+
+        record_operation_duration(
+            &metrics.operation_duration,
+            Some(&reservoir),
+            0.02,
+            &sampled,
+            Some(&span_context(TraceFlags::SAMPLED)),
+        );
+        record_operation_duration(
+            &metrics.operation_duration,
+            Some(&reservoir),
+            0.02,
+            &unsampled,
+            Some(&span_context(TraceFlags::default())),
+        );
+
+        // 0.02s falls in the (0.01, 0.025] bucket.
+        let exemplar = reservoir.get(sampled.iter(), 3).unwrap();
+        assert_eq!(
+            exemplar.trace_id.to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert!(reservoir.get(unsampled.iter(), 3).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_open_metrics_serves_exemplars() {
+        let (provider, addr) = create_prometheus_provider(
+            Resource::builder().build(),
+            "127.0.0.1:0",
+            CancellationToken::new(),
+        )
+        .unwrap();
+        let metrics = GatewayMetrics::new(&provider.meter("documentdb_gateway"));
+        record_operation_duration(
+            &metrics.operation_duration,
+            Some(&OPERATION_DURATION_EXEMPLARS),
+            0.3,
+            &[KeyValue::new("db.collection.name", "exemplar_scrape")],
+            Some(&span_context(TraceFlags::SAMPLED)),
+        );
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(
+                b"GET /metrics HTTP/1.1\r\nAccept: application/openmetrics-text; version=1.0.0\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.contains("Content-Type: application/openmetrics-text"));
+        assert!(response.contains(
+            "db_client_operation_duration_seconds_bucket{db_collection_name=\"exemplar_scrape\",le=\"0.5\"} 1 # {trace_id=\"4bf92f3577b34da6a3ce929d0e0e4736\",span_id=\"00f067aa0ba902b7\"} 0.3"
+        ));
+        assert!(response.ends_with("# EOF\n"));
+    }
+
     #[test]
     fn test_create_metrics_provider_when_disabled() {
         let json_config = MetricsOptions {
@@ -426,7 +1350,13 @@ mod tests {
         ];
         let resource = Resource::builder().with_attributes(attributes).build();
 
-        let result = create_metrics_provider(&config, resource);
+        let result = create_metrics_provider(
+            &config,
+            &OtlpTlsConfig::new(None),
+            &OtlpRetryConfig::new(None),
+            resource,
+            CancellationToken::new(),
+        );
         assert!(result.is_ok());
         assert!(result.unwrap().is_none());
     }
@@ -445,11 +1375,306 @@ mod tests {
         ];
         let resource = Resource::builder().with_attributes(attributes).build();
 
-        let result = create_metrics_provider(&config, resource);
+        let result = create_metrics_provider(
+            &config,
+            &OtlpTlsConfig::new(None),
+            &OtlpRetryConfig::new(None),
+            resource,
+            CancellationToken::new(),
+        );
         assert!(result.is_ok());
         assert!(result.unwrap().is_some());
     }
 
+    #[tokio::test]
+    async fn test_requested_max_time_histogram_buckets_unset_requests() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let histogram = requested_max_time_ms_histogram(&provider.meter("test"));
+
+        let documents = [
+            rawdoc! { "find": "c" },
+            rawdoc! { "find": "c", "maxTimeMS": 0 },
+            rawdoc! { "find": "c", "maxTimeMS": 50_i64 },
+            rawdoc! { "find": "c", "maxTimeMS": 2500.0 },
+            rawdoc! { "find": "c", "maxTimeMS": 45000 },
+        ];
+        for document in &documents {
+            let request = Request::Raw(RequestType::Find, document, None);
+            record_requested_max_time(&histogram, Some(&request), &[]);
+        }
+        record_requested_max_time(&histogram, None, &[]);
+        provider.force_flush().unwrap();
+
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let metric = metrics
+            .iter()
+            .flat_map(ResourceMetrics::scope_metrics)
+            .flat_map(ScopeMetrics::metrics)
+            .find(|metric| metric.name() == "db.client.requested_max_time_ms")
+            .unwrap();
+        let AggregatedMetrics::U64(MetricData::Histogram(histogram)) = metric.data() else {
+            panic!("expected a u64 histogram");
+        };
+        let point = histogram.data_points().next().unwrap();
+
+        assert_eq!(point.count(), 6);
+        assert_eq!(point.sum(), 47_550);
+        assert_eq!(
+            point.bucket_counts().collect::<Vec<_>>(),
+            [3, 1, 0, 0, 1, 0, 0, 1, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_connection_create_duration_is_recorded_by_pool() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let metrics = GatewayMetrics::new(&provider.meter("test"));
+
+        record_connection_create(
+            &metrics,
+            "DocumentDBGateway-UserData",
+            Duration::from_millis(20),
+        );
+        provider.force_flush().unwrap();
+
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let metric = metrics
+            .iter()
+            .flat_map(ResourceMetrics::scope_metrics)
+            .flat_map(ScopeMetrics::metrics)
+            .find(|metric| metric.name() == "db.client.connection.create.duration")
+            .unwrap();
+        assert_eq!(metric.unit(), "s");
+        let AggregatedMetrics::F64(MetricData::Histogram(histogram)) = metric.data() else {
+            panic!("expected an f64 histogram");
+        };
+        let point = histogram.data_points().next().unwrap();
+
+        assert_eq!(point.count(), 1);
+        assert!(point
+            .attributes()
+            .any(|attr| attr.key.as_str() == "db.client.connection.pool.name"
+                && attr.value.as_str() == "DocumentDBGateway-UserData"));
+    }
+
+    #[test]
+    fn test_open_cursors_gauge_reports_counts_by_database() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let _gauge = open_cursors_gauge(&provider.meter("test"), || {
+            HashMap::from([("a".to_owned(), 2), ("b".to_owned(), 1)])
+        });
+        provider.force_flush().unwrap();
+
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let metric = metrics
+            .iter()
+            .flat_map(ResourceMetrics::scope_metrics)
+            .flat_map(ScopeMetrics::metrics)
+            .find(|metric| metric.name() == "db.client.cursors.open")
+            .unwrap();
+        let AggregatedMetrics::I64(MetricData::Sum(sum)) = metric.data() else {
+            panic!("expected an i64 sum");
+        };
+        let mut counts: Vec<(String, i64)> = sum
+            .data_points()
+            .map(|point| {
+                let db = point.attributes().next().unwrap().value.to_string();
+                (db, point.value())
+            })
+            .collect();
+        counts.sort();
+
+        assert!(!sum.is_monotonic());
+        assert_eq!(counts, [("a".to_owned(), 2), ("b".to_owned(), 1)]);
+    }
+
+    #[test]
+    fn test_source_ip_bucket_groups_by_network() {
+        assert_eq!(source_ip_bucket("10.1.2.3"), "10.1.2.0/24");
+        assert_eq!(source_ip_bucket("2001:db8:1:2:3::4"), "2001:db8:1:2::/64");
+        assert_eq!(source_ip_bucket("localhost"), "localhost");
+    }
+
+    #[test]
+    fn test_auth_failure_is_counted_apart_from_success() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let metrics = GatewayMetrics::new(&provider.meter("test"));
+
+        record_auth(&metrics, false, "SCRAM-SHA-256", "10.1.2.3");
+        record_auth(&metrics, false, "SCRAM-SHA-256", "10.1.2.4");
+        record_auth(&metrics, true, "SCRAM-SHA-256", "10.1.2.3");
+        provider.force_flush().unwrap();
+
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let total = |name: &str| -> u64 {
+            metrics
+                .iter()
+                .flat_map(ResourceMetrics::scope_metrics)
+                .flat_map(ScopeMetrics::metrics)
+                .filter(|metric| metric.name() == name)
+                .map(|metric| {
+                    let AggregatedMetrics::U64(MetricData::Sum(sum)) = metric.data() else {
+                        panic!("expected a u64 sum");
+                    };
+                    sum.data_points().map(SumDataPoint::value).sum::<u64>()
+                })
+                .sum()
+        };
+
+        assert_eq!(total("gateway.auth.failures"), 2);
+        assert_eq!(total("gateway.auth.successes"), 1);
+    }
+
+    #[test]
+    fn test_unsupported_command_is_counted_by_name() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let metrics = GatewayMetrics::new(&provider.meter("test"));
+
+        record_unsupported(&metrics, "top");
+        record_unsupported(&metrics, "top");
+        record_unsupported(&metrics, "replSetGetStatus");
+        provider.force_flush().unwrap();
+
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let mut counts: Vec<(String, u64)> = metrics
+            .iter()
+            .flat_map(ResourceMetrics::scope_metrics)
+            .flat_map(ScopeMetrics::metrics)
+            .filter(|metric| metric.name() == "gateway.commands.unsupported")
+            .flat_map(|metric| {
+                let AggregatedMetrics::U64(MetricData::Sum(sum)) = metric.data() else {
+                    panic!("expected a u64 sum");
+                };
+                sum.data_points()
+                    .map(|point| {
+                        let name = point
+                            .attributes()
+                            .find(|attr| attr.key.as_str() == "db.operation.name")
+                            .map(|attr| attr.value.to_string())
+                            .unwrap();
+                        (name, point.value())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        counts.sort();
+
+        assert_eq!(
+            counts,
+            vec![("replSetGetStatus".to_owned(), 1), ("top".to_owned(), 2)]
+        );
+    }
+
+    #[test]
+    fn test_export_dropped_is_counted_by_signal() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let metrics = GatewayMetrics::new(&provider.meter("test"));
+
+        record_dropped(&metrics, "traces", 3);
+        record_dropped(&metrics, "traces", 2);
+        provider.force_flush().unwrap();
+
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let points: Vec<(String, u64)> = metrics
+            .iter()
+            .flat_map(ResourceMetrics::scope_metrics)
+            .flat_map(ScopeMetrics::metrics)
+            .filter(|metric| metric.name() == "telemetry.export.dropped")
+            .flat_map(|metric| {
+                let AggregatedMetrics::U64(MetricData::Sum(sum)) = metric.data() else {
+                    panic!("expected a u64 sum");
+                };
+                sum.data_points()
+                    .map(|point| {
+                        let signal = point
+                            .attributes()
+                            .find(|attr| attr.key.as_str() == "signal")
+                            .map(|attr| attr.value.to_string())
+                            .unwrap();
+                        (signal, point.value())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        assert_eq!(points, vec![("traces".to_owned(), 5)]);
+    }
+
+    #[test]
+    fn test_queue_wait_and_pool_checkout_are_recorded_as_phases() {
+        let exporter = InMemoryMetricExporter::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        let metrics = GatewayMetrics::new(&provider.meter("test"));
+
+        let tracker = RequestTracker::new();
+        for (kind, secs) in [
+            (RequestIntervalKind::QueueWait, 3),
+            (RequestIntervalKind::PoolCheckout, 2),
+            (RequestIntervalKind::ProcessRequest, 1),
+        ] {
+            tracker.record_elapsed(kind, Duration::from_secs(secs));
+        }
+        record_phase_durations(
+            &metrics,
+            &[KeyValue::new("db.operation.name", "find")],
+            &tracker,
+        );
+        provider.force_flush().unwrap();
+
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let mut phases: Vec<(String, f64)> = metrics
+            .iter()
+            .flat_map(ResourceMetrics::scope_metrics)
+            .flat_map(ScopeMetrics::metrics)
+            .filter(|metric| metric.name() == "db.client.operation.duration.total")
+            .flat_map(|metric| {
+                let AggregatedMetrics::F64(MetricData::Sum(sum)) = metric.data() else {
+                    panic!("expected an f64 sum");
+                };
+                sum.data_points()
+                    .map(|point| {
+                        let phase = point
+                            .attributes()
+                            .find(|attr| attr.key.as_str() == "db.operation.phase")
+                            .map(|attr| attr.value.to_string())
+                            .unwrap();
+                        (phase, point.value())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        phases.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            phases,
+            vec![
+                ("pool_checkout".to_owned(), 2.0),
+                ("postgres_execution".to_owned(), 1.0),
+                ("queue_wait".to_owned(), 3.0),
+            ]
+        );
+    }
+
     #[test]
     fn test_gateway_metrics_callable_without_provider() {
         // Verify record_gateway_metrics is callable.
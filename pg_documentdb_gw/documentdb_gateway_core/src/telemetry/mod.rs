@@ -4,26 +4,44 @@
  * documentdb_gateway_core/src/telemetry/mod.rs
  *
  * Telemetry infrastructure for the DocumentDB gateway.
- * Provides OpenTelemetry-based metrics.
+ * Provides OpenTelemetry-based metrics and tracing.
  *
  *-------------------------------------------------------------------------
  */
 
 mod log_request_fail;
 mod telemetry_provider;
+mod unsupported_command;
 mod verbose_latency;
 
+pub mod audit;
+pub mod cardinality;
 pub mod client_info;
 pub mod config;
 pub mod event_id;
+pub mod exemplars;
+pub mod export_queue;
+pub mod exporter_slot;
+pub mod log_level;
 pub mod metrics;
+pub mod prometheus;
+pub mod query_shape;
+pub mod redaction;
+pub mod retry;
 pub mod telemetry_manager;
+pub mod tenant;
+pub mod trace_context;
+pub mod traces;
 pub mod utils;
 
 // Re-export commonly used types
 pub use config::{TelemetryConfig, TelemetryOptions};
 pub use log_request_fail::log_request_failure;
-pub use metrics::{record_gateway_metrics, MetricsConfig, MetricsOptions};
+pub use metrics::{
+    record_connection_metrics, record_gateway_metrics, MetricsConfig, MetricsOptions,
+};
 pub use telemetry_manager::TelemetryManager;
 pub use telemetry_provider::TelemetryProvider;
+pub use traces::{is_tracing_enabled, record_request_span, TracingConfig, TracingOptions};
+pub use unsupported_command::record_unsupported_command;
 pub use verbose_latency::try_log_verbose_latency;
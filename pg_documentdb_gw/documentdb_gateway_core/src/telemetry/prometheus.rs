@@ -0,0 +1,383 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * src/telemetry/prometheus.rs
+ *
+ * Pull-based metrics pipeline serving the Prometheus text exposition format,
+ * or OpenMetrics with exemplars when the scraper asks for it.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    fmt::{Display, Write as _},
+    net::SocketAddr,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::{
+    error::OTelSdkResult,
+    metrics::{
+        data::{AggregatedMetrics, Metric, MetricData, ResourceMetrics, ScopeMetrics},
+        reader::MetricReader,
+        InstrumentKind, ManualReader, Pipeline, SdkMeterProvider, Temporality,
+    },
+    Resource,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    error::Result,
+    service::{start_http_server, HttpHandler, HttpRequest, HttpResponse},
+    telemetry::exemplars::{exemplar_for, Exemplar},
+};
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const OPEN_METRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Exposition format negotiated from the scrape request's `Accept` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    /// Adds exemplars to histogram buckets; counter families are named without `_total`.
+    OpenMetrics,
+}
+
+impl Format {
+    const fn content_type(self) -> &'static str {
+        match self {
+            Self::Text => CONTENT_TYPE,
+            Self::OpenMetrics => OPEN_METRICS_CONTENT_TYPE,
+        }
+    }
+}
+
+/// A [`ManualReader`] that can be registered with the meter provider and still be
+/// collected from by the scrape endpoint.
+#[derive(Debug, Clone)]
+struct SharedReader(Arc<ManualReader>);
+
+impl MetricReader for SharedReader {
+    fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+        self.0.register_pipeline(pipeline);
+    }
+
+    fn collect(&self, rm: &mut ResourceMetrics) -> OTelSdkResult {
+        self.0.collect(rm)
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.0.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.0.shutdown_with_timeout(timeout)
+    }
+
+    fn temporality(&self, kind: InstrumentKind) -> Temporality {
+        self.0.temporality(kind)
+    }
+}
+
+/// Creates a meter provider whose metrics are scraped from `http://<listen_address>/metrics`
+/// until `shutdown` is cancelled.
+///
+/// The listener is bound before returning so that an unavailable address fails startup.
+/// Returns the provider and the bound address.
+///
+/// # Errors
+///
+/// Returns an error if the listen address cannot be bound.
+pub fn create_prometheus_provider(
+    resource: Resource,
+    listen_address: &str,
+    shutdown: CancellationToken,
+) -> Result<(SdkMeterProvider, SocketAddr)> {
+    // Prometheus expects monotonically increasing counters.
+    let reader = SharedReader(Arc::new(
+        ManualReader::builder()
+            .with_temporality(Temporality::Cumulative)
+            .build(),
+    ));
+    let local_addr = start_http_server(
+        listen_address,
+        "Prometheus metrics",
+        Arc::new(ScrapeHandler(reader.clone())),
+        shutdown,
+    )?;
+    let meter_provider = SdkMeterProvider::builder()
+        .with_resource(resource)
+        .with_reader(reader)
+        .build();
+
+    tracing::info!("Serving Prometheus metrics on http://{local_addr}/metrics");
+
+    Ok((meter_provider, local_addr))
+}
+
+struct ScrapeHandler(SharedReader);
+
+#[async_trait]
+impl HttpHandler for ScrapeHandler {
+    async fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        let format = if request.mentions("application/openmetrics-text") {
+            Format::OpenMetrics
+        } else {
+            Format::Text
+        };
+
+        let (status, body) = if request.get_path() == Some(b"/metrics") {
+            let mut metrics = ResourceMetrics::default();
+            match self.0.collect(&mut metrics) {
+                Ok(()) => ("200 OK", render(&metrics, format)),
+                Err(e) => (
+                    "500 Internal Server Error",
+                    format!("failed to collect metrics: {e}\n"),
+                ),
+            }
+        } else {
+            ("404 Not Found", String::new())
+        };
+        HttpResponse {
+            status,
+            content_type: format.content_type(),
+            body,
+        }
+    }
+}
+
+/// Renders collected metrics in the requested exposition format.
+fn render(metrics: &ResourceMetrics, format: Format) -> String {
+    let mut out = String::new();
+    for metric in metrics.scope_metrics().flat_map(ScopeMetrics::metrics) {
+        match metric.data() {
+            AggregatedMetrics::F64(data) => render_metric(&mut out, metric, data, format),
+            AggregatedMetrics::U64(data) => render_metric(&mut out, metric, data, format),
+            AggregatedMetrics::I64(data) => render_metric(&mut out, metric, data, format),
+        }
+    }
+    if format == Format::OpenMetrics {
+        out.push_str("# EOF\n");
+    }
+    out
+}
+
+fn render_metric<T: Display + Copy>(
+    out: &mut String,
+    metric: &Metric,
+    data: &MetricData<T>,
+    format: Format,
+) {
+    let base = metric_name(metric.name(), metric.unit());
+    let (kind, name) = match data {
+        MetricData::Sum(sum) if sum.is_monotonic() => ("counter", format!("{base}_total")),
+        MetricData::Sum(_) | MetricData::Gauge(_) => ("gauge", base.clone()),
+        MetricData::Histogram(_) => ("histogram", base.clone()),
+        // Exponential histograms have no text-format equivalent.
+        MetricData::ExponentialHistogram(_) => return,
+    };
+
+    // OpenMetrics names a counter family without the `_total` suffix of its samples.
+    let family = if format == Format::OpenMetrics {
+        &base
+    } else {
+        &name
+    };
+    let _ = writeln!(out, "# HELP {family} {}", escape_help(metric.description()));
+    let _ = writeln!(out, "# TYPE {family} {kind}");
+
+    match data {
+        MetricData::Sum(sum) => {
+            for point in sum.data_points() {
+                write_sample(out, &name, point.attributes(), None, point.value(), None);
+            }
+        }
+        MetricData::Gauge(gauge) => {
+            for point in gauge.data_points() {
+                write_sample(out, &name, point.attributes(), None, point.value(), None);
+            }
+        }
+        MetricData::Histogram(histogram) => {
+            let bucket = format!("{name}_bucket");
+            for point in histogram.data_points() {
+                let exemplar = |index| {
+                    (format == Format::OpenMetrics)
+                        .then(|| exemplar_for(metric.name(), point.attributes(), index))
+                        .flatten()
+                };
+                let mut cumulative = 0;
+                for (index, (bound, count)) in point.bounds().zip(point.bucket_counts()).enumerate()
+                {
+                    cumulative += count;
+                    let le = bound.to_string();
+                    write_sample(
+                        out,
+                        &bucket,
+                        point.attributes(),
+                        Some(&le),
+                        cumulative,
+                        exemplar(index),
+                    );
+                }
+                write_sample(
+                    out,
+                    &bucket,
+                    point.attributes(),
+                    Some("+Inf"),
+                    point.count(),
+                    exemplar(point.bounds().count()),
+                );
+                write_sample(
+                    out,
+                    &format!("{name}_sum"),
+                    point.attributes(),
+                    None,
+                    point.sum(),
+                    None,
+                );
+                write_sample(
+                    out,
+                    &format!("{name}_count"),
+                    point.attributes(),
+                    None,
+                    point.count(),
+                    None,
+                );
+            }
+        }
+        MetricData::ExponentialHistogram(_) => {}
+    }
+}
+
+fn write_sample<'a>(
+    out: &mut String,
+    name: &str,
+    attributes: impl Iterator<Item = &'a KeyValue>,
+    le: Option<&str>,
+    value: impl Display,
+    exemplar: Option<Exemplar>,
+) {
+    let mut labels: Vec<String> = attributes
+        .map(|kv| {
+            format!(
+                "{}=\"{}\"",
+                sanitize(kv.key.as_str()),
+                escape_label(&kv.value.as_str())
+            )
+        })
+        .collect();
+    if let Some(le) = le {
+        labels.push(format!("le=\"{le}\""));
+    }
+
+    if labels.is_empty() {
+        let _ = write!(out, "{name} {value}");
+    } else {
+        let _ = write!(out, "{name}{{{}}} {value}", labels.join(","));
+    }
+    if let Some(exemplar) = exemplar {
+        let _ = write!(
+            out,
+            " # {{trace_id=\"{}\",span_id=\"{}\"}} {}",
+            exemplar.trace_id, exemplar.span_id, exemplar.value
+        );
+    }
+    out.push('\n');
+}
+
+/// Converts an OpenTelemetry instrument name and unit to a Prometheus metric name,
+/// e.g. `db.client.request.size.total` in `By` becomes `db_client_request_size_total_bytes`.
+/// A name already ending in the unit, spelled out or as its symbol, gets no suffix, so
+/// `db.client.requested_max_time_ms` in `ms` stays `db_client_requested_max_time_ms`.
+fn metric_name(name: &str, unit: &str) -> String {
+    let suffix = match unit {
+        "s" => "seconds",
+        "ms" => "milliseconds",
+        "By" => "bytes",
+        _ => "",
+    };
+
+    let mut name = sanitize(name);
+    let ends_with_unit = [suffix, unit]
+        .iter()
+        .any(|unit| name.ends_with(&format!("_{}", unit.to_ascii_lowercase())));
+    if !suffix.is_empty() && !ends_with_unit {
+        name.push('_');
+        name.push_str(suffix);
+    }
+    name
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn escape_help(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn escape_label(value: &str) -> String {
+    escape_help(value).replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    use super::*;
+
+    #[test]
+    fn metric_name_follows_prometheus_conventions() {
+        assert_eq!(
+            metric_name("db.client.operations", "{operation}"),
+            "db_client_operations"
+        );
+        assert_eq!(
+            metric_name("db.client.request.size.total", "By"),
+            "db_client_request_size_total_bytes"
+        );
+        assert_eq!(
+            metric_name("db.client.requested_max_time_ms", "ms"),
+            "db_client_requested_max_time_ms"
+        );
+        assert_eq!(
+            metric_name("db.client.connection.create.duration", "s"),
+            "db_client_connection_create_duration_seconds"
+        );
+        assert_eq!(
+            metric_name("gateway.request.bytes", "By"),
+            "gateway_request_bytes"
+        );
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[tokio::test]
+    async fn scrape_endpoint_rejects_other_paths() {
+        let (_provider, addr) = create_prometheus_provider(
+            Resource::builder().build(),
+            "127.0.0.1:0",
+            CancellationToken::new(),
+        )
+        .unwrap();
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+    }
+}
@@ -0,0 +1,294 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/telemetry/query_shape.rs
+ *
+ * Normalized query shapes for request spans. Values are replaced by type
+ * placeholders so queries that differ only in their values share a shape,
+ * which keeps the attribute's cardinality bounded.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use bson::{spec::ElementType, RawArray, RawBsonRef, RawDocument};
+use opentelemetry::KeyValue;
+
+/// Span attribute carrying the normalized shape.
+const QUERY_SHAPE_ATTRIBUTE: &str = "db.documentdb.query.shape";
+
+/// Span attribute carrying the shape's hash, to group spans by.
+const QUERY_SHAPE_HASH_ATTRIBUTE: &str = "db.documentdb.query.shape.hash";
+
+/// Shapes longer than this are truncated in the span attribute; the hash covers all of it.
+const MAX_SHAPE_ATTRIBUTE_LEN: usize = 1024;
+
+/// Command fields describing the query, per command.
+const SHAPED_FIELDS: &[(&str, &[&str])] = &[
+    ("find", &["filter", "projection", "sort"]),
+    ("aggregate", &["pipeline"]),
+    ("count", &["query"]),
+    ("distinct", &["key", "query"]),
+    ("findAndModify", &["query", "sort", "fields"]),
+    ("update", &["updates"]),
+    ("delete", &["deletes"]),
+];
+
+/// Update and delete statement fields describing the query.
+const STATEMENT_FIELDS: &[&str] = &["q"];
+
+static QUERY_SHAPE_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The normalized form of a command's query and its stable hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryShape {
+    shape: String,
+    hash: String,
+}
+
+impl QueryShape {
+    /// Returns the shape of `command`, or `None` for commands without a query.
+    #[must_use]
+    pub fn of(command: &RawDocument) -> Option<Self> {
+        let (command_name, _) = command.iter().next()?.ok()?;
+        let (_, fields) = SHAPED_FIELDS
+            .iter()
+            .find(|(name, _)| *name == command_name)?;
+
+        let mut shape = String::new();
+        let _ = write!(shape, "{{\"{command_name}\":{{");
+        let mut first = true;
+        for field in *fields {
+            let Ok(Some(value)) = command.get(field) else {
+                continue;
+            };
+            if !first {
+                shape.push(',');
+            }
+            first = false;
+            write_key(&mut shape, field);
+            match (*field, value) {
+                ("updates" | "deletes", RawBsonRef::Array(statements)) => {
+                    write_statements(&mut shape, statements);
+                }
+                // The distinct key is a field path, which is part of the shape.
+                ("key", RawBsonRef::String(key)) => write_key(&mut shape, key),
+                _ => write_value(&mut shape, value),
+            }
+        }
+        shape.push_str("}}");
+
+        let digest = openssl::sha::sha256(shape.as_bytes());
+        Some(Self {
+            hash: hex::encode(&digest[..8]),
+            shape,
+        })
+    }
+
+    #[must_use]
+    pub fn shape(&self) -> &str {
+        &self.shape
+    }
+
+    #[must_use]
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+}
+
+/// Writes the distinct shapes of update or delete statements, in order.
+fn write_statements(out: &mut String, statements: &RawArray) {
+    let shapes = distinct_shapes(statements.into_iter().flatten().map(|statement| {
+        let mut shape = String::from("{");
+        if let Some(statement) = statement.as_document() {
+            let mut first = true;
+            for field in STATEMENT_FIELDS {
+                if let Ok(Some(value)) = statement.get(field) {
+                    if !first {
+                        shape.push(',');
+                    }
+                    first = false;
+                    write_key(&mut shape, field);
+                    write_value(&mut shape, value);
+                }
+            }
+        }
+        shape.push('}');
+        shape
+    }));
+    write_array(out, &shapes);
+}
+
+fn write_value(out: &mut String, value: RawBsonRef<'_>) {
+    match value {
+        RawBsonRef::Document(document) => {
+            out.push('{');
+            for (i, (key, value)) in document.into_iter().flatten().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_key(out, key);
+                write_value(out, value);
+            }
+            out.push('}');
+        }
+        // Arrays collapse to their distinct element shapes, so `$in` lists of any length
+        // share a shape.
+        RawBsonRef::Array(array) => {
+            let shapes = distinct_shapes(array.into_iter().flatten().map(|element| {
+                let mut shape = String::new();
+                write_value(&mut shape, element);
+                shape
+            }));
+            write_array(out, &shapes);
+        }
+        other => {
+            let _ = write!(out, "\"?{}\"", placeholder(other.element_type()));
+        }
+    }
+}
+
+fn write_array(out: &mut String, shapes: &[String]) {
+    out.push('[');
+    out.push_str(&shapes.join(","));
+    out.push(']');
+}
+
+fn write_key(out: &mut String, key: &str) {
+    out.push_str(&serde_json::Value::from(key).to_string());
+    out.push(':');
+}
+
+fn distinct_shapes(shapes: impl Iterator<Item = String>) -> Vec<String> {
+    let mut distinct: Vec<String> = Vec::new();
+    for shape in shapes {
+        if !distinct.contains(&shape) {
+            distinct.push(shape);
+        }
+    }
+    distinct
+}
+
+/// Names the type of a value; all numeric types share one placeholder, since drivers
+/// differ in which they send for the same number.
+const fn placeholder(element_type: ElementType) -> &'static str {
+    match element_type {
+        ElementType::Double | ElementType::Int32 | ElementType::Int64 | ElementType::Decimal128 => {
+            "number"
+        }
+        ElementType::String | ElementType::Symbol => "string",
+        ElementType::Boolean => "bool",
+        ElementType::DateTime => "date",
+        ElementType::ObjectId => "objectId",
+        ElementType::Null | ElementType::Undefined => "null",
+        ElementType::Binary => "binary",
+        ElementType::RegularExpression => "regex",
+        ElementType::Timestamp => "timestamp",
+        ElementType::EmbeddedDocument => "document",
+        ElementType::Array => "array",
+        ElementType::JavaScriptCode | ElementType::JavaScriptCodeWithScope => "javascript",
+        ElementType::DbPointer => "dbPointer",
+        ElementType::MinKey => "minKey",
+        ElementType::MaxKey => "maxKey",
+    }
+}
+
+/// Adds the query shape attributes of `command` to a request span's attributes.
+pub(crate) fn push_query_shape_attributes(attributes: &mut Vec<KeyValue>, command: &RawDocument) {
+    if !QUERY_SHAPE_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let Some(query_shape) = QueryShape::of(command) else {
+        return;
+    };
+
+    let mut shape = query_shape.shape;
+    if shape.len() > MAX_SHAPE_ATTRIBUTE_LEN {
+        let mut end = MAX_SHAPE_ATTRIBUTE_LEN;
+        while !shape.is_char_boundary(end) {
+            end -= 1;
+        }
+        shape.truncate(end);
+    }
+    attributes.push(KeyValue::new(QUERY_SHAPE_ATTRIBUTE, shape));
+    attributes.push(KeyValue::new(QUERY_SHAPE_HASH_ATTRIBUTE, query_shape.hash));
+}
+
+pub(crate) fn set_query_shape_enabled(enabled: bool) {
+    QUERY_SHAPE_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+
+    fn shape_of(command: &RawDocument) -> QueryShape {
+        QueryShape::of(command).unwrap()
+    }
+
+    #[test]
+    fn queries_differing_only_in_values_share_a_shape() {
+        let first = shape_of(&rawdoc! {
+            "find": "orders",
+            "filter": { "a": 1, "b": { "$gt": "x" }, "c": { "$in": [1, 2, 3] } },
+            "limit": 10,
+            "$db": "sales",
+        });
+        let second = shape_of(&rawdoc! {
+            "find": "orders",
+            "filter": { "a": 2.5_f64, "b": { "$gt": "y" }, "c": { "$in": [7_i64] } },
+            "limit": 1,
+            "$db": "sales",
+        });
+
+        assert_eq!(first, second);
+        assert_eq!(
+            first.shape(),
+            r#"{"find":{"filter":{"a":"?number","b":{"$gt":"?string"},"c":{"$in":["?number"]}}}}"#
+        );
+        assert_eq!(first.hash().len(), 16);
+    }
+
+    #[test]
+    fn different_fields_or_operators_change_the_shape() {
+        let base = shape_of(&rawdoc! { "find": "c", "filter": { "a": 1 } });
+
+        for other in [
+            rawdoc! { "find": "c", "filter": { "b": 1 } },
+            rawdoc! { "find": "c", "filter": { "a": { "$gt": 1 } } },
+            rawdoc! { "find": "c", "filter": { "a": "1" } },
+            rawdoc! { "find": "c", "filter": { "a": 1 }, "projection": { "a": 1 } },
+            rawdoc! { "count": "c", "query": { "a": 1 } },
+        ] {
+            assert_ne!(shape_of(&other).hash(), base.hash(), "{other:?}");
+        }
+    }
+
+    #[test]
+    fn update_statements_collapse_to_distinct_filter_shapes() {
+        let update = shape_of(&rawdoc! {
+            "update": "c",
+            "updates": [
+                { "q": { "_id": 1 }, "u": { "$set": { "x": 1 } } },
+                { "q": { "_id": 2 }, "u": { "$set": { "y": 2 } } },
+            ],
+        });
+
+        assert_eq!(
+            update.shape(),
+            r#"{"update":{"updates":[{"q":{"_id":"?number"}}]}}"#
+        );
+    }
+
+    #[test]
+    fn commands_without_a_query_have_no_shape() {
+        assert!(QueryShape::of(&rawdoc! { "insert": "c", "documents": [] }).is_none());
+        assert!(QueryShape::of(&rawdoc! {}).is_none());
+    }
+}
@@ -0,0 +1,262 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/telemetry/redaction.rs
+ *
+ * Redaction of sensitive command fields before a command document is
+ * logged or attached to telemetry.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::fmt::{self, Display, Formatter};
+
+use bson::{Bson, RawArray, RawBsonRef, RawDocument};
+
+/// Fields whose values are always redacted, regardless of configuration.
+pub const DEFAULT_REDACTED_FIELDS: [&str; 3] = ["payload", "pwd", "saslPayload"];
+
+/// Fields holding the documents and queries of users, such as the `filter` of a find.
+///
+/// Unless user data is logged, every value within them is redacted while their field names
+/// are kept, so the shape of a query still shows.
+pub const USER_DATA_FIELDS: [&str; 7] = [
+    "documents",
+    "filter",
+    "pipeline",
+    "q",
+    "query",
+    "u",
+    "update",
+];
+
+const REDACTED_VALUE: &str = "\"***\"";
+const INVALID_VALUE: &str = "<invalid>";
+
+/// Lazily formats a raw command document with the values of sensitive fields, and by default
+/// of user data, replaced by `"***"`.
+///
+/// Formatting walks the raw BSON directly; nothing is copied or allocated until the
+/// value is actually written, so passing this to a disabled log statement is free.
+#[derive(Debug, Clone, Copy)]
+pub struct RedactedDocument<'a> {
+    document: &'a RawDocument,
+    redacted_fields: &'a [String],
+    log_user_data: bool,
+}
+
+impl<'a> RedactedDocument<'a> {
+    /// `redacted_fields` are matched in addition to [`DEFAULT_REDACTED_FIELDS`], at any nesting level.
+    /// The values within [`USER_DATA_FIELDS`] are only written when `log_user_data` is set.
+    #[must_use]
+    pub const fn new(
+        document: &'a RawDocument,
+        redacted_fields: &'a [String],
+        log_user_data: bool,
+    ) -> Self {
+        Self {
+            document,
+            redacted_fields,
+            log_user_data,
+        }
+    }
+
+    fn is_redacted(&self, key: &str) -> bool {
+        DEFAULT_REDACTED_FIELDS.contains(&key) || self.redacted_fields.iter().any(|f| f == key)
+    }
+
+    /// Whether the values under `key` are user data to redact. A command naming its collection
+    /// in such a field, as `update` does, keeps the name.
+    fn is_user_data(&self, key: &str, value: RawBsonRef<'_>) -> bool {
+        !self.log_user_data
+            && USER_DATA_FIELDS.contains(&key)
+            && matches!(value, RawBsonRef::Document(_) | RawBsonRef::Array(_))
+    }
+
+    fn fmt_document(
+        &self,
+        document: &RawDocument,
+        in_user_data: bool,
+        f: &mut Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str("{")?;
+        for (i, element) in document.into_iter().enumerate() {
+            f.write_str(if i == 0 { " " } else { ", " })?;
+            let Ok((key, value)) = element else {
+                f.write_str(INVALID_VALUE)?;
+                break;
+            };
+            write!(f, "\"{key}\": ")?;
+            if self.is_redacted(key) {
+                f.write_str(REDACTED_VALUE)?;
+            } else {
+                self.fmt_value(value, in_user_data || self.is_user_data(key, value), f)?;
+            }
+        }
+        f.write_str(" }")
+    }
+
+    fn fmt_array(
+        &self,
+        array: &RawArray,
+        in_user_data: bool,
+        f: &mut Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str("[")?;
+        for (i, value) in array.into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            let Ok(value) = value else {
+                f.write_str(INVALID_VALUE)?;
+                break;
+            };
+            self.fmt_value(value, in_user_data, f)?;
+        }
+        f.write_str("]")
+    }
+
+    fn fmt_value(
+        &self,
+        value: RawBsonRef<'_>,
+        in_user_data: bool,
+        f: &mut Formatter<'_>,
+    ) -> fmt::Result {
+        match value {
+            RawBsonRef::Document(document) => self.fmt_document(document, in_user_data, f),
+            RawBsonRef::Array(array) => self.fmt_array(array, in_user_data, f),
+            _ if in_user_data => f.write_str(REDACTED_VALUE),
+            scalar => match Bson::try_from(scalar.to_raw_bson()) {
+                Ok(bson) => write!(f, "{bson}"),
+                Err(_) => f.write_str(INVALID_VALUE),
+            },
+        }
+    }
+}
+
+impl Display for RedactedDocument<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_document(self.document, false, f)
+    }
+}
+
+/// Logs an incoming command at debug level with sensitive fields, and unless `log_user_data`
+/// is set the values of its filters and documents, redacted.
+pub fn log_command_received(
+    activity_id: &str,
+    command: &RawDocument,
+    redacted_fields: &[String],
+    log_user_data: bool,
+) {
+    tracing::debug!(
+        activity_id = activity_id,
+        command = %RedactedDocument::new(command, redacted_fields, log_user_data),
+        "Received command."
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    use bson::rawdoc;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .lock()
+                .map_err(|e| std::io::Error::other(e.to_string()))?
+                .extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn redacts_default_fields() {
+        let doc = rawdoc! {
+            "saslStart": 1,
+            "mechanism": "SCRAM-SHA-256",
+            "payload": "c2VjcmV0LXBheWxvYWQ=",
+        };
+        let formatted = RedactedDocument::new(&doc, &[], false).to_string();
+
+        assert!(!formatted.contains("c2VjcmV0LXBheWxvYWQ="));
+        assert!(formatted.contains("\"payload\": \"***\""));
+        assert!(formatted.contains("SCRAM-SHA-256"));
+    }
+
+    #[test]
+    fn redacts_nested_and_configured_fields() {
+        let doc = rawdoc! {
+            "createUser": "alice",
+            "pwd": "hunter2",
+            "filter": { "ssn": "123-45-6789", "age": 30 },
+            "documents": [{ "ssn": "987-65-4321" }],
+        };
+        let extra = vec!["ssn".to_owned()];
+        let formatted = RedactedDocument::new(&doc, &extra, true).to_string();
+
+        assert!(!formatted.contains("hunter2"));
+        assert!(!formatted.contains("123-45-6789"));
+        assert!(!formatted.contains("987-65-4321"));
+        assert!(formatted.contains("\"age\": 30"));
+        assert!(formatted.contains("alice"));
+    }
+
+    #[test]
+    fn filter_values_are_redacted_unless_user_data_is_logged() {
+        let doc = rawdoc! {
+            "update": "people",
+            "updates": [{ "q": { "ssn": "123-45-6789" }, "u": { "$set": { "age": 31 } } }],
+            "ordered": true,
+        };
+        let formatted = RedactedDocument::new(&doc, &[], false).to_string();
+
+        assert!(!formatted.contains("123-45-6789"));
+        assert!(!formatted.contains("31"));
+        assert!(formatted.contains("\"ssn\": \"***\""));
+        assert!(formatted.contains("\"$set\": { \"age\": \"***\" }"));
+        assert!(formatted.contains("\"update\": \"people\""));
+        assert!(formatted.contains("\"ordered\": true"));
+
+        let formatted = RedactedDocument::new(&doc, &[], true).to_string();
+        assert!(formatted.contains("123-45-6789"));
+    }
+
+    #[test]
+    fn sasl_start_payload_never_logged() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        let doc = rawdoc! {
+            "saslStart": 1,
+            "mechanism": "SCRAM-SHA-256",
+            "payload": "bj0sLG49dXNlcixyPXNlY3JldA==",
+            "$db": "admin",
+        };
+        tracing::subscriber::with_default(subscriber, || {
+            log_command_received("activity", &doc, &[], true);
+        });
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("Received command."));
+        assert!(output.contains("saslStart"));
+        assert!(!output.contains("bj0sLG49dXNlcixyPXNlY3JldA=="));
+    }
+}
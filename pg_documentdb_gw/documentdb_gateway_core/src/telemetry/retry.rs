@@ -0,0 +1,292 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * src/telemetry/retry.rs
+ *
+ * Bounded retry with exponential backoff for OTLP exports, so that a brief
+ * collector restart does not drop a whole interval of telemetry.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{future::Future, time::Duration};
+
+use opentelemetry_sdk::{
+    error::{OTelSdkError, OTelSdkResult},
+    metrics::{data::ResourceMetrics, exporter::PushMetricExporter, Temporality},
+    trace::{SpanData, SpanExporter},
+    Resource,
+};
+use rand::Rng;
+
+/// gRPC status codes the OTLP specification marks as retryable.
+const RETRYABLE_GRPC_CODES: [&str; 6] = [
+    "Cancelled",
+    "DeadlineExceeded",
+    "Aborted",
+    "OutOfRange",
+    "Unavailable",
+    "DataLoss",
+];
+
+/// How failed exports are retried before the batch is dropped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Fraction in `[0, 1]` by which each backoff is randomly shortened or lengthened.
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Backoff before retry number `retry` (starting at 0), doubling up to `max_backoff`.
+    #[must_use]
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(retry))
+            .min(self.max_backoff);
+        if self.jitter > 0.0 {
+            backoff.mul_f64(1.0 + rand::thread_rng().gen_range(-self.jitter..=self.jitter))
+        } else {
+            backoff
+        }
+    }
+
+    async fn run<F, Fut>(&self, signal: &str, mut export: F) -> OTelSdkResult
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = OTelSdkResult>,
+    {
+        let mut retry = 0;
+        loop {
+            match export().await {
+                Err(e) if is_retryable(&e) && retry < self.max_retries => {
+                    tracing::debug!("Retrying failed OTLP {signal} export: {e}");
+                    sleep(self.backoff(retry)).await;
+                    retry += 1;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Dropping OTLP {signal} batch after {} failed export attempts: {e}",
+                        retry + 1
+                    );
+                    return Err(e);
+                }
+                Ok(()) => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Returns true for transient gRPC failures, such as an unavailable collector.
+///
+/// The exporters only surface the tonic status as text, so the code is read from it.
+pub(crate) fn is_retryable(error: &OTelSdkError) -> bool {
+    match error {
+        OTelSdkError::InternalFailure(message) => {
+            grpc_code(message).is_some_and(|code| RETRYABLE_GRPC_CODES.contains(&code))
+        }
+        _ => false,
+    }
+}
+
+/// Extracts the status code from a tonic `Status` rendered with `Display` or `Debug`.
+fn grpc_code(message: &str) -> Option<&str> {
+    ["status: ", "code: "].iter().find_map(|prefix| {
+        let rest = &message[message.find(prefix)? + prefix.len()..];
+        let end = rest
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        Some(&rest[..end])
+    })
+}
+
+/// Exports run on the SDK's background threads, which may not have a tokio runtime.
+async fn sleep(duration: Duration) {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::time::sleep(duration).await;
+    } else {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Wraps a span exporter so that transient export failures are retried.
+#[derive(Debug)]
+pub struct RetrySpanExporter<E> {
+    inner: E,
+    policy: RetryPolicy,
+}
+
+impl<E> RetrySpanExporter<E> {
+    #[must_use]
+    pub const fn new(inner: E, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for RetrySpanExporter<E> {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        self.policy
+            .run("traces", || self.inner.export(batch.clone()))
+            .await
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.inner.set_resource(resource);
+    }
+}
+
+/// Wraps a metric exporter so that transient export failures are retried.
+#[derive(Debug)]
+pub struct RetryMetricExporter<E> {
+    inner: E,
+    policy: RetryPolicy,
+}
+
+impl<E> RetryMetricExporter<E> {
+    #[must_use]
+    pub const fn new(inner: E, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+impl<E: PushMetricExporter> PushMetricExporter for RetryMetricExporter<E> {
+    async fn export(&self, metrics: &ResourceMetrics) -> OTelSdkResult {
+        self.policy
+            .run("metrics", || self.inner.export(metrics))
+            .await
+    }
+
+    fn force_flush(&self) -> OTelSdkResult {
+        self.inner.force_flush()
+    }
+
+    fn shutdown_with_timeout(&self, timeout: Duration) -> OTelSdkResult {
+        self.inner.shutdown_with_timeout(timeout)
+    }
+
+    fn temporality(&self) -> Temporality {
+        self.inner.temporality()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        max_retries: 2,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(2),
+        jitter: 0.0,
+    };
+
+    /// Fails with each of `errors` in turn, then succeeds.
+    #[derive(Debug, Default)]
+    struct FlakyExporter {
+        errors: Vec<&'static str>,
+        attempts: AtomicU32,
+    }
+
+    impl SpanExporter for FlakyExporter {
+        async fn export(&self, _batch: Vec<SpanData>) -> OTelSdkResult {
+            let attempt = self.attempts.fetch_add(1, Ordering::Relaxed) as usize;
+            self.errors.get(attempt).map_or(Ok(()), |e| {
+                Err(OTelSdkError::InternalFailure((*e).to_owned()))
+            })
+        }
+    }
+
+    const UNAVAILABLE: &str = "status: Unavailable, message: \"connection refused\"";
+    const INVALID_ARGUMENT: &str =
+        "Status { code: InvalidArgument, message: \"bad payload\", details: [], metadata: {} }";
+
+    #[test]
+    fn classifies_grpc_codes() {
+        assert!(is_retryable(&OTelSdkError::InternalFailure(
+            UNAVAILABLE.to_owned()
+        )));
+        assert!(is_retryable(&OTelSdkError::InternalFailure(
+            "Status { code: DeadlineExceeded, message: \"\" }".to_owned()
+        )));
+        assert!(!is_retryable(&OTelSdkError::InternalFailure(
+            INVALID_ARGUMENT.to_owned()
+        )));
+        assert!(!is_retryable(&OTelSdkError::AlreadyShutdown));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(350),
+            jitter: 0.0,
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+
+        let jittered = RetryPolicy {
+            jitter: 0.5,
+            ..policy
+        };
+        let backoff = jittered.backoff(0);
+        assert!(backoff >= Duration::from_millis(50) && backoff <= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried() {
+        let exporter = RetrySpanExporter::new(
+            FlakyExporter {
+                errors: vec![UNAVAILABLE, UNAVAILABLE],
+                ..Default::default()
+            },
+            POLICY,
+        );
+
+        exporter.export(Vec::new()).await.unwrap();
+        assert_eq!(exporter.inner.attempts.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let exporter = RetrySpanExporter::new(
+            FlakyExporter {
+                errors: vec![UNAVAILABLE; 3],
+                ..Default::default()
+            },
+            POLICY,
+        );
+
+        exporter.export(Vec::new()).await.unwrap_err();
+        assert_eq!(exporter.inner.attempts.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn permanent_failures_are_not_retried() {
+        let exporter = RetrySpanExporter::new(
+            FlakyExporter {
+                errors: vec![INVALID_ARGUMENT],
+                ..Default::default()
+            },
+            POLICY,
+        );
+
+        exporter.export(Vec::new()).await.unwrap_err();
+        assert_eq!(exporter.inner.attempts.load(Ordering::Relaxed), 1);
+    }
+}
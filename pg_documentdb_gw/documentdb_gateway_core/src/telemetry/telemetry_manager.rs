@@ -6,29 +6,57 @@
  *-------------------------------------------------------------------------
  */
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+};
 
+use arc_swap::ArcSwapOption;
 use opentelemetry::{global, KeyValue};
-use opentelemetry_sdk::{metrics::SdkMeterProvider, Resource};
+use opentelemetry_sdk::{
+    metrics::SdkMeterProvider,
+    trace::{SdkTracerProvider, SpanExporter},
+    Resource,
+};
+use tokio_util::sync::CancellationToken;
 
 use crate::{
     error::{DocumentDBError, Result},
-    telemetry::{config::TelemetryConfig, metrics::create_metrics_provider},
+    telemetry::{
+        config::TelemetryConfig,
+        exporter_slot::ExporterSlot,
+        metrics::{create_metrics_exporter, create_metrics_provider, OtlpMetricExporter},
+        query_shape::set_query_shape_enabled,
+        trace_context::set_trace_context_extractor,
+        traces::{
+            create_span_exporter, create_tracer_provider, set_tracing_enabled, OtlpSpanExporter,
+        },
+    },
 };
 
+/// The manager of the running providers, for admin commands to flush or re-point.
+static ACTIVE: ArcSwapOption<TelemetryManager> = ArcSwapOption::const_empty();
+
 /// Manages OpenTelemetry providers for telemetry signals.
 ///
-/// Currently supports metrics. Tracing and logging will be added in follow-up PRs.
-#[derive(Debug)]
+/// Currently supports metrics and tracing. Logging will be added in a follow-up PR.
+#[derive(Debug, Clone)]
 pub struct TelemetryManager {
     meter_provider: Option<SdkMeterProvider>,
+    tracer_provider: Option<SdkTracerProvider>,
+    metrics_exporter: Option<ExporterSlot<OtlpMetricExporter>>,
+    span_exporter: Option<ExporterSlot<OtlpSpanExporter>>,
+    resource: Resource,
+    config: Arc<Mutex<TelemetryConfig>>,
+    /// Stops the Prometheus scrape endpoint, if one is served.
+    metrics_server: CancellationToken,
 }
 
 impl TelemetryManager {
     /// # Errors
     ///
     /// Returns an error if telemetry attributes contain reserved keys (`service.name` or `service.version`),
-    /// or if the OTLP metrics provider fails to initialize.
+    /// or if the OTLP metrics or tracing provider fails to initialize.
     pub fn init_telemetry(
         config: &TelemetryConfig,
         attributes: Option<HashMap<String, String>>,
@@ -56,29 +84,156 @@ impl TelemetryManager {
         resource_attributes.push(KeyValue::new("service.name", config.service_name()));
         resource_attributes.push(KeyValue::new("service.version", config.service_version()));
 
+        let resource = Resource::builder()
+            .with_attributes(resource_attributes)
+            .build();
+
         if !config.any_signal_enabled() {
             return Ok(Self {
                 meter_provider: None,
+                tracer_provider: None,
+                metrics_exporter: None,
+                span_exporter: None,
+                resource,
+                config: Arc::new(Mutex::new(config.clone())),
+                metrics_server: CancellationToken::new(),
             });
         }
 
-        let resource = Resource::builder()
-            .with_attributes(resource_attributes)
-            .build();
-
-        let meter_provider = create_metrics_provider(config.metrics(), resource)?;
+        let metrics_server = CancellationToken::new();
+        let (meter_provider, metrics_exporter) = create_metrics_provider(
+            config.metrics(),
+            config.tls(),
+            config.retry(),
+            resource.clone(),
+            metrics_server.clone(),
+        )?
+        .unzip();
 
         if let Some(ref provider) = meter_provider {
             global::set_meter_provider(provider.clone());
         }
 
-        Ok(Self { meter_provider })
+        let (tracer_provider, span_exporter) = create_tracer_provider(
+            config.tracing(),
+            config.tls(),
+            config.retry(),
+            config.export_queue(),
+            resource.clone(),
+        )?
+        .unzip();
+
+        if let Some(ref provider) = tracer_provider {
+            global::set_tracer_provider(provider.clone());
+            set_trace_context_extractor(config.tracing().trace_context_extractor());
+            set_query_shape_enabled(config.tracing().query_shape_enabled());
+            set_tracing_enabled(true);
+        }
+
+        let manager = Self {
+            meter_provider,
+            tracer_provider,
+            metrics_exporter: metrics_exporter.flatten(),
+            span_exporter,
+            resource,
+            config: Arc::new(Mutex::new(config.clone())),
+            metrics_server,
+        };
+        ACTIVE.store(Some(Arc::new(manager.clone())));
+        Ok(manager)
+    }
+
+    /// The manager of the running providers, if telemetry was initialized with a signal enabled.
+    #[must_use]
+    pub fn active() -> Option<Arc<Self>> {
+        ACTIVE.load_full()
     }
 
+    /// The configuration the exporters were last built from.
+    #[must_use]
+    pub fn config(&self) -> TelemetryConfig {
+        self.config
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Exports the metrics and spans the providers hold, without waiting for their next
+    /// scheduled export.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either provider fails to flush, after both have been tried.
+    pub fn force_flush(&self) -> Result<()> {
+        let tracer_result = self
+            .tracer_provider
+            .as_ref()
+            .map_or(Ok(()), SdkTracerProvider::force_flush);
+        let meter_result = self
+            .meter_provider
+            .as_ref()
+            .map_or(Ok(()), SdkMeterProvider::force_flush);
+
+        tracer_result.map_err(|e| {
+            DocumentDBError::internal_error(format!("Failed to flush tracer provider: {e}"))
+        })?;
+        meter_result.map_err(|e| {
+            DocumentDBError::internal_error(format!("Failed to flush meter provider: {e}"))
+        })
+    }
+
+    /// Rebuilds the OTLP exporters from `config`, such as to point them at a new endpoint,
+    /// and swaps them in for the exports that follow.
+    ///
+    /// The providers are kept, so instruments, tracers and the tracing subscriber stay in
+    /// place, and settings read when they were built, such as the sampler and the export
+    /// interval, keep their values. Only signals exporting over OTLP are rebuilt.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the meter provider fails to shut down.
+    /// Returns an error if an exporter fails to build, in which case the running exporters
+    /// are left in place.
+    pub fn reconfigure(&self, config: TelemetryConfig) -> Result<()> {
+        // Both exporters are built before either is swapped in.
+        let metrics_exporter = self
+            .metrics_exporter
+            .as_ref()
+            .map(|_| create_metrics_exporter(config.metrics(), config.tls(), config.retry()))
+            .transpose()?;
+        let span_exporter = self
+            .span_exporter
+            .as_ref()
+            .map(|_| create_span_exporter(config.tracing(), config.tls(), config.retry()))
+            .transpose()?;
+
+        if let (Some(slot), Some(exporter)) = (&self.metrics_exporter, metrics_exporter) {
+            slot.replace(exporter);
+        }
+        if let (Some(slot), Some(mut exporter)) = (&self.span_exporter, span_exporter) {
+            exporter.set_resource(&self.resource);
+            slot.replace(exporter);
+        }
+
+        *self.config.lock().unwrap_or_else(PoisonError::into_inner) = config;
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the meter or tracer provider fails to shut down.
     pub fn shutdown(self) -> Result<()> {
+        ACTIVE.store(None);
+        self.metrics_server.cancel();
+
+        if let Some(tracer_provider) = self.tracer_provider {
+            set_tracing_enabled(false);
+            if let Err(e) = tracer_provider.shutdown() {
+                return Err(DocumentDBError::internal_error(format!(
+                    "Failed to shutdown tracer provider: {e}"
+                )));
+            }
+        }
+
         if let Some(meter_provider) = self.meter_provider {
             if let Err(e) = meter_provider.shutdown() {
                 return Err(DocumentDBError::internal_error(format!(
@@ -90,3 +245,127 @@ impl TelemetryManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry_sdk::{
+        metrics::{InMemoryMetricExporter, PeriodicReader},
+        trace::InMemorySpanExporter,
+    };
+
+    use std::time::Duration;
+
+    use super::*;
+    use crate::telemetry::{
+        config::{TelemetryOptions, TlsOptions},
+        prometheus::create_prometheus_provider,
+        traces::TracingOptions,
+    };
+
+    fn tracing_config(endpoint: &str, tls: Option<TlsOptions>) -> TelemetryConfig {
+        TelemetryConfig::new(Some(&TelemetryOptions {
+            tracing: Some(TracingOptions {
+                enabled: Some(true),
+                otlp_endpoint: Some(endpoint.to_owned()),
+                ..Default::default()
+            }),
+            tls,
+            ..Default::default()
+        }))
+    }
+
+    fn manager_with(
+        meter_provider: Option<SdkMeterProvider>,
+        tracer_provider: Option<SdkTracerProvider>,
+    ) -> TelemetryManager {
+        TelemetryManager {
+            meter_provider,
+            tracer_provider,
+            metrics_exporter: None,
+            span_exporter: None,
+            resource: Resource::builder().build(),
+            config: Arc::new(Mutex::new(TelemetryConfig::new(None))),
+            metrics_server: CancellationToken::new(),
+        }
+    }
+
+    #[test]
+    fn force_flush_with_providers() {
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(InMemoryMetricExporter::default()).build())
+            .build();
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_simple_exporter(InMemorySpanExporter::default())
+            .build();
+
+        let manager = manager_with(Some(meter_provider), Some(tracer_provider));
+        manager.force_flush().unwrap();
+        manager.shutdown().unwrap();
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_the_prometheus_endpoint() {
+        let metrics_server = CancellationToken::new();
+        let (meter_provider, addr) = create_prometheus_provider(
+            Resource::builder().build(),
+            "127.0.0.1:0",
+            metrics_server.clone(),
+        )
+        .unwrap();
+        let manager = TelemetryManager {
+            metrics_server,
+            ..manager_with(Some(meter_provider), None)
+        };
+
+        manager.shutdown().unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        tokio::net::TcpStream::connect(addr).await.unwrap_err();
+    }
+
+    #[test]
+    fn force_flush_without_providers() {
+        manager_with(None, None).force_flush().unwrap();
+    }
+
+    #[tokio::test]
+    async fn reconfigure_swaps_exporters_only_when_all_build() {
+        let config = tracing_config("http://localhost:4317", None);
+        let (tracer_provider, span_exporter) = create_tracer_provider(
+            config.tracing(),
+            config.tls(),
+            config.retry(),
+            config.export_queue(),
+            Resource::builder().build(),
+        )
+        .unwrap()
+        .unwrap();
+        let manager = TelemetryManager {
+            span_exporter: Some(span_exporter),
+            config: Arc::new(Mutex::new(config)),
+            ..manager_with(None, Some(tracer_provider))
+        };
+
+        let missing_ca = TlsOptions {
+            ca_file: Some("/nonexistent/ca.pem".to_owned()),
+            ..Default::default()
+        };
+        manager
+            .reconfigure(tracing_config("https://collector:4317", Some(missing_ca)))
+            .unwrap_err();
+        assert_eq!(
+            manager.config().tracing().otlp_endpoint(),
+            "http://localhost:4317"
+        );
+
+        manager
+            .reconfigure(tracing_config("http://collector:4317", None))
+            .unwrap();
+        assert_eq!(
+            manager.config().tracing().otlp_endpoint(),
+            "http://collector:4317"
+        );
+        manager.force_flush().unwrap();
+        manager.shutdown().unwrap();
+    }
+}
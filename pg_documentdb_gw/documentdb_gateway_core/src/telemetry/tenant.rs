@@ -0,0 +1,61 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * src/telemetry/tenant.rs
+ *
+ * Maps the identity a connection authenticated as to the tenant its requests
+ * are recorded under, so that spans and metrics can be filtered per tenant
+ * while the resource stays shared by the whole process.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+/// Attribute carrying the tenant of the connection's identity.
+pub(crate) const TENANT_ATTRIBUTE: &str = "db.documentdb.tenant";
+
+/// Tenants keyed by connection identity, from a JSON object such as
+/// `{"users": {"alice": "team_a"}, "roles": {"reporting_role": "team_b"}}`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantMapping {
+    #[serde(default)]
+    pub users: HashMap<String, String>,
+    #[serde(default)]
+    pub roles: HashMap<String, String>,
+}
+
+impl TenantMapping {
+    /// The tenant of a connection: that of its user if mapped, otherwise that of the first
+    /// of its roles that is mapped. Connections that haven't authenticated have none.
+    #[must_use]
+    pub fn tenant(&self, username: Option<&str>, roles: &[String]) -> Option<&str> {
+        let username = username?;
+        self.users
+            .get(username)
+            .or_else(|| roles.iter().find_map(|role| self.roles.get(role)))
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_mapping_takes_precedence_over_roles() {
+        let mapping: TenantMapping = serde_json::from_str(
+            r#"{"users": {"alice": "team_a"}, "roles": {"reporting_role": "team_b"}}"#,
+        )
+        .unwrap();
+        let roles = vec!["app_role".to_owned(), "reporting_role".to_owned()];
+
+        assert_eq!(mapping.tenant(Some("alice"), &roles), Some("team_a"));
+        assert_eq!(mapping.tenant(Some("bob"), &roles), Some("team_b"));
+        assert_eq!(mapping.tenant(Some("bob"), &[]), None);
+        assert_eq!(mapping.tenant(None, &roles), None);
+    }
+}
@@ -0,0 +1,323 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/telemetry/trace_context.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    borrow::Cow,
+    str::FromStr,
+    sync::{LazyLock, PoisonError, RwLock},
+};
+
+use bson::{RawBsonRef, RawDocument};
+use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceId, TraceState};
+
+/// Sources consulted when `TraceContextSources` is not configured.
+const DEFAULT_SOURCES: &[TraceContextSource] = &[TraceContextSource::Comment];
+
+/// The extractor request spans use, installed with the tracer provider.
+static EXTRACTOR: LazyLock<RwLock<TraceContextExtractor>> =
+    LazyLock::new(|| RwLock::new(TraceContextExtractor::default()));
+
+/// Where a client may carry the W3C trace context a request span is parented to.
+///
+/// Each source holds either a `traceparent` string or a document with a `traceparent`
+/// field and an optional `tracestate` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceContextSource {
+    /// The command's `comment`, e.g. `comment: { traceparent: "00-…" }`.
+    Comment,
+    /// A top-level `$trace` field of the command.
+    TraceField,
+    /// The `client` metadata document sent in the connection handshake.
+    HandshakeMetadata,
+}
+
+impl FromStr for TraceContextSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "comment" | "comment.traceparent" => Ok(Self::Comment),
+            "$trace" => Ok(Self::TraceField),
+            "handshake" => Ok(Self::HandshakeMetadata),
+            other => Err(format!("unsupported trace context source: {other}")),
+        }
+    }
+}
+
+/// Finds the trace context a request span continues, trying each configured source in
+/// order and returning the first valid one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContextExtractor {
+    sources: Vec<TraceContextSource>,
+}
+
+impl Default for TraceContextExtractor {
+    fn default() -> Self {
+        Self::new(DEFAULT_SOURCES.to_vec())
+    }
+}
+
+/// Parses a comma-separated list of sources, such as `comment,$trace,handshake`.
+impl FromStr for TraceContextExtractor {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        value
+            .split(',')
+            .filter(|source| !source.trim().is_empty())
+            .map(str::parse)
+            .collect::<std::result::Result<_, _>>()
+            .map(Self::new)
+    }
+}
+
+impl TraceContextExtractor {
+    #[must_use]
+    pub const fn new(sources: Vec<TraceContextSource>) -> Self {
+        Self { sources }
+    }
+
+    #[must_use]
+    pub fn sources(&self) -> &[TraceContextSource] {
+        &self.sources
+    }
+
+    /// Returns the remote span context carried by `command` or the connection's handshake
+    /// `client_metadata`. Sources that are absent or malformed are skipped.
+    #[must_use]
+    pub fn extract(
+        &self,
+        command: &RawDocument,
+        client_metadata: Option<&RawDocument>,
+    ) -> Option<SpanContext> {
+        self.sources.iter().find_map(|source| {
+            let carrier = match source {
+                TraceContextSource::Comment => command.get("comment").ok().flatten(),
+                TraceContextSource::TraceField => command.get("$trace").ok().flatten(),
+                TraceContextSource::HandshakeMetadata => client_metadata.map(RawBsonRef::Document),
+            };
+            carrier.and_then(span_context_from_carrier)
+        })
+    }
+}
+
+fn span_context_from_carrier(carrier: RawBsonRef<'_>) -> Option<SpanContext> {
+    match carrier {
+        RawBsonRef::String(traceparent) => parse_traceparent(traceparent, TraceState::NONE),
+        RawBsonRef::Document(document) => {
+            let traceparent = document.get_str("traceparent").ok()?;
+            let trace_state = document
+                .get_str("tracestate")
+                .ok()
+                .and_then(|state| TraceState::from_str(state).ok())
+                .unwrap_or(TraceState::NONE);
+            parse_traceparent(traceparent, trace_state)
+        }
+        _ => None,
+    }
+}
+
+/// Parses a W3C `traceparent` header value (`version-traceid-spanid-flags`).
+fn parse_traceparent(traceparent: &str, trace_state: TraceState) -> Option<SpanContext> {
+    let mut parts = traceparent.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let span_id = parts.next()?;
+    let flags = parts.next()?;
+
+    // Later versions may append fields, but version 00 has exactly four.
+    if version.len() != 2 || version == "ff" || (version == "00" && parts.next().is_some()) {
+        return None;
+    }
+    u8::from_str_radix(version, 16).ok()?;
+    if trace_id.len() != 32 || span_id.len() != 16 || flags.len() != 2 {
+        return None;
+    }
+
+    let trace_id = TraceId::from_hex(trace_id).ok()?;
+    let span_id = SpanId::from_hex(span_id).ok()?;
+    let flags = u8::from_str_radix(flags, 16).ok()?;
+    let span_context = SpanContext::new(
+        trace_id,
+        span_id,
+        TraceFlags::new(flags) & TraceFlags::SAMPLED,
+        true,
+        trace_state,
+    );
+    span_context.is_valid().then_some(span_context)
+}
+
+/// Appends `span_context` to `sql` as a sqlcommenter `traceparent` comment, returning
+/// whether one was added. Unsampled and invalid contexts leave `sql` unchanged.
+#[must_use]
+pub fn format_trace_comment_with_flag<'a>(
+    sql: &'a str,
+    span_context: &SpanContext,
+) -> (Cow<'a, str>, bool) {
+    if !span_context.is_valid() || !span_context.is_sampled() {
+        return (Cow::Borrowed(sql), false);
+    }
+    let traceparent = format!(
+        "00-{}-{}-{:02x}",
+        span_context.trace_id(),
+        span_context.span_id(),
+        span_context.trace_flags().to_u8()
+    );
+    (
+        Cow::Owned(format!("{sql} /*traceparent='{traceparent}'*/")),
+        true,
+    )
+}
+
+/// Appends `span_context` to `sql` as a sqlcommenter `traceparent` comment when sampled.
+#[must_use]
+pub fn format_trace_comment<'a>(sql: &'a str, span_context: &SpanContext) -> Cow<'a, str> {
+    format_trace_comment_with_flag(sql, span_context).0
+}
+
+/// Returns the remote parent for a request span, using the installed extractor.
+pub(crate) fn extract_trace_context(
+    command: &RawDocument,
+    client_metadata: Option<&RawDocument>,
+) -> Option<SpanContext> {
+    EXTRACTOR
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .extract(command, client_metadata)
+}
+
+pub(crate) fn set_trace_context_extractor(extractor: TraceContextExtractor) {
+    *EXTRACTOR.write().unwrap_or_else(PoisonError::into_inner) = extractor;
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::rawdoc;
+
+    use super::*;
+
+    const COMMENT_PARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+    const TRACE_PARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00";
+    const HANDSHAKE_PARENT: &str = "00-11111111111111111111111111111111-2222222222222222-01";
+
+    fn trace_id(span_context: Option<SpanContext>) -> String {
+        span_context.unwrap().trace_id().to_string()
+    }
+
+    fn extractor(sources: &str) -> TraceContextExtractor {
+        sources.parse().unwrap()
+    }
+
+    #[test]
+    fn default_consults_only_the_comment() {
+        let extractor = TraceContextExtractor::default();
+        assert_eq!(extractor.sources(), &[TraceContextSource::Comment]);
+
+        let command = rawdoc! { "find": "c", "$trace": TRACE_PARENT };
+        let metadata = rawdoc! { "traceparent": HANDSHAKE_PARENT };
+        assert!(extractor.extract(&command, Some(&metadata)).is_none());
+
+        let command = rawdoc! { "find": "c", "comment": { "traceparent": COMMENT_PARENT } };
+        let span_context = extractor.extract(&command, None).unwrap();
+        assert!(span_context.is_remote());
+        assert!(span_context.is_sampled());
+        assert_eq!(
+            span_context.span_id().to_string(),
+            "b7ad6b7169203331".to_owned()
+        );
+    }
+
+    #[test]
+    fn first_configured_source_wins() {
+        let command = rawdoc! {
+            "find": "c",
+            "comment": { "traceparent": COMMENT_PARENT },
+            "$trace": { "traceparent": TRACE_PARENT, "tracestate": "vendor=value" },
+        };
+        let metadata = rawdoc! { "traceparent": HANDSHAKE_PARENT };
+
+        let span_context = extractor("$trace,comment").extract(&command, Some(&metadata));
+        assert_eq!(
+            span_context.as_ref().unwrap().trace_state().get("vendor"),
+            Some("value")
+        );
+        assert_eq!(trace_id(span_context), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(
+            trace_id(extractor("comment,$trace").extract(&command, Some(&metadata))),
+            "0af7651916cd43dd8448eb211c80319c"
+        );
+        assert_eq!(
+            trace_id(extractor("handshake,comment").extract(&command, Some(&metadata))),
+            "11111111111111111111111111111111"
+        );
+    }
+
+    #[test]
+    fn missing_or_invalid_sources_fall_through() {
+        let command = rawdoc! {
+            "find": "c",
+            "comment": "just a comment",
+            "$trace": { "traceparent": "00-00000000000000000000000000000000-b7ad6b7169203331-01" },
+        };
+        let metadata = rawdoc! { "traceparent": HANDSHAKE_PARENT };
+        let extractor = extractor("comment, $trace, handshake");
+
+        assert_eq!(
+            trace_id(extractor.extract(&command, Some(&metadata))),
+            "11111111111111111111111111111111"
+        );
+        assert!(extractor.extract(&command, None).is_none());
+    }
+
+    #[test]
+    fn parses_traceparent() {
+        let span_context = parse_traceparent(TRACE_PARENT, TraceState::NONE).unwrap();
+        assert!(!span_context.is_sampled());
+
+        for invalid in [
+            "",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-zzf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        ] {
+            assert!(
+                parse_traceparent(invalid, TraceState::NONE).is_none(),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn trace_comment_flag_reports_injection() {
+        let sampled = parse_traceparent(COMMENT_PARENT, TraceState::NONE).unwrap();
+        let (sql, traced) = format_trace_comment_with_flag("SELECT 1", &sampled);
+        assert!(traced);
+        assert_eq!(sql, format!("SELECT 1 /*traceparent='{COMMENT_PARENT}'*/"));
+        assert_eq!(format_trace_comment("SELECT 1", &sampled), sql);
+
+        let unsampled = parse_traceparent(TRACE_PARENT, TraceState::NONE).unwrap();
+        for span_context in [unsampled, SpanContext::empty_context()] {
+            let (sql, traced) = format_trace_comment_with_flag("SELECT 1", &span_context);
+            assert!(!traced);
+            assert!(matches!(sql, Cow::Borrowed("SELECT 1")));
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_sources() {
+        "comment,metadata"
+            .parse::<TraceContextExtractor>()
+            .unwrap_err();
+        assert_eq!(
+            extractor("Comment.traceparent,$TRACE").sources(),
+            &[TraceContextSource::Comment, TraceContextSource::TraceField]
+        );
+    }
+}
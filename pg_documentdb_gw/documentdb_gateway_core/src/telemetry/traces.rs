@@ -0,0 +1,1414 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * src/telemetry/traces.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    collections::HashMap,
+    env,
+    str::FromStr,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::{Duration, SystemTime},
+};
+
+use bson::RawDocument;
+use either::Either;
+use opentelemetry::{
+    global,
+    trace::{
+        Link, SamplingResult, Span, SpanContext, SpanKind, Status, TraceContextExt, TraceId, Tracer,
+    },
+    Context, KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    trace::{Sampler, SdkTracerProvider, ShouldSample},
+    Resource,
+};
+use serde::Deserialize;
+
+use crate::{
+    error::{DocumentDBError, Result},
+    requests::{request_tracker::RequestTracker, Request, RequestIntervalKind},
+    responses::{CommandError, Response},
+    telemetry::{
+        config::{
+            env_var, resolve_compression, with_compression, with_tls, ExportQueueConfig,
+            OtlpCompression, OtlpRetryConfig, OtlpTlsConfig, DEFAULT_EXPORT_TIMEOUT_MS,
+            DEFAULT_OTLP_ENDPOINT,
+        },
+        export_queue::QueuedSpanProcessor,
+        exporter_slot::ExporterSlot,
+        metrics::{operation_attributes, APP_NAME_ATTRIBUTE},
+        query_shape::push_query_shape_attributes,
+        retry::RetrySpanExporter,
+        tenant::TENANT_ATTRIBUTE,
+        trace_context::{extract_trace_context, TraceContextExtractor},
+    },
+};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+const DEFAULT_TRACING_ENABLED: bool = false;
+const DEFAULT_SAMPLING_RATIO: f64 = 1.0;
+const DEFAULT_TRACES_COMPRESSION: OtlpCompression = OtlpCompression::Gzip;
+
+/// Span attribute used to select a per-operation sampling ratio.
+const OPERATION_NAME_ATTRIBUTE: &str = "db.operation.name";
+
+/// Attribute carrying the elapsed time of a request phase, in nanoseconds.
+const PHASE_ELAPSED_ATTRIBUTE: &str = "db.operation.phase.elapsed_ns";
+
+/// Set once a tracer provider has been installed, so the request path can skip
+/// span construction entirely when tracing is off.
+static TRACING_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Default sampling ratio set by `setParameter`, as `f64` bits; NaN until one is set.
+static SAMPLING_RATIO_OVERRIDE: AtomicU64 = AtomicU64::new(f64::NAN.to_bits());
+
+// ============================================================================
+// JSON Configuration
+// ============================================================================
+
+/// JSON configuration for tracing (matches SetupConfiguration.json TelemetryOptions.Tracing)
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct TracingOptions {
+    /// Whether tracing is enabled
+    pub enabled: Option<bool>,
+    /// OTLP endpoint for trace export
+    pub otlp_endpoint: Option<String>,
+    /// Export timeout in milliseconds
+    pub export_timeout_ms: Option<u64>,
+    /// OTLP payload compression, `"gzip"` or `"none"`
+    pub compression: Option<String>,
+    /// How requests are sampled: `"always_on"`, `"always_off"`, `"ratio"` or
+    /// `"parentbased_ratio"`
+    pub sampler: Option<String>,
+    /// Ratio of requests to sample, between 0.0 and 1.0
+    pub sampling_ratio: Option<f64>,
+    /// Per-operation sampling ratios keyed by command name (e.g. `{"insert": 1.0, "find": 0.01}`),
+    /// overriding `SamplingRatio` for the listed operations
+    pub operation_sampling_ratios: Option<HashMap<String, f64>>,
+    /// Where to look for a client's trace context, in order: `"comment"`, `"$trace"` and
+    /// `"handshake"`
+    pub trace_context_sources: Option<Vec<String>>,
+    /// Whether request spans record the normalized shape of the query and its hash
+    pub query_shape_enabled: Option<bool>,
+}
+
+// ============================================================================
+// Runtime Configuration
+// ============================================================================
+
+/// Runtime configuration for tracing with OTLP export.
+///
+/// Stores JSON configuration values and provides accessor methods that implement
+/// the fallback logic: JSON value > environment variable > default constant.
+#[derive(Debug, Clone)]
+pub struct TracingConfig {
+    enabled: Option<bool>,
+    otlp_endpoint: Option<String>,
+    export_timeout_ms: Option<u64>,
+    compression: Option<String>,
+    sampler: Option<String>,
+    sampling_ratio: Option<f64>,
+    operation_sampling_ratios: HashMap<String, f64>,
+    trace_context_sources: Option<Vec<String>>,
+    query_shape_enabled: Option<bool>,
+}
+
+impl TracingConfig {
+    /// Creates tracing config from optional JSON configuration.
+    #[must_use]
+    pub fn new(json_config: Option<&TracingOptions>) -> Self {
+        let json = json_config.cloned().unwrap_or_default();
+
+        Self {
+            enabled: json.enabled,
+            otlp_endpoint: json.otlp_endpoint,
+            export_timeout_ms: json.export_timeout_ms,
+            compression: json.compression,
+            sampler: json.sampler,
+            sampling_ratio: json.sampling_ratio,
+            operation_sampling_ratios: json.operation_sampling_ratios.unwrap_or_default(),
+            trace_context_sources: json.trace_context_sources,
+            query_shape_enabled: json.query_shape_enabled,
+        }
+    }
+
+    /// Whether tracing is enabled. Fallback: JSON > `OTEL_TRACING_ENABLED` > false.
+    #[must_use]
+    pub fn tracing_enabled(&self) -> bool {
+        self.enabled
+            .or_else(|| env_var("OTEL_TRACING_ENABLED"))
+            .unwrap_or(DEFAULT_TRACING_ENABLED)
+    }
+
+    /// OTLP endpoint for traces. Fallback: JSON > `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` > `OTEL_EXPORTER_OTLP_ENDPOINT` > default.
+    #[must_use]
+    pub fn otlp_endpoint(&self) -> String {
+        self.otlp_endpoint
+            .clone()
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"))
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_ENDPOINT"))
+            .unwrap_or_else(|| DEFAULT_OTLP_ENDPOINT.to_owned())
+    }
+
+    /// The same configuration, exporting to `endpoint`.
+    #[must_use]
+    pub fn with_otlp_endpoint(mut self, endpoint: &str) -> Self {
+        self.otlp_endpoint = Some(endpoint.to_owned());
+        self
+    }
+
+    /// Export timeout in ms. Fallback: JSON > `OTEL_EXPORTER_OTLP_TRACES_TIMEOUT` > `OTEL_EXPORTER_OTLP_TIMEOUT` > 10000.
+    #[must_use]
+    pub fn export_timeout_ms(&self) -> u64 {
+        self.export_timeout_ms
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_TRACES_TIMEOUT"))
+            .or_else(|| env_var("OTEL_EXPORTER_OTLP_TIMEOUT"))
+            .unwrap_or(DEFAULT_EXPORT_TIMEOUT_MS)
+    }
+
+    /// How requests are sampled. Fallback: JSON > `OTEL_TRACES_SAMPLER` > `parentbased_ratio`,
+    /// which an unknown value also falls back to.
+    #[must_use]
+    pub fn sampler_kind(&self) -> SamplerKind {
+        let Some(sampler) = self
+            .sampler
+            .clone()
+            .or_else(|| env::var("OTEL_TRACES_SAMPLER").ok())
+        else {
+            return SamplerKind::default();
+        };
+        sampler.parse().unwrap_or_else(|error| {
+            tracing::warn!("Ignoring {error}, using the parentbased_ratio sampler.");
+            SamplerKind::default()
+        })
+    }
+
+    /// Ratio of requests to sample. Fallback: JSON > `OTEL_TRACES_SAMPLER_ARG` > 1.0.
+    #[must_use]
+    pub fn sampling_ratio(&self) -> f64 {
+        self.sampling_ratio
+            .or_else(|| env_var("OTEL_TRACES_SAMPLER_ARG"))
+            .unwrap_or(DEFAULT_SAMPLING_RATIO)
+    }
+
+    /// The sampling ratio in effect, which `setParameter` may have changed since startup.
+    #[must_use]
+    pub fn effective_sampling_ratio(&self) -> f64 {
+        sampling_ratio_override().unwrap_or_else(|| self.sampling_ratio())
+    }
+
+    /// Per-operation overrides of `sampling_ratio`, keyed by command name. JSON only.
+    #[must_use]
+    pub const fn operation_sampling_ratios(&self) -> &HashMap<String, f64> {
+        &self.operation_sampling_ratios
+    }
+
+    /// Sources a request's parent trace context is read from.
+    /// Fallback: JSON > `OTEL_TRACE_CONTEXT_SOURCES` (comma-separated) > comment only.
+    #[must_use]
+    pub fn trace_context_extractor(&self) -> TraceContextExtractor {
+        self.trace_context_sources
+            .as_ref()
+            .and_then(|sources| sources.join(",").parse().ok())
+            .or_else(|| env_var("OTEL_TRACE_CONTEXT_SOURCES"))
+            .unwrap_or_default()
+    }
+
+    /// Whether request spans carry the query shape.
+    /// Fallback: JSON > `OTEL_TRACES_QUERY_SHAPE_ENABLED` > false.
+    #[must_use]
+    pub fn query_shape_enabled(&self) -> bool {
+        self.query_shape_enabled
+            .or_else(|| env_var("OTEL_TRACES_QUERY_SHAPE_ENABLED"))
+            .unwrap_or(false)
+    }
+
+    /// Creates the sampler for request spans.
+    ///
+    /// With `parentbased_ratio`, spans with a parent follow the parent's decision, so a
+    /// request's phase spans are kept or dropped together with the request span. The ratio
+    /// samplers follow [`set_sampling_ratio`] for the life of the sampler.
+    #[must_use]
+    pub fn create_sampler(&self) -> RequestSampler {
+        let ratio_sampler = || {
+            OperationSampler::new(self.sampling_ratio(), self.operation_sampling_ratios())
+                .follow_runtime_ratio()
+        };
+        match self.sampler_kind() {
+            SamplerKind::AlwaysOn => RequestSampler::Sdk(Sampler::AlwaysOn),
+            SamplerKind::AlwaysOff => RequestSampler::Sdk(Sampler::AlwaysOff),
+            SamplerKind::Ratio => RequestSampler::Ratio(ratio_sampler()),
+            SamplerKind::ParentBasedRatio => {
+                RequestSampler::Sdk(Sampler::ParentBased(Box::new(ratio_sampler())))
+            }
+        }
+    }
+
+    /// OTLP payload compression. Fallback: JSON > `OTEL_EXPORTER_OTLP_TRACES_COMPRESSION` > `OTEL_EXPORTER_OTLP_COMPRESSION` > gzip.
+    #[must_use]
+    pub fn compression(&self) -> OtlpCompression {
+        resolve_compression(
+            self.compression.as_deref(),
+            &[
+                "OTEL_EXPORTER_OTLP_TRACES_COMPRESSION",
+                "OTEL_EXPORTER_OTLP_COMPRESSION",
+            ],
+            DEFAULT_TRACES_COMPRESSION,
+        )
+    }
+
+    /// Creates an OTLP export configuration for traces.
+    #[must_use]
+    pub fn create_export_config(&self) -> opentelemetry_otlp::ExportConfig {
+        opentelemetry_otlp::ExportConfig {
+            endpoint: Some(self.otlp_endpoint()),
+            protocol: opentelemetry_otlp::Protocol::Grpc,
+            timeout: Some(Duration::from_millis(self.export_timeout_ms())),
+        }
+    }
+}
+
+// ============================================================================
+// Provider Creation
+// ============================================================================
+
+/// The OTLP span exporter, with retries, that a tracer provider exports through.
+pub type OtlpSpanExporter = RetrySpanExporter<opentelemetry_otlp::SpanExporter>;
+
+/// Builds the OTLP span exporter for `config`.
+///
+/// # Errors
+///
+/// Returns an error if the TLS configuration is invalid or the exporter fails to build.
+pub fn create_span_exporter(
+    config: &TracingConfig,
+    tls: &OtlpTlsConfig,
+    retry: &OtlpRetryConfig,
+) -> Result<OtlpSpanExporter> {
+    let builder = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_export_config(config.create_export_config());
+    let builder = with_tls(builder, tls, &config.otlp_endpoint())?;
+    let exporter = with_compression(builder, config.compression())
+        .build()
+        .map_err(|e| {
+            DocumentDBError::internal_error(format!("Failed to build span exporter: {e}"))
+        })?;
+    Ok(RetrySpanExporter::new(exporter, retry.policy()))
+}
+
+/// Creates an OpenTelemetry tracer provider with batched OTLP export.
+///
+/// Returns `None` if tracing is disabled in config. The provider is returned with the
+/// slot of its exporter.
+///
+/// # Errors
+///
+/// Returns an error if the TLS configuration is invalid, the OTLP span exporter fails to build
+/// or the export thread can't be started.
+pub fn create_tracer_provider(
+    config: &TracingConfig,
+    tls: &OtlpTlsConfig,
+    retry: &OtlpRetryConfig,
+    queue: &ExportQueueConfig,
+    resource: Resource,
+) -> Result<Option<(SdkTracerProvider, ExporterSlot<OtlpSpanExporter>)>> {
+    if !config.tracing_enabled() {
+        return Ok(None);
+    }
+
+    let exporter = ExporterSlot::new(create_span_exporter(config, tls, retry)?);
+    let tracer_provider = SdkTracerProvider::builder()
+        .with_resource(resource)
+        .with_sampler(config.create_sampler())
+        .with_span_processor(QueuedSpanProcessor::new(exporter.clone(), queue)?)
+        .build();
+
+    Ok(Some((tracer_provider, exporter)))
+}
+
+// ============================================================================
+// Sampling
+// ============================================================================
+
+/// The samplers `TracingOptions.Sampler` selects between. The OpenTelemetry names
+/// `traceidratio` and `parentbased_traceidratio` are accepted too.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SamplerKind {
+    /// Samples every span.
+    AlwaysOn,
+    /// Samples no span, while trace context still propagates.
+    AlwaysOff,
+    /// Samples every span by ratio, ignoring the parent's decision.
+    Ratio,
+    /// Samples root spans by ratio and follows the parent's decision otherwise.
+    #[default]
+    ParentBasedRatio,
+}
+
+impl FromStr for SamplerKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "always_on" => Ok(Self::AlwaysOn),
+            "always_off" => Ok(Self::AlwaysOff),
+            "ratio" | "traceidratio" => Ok(Self::Ratio),
+            "parentbased_ratio" | "parentbased_traceidratio" => Ok(Self::ParentBasedRatio),
+            other => Err(format!("unsupported trace sampler: {other}")),
+        }
+    }
+}
+
+/// The sampler installed for request spans.
+#[derive(Debug, Clone)]
+pub enum RequestSampler {
+    /// A sampler built into the SDK.
+    Sdk(Sampler),
+    /// Per-operation ratios applied to every span on its own.
+    Ratio(OperationSampler),
+}
+
+impl ShouldSample for RequestSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let sampler: &dyn ShouldSample = match self {
+            Self::Sdk(sampler) => sampler,
+            Self::Ratio(sampler) => sampler,
+        };
+        sampler.should_sample(parent_context, trace_id, name, span_kind, attributes, links)
+    }
+}
+
+/// Samples root spans by trace ID ratio, choosing the ratio from the span's
+/// `db.operation.name` attribute and falling back to the global ratio for
+/// unlisted operations.
+#[derive(Debug, Clone)]
+pub struct OperationSampler {
+    default_sampler: Sampler,
+    operation_samplers: HashMap<String, Sampler>,
+    runtime_ratio: bool,
+}
+
+impl OperationSampler {
+    #[must_use]
+    pub fn new(default_ratio: f64, operation_ratios: &HashMap<String, f64>) -> Self {
+        Self {
+            default_sampler: Sampler::TraceIdRatioBased(default_ratio),
+            operation_samplers: operation_ratios
+                .iter()
+                .map(|(operation, ratio)| (operation.clone(), Sampler::TraceIdRatioBased(*ratio)))
+                .collect(),
+            runtime_ratio: false,
+        }
+    }
+
+    /// Uses the ratio set by [`set_sampling_ratio`], once there is one, for unlisted operations.
+    #[must_use]
+    pub const fn follow_runtime_ratio(mut self) -> Self {
+        self.runtime_ratio = true;
+        self
+    }
+
+    fn sampler_for(&self, attributes: &[KeyValue]) -> Either<&Sampler, Sampler> {
+        let operation_sampler = attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == OPERATION_NAME_ATTRIBUTE)
+            .and_then(|kv| self.operation_samplers.get(kv.value.as_str().as_ref()));
+        match (operation_sampler, self.runtime_ratio) {
+            (Some(sampler), _) => Either::Left(sampler),
+            (None, true) => sampling_ratio_override()
+                .map_or(Either::Left(&self.default_sampler), |ratio| {
+                    Either::Right(Sampler::TraceIdRatioBased(ratio))
+                }),
+            (None, false) => Either::Left(&self.default_sampler),
+        }
+    }
+}
+
+impl ShouldSample for OperationSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        let sampler = self.sampler_for(attributes);
+        let sampler = match &sampler {
+            Either::Left(sampler) => *sampler,
+            Either::Right(sampler) => sampler,
+        };
+        sampler.should_sample(parent_context, trace_id, name, span_kind, attributes, links)
+    }
+}
+
+/// The default sampling ratio set at runtime, if any.
+#[must_use]
+pub fn sampling_ratio_override() -> Option<f64> {
+    let ratio = f64::from_bits(SAMPLING_RATIO_OVERRIDE.load(Ordering::Relaxed));
+    (!ratio.is_nan()).then_some(ratio)
+}
+
+/// Changes the default sampling ratio of the installed sampler without a restart.
+///
+/// # Errors
+///
+/// Returns `BadValue` unless the ratio is between 0 and 1.
+pub fn set_sampling_ratio(ratio: f64) -> Result<()> {
+    if !(0.0..=1.0).contains(&ratio) {
+        return Err(DocumentDBError::bad_value(format!(
+            "tracingSamplingRatio must be between 0 and 1, got {ratio}"
+        )));
+    }
+    SAMPLING_RATIO_OVERRIDE.store(ratio.to_bits(), Ordering::Relaxed);
+    Ok(())
+}
+
+/// Returns true if a tracer provider is installed and request spans should be emitted.
+#[must_use]
+pub fn is_tracing_enabled() -> bool {
+    TRACING_ENABLED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_tracing_enabled(enabled: bool) {
+    TRACING_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+// ============================================================================
+// Request Spans (recorded directly in the request path)
+// ============================================================================
+
+/// A request phase placed on the wall clock.
+#[derive(Debug)]
+struct PhaseSegment {
+    name: &'static str,
+    parent: Option<&'static str>,
+    start: SystemTime,
+    end: SystemTime,
+    elapsed_ns: i64,
+}
+
+/// Lays out the recorded phases of a request, starting at the tracker's wall-clock anchor.
+///
+/// The tracker only stores accumulated durations, so phases are placed back to back in the
+/// order they run, nested the way the `RequestIntervalKind` intervals overlap:
+///
+/// ```text
+/// read_request
+/// handle_message
+///   format_request
+///   handle_request
+///     open_backend_connection
+///     postgres_begin_transaction
+///     postgres_set_statement_timeout
+///     postgres_execution
+///     postgres_commit
+/// write_response
+/// ```
+///
+/// Time spent across backend retries is collapsed into one segment per phase.
+/// Phases that were never entered (elapsed is zero) are skipped.
+fn phase_segments(request_tracker: &RequestTracker) -> Vec<PhaseSegment> {
+    let mut segments = Vec::new();
+
+    let mut push = |name: &'static str,
+                    parent: Option<&'static str>,
+                    start: SystemTime,
+                    kind: RequestIntervalKind|
+     -> SystemTime {
+        let elapsed_ns = request_tracker.get_interval_elapsed_time(kind);
+        if elapsed_ns <= 0 {
+            return start;
+        }
+
+        let end = start + Duration::from_nanos(elapsed_ns.cast_unsigned());
+        segments.push(PhaseSegment {
+            name,
+            parent,
+            start,
+            end,
+            elapsed_ns,
+        });
+        end
+    };
+
+    let read_request_end = push(
+        "read_request",
+        None,
+        request_tracker.start_time(),
+        RequestIntervalKind::ReadRequest,
+    );
+    let handle_message_end = push(
+        "handle_message",
+        None,
+        read_request_end,
+        RequestIntervalKind::HandleMessage,
+    );
+    let format_request_end = push(
+        "format_request",
+        Some("handle_message"),
+        read_request_end,
+        RequestIntervalKind::FormatRequest,
+    );
+    push(
+        "handle_request",
+        Some("handle_message"),
+        format_request_end,
+        RequestIntervalKind::HandleRequest,
+    );
+
+    let mut backend_phase_start = format_request_end;
+    for (name, kind) in [
+        (
+            "open_backend_connection",
+            RequestIntervalKind::OpenBackendConnection,
+        ),
+        (
+            "postgres_begin_transaction",
+            RequestIntervalKind::PostgresBeginTransaction,
+        ),
+        (
+            "postgres_set_statement_timeout",
+            RequestIntervalKind::PostgresSetStatementTimeout,
+        ),
+        ("postgres_execution", RequestIntervalKind::ProcessRequest),
+        (
+            "postgres_commit",
+            RequestIntervalKind::PostgresCommitTransaction,
+        ),
+    ] {
+        backend_phase_start = push(name, Some("handle_request"), backend_phase_start, kind);
+    }
+
+    push(
+        "write_response",
+        None,
+        handle_message_end,
+        RequestIntervalKind::WriteResponse,
+    );
+
+    segments
+}
+
+/// Returns the status of a request span: an error carrying the error code and message
+/// when the request failed, otherwise `Ok`.
+fn span_status(response: &Either<&Response, (&CommandError, usize)>) -> Status {
+    match response {
+        Either::Left(_) => Status::Ok,
+        Either::Right((err, _)) => Status::error(format!("{}: {}", err.code(), err.message())),
+    }
+}
+
+/// Records a span for a completed request.
+///
+/// The span's status is set from the outcome, and failed requests carry `error.type`.
+///
+/// Each recorded `RequestIntervalKind` phase becomes a child span with its own start and end
+/// time, and an event on the request span at the phase start. Both carry the elapsed
+/// nanoseconds of the phase.
+///
+/// The request span continues the trace context the client sent, if any, read from the
+/// sources configured by `TraceContextSources`; otherwise it is a root span.
+///
+/// Returns the request span's context, or `None` when tracing is disabled.
+#[expect(
+    clippy::too_many_arguments,
+    reason = "each argument is a separate attribute source of the request span"
+)]
+pub fn record_request_span(
+    request: Option<&Request<'_>>,
+    response: Either<&Response, (&CommandError, usize)>,
+    collection: &str,
+    request_tracker: &RequestTracker,
+    activity_id: &str,
+    client_metadata: Option<&RawDocument>,
+    app_name: &str,
+    tenant: Option<&str>,
+) -> Option<SpanContext> {
+    record_request_span_with(
+        || global::tracer("documentdb_gateway"),
+        request,
+        response,
+        collection,
+        request_tracker,
+        activity_id,
+        client_metadata,
+        app_name,
+        tenant,
+    )
+}
+
+/// Records the request span with the tracer returned by `tracer`, which is only asked for
+/// when tracing is enabled.
+#[expect(
+    clippy::too_many_arguments,
+    reason = "each argument is a separate attribute source of the request span"
+)]
+fn record_request_span_with<T>(
+    tracer: impl FnOnce() -> T,
+    request: Option<&Request<'_>>,
+    response: Either<&Response, (&CommandError, usize)>,
+    collection: &str,
+    request_tracker: &RequestTracker,
+    activity_id: &str,
+    client_metadata: Option<&RawDocument>,
+    app_name: &str,
+    tenant: Option<&str>,
+) -> Option<SpanContext>
+where
+    T: Tracer,
+    T::Span: Send + Sync + 'static,
+{
+    if !is_tracing_enabled() {
+        return None;
+    }
+
+    let attributes = request_span_attributes(
+        request,
+        &response,
+        collection,
+        activity_id,
+        app_name,
+        tenant,
+    );
+    let span_name = request.map_or_else(|| "unknown".to_owned(), |r| r.request_type().to_string());
+    let parent = request.and_then(|r| extract_trace_context(r.document(), client_metadata));
+
+    Some(emit_request_span(
+        &tracer(),
+        span_name,
+        attributes,
+        span_status(&response),
+        request_tracker,
+        parent,
+    ))
+}
+
+/// The attributes of a request span. Unlike metrics, spans record the collection and
+/// tenant as they are, since they add no time series.
+fn request_span_attributes(
+    request: Option<&Request<'_>>,
+    response: &Either<&Response, (&CommandError, usize)>,
+    collection: &str,
+    activity_id: &str,
+    app_name: &str,
+    tenant: Option<&str>,
+) -> Vec<KeyValue> {
+    let mut attributes = operation_attributes(request, response, collection);
+    attributes.push(KeyValue::new(
+        "db.documentdb.activity_id",
+        activity_id.to_owned(),
+    ));
+    attributes.push(KeyValue::new(APP_NAME_ATTRIBUTE, app_name.to_owned()));
+    if let Some(tenant) = tenant {
+        attributes.push(KeyValue::new(TENANT_ATTRIBUTE, tenant.to_owned()));
+    }
+    if let Some(comment) = request.and_then(Request::comment) {
+        attributes.push(KeyValue::new("db.documentdb.comment", comment));
+    }
+    if let Some(read_concern) = request.and_then(Request::read_concern_level) {
+        attributes.push(KeyValue::new(
+            "db.documentdb.read_concern",
+            read_concern.as_str(),
+        ));
+    }
+    if let Some(request) = request {
+        push_query_shape_attributes(&mut attributes, request.document());
+    }
+    attributes
+}
+
+fn emit_request_span<T>(
+    tracer: &T,
+    span_name: String,
+    attributes: Vec<KeyValue>,
+    status: Status,
+    request_tracker: &RequestTracker,
+    parent: Option<SpanContext>,
+) -> SpanContext
+where
+    T: Tracer,
+    T::Span: Send + Sync + 'static,
+{
+    let segments = phase_segments(request_tracker);
+    let start_time = request_tracker.start_time();
+    let end_time = segments
+        .iter()
+        .map(|segment| segment.end)
+        .max()
+        .unwrap_or(start_time);
+
+    let parent_cx = parent.map_or_else(Context::new, |parent| {
+        Context::new().with_remote_span_context(parent)
+    });
+    let mut span = tracer
+        .span_builder(span_name)
+        .with_kind(SpanKind::Server)
+        .with_start_time(start_time)
+        .with_attributes(attributes)
+        .start_with_context(tracer, &parent_cx);
+    span.set_status(status);
+
+    for segment in &segments {
+        span.add_event_with_timestamp(
+            segment.name,
+            segment.start,
+            vec![KeyValue::new(PHASE_ELAPSED_ATTRIBUTE, segment.elapsed_ns)],
+        );
+    }
+
+    // Requests are never parented to the ambient context, only to the client's.
+    let request_cx = parent_cx.with_span(span);
+    let mut phase_cxs: Vec<(&'static str, Context)> = Vec::with_capacity(segments.len());
+
+    for segment in &segments {
+        let parent_cx = segment
+            .parent
+            .and_then(|parent| phase_cxs.iter().find(|(name, _)| *name == parent))
+            .map_or_else(|| request_cx.clone(), |(_, cx)| cx.clone());
+
+        let phase_span = tracer
+            .span_builder(segment.name)
+            .with_kind(SpanKind::Internal)
+            .with_start_time(segment.start)
+            .with_attributes(vec![KeyValue::new(
+                PHASE_ELAPSED_ATTRIBUTE,
+                segment.elapsed_ns,
+            )])
+            .start_with_context(tracer, &parent_cx);
+
+        phase_cxs.push((segment.name, parent_cx.with_span(phase_span)));
+    }
+
+    for (segment, (_, cx)) in segments.iter().zip(&phase_cxs) {
+        cx.span().end_with_timestamp(segment.end);
+    }
+
+    request_cx.span().end_with_timestamp(end_time);
+    request_cx.span().span_context().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry::trace::{SamplingDecision, SpanId, TraceFlags, TraceState};
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SpanData};
+
+    use super::*;
+    use crate::{error::ErrorCode, telemetry::tenant::TenantMapping, testing::EnvGuard};
+
+    fn tracker_with(intervals: &[(RequestIntervalKind, i64)]) -> RequestTracker {
+        let tracker = RequestTracker::new();
+        for &(kind, ns) in intervals {
+            tracker.request_interval_metrics_array[kind as usize].store(ns, Ordering::Relaxed);
+        }
+        tracker
+    }
+
+    fn in_memory_provider() -> (SdkTracerProvider, InMemorySpanExporter) {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        (provider, exporter)
+    }
+
+    fn find_span<'a>(spans: &'a [SpanData], name: &str) -> &'a SpanData {
+        spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap_or_else(|| panic!("span {name} was not exported"))
+    }
+
+    #[test]
+    fn test_tracing_config_uses_env_var() {
+        let _guard = EnvGuard::set_many([
+            ("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT", "http://custom:4317"),
+            ("OTEL_TRACING_ENABLED", "true"),
+        ]);
+        let config = TracingConfig::new(None);
+        assert!(config.tracing_enabled());
+        assert_eq!(config.otlp_endpoint(), "http://custom:4317");
+    }
+
+    #[test]
+    fn test_tracing_config_json_overrides_env() {
+        let _guard = EnvGuard::set("OTEL_EXPORTER_OTLP_TRACES_TIMEOUT", "500");
+
+        let json_config = TracingOptions {
+            enabled: Some(false),
+            export_timeout_ms: Some(2000),
+            ..Default::default()
+        };
+        let config = TracingConfig::new(Some(&json_config));
+        assert!(!config.tracing_enabled());
+        assert_eq!(config.export_timeout_ms(), 2000);
+
+        let config = TracingConfig::new(None);
+        assert_eq!(config.export_timeout_ms(), 500);
+    }
+
+    #[test]
+    fn test_tracing_compression_defaults_to_gzip() {
+        let _guard = EnvGuard::remove_many([
+            "OTEL_EXPORTER_OTLP_TRACES_COMPRESSION",
+            "OTEL_EXPORTER_OTLP_COMPRESSION",
+        ]);
+        assert_eq!(
+            TracingConfig::new(None).compression(),
+            OtlpCompression::Gzip
+        );
+    }
+
+    #[test]
+    fn test_tracing_compression_precedence() {
+        let _guard = EnvGuard::set_many([
+            ("OTEL_EXPORTER_OTLP_TRACES_COMPRESSION", "gzip"),
+            ("OTEL_EXPORTER_OTLP_COMPRESSION", "none"),
+        ]);
+        assert_eq!(
+            TracingConfig::new(None).compression(),
+            OtlpCompression::Gzip
+        );
+
+        let json_config = TracingOptions {
+            compression: Some("none".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            TracingConfig::new(Some(&json_config)).compression(),
+            OtlpCompression::None
+        );
+    }
+
+    #[test]
+    fn test_tracing_compression_falls_back_to_generic_env() {
+        let _guard = EnvGuard::set_many([
+            ("OTEL_EXPORTER_OTLP_TRACES_COMPRESSION", "zstd"),
+            ("OTEL_EXPORTER_OTLP_COMPRESSION", "none"),
+        ]);
+        assert_eq!(
+            TracingConfig::new(None).compression(),
+            OtlpCompression::None
+        );
+    }
+
+    #[test]
+    fn test_create_tracer_provider_when_disabled() {
+        let json_config = TracingOptions {
+            enabled: Some(false),
+            ..Default::default()
+        };
+        let config = TracingConfig::new(Some(&json_config));
+        let resource = Resource::builder().build();
+
+        let result = create_tracer_provider(
+            &config,
+            &OtlpTlsConfig::new(None),
+            &OtlpRetryConfig::new(None),
+            &ExportQueueConfig::new(None),
+            resource,
+        );
+        assert!(result.is_ok());
+        assert!(result.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_create_tracer_provider_when_enabled() {
+        let json_config = TracingOptions {
+            enabled: Some(true),
+            ..Default::default()
+        };
+        let config = TracingConfig::new(Some(&json_config));
+        let resource = Resource::builder().build();
+
+        let result = create_tracer_provider(
+            &config,
+            &OtlpTlsConfig::new(None),
+            &OtlpRetryConfig::new(None),
+            &ExportQueueConfig::new(None),
+            resource,
+        );
+        assert!(result.is_ok());
+        assert!(result.unwrap().is_some());
+    }
+
+    fn sampled_fraction(sampler: &OperationSampler, operation: &str) -> f64 {
+        const SAMPLES: u32 = 10_000;
+        let attributes = [KeyValue::new(
+            OPERATION_NAME_ATTRIBUTE,
+            operation.to_owned(),
+        )];
+        let sampled_count = (0..SAMPLES)
+            .filter(|_| {
+                let result = sampler.should_sample(
+                    None,
+                    TraceId::from(rand::random::<u128>()),
+                    operation,
+                    &SpanKind::Server,
+                    &attributes,
+                    &[],
+                );
+                result.decision == SamplingDecision::RecordAndSample
+            })
+            .count();
+        f64::from(u32::try_from(sampled_count).unwrap()) / f64::from(SAMPLES)
+    }
+
+    #[test]
+    fn test_tracing_config_sampling_ratio_precedence() {
+        let _guard = EnvGuard::set("OTEL_TRACES_SAMPLER_ARG", "0.5");
+        assert!((TracingConfig::new(None).sampling_ratio() - 0.5).abs() < f64::EPSILON);
+
+        let json_config = TracingOptions {
+            sampling_ratio: Some(0.1),
+            ..Default::default()
+        };
+        let config = TracingConfig::new(Some(&json_config));
+        assert!((config.sampling_ratio() - 0.1).abs() < f64::EPSILON);
+    }
+
+    fn sampler_named(sampler: &str) -> TracingConfig {
+        TracingConfig::new(Some(&TracingOptions {
+            sampler: Some(sampler.to_owned()),
+            sampling_ratio: Some(0.0),
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn test_sampler_names_select_the_matching_sampler() {
+        assert!(matches!(
+            sampler_named("always_on").create_sampler(),
+            RequestSampler::Sdk(Sampler::AlwaysOn)
+        ));
+        assert!(matches!(
+            sampler_named("ALWAYS_OFF").create_sampler(),
+            RequestSampler::Sdk(Sampler::AlwaysOff)
+        ));
+        assert!(matches!(
+            sampler_named("ratio").create_sampler(),
+            RequestSampler::Ratio(_)
+        ));
+        assert!(matches!(
+            sampler_named("parentbased_ratio").create_sampler(),
+            RequestSampler::Sdk(Sampler::ParentBased(_))
+        ));
+
+        // At ratio 0, only the parent-based sampler keeps the child of a sampled parent.
+        let trace_id = TraceId::from(1);
+        let parent = Context::new().with_remote_span_context(SpanContext::new(
+            trace_id,
+            SpanId::from(1),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        ));
+        let decision = |sampler: &str| {
+            sampler_named(sampler)
+                .create_sampler()
+                .should_sample(Some(&parent), trace_id, "find", &SpanKind::Server, &[], &[])
+                .decision
+        };
+        assert_eq!(
+            decision("parentbased_ratio"),
+            SamplingDecision::RecordAndSample
+        );
+        assert_eq!(decision("ratio"), SamplingDecision::Drop);
+    }
+
+    #[test]
+    fn test_sampler_falls_back_to_env_then_default() {
+        let guard = EnvGuard::set("OTEL_TRACES_SAMPLER", "always_off");
+        assert_eq!(
+            TracingConfig::new(None).sampler_kind(),
+            SamplerKind::AlwaysOff
+        );
+        assert_eq!(
+            sampler_named("always_on").sampler_kind(),
+            SamplerKind::AlwaysOn
+        );
+        drop(guard);
+
+        let _guard = EnvGuard::remove("OTEL_TRACES_SAMPLER");
+        assert_eq!(
+            TracingConfig::new(None).sampler_kind(),
+            SamplerKind::ParentBasedRatio
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .lock()
+                .map_err(|e| std::io::Error::other(e.to_string()))?
+                .extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_unknown_sampler_warns_and_uses_default() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        let kind = tracing::subscriber::with_default(subscriber, || {
+            sampler_named("sometimes").sampler_kind()
+        });
+
+        assert_eq!(kind, SamplerKind::ParentBasedRatio);
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("WARN"));
+        assert!(output.contains("unsupported trace sampler: sometimes"));
+    }
+
+    #[test]
+    fn test_operation_sampler_uses_override_for_listed_operation() {
+        let overrides = HashMap::from([("insert".to_owned(), 1.0), ("find".to_owned(), 0.0)]);
+        let sampler = OperationSampler::new(0.0, &overrides);
+
+        assert!((sampled_fraction(&sampler, "insert") - 1.0).abs() < f64::EPSILON);
+        assert!(sampled_fraction(&sampler, "find").abs() < f64::EPSILON);
+        assert!(sampled_fraction(&sampler, "aggregate").abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_operation_sampler_samples_at_override_rate() {
+        let overrides = HashMap::from([("aggregate".to_owned(), 0.25)]);
+        let sampler = OperationSampler::new(1.0, &overrides);
+
+        let fraction = sampled_fraction(&sampler, "aggregate");
+        assert!((0.2..0.3).contains(&fraction), "sampled {fraction}");
+        assert!((sampled_fraction(&sampler, "update") - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_runtime_ratio_applies_only_to_following_samplers() {
+        let overrides = HashMap::from([("insert".to_owned(), 1.0)]);
+        let following = OperationSampler::new(1.0, &overrides).follow_runtime_ratio();
+        let fixed = OperationSampler::new(1.0, &overrides);
+
+        // Other tests only ever set the process-wide ratio to 0 as well.
+        set_sampling_ratio(0.0).unwrap();
+        assert!(sampled_fraction(&following, "find").abs() < f64::EPSILON);
+        assert!((sampled_fraction(&following, "insert") - 1.0).abs() < f64::EPSILON);
+        assert!((sampled_fraction(&fixed, "find") - 1.0).abs() < f64::EPSILON);
+
+        set_sampling_ratio(1.5).unwrap_err();
+    }
+
+    #[test]
+    fn test_phase_segments_are_nested_and_sequential() {
+        let tracker = tracker_with(&[
+            (RequestIntervalKind::ReadRequest, 10),
+            (RequestIntervalKind::HandleMessage, 1_000),
+            (RequestIntervalKind::FormatRequest, 20),
+            (RequestIntervalKind::HandleRequest, 900),
+            (RequestIntervalKind::PostgresBeginTransaction, 100),
+            (RequestIntervalKind::ProcessRequest, 600),
+            (RequestIntervalKind::WriteResponse, 30),
+        ]);
+        let start = tracker.start_time();
+        let at = |ns: u64| start + Duration::from_nanos(ns);
+
+        let segments = phase_segments(&tracker);
+        let layout = segments
+            .iter()
+            .map(|s| (s.name, s.parent, s.start, s.end))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            layout,
+            vec![
+                ("read_request", None, at(0), at(10)),
+                ("handle_message", None, at(10), at(1_010)),
+                ("format_request", Some("handle_message"), at(10), at(30)),
+                ("handle_request", Some("handle_message"), at(30), at(930)),
+                (
+                    "postgres_begin_transaction",
+                    Some("handle_request"),
+                    at(30),
+                    at(130)
+                ),
+                (
+                    "postgres_execution",
+                    Some("handle_request"),
+                    at(130),
+                    at(730)
+                ),
+                ("write_response", None, at(1_010), at(1_040)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_phase_segments_empty_for_new_tracker() {
+        assert!(phase_segments(&RequestTracker::new()).is_empty());
+    }
+
+    #[test]
+    fn test_emit_request_span_exports_phase_events_and_child_spans() {
+        let (provider, exporter) = in_memory_provider();
+        let tracker = tracker_with(&[
+            (RequestIntervalKind::ReadRequest, 10),
+            (RequestIntervalKind::HandleMessage, 1_000),
+            (RequestIntervalKind::HandleRequest, 900),
+            (RequestIntervalKind::ProcessRequest, 600),
+            (RequestIntervalKind::PostgresCommitTransaction, 50),
+        ]);
+
+        emit_request_span(
+            &provider.tracer("test"),
+            "find".to_owned(),
+            vec![KeyValue::new("db.operation.name", "find")],
+            Status::Ok,
+            &tracker,
+            None,
+        );
+
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 6);
+
+        let request_span = find_span(&spans, "find");
+        assert_eq!(request_span.start_time, tracker.start_time());
+
+        let events = &request_span.events.events;
+        let event_names = events.iter().map(|e| e.name.as_ref()).collect::<Vec<_>>();
+        assert_eq!(
+            event_names,
+            vec![
+                "read_request",
+                "handle_message",
+                "handle_request",
+                "postgres_execution",
+                "postgres_commit",
+            ]
+        );
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        assert!(events.iter().all(
+            |e| e.timestamp >= request_span.start_time && e.timestamp <= request_span.end_time
+        ));
+
+        let commit_event = &events[4];
+        assert_eq!(
+            commit_event.attributes[0].key.as_str(),
+            PHASE_ELAPSED_ATTRIBUTE
+        );
+        assert_eq!(commit_event.attributes[0].value, 50_i64.into());
+
+        let handle_request = find_span(&spans, "handle_request");
+        let execution = find_span(&spans, "postgres_execution");
+        let commit = find_span(&spans, "postgres_commit");
+        assert_eq!(
+            handle_request.parent_span_id,
+            find_span(&spans, "handle_message").span_context.span_id()
+        );
+        assert_eq!(
+            execution.parent_span_id,
+            handle_request.span_context.span_id()
+        );
+        assert_eq!(execution.end_time, commit.start_time);
+        assert_eq!(
+            commit.end_time - Duration::from_nanos(50),
+            commit.start_time
+        );
+    }
+
+    #[test]
+    fn test_failed_request_span_has_error_status() {
+        let (provider, exporter) = in_memory_provider();
+        let tracker = tracker_with(&[(RequestIntervalKind::HandleMessage, 1_000)]);
+        let error = CommandError::new(ErrorCode::NamespaceNotFound, "ns not found".to_owned());
+        let response = Either::Right((&error, 0));
+
+        emit_request_span(
+            &provider.tracer("test"),
+            "find".to_owned(),
+            operation_attributes(None, &response, "c"),
+            span_status(&response),
+            &tracker,
+            None,
+        );
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let request_span = find_span(&spans, "find");
+        assert_eq!(
+            request_span.status,
+            Status::error(format!("{}: ns not found", ErrorCode::NamespaceNotFound))
+        );
+        assert!(request_span
+            .attributes
+            .iter()
+            .any(|kv| kv.key.as_str() == "error.type"));
+
+        let ok = Response::ok();
+        assert_eq!(span_status(&Either::Left(&ok)), Status::Ok);
+    }
+
+    #[test]
+    fn test_connections_of_different_tenants_emit_distinct_tenant_attributes() {
+        let (provider, exporter) = in_memory_provider();
+        let mapping = TenantMapping {
+            users: HashMap::from([("alice".to_owned(), "team_a".to_owned())]),
+            roles: HashMap::from([("reporting_role".to_owned(), "team_b".to_owned())]),
+        };
+        let tracker = tracker_with(&[(RequestIntervalKind::HandleMessage, 1_000)]);
+        let ok = Response::ok();
+        let response = Either::Left(&ok);
+
+        for (span_name, username, roles) in [
+            ("alice_find", "alice", vec![]),
+            ("bob_find", "bob", vec!["reporting_role".to_owned()]),
+        ] {
+            emit_request_span(
+                &provider.tracer("test"),
+                span_name.to_owned(),
+                request_span_attributes(
+                    None,
+                    &response,
+                    "c",
+                    "id",
+                    "app",
+                    mapping.tenant(Some(username), &roles),
+                ),
+                span_status(&response),
+                &tracker,
+                None,
+            );
+        }
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let tenant = |name: &str| {
+            find_span(&spans, name)
+                .attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == TENANT_ATTRIBUTE)
+                .map(|kv| kv.value.to_string())
+        };
+        assert_eq!(tenant("alice_find").as_deref(), Some("team_a"));
+        assert_eq!(tenant("bob_find").as_deref(), Some("team_b"));
+    }
+
+    #[test]
+    fn test_emit_request_span_continues_client_trace() {
+        let (provider, exporter) = in_memory_provider();
+        let tracker = tracker_with(&[(RequestIntervalKind::HandleMessage, 1_000)]);
+        let parent = TraceContextExtractor::default()
+            .extract(
+                &bson::rawdoc! {
+                    "find": "c",
+                    "comment": { "traceparent": "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01" },
+                },
+                None,
+            )
+            .unwrap();
+
+        let span_context = emit_request_span(
+            &provider.tracer("test"),
+            "find".to_owned(),
+            Vec::new(),
+            Status::Ok,
+            &tracker,
+            Some(parent.clone()),
+        );
+
+        assert_eq!(span_context.trace_id(), parent.trace_id());
+        let spans = exporter.get_finished_spans().unwrap();
+        let request_span = find_span(&spans, "find");
+        assert_eq!(request_span.parent_span_id, parent.span_id());
+        assert_eq!(
+            find_span(&spans, "handle_message").span_context.trace_id(),
+            parent.trace_id()
+        );
+    }
+
+    #[test]
+    fn test_trace_context_sources_precedence() {
+        let _guard = EnvGuard::set("OTEL_TRACE_CONTEXT_SOURCES", "$trace,handshake");
+        assert_eq!(
+            TracingConfig::new(None).trace_context_extractor(),
+            "$trace,handshake".parse().unwrap()
+        );
+
+        let json = TracingOptions {
+            trace_context_sources: Some(vec!["handshake".to_owned(), "comment".to_owned()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            TracingConfig::new(Some(&json)).trace_context_extractor(),
+            "handshake,comment".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_query_shape_enabled_precedence() {
+        let _guard = EnvGuard::set("OTEL_TRACES_QUERY_SHAPE_ENABLED", "true");
+        assert!(TracingConfig::new(None).query_shape_enabled());
+
+        let json = TracingOptions {
+            query_shape_enabled: Some(false),
+            ..Default::default()
+        };
+        assert!(!TracingConfig::new(Some(&json)).query_shape_enabled());
+    }
+
+    #[test]
+    fn test_record_request_span_is_noop_when_tracing_disabled() {
+        let (provider, exporter) = in_memory_provider();
+        set_tracing_enabled(false);
+
+        let tracker = tracker_with(&[(RequestIntervalKind::HandleMessage, 1_000)]);
+        let span_context = record_request_span_with(
+            || provider.tracer("test"),
+            None,
+            Either::Left(&Response::ok()),
+            "coll",
+            &tracker,
+            "id",
+            None,
+            "unknown",
+            None,
+        );
+
+        assert!(span_context.is_none());
+        assert!(exporter.get_finished_spans().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_gateway_core/src/telemetry/unsupported_command.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    sync::{LazyLock, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::telemetry::{event_id::EventId, metrics};
+
+/// At most one unsupported command is logged per interval; the rest are only counted.
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Command names that don't match any known command are logged up to this many characters.
+const MAX_LOGGED_NAME_LEN: usize = 64;
+
+/// Metric tag for command names that don't match any known command, which clients can pick
+/// freely and so would create a series per name.
+const UNRECOGNIZED_COMMAND: &str = "unrecognized";
+
+static LOG_LIMITER: LazyLock<LogLimiter> = LazyLock::new(|| LogLimiter::new(LOG_INTERVAL));
+
+/// Lets one event through per interval and counts the ones it holds back.
+#[derive(Debug)]
+struct LogLimiter {
+    interval: Duration,
+    state: Mutex<(Option<Instant>, u64)>,
+}
+
+impl LogLimiter {
+    const fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            state: Mutex::new((None, 0)),
+        }
+    }
+
+    /// Returns the number of events suppressed since the last one let through, or `None`
+    /// if this one is suppressed too.
+    fn try_acquire(&self, now: Instant) -> Option<u64> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let (last_logged, suppressed) = &mut *state;
+        let acquired = if last_logged.is_some_and(|last| now.duration_since(last) < self.interval) {
+            *suppressed += 1;
+            None
+        } else {
+            *last_logged = Some(now);
+            Some(std::mem::take(suppressed))
+        };
+        drop(state);
+        acquired
+    }
+}
+
+/// Counts a command the gateway can't dispatch and logs its name.
+///
+/// The commands clients actually send can then guide which handlers to add. `recognized`
+/// tells a known command without a handler apart from a name that matches no command at all.
+pub fn record_unsupported_command(command: &str, recognized: bool) {
+    metrics::record_unsupported_command(if recognized {
+        command
+    } else {
+        UNRECOGNIZED_COMMAND
+    });
+
+    if let Some(suppressed) = LOG_LIMITER.try_acquire(Instant::now()) {
+        let command: String = command.chars().take(MAX_LOGGED_NAME_LEN).collect();
+        tracing::info!(
+            event_id = EventId::UnsupportedCommand.code(),
+            command_name = command,
+            recognized = recognized,
+            suppressed_since_last_log = suppressed,
+            "Client sent a command the gateway does not support"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limiter_counts_suppressed_events() {
+        let limiter = LogLimiter::new(Duration::from_secs(10));
+        let start = Instant::now();
+
+        assert_eq!(limiter.try_acquire(start), Some(0));
+        assert_eq!(limiter.try_acquire(start + Duration::from_secs(1)), None);
+        assert_eq!(limiter.try_acquire(start + Duration::from_secs(9)), None);
+        assert_eq!(
+            limiter.try_acquire(start + Duration::from_secs(10)),
+            Some(2)
+        );
+        assert_eq!(limiter.try_acquire(start + Duration::from_secs(11)), None);
+    }
+}
@@ -13,32 +13,60 @@ use crate::{
     telemetry::{event_id::EventId, utils},
 };
 
-/// Returns whether verbose latency logging should be emitted for this request.
-fn should_log_verbose_latency(
+/// Why a request's latency is logged.
+enum LatencyLog {
+    Verbose,
+    /// The request took longer than the slow query threshold, in milliseconds.
+    Slow(i64),
+}
+
+/// Returns why latency should be logged for this request, if it should be. A slow request is
+/// reported as such even when verbose logging is enabled.
+fn latency_log(
     connection_context: &ConnectionContext,
     request_tracker: &RequestTracker,
-) -> bool {
-    if connection_context
-        .dynamic_configuration()
-        .enable_verbose_logging_in_gateway()
-    {
-        return true;
-    }
-
+) -> Option<LatencyLog> {
     let slow_query_threshold_ms = connection_context
         .dynamic_configuration()
         .slow_query_log_interval_ms();
     if slow_query_threshold_ms > 0 {
         let duration_ms =
             request_tracker.get_interval_elapsed_time_ms(RequestIntervalKind::HandleMessage);
-        return duration_ms >= i64::from(slow_query_threshold_ms);
+        if duration_ms >= i64::from(slow_query_threshold_ms) {
+            return Some(LatencyLog::Slow(i64::from(slow_query_threshold_ms)));
+        }
     }
 
-    false
+    connection_context
+        .dynamic_configuration()
+        .enable_verbose_logging_in_gateway()
+        .then_some(LatencyLog::Verbose)
+}
+
+/// Emits the latency event at `$level`, with the interval timings and request dimensions
+/// followed by any extra fields.
+macro_rules! latency_event {
+    ($level:ident, $request_tracker:expr, $($fields:tt)+) => {
+        tracing::$level!(
+            read_request = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::ReadRequest),
+            handle_message = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::HandleMessage),
+            queue_wait = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::QueueWait),
+            format_request = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::FormatRequest),
+            handle_request = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::HandleRequest),
+            process_request = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::ProcessRequest),
+            postgres_begin_transaction = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::PostgresBeginTransaction),
+            postgres_set_statement_timeout = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::PostgresSetStatementTimeout),
+            postgres_commit_transaction = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::PostgresCommitTransaction),
+            open_backend_connection = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::OpenBackendConnection),
+            pool_checkout = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::PoolCheckout),
+            write_response = $request_tracker.get_interval_elapsed_time(RequestIntervalKind::WriteResponse),
+            $($fields)+
+        )
+    };
 }
 
-/// Logs verbose latency information for a request if verbose logging is enabled
-/// or the request exceeded the slow query threshold.
+/// Logs verbose latency information for a request if verbose logging is enabled, or as a
+/// warning if the request exceeded the slow query threshold.
 #[expect(
     clippy::too_many_arguments,
     reason = "verbose latency logging requires all request context dimensions"
@@ -53,9 +81,9 @@ pub fn try_log_verbose_latency(
     request_length: i64,
     response_length: i64,
 ) {
-    if !should_log_verbose_latency(connection_context, request_tracker) {
+    let Some(log) = latency_log(connection_context, request_tracker) else {
         return;
-    }
+    };
 
     let database_name = request.and_then(|r| r.db().ok()).unwrap_or_default();
     let request_type = request
@@ -65,29 +93,127 @@ pub fn try_log_verbose_latency(
     let status_code = utils::get_status_code_u16(error);
     let error_code = utils::get_error_code_i32(error);
 
-    tracing::info!(
-        activity_id = activity_id,
-        event_id = EventId::RequestTrace.code(),
-        read_request = request_tracker.get_interval_elapsed_time(RequestIntervalKind::ReadRequest),
-        handle_message = request_tracker.get_interval_elapsed_time(RequestIntervalKind::HandleMessage),
-        format_request = request_tracker.get_interval_elapsed_time(RequestIntervalKind::FormatRequest),
-        handle_request = request_tracker.get_interval_elapsed_time(RequestIntervalKind::HandleRequest),
-        process_request = request_tracker.get_interval_elapsed_time(RequestIntervalKind::ProcessRequest),
-        postgres_begin_transaction = request_tracker.get_interval_elapsed_time(RequestIntervalKind::PostgresBeginTransaction),
-        postgres_set_statement_timeout = request_tracker.get_interval_elapsed_time(RequestIntervalKind::PostgresSetStatementTimeout),
-        postgres_commit_transaction = request_tracker.get_interval_elapsed_time(RequestIntervalKind::PostgresCommitTransaction),
-        open_backend_connection = request_tracker.get_interval_elapsed_time(RequestIntervalKind::OpenBackendConnection),
-        write_response = request_tracker.get_interval_elapsed_time(RequestIntervalKind::WriteResponse),
-        address = %connection_context.ip_address,
-        transport_protocol = %connection_context.transport_protocol(),
-        database_name = database_name,
-        collection_name = collection,
-        operation_name = request_type,
-        status_code = status_code,
-        sub_status_code = 0,
-        error_code = error_code,
-        request_length = request_length,
-        response_length = response_length,
-        "Latency for Mongo Request with interval timings (ns)."
-    );
+    match log {
+        LatencyLog::Verbose => latency_event!(
+            info,
+            request_tracker,
+            activity_id = activity_id,
+            event_id = EventId::RequestTrace.code(),
+            address = %connection_context.ip_address,
+            transport_protocol = %connection_context.transport_protocol(),
+            database_name = database_name,
+            collection_name = collection,
+            operation_name = request_type,
+            status_code = status_code,
+            sub_status_code = 0,
+            error_code = error_code,
+            request_length = request_length,
+            response_length = response_length,
+            "Latency for Mongo Request with interval timings (ns)."
+        ),
+        LatencyLog::Slow(threshold_ms) => latency_event!(
+            warn,
+            request_tracker,
+            activity_id = activity_id,
+            event_id = EventId::SlowOperation.code(),
+            address = %connection_context.ip_address,
+            transport_protocol = %connection_context.transport_protocol(),
+            app_name = connection_context.app_name(),
+            database_name = database_name,
+            collection_name = collection,
+            namespace = format!("{database_name}.{collection}"),
+            operation_name = request_type,
+            status_code = status_code,
+            sub_status_code = 0,
+            error_code = error_code,
+            request_length = request_length,
+            response_length = response_length,
+            duration_ms = request_tracker.get_interval_elapsed_time_ms(RequestIntervalKind::HandleMessage),
+            threshold_ms = threshold_ms,
+            "Slow operation with interval timings (ns)."
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        sync::{atomic::Ordering, Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::testing::{self, MapConfiguration};
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .lock()
+                .map_err(|e| std::io::Error::other(e.to_string()))?
+                .extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Logs the latency of a request that took `duration_ms` with the slow query threshold at
+    /// 100ms, and returns what was written.
+    async fn captured_latency_log(duration_ms: i64) -> String {
+        let service_context = testing::service_context(MapConfiguration::new(&[(
+            "slowQueryLogIntervalInMilliseconds",
+            "100",
+        )]))
+        .await;
+        let connection_context = testing::connection_context(service_context, "user");
+
+        let tracker = RequestTracker::new();
+        tracker.request_interval_metrics_array[RequestIntervalKind::HandleMessage as usize]
+            .store(duration_ms * 1_000_000, Ordering::Relaxed);
+        tracker.request_interval_metrics_array[RequestIntervalKind::QueueWait as usize]
+            .store(40_000_000, Ordering::Relaxed);
+
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            try_log_verbose_latency(
+                &connection_context,
+                None,
+                "coll",
+                &tracker,
+                "activity",
+                None,
+                64,
+                128,
+            );
+        });
+
+        let output = logs.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[tokio::test]
+    async fn request_over_slow_query_threshold_is_logged() {
+        let output = captured_latency_log(250).await;
+        assert!(output.contains("WARN"));
+        assert!(output.contains("Slow operation"));
+        assert!(output.contains("duration_ms=250"));
+        assert!(output.contains("threshold_ms=100"));
+        assert!(output.contains("collection_name=\"coll\""));
+        assert!(output.contains("queue_wait=40000000"));
+    }
+
+    #[tokio::test]
+    async fn request_under_slow_query_threshold_is_not_logged() {
+        assert!(captured_latency_log(50).await.is_empty());
+    }
 }
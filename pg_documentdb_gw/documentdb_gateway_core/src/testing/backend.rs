@@ -0,0 +1,162 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * src/testing/backend.rs
+ *
+ * A scripted Postgres backend for driving real pool connections in tests.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+use tokio_postgres::error::SqlState;
+
+use crate::configuration::BackendTarget;
+
+/// A Postgres backend on a local port that accepts any startup and answers simple queries.
+///
+/// Each of the scripted failures answers one query with an error carrying its SQL state,
+/// leaving the connection open; every query after them succeeds.
+pub struct ScriptedBackend {
+    target: BackendTarget,
+    connections: Arc<AtomicUsize>,
+    queries: Arc<AtomicUsize>,
+    task: JoinHandle<()>,
+}
+
+impl ScriptedBackend {
+    pub async fn start(failures: Vec<SqlState>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = BackendTarget {
+            host_name: "127.0.0.1".to_owned(),
+            port: listener.local_addr().unwrap().port(),
+        };
+        let connections = Arc::new(AtomicUsize::new(0));
+        let queries = Arc::new(AtomicUsize::new(0));
+        let failures = Arc::new(Mutex::new(failures.into_iter()));
+
+        let task = tokio::spawn({
+            let connections = Arc::clone(&connections);
+            let queries = Arc::clone(&queries);
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    connections.fetch_add(1, Ordering::Relaxed);
+                    tokio::spawn(serve(stream, Arc::clone(&queries), Arc::clone(&failures)));
+                }
+            }
+        });
+
+        Self {
+            target,
+            connections,
+            queries,
+            task,
+        }
+    }
+
+    /// Where pools reach this backend.
+    pub const fn target(&self) -> &BackendTarget {
+        &self.target
+    }
+
+    /// The number of connections opened to this backend.
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    /// The number of queries this backend answered, failed ones included.
+    pub fn queries(&self) -> usize {
+        self.queries.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for ScriptedBackend {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    queries: Arc<AtomicUsize>,
+    failures: Arc<Mutex<std::vec::IntoIter<SqlState>>>,
+) {
+    // The startup message has no tag; its parameters are ignored.
+    let Ok(length) = stream.read_i32().await else {
+        return;
+    };
+    let mut startup = vec![0; usize::try_from(length).unwrap() - 4];
+    if stream.read_exact(&mut startup).await.is_err() {
+        return;
+    }
+
+    let mut reply = message(b'R', &0_i32.to_be_bytes());
+    reply.extend(ready_for_query());
+    if stream.write_all(&reply).await.is_err() {
+        return;
+    }
+
+    while let Ok(tag) = stream.read_u8().await {
+        let Ok(length) = stream.read_i32().await else {
+            return;
+        };
+        let mut body = vec![0; usize::try_from(length).unwrap() - 4];
+        if stream.read_exact(&mut body).await.is_err() {
+            return;
+        }
+
+        let reply = match tag {
+            b'Q' => {
+                queries.fetch_add(1, Ordering::Relaxed);
+                let failure = failures.lock().unwrap().next();
+                let mut reply = failure.map_or_else(
+                    || message(b'C', b"SELECT 1\0"),
+                    |state| error_response(&state),
+                );
+                reply.extend(ready_for_query());
+                reply
+            }
+            // Terminate
+            b'X' => return,
+            _ => continue,
+        };
+        if stream.write_all(&reply).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn message(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut message = vec![tag];
+    message.extend_from_slice(&i32::try_from(body.len() + 4).unwrap().to_be_bytes());
+    message.extend_from_slice(body);
+    message
+}
+
+fn ready_for_query() -> Vec<u8> {
+    message(b'Z', b"I")
+}
+
+fn error_response(state: &SqlState) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (field, value) in [
+        (b'S', "ERROR"),
+        (b'C', state.code()),
+        (b'M', "scripted failure"),
+    ] {
+        body.push(field);
+        body.extend_from_slice(value.as_bytes());
+        body.push(0);
+    }
+    body.push(0);
+    message(b'E', &body)
+}
@@ -0,0 +1,576 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * src/testing/data_client.rs
+ *
+ * A data client that records the commands the processor sends to the
+ * backend instead of running them.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use async_trait::async_trait;
+use bson::{RawDocument, RawDocumentBuf};
+use tokio_postgres::Row;
+
+use crate::{
+    auth::AuthState,
+    context::{ConnectionContext, Cursor, RequestContext, ServiceContext},
+    error::{DocumentDBError, Result},
+    explain::Verbosity,
+    postgres::{
+        conn_mgmt::{ConnectionPool, PoolConnection, PullConnection},
+        PgDataClient, PgDocument,
+    },
+    responses::{PgResponse, RawResponse, Response},
+};
+
+/// A command the processor sent to the backend through the stub.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StubCall {
+    pub method: &'static str,
+    pub command: RawDocumentBuf,
+}
+
+/// A data client for processor tests. Every call is recorded; a call returning a response is
+/// answered with the reply set for its method, and every other call fails.
+#[derive(Debug, Clone)]
+pub struct StubDataClient {
+    service_context: ServiceContext,
+    calls: Arc<Mutex<Vec<StubCall>>>,
+    replies: Arc<Mutex<HashMap<&'static str, RawDocumentBuf>>>,
+}
+
+impl StubDataClient {
+    pub fn new(service_context: ServiceContext) -> Self {
+        Self {
+            service_context,
+            calls: Arc::default(),
+            replies: Arc::default(),
+        }
+    }
+
+    /// Answers calls to `method` with `reply`.
+    pub fn reply_with(&self, method: &'static str, reply: RawDocumentBuf) {
+        self.replies.lock().unwrap().insert(method, reply);
+    }
+
+    /// The calls made so far, in order.
+    pub fn calls(&self) -> Vec<StubCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// The methods called so far, in order.
+    pub fn methods(&self) -> Vec<&'static str> {
+        self.calls().iter().map(|call| call.method).collect()
+    }
+
+    fn record(&self, method: &'static str, request_context: &RequestContext<'_>) {
+        self.calls.lock().unwrap().push(StubCall {
+            method,
+            command: request_context.payload.document().to_raw_document_buf(),
+        });
+    }
+
+    fn unanswered(
+        &self,
+        method: &'static str,
+        request_context: &RequestContext<'_>,
+    ) -> DocumentDBError {
+        self.record(method, request_context);
+        DocumentDBError::internal_error(format!("{method} is not answered by the stub"))
+    }
+
+    fn reply(
+        &self,
+        method: &'static str,
+        request_context: &RequestContext<'_>,
+    ) -> Result<Response> {
+        self.record(method, request_context);
+        self.replies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(method)
+            .map(|reply| Response::Raw(RawResponse(reply.clone())))
+            .ok_or_else(|| {
+                DocumentDBError::internal_error(format!("{method} is not answered by the stub"))
+            })
+    }
+}
+
+#[async_trait]
+impl PgDataClient for StubDataClient {
+    fn new_authorized(
+        service_context: &ServiceContext,
+        _authorization: &AuthState,
+        _db: Option<&str>,
+    ) -> Result<Self> {
+        Ok(Self::new(service_context.clone()))
+    }
+
+    fn new_unauthorized(service_context: &ServiceContext) -> Result<Self> {
+        Ok(Self::new(service_context.clone()))
+    }
+
+    fn service_context(&self) -> &ServiceContext {
+        &self.service_context
+    }
+
+    async fn acquire_pool_connection(&self) -> Result<PoolConnection> {
+        Err(DocumentDBError::internal_error(
+            "The stub has no connections".to_owned(),
+        ))
+    }
+
+    fn connection_pool(&self) -> Result<&ConnectionPool> {
+        Err(DocumentDBError::internal_error(
+            "The stub has no connection pool".to_owned(),
+        ))
+    }
+
+    fn get_index_build_id<'a>(&self, _index_response: &'a PgResponse) -> Result<PgDocument<'a>> {
+        Err(DocumentDBError::internal_error(
+            "The stub builds no indexes".to_owned(),
+        ))
+    }
+
+    async fn execute_aggregate(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_aggregate", request_context)
+    }
+
+    async fn execute_coll_stats(
+        &self,
+        request_context: &RequestContext<'_>,
+        _scale: f64,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_coll_stats", request_context)
+    }
+
+    async fn execute_count_query(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_count_query", request_context)
+    }
+
+    async fn execute_create_collection(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_create_collection", request_context)
+    }
+
+    async fn execute_create_indexes(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Vec<Row>> {
+        Err(self.unanswered("execute_create_indexes", request_context))
+    }
+
+    async fn execute_wait_for_index(
+        &self,
+        request_context: &RequestContext<'_>,
+        _index_build_id: &PgDocument<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Vec<Row>> {
+        Err(self.unanswered("execute_wait_for_index", request_context))
+    }
+
+    async fn execute_delete(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Vec<Row>> {
+        Err(self.unanswered("execute_delete", request_context))
+    }
+
+    async fn execute_delete_when_readonly(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Vec<Row>> {
+        Err(self.unanswered("execute_delete_when_readonly", request_context))
+    }
+
+    async fn execute_distinct_query(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_distinct_query", request_context)
+    }
+
+    async fn execute_drop_collection(
+        &self,
+        request_context: &RequestContext<'_>,
+        _db: &str,
+        _collection: &str,
+        _connection_context: &ConnectionContext,
+    ) -> Result<bool> {
+        Err(self.unanswered("execute_drop_collection", request_context))
+    }
+
+    async fn execute_drop_collection_when_readonly(
+        &self,
+        request_context: &RequestContext<'_>,
+        _db: &str,
+        _collection: &str,
+        _connection_context: &ConnectionContext,
+    ) -> Result<bool> {
+        Err(self.unanswered("execute_drop_collection_when_readonly", request_context))
+    }
+
+    async fn execute_drop_database(
+        &self,
+        request_context: &RequestContext<'_>,
+        _db: &str,
+        _connection_context: &ConnectionContext,
+    ) -> Result<()> {
+        Err(self.unanswered("execute_drop_database", request_context))
+    }
+
+    async fn execute_drop_database_when_readonly(
+        &self,
+        request_context: &RequestContext<'_>,
+        _db: &str,
+        _connection_context: &ConnectionContext,
+    ) -> Result<()> {
+        Err(self.unanswered("execute_drop_database_when_readonly", request_context))
+    }
+
+    async fn execute_explain(
+        &self,
+        request_context: &RequestContext<'_>,
+        _query_base: &str,
+        _verbosity: Verbosity,
+        _connection_context: &ConnectionContext,
+    ) -> Result<(Option<serde_json::Value>, String)> {
+        Err(self.unanswered("execute_explain", request_context))
+    }
+
+    async fn execute_find(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_find", request_context)
+    }
+
+    async fn execute_find_and_modify(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_find_and_modify", request_context)
+    }
+
+    async fn execute_cursor_get_more(
+        &self,
+        request_context: &RequestContext<'_>,
+        _db: &str,
+        _cursor: &Cursor,
+        _pull_connection: PullConnection,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Vec<Row>> {
+        Err(self.unanswered("execute_cursor_get_more", request_context))
+    }
+
+    async fn execute_index_stats(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Vec<Row>> {
+        Err(self.unanswered("execute_index_stats", request_context))
+    }
+
+    async fn execute_insert(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+        _enable_write_procedures: bool,
+        _enable_write_procedures_with_batch_commit: bool,
+    ) -> Result<Vec<Row>> {
+        Err(self.unanswered("execute_insert", request_context))
+    }
+
+    async fn execute_list_collections(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_list_collections", request_context)
+    }
+
+    async fn execute_list_databases(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_list_databases", request_context)
+    }
+
+    async fn execute_list_indexes(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_list_indexes", request_context)
+    }
+
+    async fn execute_update(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+        _enable_write_procedures: bool,
+        _enable_write_procedures_with_batch_commit: bool,
+    ) -> Result<Vec<Row>> {
+        Err(self.unanswered("execute_update", request_context))
+    }
+
+    async fn execute_validate(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_validate", request_context)
+    }
+
+    async fn execute_drop_indexes(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<PgResponse> {
+        Err(self.unanswered("execute_drop_indexes", request_context))
+    }
+
+    async fn execute_shard_collection(
+        &self,
+        request_context: &RequestContext<'_>,
+        _db: &str,
+        _collection: &str,
+        _key: &RawDocument,
+        _reshard: bool,
+        _connection_context: &ConnectionContext,
+    ) -> Result<()> {
+        Err(self.unanswered("execute_shard_collection", request_context))
+    }
+
+    async fn execute_reindex(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_reindex", request_context)
+    }
+
+    async fn execute_current_op(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_current_op", request_context)
+    }
+
+    async fn execute_kill_op(
+        &self,
+        request_context: &RequestContext<'_>,
+        _operation_id: &str,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_kill_op", request_context)
+    }
+
+    async fn execute_coll_mod(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_coll_mod", request_context)
+    }
+
+    async fn execute_get_parameter(
+        &self,
+        request_context: &RequestContext<'_>,
+        _all: bool,
+        _show_details: bool,
+        _params: Vec<String>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_get_parameter", request_context)
+    }
+
+    async fn execute_db_stats(
+        &self,
+        request_context: &RequestContext<'_>,
+        _scale: f64,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_db_stats", request_context)
+    }
+
+    async fn execute_rename_collection(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Vec<Row>> {
+        Err(self.unanswered("execute_rename_collection", request_context))
+    }
+
+    async fn execute_create_user(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_create_user", request_context)
+    }
+
+    async fn execute_drop_user(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_drop_user", request_context)
+    }
+
+    async fn execute_update_user(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_update_user", request_context)
+    }
+
+    async fn execute_users_info(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_users_info", request_context)
+    }
+
+    async fn execute_connection_status(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_connection_status", request_context)
+    }
+
+    async fn execute_compact(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_compact", request_context)
+    }
+
+    async fn execute_kill_cursors(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+        _cursor_ids: &[i64],
+    ) -> Result<Response> {
+        self.reply("execute_kill_cursors", request_context)
+    }
+
+    async fn execute_create_role(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_create_role", request_context)
+    }
+
+    async fn execute_update_role(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_update_role", request_context)
+    }
+
+    async fn execute_drop_role(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_drop_role", request_context)
+    }
+
+    async fn execute_roles_info(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_roles_info", request_context)
+    }
+
+    async fn execute_unshard_collection(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<()> {
+        Err(self.unanswered("execute_unshard_collection", request_context))
+    }
+
+    async fn execute_get_shard_map(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_get_shard_map", request_context)
+    }
+
+    async fn execute_list_shards(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_list_shards", request_context)
+    }
+
+    async fn execute_balancer_start(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_balancer_start", request_context)
+    }
+
+    async fn execute_balancer_status(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_balancer_status", request_context)
+    }
+
+    async fn execute_balancer_stop(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_balancer_stop", request_context)
+    }
+
+    async fn execute_move_collection(
+        &self,
+        request_context: &RequestContext<'_>,
+        _connection_context: &ConnectionContext,
+    ) -> Result<Response> {
+        self.reply("execute_move_collection", request_context)
+    }
+}
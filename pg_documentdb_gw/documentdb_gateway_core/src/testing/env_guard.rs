@@ -49,12 +49,27 @@ impl EnvGuard {
     }
 
     pub fn remove(key: &str) -> Self {
+        Self::remove_many([key])
+    }
+
+    pub fn remove_many<'a, I>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
         let lock = ENV_GUARD_MUTEX.lock().expect("env guard mutex poisoned");
-        let original = env::var(key).ok();
-        env::remove_var(key);
+        let mut originals = Vec::new();
+        let mut seen_keys = HashSet::new();
+
+        for key in keys {
+            if seen_keys.insert(key.to_owned()) {
+                originals.push((key.to_owned(), env::var(key).ok()));
+            }
+            env::remove_var(key);
+        }
+
         Self {
             _lock: lock,
-            originals: vec![(key.to_owned(), original)],
+            originals,
         }
     }
 }
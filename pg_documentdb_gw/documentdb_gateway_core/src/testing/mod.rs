@@ -8,6 +8,16 @@
  *-------------------------------------------------------------------------
  */
 
+mod backend;
+mod data_client;
 mod env_guard;
+mod service;
+mod wire;
 
+pub use backend::ScriptedBackend;
+pub use data_client::{StubCall, StubDataClient};
 pub use env_guard::EnvGuard;
+pub use service::{
+    connection_context, service_context, service_context_with_authorizer, MapConfiguration,
+};
+pub use wire::{op_msg, read_message};
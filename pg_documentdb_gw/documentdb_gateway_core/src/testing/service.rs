@@ -0,0 +1,205 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * src/testing/service.rs
+ *
+ * Shared testing helpers for building service and connection contexts
+ * without a backend.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+use bson::{rawbson, RawBson};
+use openssl::{
+    asn1::Asn1Time,
+    hash::MessageDigest,
+    pkey::PKey,
+    rsa::Rsa,
+    x509::{X509NameBuilder, X509},
+};
+use uuid::Uuid;
+
+use crate::{
+    auth::DatabaseAuthorizer,
+    configuration::{
+        CertInputType, CertificateOptions, DocumentDBSetupConfiguration, DynamicConfiguration,
+        SetupConfiguration,
+    },
+    context::{ConnectionContext, ServiceContext},
+    postgres::{
+        conn_mgmt::{ConnectionPool, PgPoolSettings, PoolManager},
+        create_query_catalog,
+    },
+    service::TlsProvider,
+    startup::get_service_context,
+};
+
+/// A dynamic configuration read from a map, falling back to each setting's default.
+#[derive(Debug, Default)]
+pub struct MapConfiguration(HashMap<String, String>);
+
+impl MapConfiguration {
+    pub fn new(values: &[(&str, &str)]) -> Self {
+        Self(
+            values
+                .iter()
+                .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
+        )
+    }
+}
+
+impl DynamicConfiguration for MapConfiguration {
+    fn get_str(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+
+    fn get_bool(&self, key: &str, default: bool) -> bool {
+        self.0.get(key).map_or(default, |v| v.parse().unwrap())
+    }
+
+    fn get_i32(&self, key: &str, default: i32) -> i32 {
+        self.0.get(key).map_or(default, |v| v.parse().unwrap())
+    }
+
+    fn get_u64(&self, key: &str, default: u64) -> u64 {
+        self.0.get(key).map_or(default, |v| v.parse().unwrap())
+    }
+
+    fn equals_value(&self, key: &str, value: &str) -> bool {
+        self.0.get(key).is_some_and(|v| v == value)
+    }
+
+    fn topology(&self) -> RawBson {
+        rawbson!({})
+    }
+
+    fn enable_developer_explain(&self) -> bool {
+        false
+    }
+
+    fn max_connections(&self) -> usize {
+        100
+    }
+
+    fn allow_transaction_snapshot(&self) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// A self-signed certificate and its key, written once per test process.
+fn certificate_options() -> CertificateOptions {
+    static PATHS: OnceLock<(String, String)> = OnceLock::new();
+    let (certificate, key) = PATHS.get_or_init(|| {
+        let rsa = Rsa::generate(2048).unwrap();
+        let key = PKey::from_rsa(rsa).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "localhost").unwrap();
+        let name = name.build();
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+
+        let directory =
+            std::env::temp_dir().join(format!("documentdb_gateway_tests_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let certificate_path = directory.join("cert.pem");
+        let key_path = directory.join("key.pem");
+        std::fs::write(&certificate_path, builder.build().to_pem().unwrap()).unwrap();
+        std::fs::write(&key_path, key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+        (
+            certificate_path.to_string_lossy().into_owned(),
+            key_path.to_string_lossy().into_owned(),
+        )
+    });
+
+    CertificateOptions {
+        cert_type: CertInputType::PemFile,
+        file_path: Some(certificate.clone()),
+        key_file_path: Some(key.clone()),
+        ca_path: None,
+    }
+}
+
+/// A service context whose pools never connect, for requests served by a stub data client.
+pub async fn service_context(dynamic_configuration: MapConfiguration) -> ServiceContext {
+    service_context_with_authorizer(dynamic_configuration, None).await
+}
+
+/// A [`service_context`] that restricts listed databases through `database_authorizer`.
+pub async fn service_context_with_authorizer(
+    dynamic_configuration: MapConfiguration,
+    database_authorizer: Option<Box<dyn DatabaseAuthorizer>>,
+) -> ServiceContext {
+    let setup_configuration = DocumentDBSetupConfiguration {
+        node_host_name: "localhost".to_owned(),
+        certificate_options: certificate_options(),
+        ..DocumentDBSetupConfiguration::default()
+    };
+    let query_catalog = create_query_catalog();
+    let pool = |name: &str| {
+        ConnectionPool::new_with_user(
+            &setup_configuration,
+            &query_catalog,
+            setup_configuration.postgres_system_user(),
+            None,
+            name,
+            PgPoolSettings::system_pool_settings(1),
+        )
+        .unwrap()
+    };
+    let pool_manager = PoolManager::new(
+        query_catalog.clone(),
+        Box::new(setup_configuration.clone()),
+        pool("SystemRequests"),
+        pool("PreAuthRequests"),
+    );
+    let tls_provider = TlsProvider::new(&setup_configuration.certificate_options, None, None)
+        .await
+        .unwrap();
+
+    get_service_context(
+        Box::new(setup_configuration),
+        Arc::new(dynamic_configuration),
+        Arc::new(pool_manager),
+        tls_provider,
+        None,
+        database_authorizer,
+    )
+}
+
+/// A connection authenticated as `user` with the `readWriteAnyDatabase` role.
+pub fn connection_context(service_context: ServiceContext, user: &str) -> ConnectionContext {
+    let mut connection_context = ConnectionContext::new(
+        service_context,
+        None,
+        "127.0.0.1".to_owned(),
+        None,
+        Uuid::new_v4(),
+        "TCP".to_owned(),
+    );
+    connection_context.auth_state.set_username(user);
+    connection_context
+        .auth_state
+        .set_roles(vec!["readWriteAnyDatabase".to_owned()]);
+    connection_context.auth_state.set_authorized(true);
+    connection_context
+}
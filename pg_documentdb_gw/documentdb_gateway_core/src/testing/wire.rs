@@ -0,0 +1,40 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * src/testing/wire.rs
+ *
+ * Shared testing helpers for exchanging wire protocol messages with a connection.
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::RawDocument;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::protocol::{header::Header, opcode::OpCode};
+
+/// An `OP_MSG` request with `request_id` carrying `body` as its only section.
+pub fn op_msg(request_id: i32, body: &RawDocument) -> Vec<u8> {
+    let length = Header::LENGTH + 5 + body.as_bytes().len();
+    let mut message = Vec::with_capacity(length);
+    message.extend_from_slice(&i32::try_from(length).unwrap().to_le_bytes());
+    message.extend_from_slice(&request_id.to_le_bytes());
+    message.extend_from_slice(&0_i32.to_le_bytes());
+    message.extend_from_slice(&(OpCode::Msg as i32).to_le_bytes());
+    message.extend_from_slice(&0_u32.to_le_bytes());
+    message.push(0);
+    message.extend_from_slice(body.as_bytes());
+    message
+}
+
+/// Reads one whole message, header included, off `stream`.
+pub async fn read_message<S>(stream: &mut S) -> Vec<u8>
+where
+    S: AsyncRead + Unpin,
+{
+    let length = stream.read_i32_le().await.unwrap();
+    let mut message = length.to_le_bytes().to_vec();
+    message.resize(usize::try_from(length).unwrap(), 0);
+    stream.read_exact(&mut message[4..]).await.unwrap();
+    message
+}
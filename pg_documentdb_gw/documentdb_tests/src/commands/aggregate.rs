@@ -21,7 +21,9 @@
 
 use bson::{doc, Document};
 use futures::StreamExt;
-use mongodb::{error::Error, Database};
+use mongodb::{error::Error, Database, IndexModel};
+
+use crate::utils::commands::execute_command_and_validate_error;
 
 pub async fn validate_aggregate(db: &Database) -> Result<(), Error> {
     let coll = db.collection("test");
@@ -79,3 +81,330 @@ pub async fn validate_concat_arrays_error(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Returns the documents of `collection` sorted by `_id`.
+async fn sorted_documents(db: &Database, collection: &str) -> Result<Vec<Document>, Error> {
+    db.collection::<Document>(collection)
+        .find(doc! {})
+        .sort(doc! {"_id": 1})
+        .await?
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect()
+}
+
+fn assert_empty_cursor(result: &Document) {
+    let cursor = result.get_document("cursor").unwrap();
+    assert!(cursor.get_array("firstBatch").unwrap().is_empty());
+    assert_eq!(cursor.get_i64("id").unwrap(), 0);
+}
+
+pub async fn validate_out_replaces_collection(db: &Database) -> Result<(), Error> {
+    db.collection("source")
+        .insert_many(vec![doc! {"_id": 1, "a": 1}, doc! {"_id": 2, "a": 2}])
+        .await?;
+    db.collection("target")
+        .insert_one(doc! {"_id": 3, "stale": true})
+        .await?;
+
+    let result = db
+        .run_command(doc! {
+            "aggregate": "source",
+            "pipeline": [{"$match": {"a": {"$gt": 1}}}, {"$out": "target"}],
+            "cursor": {},
+        })
+        .await?;
+    assert_empty_cursor(&result);
+
+    assert_eq!(
+        sorted_documents(db, "target").await?,
+        vec![doc! {"_id": 2, "a": 2}]
+    );
+
+    Ok(())
+}
+
+pub async fn validate_merge_upserts(db: &Database) -> Result<(), Error> {
+    db.collection("source")
+        .insert_many(vec![doc! {"_id": 1, "a": 10}, doc! {"_id": 2, "a": 20}])
+        .await?;
+    db.collection("target")
+        .insert_one(doc! {"_id": 1, "a": 1, "b": 1})
+        .await?;
+
+    let result = db
+        .run_command(doc! {
+            "aggregate": "source",
+            "pipeline": [{"$merge": {
+                "into": "target",
+                "whenMatched": "merge",
+                "whenNotMatched": "insert",
+            }}],
+            "cursor": {},
+        })
+        .await?;
+    assert_empty_cursor(&result);
+
+    assert_eq!(
+        sorted_documents(db, "target").await?,
+        vec![doc! {"_id": 1, "a": 10, "b": 1}, doc! {"_id": 2, "a": 20}]
+    );
+
+    Ok(())
+}
+
+pub async fn validate_zero_batch_size_primes_cursor(db: &Database) -> Result<(), Error> {
+    db.collection("source")
+        .insert_many(vec![doc! {"_id": 1, "a": 1}, doc! {"_id": 2, "a": 2}])
+        .await?;
+
+    let result = db
+        .run_command(doc! {
+            "aggregate": "source",
+            "pipeline": [{"$sort": {"_id": 1}}],
+            "cursor": {"batchSize": 0},
+        })
+        .await?;
+    let cursor = result.get_document("cursor").unwrap();
+    assert!(cursor.get_array("firstBatch").unwrap().is_empty());
+    let cursor_id = cursor.get_i64("id").unwrap();
+    assert_ne!(
+        cursor_id, 0,
+        "a zero first batch should leave the cursor open"
+    );
+
+    let result = db
+        .run_command(doc! {"getMore": cursor_id, "collection": "source"})
+        .await?;
+    let next_batch = result
+        .get_document("cursor")
+        .unwrap()
+        .get_array("nextBatch")
+        .unwrap();
+    assert_eq!(next_batch.len(), 2);
+
+    Ok(())
+}
+
+pub async fn validate_zero_batch_size_runs_writes_at_open(db: &Database) -> Result<(), Error> {
+    db.collection("source")
+        .insert_many(vec![doc! {"_id": 1, "a": 1}, doc! {"_id": 2, "a": 2}])
+        .await?;
+
+    for pipeline in [
+        vec![doc! {"$out": "out_target"}],
+        vec![doc! {"$merge": {"into": "merge_target"}}],
+    ] {
+        let result = db
+            .run_command(doc! {
+                "aggregate": "source",
+                "pipeline": pipeline,
+                "cursor": {"batchSize": 0},
+            })
+            .await?;
+        assert_empty_cursor(&result);
+    }
+
+    let expected = vec![doc! {"_id": 1, "a": 1}, doc! {"_id": 2, "a": 2}];
+    assert_eq!(sorted_documents(db, "out_target").await?, expected);
+    assert_eq!(sorted_documents(db, "merge_target").await?, expected);
+
+    Ok(())
+}
+
+pub async fn validate_merge_when_not_matched_fail(db: &Database) -> Result<(), Error> {
+    db.collection("source")
+        .insert_many(vec![doc! {"_id": 1, "a": 10}, doc! {"_id": 2, "a": 20}])
+        .await?;
+    db.collection("target")
+        .insert_one(doc! {"_id": 1, "a": 1})
+        .await?;
+
+    execute_command_and_validate_error(
+        db,
+        doc! {
+            "aggregate": "source",
+            "pipeline": [{"$merge": {
+                "into": "target",
+                "whenMatched": "merge",
+                "whenNotMatched": "fail",
+            }}],
+            "cursor": {},
+        },
+        13113,
+        "$merge failed to locate a corresponding document in the target collection",
+        "MergeStageNoMatchingDocument",
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Runs `pipeline` on `collection` and returns the results.
+async fn aggregate_documents(
+    db: &Database,
+    collection: &str,
+    pipeline: Vec<Document>,
+) -> Result<Vec<Document>, Error> {
+    db.collection::<Document>(collection)
+        .aggregate(pipeline)
+        .await?
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect()
+}
+
+async fn insert_orders_and_items(db: &Database) -> Result<(), Error> {
+    db.collection("orders")
+        .insert_many(vec![
+            doc! {"_id": 1, "item": "almonds", "qty": 2},
+            doc! {"_id": 2, "item": "pecans", "qty": 1},
+            doc! {"_id": 3, "item": "cashews", "qty": 5},
+        ])
+        .await?;
+    db.collection("inventory")
+        .insert_many(vec![
+            doc! {"_id": 10, "sku": "almonds", "instock": 120},
+            doc! {"_id": 11, "sku": "pecans", "instock": 0},
+            doc! {"_id": 12, "sku": "almonds", "instock": 30},
+        ])
+        .await?;
+    Ok(())
+}
+
+pub async fn validate_lookup_local_foreign_field(db: &Database) -> Result<(), Error> {
+    insert_orders_and_items(db).await?;
+
+    let results = aggregate_documents(
+        db,
+        "orders",
+        vec![
+            doc! {"$lookup": {
+                "from": "inventory",
+                "localField": "item",
+                "foreignField": "sku",
+                "as": "stock",
+            }},
+            doc! {"$project": {"stock._id": 1}},
+            doc! {"$sort": {"_id": 1}},
+        ],
+    )
+    .await?;
+
+    assert_eq!(
+        results,
+        vec![
+            doc! {"_id": 1, "stock": [{"_id": 10}, {"_id": 12}]},
+            doc! {"_id": 2, "stock": [{"_id": 11}]},
+            // No matching inventory yields an empty array.
+            doc! {"_id": 3, "stock": []},
+        ]
+    );
+
+    Ok(())
+}
+
+pub async fn validate_lookup_pipeline(db: &Database) -> Result<(), Error> {
+    insert_orders_and_items(db).await?;
+
+    let results = aggregate_documents(
+        db,
+        "orders",
+        vec![
+            doc! {"$lookup": {
+                "from": "inventory",
+                "let": {"order_item": "$item", "order_qty": "$qty"},
+                "pipeline": [
+                    {"$match": {"$expr": {"$and": [
+                        {"$eq": ["$sku", "$$order_item"]},
+                        {"$gte": ["$instock", "$$order_qty"]},
+                    ]}}},
+                    {"$project": {"_id": 1}},
+                    {"$sort": {"_id": 1}},
+                ],
+                "as": "stock",
+            }},
+            doc! {"$project": {"stock": 1}},
+            doc! {"$sort": {"_id": 1}},
+        ],
+    )
+    .await?;
+
+    assert_eq!(
+        results,
+        vec![
+            doc! {"_id": 1, "stock": [{"_id": 10}, {"_id": 12}]},
+            // pecans are out of stock, so the sub-pipeline matches nothing.
+            doc! {"_id": 2, "stock": []},
+            doc! {"_id": 3, "stock": []},
+        ]
+    );
+
+    Ok(())
+}
+
+pub async fn validate_lookup_missing_collection(db: &Database) -> Result<(), Error> {
+    insert_orders_and_items(db).await?;
+
+    for lookup in [
+        doc! {"$lookup": {
+            "from": "missing",
+            "localField": "item",
+            "foreignField": "sku",
+            "as": "stock",
+        }},
+        doc! {"$lookup": {
+            "from": "missing",
+            "pipeline": [{"$match": {}}],
+            "as": "stock",
+        }},
+    ] {
+        let results = aggregate_documents(
+            db,
+            "orders",
+            vec![
+                lookup,
+                doc! {"$project": {"stock": 1}},
+                doc! {"$sort": {"_id": 1}},
+            ],
+        )
+        .await?;
+
+        assert_eq!(
+            results,
+            vec![
+                doc! {"_id": 1, "stock": []},
+                doc! {"_id": 2, "stock": []},
+                doc! {"_id": 3, "stock": []},
+            ]
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn validate_index_stats(db: &Database) -> Result<(), Error> {
+    let coll = db.collection::<Document>("indexed");
+    coll.insert_one(doc! {"_id": 1, "a": 1}).await?;
+    coll.create_index(IndexModel::builder().keys(doc! {"a": 1}).build())
+        .await?;
+
+    let mut stats = aggregate_documents(db, "indexed", vec![doc! {"$indexStats": {}}]).await?;
+    stats.sort_by_key(|stat| stat.get_str("name").unwrap().to_owned());
+
+    assert_eq!(stats.len(), 2);
+    for (stat, (name, key)) in stats
+        .iter()
+        .zip([("_id_", doc! {"_id": 1}), ("a_1", doc! {"a": 1})])
+    {
+        assert_eq!(stat.get_str("name").unwrap(), name);
+        assert_eq!(stat.get_document("key").unwrap(), &key);
+        let accesses = stat.get_document("accesses").unwrap();
+        assert!(accesses.get_i64("ops").unwrap() >= 0);
+        accesses.get_datetime("since").unwrap();
+    }
+
+    Ok(())
+}
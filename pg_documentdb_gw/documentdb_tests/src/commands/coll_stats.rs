@@ -19,9 +19,12 @@
     reason = "Test helper functions - unwrap failures indicate test failures"
 )]
 
-use bson::doc;
+use bson::{doc, Document};
+use futures::StreamExt;
 use mongodb::{error::Error, Database};
 
+use crate::utils::commands::execute_command_and_validate_error;
+
 pub async fn validate_coll_stats(db: &Database) -> Result<(), Error> {
     let coll = db.collection("test");
     coll.insert_one(doc! {"a": 1}).await?;
@@ -31,3 +34,98 @@ pub async fn validate_coll_stats(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Size fields that collStats divides by `scale`.
+const SCALED_SIZE_FIELDS: [&str; 4] = ["size", "storageSize", "totalIndexSize", "totalSize"];
+
+fn size_field(stats: &Document, field: &str) -> i64 {
+    match stats.get(field).unwrap() {
+        bson::Bson::Int32(value) => i64::from(*value),
+        bson::Bson::Int64(value) => *value,
+        other => panic!("expected an integer {field}, got {other:?}"),
+    }
+}
+
+pub async fn validate_coll_stats_scale(db: &Database) -> Result<(), Error> {
+    let coll = db.collection("test");
+    for i in 0..100 {
+        coll.insert_one(doc! {"a": i, "padding": "x".repeat(100)})
+            .await?;
+    }
+
+    let unscaled = db.run_command(doc! {"collStats": "test"}).await?;
+    let scaled = db
+        .run_command(doc! {"collStats": "test", "scale": 1024})
+        .await?;
+    assert_eq!(scaled.get_i32("scaleFactor").unwrap(), 1024);
+
+    for field in SCALED_SIZE_FIELDS {
+        assert_eq!(
+            size_field(&scaled, field),
+            size_field(&unscaled, field) / 1024,
+            "{field} should be divided by the scale"
+        );
+    }
+
+    let unscaled_index_sizes = unscaled.get_document("indexSizes").unwrap();
+    let scaled_index_sizes = scaled.get_document("indexSizes").unwrap();
+    assert!(scaled_index_sizes.contains_key("_id_"));
+    for (index, _) in scaled_index_sizes {
+        assert_eq!(
+            size_field(scaled_index_sizes, index),
+            size_field(unscaled_index_sizes, index) / 1024,
+            "indexSizes.{index} should be divided by the scale"
+        );
+    }
+
+    assert_eq!(
+        scaled.get_i32("avgObjSize").unwrap(),
+        unscaled.get_i32("avgObjSize").unwrap()
+    );
+
+    execute_command_and_validate_error(
+        db,
+        doc! {"collStats": "test", "scale": -1},
+        2,
+        "must have a value of at least 1",
+        "BadValue",
+    )
+    .await;
+
+    Ok(())
+}
+
+pub async fn validate_coll_stats_stage(db: &Database) -> Result<(), Error> {
+    let coll = db.collection::<Document>("test");
+    for i in 0..10 {
+        coll.insert_one(doc! {"a": i}).await?;
+    }
+
+    let stats: Vec<Result<Document, Error>> = coll
+        .aggregate(vec![doc! {"$collStats": {"storageStats": {}, "count": {}}}])
+        .await?
+        .collect()
+        .await;
+    assert_eq!(stats.len(), 1);
+    let stats = stats[0].as_ref().unwrap();
+    assert_eq!(stats.get_i32("count").unwrap(), 10);
+    let storage_stats = stats.get_document("storageStats").unwrap();
+    for field in SCALED_SIZE_FIELDS {
+        assert!(size_field(storage_stats, field) >= 0, "{field}");
+    }
+    assert!(storage_stats.contains_key("indexSizes"));
+
+    // Later stages receive the stage's output.
+    let projected: Vec<Result<Document, Error>> = coll
+        .aggregate(vec![
+            doc! {"$collStats": {"count": {}}},
+            doc! {"$project": {"_id": 0, "n": "$count"}},
+        ])
+        .await?
+        .collect()
+        .await;
+    assert_eq!(projected.len(), 1);
+    assert_eq!(projected[0].as_ref().unwrap(), &doc! {"n": 10});
+
+    Ok(())
+}
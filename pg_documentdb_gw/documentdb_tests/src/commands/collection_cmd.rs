@@ -23,14 +23,30 @@
     reason = "Test assertions compare exact float values returned from database"
 )]
 
-use bson::doc;
+use bson::{doc, Document};
 use mongodb::{error::Error, Database};
 
+use crate::utils::commands::execute_command_and_validate_error;
+
 pub async fn validate_drop(db: &Database) -> Result<(), Error> {
     let coll = db.collection("test");
     coll.insert_one(doc! {"a": 1}).await?;
 
-    coll.drop().await?;
+    let result = db.run_command(doc! {"drop": "test"}).await?;
+    assert_eq!(result.get_f64("ok").unwrap(), 1.0);
+    assert_eq!(result.get_str("ns").unwrap(), format!("{}.test", db.name()));
+    assert_eq!(coll.count_documents(doc! {}).await?, 0);
+
+    Ok(())
+}
+
+pub async fn validate_drop_missing(db: &Database) -> Result<(), Error> {
+    let result = db.run_command(doc! {"drop": "missing"}).await?;
+    assert_eq!(result.get_f64("ok").unwrap(), 1.0);
+    assert!(result.get("ns").is_none());
+
+    // Drivers' drop helpers must succeed too.
+    db.collection::<Document>("missing").drop().await?;
 
     Ok(())
 }
@@ -52,3 +68,47 @@ pub async fn validate_shard_collections(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+pub async fn validate_coll_mod(db: &Database) -> Result<(), Error> {
+    db.run_command(doc! {
+        "createIndexes": "test",
+        "indexes": [{"key": {"ts": 1}, "name": "ts_ttl", "expireAfterSeconds": 60}],
+    })
+    .await?;
+
+    // Unknown modifiers are ignored rather than failing the command.
+    let result = db
+        .run_command(doc! {
+            "collMod": "test",
+            "index": {"keyPattern": {"ts": 1}, "expireAfterSeconds": 120},
+            "changeStreamPreAndPostImages": {"enabled": true},
+        })
+        .await?;
+    assert_eq!(result.get_f64("ok").unwrap(), 1.0);
+    assert_eq!(result.get_i64("expireAfterSeconds_old").unwrap(), 60);
+    assert_eq!(result.get_f64("expireAfterSeconds_new").unwrap(), 120.0);
+
+    let result = db
+        .run_command(doc! {
+            "collMod": "test",
+            "validator": {"a": {"$exists": true}},
+            "validationLevel": "moderate",
+            "validationAction": "warn",
+        })
+        .await?;
+    assert_eq!(result.get_f64("ok").unwrap(), 1.0);
+
+    execute_command_and_validate_error(
+        db,
+        doc! {
+            "collMod": "test",
+            "index": {"name": "missing_index", "hidden": true},
+        },
+        27,
+        "",
+        "IndexNotFound",
+    )
+    .await;
+
+    Ok(())
+}
@@ -26,13 +26,17 @@
 use bson::{doc, Document};
 use mongodb::{error::Error, Database};
 
+use crate::utils::commands::execute_command_and_validate_error;
+
 pub async fn validate_compact_basic(db: &Database) -> Result<(), Error> {
     let collection = db.collection::<Document>("test_collection");
     collection
         .insert_one(doc! {"_id": 1, "name": "test"})
         .await?;
 
-    let result = db.run_command(doc! {"compact": "test_collection"}).await?;
+    let result = db
+        .run_command(doc! {"compact": "test_collection", "force": true})
+        .await?;
 
     assert_eq!(result.get_f64("ok").unwrap(), 1.0);
     assert!(result.contains_key("bytesFreed"));
@@ -40,6 +44,29 @@ pub async fn validate_compact_basic(db: &Database) -> Result<(), Error> {
     Ok(())
 }
 
+pub async fn validate_compact_requires_force(db: &Database) -> Result<(), Error> {
+    let collection = db.collection::<Document>("test_collection");
+    collection
+        .insert_one(doc! {"_id": 1, "name": "test"})
+        .await?;
+
+    execute_command_and_validate_error(
+        db,
+        doc! {"compact": "test_collection"},
+        20,
+        "use force:true to force",
+        "IllegalOperation",
+    )
+    .await;
+
+    let result = db
+        .run_command(doc! {"compact": "test_collection", "dryRun": true})
+        .await?;
+    assert!(result.contains_key("estimatedBytesFreed"));
+
+    Ok(())
+}
+
 pub async fn validate_compact_with_force(db: &Database) -> Result<(), Error> {
     let collection = db.collection::<Document>("test_collection");
     collection
@@ -63,7 +90,7 @@ pub async fn validate_compact_with_padding_factor(db: &Database) -> Result<(), E
         .await?;
 
     let result = db
-        .run_command(doc! {"compact": "test_collection", "paddingFactor": 1.5})
+        .run_command(doc! {"compact": "test_collection", "force": true, "paddingFactor": 1.5})
         .await;
 
     match result {
@@ -87,7 +114,7 @@ pub async fn validate_compact_with_padding_bytes(db: &Database) -> Result<(), Er
         .await?;
 
     let result = db
-        .run_command(doc! {"compact": "test_collection", "paddingBytes": 1024})
+        .run_command(doc! {"compact": "test_collection", "force": true, "paddingBytes": 1024})
         .await;
 
     match result {
@@ -106,7 +133,7 @@ pub async fn validate_compact_with_padding_bytes(db: &Database) -> Result<(), Er
 
 pub async fn validate_compact_nonexistent_collection(db: &Database) {
     let result = db
-        .run_command(doc! {"compact": "nonexistent_collection"})
+        .run_command(doc! {"compact": "nonexistent_collection", "force": true})
         .await;
 
     match result {
@@ -122,7 +149,7 @@ pub async fn validate_compact_nonexistent_collection(db: &Database) {
 }
 
 pub async fn validate_compact_invalid_arguments(db: &Database) {
-    let result = db.run_command(doc! {"compact": 123}).await;
+    let result = db.run_command(doc! {"compact": 123, "force": true}).await;
 
     match result {
         Err(e) => {
@@ -24,7 +24,10 @@
 )]
 
 use bson::doc;
-use mongodb::{error::Error, Database};
+use mongodb::{
+    error::{CommandError, Error, ErrorKind},
+    Database,
+};
 
 pub async fn validate_count(db: &Database) -> Result<(), Error> {
     let coll = db.collection("test");
@@ -37,3 +40,85 @@ pub async fn validate_count(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+pub async fn validate_count_estimate(db: &Database) -> Result<(), Error> {
+    let coll = db.collection("test");
+    coll.insert_many(vec![doc! {"a": 1}, doc! {"a": 2}, doc! {"a": 3}])
+        .await?;
+
+    let result = db
+        .run_command(doc! {"count": "test", "estimate": true})
+        .await?;
+    assert!(result.get_bool("estimated").unwrap());
+    assert_eq!(result.get_f64("ok").unwrap(), 1.0);
+
+    let result = db
+        .run_command(doc! {"count": "test", "estimate": true, "query": {"a": 1}})
+        .await;
+    let Err(err) = result else {
+        panic!("an estimate with a query should be rejected");
+    };
+    assert!(matches!(
+        *err.kind,
+        ErrorKind::Command(CommandError { code: 2, .. })
+    ));
+
+    Ok(())
+}
+
+pub async fn validate_count_exceeds_time_limit(db: &Database) -> Result<(), Error> {
+    let coll = db.collection("test");
+    let docs: Vec<_> = (0..10_000).map(|i| doc! {"a": i}).collect();
+    coll.insert_many(docs).await?;
+
+    // Building a large array per document takes far longer than the time limit.
+    let result = db
+        .run_command(doc! {
+            "count": "test",
+            "query": {"$expr": {"$gt": [{"$size": {"$range": [0, 100_000]}}, 0]}},
+            "maxTimeMS": 10,
+        })
+        .await;
+    let Err(err) = result else {
+        panic!("an exact count past maxTimeMS should fail rather than return a partial count");
+    };
+    assert!(matches!(
+        *err.kind,
+        ErrorKind::Command(CommandError { code: 50, .. })
+    ));
+
+    Ok(())
+}
+
+pub async fn validate_count_compound_hint(db: &Database) -> Result<(), Error> {
+    let coll = db.collection("test");
+    coll.insert_many(vec![
+        doc! {"a": 1, "b": 1},
+        doc! {"a": 1, "b": 2},
+        doc! {"a": 2, "b": 1},
+    ])
+    .await?;
+    db.run_command(doc! {
+        "createIndexes": "test",
+        "indexes": [{"key": {"a": 1, "b": -1}, "name": "a_1_b_-1"}],
+    })
+    .await?;
+
+    let result = db
+        .run_command(doc! {"count": "test", "query": {"a": 1}, "hint": {"a": 1, "b": -1}})
+        .await?;
+    assert_eq!(result.get_i32("n").unwrap(), 2);
+
+    let result = db
+        .run_command(doc! {"count": "test", "query": {"a": 1}, "hint": "b_1"})
+        .await;
+    let Err(err) = result else {
+        panic!("a hint naming no index should be rejected");
+    };
+    assert!(matches!(
+        *err.kind,
+        ErrorKind::Command(CommandError { code: 2, .. })
+    ));
+
+    Ok(())
+}
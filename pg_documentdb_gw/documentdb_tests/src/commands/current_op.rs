@@ -96,10 +96,13 @@ fn validate_current_op_response(current_op_response: &Document, inprog_present:
                     "Operation should contain 'opid' field"
                 );
 
-                assert!(
-                    op_doc.contains_key("op_prefix"),
-                    "Operation should contain 'op_prefix' field"
-                );
+                // Requests in flight on the gateway have numeric opids without a shard prefix
+                if op_doc.get_str("shard").ok() != Some("gateway") {
+                    assert!(
+                        op_doc.contains_key("op_prefix"),
+                        "Operation should contain 'op_prefix' field"
+                    );
+                }
             }
 
             if let Ok(active) = op_doc.get_bool("active") {
@@ -306,3 +309,40 @@ pub async fn validate_currentop_captures_mongodb_operations(db: &Database) -> Re
 
     Ok(())
 }
+
+pub async fn validate_current_op_secs_running_filter(db: &Database) -> Result<(), Error> {
+    let result = db
+        .run_command(doc! { "currentOp": 1, "$all": true, "secs_running": { "$gte": 3600 } })
+        .await?;
+
+    for op in result.get_array("inprog").unwrap() {
+        let op = op
+            .as_document()
+            .expect("Each inprog item should be a document");
+        assert!(
+            op.get_i64("secs_running").unwrap() >= 3600,
+            "Short-running operation should be filtered out: {op:?}"
+        );
+    }
+
+    // The currentOp request itself is in flight on the gateway.
+    let result = db
+        .run_command(doc! { "currentOp": 1, "shard": "gateway", "op": "command" })
+        .await?;
+    let inprog = result.get_array("inprog").unwrap();
+    assert!(!inprog.is_empty(), "Gateway operations should be reported");
+    for op in inprog {
+        let op = op.as_document().unwrap();
+        for field in [
+            "opid",
+            "secs_running",
+            "microsecs_running",
+            "client",
+            "desc",
+        ] {
+            assert!(op.contains_key(field), "Operation should contain '{field}'");
+        }
+    }
+
+    Ok(())
+}
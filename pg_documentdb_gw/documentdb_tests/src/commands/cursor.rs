@@ -22,6 +22,8 @@
 use bson::{doc, Document};
 use mongodb::{error::Error, Collection, Database};
 
+use crate::utils::commands::execute_command_and_validate_error;
+
 pub async fn validate_batch_size(db: &Database) -> Result<(), Error> {
     let coll = db.collection("test");
     coll.insert_many((0..6).map(|_| doc! { "a": 1 })).await?;
@@ -312,3 +314,88 @@ pub async fn validate_kill_cursor(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+fn cursor_batch<'a>(result: &'a Document, batch: &str) -> (i64, &'a bson::Array) {
+    let cursor = result.get_document("cursor").unwrap();
+    (
+        cursor.get_i64("id").unwrap(),
+        cursor.get_array(batch).unwrap(),
+    )
+}
+
+pub async fn validate_exhausted_cursor_is_closed(db: &Database) -> Result<(), Error> {
+    let coll = db.collection("test");
+    coll.insert_many((0..2).map(|i| doc! { "_id": i })).await?;
+
+    let result = db.run_command(doc! { "find": "test" }).await?;
+    let (cursor_id, first_batch) = cursor_batch(&result, "firstBatch");
+    assert_eq!(first_batch.len(), 2);
+    assert_eq!(cursor_id, 0, "An exhausted cursor should be closed");
+
+    Ok(())
+}
+
+pub async fn validate_tailable_cursor_stays_open(db: &Database) -> Result<(), Error> {
+    let coll = db.collection("test");
+    coll.insert_many((0..2).map(|i| doc! { "_id": i })).await?;
+
+    let result = db
+        .run_command(doc! { "find": "test", "tailable": true, "awaitData": true })
+        .await?;
+    let (cursor_id, first_batch) = cursor_batch(&result, "firstBatch");
+    assert_eq!(first_batch.len(), 2);
+    assert_ne!(
+        cursor_id, 0,
+        "A tailable cursor should outlive its documents"
+    );
+
+    let result = db
+        .run_command(doc! { "getMore": cursor_id, "collection": "test", "maxTimeMS": 200 })
+        .await?;
+    let (next_id, next_batch) = cursor_batch(&result, "nextBatch");
+    assert!(next_batch.is_empty());
+    assert_eq!(next_id, cursor_id);
+
+    coll.insert_one(doc! { "_id": 2 }).await?;
+    let result = db
+        .run_command(doc! { "getMore": cursor_id, "collection": "test", "maxTimeMS": 200 })
+        .await?;
+    let (next_id, next_batch) = cursor_batch(&result, "nextBatch");
+    assert_eq!(next_batch, &vec![bson::Bson::Document(doc! { "_id": 2 })]);
+    assert_eq!(next_id, cursor_id);
+
+    Ok(())
+}
+
+pub async fn validate_tailable_cursor_limit(db: &Database) -> Result<(), Error> {
+    let coll = db.collection("test");
+    coll.insert_many((0..3).map(|i| doc! { "_id": i })).await?;
+
+    let result = db
+        .run_command(doc! { "find": "test", "tailable": true, "batchSize": 1, "limit": 2 })
+        .await?;
+    let (cursor_id, first_batch) = cursor_batch(&result, "firstBatch");
+    assert_eq!(first_batch.len(), 1);
+    assert_ne!(cursor_id, 0);
+
+    let result = db
+        .run_command(doc! { "getMore": cursor_id, "collection": "test" })
+        .await?;
+    let (next_id, next_batch) = cursor_batch(&result, "nextBatch");
+    assert_eq!(next_batch.len(), 1);
+    assert_eq!(
+        next_id, 0,
+        "A tailable cursor closes once its limit is reached"
+    );
+
+    execute_command_and_validate_error(
+        db,
+        doc! { "find": "test", "awaitData": true },
+        9,
+        "Cannot set 'awaitData' without also setting 'tailable'",
+        "FailedToParse",
+    )
+    .await;
+
+    Ok(())
+}
@@ -26,6 +26,8 @@
 use bson::doc;
 use mongodb::{error::Error, Database};
 
+use crate::utils::commands::execute_command_and_validate_error;
+
 pub async fn validate_distinct(db: &Database) -> Result<(), Error> {
     let coll = db.collection("test");
     coll.insert_one(doc! {"a": 1}).await?;
@@ -52,3 +54,34 @@ pub async fn validate_distinct(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+pub async fn validate_distinct_with_collation(db: &Database) -> Result<(), Error> {
+    let coll = db.collection("test");
+    coll.insert_one(doc! {"a": "A"}).await?;
+    coll.insert_one(doc! {"a": "a"}).await?;
+
+    let result = db
+        .run_command(doc! {
+            "distinct": "test",
+            "key": "a",
+            "collation": { "locale": "en", "strength": 2 },
+        })
+        .await?;
+    assert_eq!(result.get_f64("ok").unwrap(), 1.0);
+    assert_eq!(result.get_array("values").unwrap().len(), 1);
+
+    execute_command_and_validate_error(
+        db,
+        doc! {
+            "distinct": "test",
+            "key": "a",
+            "collation": { "locale": "xx_YY" },
+        },
+        2,
+        "xx_YY",
+        "BadValue",
+    )
+    .await;
+
+    Ok(())
+}
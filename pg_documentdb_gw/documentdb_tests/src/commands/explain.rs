@@ -6,14 +6,24 @@
  *-------------------------------------------------------------------------
  */
 
+#![expect(
+    clippy::missing_panics_doc,
+    reason = "Test helper functions - panics are expected test failures"
+)]
 #![expect(
     clippy::missing_errors_doc,
     reason = "Test helper functions - error conditions are self-explanatory"
 )]
+#![expect(
+    clippy::unwrap_used,
+    reason = "Test helper functions - unwrap failures indicate test failures"
+)]
 
-use bson::doc;
+use bson::{doc, Bson};
 use mongodb::{error::Error, Database};
 
+use crate::utils::commands::execute_command_and_validate_error;
+
 pub async fn validate_explain(db: &Database) -> Result<(), Error> {
     let coll = db.collection("test");
 
@@ -46,3 +56,48 @@ pub async fn validate_explain(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+pub async fn validate_explain_execution_stats(db: &Database) -> Result<(), Error> {
+    let coll = db.collection("test");
+
+    coll.insert_one(doc! {"a":1}).await?;
+    coll.insert_one(doc! {"a":2}).await?;
+    coll.insert_one(doc! {"a":3}).await?;
+
+    let result = db
+        .run_command(doc! {
+            "explain": {
+                "find": "test",
+                "filter": {"a": {"$gte": 2}},
+            },
+            "verbosity": "executionStats",
+        })
+        .await?;
+
+    let stats = result.get_document("executionStats").unwrap();
+    assert_eq!(stats.get_i64("nReturned").unwrap(), 2);
+    let millis = match stats.get("executionTimeMillis").unwrap() {
+        Bson::Double(millis) => *millis,
+        Bson::Int32(millis) => f64::from(*millis),
+        other => panic!("unexpected executionTimeMillis {other:?}"),
+    };
+    assert!(millis > 0.0);
+
+    execute_command_and_validate_error(
+        db,
+        doc! {
+            "explain": {
+                "delete": "test",
+                "deletes": [{"q": {"a": 1}, "limit": 1}],
+            },
+            "verbosity": "executionStats",
+        },
+        20,
+        "cannot gather execution statistics without running the write",
+        "IllegalOperation",
+    )
+    .await;
+    assert_eq!(coll.count_documents(doc! {}).await?, 3);
+
+    Ok(())
+}
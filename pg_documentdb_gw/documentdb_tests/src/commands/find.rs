@@ -14,10 +14,17 @@
     clippy::missing_errors_doc,
     reason = "Test helper functions - error conditions are self-explanatory"
 )]
+#![expect(
+    clippy::unwrap_used,
+    reason = "Test helper functions - unwrap failures indicate test failures"
+)]
 
-use bson::{doc, Document};
+use bson::{doc, Bson, Document};
 use futures::StreamExt;
-use mongodb::{error::Error, Database};
+use mongodb::{
+    error::{CommandError, Error, ErrorKind},
+    Database,
+};
 
 pub async fn validate_find(db: &Database) -> Result<(), Error> {
     let coll = db.collection("test");
@@ -33,3 +40,92 @@ pub async fn validate_find(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+pub async fn validate_find_gateway_timing(db: &Database) -> Result<(), Error> {
+    let coll = db.collection("test");
+    coll.insert_one(doc! {"a":1}).await?;
+
+    let untimed = db.run_command(doc! { "find": "test" }).await?;
+    assert!(!untimed.contains_key("$gatewayTiming"));
+
+    let timed = db
+        .run_command(doc! { "find": "test", "$gatewayTiming": true })
+        .await?;
+    let timing = timed.get_document("$gatewayTiming").unwrap();
+    for phase in ["beginTransactionMicros", "executionMicros", "commitMicros"] {
+        assert!(timing.get_i64(phase).unwrap() >= 0, "{phase} should be set");
+    }
+    assert_eq!(
+        timed.get_document("cursor").unwrap(),
+        untimed.get_document("cursor").unwrap()
+    );
+
+    Ok(())
+}
+
+pub async fn validate_find_compound_hint(db: &Database) -> Result<(), Error> {
+    let coll = db.collection("test");
+    coll.insert_many(vec![
+        doc! {"a": 1, "b": 1},
+        doc! {"a": 1, "b": 2},
+        doc! {"a": 2, "b": 1},
+    ])
+    .await?;
+    db.run_command(doc! {
+        "createIndexes": "test",
+        "indexes": [{"key": {"a": 1, "b": -1}, "name": "a_1_b_-1"}],
+    })
+    .await?;
+
+    for hint in [
+        Bson::from("a_1_b_-1"),
+        Bson::from(doc! {"a": 1, "b": -1}),
+        Bson::from(doc! {}),
+    ] {
+        let result = db
+            .run_command(doc! {"find": "test", "filter": {"a": 1}, "hint": hint})
+            .await?;
+        let first_batch = result
+            .get_document("cursor")
+            .unwrap()
+            .get_array("firstBatch")
+            .unwrap();
+        assert_eq!(first_batch.len(), 2);
+    }
+
+    let result = db
+        .run_command(doc! {"find": "test", "hint": {"a": 1, "c": 1}})
+        .await;
+    let Err(err) = result else {
+        panic!("a hint matching no index should be rejected");
+    };
+    assert!(matches!(
+        *err.kind,
+        ErrorKind::Command(CommandError { code: 2, .. })
+    ));
+
+    Ok(())
+}
+
+pub async fn validate_find_nesting_limit(db: &Database) -> Result<(), Error> {
+    let coll = db.collection::<Document>("test");
+    coll.insert_one(doc! {"a": 1}).await?;
+
+    // Each $and nests a document in an array, two levels past the default limit of 200.
+    let mut filter = doc! {"a": 1};
+    for _ in 0..100 {
+        filter = doc! {"$and": [filter]};
+    }
+    let result = db
+        .run_command(doc! {"find": "test", "filter": filter})
+        .await;
+    let Err(err) = result else {
+        panic!("a filter nested past the limit should be rejected");
+    };
+    assert!(matches!(
+        *err.kind,
+        ErrorKind::Command(CommandError { code: 2, .. })
+    ));
+
+    Ok(())
+}
@@ -27,6 +27,8 @@ use bson::{doc, Document};
 use futures::StreamExt;
 use mongodb::{error::Error, Database};
 
+use crate::utils::commands::execute_command_and_validate_error;
+
 pub async fn validate_find_and_modify(db: &Database) -> Result<(), Error> {
     let coll = db.collection("test");
     coll.insert_one(doc! {"a": 1}).await?;
@@ -50,3 +52,85 @@ pub async fn validate_find_and_modify(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+pub async fn validate_find_and_modify_array_filters(db: &Database) -> Result<(), Error> {
+    let coll = db.collection::<Document>("test");
+    coll.insert_one(doc! {"_id": 1, "grades": [80, 90, 95]})
+        .await?;
+
+    let result = db
+        .run_command(doc! {
+            "findAndModify": "test",
+            "query": {"_id": 1},
+            "update": {"$set": {"grades.$[high]": 100}},
+            "arrayFilters": [{"high": {"$gte": 90}}],
+            "new": true,
+        })
+        .await?;
+    let grades: Vec<i32> = result
+        .get_document("value")
+        .unwrap()
+        .get_array("grades")
+        .unwrap()
+        .iter()
+        .map(|grade| grade.as_i32().unwrap())
+        .collect();
+    assert_eq!(grades, [80, 100, 100]);
+
+    execute_command_and_validate_error(
+        db,
+        doc! {
+            "findAndModify": "test",
+            "query": {"_id": 1},
+            "update": {"$set": {"grades.$[High]": 0}},
+            "arrayFilters": [{"High": {"$gte": 90}}],
+        },
+        2,
+        "Error parsing array filter :: caused by :: The top-level field name must be an alphanumeric string beginning with a lowercase letter, found 'High'",
+        "BadValue",
+    )
+    .await;
+
+    Ok(())
+}
+
+pub async fn validate_find_and_modify_upsert(db: &Database) -> Result<(), Error> {
+    let result = db
+        .run_command(doc! {
+            "findAndModify": "test",
+            "query": {"_id": 1},
+            "update": {"$set": {"a": 1}},
+            "upsert": true,
+            "new": true,
+        })
+        .await?;
+    assert_eq!(
+        result.get_document("value").unwrap(),
+        &doc! {"_id": 1, "a": 1}
+    );
+    let last_error = result.get_document("lastErrorObject").unwrap();
+    assert_eq!(last_error.get_i32("n").unwrap(), 1);
+    assert!(!last_error.get_bool("updatedExisting").unwrap());
+    assert_eq!(last_error.get_i32("upserted").unwrap(), 1);
+
+    // Without new, an upsert has no previous document to return.
+    let result = db
+        .run_command(doc! {
+            "findAndModify": "test",
+            "query": {"_id": 2},
+            "update": {"$set": {"a": 2}},
+            "upsert": true,
+        })
+        .await?;
+    assert_eq!(result.get("value"), Some(&bson::Bson::Null));
+    assert_eq!(
+        result
+            .get_document("lastErrorObject")
+            .unwrap()
+            .get_i32("upserted")
+            .unwrap(),
+        2
+    );
+
+    Ok(())
+}
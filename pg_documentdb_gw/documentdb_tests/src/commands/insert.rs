@@ -14,6 +14,10 @@
     clippy::missing_errors_doc,
     reason = "Test helper functions - error conditions are self-explanatory"
 )]
+#![expect(
+    clippy::unwrap_used,
+    reason = "Test helper functions - unwrap failures indicate test failures"
+)]
 
 use bson::{doc, Document};
 use futures::StreamExt;
@@ -40,3 +44,94 @@ pub async fn validate_insert_many(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Inserts five documents where the third duplicates the first's `_id`.
+async fn insert_with_duplicate(db: &Database, ordered: bool) -> Result<Document, Error> {
+    db.run_command(doc! {
+        "insert": "test",
+        "documents": [
+            {"_id": 1}, {"_id": 2}, {"_id": 1}, {"_id": 4}, {"_id": 5},
+        ],
+        "ordered": ordered,
+    })
+    .await
+}
+
+fn write_error_indexes(result: &Document) -> Vec<i32> {
+    result
+        .get_array("writeErrors")
+        .unwrap()
+        .iter()
+        .map(|error| {
+            let error = error.as_document().unwrap();
+            assert_eq!(error.get_i32("code").unwrap(), 11000);
+            error.get_i32("index").unwrap()
+        })
+        .collect()
+}
+
+async fn inserted_ids(db: &Database) -> Result<Vec<i32>, Error> {
+    let docs: Vec<Document> = db
+        .collection::<Document>("test")
+        .find(doc! {})
+        .sort(doc! {"_id": 1})
+        .await?
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    Ok(docs.iter().map(|d| d.get_i32("_id").unwrap()).collect())
+}
+
+pub async fn validate_insert_ordered_stops_at_first_error(db: &Database) -> Result<(), Error> {
+    let result = insert_with_duplicate(db, true).await?;
+
+    assert_eq!(result.get_i32("n").unwrap(), 2);
+    assert_eq!(write_error_indexes(&result), vec![2]);
+    assert_eq!(inserted_ids(db).await?, vec![1, 2]);
+
+    Ok(())
+}
+
+pub async fn validate_insert_unordered_continues_past_errors(db: &Database) -> Result<(), Error> {
+    let result = insert_with_duplicate(db, false).await?;
+
+    assert_eq!(result.get_i32("n").unwrap(), 4);
+    assert_eq!(write_error_indexes(&result), vec![2]);
+    assert_eq!(inserted_ids(db).await?, vec![1, 2, 4, 5]);
+
+    Ok(())
+}
+
+pub async fn validate_insert_write_concern_timeout(db: &Database) -> Result<(), Error> {
+    // The test backend has no replicas, so a second node never acknowledges the write.
+    let result = db
+        .run_command(doc! {
+            "insert": "test",
+            "documents": [{"_id": 1}],
+            "writeConcern": {"w": 2, "wtimeout": 100},
+        })
+        .await?;
+
+    assert_eq!(result.get_i32("n").unwrap(), 1);
+    let error = result.get_document("writeConcernError").unwrap();
+    assert_eq!(error.get_i32("code").unwrap(), 64);
+    assert_eq!(error.get_str("codeName").unwrap(), "WriteConcernFailed");
+    assert_eq!(inserted_ids(db).await?, vec![1]);
+
+    Ok(())
+}
+
+pub async fn validate_insert_unacknowledged(db: &Database) -> Result<(), Error> {
+    let result = db
+        .run_command(doc! {
+            "insert": "test",
+            "documents": [{"_id": 1}],
+            "writeConcern": {"w": 0},
+        })
+        .await?;
+
+    assert!(result.get("n").is_none());
+    assert_eq!(inserted_ids(db).await?, vec![1]);
+
+    Ok(())
+}
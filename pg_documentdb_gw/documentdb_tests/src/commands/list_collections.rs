@@ -19,7 +19,7 @@
     reason = "Test helper functions - unwrap failures indicate test failures"
 )]
 
-use bson::doc;
+use bson::{doc, Document};
 use mongodb::{error::Error, Database};
 
 pub async fn validate_list_collections(db: &Database) -> Result<(), Error> {
@@ -39,3 +39,44 @@ pub async fn validate_list_collections(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+fn first_batch(result: &Document) -> Vec<Document> {
+    result
+        .get_document("cursor")
+        .unwrap()
+        .get_array("firstBatch")
+        .unwrap()
+        .iter()
+        .map(|collection| collection.as_document().unwrap().clone())
+        .collect()
+}
+
+pub async fn validate_list_collections_filter_by_name(db: &Database) -> Result<(), Error> {
+    db.collection("orders").insert_one(doc! {"a": 1}).await?;
+    db.collection("customers").insert_one(doc! {"a": 1}).await?;
+
+    let result = db
+        .run_command(doc! {"listCollections": 1, "filter": {"name": "orders"}})
+        .await?;
+    let collections = first_batch(&result);
+    assert_eq!(collections.len(), 1);
+    assert_eq!(collections[0].get_str("name").unwrap(), "orders");
+    assert_eq!(collections[0].get_str("type").unwrap(), "collection");
+
+    Ok(())
+}
+
+pub async fn validate_list_collections_name_only(db: &Database) -> Result<(), Error> {
+    db.collection("test").insert_one(doc! {"a": 1}).await?;
+
+    let result = db
+        .run_command(doc! {"listCollections": 1, "nameOnly": true})
+        .await?;
+    let collections = first_batch(&result);
+    assert_eq!(collections.len(), 1);
+    let keys: Vec<&str> = collections[0].keys().map(String::as_str).collect();
+    assert_eq!(keys, ["name", "type"]);
+    assert_eq!(collections[0].get_str("name").unwrap(), "test");
+
+    Ok(())
+}
@@ -25,6 +25,7 @@ pub mod killop;
 pub mod list_collections;
 pub mod list_databases;
 pub mod multi_connect;
+pub mod parameters;
 pub mod rbac_builtin_read_any_database_tests;
 pub mod rename_collection;
 pub mod session;
@@ -0,0 +1,92 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_tests/src/commands/parameters.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+#![expect(
+    clippy::missing_panics_doc,
+    reason = "Test helper functions - panics are expected test failures"
+)]
+#![expect(
+    clippy::missing_errors_doc,
+    reason = "Test helper functions - error conditions are self-explanatory"
+)]
+#![expect(
+    clippy::unwrap_used,
+    reason = "Test helper functions - unwrap failures indicate test failures"
+)]
+
+use bson::doc;
+use mongodb::{error::Error, Client};
+
+use crate::utils::commands::execute_command_and_validate_error;
+
+pub async fn validate_get_parameter_wildcard(client: &Client) -> Result<(), Error> {
+    let db = client.database("admin");
+
+    let result = db.run_command(doc! {"getParameter": "*"}).await?;
+
+    assert!(result.contains_key("logLevel"));
+    assert!(result.contains_key("tracingSamplingRatio"));
+    assert!(result.contains_key("connectionPoolMaxConnections"));
+    assert_eq!(result.keys().last().unwrap(), "ok");
+
+    Ok(())
+}
+
+pub async fn validate_get_gateway_parameter(client: &Client) -> Result<(), Error> {
+    let db = client.database("admin");
+
+    let result = db
+        .run_command(doc! {"getParameter": {"showDetails": true}, "slowOpThresholdMs": 1})
+        .await?;
+
+    let details = result.get_document("slowOpThresholdMs").unwrap();
+    assert!(details.get_i64("value").unwrap() >= 0);
+    assert!(details.contains_key("settableAtRuntime"));
+    assert!(details.contains_key("settableAtStartup"));
+
+    let result = db
+        .run_command(doc! {"getParameter": {"prefix": "connectionPool"}})
+        .await?;
+    for key in result.keys().filter(|key| *key != "ok") {
+        assert!(key.starts_with("connectionPool"), "unexpected {key}");
+    }
+
+    Ok(())
+}
+
+pub async fn validate_set_parameter(client: &Client) -> Result<(), Error> {
+    let db = client.database("admin");
+
+    let result = db
+        .run_command(doc! {"setParameter": 1, "logLevel": "info"})
+        .await?;
+    assert!(result.contains_key("was"));
+
+    let result = db
+        .run_command(doc! {"setParameter": 1, "tracingSamplingRatio": 1.0})
+        .await?;
+    result.get_f64("was").unwrap();
+
+    let result = db
+        .run_command(doc! {"getParameter": 1, "tracingSamplingRatio": 1})
+        .await?;
+    assert!((result.get_f64("tracingSamplingRatio").unwrap() - 1.0).abs() < f64::EPSILON);
+
+    Ok(())
+}
+
+pub async fn validate_set_unknown_parameter(client: &Client) {
+    execute_command_and_validate_error(
+        &client.database("admin"),
+        doc! {"setParameter": 1, "notAParameter": 1},
+        2,
+        "attempted to set unrecognized parameter [notAParameter]",
+        "BadValue",
+    )
+    .await;
+}
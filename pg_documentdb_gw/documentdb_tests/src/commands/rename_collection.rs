@@ -33,9 +33,16 @@ use crate::utils::commands;
 // Error code constants
 const ERR_COMMAND_NOT_SUPPORTED: i32 = 115;
 const ERR_ILLEGAL_OPERATION: i32 = 20;
+const ERR_NAMESPACE_EXISTS: i32 = 48;
 const ERR_NAMESPACE_NOT_FOUND: i32 = 26;
+const ERR_UNAUTHORIZED: i32 = 13;
 const ERR_UNKNOWN_BSON_FIELD: i32 = 40415;
 
+/// renameCollection may only be run against the admin database.
+fn admin(db: &Database) -> Database {
+    db.client().database("admin")
+}
+
 /// Returns a fully-qualified namespace string "db.collection".
 fn ns(db: &Database, coll: &str) -> String {
     format!("{}.{}", db.name(), coll)
@@ -46,7 +53,7 @@ pub async fn validate_rename_collection_basic(db: &Database) -> Result<(), Error
         .insert_one(doc! { "_id": 1, "a": 1 })
         .await?;
 
-    let result = db
+    let result = admin(db)
         .run_command(doc! {
             "renameCollection": ns(db, "source_coll"),
             "to": ns(db, "target_coll"),
@@ -72,7 +79,7 @@ pub async fn validate_rename_collection_with_drop_target(db: &Database) -> Resul
         .insert_one(doc! { "_id": 2, "from": "b" })
         .await?;
 
-    let result = db
+    let result = admin(db)
         .run_command(doc! {
             "renameCollection": ns(db, "coll_a"),
             "to": ns(db, "coll_b"),
@@ -98,7 +105,7 @@ pub async fn validate_rename_collection_with_drop_target(db: &Database) -> Resul
 
 pub async fn validate_rename_collection_cross_db_error(db: &Database) {
     commands::execute_command_and_validate_error(
-        db,
+        &admin(db),
         doc! {
             "renameCollection": ns(db, "coll1"),
             "to": "other_db.coll1",
@@ -117,7 +124,7 @@ pub async fn validate_rename_collection_self_rename_error(db: &Database) {
         .unwrap();
 
     commands::execute_command_and_validate_error(
-        db,
+        &admin(db),
         doc! {
             "renameCollection": ns(db, "same_coll"),
             "to": ns(db, "same_coll"),
@@ -131,7 +138,7 @@ pub async fn validate_rename_collection_self_rename_error(db: &Database) {
 
 pub async fn validate_rename_collection_not_found_error(db: &Database) {
     commands::execute_command_and_validate_error(
-        db,
+        &admin(db),
         doc! {
             "renameCollection": ns(db, "no_such_coll"),
             "to": ns(db, "target"),
@@ -150,7 +157,7 @@ pub async fn validate_rename_collection_unknown_field_error(db: &Database) {
         .unwrap();
 
     commands::execute_command_and_validate_error(
-        db,
+        &admin(db),
         doc! {
             "renameCollection": ns(db, "coll_unknown"),
             "to": ns(db, "coll_unknown_target"),
@@ -162,3 +169,40 @@ pub async fn validate_rename_collection_unknown_field_error(db: &Database) {
     )
     .await;
 }
+
+pub async fn validate_rename_collection_target_exists_error(db: &Database) {
+    db.collection::<Document>("existing_source")
+        .insert_one(doc! { "_id": 1 })
+        .await
+        .unwrap();
+    db.collection::<Document>("existing_target")
+        .insert_one(doc! { "_id": 2 })
+        .await
+        .unwrap();
+
+    commands::execute_command_and_validate_error(
+        &admin(db),
+        doc! {
+            "renameCollection": ns(db, "existing_source"),
+            "to": ns(db, "existing_target"),
+        },
+        ERR_NAMESPACE_EXISTS,
+        "is already present",
+        "NamespaceExists",
+    )
+    .await;
+}
+
+pub async fn validate_rename_collection_requires_admin_error(db: &Database) {
+    commands::execute_command_and_validate_error(
+        db,
+        doc! {
+            "renameCollection": ns(db, "coll1"),
+            "to": ns(db, "coll2"),
+        },
+        ERR_UNAUTHORIZED,
+        "may only be run against the admin database",
+        "Unauthorized",
+    )
+    .await;
+}
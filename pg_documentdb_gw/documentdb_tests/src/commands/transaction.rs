@@ -22,6 +22,8 @@ use mongodb::{
     Client, Database,
 };
 
+use crate::utils::commands::execute_command_and_validate_error;
+
 pub async fn validate_commit_transaction(client: &Client, db: &Database) -> Result<(), Error> {
     let mut session = client.start_session().await?;
 
@@ -104,6 +106,26 @@ async fn assert_blocked_in_transaction(
     Ok(())
 }
 
+pub async fn validate_snapshot_read_concern_outside_transaction(
+    db: &Database,
+) -> Result<(), Error> {
+    execute_command_and_validate_error(
+        db,
+        doc! { "find": "test", "readConcern": { "level": "snapshot" } },
+        72,
+        "readConcern level snapshot is only valid in multi-statement transactions",
+        "InvalidOptions",
+    )
+    .await;
+
+    // Other levels run at the default isolation.
+    for level in ["local", "majority"] {
+        db.run_command(doc! { "find": "test", "readConcern": { "level": level } })
+            .await?;
+    }
+    Ok(())
+}
+
 pub async fn validate_list_collections_blocked_in_transaction(
     client: &Client,
 ) -> Result<(), Error> {
@@ -58,7 +58,6 @@ const UNSUPPORTED_COMMANDS: &[&str] = &[
     "clone",
     "cloneCollection",
     "cloneCollectionAsCapped",
-    "connPoolStats",
     "connPoolSync",
     "convertToCapped",
     "copydb",
@@ -143,7 +142,6 @@ const UNSUPPORTED_COMMANDS: &[&str] = &[
     "serverStatus",
     "setFeatureCompatibilityVersion",
     "setFreeMonitoring",
-    "setParameter",
     "setShardVersion",
     "shardConnPoolStats",
     "shardingState",
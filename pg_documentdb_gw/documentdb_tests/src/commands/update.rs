@@ -23,6 +23,8 @@ use bson::{doc, Document};
 use futures::StreamExt;
 use mongodb::{error::Error, options::UpdateOptions, results::UpdateResult, Database};
 
+use crate::utils::commands::execute_command_and_validate_error;
+
 pub async fn validate_update_one(db: &Database) -> Result<(), Error> {
     let coll = db.collection("test");
     coll.insert_one(doc! {"a": 1}).await?;
@@ -102,3 +104,42 @@ pub async fn validate_update_many(db: &Database) -> Result<(), Error> {
 
     Ok(())
 }
+
+pub async fn validate_update_let_variables(db: &Database) -> Result<(), Error> {
+    let coll = db.collection::<Document>("test");
+    coll.insert_many([doc! {"_id": 1, "a": 1}, doc! {"_id": 2, "a": 2}])
+        .await?;
+
+    let result = db
+        .run_command(doc! {
+            "update": "test",
+            "updates": [{
+                "q": {"$expr": {"$eq": ["$a", "$$target"]}},
+                "u": [{"$set": {"b": {"$add": ["$a", "$$increment"]}}}],
+            }],
+            "let": {"target": 2, "increment": 10},
+        })
+        .await?;
+    assert_eq!(result.get_i32("nModified").expect("nModified"), 1);
+
+    let updated = coll
+        .find_one(doc! {"_id": 2})
+        .await?
+        .expect("document 2 should exist");
+    assert_eq!(updated.get_i32("b").expect("b should be set"), 12);
+
+    execute_command_and_validate_error(
+        db,
+        doc! {
+            "update": "test",
+            "updates": [{"q": {}, "u": [{"$set": {"b": "$$missing"}}]}],
+            "let": {"target": 2},
+        },
+        9,
+        "Use of undefined variable: missing",
+        "FailedToParse",
+    )
+    .await;
+
+    Ok(())
+}
@@ -22,12 +22,38 @@
 use bson::doc;
 use mongodb::{error::Error, Database};
 
+use crate::utils::commands::execute_command_and_validate_error;
+
 pub async fn validate_command_validate(db: &Database) -> Result<(), Error> {
     db.collection("test").insert_one(doc! {"a": 1}).await?;
     let result = db.run_command(doc! {"validate": "test"}).await?;
 
     assert!(result.get_bool("valid").unwrap());
     assert!(!result.get_bool("repaired").unwrap());
+    assert_eq!(result.get_i64("nrecords").unwrap(), 1);
+    assert_eq!(
+        result
+            .get_document("keysPerIndex")
+            .unwrap()
+            .get_i64("_id_")
+            .unwrap(),
+        1
+    );
+
+    Ok(())
+}
+
+pub async fn validate_repair_requires_admin(db: &Database) -> Result<(), Error> {
+    db.collection("test").insert_one(doc! {"a": 1}).await?;
+
+    execute_command_and_validate_error(
+        db,
+        doc! {"validate": "test", "repair": true},
+        13,
+        "validate with repair may only be run against the admin database.",
+        "Unauthorized",
+    )
+    .await;
 
     Ok(())
 }
@@ -63,6 +63,7 @@ pub async fn run_test_gateway(
         connection_pool_manager,
         tls_provider,
         None, // custom_pg_error_mapper
+        None, // database_authorizer
     );
 
     ready_flag.store(true, Ordering::SeqCst);
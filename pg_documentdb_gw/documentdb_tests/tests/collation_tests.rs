@@ -0,0 +1,73 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_tests/tests/collation_tests.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use bson::{doc, Document};
+use documentdb_tests::test_setup::{clients, config::setup_configuration, initialize};
+use mongodb::{error::Error, Database};
+
+async fn collation_db(name: &str) -> Result<Database, Error> {
+    let mut config = setup_configuration();
+    config.enable_collation = Some(true);
+    let client = initialize::initialize_with_config(config).await?;
+    clients::setup_db(&client, name).await
+}
+
+#[tokio::test]
+async fn case_insensitive_find_matches_mixed_case() -> Result<(), Error> {
+    let db = collation_db("collation_find").await?;
+    db.collection("test")
+        .insert_many([
+            doc! { "_id": 1, "a": "abc" },
+            doc! { "_id": 2, "a": "ABC" },
+            doc! { "_id": 3, "a": "abd" },
+        ])
+        .await?;
+
+    let result = db
+        .run_command(doc! {
+            "find": "test",
+            "filter": { "a": "aBc" },
+            "sort": { "_id": 1 },
+            "collation": { "locale": "en", "strength": 2 },
+        })
+        .await?;
+    let ids: Vec<i32> = result
+        .get_document("cursor")
+        .and_then(|cursor| cursor.get_array("firstBatch"))
+        .expect("find should return a first batch")
+        .iter()
+        .filter_map(|document| document.as_document().and_then(|d| d.get_i32("_id").ok()))
+        .collect();
+    assert_eq!(ids, [1, 2]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn command_collation_applies_to_deletes() -> Result<(), Error> {
+    let db = collation_db("collation_delete").await?;
+    let coll = db.collection::<Document>("test");
+    coll.insert_many([
+        doc! { "_id": 1, "a": "abc" },
+        doc! { "_id": 2, "a": "ABC" },
+        doc! { "_id": 3, "a": "abd" },
+    ])
+    .await?;
+
+    let result = db
+        .run_command(doc! {
+            "delete": "test",
+            "deletes": [{ "q": { "a": "abc" }, "limit": 0 }],
+            "collation": { "locale": "en", "strength": 2 },
+        })
+        .await?;
+    assert_eq!(result.get_i32("n").unwrap_or_default(), 2);
+    assert_eq!(coll.count_documents(doc! {}).await?, 1);
+
+    Ok(())
+}
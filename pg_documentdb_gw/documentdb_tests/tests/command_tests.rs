@@ -29,6 +29,34 @@ async fn insert_many() -> Result<(), Error> {
     insert::validate_insert_many(&db).await
 }
 
+#[tokio::test]
+async fn insert_ordered_stops_at_first_error() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_insert_ordered_error").await?;
+
+    insert::validate_insert_ordered_stops_at_first_error(&db).await
+}
+
+#[tokio::test]
+async fn insert_unordered_continues_past_errors() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_insert_unordered_error").await?;
+
+    insert::validate_insert_unordered_continues_past_errors(&db).await
+}
+
+#[tokio::test]
+async fn insert_write_concern_timeout() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_insert_write_concern_timeout").await?;
+
+    insert::validate_insert_write_concern_timeout(&db).await
+}
+
+#[tokio::test]
+async fn insert_unacknowledged() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_insert_unacknowledged").await?;
+
+    insert::validate_insert_unacknowledged(&db).await
+}
+
 #[tokio::test]
 async fn find() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_find").await?;
@@ -36,6 +64,27 @@ async fn find() -> Result<(), Error> {
     find::validate_find(&db).await
 }
 
+#[tokio::test]
+async fn find_gateway_timing() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_find_gateway_timing").await?;
+
+    find::validate_find_gateway_timing(&db).await
+}
+
+#[tokio::test]
+async fn find_compound_hint() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_find_compound_hint").await?;
+
+    find::validate_find_compound_hint(&db).await
+}
+
+#[tokio::test]
+async fn find_nesting_limit() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_find_nesting_limit").await?;
+
+    find::validate_find_nesting_limit(&db).await
+}
+
 #[tokio::test]
 async fn aggregate() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_aggregate").await?;
@@ -43,6 +92,70 @@ async fn aggregate() -> Result<(), Error> {
     aggregate::validate_aggregate(&db).await
 }
 
+#[tokio::test]
+async fn aggregate_out() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_aggregate_out").await?;
+
+    aggregate::validate_out_replaces_collection(&db).await
+}
+
+#[tokio::test]
+async fn aggregate_merge() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_aggregate_merge").await?;
+
+    aggregate::validate_merge_upserts(&db).await
+}
+
+#[tokio::test]
+async fn aggregate_merge_when_not_matched_fail() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_aggregate_merge_fail").await?;
+
+    aggregate::validate_merge_when_not_matched_fail(&db).await
+}
+
+#[tokio::test]
+async fn aggregate_zero_batch_size_read() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_aggregate_zero_batch_read").await?;
+
+    aggregate::validate_zero_batch_size_primes_cursor(&db).await
+}
+
+#[tokio::test]
+async fn aggregate_zero_batch_size_write() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_aggregate_zero_batch_write").await?;
+
+    aggregate::validate_zero_batch_size_runs_writes_at_open(&db).await
+}
+
+#[tokio::test]
+async fn aggregate_lookup() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_aggregate_lookup").await?;
+
+    aggregate::validate_lookup_local_foreign_field(&db).await
+}
+
+#[tokio::test]
+async fn aggregate_lookup_pipeline() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_aggregate_lookup_pipeline").await?;
+
+    aggregate::validate_lookup_pipeline(&db).await
+}
+
+#[tokio::test]
+async fn aggregate_lookup_missing_collection() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_aggregate_lookup_missing_collection")
+        .await?;
+
+    aggregate::validate_lookup_missing_collection(&db).await
+}
+
+#[tokio::test]
+async fn aggregate_index_stats() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_aggregate_index_stats").await?;
+
+    aggregate::validate_index_stats(&db).await
+}
+
 #[tokio::test]
 async fn update_one() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_update_one").await?;
@@ -57,6 +170,13 @@ async fn update_many() -> Result<(), Error> {
     update::validate_update_many(&db).await
 }
 
+#[tokio::test]
+async fn update_let_variables() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_update_let_variables").await?;
+
+    update::validate_update_let_variables(&db).await
+}
+
 #[tokio::test]
 async fn delete_one() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_delete_one").await?;
@@ -78,6 +198,20 @@ async fn list_collections() -> Result<(), Error> {
     list_collections::validate_list_collections(&db).await
 }
 
+#[tokio::test]
+async fn list_collections_filter_by_name() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_list_collections_filter").await?;
+
+    list_collections::validate_list_collections_filter_by_name(&db).await
+}
+
+#[tokio::test]
+async fn list_collections_name_only() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_list_collections_name_only").await?;
+
+    list_collections::validate_list_collections_name_only(&db).await
+}
+
 #[tokio::test]
 async fn list_indexes() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_list_indexes").await?;
@@ -106,6 +240,13 @@ async fn validate() -> Result<(), Error> {
     validate_cmd::validate_command_validate(&db).await
 }
 
+#[tokio::test]
+async fn validate_repair_requires_admin() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_validate_repair").await?;
+
+    validate_cmd::validate_repair_requires_admin(&db).await
+}
+
 #[tokio::test]
 async fn is_db_grid() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_is_db_grid").await?;
@@ -120,6 +261,20 @@ async fn find_and_modify() -> Result<(), Error> {
     find_and_modify::validate_find_and_modify(&db).await
 }
 
+#[tokio::test]
+async fn find_and_modify_array_filters() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_find_and_modify_array_filters").await?;
+
+    find_and_modify::validate_find_and_modify_array_filters(&db).await
+}
+
+#[tokio::test]
+async fn find_and_modify_upsert() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_find_and_modify_upsert").await?;
+
+    find_and_modify::validate_find_and_modify_upsert(&db).await
+}
+
 #[tokio::test]
 async fn distinct() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_distinct").await?;
@@ -127,6 +282,13 @@ async fn distinct() -> Result<(), Error> {
     distinct::validate_distinct(&db).await
 }
 
+#[tokio::test]
+async fn distinct_with_collation() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_distinct_collation").await?;
+
+    distinct::validate_distinct_with_collation(&db).await
+}
+
 #[tokio::test]
 async fn create_indexes() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_create_indexes").await?;
@@ -141,6 +303,27 @@ async fn count() -> Result<(), Error> {
     count::validate_count(&db).await
 }
 
+#[tokio::test]
+async fn count_estimate() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_count_estimate").await?;
+
+    count::validate_count_estimate(&db).await
+}
+
+#[tokio::test]
+async fn count_exceeds_time_limit() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_count_exceeds_time_limit").await?;
+
+    count::validate_count_exceeds_time_limit(&db).await
+}
+
+#[tokio::test]
+async fn count_compound_hint() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_count_compound_hint").await?;
+
+    count::validate_count_compound_hint(&db).await
+}
+
 #[tokio::test]
 async fn create() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_create").await?;
@@ -148,6 +331,13 @@ async fn create() -> Result<(), Error> {
     collection_cmd::validate_create(&db).await
 }
 
+#[tokio::test]
+async fn coll_mod() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_coll_mod").await?;
+
+    collection_cmd::validate_coll_mod(&db).await
+}
+
 #[tokio::test]
 async fn host_info() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_host_info").await?;
@@ -169,6 +359,20 @@ async fn coll_stats() -> Result<(), Error> {
     coll_stats::validate_coll_stats(&db).await
 }
 
+#[tokio::test]
+async fn coll_stats_scale() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_coll_stats_scale").await?;
+
+    coll_stats::validate_coll_stats_scale(&db).await
+}
+
+#[tokio::test]
+async fn coll_stats_stage() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_coll_stats_stage").await?;
+
+    coll_stats::validate_coll_stats_stage(&db).await
+}
+
 #[tokio::test]
 async fn drop() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_drop").await?;
@@ -176,6 +380,13 @@ async fn drop() -> Result<(), Error> {
     collection_cmd::validate_drop(&db).await
 }
 
+#[tokio::test]
+async fn drop_missing() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("commands_tests_drop_missing").await?;
+
+    collection_cmd::validate_drop_missing(&db).await
+}
+
 #[tokio::test]
 async fn drop_indexes() -> Result<(), Error> {
     let db = initialize::initialize_with_db("commands_tests_drop_indexes").await?;
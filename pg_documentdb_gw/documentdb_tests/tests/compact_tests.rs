@@ -16,6 +16,13 @@ async fn validate_compact_basic() -> Result<(), Error> {
     compact::validate_compact_basic(&db).await
 }
 
+#[tokio::test]
+async fn validate_compact_requires_force() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("compact_tests_requires_force").await?;
+
+    compact::validate_compact_requires_force(&db).await
+}
+
 #[tokio::test]
 async fn validate_compact_with_force() -> Result<(), Error> {
     let db = initialize::initialize_with_db("compact_tests_force").await?;
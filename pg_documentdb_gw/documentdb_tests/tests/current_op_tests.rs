@@ -36,3 +36,10 @@ async fn test_currentop_captures_mongodb_operations() -> Result<(), Error> {
 
     current_op::validate_currentop_captures_mongodb_operations(&db).await
 }
+
+#[tokio::test]
+async fn test_current_op_secs_running_filter() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("currentop_secs_running").await?;
+
+    current_op::validate_current_op_secs_running_filter(&db).await
+}
@@ -45,3 +45,24 @@ async fn test_kill_cursor() -> Result<(), Error> {
 
     cursor::validate_kill_cursor(&db).await
 }
+
+#[tokio::test]
+async fn test_exhausted_cursor_is_closed() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("cursor_tests_exhausted").await?;
+
+    cursor::validate_exhausted_cursor_is_closed(&db).await
+}
+
+#[tokio::test]
+async fn test_tailable_cursor_stays_open() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("cursor_tests_tailable").await?;
+
+    cursor::validate_tailable_cursor_stays_open(&db).await
+}
+
+#[tokio::test]
+async fn test_tailable_cursor_limit() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("cursor_tests_tailable_limit").await?;
+
+    cursor::validate_tailable_cursor_limit(&db).await
+}
@@ -15,3 +15,10 @@ async fn explain() -> Result<(), Error> {
 
     explain::validate_explain(&db).await
 }
+
+#[tokio::test]
+async fn explain_execution_stats() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("explain_execution_stats").await?;
+
+    explain::validate_explain_execution_stats(&db).await
+}
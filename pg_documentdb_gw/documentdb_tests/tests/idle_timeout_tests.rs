@@ -0,0 +1,56 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_tests/tests/idle_timeout_tests.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use std::time::Duration;
+
+use bson::doc;
+use documentdb_tests::{
+    test_setup::{clients, config::setup_configuration, initialize},
+    utils::commands::execute_command_and_validate_error,
+};
+use mongodb::error::Error;
+
+#[tokio::test]
+async fn idle_connection_releases_its_cursors() -> Result<(), Error> {
+    let mut config = setup_configuration();
+    config.client_idle_timeout_ms = Some(1000);
+    let client = initialize::initialize_with_config(config).await?;
+    let db = clients::setup_db(&client, "idle_timeout_tests").await?;
+
+    db.collection("test")
+        .insert_many((0..5).map(|i| doc! { "_id": i }))
+        .await?;
+    let find_result = db
+        .run_command(doc! { "find": "test", "batchSize": 1 })
+        .await?;
+    let cursor_id = find_result
+        .get_document("cursor")
+        .unwrap()
+        .get_i64("id")
+        .unwrap();
+    assert_ne!(
+        cursor_id, 0,
+        "Cursor should stay open after the first batch"
+    );
+
+    // Let the gateway close the connection that opened the cursor.
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    // A new client gets fresh connections, so the getMore can't be retried on the old one.
+    let db = clients::get_client()?.database("idle_timeout_tests");
+    execute_command_and_validate_error(
+        &db,
+        doc! { "getMore": cursor_id, "collection": "test" },
+        43,
+        "Provided cursor was not found.",
+        "CursorNotFound",
+    )
+    .await;
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+/*-------------------------------------------------------------------------
+ * Copyright (c) Microsoft Corporation.  All rights reserved.
+ *
+ * documentdb_tests/tests/parameter_tests.rs
+ *
+ *-------------------------------------------------------------------------
+ */
+
+use documentdb_tests::{commands::parameters, test_setup::initialize};
+use mongodb::error::Error;
+
+#[tokio::test]
+async fn validate_get_parameter_wildcard() -> Result<(), Error> {
+    let client = initialize::initialize().await?;
+
+    parameters::validate_get_parameter_wildcard(&client).await
+}
+
+#[tokio::test]
+async fn validate_get_gateway_parameter() -> Result<(), Error> {
+    let client = initialize::initialize().await?;
+
+    parameters::validate_get_gateway_parameter(&client).await
+}
+
+#[tokio::test]
+async fn validate_set_parameter() -> Result<(), Error> {
+    let client = initialize::initialize().await?;
+
+    parameters::validate_set_parameter(&client).await
+}
+
+#[tokio::test]
+async fn validate_set_unknown_parameter() -> Result<(), Error> {
+    let client = initialize::initialize().await?;
+
+    parameters::validate_set_unknown_parameter(&client).await;
+    Ok(())
+}
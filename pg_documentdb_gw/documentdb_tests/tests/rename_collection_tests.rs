@@ -54,3 +54,19 @@ async fn validate_rename_collection_unknown_field_error() -> Result<(), Error> {
     rename_collection::validate_rename_collection_unknown_field_error(&db).await;
     Ok(())
 }
+
+#[tokio::test]
+async fn validate_rename_collection_target_exists_error() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("rename_tests_target_exists").await?;
+
+    rename_collection::validate_rename_collection_target_exists_error(&db).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn validate_rename_collection_requires_admin_error() -> Result<(), Error> {
+    let db = initialize::initialize_with_db("rename_tests_requires_admin").await?;
+
+    rename_collection::validate_rename_collection_requires_admin_error(&db).await;
+    Ok(())
+}
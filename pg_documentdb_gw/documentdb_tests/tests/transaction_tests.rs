@@ -28,6 +28,14 @@ async fn abort() -> Result<(), Error> {
     transaction::validate_abort_transaction(&client, &db).await
 }
 
+#[tokio::test]
+async fn snapshot_read_concern_outside_transaction() -> Result<(), Error> {
+    let client = initialize::initialize().await?;
+    let db = clients::setup_db(&client, "snapshot_outside_txn").await?;
+
+    transaction::validate_snapshot_read_concern_outside_transaction(&db).await
+}
+
 #[tokio::test]
 async fn list_collections_blocked_in_transaction() -> Result<(), Error> {
     let client = initialize::initialize().await?;
@@ -135,6 +135,7 @@ async fn run_docdb_gateway(setup_configuration_file: &str) {
         connection_pool_manager,
         tls_provider,
         None, // custom_pg_error_mapper
+        None, // database_authorizer
     );
 
     run_gateway::<DocumentDBDataClient>(service_context, None, shutdown_token)